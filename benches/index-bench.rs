@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tantivy::schema::{Schema, STRING, TEXT};
+use tantivy::{doc, Index};
+
+const ALICE_TXT: &str = include_str!("alice.txt");
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut schema_builder = Schema::builder();
+    let text_field = schema_builder.add_text_field("text", TEXT);
+    let line_field = schema_builder.add_text_field("line", STRING);
+    let schema = schema_builder.build();
+    let lines: Vec<&str> = ALICE_TXT.lines().filter(|line| !line.is_empty()).collect();
+
+    let mut group = c.benchmark_group("index-alice");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(ALICE_TXT.len() as u64));
+    group.bench_function("index-alice-lines", |b| {
+        b.iter(|| {
+            let index = Index::create_in_ram(schema.clone());
+            let mut index_writer = index.writer_with_num_threads(1, 100_000_000).unwrap();
+            for (line_id, &line) in lines.iter().enumerate() {
+                index_writer
+                    .add_document(doc!(
+                        text_field => line,
+                        line_field => format!("line{}", line_id)
+                    ))
+                    .unwrap();
+            }
+            index_writer.commit().unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,196 @@
+use crate::FastFieldCodecReader;
+use crate::FastFieldDataAccess;
+use crate::FastFieldStats;
+use common::BinarySerializable;
+use std::io::{self, Write};
+
+/// Id used to flag a fast field that has been GCD compressed.
+///
+/// It is followed by the id of the codec used to encode the
+/// values divided by the GCD.
+pub const GCD_CODEC_ID: u8 = 4;
+
+const GCD_HEADER_LEN: usize = 16;
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Computes the greatest common divisor of `val - min_value` over all values.
+///
+/// Returns `None` if the GCD is `0` or `1`, as the GCD
+/// compression would not bring anything in that case.
+pub fn find_gcd(vals: impl Iterator<Item = u64>, stats: &FastFieldStats) -> Option<u64> {
+    if stats.max_value == stats.min_value {
+        return None;
+    }
+    let mut current_gcd = 0u64;
+    for val in vals {
+        current_gcd = gcd(current_gcd, val - stats.min_value);
+        if current_gcd == 1 {
+            return None;
+        }
+    }
+    if current_gcd > 1 {
+        Some(current_gcd)
+    } else {
+        None
+    }
+}
+
+/// Writes the header of a GCD compressed fast field.
+///
+/// The header has to be followed by the data of the wrapped codec,
+/// applied to the values `(val - min_value) / gcd`.
+pub fn write_gcd_header<W: Write>(write: &mut W, gcd: u64, min_value: u64) -> io::Result<()> {
+    gcd.serialize(write)?;
+    min_value.serialize(write)?;
+    Ok(())
+}
+
+/// Returns the stats of the values once divided by the GCD.
+pub fn gcd_stats(stats: &FastFieldStats, gcd: u64) -> FastFieldStats {
+    FastFieldStats {
+        min_value: 0,
+        max_value: (stats.max_value - stats.min_value) / gcd,
+        num_vals: stats.num_vals,
+    }
+}
+
+/// Exposes the values of a fast field divided by their GCD,
+/// so that they can be fed to the wrapped codec.
+pub struct GCDFastFieldDataAccess<'a, A> {
+    fastfield_accessor: &'a A,
+    gcd: u64,
+    min_value: u64,
+}
+
+impl<'a, A: FastFieldDataAccess> GCDFastFieldDataAccess<'a, A> {
+    /// Wraps a `FastFieldDataAccess`.
+    pub fn new(fastfield_accessor: &'a A, gcd: u64, min_value: u64) -> Self {
+        GCDFastFieldDataAccess {
+            fastfield_accessor,
+            gcd,
+            min_value,
+        }
+    }
+}
+
+impl<'a, A: FastFieldDataAccess> FastFieldDataAccess for GCDFastFieldDataAccess<'a, A> {
+    fn get_val(&self, position: u64) -> u64 {
+        (self.fastfield_accessor.get_val(position) - self.min_value) / self.gcd
+    }
+}
+
+/// Reader for a fast field that was compressed by its GCD,
+/// wrapping the reader of the codec used to encode the divided values.
+#[derive(Clone)]
+pub struct GCDFastFieldCodecReader<CodecReader> {
+    reader: CodecReader,
+    gcd: u64,
+    min_value: u64,
+}
+
+impl<C: FastFieldCodecReader> FastFieldCodecReader for GCDFastFieldCodecReader<C> {
+    fn open_from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let (mut header, data) = bytes.split_at(GCD_HEADER_LEN);
+        let gcd = u64::deserialize(&mut header)?;
+        let min_value = u64::deserialize(&mut header)?;
+        let reader = C::open_from_bytes(data)?;
+        Ok(GCDFastFieldCodecReader {
+            reader,
+            gcd,
+            min_value,
+        })
+    }
+
+    #[inline]
+    fn get_u64(&self, doc: u64, data: &[u8]) -> u64 {
+        self.min_value + self.gcd * self.reader.get_u64(doc, &data[GCD_HEADER_LEN..])
+    }
+
+    fn min_value(&self) -> u64 {
+        self.min_value + self.gcd * self.reader.min_value()
+    }
+
+    fn max_value(&self) -> u64 {
+        self.min_value + self.gcd * self.reader.max_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitpacked::{BitpackedFastFieldReader, BitpackedFastFieldSerializer};
+    use crate::linearinterpol::{LinearInterpolFastFieldReader, LinearInterpolFastFieldSerializer};
+    use crate::tests::stats_from_vec;
+    use crate::FastFieldCodecSerializer;
+
+    fn serialize_with_gcd<S: FastFieldCodecSerializer>(data: &[u64]) -> Vec<u8> {
+        let stats = stats_from_vec(data);
+        let gcd = find_gcd(data.iter().cloned(), &stats).unwrap();
+        let mut out = vec![];
+        write_gcd_header(&mut out, gcd, stats.min_value).unwrap();
+        let accessor = GCDFastFieldDataAccess::new(&data, gcd, stats.min_value);
+        let divided_vals = data.iter().map(|val| (val - stats.min_value) / gcd);
+        S::serialize(
+            &mut out,
+            &accessor,
+            gcd_stats(&stats, gcd),
+            divided_vals.clone(),
+            divided_vals,
+        )
+        .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_find_gcd() {
+        let data: Vec<u64> = vec![1_000, 3_000, 2_000, 10_000];
+        assert_eq!(
+            find_gcd(data.iter().cloned(), &stats_from_vec(&data)),
+            Some(1_000)
+        );
+        let data: Vec<u64> = vec![15, 30, 21];
+        assert_eq!(
+            find_gcd(data.iter().cloned(), &stats_from_vec(&data)),
+            Some(3)
+        );
+        let data: Vec<u64> = vec![15, 16];
+        assert_eq!(find_gcd(data.iter().cloned(), &stats_from_vec(&data)), None);
+        let data: Vec<u64> = vec![7, 7, 7];
+        assert_eq!(find_gcd(data.iter().cloned(), &stats_from_vec(&data)), None);
+    }
+
+    #[test]
+    fn test_gcd_bitpacked() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| 1_000_000 + (i % 7) * 1_000).collect();
+        let out = serialize_with_gcd::<BitpackedFastFieldSerializer>(&data);
+        let reader =
+            GCDFastFieldCodecReader::<BitpackedFastFieldReader>::open_from_bytes(&out).unwrap();
+        for (doc, val) in data.iter().enumerate() {
+            assert_eq!(reader.get_u64(doc as u64, &out), *val);
+        }
+        assert_eq!(reader.min_value(), 1_000_000);
+        assert_eq!(reader.max_value(), 1_006_000);
+        // 3 bits per value instead of 13 without the gcd.
+        assert!(out.len() < 1_000 * 3 / 8 + 64);
+    }
+
+    #[test]
+    fn test_gcd_linear_interpol() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| 10 + i * 60).collect();
+        let out = serialize_with_gcd::<LinearInterpolFastFieldSerializer>(&data);
+        let reader =
+            GCDFastFieldCodecReader::<LinearInterpolFastFieldReader>::open_from_bytes(&out)
+                .unwrap();
+        for (doc, val) in data.iter().enumerate() {
+            assert_eq!(reader.get_u64(doc as u64, &out), *val);
+        }
+    }
+}
@@ -6,6 +6,7 @@ use std::io;
 use std::io::Write;
 
 pub mod bitpacked;
+pub mod gcd;
 pub mod linearinterpol;
 pub mod multilinearinterpol;
 
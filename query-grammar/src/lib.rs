@@ -2,14 +2,77 @@ mod occur;
 mod query_grammar;
 mod user_input_ast;
 use combine::parser::Parser;
+use combine::stream::easy;
+use std::fmt;
+use std::ops::Range;
 
 pub use crate::occur::Occur;
-use crate::query_grammar::parse_to_ast;
+use crate::query_grammar::{input, parse_to_ast};
 pub use crate::user_input_ast::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 
-pub struct Error;
+/// Syntax error in a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Byte range of the query where the error was detected.
+    ///
+    /// It is empty if the query ended unexpectedly.
+    pub span: Range<usize>,
+    /// Description of the error, e.g. what was unexpected and what was expected instead.
+    pub message: String,
+}
+
+impl Error {
+    fn from_parse_errors(query: &str, errors: easy::Errors<char, &str, usize>) -> Error {
+        let start = errors.position.min(query.len());
+        let end = errors
+            .errors
+            .iter()
+            .find_map(|error| match error {
+                easy::Error::Unexpected(easy::Info::Token(token)) => Some(start + token.len_utf8()),
+                easy::Error::Unexpected(easy::Info::Range(range)) => Some(start + range.len()),
+                _ => None,
+            })
+            .unwrap_or(start)
+            .min(query.len());
+        let message = ParseErrorsMessage(&errors.errors)
+            .to_string()
+            .lines()
+            .collect::<Vec<_>>()
+            .join(". ");
+        Error {
+            span: start..end,
+            message,
+        }
+    }
+}
+
+struct ParseErrorsMessage<'a, 'b>(&'a [easy::Error<char, &'b str>]);
+
+impl<'a, 'b> fmt::Display for ParseErrorsMessage<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        easy::Error::fmt_errors(self.0, f)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Syntax error at {}..{}: {}",
+            self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+impl std::error::Error for Error {}
 
+/// Parses a query into a [`UserInputAst`].
+///
+/// On a syntax error, the returned `Error` locates the part of the query
+/// that could not be parsed.
 pub fn parse_query(query: &str) -> Result<UserInputAst, Error> {
-    let (user_input_ast, _remaining) = parse_to_ast().parse(query).map_err(|_| Error)?;
+    let (user_input_ast, _remaining) = parse_to_ast()
+        .parse(input(query))
+        .map_err(|errors| Error::from_parse_errors(query, errors))?;
     Ok(user_input_ast)
 }
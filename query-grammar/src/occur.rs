@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Write;
 
 /// Defines whether a term in a query must be present,
 /// should be present or must be not present.
-#[derive(Debug, Clone, Hash, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Occur {
     /// For a given document to be considered for scoring,
     /// at least one of the document with the Should or the Must
@@ -1,16 +1,62 @@
 use super::user_input_ast::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 use crate::Occur;
 use combine::parser::char::{char, digit, space, spaces, string};
+use combine::parser::combinator::recognize;
 use combine::parser::range::{take_while, take_while1};
 use combine::parser::repeat::escaped;
 use combine::parser::Parser;
+use combine::stream::position::{Positioner, RangePositioner};
+use combine::stream::{easy, position};
 use combine::{
-    attempt, choice, eof, many, many1, one_of, optional, parser, satisfy, skip_many1, value,
+    attempt, between, choice, eof, look_ahead, many, many1, one_of, optional, parser, satisfy,
+    skip_many1, value,
 };
-use combine::{error::StringStreamError, parser::combinator::recognize};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+/// Stream of the query being parsed.
+///
+/// The errors hold the byte offset in the query where they were detected.
+pub(crate) type Input<'a> = easy::Stream<position::Stream<&'a str, BytePositioner>>;
+
+pub(crate) fn input(query: &str) -> Input<'_> {
+    easy::Stream(position::Stream::with_positioner(
+        query,
+        BytePositioner::default(),
+    ))
+}
+
+/// Positions the stream with the byte offset in the query.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BytePositioner(usize);
+
+impl Positioner<char> for BytePositioner {
+    type Position = usize;
+    type Checkpoint = Self;
+
+    fn position(&self) -> usize {
+        self.0
+    }
+
+    fn update(&mut self, item: &char) {
+        self.0 += item.len_utf8();
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        self.clone()
+    }
+
+    fn reset(&mut self, checkpoint: Self::Checkpoint) {
+        *self = checkpoint;
+    }
+}
+
+impl<'a> RangePositioner<char, &'a str> for BytePositioner {
+    fn update_range(&mut self, range: &&'a str) {
+        self.0 += range.len();
+    }
+}
+
 // Note: '-' char is only forbidden at the beginning of a field name, would be clearer to add it to special characters.
 const SPECIAL_CHARS: &[char] = &[
     '+', '^', '`', ':', '{', '}', '"', '[', ']', '(', ')', '~', '!', '\\', '*', ' ',
@@ -21,7 +67,7 @@ const ESCAPED_SPECIAL_CHARS_PATTERN: &str = r#"\\(\+|\^|`|:|\{|\}|"|\[|\]|\(|\)|
 /// A field name must have at least one character and be followed by a colon.
 /// All characters are allowed including special characters `SPECIAL_CHARS`, but these
 /// need to be escaped with a backslack character '\'.
-fn field_name<'a>() -> impl Parser<&'a str, Output = String> {
+fn field_name<'a>() -> impl Parser<Input<'a>, Output = String> {
     static ESCAPED_SPECIAL_CHARS_RE: Lazy<Regex> =
         Lazy::new(|| Regex::new(ESCAPED_SPECIAL_CHARS_PATTERN).unwrap());
 
@@ -36,24 +82,33 @@ fn field_name<'a>() -> impl Parser<&'a str, Output = String> {
     .skip(char(':'))
     .map(|s| ESCAPED_SPECIAL_CHARS_RE.replace_all(&s, "$1").to_string())
     .and_then(|s: String| match s.is_empty() {
-        true => Err(StringStreamError::UnexpectedParse),
+        true => Err(easy::Error::Message("empty field name".into())),
         _ => Ok(s),
     })
 }
 
-fn word<'a>() -> impl Parser<&'a str, Output = String> {
+/// Parses a character of a word, which can be a special character escaped
+/// with a backslash, e.g. `\:`.
+fn word_char<'a>(is_word_char: impl Fn(char) -> bool) -> impl Parser<Input<'a>, Output = char> {
+    attempt(char('\\').with(satisfy(|c| SPECIAL_CHARS.contains(&c) || c == '-')))
+        .or(satisfy(is_word_char))
+}
+
+fn word<'a>() -> impl Parser<Input<'a>, Output = String> {
     (
-        satisfy(|c: char| {
+        word_char(|c: char| {
             !c.is_whitespace()
                 && !['-', '^', '`', ':', '{', '}', '"', '[', ']', '(', ')'].contains(&c)
         }),
-        many(satisfy(|c: char| {
+        many(word_char(|c: char| {
             !c.is_whitespace() && ![':', '^', '{', '}', '"', '[', ']', '(', ')'].contains(&c)
         })),
     )
         .map(|(s1, s2): (char, String)| format!("{}{}", s1, s2))
         .and_then(|s: String| match s.as_str() {
-            "OR" | "AND " | "NOT" => Err(StringStreamError::UnexpectedParse),
+            "OR" | "AND" | "NOT" => {
+                Err(easy::Error::Unexpected(format!("operator `{}`", s).into()))
+            }
             _ => Ok(s),
         })
 }
@@ -66,7 +121,7 @@ fn word<'a>() -> impl Parser<&'a str, Output = String> {
 /// We delegate rejecting such invalid dates to the logical AST compuation code
 /// which invokes chrono::DateTime::parse_from_rfc3339 on the value to actually parse
 /// it (instead of merely extracting the datetime value as string as done here).
-fn date_time<'a>() -> impl Parser<&'a str, Output = String> {
+fn date_time<'a>() -> impl Parser<Input<'a>, Output = String> {
     let two_digits = || recognize::<String, _, _>((digit(), digit()));
 
     // Parses a time zone
@@ -114,30 +169,30 @@ fn date_time<'a>() -> impl Parser<&'a str, Output = String> {
     recognize((date, char('T'), time))
 }
 
-fn term_val<'a>() -> impl Parser<&'a str, Output = String> {
-    let phrase = char('"').with(many1(satisfy(|c| c != '"'))).skip(char('"'));
+fn term_val<'a>() -> impl Parser<Input<'a>, Output = String> {
+    let phrase_char = attempt(char('\\').with(char('"'))).or(satisfy(|c| c != '"'));
+    let phrase = char('"').with(many1(phrase_char)).skip(char('"'));
     phrase.or(word())
 }
 
-fn term_query<'a>() -> impl Parser<&'a str, Output = UserInputLiteral> {
+fn term_query<'a>() -> impl Parser<Input<'a>, Output = UserInputLiteral> {
     let term_val_with_field = negative_number().or(term_val());
-    (field_name(), term_val_with_field).map(|(field_name, phrase)| UserInputLiteral {
+    // Once the field name is parsed, the errors in the value are reported as is.
+    (attempt(field_name()), term_val_with_field).map(|(field_name, phrase)| UserInputLiteral {
         field_name: Some(field_name),
         phrase,
     })
 }
 
-fn literal<'a>() -> impl Parser<&'a str, Output = UserInputLeaf> {
+fn literal<'a>() -> impl Parser<Input<'a>, Output = UserInputLeaf> {
     let term_default_field = term_val().map(|phrase| UserInputLiteral {
         field_name: None,
         phrase,
     });
-    attempt(term_query())
-        .or(term_default_field)
-        .map(UserInputLeaf::from)
+    term_query().or(term_default_field).map(UserInputLeaf::from)
 }
 
-fn negative_number<'a>() -> impl Parser<&'a str, Output = String> {
+fn negative_number<'a>() -> impl Parser<Input<'a>, Output = String> {
     (
         char('-'),
         many1(digit()),
@@ -152,7 +207,7 @@ fn negative_number<'a>() -> impl Parser<&'a str, Output = String> {
         })
 }
 
-fn spaces1<'a>() -> impl Parser<&'a str, Output = ()> {
+fn spaces1<'a>() -> impl Parser<Input<'a>, Output = ()> {
     skip_many1(space())
 }
 
@@ -160,7 +215,7 @@ fn spaces1<'a>() -> impl Parser<&'a str, Output = ()> {
 /// Supports ranges like:
 /// [5 TO 10], {5 TO 10}, [* TO 10], [10 TO *], {10 TO *], >5, <=10
 /// [a TO *], [a TO c], [abc TO bcd}
-fn range<'a>() -> impl Parser<&'a str, Output = UserInputLeaf> {
+fn range<'a>() -> impl Parser<Input<'a>, Output = UserInputLeaf> {
     let range_term_val = || {
         attempt(date_time())
             .or(word())
@@ -218,7 +273,7 @@ fn range<'a>() -> impl Parser<&'a str, Output = UserInputLeaf> {
     );
 
     (
-        optional(field_name()).skip(spaces()),
+        optional(attempt(field_name())).skip(spaces()),
         // try elastic first, if it matches, the range is unbounded
         attempt(elastic_unbounded_range).or(lower_to_upper),
     )
@@ -236,11 +291,63 @@ fn negate(expr: UserInputAst) -> UserInputAst {
     expr.unary(Occur::MustNot)
 }
 
-fn leaf<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
+/// Searches the literals and ranges of `ast` that do not have a field in `field_name`.
+fn set_default_field(ast: UserInputAst, field_name: &str) -> UserInputAst {
+    match ast {
+        UserInputAst::Clause(clauses) => UserInputAst::Clause(
+            clauses
+                .into_iter()
+                .map(|(occur, ast)| (occur, set_default_field(ast, field_name)))
+                .collect(),
+        ),
+        UserInputAst::Boost(ast, boost) => {
+            UserInputAst::Boost(Box::new(set_default_field(*ast, field_name)), boost)
+        }
+        UserInputAst::Leaf(leaf) => UserInputAst::from(match *leaf {
+            UserInputLeaf::Literal(UserInputLiteral {
+                field_name: None,
+                phrase,
+            }) => UserInputLeaf::Literal(UserInputLiteral {
+                field_name: Some(field_name.to_string()),
+                phrase,
+            }),
+            UserInputLeaf::Range {
+                field: None,
+                lower,
+                upper,
+            } => UserInputLeaf::Range {
+                field: Some(field_name.to_string()),
+                lower,
+                upper,
+            },
+            leaf => leaf,
+        }),
+    }
+}
+
+fn group<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
+    parser(|input| {
+        between(char('('), char(')'), ast())
+            .parse_stream(input)
+            .into_result()
+    })
+}
+
+/// Parses a group of sub queries searched in a given field, e.g. `title:(a b c)`.
+fn field_group<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
+    // Once the opening parenthesis is reached, the errors within the group
+    // are reported as is, rather than backtracking to the field name.
+    (
+        attempt(field_name().skip(spaces()).skip(look_ahead(char('(')))),
+        group(),
+    )
+        .map(|(field_name, ast)| set_default_field(ast, &field_name))
+}
+
+fn leaf<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
     parser(|input| {
-        char('(')
-            .with(ast())
-            .skip(char(')'))
+        group()
+            .or(field_group())
             .or(char('*').map(|_| UserInputAst::from(UserInputLeaf::All)))
             .or(attempt(
                 string("NOT").skip(spaces1()).with(leaf()).map(negate),
@@ -252,17 +359,17 @@ fn leaf<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
     })
 }
 
-fn occur_symbol<'a>() -> impl Parser<&'a str, Output = Occur> {
+fn occur_symbol<'a>() -> impl Parser<Input<'a>, Output = Occur> {
     char('-')
         .map(|_| Occur::MustNot)
         .or(char('+').map(|_| Occur::Must))
 }
 
-fn occur_leaf<'a>() -> impl Parser<&'a str, Output = (Option<Occur>, UserInputAst)> {
+fn occur_leaf<'a>() -> impl Parser<Input<'a>, Output = (Option<Occur>, UserInputAst)> {
     (optional(occur_symbol()), boosted_leaf())
 }
 
-fn positive_float_number<'a>() -> impl Parser<&'a str, Output = f64> {
+fn positive_float_number<'a>() -> impl Parser<Input<'a>, Output = f64> {
     (many1(digit()), optional((char('.'), many1(digit())))).map(
         |(int_part, decimal_part_opt): (String, Option<(char, String)>)| {
             let mut float_str = int_part;
@@ -275,11 +382,11 @@ fn positive_float_number<'a>() -> impl Parser<&'a str, Output = f64> {
     )
 }
 
-fn boost<'a>() -> impl Parser<&'a str, Output = f64> {
+fn boost<'a>() -> impl Parser<Input<'a>, Output = f64> {
     (char('^'), positive_float_number()).map(|(_, boost)| boost)
 }
 
-fn boosted_leaf<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
+fn boosted_leaf<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
     (leaf(), optional(boost())).map(|(leaf, boost_opt)| match boost_opt {
         Some(boost) if (boost - 1.0).abs() > std::f64::EPSILON => {
             UserInputAst::Boost(Box::new(leaf), boost)
@@ -294,7 +401,7 @@ enum BinaryOperand {
     And,
 }
 
-fn binary_operand<'a>() -> impl Parser<&'a str, Output = BinaryOperand> {
+fn binary_operand<'a>() -> impl Parser<Input<'a>, Output = BinaryOperand> {
     string("AND")
         .with(value(BinaryOperand::And))
         .or(string("OR").with(value(BinaryOperand::Or)))
@@ -325,15 +432,24 @@ fn aggregate_binary_expressions(
     }
 }
 
-fn operand_leaf<'a>() -> impl Parser<&'a str, Output = (BinaryOperand, UserInputAst)> {
+fn operand_leaf<'a>() -> impl Parser<Input<'a>, Output = (BinaryOperand, UserInputAst)> {
     (
         binary_operand().skip(spaces()),
         boosted_leaf().skip(spaces()),
     )
 }
 
-pub fn ast<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
-    let boolean_expr = (boosted_leaf().skip(spaces()), many1(operand_leaf()))
+pub fn ast<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
+    // Once a binary operator is reached, the errors in the following operands
+    // are reported as is, rather than backtracking to the whitespace separated leaves.
+    let boolean_expr = (
+        attempt(
+            boosted_leaf()
+                .skip(spaces())
+                .skip(look_ahead(binary_operand())),
+        ),
+        many1(operand_leaf()),
+    )
         .map(|(left, right)| aggregate_binary_expressions(left, right));
     let whitespace_separated_leaves = many1(occur_leaf().skip(spaces().silent())).map(
         |subqueries: Vec<(Option<Occur>, UserInputAst)>| {
@@ -348,11 +464,11 @@ pub fn ast<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
             }
         },
     );
-    let expr = attempt(boolean_expr).or(whitespace_separated_leaves);
+    let expr = boolean_expr.or(whitespace_separated_leaves);
     spaces().with(expr).skip(spaces())
 }
 
-pub fn parse_to_ast<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
+pub fn parse_to_ast<'a>() -> impl Parser<Input<'a>, Output = UserInputAst> {
     spaces()
         .with(optional(ast()).skip(eof()))
         .map(|opt_ast| opt_ast.unwrap_or_else(UserInputAst::empty_query))
@@ -361,11 +477,20 @@ pub fn parse_to_ast<'a>() -> impl Parser<&'a str, Output = UserInputAst> {
 #[cfg(test)]
 mod test {
 
-    type TestParseResult = Result<(), StringStreamError>;
+    type TestParseResult = Result<(), easy::Errors<char, &'static str, usize>>;
 
     use super::*;
     use combine::parser::Parser;
 
+    fn parse_str<'a, P: Parser<Input<'a>>>(
+        mut parser: P,
+        text: &'a str,
+    ) -> Result<(P::Output, &'a str), easy::Errors<char, &'a str, usize>> {
+        parser
+            .parse(input(text))
+            .map(|(output, remaining)| (output, remaining.0.input))
+    }
+
     pub fn nearly_equals(a: f64, b: f64) -> bool {
         (a - b).abs() < 0.0005 * (a + b).abs()
     }
@@ -381,20 +506,20 @@ mod test {
 
     #[test]
     fn test_occur_symbol() -> TestParseResult {
-        assert_eq!(super::occur_symbol().parse("-")?, (Occur::MustNot, ""));
-        assert_eq!(super::occur_symbol().parse("+")?, (Occur::Must, ""));
+        assert_eq!(parse_str(super::occur_symbol(), "-")?, (Occur::MustNot, ""));
+        assert_eq!(parse_str(super::occur_symbol(), "+")?, (Occur::Must, ""));
         Ok(())
     }
 
     #[test]
     fn test_positive_float_number() {
         fn valid_parse(float_str: &str, expected_val: f64, expected_remaining: &str) {
-            let (val, remaining) = positive_float_number().parse(float_str).unwrap();
+            let (val, remaining) = parse_str(positive_float_number(), float_str).unwrap();
             assert_eq!(remaining, expected_remaining);
             assert_nearly_equals(val, expected_val);
         }
         fn error_parse(float_str: &str) {
-            assert!(positive_float_number().parse(float_str).is_err());
+            assert!(parse_str(positive_float_number(), float_str).is_err());
         }
         valid_parse("1.0", 1.0, "");
         valid_parse("1", 1.0, "");
@@ -406,28 +531,26 @@ mod test {
 
     #[test]
     fn test_date_time() {
-        let (val, remaining) = date_time()
-            .parse("2015-08-02T18:54:42+02:30")
-            .expect("cannot parse date");
+        let (val, remaining) =
+            parse_str(date_time(), "2015-08-02T18:54:42+02:30").expect("cannot parse date");
         assert_eq!(val, "2015-08-02T18:54:42+02:30");
         assert_eq!(remaining, "");
-        assert!(date_time().parse("2015-08-02T18:54:42+02").is_err());
+        assert!(parse_str(date_time(), "2015-08-02T18:54:42+02").is_err());
 
-        let (val, remaining) = date_time()
-            .parse("2021-04-13T19:46:26.266051969+00:00")
+        let (val, remaining) = parse_str(date_time(), "2021-04-13T19:46:26.266051969+00:00")
             .expect("cannot parse fractional date");
         assert_eq!(val, "2021-04-13T19:46:26.266051969+00:00");
         assert_eq!(remaining, "");
     }
 
     fn test_parse_query_to_ast_helper(query: &str, expected: &str) {
-        let query = parse_to_ast().parse(query).unwrap().0;
+        let query = parse_str(parse_to_ast(), query).unwrap().0;
         let query_str = format!("{:?}", query);
         assert_eq!(query_str, expected);
     }
 
     fn test_is_parse_err(query: &str) {
-        assert!(parse_to_ast().parse(query).is_err());
+        assert!(parse_str(parse_to_ast(), query).is_err());
     }
 
     #[test]
@@ -444,18 +567,15 @@ mod test {
 
     #[test]
     fn test_parse_query_to_ast_not_op() {
-        assert_eq!(
-            format!("{:?}", parse_to_ast().parse("NOT")),
-            "Err(UnexpectedParse)"
-        );
+        test_is_parse_err("NOT");
         test_parse_query_to_ast_helper("NOTa", "\"NOTa\"");
         test_parse_query_to_ast_helper("NOT a", "(-\"a\")");
     }
 
     #[test]
     fn test_boosting() {
-        assert!(parse_to_ast().parse("a^2^3").is_err());
-        assert!(parse_to_ast().parse("a^2^").is_err());
+        assert!(parse_str(parse_to_ast(), "a^2^3").is_err());
+        assert!(parse_str(parse_to_ast(), "a^2^").is_err());
         test_parse_query_to_ast_helper("a^3", "(\"a\")^3");
         test_parse_query_to_ast_helper("a^3 b^2", "(*(\"a\")^3 *(\"b\")^2)");
         test_parse_query_to_ast_helper("a^1", "\"a\"");
@@ -467,22 +587,10 @@ mod test {
         test_parse_query_to_ast_helper("a OR b", "(?\"a\" ?\"b\")");
         test_parse_query_to_ast_helper("a OR b AND c", "(?\"a\" ?(+\"b\" +\"c\"))");
         test_parse_query_to_ast_helper("a AND b         AND c", "(+\"a\" +\"b\" +\"c\")");
-        assert_eq!(
-            format!("{:?}", parse_to_ast().parse("a OR b aaa")),
-            "Err(UnexpectedParse)"
-        );
-        assert_eq!(
-            format!("{:?}", parse_to_ast().parse("a AND b aaa")),
-            "Err(UnexpectedParse)"
-        );
-        assert_eq!(
-            format!("{:?}", parse_to_ast().parse("aaa a OR b ")),
-            "Err(UnexpectedParse)"
-        );
-        assert_eq!(
-            format!("{:?}", parse_to_ast().parse("aaa ccc a OR b ")),
-            "Err(UnexpectedParse)"
-        );
+        test_is_parse_err("a OR b aaa");
+        test_is_parse_err("a AND b aaa");
+        test_is_parse_err("aaa a OR b ");
+        test_is_parse_err("aaa ccc a OR b ");
     }
 
     #[test]
@@ -506,7 +614,7 @@ mod test {
 
     #[test]
     fn test_occur_leaf() {
-        let ((occur, ast), _) = super::occur_leaf().parse("+abc").unwrap();
+        let ((occur, ast), _) = parse_str(super::occur_leaf(), "+abc").unwrap();
         assert_eq!(occur, Some(Occur::Must));
         assert_eq!(format!("{:?}", ast), "\"abc\"");
     }
@@ -514,27 +622,27 @@ mod test {
     #[test]
     fn test_field_name() -> TestParseResult {
         assert_eq!(
-            super::field_name().parse(".my.field.name:a"),
+            parse_str(super::field_name(), ".my.field.name:a"),
             Ok((".my.field.name".to_string(), "a"))
         );
         assert_eq!(
-            super::field_name().parse("my\\ field\\ name:a"),
+            parse_str(super::field_name(), "my\\ field\\ name:a"),
             Ok(("my field name".to_string(), "a"))
         );
-        assert!(super::field_name().parse("my field:a").is_err());
+        assert!(parse_str(super::field_name(), "my field:a").is_err());
         assert_eq!(
-            super::field_name().parse("\\(1\\+1\\):2"),
+            parse_str(super::field_name(), "\\(1\\+1\\):2"),
             Ok(("(1+1)".to_string(), "2"))
         );
         assert_eq!(
-            super::field_name().parse("my_field_name:a"),
+            parse_str(super::field_name(), "my_field_name:a"),
             Ok(("my_field_name".to_string(), "a"))
         );
-        assert!(super::field_name().parse("my_field_name").is_err());
-        assert!(super::field_name().parse(":a").is_err());
-        assert!(super::field_name().parse("-my_field:a").is_err());
+        assert!(parse_str(super::field_name(), "my_field_name").is_err());
+        assert!(parse_str(super::field_name(), ":a").is_err());
+        assert!(parse_str(super::field_name(), "-my_field:a").is_err());
         assert_eq!(
-            super::field_name().parse("_my_field:a")?,
+            parse_str(super::field_name(), "_my_field:a")?,
             ("_my_field".to_string(), "a")
         );
         Ok(())
@@ -554,8 +662,7 @@ mod test {
     #[test]
     fn test_range_parser() {
         // testing the range() parser separately
-        let res = range()
-            .parse("title: <hello")
+        let res = parse_str(range(), "title: <hello")
             .expect("Cannot parse felxible bound word")
             .0;
         let expected = UserInputLeaf::Range {
@@ -563,8 +670,7 @@ mod test {
             lower: UserInputBound::Unbounded,
             upper: UserInputBound::Exclusive("hello".to_string()),
         };
-        let res2 = range()
-            .parse("title:{* TO hello}")
+        let res2 = parse_str(range(), "title:{* TO hello}")
             .expect("Cannot parse ununbounded to word")
             .0;
         assert_eq!(res, expected);
@@ -575,12 +681,10 @@ mod test {
             lower: UserInputBound::Inclusive("71.2".to_string()),
             upper: UserInputBound::Unbounded,
         };
-        let res3 = range()
-            .parse("weight: >=71.2")
+        let res3 = parse_str(range(), "weight: >=71.2")
             .expect("Cannot parse flexible bound float")
             .0;
-        let res4 = range()
-            .parse("weight:[71.2 TO *}")
+        let res4 = parse_str(range(), "weight:[71.2 TO *}")
             .expect("Cannot parse float to unbounded")
             .0;
         assert_eq!(res3, expected_weight);
@@ -591,10 +695,12 @@ mod test {
             lower: UserInputBound::Exclusive("2015-08-02T18:54:42Z".to_string()),
             upper: UserInputBound::Inclusive("2021-08-02T18:54:42+02:30".to_string()),
         };
-        let res5 = range()
-            .parse("date_field:{2015-08-02T18:54:42Z TO 2021-08-02T18:54:42+02:30]")
-            .expect("Cannot parse date range")
-            .0;
+        let res5 = parse_str(
+            range(),
+            "date_field:{2015-08-02T18:54:42Z TO 2021-08-02T18:54:42+02:30]",
+        )
+        .expect("Cannot parse date range")
+        .0;
         assert_eq!(res5, expected_dates);
 
         let expected_flexible_dates = UserInputLeaf::Range {
@@ -603,8 +709,7 @@ mod test {
             upper: UserInputBound::Inclusive("2021-08-02T18:54:42.12345+02:30".to_string()),
         };
 
-        let res6 = range()
-            .parse("date_field: <=2021-08-02T18:54:42.12345+02:30")
+        let res6 = parse_str(range(), "date_field: <=2021-08-02T18:54:42.12345+02:30")
             .expect("Cannot parse date range")
             .0;
         assert_eq!(res6, expected_flexible_dates);
@@ -690,4 +795,61 @@ mod test {
         );
         test_is_parse_err("abc +    ");
     }
+
+    #[test]
+    fn test_parse_field_group() {
+        test_parse_query_to_ast_helper(
+            "title:(a b c)",
+            "(*\"title\":\"a\" *\"title\":\"b\" *\"title\":\"c\")",
+        );
+        test_parse_query_to_ast_helper(
+            "title: (+a -body:b \"c d\")",
+            "(+\"title\":\"a\" -\"body\":\"b\" *\"title\":\"c d\")",
+        );
+        test_parse_query_to_ast_helper(
+            "title:(a OR b)^2 c",
+            "(*((?\"title\":\"a\" ?\"title\":\"b\"))^2 *\"c\")",
+        );
+        test_parse_query_to_ast_helper(
+            "year:(>2000 [1990 TO 1995])",
+            "(*\"year\":{\"2000\" TO \"*\"} *\"year\":[\"1990\" TO \"1995\"])",
+        );
+        test_parse_query_to_ast_helper("title:(a)", "\"title\":\"a\"");
+        test_is_parse_err("title:(a b");
+    }
+
+    #[test]
+    fn test_parse_escaped_special_chars() {
+        test_parse_query_to_ast_helper("a\\:b", "\"a:b\"");
+        test_parse_query_to_ast_helper("\\(a\\)", "\"(a)\"");
+        test_parse_query_to_ast_helper("\\-a", "\"-a\"");
+        test_parse_query_to_ast_helper("title:c\\+\\+", "\"title\":\"c++\"");
+        test_parse_query_to_ast_helper("a\\ b", "\"a b\"");
+        test_parse_query_to_ast_helper("a\\b", "\"a\\b\"");
+        test_parse_query_to_ast_helper("\"say \\\"hi\\\"\"", "\"say \"hi\"\"");
+    }
+
+    #[test]
+    fn test_parse_boolean_keywords() {
+        test_parse_query_to_ast_helper("a AND NOT b", "(+\"a\" +(-\"b\"))");
+        test_parse_query_to_ast_helper("+a -b c^2", "(+\"a\" -\"b\" *(\"c\")^2)");
+        test_is_parse_err("a AND");
+        test_is_parse_err("AND");
+    }
+
+    #[test]
+    fn test_parse_error_span() {
+        let error = crate::parse_query("a AND b)").unwrap_err();
+        assert_eq!(error.span, 7..8);
+        assert!(error.message.contains("Unexpected `)`"));
+        let error = crate::parse_query("title:(a b").unwrap_err();
+        assert_eq!(error.span, 10..10);
+        assert!(error.message.contains("end of input"));
+        let error = crate::parse_query("é AND (b ]").unwrap_err();
+        assert_eq!(error.span, 10..11);
+        assert_eq!(
+            format!("{}", error),
+            format!("Syntax error at 10..11: {}", error.message)
+        );
+    }
 }
@@ -1,12 +1,19 @@
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 
+use serde::{Deserialize, Serialize};
+
 use crate::Occur;
 
-#[derive(PartialEq)]
+/// Leaf of a user query.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UserInputLeaf {
+    /// Text searched in a field, or in the default fields.
     Literal(UserInputLiteral),
+    /// Matches all documents.
     All,
+    /// Range of values of a field.
     Range {
         field: Option<String>,
         lower: UserInputBound,
@@ -36,7 +43,8 @@ impl Debug for UserInputLeaf {
     }
 }
 
-#[derive(PartialEq)]
+/// Text searched in the field `field_name`, or in the default fields if `None`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserInputLiteral {
     pub field_name: Option<String>,
     pub phrase: String,
@@ -51,7 +59,9 @@ impl fmt::Debug for UserInputLiteral {
     }
 }
 
-#[derive(PartialEq)]
+/// Bound of a range.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UserInputBound {
     Inclusive(String),
     Exclusive(String),
@@ -84,9 +94,17 @@ impl UserInputBound {
     }
 }
 
+/// Abstract syntax tree of a user query, before it is resolved against a schema.
+///
+/// It can be obtained by parsing a query, or built programmatically.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UserInputAst {
+    /// Boolean combination of sub queries.
+    /// A sub query without occur uses the default occur of the query parser.
     Clause(Vec<(Option<Occur>, UserInputAst)>),
     Leaf(Box<UserInputLeaf>),
+    /// Sub query whose score is multiplied by the given factor.
     Boost(Box<UserInputAst>, f64),
 }
 
@@ -169,3 +187,48 @@ impl fmt::Debug for UserInputAst {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
+    use crate::{parse_query, Occur};
+
+    #[test]
+    fn test_user_input_ast_serialization() {
+        let ast = UserInputAst::Clause(vec![
+            (
+                Some(Occur::Must),
+                UserInputLeaf::Literal(UserInputLiteral {
+                    field_name: Some("title".to_string()),
+                    phrase: "hello".to_string(),
+                })
+                .into(),
+            ),
+            (
+                None,
+                UserInputLeaf::Range {
+                    field: Some("year".to_string()),
+                    lower: UserInputBound::Inclusive("2000".to_string()),
+                    upper: UserInputBound::Unbounded,
+                }
+                .into(),
+            ),
+        ]);
+        let json = serde_json::to_string(&ast).unwrap();
+        assert_eq!(
+            json,
+            r#"{"clause":[["must",{"leaf":{"literal":{"field_name":"title","phrase":"hello"}}}],[null,{"leaf":{"range":{"field":"year","lower":{"inclusive":"2000"},"upper":"unbounded"}}}]]}"#
+        );
+        let deser_ast: UserInputAst = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser_ast, ast);
+    }
+
+    #[test]
+    fn test_parsed_user_input_ast_serialization() {
+        let ast = parse_query("+title:hello -body:\"big world\"^2").unwrap_or_else(|_| panic!());
+        let json = serde_json::to_string(&ast).unwrap();
+        let deser_ast: UserInputAst = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", deser_ast), format!("{:?}", ast));
+        assert_eq!(deser_ast, ast);
+    }
+}
@@ -0,0 +1,337 @@
+//! Measurement of the latency of queries.
+//!
+//! A [`QueryBenchmark`](./struct.QueryBenchmark.html) runs a query a given number of times
+//! against a searcher, and reports the distribution of its latency as well as the time
+//! spent in the different phases of the search. It makes it possible to regression-test
+//! the performance of a schema and of its typical queries, e.g. as part of a test suite.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use tantivy::bench::QueryBenchmark;
+//! use tantivy::collector::TopDocs;
+//! use tantivy::query::QueryParser;
+//! use tantivy::schema::{Schema, TEXT};
+//! use tantivy::{doc, Index};
+//!
+//! # fn main() -> tantivy::Result<()> {
+//! let mut schema_builder = Schema::builder();
+//! let title = schema_builder.add_text_field("title", TEXT);
+//! let index = Index::create_in_ram(schema_builder.build());
+//! let mut index_writer = index.writer(50_000_000)?;
+//! index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+//! index_writer.commit()?;
+//!
+//! let searcher = index.reader()?.searcher();
+//! let query = QueryParser::for_index(&index, vec![title]).parse_query("wind")?;
+//! let report = QueryBenchmark::new(100)
+//!     .with_warmup_iterations(10)
+//!     .run(&searcher, &query, &TopDocs::with_limit(10))?;
+//! assert_eq!(report.latencies().len(), 100);
+//! assert!(report.percentile(50.0) <= report.percentile(99.0));
+//! assert!(report.percentile(99.0) < Duration::from_secs(1));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::collector::Collector;
+use crate::core::WarmupSpec;
+use crate::query::{FilterCache, Query};
+use crate::{Searcher, TantivyError};
+
+/// Runs a query a number of times against a searcher, and measures its latency.
+///
+/// The caches are controlled as follows:
+/// - the query is first run `num_warmup_iterations` times without being measured
+///   (1 by default), e.g. to load the data read by the query in the page cache.
+/// - the data described by a [`WarmupSpec`](../struct.WarmupSpec.html) can be preloaded
+///   before any run with `with_warmup_spec`.
+/// - a [`FilterCache`](../query/struct.FilterCache.html) registered with
+///   `with_cold_filter_cache` is cleared before each run, to measure the cost of
+///   the cached filters when they miss the cache.
+#[derive(Clone, Debug)]
+pub struct QueryBenchmark {
+    num_iterations: usize,
+    num_warmup_iterations: usize,
+    num_profiled_iterations: usize,
+    warmup_spec: Option<WarmupSpec>,
+    cold_filter_cache: Option<FilterCache>,
+}
+
+impl QueryBenchmark {
+    /// Creates a benchmark measuring `num_iterations` runs of the query.
+    pub fn new(num_iterations: usize) -> QueryBenchmark {
+        QueryBenchmark {
+            num_iterations,
+            num_warmup_iterations: 1,
+            num_profiled_iterations: 1,
+            warmup_spec: None,
+            cold_filter_cache: None,
+        }
+    }
+
+    /// Sets the number of runs of the query before the measured runs.
+    ///
+    /// Defaults to 1. With 0, the first measured run includes the cost of a cold start.
+    pub fn with_warmup_iterations(mut self, num_warmup_iterations: usize) -> QueryBenchmark {
+        self.num_warmup_iterations = num_warmup_iterations;
+        self
+    }
+
+    /// Sets the number of profiled runs used to measure the time spent in
+    /// each phase of the search. See `BenchmarkReport::phases`.
+    ///
+    /// Defaults to 1. The profiled runs are not part of the measured latencies,
+    /// as profiling has an overhead and disables some of the optimizations of the search.
+    pub fn with_profiled_iterations(mut self, num_profiled_iterations: usize) -> QueryBenchmark {
+        self.num_profiled_iterations = num_profiled_iterations;
+        self
+    }
+
+    /// Preloads the data described by `warmup_spec` in each segment, before running the query.
+    pub fn with_warmup_spec(mut self, warmup_spec: WarmupSpec) -> QueryBenchmark {
+        self.warmup_spec = Some(warmup_spec);
+        self
+    }
+
+    /// Clears `filter_cache` before each run of the query, so that the filters it
+    /// caches are computed by each run.
+    pub fn with_cold_filter_cache(mut self, filter_cache: FilterCache) -> QueryBenchmark {
+        self.cold_filter_cache = Some(filter_cache);
+        self
+    }
+
+    fn clear_cold_caches(&self) {
+        if let Some(filter_cache) = self.cold_filter_cache.as_ref() {
+            filter_cache.clear();
+        }
+    }
+
+    /// Runs the benchmark of `query` collected by `collector` against `searcher`.
+    ///
+    /// Returns an `InvalidArgument` error if the number of measured runs is 0.
+    pub fn run<C: Collector>(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<BenchmarkReport> {
+        if self.num_iterations == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "The benchmark requires at least one iteration.".to_string(),
+            ));
+        }
+        if let Some(warmup_spec) = self.warmup_spec.as_ref() {
+            for segment_reader in searcher.segment_readers() {
+                segment_reader.warmup(warmup_spec)?;
+            }
+        }
+        for _ in 0..self.num_warmup_iterations {
+            self.clear_cold_caches();
+            searcher.search(query, collector)?;
+        }
+        let mut latencies = Vec::with_capacity(self.num_iterations);
+        for _ in 0..self.num_iterations {
+            self.clear_cold_caches();
+            let start = Instant::now();
+            searcher.search(query, collector)?;
+            latencies.push(start.elapsed());
+        }
+        let mut profiled_phases = Vec::with_capacity(self.num_profiled_iterations);
+        for _ in 0..self.num_profiled_iterations {
+            self.clear_cold_caches();
+            let (_, statistics) = searcher.search_with_statistics(query, collector)?;
+            let mut phases = PhaseTimings {
+                weight: statistics.weight_duration,
+                merge: statistics.merge_duration,
+                ..PhaseTimings::default()
+            };
+            for segment_statistics in &statistics.segments {
+                phases.scorer_setup += segment_statistics.scorer_setup_duration;
+                phases.scoring += segment_statistics.scoring_duration;
+                phases.collection += segment_statistics.collection_duration;
+            }
+            profiled_phases.push(phases);
+        }
+        let mut sorted_latencies = latencies.clone();
+        sorted_latencies.sort_unstable();
+        Ok(BenchmarkReport {
+            latencies,
+            sorted_latencies,
+            phases: PhaseTimings::median(profiled_phases),
+        })
+    }
+}
+
+/// Time spent in each phase of the search of a query.
+///
+/// The durations of the segments are summed, even when the segments
+/// are searched in parallel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// Time spent creating the `Weight` of the query.
+    pub weight: Duration,
+    /// Time spent creating the scorers of the segments.
+    pub scorer_setup: Duration,
+    /// Time spent in the scorers of the segments.
+    pub scoring: Duration,
+    /// Time spent in the segment collectors.
+    pub collection: Duration,
+    /// Time spent merging the fruits of the segments.
+    pub merge: Duration,
+}
+
+impl PhaseTimings {
+    /// Returns the median of each phase over the profiled runs.
+    fn median(profiled_phases: Vec<PhaseTimings>) -> Option<PhaseTimings> {
+        if profiled_phases.is_empty() {
+            return None;
+        }
+        let median_of = |phase: fn(&PhaseTimings) -> Duration| -> Duration {
+            let mut durations: Vec<Duration> = profiled_phases.iter().map(phase).collect();
+            durations.sort_unstable();
+            durations[durations.len() / 2]
+        };
+        Some(PhaseTimings {
+            weight: median_of(|phases| phases.weight),
+            scorer_setup: median_of(|phases| phases.scorer_setup),
+            scoring: median_of(|phases| phases.scoring),
+            collection: median_of(|phases| phases.collection),
+            merge: median_of(|phases| phases.merge),
+        })
+    }
+}
+
+/// Measures of a [`QueryBenchmark`](./struct.QueryBenchmark.html).
+#[derive(Clone, Debug)]
+pub struct BenchmarkReport {
+    latencies: Vec<Duration>,
+    sorted_latencies: Vec<Duration>,
+    phases: Option<PhaseTimings>,
+}
+
+impl BenchmarkReport {
+    /// Returns the latency of each measured run, in the order of the runs.
+    pub fn latencies(&self) -> &[Duration] {
+        &self.latencies
+    }
+
+    /// Returns the latency below which `percentile` percent of the runs are,
+    /// using the nearest-rank method.
+    ///
+    /// `percentile` is clamped to `[0, 100]`: `percentile(0.0)` is the smallest latency
+    /// and `percentile(100.0)` the largest one.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let num_latencies = self.sorted_latencies.len();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * num_latencies as f64).ceil() as usize;
+        self.sorted_latencies[rank.max(1).min(num_latencies) - 1]
+    }
+
+    /// Returns the smallest latency.
+    pub fn min(&self) -> Duration {
+        self.sorted_latencies[0]
+    }
+
+    /// Returns the largest latency.
+    pub fn max(&self) -> Duration {
+        self.sorted_latencies[self.sorted_latencies.len() - 1]
+    }
+
+    /// Returns the mean latency.
+    pub fn mean(&self) -> Duration {
+        let total: Duration = self.latencies.iter().sum();
+        total / self.latencies.len() as u32
+    }
+
+    /// Returns the median time spent in each phase of the search over the profiled runs,
+    /// or `None` if there was no profiled run.
+    pub fn phases(&self) -> Option<PhaseTimings> {
+        self.phases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BenchmarkReport, QueryBenchmark};
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{FilterCache, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STRING, TEXT};
+    use crate::{Index, TantivyError, Term, WarmupSpec};
+    use std::time::Duration;
+
+    #[test]
+    fn test_benchmark_report_percentiles() {
+        let latencies: Vec<Duration> = (1..=10u64).rev().map(Duration::from_millis).collect();
+        let mut sorted_latencies = latencies.clone();
+        sorted_latencies.sort_unstable();
+        let report = BenchmarkReport {
+            latencies,
+            sorted_latencies,
+            phases: None,
+        };
+        assert_eq!(report.latencies()[0], Duration::from_millis(10));
+        assert_eq!(report.min(), Duration::from_millis(1));
+        assert_eq!(report.max(), Duration::from_millis(10));
+        assert_eq!(report.mean(), Duration::from_micros(5_500));
+        assert_eq!(report.percentile(0.0), Duration::from_millis(1));
+        assert_eq!(report.percentile(50.0), Duration::from_millis(5));
+        assert_eq!(report.percentile(55.0), Duration::from_millis(6));
+        assert_eq!(report.percentile(99.0), Duration::from_millis(10));
+        assert_eq!(report.percentile(200.0), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_query_benchmark() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            index_writer.add_document(doc!(
+                text => if i % 2 == 0 { "hello world" } else { "hello" },
+                tag => if i % 10 == 0 { "rare" } else { "common" },
+            ))?;
+            if i % 50 == 49 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        let filter_cache = FilterCache::new(10);
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("world")?;
+        let report = QueryBenchmark::new(20)
+            .with_warmup_iterations(2)
+            .with_profiled_iterations(3)
+            .with_warmup_spec(WarmupSpec::new().term_dict(text))
+            .run(&searcher, &query, &TopDocs::with_limit(10))?;
+        assert_eq!(report.latencies().len(), 20);
+        assert!(report.min() <= report.percentile(50.0));
+        assert!(report.percentile(50.0) <= report.percentile(90.0));
+        assert!(report.percentile(90.0) <= report.max());
+        assert!(report.min() <= report.mean() && report.mean() <= report.max());
+        assert!(report.phases().is_some());
+
+        // The filter cache is cleared before each run.
+        let filter_query = filter_cache.cached(Box::new(TermQuery::new(
+            Term::from_field_text(tag, "rare"),
+            IndexRecordOption::Basic,
+        )));
+        assert_eq!(searcher.search(&filter_query, &Count)?, 10);
+        assert_eq!(filter_cache.len(), 2);
+        let report = QueryBenchmark::new(5)
+            .with_warmup_iterations(0)
+            .with_profiled_iterations(0)
+            .with_cold_filter_cache(filter_cache.clone())
+            .run(&searcher, &filter_query, &Count)?;
+        assert_eq!(report.latencies().len(), 5);
+        assert!(report.phases().is_none());
+        assert_eq!(filter_cache.len(), 2);
+
+        assert!(matches!(
+            QueryBenchmark::new(0).run(&searcher, &query, &Count),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
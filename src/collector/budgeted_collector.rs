@@ -0,0 +1,251 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::collector::{Collector, SegmentCollector};
+use crate::query::Weight;
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TERMINATED};
+
+/// The clock is only checked every `CHECK_DEADLINE_PERIOD` documents,
+/// to keep the overhead of the timeout negligible. Must be a power of two.
+const CHECK_DEADLINE_PERIOD: usize = 64;
+
+/// Fruit of a [`BudgetedCollector`](./struct.BudgetedCollector.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetedFruit<TFruit> {
+    /// Fruit of the wrapped collector.
+    pub fruit: TFruit,
+    /// True iff the search was stopped before all of the matching
+    /// documents were collected, because of the timeout or of the
+    /// maximum number of documents.
+    pub truncated: bool,
+}
+
+/// The `BudgetedCollector` wraps a collector and stops the collection
+/// once a timeout has elapsed, or a maximum number of documents has been collected.
+///
+/// The fruit of the wrapped collector is then computed on the documents collected so far,
+/// and flagged as `truncated`. This prevents a single pathological query from
+/// running for an unbounded amount of time.
+///
+/// The timeout is measured from the creation of the collector, so a `BudgetedCollector`
+/// is meant to be used for a single search. It is checked within the collection loop
+/// of each segment, but the creation of the query weight and of the segment collectors
+/// are not interrupted.
+///
+/// Note that the documents are pushed one by one to the wrapped collector:
+/// the specialized collection of some collectors (e.g. the pruning of `TopDocs`)
+/// is not used.
+///
+/// ```rust
+/// use tantivy::collector::{BudgetedCollector, Count};
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+/// use std::time::Duration;
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for _ in 0..10 {
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let collector = BudgetedCollector::new(Count)
+///     .with_timeout(Duration::from_secs(1))
+///     .with_max_docs(3);
+/// let count = searcher.search(&AllQuery, &collector)?;
+/// assert_eq!(count.fruit, 3);
+/// assert!(count.truncated);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BudgetedCollector<TCollector> {
+    collector: TCollector,
+    deadline: Option<Instant>,
+    max_docs: Option<usize>,
+    num_collected_docs: AtomicUsize,
+    truncated: AtomicBool,
+}
+
+impl<TCollector: Collector> BudgetedCollector<TCollector> {
+    /// Wraps `collector`, without any limit.
+    pub fn new(collector: TCollector) -> BudgetedCollector<TCollector> {
+        BudgetedCollector {
+            collector,
+            deadline: None,
+            max_docs: None,
+            num_collected_docs: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops the collection once `timeout` has elapsed since the
+    /// creation of the collector.
+    pub fn with_timeout(mut self, timeout: Duration) -> BudgetedCollector<TCollector> {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// Stops the collection once `max_docs` documents have been collected,
+    /// over all of the segments.
+    pub fn with_max_docs(mut self, max_docs: usize) -> BudgetedCollector<TCollector> {
+        self.max_docs = Some(max_docs);
+        self
+    }
+
+    fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false)
+    }
+
+    /// Reserves a slot for one more document.
+    /// Returns false if the budget is exhausted.
+    fn acquire_doc(&self) -> bool {
+        let num_collected_docs = self.num_collected_docs.fetch_add(1, Ordering::Relaxed);
+        if let Some(max_docs) = self.max_docs {
+            if num_collected_docs >= max_docs {
+                return false;
+            }
+        }
+        if num_collected_docs & (CHECK_DEADLINE_PERIOD - 1) == 0 && self.is_past_deadline() {
+            return false;
+        }
+        true
+    }
+}
+
+impl<TCollector: Collector> Collector for BudgetedCollector<TCollector> {
+    type Fruit = BudgetedFruit<TCollector::Fruit>;
+
+    type Child = TCollector::Child;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<TCollector::Child> {
+        self.collector.for_segment(segment_local_id, segment)
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<TCollector::Child as SegmentCollector>::Fruit>,
+    ) -> crate::Result<Self::Fruit> {
+        Ok(BudgetedFruit {
+            fruit: self.collector.merge_fruits(segment_fruits)?,
+            truncated: self.truncated.load(Ordering::Relaxed),
+        })
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<<TCollector::Child as SegmentCollector>::Fruit> {
+        let mut segment_collector = self.for_segment(segment_ord, reader)?;
+        if self.truncated.load(Ordering::Relaxed) || self.is_past_deadline() {
+            self.truncated.store(true, Ordering::Relaxed);
+            return Ok(segment_collector.harvest());
+        }
+        let alive_bitset_opt = reader.alive_bitset();
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let mut doc: DocId = scorer.doc();
+        while doc != TERMINATED {
+            let is_alive = alive_bitset_opt
+                .map(|alive_bitset| alive_bitset.is_alive(doc))
+                .unwrap_or(true);
+            if is_alive {
+                if !self.acquire_doc() {
+                    self.truncated.store(true, Ordering::Relaxed);
+                    break;
+                }
+                let score: Score = scorer.score();
+                segment_collector.collect(doc, score);
+            }
+            doc = scorer.advance();
+        }
+        Ok(segment_collector.harvest())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BudgetedCollector;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STRING};
+    use crate::{Index, Term};
+    use std::time::Duration;
+
+    fn build_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let value = if i % 2 == 0 { "even" } else { "odd" };
+            index_writer.add_document(doc!(text => value))?;
+            if i % 30 == 29 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "odd"));
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    #[test]
+    fn test_budgeted_collector_unlimited() -> crate::Result<()> {
+        let index = build_index()?;
+        let searcher = index.reader()?.searcher();
+        let count = searcher.search(&AllQuery, &BudgetedCollector::new(Count))?;
+        assert_eq!(count.fruit, 50);
+        assert!(!count.truncated);
+        let collector = BudgetedCollector::new(Count)
+            .with_timeout(Duration::from_secs(3600))
+            .with_max_docs(50);
+        let count = searcher.search(&AllQuery, &collector)?;
+        assert_eq!(count.fruit, 50);
+        assert!(!count.truncated);
+        Ok(())
+    }
+
+    #[test]
+    fn test_budgeted_collector_max_docs() -> crate::Result<()> {
+        let index = build_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(text, "even"),
+            IndexRecordOption::Basic,
+        );
+        let collector = BudgetedCollector::new((Count, TopDocs::with_limit(100))).with_max_docs(45);
+        let fruit = searcher.search(&query, &collector)?;
+        let (count, top_docs) = fruit.fruit;
+        assert_eq!(count, 45);
+        assert_eq!(top_docs.len(), 45);
+        assert!(fruit.truncated);
+        Ok(())
+    }
+
+    #[test]
+    fn test_budgeted_collector_timeout() -> crate::Result<()> {
+        let index = build_index()?;
+        let searcher = index.reader()?.searcher();
+        let collector = BudgetedCollector::new(Count).with_timeout(Duration::from_secs(0));
+        let count = searcher.search(&AllQuery, &collector)?;
+        assert_eq!(count.fruit, 0);
+        assert!(count.truncated);
+        Ok(())
+    }
+}
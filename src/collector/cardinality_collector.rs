@@ -0,0 +1,434 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader, MultiValuedFastFieldReader};
+use crate::schema::{Cardinality, Field, FieldType};
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use common::ReadOnlyBitSet;
+use serde::{Deserialize, Serialize};
+
+/// Precision used by `CardinalityCollector::new`.
+/// The sketch then uses 16KB, for a standard error of 0.8%.
+const DEFAULT_PRECISION: u8 = 14;
+
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+
+/// Mixes the bits of a value, so that the hashes of close values
+/// (e.g. consecutive ids or timestamps) are uniformly distributed.
+/// (this is the finalizer of splitmix64)
+#[inline]
+fn hash_u64(val: u64) -> u64 {
+    let mut z = val.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// HyperLogLog sketch, estimating the number of distinct values
+/// inserted into it.
+///
+/// The sketch has `2^precision` one-byte registers, and the standard error
+/// of its estimate is about `1.04 / sqrt(2^precision)`.
+/// As in HyperLogLog++, values are hashed on 64 bits and
+/// small cardinalities are estimated by linear counting.
+///
+/// Two sketches with the same precision can be merged: the merged sketch
+/// is the sketch of the union of the values. This makes it possible to
+/// compute the cardinality over several segments, or to store the sketch
+/// of each day and compute the cardinality over a range of days.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Creates an empty sketch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not within `[4, 18]`.
+    pub fn with_precision(precision: u8) -> HyperLogLog {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "The precision of a HyperLogLog must be within [{}, {}], got {}.",
+            MIN_PRECISION,
+            MAX_PRECISION,
+            precision
+        );
+        HyperLogLog {
+            precision,
+            registers: vec![0u8; 1 << precision],
+        }
+    }
+
+    /// Returns the precision of the sketch.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Adds a value to the sketch.
+    #[inline]
+    pub fn insert(&mut self, val: u64) {
+        let hash = hash_u64(val);
+        let register_id = (hash >> (64 - self.precision)) as usize;
+        // The trailing one bounds the rank if all of the remaining bits are 0.
+        let remaining_bits = (hash << self.precision) | (1u64 << (self.precision - 1));
+        let rank = remaining_bits.leading_zeros() as u8 + 1;
+        let register = &mut self.registers[register_id];
+        if rank > *register {
+            *register = rank;
+        }
+    }
+
+    /// Merges `other` into `self`.
+    ///
+    /// Returns an error if the two sketches do not have the same precision.
+    pub fn merge(&mut self, other: &HyperLogLog) -> crate::Result<()> {
+        if self.precision != other.precision {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Cannot merge HyperLogLog sketches of different precisions ({} and {}).",
+                self.precision, other.precision
+            )));
+        }
+        for (register, &other_register) in self.registers.iter_mut().zip(&other.registers) {
+            if other_register > *register {
+                *register = other_register;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the estimated number of distinct values inserted into the sketch.
+    pub fn estimate(&self) -> u64 {
+        let num_registers = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / num_registers),
+        };
+        let mut sum = 0.0f64;
+        let mut num_zeros = 0usize;
+        for &register in &self.registers {
+            sum += 1.0 / (1u64 << register) as f64;
+            if register == 0 {
+                num_zeros += 1;
+            }
+        }
+        let raw_estimate = alpha * num_registers * num_registers / sum;
+        let estimate = if raw_estimate <= 2.5 * num_registers && num_zeros > 0 {
+            num_registers * (num_registers / num_zeros as f64).ln()
+        } else {
+            raw_estimate
+        };
+        estimate.round() as u64
+    }
+}
+
+/// The `CardinalityCollector` estimates the number of distinct values
+/// of a fast field among the matching documents, using a [`HyperLogLog`] sketch.
+///
+/// The field must be a `u64`, `i64`, `f64` or date fast field, single-valued or
+/// multi-valued. Documents without any value are ignored.
+///
+/// The fruit is the merged sketch of all of the segments: its
+/// [`estimate`](./struct.HyperLogLog.html#method.estimate) is the approximate count
+/// of distinct values, and it can be merged with the fruits of other searches.
+///
+/// ```rust
+/// use tantivy::collector::CardinalityCollector;
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, FAST};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let user_id = schema_builder.add_u64_field("user_id", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for i in 0..1_000u64 {
+///     index_writer.add_document(doc!(user_id => i % 300))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let sketch = searcher.search(&AllQuery, &CardinalityCollector::new(user_id))?;
+/// let num_users = sketch.estimate();
+/// assert!(num_users >= 290 && num_users <= 310);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CardinalityCollector {
+    field: Field,
+    precision: u8,
+}
+
+impl CardinalityCollector {
+    /// Creates a collector estimating the number of distinct values of `field`,
+    /// with a sketch of precision 14 (16KB, standard error of 0.8%).
+    pub fn new(field: Field) -> CardinalityCollector {
+        CardinalityCollector {
+            field,
+            precision: DEFAULT_PRECISION,
+        }
+    }
+
+    /// Sets the precision of the sketch. See [`HyperLogLog`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not within `[4, 18]`.
+    pub fn with_precision(mut self, precision: u8) -> CardinalityCollector {
+        assert!((MIN_PRECISION..=MAX_PRECISION).contains(&precision));
+        self.precision = precision;
+        self
+    }
+}
+
+enum FieldValues {
+    SingleValue {
+        values: DynamicFastFieldReader<u64>,
+        presence_bitset: Option<ReadOnlyBitSet>,
+    },
+    MultiValues {
+        values: MultiValuedFastFieldReader<u64>,
+        buffer: Vec<u64>,
+    },
+}
+
+pub struct CardinalitySegmentCollector {
+    field_values: FieldValues,
+    sketch: HyperLogLog,
+}
+
+impl SegmentCollector for CardinalitySegmentCollector {
+    type Fruit = HyperLogLog;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        match &mut self.field_values {
+            FieldValues::SingleValue {
+                values,
+                presence_bitset,
+            } => {
+                let has_value = presence_bitset
+                    .as_ref()
+                    .map(|presence_bitset| presence_bitset.contains(doc))
+                    .unwrap_or(true);
+                if has_value {
+                    self.sketch.insert(values.get(doc));
+                }
+            }
+            FieldValues::MultiValues { values, buffer } => {
+                values.get_vals(doc, buffer);
+                for &val in buffer.iter() {
+                    self.sketch.insert(val);
+                }
+            }
+        }
+    }
+
+    fn harvest(self) -> HyperLogLog {
+        self.sketch
+    }
+}
+
+impl Collector for CardinalityCollector {
+    type Fruit = HyperLogLog;
+    type Child = CardinalitySegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<CardinalitySegmentCollector> {
+        let field_entry = segment.schema().get_field_entry(self.field);
+        let cardinality_opt = match field_entry.field_type() {
+            FieldType::U64(options)
+            | FieldType::I64(options)
+            | FieldType::F64(options)
+            | FieldType::Date(options) => options.get_fastfield_cardinality(),
+            _ => None,
+        };
+        let fast_fields = segment.fast_fields();
+        let field_values = match cardinality_opt {
+            Some(Cardinality::SingleValue) => FieldValues::SingleValue {
+                values: fast_fields.u64_lenient(self.field)?,
+                presence_bitset: fast_fields.presence_bitset(self.field)?,
+            },
+            Some(Cardinality::MultiValues) => FieldValues::MultiValues {
+                values: fast_fields.u64s_lenient(self.field)?,
+                buffer: Vec::new(),
+            },
+            None => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} is not a numeric or date fast field.",
+                    field_entry.name()
+                )));
+            }
+        };
+        Ok(CardinalitySegmentCollector {
+            field_values,
+            sketch: HyperLogLog::with_precision(self.precision),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_sketches: Vec<HyperLogLog>) -> crate::Result<HyperLogLog> {
+        let mut sketch = HyperLogLog::with_precision(self.precision);
+        for segment_sketch in &segment_sketches {
+            sketch.merge(segment_sketch)?;
+        }
+        Ok(sketch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CardinalityCollector, HyperLogLog};
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{Cardinality, IndexRecordOption, IntOptions, Schema, FAST, STRING};
+    use crate::{Document, Index, TantivyError, Term};
+
+    fn assert_close(estimate: u64, expected: u64, max_relative_error: f64) {
+        let relative_error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(
+            relative_error <= max_relative_error,
+            "estimate {} too far from {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_hyperloglog_empty() {
+        assert_eq!(HyperLogLog::with_precision(14).estimate(), 0);
+        assert_eq!(HyperLogLog::with_precision(4).estimate(), 0);
+    }
+
+    #[test]
+    fn test_hyperloglog_small_cardinalities() {
+        let mut sketch = HyperLogLog::with_precision(14);
+        for val in 0..10u64 {
+            sketch.insert(val);
+            sketch.insert(val);
+        }
+        assert_eq!(sketch.estimate(), 10);
+        for val in 10..1_000u64 {
+            sketch.insert(val);
+        }
+        assert_close(sketch.estimate(), 1_000, 0.02);
+    }
+
+    #[test]
+    fn test_hyperloglog_large_cardinalities() {
+        for &precision in &[4u8, 10, 14, 18] {
+            let mut sketch = HyperLogLog::with_precision(precision);
+            for val in 0..500_000u64 {
+                sketch.insert(val * 7);
+            }
+            let max_relative_error = 4.0 * 1.04 / ((1u64 << precision) as f64).sqrt();
+            assert_close(sketch.estimate(), 500_000, max_relative_error);
+        }
+    }
+
+    #[test]
+    fn test_hyperloglog_merge() -> crate::Result<()> {
+        let mut left = HyperLogLog::with_precision(12);
+        let mut right = HyperLogLog::with_precision(12);
+        let mut all = HyperLogLog::with_precision(12);
+        for val in 0..20_000u64 {
+            left.insert(val);
+            all.insert(val);
+        }
+        for val in 10_000..50_000u64 {
+            right.insert(val);
+            all.insert(val);
+        }
+        left.merge(&right)?;
+        assert_eq!(left, all);
+        assert!(matches!(
+            left.merge(&HyperLogLog::with_precision(13)),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hyperloglog_invalid_precision() {
+        HyperLogLog::with_precision(19);
+    }
+
+    #[test]
+    fn test_cardinality_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let user_id = schema_builder.add_i64_field("user_id", FAST);
+        let tags = schema_builder.add_u64_field(
+            "tags",
+            IntOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..3_000i64 {
+            let parity = if i % 2 == 0 { "even" } else { "odd" };
+            index_writer.add_document(doc!(
+                text => parity,
+                user_id => -(i % 1_000),
+                tags => (i % 7) as u64,
+                tags => (i % 11) as u64 + 100
+            ))?;
+            if i % 1_000 == 999 {
+                index_writer.commit()?;
+            }
+        }
+        // documents without any value are ignored.
+        index_writer.add_document(Document::default())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert!(searcher.segment_readers().len() > 1);
+
+        let user_sketch = searcher.search(&AllQuery, &CardinalityCollector::new(user_id))?;
+        assert_eq!(user_sketch.precision(), 14);
+        assert_close(user_sketch.estimate(), 1_000, 0.03);
+        let tags_sketch = searcher.search(&AllQuery, &CardinalityCollector::new(tags))?;
+        assert_eq!(tags_sketch.estimate(), 18);
+
+        let even_query = TermQuery::new(
+            Term::from_field_text(text, "even"),
+            IndexRecordOption::Basic,
+        );
+        let even_user_sketch = searcher.search(
+            &even_query,
+            &CardinalityCollector::new(user_id).with_precision(10),
+        )?;
+        assert_eq!(even_user_sketch.precision(), 10);
+        assert_close(even_user_sketch.estimate(), 500, 0.1);
+
+        assert!(matches!(
+            searcher.search(&AllQuery, &CardinalityCollector::new(text)),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cardinality_collector_ignores_missing_values() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let val = schema_builder.add_u64_field("val", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(val => 3u64))?;
+        index_writer.add_document(Document::default())?;
+        index_writer.add_document(doc!(val => 5u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let sketch = searcher.search(&AllQuery, &CardinalityCollector::new(val))?;
+        assert_eq!(sketch.estimate(), 2);
+        Ok(())
+    }
+}
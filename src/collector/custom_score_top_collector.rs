@@ -121,3 +121,19 @@ where
         (self)(doc)
     }
 }
+
+/// `CustomScorer` built from a closure that may fail to build the segment scorer,
+/// e.g. when opening a fast field reader.
+pub(crate) struct TryCustomScorer<F>(pub F);
+
+impl<F, TScore, T> CustomScorer<TScore> for TryCustomScorer<F>
+where
+    F: 'static + Send + Sync + Fn(&SegmentReader) -> crate::Result<T>,
+    T: CustomSegmentScorer<TScore>,
+{
+    type Child = T;
+
+    fn segment_scorer(&self, segment_reader: &SegmentReader) -> crate::Result<Self::Child> {
+        (self.0)(segment_reader)
+    }
+}
@@ -0,0 +1,312 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader};
+use crate::schema::{Cardinality, Field, FieldType};
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use common::ReadOnlyBitSet;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// The `DedupCollector` wraps a top docs collector, and only passes it the best
+/// scoring document of each value of a single-valued fast field.
+///
+/// For instance, with a `domain_id` fast field, it returns at most one page per domain.
+/// Unlike the [`TopHitsCollector`](./struct.TopHitsCollector.html), it only keeps a
+/// single document per value, and does not need to know in advance how many values
+/// will be returned.
+///
+/// Ties between documents with the same value and the same score are broken in favor
+/// of the document with the smallest `DocAddress`. Documents without any value for the
+/// field are never deduplicated.
+///
+/// ```rust
+/// use tantivy::collector::{DedupCollector, TopDocs};
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, DocAddress, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let domain_id = schema_builder.add_u64_field("domain_id", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(title => "rust search", domain_id => 1u64))?;
+/// index_writer.add_document(doc!(title => "rust search rust", domain_id => 1u64))?;
+/// index_writer.add_document(doc!(title => "rust compiler", domain_id => 2u64))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("rust")?;
+/// let top_docs = searcher.search(&query, &DedupCollector::new(domain_id, TopDocs::with_limit(10)))?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+/// # Ok(())
+/// # }
+/// ```
+pub struct DedupCollector<TCollector> {
+    field: Field,
+    collector: TCollector,
+}
+
+impl<TCollector> DedupCollector<TCollector>
+where
+    TCollector: Collector<Fruit = Vec<(Score, DocAddress)>>,
+{
+    /// Creates a collector passing the best document of each value of
+    /// the single-valued fast field `field` to `collector`.
+    pub fn new(field: Field, collector: TCollector) -> DedupCollector<TCollector> {
+        DedupCollector { field, collector }
+    }
+}
+
+/// Returns true if the hit `(score, doc_address)` ranks before `other`.
+fn is_better_hit(score: Score, doc_address: DocAddress, other: &(Score, DocAddress)) -> bool {
+    score > other.0 || (score == other.0 && doc_address < other.1)
+}
+
+impl<TCollector> Collector for DedupCollector<TCollector>
+where
+    TCollector: Collector<Fruit = Vec<(Score, DocAddress)>>,
+    TCollector::Child: SegmentCollector<Fruit = Vec<(Score, DocAddress)>>,
+{
+    type Fruit = Vec<(Score, DocAddress)>;
+
+    type Child = DedupSegmentCollector<TCollector::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<DedupSegmentCollector<TCollector::Child>> {
+        let field_entry = segment.schema().get_field_entry(self.field);
+        let cardinality_opt = match field_entry.field_type() {
+            FieldType::U64(options)
+            | FieldType::I64(options)
+            | FieldType::F64(options)
+            | FieldType::Date(options) => options.get_fastfield_cardinality(),
+            _ => None,
+        };
+        if cardinality_opt != Some(Cardinality::SingleValue) {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a single-valued fast field.",
+                field_entry.name()
+            )));
+        }
+        let fast_fields = segment.fast_fields();
+        Ok(DedupSegmentCollector {
+            segment_ord: segment_local_id,
+            values: fast_fields.u64_lenient(self.field)?,
+            presence_bitset: fast_fields.presence_bitset(self.field)?,
+            best_docs: HashMap::new(),
+            docs_without_value: Vec::new(),
+            segment_collector: self.collector.for_segment(segment_local_id, segment)?,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    /// Deduplicates the hits of the different segments, before merging them.
+    ///
+    /// The best document of a value is among the top docs of its segment, so
+    /// deduplicating the top docs of each segment is enough.
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<(Option<u64>, Score, DocAddress)>>,
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let mut best_hits: HashMap<u64, (Score, DocAddress)> = HashMap::new();
+        let mut hits_without_value: Vec<(Score, DocAddress)> = Vec::new();
+        for (value_opt, score, doc_address) in segment_fruits.into_iter().flatten() {
+            let value = match value_opt {
+                Some(value) => value,
+                None => {
+                    hits_without_value.push((score, doc_address));
+                    continue;
+                }
+            };
+            match best_hits.entry(value) {
+                Entry::Occupied(mut entry) => {
+                    if is_better_hit(score, doc_address, entry.get()) {
+                        entry.insert((score, doc_address));
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((score, doc_address));
+                }
+            }
+        }
+        hits_without_value.extend(best_hits.into_values());
+        self.collector.merge_fruits(vec![hits_without_value])
+    }
+}
+
+pub struct DedupSegmentCollector<TSegmentCollector> {
+    segment_ord: SegmentOrdinal,
+    values: DynamicFastFieldReader<u64>,
+    presence_bitset: Option<ReadOnlyBitSet>,
+    best_docs: HashMap<u64, (DocId, Score)>,
+    docs_without_value: Vec<(DocId, Score)>,
+    segment_collector: TSegmentCollector,
+}
+
+impl<TSegmentCollector> SegmentCollector for DedupSegmentCollector<TSegmentCollector>
+where
+    TSegmentCollector: SegmentCollector<Fruit = Vec<(Score, DocAddress)>>,
+{
+    type Fruit = Vec<(Option<u64>, Score, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let has_value = self
+            .presence_bitset
+            .as_ref()
+            .map(|presence_bitset| presence_bitset.contains(doc))
+            .unwrap_or(true);
+        if !has_value {
+            self.docs_without_value.push((doc, score));
+            return;
+        }
+        // the documents are collected by increasing doc id, so the first one
+        // wins the ties.
+        match self.best_docs.entry(self.values.get(doc)) {
+            Entry::Occupied(mut entry) => {
+                if score > entry.get().1 {
+                    entry.insert((doc, score));
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((doc, score));
+            }
+        }
+    }
+
+    fn harvest(mut self) -> Vec<(Option<u64>, Score, DocAddress)> {
+        let mut values: HashMap<DocId, u64> = HashMap::with_capacity(self.best_docs.len());
+        let mut docs = std::mem::take(&mut self.docs_without_value);
+        for (value, (doc, score)) in std::mem::take(&mut self.best_docs) {
+            values.insert(doc, value);
+            docs.push((doc, score));
+        }
+        // the wrapped collector expects the documents in increasing order.
+        docs.sort_unstable_by_key(|&(doc, _)| doc);
+        for (doc, score) in docs {
+            self.segment_collector.collect(doc, score);
+        }
+        let segment_ord = self.segment_ord;
+        self.segment_collector
+            .harvest()
+            .into_iter()
+            .map(|(score, doc_address)| {
+                debug_assert_eq!(doc_address.segment_ord, segment_ord);
+                (values.get(&doc_address.doc_id).cloned(), score, doc_address)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DedupCollector;
+    use crate::collector::{ScoreNormalization, TopDocs};
+    use crate::fastfield::FastFieldReader;
+    use crate::query::{AllQuery, QueryParser};
+    use crate::schema::{Schema, FAST, STRING, TEXT};
+    use crate::{DocAddress, Document, Index, TantivyError};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_dedup_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let domain = schema_builder.add_i64_field("domain", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..40u64 {
+            let mut doc = Document::default();
+            // the more "hello" tokens, the higher the score.
+            doc.add_text(text, "hello ".repeat(1 + (i % 7) as usize) + "world");
+            if i % 10 != 9 {
+                doc.add_i64(domain, (i % 5) as i64 - 2);
+            }
+            index_writer.add_document(doc)?;
+            if i % 20 == 19 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("hello")?;
+
+        let domain_of = |doc_address: DocAddress| -> Option<u64> {
+            let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+            let fast_fields = segment_reader.fast_fields();
+            let has_value = fast_fields
+                .presence_bitset(domain)
+                .unwrap()
+                .map(|bitset| bitset.contains(doc_address.doc_id))
+                .unwrap_or(true);
+            if !has_value {
+                return None;
+            }
+            Some(
+                fast_fields
+                    .u64_lenient(domain)
+                    .unwrap()
+                    .get(doc_address.doc_id),
+            )
+        };
+        // Expected: the best hit of each domain, and all of the hits without domain.
+        let all_hits = searcher.search(&query, &TopDocs::with_limit(40))?;
+        let mut seen_domains = HashSet::new();
+        let expected: Vec<_> = all_hits
+            .into_iter()
+            .filter(|&(_, doc_address)| match domain_of(doc_address) {
+                Some(domain_value) => seen_domains.insert(domain_value),
+                None => true,
+            })
+            .collect();
+        assert_eq!(expected.len(), 5 + 4);
+
+        let top_docs = searcher.search(
+            &query,
+            &DedupCollector::new(domain, TopDocs::with_limit(20)),
+        )?;
+        assert_eq!(top_docs, expected);
+        let top_docs = searcher.search(
+            &query,
+            &DedupCollector::new(domain, TopDocs::with_limit(3).and_offset(2)),
+        )?;
+        assert_eq!(top_docs, &expected[2..5]);
+
+        // The wrapped collector post-processes the deduplicated hits.
+        let top_docs = searcher.search(
+            &query,
+            &DedupCollector::new(
+                domain,
+                TopDocs::with_limit(20).with_score_normalization(ScoreNormalization::MinMax),
+            ),
+        )?;
+        assert_eq!(top_docs.len(), expected.len());
+        assert_eq!(top_docs[0].0, 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedup_collector_requires_single_valued_fast_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert!(matches!(
+            searcher.search(
+                &AllQuery,
+                &DedupCollector::new(text, TopDocs::with_limit(1))
+            ),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::query::{Query, Scorer, Weight};
+use crate::{DocId, DocSet, Score, Searcher, SegmentOrdinal, SegmentReader};
+
+/// Bucket of a [`FiltersCollector`](./struct.FiltersCollector.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterBucket<TFruit> {
+    /// Key given to the filter in `FiltersCollector::add_filter`.
+    pub key: String,
+    /// Fruit of the sub-collector, over the documents matching the filter.
+    pub fruit: TFruit,
+}
+
+/// The `FiltersCollector` defines one bucket per named filter query,
+/// and runs a sub-collector on the matching documents of each bucket.
+///
+/// All of the buckets are computed in a single search: the documents matching the
+/// main query are checked against each of the filters. A document is collected in
+/// all of the buckets whose filter it matches, with its score for the main query.
+///
+/// The weights of the filters are created when they are added, for a given `Searcher`:
+/// the collector must then be used with that same searcher.
+///
+/// The fruit has one `FilterBucket` per filter, in the order in which the filters were added.
+///
+/// ```rust
+/// use tantivy::collector::{Count, FiltersCollector};
+/// use tantivy::query::{AllQuery, TermQuery};
+/// use tantivy::schema::{IndexRecordOption, Schema, STRING};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let level = schema_builder.add_text_field("level", STRING);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for &val in &["error", "warning", "info", "error", "info"] {
+///     index_writer.add_document(doc!(level => val))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let level_query = |val: &str| {
+///     TermQuery::new(Term::from_field_text(level, val), IndexRecordOption::Basic)
+/// };
+/// let collector = FiltersCollector::new(Count)
+///     .add_filter("errors", &level_query("error"), &searcher)?
+///     .add_filter("warnings", &level_query("warning"), &searcher)?;
+/// let buckets = searcher.search(&AllQuery, &collector)?;
+/// let counts: Vec<(&str, usize)> = buckets
+///     .iter()
+///     .map(|bucket| (bucket.key.as_str(), bucket.fruit))
+///     .collect();
+/// assert_eq!(counts, vec![("errors", 2), ("warnings", 1)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FiltersCollector<TCollector> {
+    filters: Vec<(String, Box<dyn Weight>)>,
+    collector: TCollector,
+}
+
+impl<TCollector: Collector> FiltersCollector<TCollector> {
+    /// Creates a `FiltersCollector` running `collector` on each bucket.
+    ///
+    /// Filters are then added with `add_filter`.
+    pub fn new(collector: TCollector) -> FiltersCollector<TCollector> {
+        FiltersCollector {
+            filters: Vec::new(),
+            collector,
+        }
+    }
+
+    /// Adds a bucket with the given key, containing the documents matching `filter`.
+    ///
+    /// The filter is not scored. Its weight is created for `searcher`, the searcher the
+    /// collector is meant to be used with.
+    pub fn add_filter<TKey: ToString>(
+        mut self,
+        key: TKey,
+        filter: &dyn Query,
+        searcher: &Searcher,
+    ) -> crate::Result<FiltersCollector<TCollector>> {
+        let weight = filter.weight(searcher, false)?;
+        self.filters.push((key.to_string(), weight));
+        Ok(self)
+    }
+}
+
+impl<TCollector: Collector> Collector for FiltersCollector<TCollector> {
+    type Fruit = Vec<FilterBucket<TCollector::Fruit>>;
+
+    type Child = FiltersSegmentCollector<TCollector::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let buckets = self
+            .filters
+            .iter()
+            .map(|(_, weight)| {
+                let filter_scorer = weight.scorer(segment_reader, 1.0)?;
+                let segment_collector = self
+                    .collector
+                    .for_segment(segment_local_id, segment_reader)?;
+                Ok((filter_scorer, segment_collector))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(FiltersSegmentCollector { buckets })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<<TCollector::Child as SegmentCollector>::Fruit>>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut bucket_fruits: Vec<Vec<_>> = self.filters.iter().map(|_| Vec::new()).collect();
+        for segment_fruit in segment_fruits {
+            for (bucket_fruit, fruit) in bucket_fruits.iter_mut().zip(segment_fruit) {
+                bucket_fruit.push(fruit);
+            }
+        }
+        self.filters
+            .iter()
+            .zip(bucket_fruits)
+            .map(|((key, _), fruits)| {
+                Ok(FilterBucket {
+                    key: key.clone(),
+                    fruit: self.collector.merge_fruits(fruits)?,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct FiltersSegmentCollector<TSegmentCollector> {
+    buckets: Vec<(Box<dyn Scorer>, TSegmentCollector)>,
+}
+
+impl<TSegmentCollector: SegmentCollector> SegmentCollector
+    for FiltersSegmentCollector<TSegmentCollector>
+{
+    type Fruit = Vec<TSegmentCollector::Fruit>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        // Documents are collected in increasing order,
+        // so the filters can be advanced with `seek`.
+        for (filter_scorer, segment_collector) in &mut self.buckets {
+            if filter_scorer.doc() < doc {
+                filter_scorer.seek(doc);
+            }
+            if filter_scorer.doc() == doc {
+                segment_collector.collect(doc, score);
+            }
+        }
+    }
+
+    fn harvest(self) -> Vec<TSegmentCollector::Fruit> {
+        self.buckets
+            .into_iter()
+            .map(|(_, segment_collector)| segment_collector.harvest())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FiltersCollector;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{AllQuery, Query, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{Index, Term};
+
+    #[test]
+    fn test_filters_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let level = match i % 4 {
+                0 => "error",
+                1 => "warning",
+                2 => "error timeout",
+                _ => "info",
+            };
+            index_writer.add_document(doc!(text => level))?;
+            if i % 30 == 29 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "info"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert!(searcher.segment_readers().len() > 1);
+        let term_query = |val: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, val),
+                IndexRecordOption::Basic,
+            ))
+        };
+        let collector = FiltersCollector::new((Count, TopDocs::with_limit(3)))
+            .add_filter("errors", term_query("error").as_ref(), &searcher)?
+            .add_filter("warnings", term_query("warning").as_ref(), &searcher)?
+            .add_filter("timeouts", term_query("timeout").as_ref(), &searcher)?
+            .add_filter("infos", term_query("info").as_ref(), &searcher)?;
+        let buckets = searcher.search(&AllQuery, &collector)?;
+        let counts: Vec<(&str, usize)> = buckets
+            .iter()
+            .map(|bucket| (bucket.key.as_str(), bucket.fruit.0))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("errors", 50),
+                ("warnings", 25),
+                ("timeouts", 25),
+                ("infos", 0)
+            ]
+        );
+        assert_eq!(buckets[0].fruit.1.len(), 3);
+
+        // the buckets are computed over the documents matching the main query.
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("timeout OR warning")?;
+        let buckets = searcher.search(&query, &collector)?;
+        let counts: Vec<usize> = buckets.iter().map(|bucket| bucket.fruit.0).collect();
+        assert_eq!(counts, vec![25, 25, 25, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters_collector_no_filter() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let buckets = searcher.search(&AllQuery, &FiltersCollector::new(Count))?;
+        assert!(buckets.is_empty());
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use crate::collector::{Collector, SegmentCollector};
-use crate::fastfield::{DynamicFastFieldReader, FastFieldReader, FastValue};
+use crate::fastfield::{FastValue, OptionalFastFieldReader};
 use crate::schema::{Field, Type};
 use crate::{DocId, Score};
 use fastdivide::DividerU64;
@@ -14,6 +14,9 @@ use fastdivide::DividerU64;
 /// An histogram is then defined as a `Vec<u64>` of length `num_buckets`, that contains a count of
 /// documents for each value bucket.
 ///
+/// The documents without any value for the field are ignored, unless
+/// a value is substituted with [`HistogramCollector::with_missing()`].
+///
 /// See also [`HistogramCollector::new()`].
 ///
 /// # Warning
@@ -25,6 +28,7 @@ pub struct HistogramCollector {
     num_buckets: usize,
     divider: DividerU64,
     field: Field,
+    missing: Option<u64>,
 }
 
 impl HistogramCollector {
@@ -54,8 +58,19 @@ impl HistogramCollector {
             num_buckets,
             field,
             divider: DividerU64::divide_by(bucket_width),
+            missing: None,
         }
     }
+
+    /// Counts the documents without any value for the field as if their value was `missing`,
+    /// instead of ignoring them.
+    pub fn with_missing<TFastValue: FastValue>(
+        mut self,
+        missing: TFastValue,
+    ) -> HistogramCollector {
+        self.missing = Some(missing.to_u64());
+        self
+    }
 }
 
 struct HistogramComputer {
@@ -84,15 +99,17 @@ impl HistogramComputer {
 }
 pub struct SegmentHistogramCollector {
     histogram_computer: HistogramComputer,
-    ff_reader: DynamicFastFieldReader<u64>,
+    ff_reader: OptionalFastFieldReader<u64>,
+    missing: Option<u64>,
 }
 
 impl SegmentCollector for SegmentHistogramCollector {
     type Fruit = Vec<u64>;
 
     fn collect(&mut self, doc: DocId, _score: Score) {
-        let value = self.ff_reader.get(doc);
-        self.histogram_computer.add_value(value);
+        if let Some(value) = self.ff_reader.get(doc).or(self.missing) {
+            self.histogram_computer.add_value(value);
+        }
     }
 
     fn harvest(self) -> Self::Fruit {
@@ -109,7 +126,11 @@ impl Collector for HistogramCollector {
         _segment_local_id: crate::SegmentOrdinal,
         segment: &crate::SegmentReader,
     ) -> crate::Result<Self::Child> {
-        let ff_reader = segment.fast_fields().u64_lenient(self.field)?;
+        let fast_fields = segment.fast_fields();
+        let ff_reader = OptionalFastFieldReader::new(
+            fast_fields.u64_lenient(self.field)?,
+            fast_fields.presence_bitset(self.field)?,
+        );
         Ok(SegmentHistogramCollector {
             histogram_computer: HistogramComputer {
                 counts: vec![0; self.num_buckets],
@@ -117,6 +138,7 @@ impl Collector for HistogramCollector {
                 divider: self.divider,
             },
             ff_reader,
+            missing: self.missing,
         })
     }
 
@@ -150,7 +172,7 @@ mod tests {
     use super::{add_vecs, HistogramCollector, HistogramComputer};
     use crate::chrono::{TimeZone, Utc};
     use crate::schema::{Schema, FAST};
-    use crate::{doc, query, Index};
+    use crate::{doc, query, Document, Index};
     use fastdivide::DividerU64;
     use query::AllQuery;
 
@@ -288,4 +310,28 @@ mod tests {
         assert_eq!(week_histogram, vec![0, 0, 1, 1, 0, 0, 1, 0, 0, 0]);
         Ok(())
     }
+
+    #[test]
+    fn test_histogram_collector_missing() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let count = schema_builder.add_u64_field("count", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(count => 3u64))?;
+        index_writer.add_document(Document::default())?;
+        index_writer.add_document(doc!(count => 0u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let histogram_collector = HistogramCollector::new(count, 0u64, 2, 3);
+        assert_eq!(
+            searcher.search(&AllQuery, &histogram_collector)?,
+            vec![1, 1, 0]
+        );
+        let histogram_collector = HistogramCollector::new(count, 0u64, 2, 3).with_missing(5u64);
+        assert_eq!(
+            searcher.search(&AllQuery, &histogram_collector)?,
+            vec![1, 1, 1]
+        );
+        Ok(())
+    }
 }
@@ -96,13 +96,28 @@ pub use self::count_collector::Count;
 mod histogram_collector;
 pub use histogram_collector::HistogramCollector;
 
+mod range_collector;
+pub use self::range_collector::{RangeBucket, RangeCollector};
+
+mod stats_collector;
+pub use self::stats_collector::{Stats, StatsCollector};
+
+mod cardinality_collector;
+pub use self::cardinality_collector::{CardinalityCollector, HyperLogLog};
+
+mod random_sample_collector;
+pub use self::random_sample_collector::RandomSampleCollector;
+
+mod top_hits_collector;
+pub use self::top_hits_collector::{TopHit, TopHitsCollector};
+
 mod multi_collector;
 pub use self::multi_collector::MultiCollector;
 
 mod top_collector;
 
 mod top_score_collector;
-pub use self::top_score_collector::TopDocs;
+pub use self::top_score_collector::{ScoreNormalization, TopDocs};
 
 mod custom_score_top_collector;
 pub use self::custom_score_top_collector::{CustomScorer, CustomSegmentScorer};
@@ -121,6 +136,21 @@ pub use self::docset_collector::DocSetCollector;
 mod filter_collector_wrapper;
 pub use self::filter_collector_wrapper::FilterCollector;
 
+mod filters_collector;
+pub use self::filters_collector::{FilterBucket, FiltersCollector};
+
+mod budgeted_collector;
+pub use self::budgeted_collector::{BudgetedCollector, BudgetedFruit};
+
+mod terms_collector;
+pub use self::terms_collector::{TermCounts, TermsCollector};
+
+mod dedup_collector;
+pub use self::dedup_collector::DedupCollector;
+
+mod ordinal_count_collector;
+pub use self::ordinal_count_collector::{OrdinalCountCollector, OrdinalCounts};
+
 /// `Fruit` is the type for the result of our collection.
 /// e.g. `usize` for the `Count` collector.
 pub trait Fruit: Send + downcast_rs::Downcast {}
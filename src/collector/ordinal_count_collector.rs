@@ -0,0 +1,279 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::FacetReader;
+use crate::schema::Facet;
+use crate::termdict::TermOrdinal;
+use crate::{DocId, GlobalOrdinals, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+/// The `OrdinalCountCollector` counts the matching documents for each facet of a facet
+/// field, by [global ordinal](../struct.GlobalOrdinals.html).
+///
+/// The documents are counted per local ordinal in each segment, and the counts are
+/// mapped to the global ordinals when the segment is harvested, so that merging the counts
+/// of the segments never compares the facets themselves. Unlike the
+/// [`FacetCollector`](./struct.FacetCollector.html), only the facets of the documents are
+/// counted, not their ancestors.
+///
+/// The global ordinals must be the ones of the searcher the collector is used with.
+///
+/// ```rust
+/// use tantivy::collector::OrdinalCountCollector;
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Facet, Schema, INDEXED};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let category = schema_builder.add_facet_field("category", INDEXED);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(category => Facet::from("/books")))?;
+/// index_writer.add_document(doc!(category => Facet::from("/art")))?;
+/// index_writer.commit()?;
+/// index_writer.add_document(doc!(category => Facet::from("/books")))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let collector = OrdinalCountCollector::new(searcher.global_ordinals(category)?);
+/// let counts = searcher.search(&AllQuery, &collector)?;
+/// assert_eq!(counts.top_k_facets(1)?, vec![(Facet::from("/books"), 2)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrdinalCountCollector {
+    global_ordinals: Arc<GlobalOrdinals>,
+}
+
+impl OrdinalCountCollector {
+    /// Creates a collector counting the matching documents for each global ordinal.
+    pub fn new(global_ordinals: Arc<GlobalOrdinals>) -> OrdinalCountCollector {
+        OrdinalCountCollector { global_ordinals }
+    }
+}
+
+pub struct OrdinalCountSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    global_ordinals: Arc<GlobalOrdinals>,
+    facet_reader: FacetReader,
+    local_counts: Vec<u64>,
+    local_ords: Vec<TermOrdinal>,
+}
+
+impl SegmentCollector for OrdinalCountSegmentCollector {
+    type Fruit = Vec<(TermOrdinal, u64)>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        self.facet_reader.facet_ords(doc, &mut self.local_ords);
+        for &local_ord in &self.local_ords {
+            self.local_counts[local_ord as usize] += 1;
+        }
+    }
+
+    fn harvest(self) -> Vec<(TermOrdinal, u64)> {
+        let segment_mapping = self.global_ordinals.segment_mapping(self.segment_ord);
+        self.local_counts
+            .iter()
+            .zip(segment_mapping)
+            .filter(|&(&count, _)| count > 0)
+            .map(|(&count, &global_ord)| (global_ord, count))
+            .collect()
+    }
+}
+
+impl Collector for OrdinalCountCollector {
+    type Fruit = OrdinalCounts;
+    type Child = OrdinalCountSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<OrdinalCountSegmentCollector> {
+        let facet_reader = segment.facet_reader(self.global_ordinals.field())?;
+        let num_local_terms = facet_reader.num_facets();
+        let num_segments = self.global_ordinals.num_segments();
+        if segment_local_id as usize >= num_segments
+            || self.global_ordinals.segment_mapping(segment_local_id).len() != num_local_terms
+        {
+            return Err(TantivyError::InvalidArgument(
+                "The global ordinals were built for the segments of another searcher.".to_string(),
+            ));
+        }
+        Ok(OrdinalCountSegmentCollector {
+            segment_ord: segment_local_id,
+            global_ordinals: self.global_ordinals.clone(),
+            facet_reader,
+            local_counts: vec![0; num_local_terms],
+            local_ords: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_counts: Vec<Vec<(TermOrdinal, u64)>>,
+    ) -> crate::Result<OrdinalCounts> {
+        let mut counts = vec![0u64; self.global_ordinals.num_terms()];
+        for (global_ord, count) in segment_counts.into_iter().flatten() {
+            counts[global_ord as usize] += count;
+        }
+        Ok(OrdinalCounts {
+            global_ordinals: self.global_ordinals.clone(),
+            counts,
+        })
+    }
+}
+
+/// Number of matching documents per global ordinal, as returned by the
+/// [`OrdinalCountCollector`](./struct.OrdinalCountCollector.html).
+pub struct OrdinalCounts {
+    global_ordinals: Arc<GlobalOrdinals>,
+    counts: Vec<u64>,
+}
+
+impl OrdinalCounts {
+    /// Returns the number of matching documents with the global ordinal `global_ord`.
+    pub fn get(&self, global_ord: TermOrdinal) -> u64 {
+        self.counts.get(global_ord as usize).cloned().unwrap_or(0)
+    }
+
+    /// Returns the number of matching documents with `facet`.
+    pub fn get_facet(&self, facet: &Facet) -> crate::Result<u64> {
+        Ok(self
+            .global_ordinals
+            .global_ord(facet)?
+            .map(|global_ord| self.get(global_ord))
+            .unwrap_or(0))
+    }
+
+    /// Returns the top `k` global ordinals with their counts, sorted highest-to-lowest
+    /// by counts. Ordinals with the same count are sorted in increasing order.
+    pub fn top_k(&self, k: usize) -> Vec<(TermOrdinal, u64)> {
+        let mut ord_counts: Vec<(TermOrdinal, u64)> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(global_ord, &count)| (global_ord as TermOrdinal, count))
+            .collect();
+        // the sort is stable, and the ordinals are already sorted.
+        ord_counts.sort_by_key(|&(_, count)| Reverse(count));
+        ord_counts.truncate(k);
+        ord_counts
+    }
+
+    /// Same as [`top_k`](#method.top_k), with the facets of the global ordinals.
+    pub fn top_k_facets(&self, k: usize) -> crate::Result<Vec<(Facet, u64)>> {
+        self.top_k(k)
+            .into_iter()
+            .map(|(global_ord, count)| Ok((self.global_ordinals.facet(global_ord)?, count)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrdinalCountCollector;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{Facet, IndexRecordOption, Schema, INDEXED, STRING};
+    use crate::{Index, TantivyError, Term};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_global_ordinals() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let category = schema_builder.add_facet_field("category", INDEXED);
+        let kind = schema_builder.add_text_field("kind", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index.reader_builder().num_searchers(2).try_into()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(category => Facet::from("/b"), kind => "x"))?;
+        index_writer.add_document(doc!(category => Facet::from("/d"), kind => "y"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(category => Facet::from("/a"), kind => "x"))?;
+        index_writer.add_document(doc!(category => Facet::from("/d"), kind => "x"))?;
+        index_writer.add_document(doc!(kind => "x"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(
+            category => Facet::from("/c"),
+            category => Facet::from("/b"),
+            kind => "x"
+        ))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+
+        let global_ordinals = searcher.global_ordinals(category)?;
+        // The term dictionary of a facet field also contains the root facet.
+        assert_eq!(global_ordinals.num_terms(), 5);
+        let facets: Vec<Facet> = (0..5)
+            .map(|global_ord| global_ordinals.facet(global_ord))
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(
+            facets,
+            vec![
+                Facet::root(),
+                Facet::from("/a"),
+                Facet::from("/b"),
+                Facet::from("/c"),
+                Facet::from("/d")
+            ]
+        );
+        // The local ordinals of each segment map to the global ordinals of their facets.
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let mut facet_reader = segment_reader.facet_reader(category)?;
+            let mut facet = Facet::root();
+            for local_ord in 0..facet_reader.num_facets() as u64 {
+                facet_reader.facet_from_ord(local_ord, &mut facet)?;
+                let global_ord = global_ordinals.to_global(segment_ord as u32, local_ord);
+                assert_eq!(global_ordinals.facet(global_ord)?, facet);
+            }
+        }
+        assert_eq!(global_ordinals.global_ord(&Facet::from("/c"))?, Some(3));
+        assert_eq!(global_ordinals.global_ord(&Facet::from("/e"))?, None);
+
+        // The global ordinals are cached for the generation of the reader.
+        assert!(Arc::ptr_eq(
+            &global_ordinals,
+            &reader.searcher().global_ordinals(category)?
+        ));
+
+        let query = TermQuery::new(Term::from_field_text(kind, "x"), IndexRecordOption::Basic);
+        let counts = searcher.search(&query, &OrdinalCountCollector::new(global_ordinals))?;
+        assert_eq!(counts.top_k(2), vec![(2, 2), (1, 1)]);
+        assert_eq!(counts.get_facet(&Facet::from("/d"))?, 1);
+        assert_eq!(
+            counts.top_k_facets(5)?,
+            vec![
+                (Facet::from("/b"), 2),
+                (Facet::from("/a"), 1),
+                (Facet::from("/c"), 1),
+                (Facet::from("/d"), 1)
+            ]
+        );
+
+        // After a reload, the global ordinals of the new segments are built.
+        index_writer.add_document(doc!(category => Facet::from("/e"), kind => "x"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let new_searcher = reader.searcher();
+        let new_global_ordinals = new_searcher.global_ordinals(category)?;
+        assert_eq!(new_global_ordinals.num_terms(), 6);
+        // The global ordinals of a searcher cannot be used with another one.
+        let stale_collector = OrdinalCountCollector::new(searcher.global_ordinals(category)?);
+        assert!(matches!(
+            new_searcher.search(&AllQuery, &stale_collector),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            searcher.global_ordinals(kind),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+use std::collections::hash_map::RandomState;
+use std::collections::BinaryHeap;
+use std::hash::{BuildHasher, Hasher};
+
+/// Mixes the bits of a value (this is the finalizer of splitmix64).
+#[inline]
+fn splitmix64(val: u64) -> u64 {
+    let mut z = val.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Draws a seed from the randomly keyed hasher of the standard library.
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// The `RandomSampleCollector` returns a uniform random sample of the matching documents.
+///
+/// Each of the matching documents is drawn with the same probability, and the sample
+/// contains `sample_size` documents, or all of the matching documents if there are fewer.
+///
+/// The sampling is a reservoir sampling: every document gets a random key, and the
+/// sample is made of the documents with the smallest keys. The collector therefore
+/// only keeps `sample_size` documents in memory per segment, whatever the number of hits.
+///
+/// The keys are derived from a seed and from the address of the documents, so
+/// that searching the same searcher with the same seed returns the same sample.
+/// Unless a seed is given with [`with_seed`](#method.with_seed), a random seed is
+/// drawn when the collector is created.
+///
+/// The sample is returned sorted by `DocAddress`.
+///
+/// ```rust
+/// use tantivy::collector::RandomSampleCollector;
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for i in 0..1_000 {
+///     let parity = if i % 2 == 0 { "even" } else { "odd" };
+///     index_writer.add_document(doc!(title => parity))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("even")?;
+/// let collector = RandomSampleCollector::new(10).with_seed(42);
+/// let sample = searcher.search(&query, &collector)?;
+/// assert_eq!(sample.len(), 10);
+/// assert_eq!(sample, searcher.search(&query, &collector)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RandomSampleCollector {
+    sample_size: usize,
+    seed: u64,
+}
+
+impl RandomSampleCollector {
+    /// Creates a collector sampling `sample_size` documents, with a random seed.
+    pub fn new(sample_size: usize) -> RandomSampleCollector {
+        RandomSampleCollector {
+            sample_size,
+            seed: random_seed(),
+        }
+    }
+
+    /// Sets the seed of the sampling, making the sample reproducible.
+    pub fn with_seed(mut self, seed: u64) -> RandomSampleCollector {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the number of documents sampled.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+}
+
+/// Keeps the `sample_size` documents with the smallest keys of a segment.
+pub struct RandomSampleSegmentCollector {
+    segment_seed: u64,
+    segment_ord: SegmentOrdinal,
+    sample_size: usize,
+    // Max-heap of the (key, doc) of the sample, the top being the next one to be evicted.
+    sample: BinaryHeap<(u64, DocId)>,
+}
+
+impl SegmentCollector for RandomSampleSegmentCollector {
+    type Fruit = Vec<(u64, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        if self.sample_size == 0 {
+            return;
+        }
+        let key = splitmix64(self.segment_seed ^ u64::from(doc));
+        if self.sample.len() < self.sample_size {
+            self.sample.push((key, doc));
+        } else if let Some(mut largest) = self.sample.peek_mut() {
+            if (key, doc) < *largest {
+                *largest = (key, doc);
+            }
+        }
+    }
+
+    fn harvest(self) -> Vec<(u64, DocAddress)> {
+        let segment_ord = self.segment_ord;
+        self.sample
+            .into_iter()
+            .map(|(key, doc)| (key, DocAddress::new(segment_ord, doc)))
+            .collect()
+    }
+}
+
+impl Collector for RandomSampleCollector {
+    type Fruit = Vec<DocAddress>;
+    type Child = RandomSampleSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        _segment: &SegmentReader,
+    ) -> crate::Result<RandomSampleSegmentCollector> {
+        Ok(RandomSampleSegmentCollector {
+            segment_seed: splitmix64(self.seed ^ splitmix64(u64::from(segment_local_id))),
+            segment_ord: segment_local_id,
+            sample_size: self.sample_size,
+            sample: BinaryHeap::with_capacity(self.sample_size.min(1_024)),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_samples: Vec<Vec<(u64, DocAddress)>>,
+    ) -> crate::Result<Vec<DocAddress>> {
+        let mut sample: Vec<(u64, DocAddress)> = segment_samples.into_iter().flatten().collect();
+        sample.sort_unstable();
+        sample.truncate(self.sample_size);
+        let mut doc_addresses: Vec<DocAddress> = sample
+            .into_iter()
+            .map(|(_, doc_address)| doc_address)
+            .collect();
+        doc_addresses.sort_unstable();
+        Ok(doc_addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RandomSampleCollector;
+    use crate::collector::Count;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STORED, STRING};
+    use crate::{Index, Term};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_random_sample_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..3_000 {
+            let parity = if i % 2 == 0 { "even" } else { "odd" };
+            index_writer.add_document(doc!(text => parity))?;
+            if i % 1_000 == 999 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert!(searcher.segment_readers().len() > 1);
+
+        let even_query = TermQuery::new(
+            Term::from_field_text(text, "even"),
+            IndexRecordOption::Basic,
+        );
+        let collector = RandomSampleCollector::new(100).with_seed(7);
+        let sample = searcher.search(&even_query, &collector)?;
+        assert_eq!(sample.len(), 100);
+        assert!(sample.windows(2).all(|docs| docs[0] < docs[1]));
+        for &doc_address in &sample {
+            let doc = searcher.doc(doc_address)?;
+            assert_eq!(
+                doc.get_first(text).and_then(|value| value.text()),
+                Some("even")
+            );
+        }
+        // The sample is reproducible with the same seed, and differs with another seed.
+        assert_eq!(searcher.search(&even_query, &collector)?, sample);
+        let other_sample =
+            searcher.search(&even_query, &RandomSampleCollector::new(100).with_seed(8))?;
+        assert_ne!(other_sample, sample);
+
+        // All of the documents are returned if there are fewer hits than the sample size.
+        let num_docs = searcher.search(&AllQuery, &Count)?;
+        let all_docs = searcher.search(&AllQuery, &RandomSampleCollector::new(5_000))?;
+        assert_eq!(all_docs.len(), num_docs);
+        assert!(searcher
+            .search(&AllQuery, &RandomSampleCollector::new(0))?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_random_sample_collector_is_uniform() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..1_000 {
+            index_writer.add_document(doc!(text => "doc"))?;
+            if i % 250 == 249 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        let mut counts = vec![0usize; searcher.segment_readers().len()];
+        let mut distinct_docs = HashSet::new();
+        for seed in 0..200 {
+            let collector = RandomSampleCollector::new(40).with_seed(seed);
+            for doc_address in searcher.search(&AllQuery, &collector)? {
+                counts[doc_address.segment_ord as usize] += 1;
+                distinct_docs.insert(doc_address);
+            }
+        }
+        // Each of the 4 segments holds a quarter of the documents, hence of the samples.
+        for &count in &counts {
+            assert!((1_600..2_400).contains(&count), "{:?}", counts);
+        }
+        assert!(distinct_docs.len() > 950);
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader, FastValue};
+use crate::schema::{Cardinality, Field, FieldType};
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use common::ReadOnlyBitSet;
+
+/// Bucket of a [`RangeCollector`](./struct.RangeCollector.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeBucket<TFruit> {
+    /// Key given to the range in `RangeCollector::add_range`.
+    pub key: String,
+    /// Fruit of the sub-collector, over the documents of the bucket.
+    pub fruit: TFruit,
+}
+
+fn copy_bound<T: Copy>(bound: Bound<&T>) -> Bound<T> {
+    match bound {
+        Bound::Included(val) => Bound::Included(*val),
+        Bound::Excluded(val) => Bound::Excluded(*val),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// The `RangeCollector` splits the matching documents into buckets defined by
+/// explicit ranges of values of a single-valued fast field, and runs a sub-collector
+/// on each bucket.
+///
+/// Contrary to the `HistogramCollector`, the bounds of the buckets are arbitrary, and
+/// the buckets may overlap: a document is collected in all of the buckets
+/// containing its value. Documents without any value for the field are ignored.
+/// Date ranges are obtained with a date field and `DateTime` bounds.
+///
+/// The fruit has one `RangeBucket` per range, in the order in which the ranges were added.
+/// With the `Count` sub-collector, it gives the number of documents of each bucket.
+///
+/// ```rust
+/// use tantivy::collector::{Count, RangeCollector};
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, FAST};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let price = schema_builder.add_f64_field("price", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for &val in &[5.0f64, 12.5, 20.0, 99.9, 150.0] {
+///     index_writer.add_document(doc!(price => val))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let collector = RangeCollector::new(price, Count)
+///     .add_range("cheap", ..20.0)
+///     .add_range("medium", 20.0..100.0)
+///     .add_range("expensive", 100.0..);
+/// let buckets = searcher.search(&AllQuery, &collector)?;
+/// let counts: Vec<(&str, usize)> = buckets
+///     .iter()
+///     .map(|bucket| (bucket.key.as_str(), bucket.fruit))
+///     .collect();
+/// assert_eq!(counts, vec![("cheap", 2), ("medium", 2), ("expensive", 1)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RangeCollector<TFastValue: FastValue, TCollector> {
+    field: Field,
+    ranges: Vec<(String, Bound<TFastValue>, Bound<TFastValue>)>,
+    collector: TCollector,
+    t_fast_value: PhantomData<TFastValue>,
+}
+
+impl<TFastValue, TCollector> RangeCollector<TFastValue, TCollector>
+where
+    TFastValue: FastValue,
+    TCollector: Collector,
+{
+    /// Creates a `RangeCollector` over `field`, running `collector` on each bucket.
+    ///
+    /// Ranges are then added with `add_range`.
+    pub fn new(field: Field, collector: TCollector) -> RangeCollector<TFastValue, TCollector> {
+        RangeCollector {
+            field,
+            ranges: Vec::new(),
+            collector,
+            t_fast_value: PhantomData,
+        }
+    }
+
+    /// Adds a bucket with the given key, containing the documents
+    /// whose value is within `range`.
+    pub fn add_range<TKey: ToString, TRange: RangeBounds<TFastValue>>(
+        mut self,
+        key: TKey,
+        range: TRange,
+    ) -> RangeCollector<TFastValue, TCollector> {
+        self.ranges.push((
+            key.to_string(),
+            copy_bound(range.start_bound()),
+            copy_bound(range.end_bound()),
+        ));
+        self
+    }
+}
+
+impl<TFastValue, TCollector> Collector for RangeCollector<TFastValue, TCollector>
+where
+    TFastValue: FastValue,
+    TCollector: Collector,
+{
+    type Fruit = Vec<RangeBucket<TCollector::Fruit>>;
+
+    type Child = RangeSegmentCollector<TFastValue, TCollector::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let field_entry = segment_reader.schema().get_field_entry(self.field);
+        let requested_type = TFastValue::to_type();
+        let field_schema_type = field_entry.field_type().value_type();
+        if requested_type != field_schema_type {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is of type {:?}!={:?}",
+                field_entry.name(),
+                requested_type,
+                field_schema_type
+            )));
+        }
+        let cardinality_opt = match field_entry.field_type() {
+            FieldType::U64(options)
+            | FieldType::I64(options)
+            | FieldType::F64(options)
+            | FieldType::Date(options) => options.get_fastfield_cardinality(),
+            _ => None,
+        };
+        if cardinality_opt != Some(Cardinality::SingleValue) {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a single-valued fast field.",
+                field_entry.name()
+            )));
+        }
+        let fast_fields = segment_reader.fast_fields();
+        let buckets = self
+            .ranges
+            .iter()
+            .map(|(_, lower_bound, upper_bound)| {
+                let segment_collector = self
+                    .collector
+                    .for_segment(segment_local_id, segment_reader)?;
+                Ok((*lower_bound, *upper_bound, segment_collector))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(RangeSegmentCollector {
+            fast_field_reader: fast_fields.typed_fast_field_reader(self.field)?,
+            presence_bitset: fast_fields.presence_bitset(self.field)?,
+            buckets,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<<TCollector::Child as SegmentCollector>::Fruit>>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut bucket_fruits: Vec<Vec<_>> = self.ranges.iter().map(|_| Vec::new()).collect();
+        for segment_fruit in segment_fruits {
+            for (bucket_fruit, fruit) in bucket_fruits.iter_mut().zip(segment_fruit) {
+                bucket_fruit.push(fruit);
+            }
+        }
+        self.ranges
+            .iter()
+            .zip(bucket_fruits)
+            .map(|((key, _, _), fruits)| {
+                Ok(RangeBucket {
+                    key: key.clone(),
+                    fruit: self.collector.merge_fruits(fruits)?,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct RangeSegmentCollector<TFastValue: FastValue, TSegmentCollector> {
+    fast_field_reader: DynamicFastFieldReader<TFastValue>,
+    presence_bitset: Option<ReadOnlyBitSet>,
+    buckets: Vec<(Bound<TFastValue>, Bound<TFastValue>, TSegmentCollector)>,
+}
+
+impl<TFastValue, TSegmentCollector> SegmentCollector
+    for RangeSegmentCollector<TFastValue, TSegmentCollector>
+where
+    TFastValue: FastValue,
+    TSegmentCollector: SegmentCollector,
+{
+    type Fruit = Vec<TSegmentCollector::Fruit>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let has_value = self
+            .presence_bitset
+            .as_ref()
+            .map(|presence_bitset| presence_bitset.contains(doc))
+            .unwrap_or(true);
+        if !has_value {
+            return;
+        }
+        let value = self.fast_field_reader.get(doc);
+        for (lower_bound, upper_bound, segment_collector) in &mut self.buckets {
+            if (*lower_bound, *upper_bound).contains(&value) {
+                segment_collector.collect(doc, score);
+            }
+        }
+    }
+
+    fn harvest(self) -> Vec<TSegmentCollector::Fruit> {
+        self.buckets
+            .into_iter()
+            .map(|(_, _, segment_collector)| segment_collector.harvest())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeCollector;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{AllQuery, QueryParser};
+    use crate::schema::{Schema, FAST, STRING, TEXT};
+    use crate::{DateTime, Document, Index, TantivyError};
+
+    #[test]
+    fn test_range_collector_i64() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let val_field = schema_builder.add_i64_field("val", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for val in -10i64..10 {
+            index_writer.add_document(doc!(val_field => val))?;
+            if val == 0 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.add_document(Document::default())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let collector = RangeCollector::new(val_field, Count)
+            .add_range("negative", ..0i64)
+            .add_range("small", -2..=2)
+            .add_range("positive", 0..)
+            .add_range("all", ..)
+            .add_range("empty", 100..200);
+        let buckets = searcher.search(&AllQuery, &collector)?;
+        let counts: Vec<(&str, usize)> = buckets
+            .iter()
+            .map(|bucket| (bucket.key.as_str(), bucket.fruit))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("negative", 10),
+                ("small", 5),
+                ("positive", 10),
+                ("all", 20),
+                ("empty", 0)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_collector_no_range() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let val_field = schema_builder.add_u64_field("val", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(val_field => 1u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let collector: RangeCollector<u64, Count> = RangeCollector::new(val_field, Count);
+        assert!(searcher.search(&AllQuery, &collector)?.is_empty());
+        Ok(())
+    }
+
+    fn date(date_str: &str) -> DateTime {
+        date_str.parse::<DateTime>().unwrap()
+    }
+
+    #[test]
+    fn test_date_range_collector_with_sub_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let date_field = schema_builder.add_date_field("date", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for year in 2000..2010 {
+            let text = if year % 2 == 0 {
+                "even year"
+            } else {
+                "odd year"
+            };
+            index_writer.add_document(doc!(
+                text_field => text,
+                date_field => date(&format!("{}-06-01T00:00:00Z", year))
+            ))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let year_start = |year: i32| date(&format!("{}-01-01T00:00:00Z", year));
+        let collector = RangeCollector::new(date_field, (Count, TopDocs::with_limit(1)))
+            .add_range("2000s", year_start(2000)..year_start(2005))
+            .add_range("late 2000s", year_start(2005)..year_start(2010));
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("even")?;
+        let buckets = searcher.search(&query, &collector)?;
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].key, "2000s");
+        assert_eq!(buckets[0].fruit.0, 3);
+        assert_eq!(buckets[0].fruit.1.len(), 1);
+        assert_eq!(buckets[1].key, "late 2000s");
+        assert_eq!(buckets[1].fruit.0, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_collector_wrong_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let val_field = schema_builder.add_u64_field("val", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "a", val_field => 1u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let i64_collector = RangeCollector::new(val_field, Count).add_range("all", 0i64..);
+        assert!(matches!(
+            searcher.search(&AllQuery, &i64_collector),
+            Err(TantivyError::SchemaError(_))
+        ));
+        let u64_collector = RangeCollector::new(text_field, Count).add_range("all", 0u64..);
+        assert!(matches!(
+            searcher.search(&AllQuery, &u64_collector),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{
+    open_f64_values, Expression, OptionalFastFieldReader, SegmentExpression, ToF64,
+};
+use crate::schema::Field;
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+/// Statistics of the values of a fast field, computed by the [`StatsCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// Number of values, including the substituted missing values.
+    pub count: u64,
+    /// Number of documents without any value for the field.
+    pub missing: u64,
+    /// Sum of the values.
+    pub sum: f64,
+    /// Smallest value, or `None` if there are no values.
+    pub min: Option<f64>,
+    /// Largest value, or `None` if there are no values.
+    pub max: Option<f64>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            count: 0,
+            missing: 0,
+            sum: 0.0,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl Stats {
+    /// Returns the mean of the values, or `None` if there are no values.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+
+    fn add_value(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    fn merge(&mut self, other: &Stats) {
+        self.count += other.count;
+        self.missing += other.missing;
+        self.sum += other.sum;
+        if let Some(other_min) = other.min {
+            self.min = Some(self.min.map_or(other_min, |min| min.min(other_min)));
+        }
+        if let Some(other_max) = other.max {
+            self.max = Some(self.max.map_or(other_max, |max| max.max(other_max)));
+        }
+    }
+}
+
+/// The `StatsCollector` computes the count, sum, min, max and mean
+/// of the values of a fast field among the matching documents.
+///
+/// The field must be a single-valued `u64`, `i64`, `f64` or date fast field.
+/// Dates are accounted as their timestamp in seconds.
+///
+/// The documents without any value for the field are counted as `missing`, and are
+/// not part of the statistics, so that a sparse field does not pollute them with zeros.
+/// A value can instead be substituted to the missing values with
+/// [`with_missing`](#method.with_missing).
+///
+/// The statistics can also be computed over the values of an
+/// [`Expression`](../fastfield/struct.Expression.html) of the fast fields, with
+/// [`for_expression`](#method.for_expression).
+///
+/// ```rust
+/// use tantivy::collector::StatsCollector;
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, FAST};
+/// use tantivy::{doc, Document, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let price = schema_builder.add_f64_field("price", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(price => 10.0))?;
+/// index_writer.add_document(doc!(price => 20.0))?;
+/// index_writer.add_document(Document::default())?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let stats = searcher.search(&AllQuery, &StatsCollector::new(price))?;
+/// assert_eq!(stats.count, 2);
+/// assert_eq!(stats.missing, 1);
+/// assert_eq!(stats.mean(), Some(15.0));
+///
+/// let stats = searcher.search(&AllQuery, &StatsCollector::new(price).with_missing(0.0))?;
+/// assert_eq!(stats.count, 3);
+/// assert_eq!(stats.missing, 1);
+/// assert_eq!(stats.mean(), Some(10.0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct StatsCollector {
+    source: StatsSource,
+    missing: Option<f64>,
+}
+
+#[derive(Clone)]
+enum StatsSource {
+    Field(Field),
+    Expression(Expression),
+}
+
+impl StatsCollector {
+    /// Creates a collector computing the statistics of the values of `field`.
+    pub fn new(field: Field) -> StatsCollector {
+        StatsCollector {
+            source: StatsSource::Field(field),
+            missing: None,
+        }
+    }
+
+    /// Creates a collector computing the statistics of the values of `expression`.
+    ///
+    /// The expression has a value for every document, so none of them are missing.
+    /// Returns an `InvalidArgument` error on search if the expression depends on the
+    /// score, as the documents are not scored.
+    pub fn for_expression(expression: Expression) -> StatsCollector {
+        StatsCollector {
+            source: StatsSource::Expression(expression),
+            missing: None,
+        }
+    }
+
+    /// Accounts the documents without any value for the field as if their value
+    /// was `missing`. They are still counted in `Stats::missing`.
+    pub fn with_missing(mut self, missing: f64) -> StatsCollector {
+        self.missing = Some(missing);
+        self
+    }
+}
+
+pub struct StatsSegmentCollector {
+    values: SegmentValues,
+    missing: Option<f64>,
+    stats: Stats,
+}
+
+enum SegmentValues {
+    Field(OptionalFastFieldReader<u64>, ToF64),
+    Expression(SegmentExpression),
+}
+
+impl SegmentCollector for StatsSegmentCollector {
+    type Fruit = Stats;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let value_opt = match &self.values {
+            SegmentValues::Field(values, to_f64) => values.get(doc).map(to_f64),
+            SegmentValues::Expression(expression) => Some(expression.eval(doc, 0.0)),
+        };
+        let value = match value_opt {
+            Some(value) => value,
+            None => {
+                self.stats.missing += 1;
+                match self.missing {
+                    Some(missing) => missing,
+                    None => return,
+                }
+            }
+        };
+        self.stats.add_value(value);
+    }
+
+    fn harvest(self) -> Stats {
+        self.stats
+    }
+}
+
+impl Collector for StatsCollector {
+    type Fruit = Stats;
+    type Child = StatsSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<StatsSegmentCollector> {
+        let values = match &self.source {
+            StatsSource::Field(field) => {
+                let (values, to_f64) = open_f64_values(segment, *field)?;
+                SegmentValues::Field(values, to_f64)
+            }
+            StatsSource::Expression(expression) => {
+                if expression.requires_scoring() {
+                    return Err(TantivyError::InvalidArgument(
+                        "An expression depending on the score cannot be used without scoring."
+                            .to_string(),
+                    ));
+                }
+                SegmentValues::Expression(expression.for_segment(segment)?)
+            }
+        };
+        Ok(StatsSegmentCollector {
+            values,
+            missing: self.missing,
+            stats: Stats::default(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_stats: Vec<Stats>) -> crate::Result<Stats> {
+        let mut stats = Stats::default();
+        for segment_stats in &segment_stats {
+            stats.merge(segment_stats);
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatsCollector;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{Cardinality, IndexRecordOption, IntOptions, Schema, FAST, STRING};
+    use crate::{Document, Index, TantivyError, Term};
+
+    #[test]
+    fn test_stats_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let temperature = schema_builder.add_i64_field("temperature", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a", temperature => -10i64))?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "b", temperature => 20i64))?;
+        index_writer.add_document(doc!(text => "a", temperature => 30i64))?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let stats = searcher.search(&AllQuery, &StatsCollector::new(temperature))?;
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.missing, 2);
+        assert_eq!(stats.sum, 40.0);
+        assert_eq!(stats.min, Some(-10.0));
+        assert_eq!(stats.max, Some(30.0));
+
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+        let collector = StatsCollector::new(temperature).with_missing(100.0);
+        let stats = searcher.search(&query, &collector)?;
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.missing, 2);
+        assert_eq!(stats.mean(), Some(55.0));
+        assert_eq!(stats.max, Some(100.0));
+
+        let query = TermQuery::new(Term::from_field_text(text, "c"), IndexRecordOption::Basic);
+        let stats = searcher.search(&query, &StatsCollector::new(temperature))?;
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.min, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_collector_invalid_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let tags = schema_builder.add_u64_field(
+            "tags",
+            IntOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(Document::default())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        for &field in &[text, tags] {
+            assert!(matches!(
+                searcher.search(&AllQuery, &StatsCollector::new(field)),
+                Err(TantivyError::SchemaError(_))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use crate::collector::{Collector, SegmentCollector};
+use crate::core::InvertedIndexReader;
+use crate::schema::{Field, FieldType, IndexRecordOption};
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use common::BitSet;
+use std::cmp::Reverse;
+use std::collections::{btree_map, BTreeMap};
+use std::sync::Arc;
+
+/// The `TermsCollector` counts the matching documents containing each of the terms
+/// of an indexed text field, without requiring the field to be a fast field.
+///
+/// The matching documents of each segment are gathered into a bitset. Once all of
+/// them are collected, the whole term dictionary of the field is walked, and the
+/// postings of each term are checked against the bitset.
+///
+/// This is much slower than a [`FacetCollector`](./struct.FacetCollector.html) or a
+/// fast field based collector, as the postings of all of the terms of the field are read,
+/// whatever the number of matching documents. It is meant to get a quick count on a field
+/// with a small number of distinct terms (e.g. tags or statuses) without reindexing it.
+///
+/// Terms without any matching document are not part of the counts.
+///
+/// ```rust
+/// use tantivy::collector::TermsCollector;
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, STRING, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let status = schema_builder.add_text_field("status", STRING);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(title => "broken link", status => "open"))?;
+/// index_writer.add_document(doc!(title => "broken build", status => "closed"))?;
+/// index_writer.add_document(doc!(title => "broken test", status => "open"))?;
+/// index_writer.add_document(doc!(title => "slow build", status => "open"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("broken")?;
+/// let term_counts = searcher.search(&query, &TermsCollector::new(status))?;
+/// assert_eq!(term_counts.get("open"), 2);
+/// assert_eq!(term_counts.top_k(1), vec![("open", 2)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TermsCollector {
+    field: Field,
+}
+
+impl TermsCollector {
+    /// Creates a collector counting the matching documents for each term of `field`.
+    pub fn new(field: Field) -> TermsCollector {
+        TermsCollector { field }
+    }
+}
+
+pub struct TermsSegmentCollector {
+    inverted_index: Arc<InvertedIndexReader>,
+    docs: BitSet,
+}
+
+impl TermsSegmentCollector {
+    fn count_terms(&self) -> crate::Result<BTreeMap<String, u64>> {
+        let mut term_counts = BTreeMap::new();
+        if self.docs.len() == 0 {
+            return Ok(term_counts);
+        }
+        let mut term_stream = self.inverted_index.terms().stream()?;
+        while term_stream.try_advance()? {
+            let mut block_postings = self
+                .inverted_index
+                .read_block_postings_from_terminfo(term_stream.value(), IndexRecordOption::Basic)?;
+            let mut count = 0u64;
+            loop {
+                let docs = block_postings.docs();
+                if docs.is_empty() {
+                    break;
+                }
+                count += docs.iter().filter(|&&doc| self.docs.contains(doc)).count() as u64;
+                block_postings.advance();
+            }
+            if count > 0 {
+                let term = String::from_utf8_lossy(term_stream.key()).into_owned();
+                term_counts.insert(term, count);
+            }
+        }
+        Ok(term_counts)
+    }
+}
+
+impl SegmentCollector for TermsSegmentCollector {
+    type Fruit = crate::Result<BTreeMap<String, u64>>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        self.docs.insert(doc);
+    }
+
+    fn harvest(self) -> crate::Result<BTreeMap<String, u64>> {
+        self.count_terms()
+    }
+}
+
+impl Collector for TermsCollector {
+    type Fruit = TermCounts;
+    type Child = TermsSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<TermsSegmentCollector> {
+        let field_entry = segment.schema().get_field_entry(self.field);
+        if !matches!(field_entry.field_type(), FieldType::Str(_)) || !field_entry.is_indexed() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not an indexed text field.",
+                field_entry.name()
+            )));
+        }
+        Ok(TermsSegmentCollector {
+            inverted_index: segment.inverted_index(self.field)?,
+            docs: BitSet::with_max_value(segment.max_doc()),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_term_counts: Vec<crate::Result<BTreeMap<String, u64>>>,
+    ) -> crate::Result<TermCounts> {
+        let mut term_counts = BTreeMap::new();
+        for segment_term_counts in segment_term_counts {
+            for (term, count) in segment_term_counts? {
+                *term_counts.entry(term).or_default() += count;
+            }
+        }
+        Ok(TermCounts { term_counts })
+    }
+}
+
+/// Number of matching documents per term, as returned by the
+/// [`TermsCollector`](./struct.TermsCollector.html).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TermCounts {
+    term_counts: BTreeMap<String, u64>,
+}
+
+impl TermCounts {
+    /// Returns the number of matching documents containing `term`.
+    pub fn get(&self, term: &str) -> u64 {
+        self.term_counts.get(term).cloned().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the terms and their counts, in the order of the terms.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, u64> {
+        self.term_counts.iter()
+    }
+
+    /// Returns the number of terms with at least one matching document.
+    pub fn len(&self) -> usize {
+        self.term_counts.len()
+    }
+
+    /// Returns true if no term has a matching document.
+    pub fn is_empty(&self) -> bool {
+        self.term_counts.is_empty()
+    }
+
+    /// Returns the top `k` terms with their counts, sorted highest-to-lowest by counts.
+    /// Terms with the same count are sorted in increasing order.
+    pub fn top_k(&self, k: usize) -> Vec<(&str, u64)> {
+        let mut term_counts: Vec<(&str, u64)> = self
+            .term_counts
+            .iter()
+            .map(|(term, &count)| (term.as_str(), count))
+            .collect();
+        // the sort is stable, and the terms are already sorted.
+        term_counts.sort_by_key(|&(_, count)| Reverse(count));
+        term_counts.truncate(k);
+        term_counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermsCollector;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, INDEXED, STORED, STRING, TEXT};
+    use crate::{Index, TantivyError, Term};
+
+    #[test]
+    fn test_terms_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tags = schema_builder.add_text_field("tags", TEXT);
+        let kind = schema_builder.add_text_field("kind", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(tags => "rust search", kind => "a"))?;
+        index_writer.add_document(doc!(tags => "rust", tags => "index", kind => "b"))?;
+        index_writer.add_document(doc!(tags => "search search", kind => "a"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(tags => "index", kind => "a"))?;
+        index_writer.add_document(doc!(tags => "database", kind => "b"))?;
+        index_writer.delete_term(Term::from_field_text(kind, "b"));
+        index_writer.add_document(doc!(tags => "rust", kind => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // Deleted documents are not counted, and documents are counted once per term.
+        let term_counts = searcher.search(&AllQuery, &TermsCollector::new(tags))?;
+        let counts: Vec<(&str, u64)> = term_counts
+            .iter()
+            .map(|(term, &count)| (term.as_str(), count))
+            .collect();
+        assert_eq!(counts, vec![("index", 1), ("rust", 2), ("search", 2)]);
+        assert_eq!(term_counts.top_k(2), vec![("rust", 2), ("search", 2)]);
+        assert_eq!(term_counts.get("database"), 0);
+
+        let rust_query = TermQuery::new(
+            Term::from_field_text(tags, "rust"),
+            IndexRecordOption::Basic,
+        );
+        let term_counts = searcher.search(&rust_query, &TermsCollector::new(tags))?;
+        assert_eq!(term_counts.len(), 2);
+        assert_eq!(term_counts.get("rust"), 2);
+        assert_eq!(term_counts.get("search"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_terms_collector_invalid_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_u64_field("num", INDEXED);
+        let stored = schema_builder.add_text_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(num => 1u64, stored => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        for &field in &[num, stored] {
+            assert!(matches!(
+                searcher.search(&AllQuery, &TermsCollector::new(field)),
+                Err(TantivyError::SchemaError(_))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -149,7 +149,7 @@ pub(crate) struct TopSegmentCollector<T> {
 }
 
 impl<T: PartialOrd> TopSegmentCollector<T> {
-    fn new(segment_ord: SegmentOrdinal, limit: usize) -> TopSegmentCollector<T> {
+    pub(crate) fn new(segment_ord: SegmentOrdinal, limit: usize) -> TopSegmentCollector<T> {
         TopSegmentCollector {
             limit,
             heap: BinaryHeap::with_capacity(limit),
@@ -0,0 +1,335 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use super::top_collector::TopSegmentCollector;
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader};
+use crate::schema::{Cardinality, Field, FieldType};
+use crate::store::StoreReader;
+use crate::{DocAddress, DocId, Document, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use common::ReadOnlyBitSet;
+
+/// One of the best documents of a bucket of the
+/// [`TopHitsCollector`](./struct.TopHitsCollector.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopHit {
+    /// Score of the document.
+    pub score: Score,
+    /// Address of the document.
+    pub doc_address: DocAddress,
+    /// Values of the stored fields selected with `TopHitsCollector::with_stored_fields`.
+    /// Empty if no stored field was selected.
+    pub doc: Document,
+}
+
+/// The `TopHitsCollector` groups the matching documents into buckets,
+/// according to the value of a single-valued fast field, and returns the `limit`
+/// best scoring documents of each bucket. For instance, with a `brand_id` fast field,
+/// it returns the best products of each brand in a single search.
+///
+/// The fruit maps the value of each non-empty bucket to its hits, sorted by
+/// decreasing score. Values are given as `u64`: the values of an `i64`, `f64`
+/// or date field can be decoded with `FastValue::from_u64`.
+/// Documents without any value for the field do not belong to any bucket.
+///
+/// Optionally, some stored fields can be loaded for each hit (see `with_stored_fields`).
+/// They are loaded for the best documents of each bucket within each segment,
+/// so the number of loaded documents is bounded by
+/// `limit * number of buckets * number of segments`.
+///
+/// ```rust
+/// use tantivy::collector::TopHitsCollector;
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, FAST, STORED, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let name = schema_builder.add_text_field("name", TEXT | STORED);
+/// let brand_id = schema_builder.add_u64_field("brand_id", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(name => "running shoes", brand_id => 1u64))?;
+/// index_writer.add_document(doc!(name => "shoes for running shoes", brand_id => 1u64))?;
+/// index_writer.add_document(doc!(name => "tennis shoes", brand_id => 2u64))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![name]).parse_query("shoes")?;
+/// let collector = TopHitsCollector::new(brand_id, 1).with_stored_fields(vec![name]);
+/// let top_hits = searcher.search(&query, &collector)?;
+/// assert_eq!(top_hits.len(), 2);
+/// let best_hit = &top_hits[&1][0];
+/// assert_eq!(
+///     best_hit.doc.get_first(name).and_then(|value| value.text()),
+///     Some("shoes for running shoes")
+/// );
+/// assert_eq!(top_hits[&2].len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TopHitsCollector {
+    field: Field,
+    limit: usize,
+    stored_fields: Vec<Field>,
+}
+
+impl TopHitsCollector {
+    /// Creates a collector returning the `limit` best documents
+    /// of each bucket of the single-valued fast field `field`.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn new(field: Field, limit: usize) -> TopHitsCollector {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        TopHitsCollector {
+            field,
+            limit,
+            stored_fields: Vec::new(),
+        }
+    }
+
+    /// Loads the values of the given stored fields for each hit.
+    pub fn with_stored_fields(mut self, stored_fields: Vec<Field>) -> TopHitsCollector {
+        self.stored_fields = stored_fields;
+        self
+    }
+}
+
+fn cmp_hits(left: &TopHit, right: &TopHit) -> Ordering {
+    right
+        .score
+        .partial_cmp(&left.score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| left.doc_address.cmp(&right.doc_address))
+}
+
+impl Collector for TopHitsCollector {
+    type Fruit = BTreeMap<u64, Vec<TopHit>>;
+
+    type Child = TopHitsSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<TopHitsSegmentCollector> {
+        let field_entry = segment.schema().get_field_entry(self.field);
+        let cardinality_opt = match field_entry.field_type() {
+            FieldType::U64(options)
+            | FieldType::I64(options)
+            | FieldType::F64(options)
+            | FieldType::Date(options) => options.get_fastfield_cardinality(),
+            _ => None,
+        };
+        if cardinality_opt != Some(Cardinality::SingleValue) {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a single-valued fast field.",
+                field_entry.name()
+            )));
+        }
+        let fast_fields = segment.fast_fields();
+        let store_reader = if self.stored_fields.is_empty() {
+            None
+        } else {
+            Some(segment.get_store_reader()?)
+        };
+        Ok(TopHitsSegmentCollector {
+            segment_ord: segment_local_id,
+            limit: self.limit,
+            values: fast_fields.u64_lenient(self.field)?,
+            presence_bitset: fast_fields.presence_bitset(self.field)?,
+            buckets: HashMap::new(),
+            store_reader,
+            stored_fields: self.stored_fields.clone(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<crate::Result<BTreeMap<u64, Vec<TopHit>>>>,
+    ) -> crate::Result<BTreeMap<u64, Vec<TopHit>>> {
+        let mut buckets: BTreeMap<u64, Vec<TopHit>> = BTreeMap::new();
+        for segment_fruit in segment_fruits {
+            for (bucket, hits) in segment_fruit? {
+                buckets.entry(bucket).or_default().extend(hits);
+            }
+        }
+        for hits in buckets.values_mut() {
+            hits.sort_by(cmp_hits);
+            hits.truncate(self.limit);
+        }
+        Ok(buckets)
+    }
+}
+
+pub struct TopHitsSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    limit: usize,
+    values: DynamicFastFieldReader<u64>,
+    presence_bitset: Option<ReadOnlyBitSet>,
+    buckets: HashMap<u64, TopSegmentCollector<Score>>,
+    store_reader: Option<StoreReader>,
+    stored_fields: Vec<Field>,
+}
+
+impl TopHitsSegmentCollector {
+    fn load_hits(&self, top_docs: Vec<(Score, DocAddress)>) -> crate::Result<Vec<TopHit>> {
+        top_docs
+            .into_iter()
+            .map(|(score, doc_address)| {
+                let doc = if let Some(store_reader) = self.store_reader.as_ref() {
+                    store_reader.get_fields(doc_address.doc_id, &self.stored_fields)?
+                } else {
+                    Document::default()
+                };
+                Ok(TopHit {
+                    score,
+                    doc_address,
+                    doc,
+                })
+            })
+            .collect()
+    }
+}
+
+impl SegmentCollector for TopHitsSegmentCollector {
+    type Fruit = crate::Result<BTreeMap<u64, Vec<TopHit>>>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let has_value = self
+            .presence_bitset
+            .as_ref()
+            .map(|presence_bitset| presence_bitset.contains(doc))
+            .unwrap_or(true);
+        if !has_value {
+            return;
+        }
+        let bucket = self.values.get(doc);
+        let (segment_ord, limit) = (self.segment_ord, self.limit);
+        self.buckets
+            .entry(bucket)
+            .or_insert_with(|| TopSegmentCollector::new(segment_ord, limit))
+            .collect(doc, score);
+    }
+
+    fn harvest(mut self) -> crate::Result<BTreeMap<u64, Vec<TopHit>>> {
+        let mut buckets = BTreeMap::new();
+        for (bucket, top_segment_collector) in std::mem::take(&mut self.buckets) {
+            let hits = self.load_hits(top_segment_collector.harvest())?;
+            buckets.insert(bucket, hits);
+        }
+        Ok(buckets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TopHitsCollector;
+    use crate::collector::TopDocs;
+    use crate::fastfield::{FastFieldReader, FastValue};
+    use crate::query::{AllQuery, QueryParser};
+    use crate::schema::{Schema, FAST, STORED, STRING, TEXT};
+    use crate::{DocAddress, Document, Index, TantivyError};
+
+    #[test]
+    fn test_top_hits_collector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let id = schema_builder.add_u64_field("id", STORED);
+        let brand = schema_builder.add_i64_field("brand", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..60u64 {
+            let mut doc = Document::default();
+            // the more "hello" tokens, the higher the score.
+            doc.add_text(text, "hello ".repeat(1 + (i % 7) as usize) + "world");
+            doc.add_u64(id, i);
+            if i % 10 != 9 {
+                doc.add_i64(brand, (i % 3) as i64 - 1);
+            }
+            index_writer.add_document(doc)?;
+            if i % 20 == 19 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("hello")?;
+
+        let top_hits = searcher.search(
+            &query,
+            &TopHitsCollector::new(brand, 2).with_stored_fields(vec![id]),
+        )?;
+        let brands: Vec<i64> = top_hits.keys().cloned().map(i64::from_u64).collect();
+        assert_eq!(brands, vec![-1, 0, 1]);
+        for (&brand_val, hits) in &top_hits {
+            assert_eq!(hits.len(), 2);
+            assert!(hits[0].score >= hits[1].score);
+            // compare with the top docs of the bucket, computed by a filtered search.
+            let brand_docs = searcher.search(&query, &TopDocs::with_limit(60))?;
+            let expected: Vec<DocAddress> = brand_docs
+                .into_iter()
+                .map(|(_, doc_address)| doc_address)
+                .filter(|doc_address| {
+                    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+                    let presence_bitset =
+                        segment_reader.fast_fields().presence_bitset(brand).unwrap();
+                    let has_value = presence_bitset
+                        .map(|bitset| bitset.contains(doc_address.doc_id))
+                        .unwrap_or(true);
+                    has_value
+                        && segment_reader
+                            .fast_fields()
+                            .u64_lenient(brand)
+                            .unwrap()
+                            .get(doc_address.doc_id)
+                            == brand_val
+                })
+                .take(2)
+                .collect();
+            let hit_addresses: Vec<DocAddress> = hits.iter().map(|hit| hit.doc_address).collect();
+            assert_eq!(hit_addresses, expected);
+            for hit in hits {
+                let stored_doc = searcher.doc(hit.doc_address)?;
+                assert_eq!(hit.doc.field_values().len(), 1);
+                assert_eq!(hit.doc.get_first(id), stored_doc.get_first(id));
+            }
+        }
+
+        let top_hits = searcher.search(&AllQuery, &TopHitsCollector::new(brand, 100))?;
+        let num_hits: usize = top_hits.values().map(Vec::len).sum();
+        assert_eq!(num_hits, 54);
+        assert!(top_hits
+            .values()
+            .flatten()
+            .all(|hit| hit.doc.field_values().is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_collector_requires_single_valued_fast_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let num = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a", num => 1u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert!(matches!(
+            searcher.search(&AllQuery, &TopHitsCollector::new(text, 1)),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert_eq!(
+            searcher.search(&AllQuery, &TopHitsCollector::new(num, 1))?[&1].len(),
+            1
+        );
+        Ok(())
+    }
+}
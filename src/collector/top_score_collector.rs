@@ -1,9 +1,11 @@
 use super::Collector;
+use crate::collector::custom_score_top_collector::{CustomScoreTopCollector, TryCustomScorer};
 use crate::collector::top_collector::{ComparableDoc, TopCollector};
-use crate::collector::tweak_score_top_collector::TweakedScoreTopCollector;
+use crate::collector::tweak_score_top_collector::{TryScoreTweaker, TweakedScoreTopCollector};
 use crate::collector::{
     CustomScorer, CustomSegmentScorer, ScoreSegmentTweaker, ScoreTweaker, SegmentCollector,
 };
+use crate::fastfield::FastValue;
 use crate::fastfield::{DynamicFastFieldReader, FastFieldReader};
 use crate::query::Weight;
 use crate::schema::Field;
@@ -12,7 +14,7 @@ use crate::DocId;
 use crate::Score;
 use crate::SegmentOrdinal;
 use crate::SegmentReader;
-use crate::{collector::custom_score_top_collector::CustomScoreTopCollector, fastfield::FastValue};
+use crate::TERMINATED;
 use crate::{collector::top_collector::TopSegmentCollector, TantivyError};
 use std::fmt;
 use std::{collections::BinaryHeap, marker::PhantomData};
@@ -26,20 +28,12 @@ struct FastFieldConvertCollector<
     pub fast_value: std::marker::PhantomData<TFastValue>,
 }
 
-impl<TCollector, TFastValue> Collector for FastFieldConvertCollector<TCollector, TFastValue>
+impl<TCollector, TFastValue> FastFieldConvertCollector<TCollector, TFastValue>
 where
     TCollector: Collector<Fruit = Vec<(u64, DocAddress)>>,
     TFastValue: FastValue,
 {
-    type Fruit = Vec<(TFastValue, DocAddress)>;
-
-    type Child = TCollector::Child;
-
-    fn for_segment(
-        &self,
-        segment_local_id: crate::SegmentOrdinal,
-        segment: &SegmentReader,
-    ) -> crate::Result<Self::Child> {
+    fn check_field_type(&self, segment: &SegmentReader) -> crate::Result<()> {
         let schema = segment.schema();
         let field_entry = schema.get_field_entry(self.field);
         if !field_entry.is_fast() {
@@ -58,6 +52,25 @@ where
                 requested_type
             )));
         }
+        Ok(())
+    }
+}
+
+impl<TCollector, TFastValue> Collector for FastFieldConvertCollector<TCollector, TFastValue>
+where
+    TCollector: Collector<Fruit = Vec<(u64, DocAddress)>>,
+    TFastValue: FastValue,
+{
+    type Fruit = Vec<(TFastValue, DocAddress)>;
+
+    type Child = TCollector::Child;
+
+    fn for_segment(
+        &self,
+        segment_local_id: crate::SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        self.check_field_type(segment)?;
         self.collector.for_segment(segment_local_id, segment)
     }
 
@@ -76,6 +89,16 @@ where
             .collect::<Vec<_>>();
         Ok(transformed_result)
     }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<<Self::Child as SegmentCollector>::Fruit> {
+        self.check_field_type(reader)?;
+        self.collector.collect_segment(weight, segment_ord, reader)
+    }
 }
 
 /// The `TopDocs` collector keeps track of the top `K` documents
@@ -119,45 +142,201 @@ where
 /// # Ok(())
 /// # }
 /// ```
-pub struct TopDocs(TopCollector<Score>);
+pub struct TopDocs {
+    collector: TopCollector<Score>,
+    search_after: Option<(u64, DocAddress)>,
+    score_normalization: Option<ScoreNormalization>,
+}
+
+/// Normalization applied by [`TopDocs`](./struct.TopDocs.html) to the scores of the
+/// documents it returns, e.g. to blend them with scores coming from another source.
+///
+/// The normalization is computed over the returned documents only, so that the
+/// normalized scores of two pages are not comparable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreNormalization {
+    /// Rescales the scores linearly to `[0, 1]`: the best document gets 1.0, and the
+    /// worst one gets 0.0. If all of the scores are equal, they are all set to 1.0.
+    MinMax,
+    /// Replaces the scores by their standard score, i.e. the number of standard
+    /// deviations above the mean score. If all of the scores are equal, they are all
+    /// set to 0.0.
+    ZScore,
+}
+
+impl ScoreNormalization {
+    fn normalize(self, top_docs: &mut [(Score, DocAddress)]) {
+        if top_docs.is_empty() {
+            return;
+        }
+        match self {
+            ScoreNormalization::MinMax => {
+                let (min, max) = top_docs.iter().fold(
+                    (Score::INFINITY, Score::NEG_INFINITY),
+                    |(min, max), &(score, _)| (min.min(score), max.max(score)),
+                );
+                for (score, _) in top_docs.iter_mut() {
+                    *score = if max > min {
+                        (*score - min) / (max - min)
+                    } else {
+                        1.0
+                    };
+                }
+            }
+            ScoreNormalization::ZScore => {
+                let len = top_docs.len() as Score;
+                let mean = top_docs.iter().map(|&(score, _)| score).sum::<Score>() / len;
+                let variance = top_docs
+                    .iter()
+                    .map(|&(score, _)| (score - mean) * (score - mean))
+                    .sum::<Score>()
+                    / len;
+                let std_dev = variance.sqrt();
+                for (score, _) in top_docs.iter_mut() {
+                    *score = if std_dev > 0.0 {
+                        (*score - mean) / std_dev
+                    } else {
+                        0.0
+                    };
+                }
+            }
+        }
+    }
+}
 
 impl fmt::Debug for TopDocs {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "TopDocs(limit={}, offset={})",
-            self.0.limit, self.0.offset
+            self.collector.limit, self.collector.offset
         )
     }
 }
 
-struct ScorerByFastFieldReader {
-    ff_reader: DynamicFastFieldReader<u64>,
+/// Top-K collector ranking the documents by a fast field.
+///
+/// If the index is sorted by this very field in decreasing order,
+/// the documents of a segment are visited by decreasing value, so that the collection
+/// of a segment can stop as soon as `K` documents have been collected.
+struct TopDocsByField {
+    field: Field,
+    search_after: Option<(u64, DocAddress)>,
+    collector: TopCollector<u64>,
 }
 
-impl CustomSegmentScorer<u64> for ScorerByFastFieldReader {
-    fn score(&mut self, doc: DocId) -> u64 {
-        self.ff_reader.get(doc)
-    }
+/// Returns true iff a document ranks after the `search_after` cursor, the documents
+/// being ranked by decreasing value, then by increasing `DocAddress`.
+fn is_after(value: u64, doc_address: DocAddress, search_after: (u64, DocAddress)) -> bool {
+    let (last_value, last_doc_address) = search_after;
+    value < last_value || (value == last_value && doc_address > last_doc_address)
 }
 
-struct ScorerByField {
-    field: Field,
+impl TopDocsByField {
+    fn is_sorted_by_field(&self, reader: &SegmentReader) -> bool {
+        reader
+            .sort_by_field()
+            .map(|sort_by_field| {
+                sort_by_field.order.is_desc()
+                    && reader.schema().get_field(&sort_by_field.field) == Some(self.field)
+            })
+            .unwrap_or(false)
+    }
 }
 
-impl CustomScorer<u64> for ScorerByField {
-    type Child = ScorerByFastFieldReader;
+impl Collector for TopDocsByField {
+    type Fruit = Vec<(u64, DocAddress)>;
 
-    fn segment_scorer(&self, segment_reader: &SegmentReader) -> crate::Result<Self::Child> {
+    type Child = TopDocsByFieldSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
         // We interpret this field as u64, regardless of its type, that way,
         // we avoid needless conversion. Regardless of the fast field type, the
         // mapping is monotonic, so it is sufficient to compute our top-K docs.
         //
         // The conversion will then happen only on the top-K docs.
-        let ff_reader = segment_reader
-            .fast_fields()
-            .typed_fast_field_reader(self.field)?;
-        Ok(ScorerByFastFieldReader { ff_reader })
+        let ff_reader = reader.fast_fields().typed_fast_field_reader(self.field)?;
+        Ok(TopDocsByFieldSegmentCollector {
+            ff_reader,
+            segment_ord: segment_local_id,
+            search_after: self.search_after,
+            collector: self.collector.for_segment(segment_local_id, reader),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Self::Fruit>) -> crate::Result<Self::Fruit> {
+        self.collector.merge_fruits(segment_fruits)
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<Self::Fruit> {
+        let mut segment_collector = self.for_segment(segment_ord, reader)?;
+        if !self.is_sorted_by_field(reader) {
+            let alive_bitset_opt = reader.alive_bitset();
+            weight.for_each(reader, &mut |doc, score| {
+                let is_alive = alive_bitset_opt
+                    .map(|alive_bitset| alive_bitset.is_alive(doc))
+                    .unwrap_or(true);
+                if is_alive {
+                    segment_collector.collect(doc, score);
+                }
+            })?;
+            return Ok(segment_collector.harvest());
+        }
+        let alive_bitset_opt = reader.alive_bitset();
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let mut doc = scorer.doc();
+        // Documents with the same value are ranked by increasing `DocId`,
+        // so the first `K` alive documents are exactly the top `K` documents.
+        // This also holds for the documents ranking after the `search_after` cursor.
+        while doc != TERMINATED && !segment_collector.collector.at_capacity() {
+            let is_alive = alive_bitset_opt
+                .map(|alive_bitset| alive_bitset.is_alive(doc))
+                .unwrap_or(true);
+            if is_alive {
+                segment_collector.collect(doc, 0.0);
+            }
+            doc = scorer.advance();
+        }
+        Ok(segment_collector.harvest())
+    }
+}
+
+/// Segment collector associated to `TopDocsByField`.
+struct TopDocsByFieldSegmentCollector {
+    ff_reader: DynamicFastFieldReader<u64>,
+    segment_ord: SegmentOrdinal,
+    search_after: Option<(u64, DocAddress)>,
+    collector: TopSegmentCollector<u64>,
+}
+
+impl SegmentCollector for TopDocsByFieldSegmentCollector {
+    type Fruit = Vec<(u64, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let value = self.ff_reader.get(doc);
+        if let Some(search_after) = self.search_after {
+            if !is_after(value, DocAddress::new(self.segment_ord, doc), search_after) {
+                return;
+            }
+        }
+        self.collector.collect(doc, value);
+    }
+
+    fn harvest(self) -> Vec<(u64, DocAddress)> {
+        self.collector.harvest()
     }
 }
 
@@ -167,7 +346,11 @@ impl TopDocs {
     /// # Panics
     /// The method panics if limit is 0
     pub fn with_limit(limit: usize) -> TopDocs {
-        TopDocs(TopCollector::with_limit(limit))
+        TopDocs {
+            collector: TopCollector::with_limit(limit),
+            search_after: None,
+            score_normalization: None,
+        }
     }
 
     /// Skip the first "offset" documents when collecting.
@@ -211,7 +394,139 @@ impl TopDocs {
     /// # }
     /// ```
     pub fn and_offset(self, offset: usize) -> TopDocs {
-        TopDocs(self.0.and_offset(offset))
+        TopDocs {
+            collector: self.collector.and_offset(offset),
+            search_after: self.search_after,
+            score_normalization: self.score_normalization,
+        }
+    }
+
+    /// Normalizes the scores of the returned documents.
+    ///
+    /// The documents are still ranked by their original scores. The normalization only
+    /// applies to the documents ranked by score: it is ignored when ranking them by a
+    /// fast field, or by a custom or tweaked score.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::{ScoreNormalization, TopDocs};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+    /// index_writer.add_document(doc!(title => "Diary of a diary"))?;
+    /// index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+    /// let top_docs = searcher.search(
+    ///     &query,
+    ///     &TopDocs::with_limit(3).with_score_normalization(ScoreNormalization::MinMax),
+    /// )?;
+    /// assert_eq!(top_docs[0].0, 1.0);
+    /// assert_eq!(top_docs[2].0, 0.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_score_normalization(self, score_normalization: ScoreNormalization) -> TopDocs {
+        TopDocs {
+            score_normalization: Some(score_normalization),
+            ..self
+        }
+    }
+
+    /// Only collects the documents ranking after a given document, to paginate
+    /// deeply through the documents ranked by a fast field.
+    ///
+    /// `last_sort_key` and `last_doc_address` are the fast field value and the address
+    /// of the last document of the previous page. The next page is then collected
+    /// without collecting and discarding all of the documents of the previous pages,
+    /// as [`and_offset`](#method.and_offset) does.
+    ///
+    /// The documents are ranked by decreasing fast field value, then by increasing
+    /// `DocAddress`. Doc addresses are only meaningful within a given `Searcher`, so all
+    /// of the pages should be collected using the same `Searcher`.
+    ///
+    /// The cursor only applies to the documents ranked by a fast field, with
+    /// [`order_by_u64_field`](#method.order_by_u64_field) or
+    /// [`order_by_fast_field`](#method.order_by_fast_field). Ranking the documents by
+    /// score returns an error at the moment of search.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::TopDocs;
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{Schema, FAST};
+    /// use tantivy::{doc, DocAddress, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let rating = schema_builder.add_i64_field("rating", FAST);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// for value in &[3i64, -1, 7, 3, 5] {
+    ///     index_writer.add_document(doc!(rating => *value))?;
+    /// }
+    /// index_writer.commit()?;
+    /// let searcher = index.reader()?.searcher();
+    ///
+    /// let first_page: Vec<(i64, DocAddress)> =
+    ///     searcher.search(&AllQuery, &TopDocs::with_limit(2).order_by_fast_field(rating))?;
+    /// assert_eq!(first_page, vec![(7, DocAddress::new(0, 2)), (5, DocAddress::new(0, 4))]);
+    ///
+    /// let (last_rating, last_doc_address) = first_page[1];
+    /// let second_page: Vec<(i64, DocAddress)> = searcher.search(
+    ///     &AllQuery,
+    ///     &TopDocs::with_limit(2)
+    ///         .search_after(last_rating, last_doc_address)
+    ///         .order_by_fast_field(rating),
+    /// )?;
+    /// assert_eq!(second_page, vec![(3, DocAddress::new(0, 0)), (3, DocAddress::new(0, 3))]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_after<TFastValue: FastValue>(
+        self,
+        last_sort_key: TFastValue,
+        last_doc_address: DocAddress,
+    ) -> TopDocs {
+        TopDocs {
+            collector: self.collector,
+            search_after: Some((last_sort_key.to_u64(), last_doc_address)),
+            score_normalization: self.score_normalization,
+        }
+    }
+
+    /// Returns the top collector used to rank the documents by a custom score.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a `search_after` cursor was set, as it only applies to the documents
+    /// ranked by a fast field.
+    fn into_custom_score_collector<TScore: PartialOrd + Clone>(self) -> TopCollector<TScore> {
+        assert!(
+            self.search_after.is_none(),
+            "search_after only applies to the documents ranked by a fast field."
+        );
+        self.collector.into_tscore()
+    }
+
+    fn check_no_search_after(&self) -> crate::Result<()> {
+        if self.search_after.is_some() {
+            return Err(TantivyError::InvalidArgument(
+                "search_after only applies to the documents ranked by a fast field.".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     /// Set top-K to rank documents by a given fast field.
@@ -286,11 +601,20 @@ impl TopDocs {
     ///
     /// To confortably work with `u64`s, `i64`s, `f64`s, or `date`s, please refer to
     /// [.order_by_fast_field(...)](#method.order_by_fast_field) method.
+    ///
+    /// # Sorted index
+    ///
+    /// If the index is sorted by `field` in decreasing order (see `IndexSettings`),
+    /// the collection of each segment terminates as soon as `K` documents have been collected.
     pub fn order_by_u64_field(
         self,
         field: Field,
     ) -> impl Collector<Fruit = Vec<(u64, DocAddress)>> {
-        CustomScoreTopCollector::new(ScorerByField { field }, self.0.into_tscore())
+        TopDocsByField {
+            field,
+            search_after: self.search_after,
+            collector: self.collector.into_tscore(),
+        }
     }
 
     /// Set top-K to rank documents by a given fast field.
@@ -482,7 +806,55 @@ impl TopDocs {
         TScoreSegmentTweaker: ScoreSegmentTweaker<TScore> + 'static,
         TScoreTweaker: ScoreTweaker<TScore, Child = TScoreSegmentTweaker> + Send + Sync,
     {
-        TweakedScoreTopCollector::new(score_tweaker, self.0.into_tscore())
+        TweakedScoreTopCollector::new(score_tweaker, self.into_custom_score_collector())
+    }
+
+    /// Same as [tweak_score(...)](#method.tweak_score), but the closure building the
+    /// scoring function of a segment may fail, e.g. when opening a fast field reader.
+    ///
+    /// The error is then returned by the search.
+    ///
+    /// ```rust
+    /// # use tantivy::schema::{Schema, FAST, TEXT};
+    /// # use tantivy::{doc, Index, DocAddress, DocId, Score, SegmentReader};
+    /// # use tantivy::query::QueryParser;
+    /// use tantivy::collector::TopDocs;
+    /// use tantivy::fastfield::FastFieldReader;
+    /// # fn main() -> tantivy::Result<()> {
+    /// # let mut schema_builder = Schema::builder();
+    /// # let title = schema_builder.add_text_field("title", TEXT);
+    /// # let popularity = schema_builder.add_u64_field("popularity", FAST);
+    /// # let index = Index::create_in_ram(schema_builder.build());
+    /// # let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// # index_writer.add_document(doc!(title => "The Diary of Muadib", popularity => 1u64))?;
+    /// # index_writer.add_document(doc!(title => "The Diary of a Young Girl", popularity => 15u64))?;
+    /// # index_writer.commit()?;
+    /// # let query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+    /// let top_docs = TopDocs::with_limit(10).try_tweak_score(
+    ///     move |segment_reader: &SegmentReader| -> tantivy::Result<_> {
+    ///         let popularity_reader = segment_reader.fast_fields().u64(popularity)?;
+    ///         Ok(move |doc: DocId, original_score: Score| {
+    ///             let popularity = popularity_reader.get(doc);
+    ///             ((2u64 + popularity) as Score).log2() * original_score
+    ///         })
+    ///     },
+    /// );
+    /// let searcher = index.reader()?.searcher();
+    /// let resulting_docs: Vec<(Score, DocAddress)> = searcher.search(&query, &top_docs)?;
+    /// assert_eq!(resulting_docs[0].1, DocAddress::new(0, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_tweak_score<TScore, TScoreSegmentTweaker, F>(
+        self,
+        score_tweaker: F,
+    ) -> impl Collector<Fruit = Vec<(TScore, DocAddress)>>
+    where
+        TScore: 'static + Send + Sync + Clone + PartialOrd,
+        TScoreSegmentTweaker: ScoreSegmentTweaker<TScore> + 'static,
+        F: 'static + Send + Sync + Fn(&SegmentReader) -> crate::Result<TScoreSegmentTweaker>,
+    {
+        self.tweak_score(TryScoreTweaker(score_tweaker))
     }
 
     /// Ranks the documents using a custom score.
@@ -597,7 +969,23 @@ impl TopDocs {
         TCustomSegmentScorer: CustomSegmentScorer<TScore> + 'static,
         TCustomScorer: CustomScorer<TScore, Child = TCustomSegmentScorer> + Send + Sync,
     {
-        CustomScoreTopCollector::new(custom_score, self.0.into_tscore())
+        CustomScoreTopCollector::new(custom_score, self.into_custom_score_collector())
+    }
+
+    /// Same as [custom_score(...)](#method.custom_score), but the closure building the
+    /// scoring function of a segment may fail, e.g. when opening a fast field reader.
+    ///
+    /// The error is then returned by the search.
+    pub fn try_custom_score<TScore, TCustomSegmentScorer, F>(
+        self,
+        custom_score: F,
+    ) -> impl Collector<Fruit = Vec<(TScore, DocAddress)>>
+    where
+        TScore: 'static + Send + Sync + Clone + PartialOrd,
+        TCustomSegmentScorer: CustomSegmentScorer<TScore> + 'static,
+        F: 'static + Send + Sync + Fn(&SegmentReader) -> crate::Result<TCustomSegmentScorer>,
+    {
+        self.custom_score(TryCustomScorer(custom_score))
     }
 }
 
@@ -611,7 +999,8 @@ impl Collector for TopDocs {
         segment_local_id: SegmentOrdinal,
         reader: &SegmentReader,
     ) -> crate::Result<Self::Child> {
-        let collector = self.0.for_segment(segment_local_id, reader);
+        self.check_no_search_after()?;
+        let collector = self.collector.for_segment(segment_local_id, reader);
         Ok(TopScoreSegmentCollector(collector))
     }
 
@@ -623,7 +1012,11 @@ impl Collector for TopDocs {
         &self,
         child_fruits: Vec<Vec<(Score, DocAddress)>>,
     ) -> crate::Result<Self::Fruit> {
-        self.0.merge_fruits(child_fruits)
+        let mut top_docs = self.collector.merge_fruits(child_fruits)?;
+        if let Some(score_normalization) = self.score_normalization {
+            score_normalization.normalize(&mut top_docs);
+        }
+        Ok(top_docs)
     }
 
     fn collect_segment(
@@ -632,7 +1025,8 @@ impl Collector for TopDocs {
         segment_ord: u32,
         reader: &SegmentReader,
     ) -> crate::Result<<Self::Child as SegmentCollector>::Fruit> {
-        let heap_len = self.0.limit + self.0.offset;
+        self.check_no_search_after()?;
+        let heap_len = self.collector.limit + self.collector.offset;
         let mut heap: BinaryHeap<ComparableDoc<Score, DocId>> = BinaryHeap::with_capacity(heap_len);
 
         if let Some(alive_bitset) = reader.alive_bitset() {
@@ -710,14 +1104,17 @@ impl SegmentCollector for TopScoreSegmentCollector {
 
 #[cfg(test)]
 mod tests {
-    use super::TopDocs;
-    use crate::collector::Collector;
+    use super::{ScoreNormalization, TopDocs};
+    use crate::collector::{Collector, Count};
+    use crate::fastfield::FastFieldReader;
+    use crate::query::TermQuery;
     use crate::query::{AllQuery, Query, QueryParser};
-    use crate::schema::{Field, Schema, FAST, STORED, TEXT};
+    use crate::schema::{Field, IndexRecordOption, Schema, FAST, INDEXED, STORED, STRING, TEXT};
     use crate::Index;
     use crate::IndexWriter;
     use crate::Score;
     use crate::{DocAddress, DocId, SegmentReader};
+    use crate::{IndexSettings, IndexSortByField, Order, TantivyError, Term};
 
     fn make_index() -> crate::Result<Index> {
         let mut schema_builder = Schema::builder();
@@ -761,6 +1158,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_top_collector_score_normalization() -> crate::Result<()> {
+        let index = make_index()?;
+        let field = index.schema().get_field("text").unwrap();
+        let query_parser = QueryParser::for_index(&index, vec![field]);
+        let text_query = query_parser.parse_query("droopy tax")?;
+        let searcher = index.reader()?.searcher();
+        let min_max_docs = searcher.search(
+            &text_query,
+            &TopDocs::with_limit(4).with_score_normalization(ScoreNormalization::MinMax),
+        )?;
+        assert_results_equals(
+            &min_max_docs,
+            &[
+                (1.0, DocAddress::new(0u32, 1)),
+                (0.16030502, DocAddress::new(0u32, 2)),
+                (0.0, DocAddress::new(0, 0)),
+            ],
+        );
+        let z_score_docs = searcher.search(
+            &text_query,
+            &TopDocs::with_limit(4).with_score_normalization(ScoreNormalization::ZScore),
+        )?;
+        assert_eq!(z_score_docs.len(), 3);
+        let mean: Score = z_score_docs.iter().map(|(score, _)| score).sum::<Score>() / 3.0;
+        assert!(mean.abs() < 1e-6);
+        assert!(z_score_docs[0].0 > 1.0);
+        assert_eq!(z_score_docs[0].1, DocAddress::new(0u32, 1));
+
+        // Normalizing a single document.
+        let single_doc = searcher.search(
+            &text_query,
+            &TopDocs::with_limit(1).with_score_normalization(ScoreNormalization::MinMax),
+        )?;
+        assert_eq!(single_doc, vec![(1.0, DocAddress::new(0u32, 1))]);
+        let single_doc = searcher.search(
+            &text_query,
+            &TopDocs::with_limit(1).with_score_normalization(ScoreNormalization::ZScore),
+        )?;
+        assert_eq!(single_doc, vec![(0.0, DocAddress::new(0u32, 1))]);
+        Ok(())
+    }
+
     #[test]
     fn test_top_collector_not_at_capacity_with_offset() {
         let index = make_index().unwrap();
@@ -1062,6 +1502,213 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_try_tweak_score_and_try_custom_score() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let not_fast = schema_builder.add_u64_field("not_fast", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a", popularity => 3u64, not_fast => 1u64))?;
+        index_writer.add_document(doc!(text => "a", popularity => 10u64, not_fast => 1u64))?;
+        index_writer.add_document(doc!(text => "a", popularity => 1u64, not_fast => 1u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+
+        let tweaked_docs: Vec<(Score, DocAddress)> = searcher.search(
+            &query,
+            &TopDocs::with_limit(2).try_tweak_score(move |segment_reader: &SegmentReader| {
+                let popularity_reader = segment_reader.fast_fields().u64(popularity)?;
+                Ok(move |doc: DocId, score: Score| popularity_reader.get(doc) as Score * score)
+            }),
+        )?;
+        assert_eq!(
+            tweaked_docs
+                .iter()
+                .map(|(_, doc_address)| *doc_address)
+                .collect::<Vec<_>>(),
+            vec![DocAddress::new(0, 1), DocAddress::new(0, 0)]
+        );
+        let custom_docs: Vec<(u64, DocAddress)> = searcher.search(
+            &query,
+            &TopDocs::with_limit(2).try_custom_score(move |segment_reader: &SegmentReader| {
+                let popularity_reader = segment_reader.fast_fields().u64(popularity)?;
+                Ok(move |doc: DocId| popularity_reader.get(doc))
+            }),
+        )?;
+        assert_eq!(
+            custom_docs,
+            vec![(10, DocAddress::new(0, 1)), (3, DocAddress::new(0, 0))]
+        );
+
+        // Errors are returned by the search.
+        let tweak_result = searcher.search(
+            &query,
+            &TopDocs::with_limit(2).try_tweak_score(move |segment_reader: &SegmentReader| {
+                let not_fast_reader = segment_reader.fast_fields().u64(not_fast)?;
+                Ok(move |doc: DocId, _score: Score| not_fast_reader.get(doc))
+            }),
+        );
+        assert!(matches!(tweak_result, Err(TantivyError::SchemaError(_))));
+        let custom_result = searcher.search(
+            &query,
+            &TopDocs::with_limit(2).try_custom_score(move |segment_reader: &SegmentReader| {
+                let not_fast_reader = segment_reader.fast_fields().u64(not_fast)?;
+                Ok(move |doc: DocId| not_fast_reader.get(doc))
+            }),
+        );
+        assert!(matches!(custom_result, Err(TantivyError::SchemaError(_))));
+        Ok(())
+    }
+
+    fn test_top_field_collector_sorted_index_aux(order: Order) -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let size = schema_builder.add_i64_field("size", FAST);
+        let schema = schema_builder.build();
+        let index = Index::builder()
+            .schema(schema)
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "size".to_string(),
+                    order,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..200i64 {
+            let value = if i % 3 == 0 { "fizz" } else { "buzz" };
+            index_writer.add_document(doc!(text => value, size => (i * 7) % 50 - 20))?;
+            if i % 70 == 69 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "buzz"));
+        index_writer.add_document(doc!(text => "buzz", size => 1000i64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fizz_query = TermQuery::new(
+            Term::from_field_text(text, "fizz"),
+            IndexRecordOption::Basic,
+        );
+        let queries: Vec<Box<dyn Query>> = vec![Box::new(AllQuery), Box::new(fizz_query)];
+        for query in &queries {
+            for &(limit, offset) in &[(1, 0), (10, 0), (10, 5), (100, 0)] {
+                let top_docs: Vec<(i64, DocAddress)> = searcher.search(
+                    query.as_ref(),
+                    &TopDocs::with_limit(limit)
+                        .and_offset(offset)
+                        .order_by_fast_field(size),
+                )?;
+                let expected_top_docs: Vec<(i64, DocAddress)> = searcher.search(
+                    query.as_ref(),
+                    &TopDocs::with_limit(limit).and_offset(offset).custom_score(
+                        move |segment_reader: &SegmentReader| {
+                            let size_reader = segment_reader.fast_fields().i64(size).unwrap();
+                            move |doc: DocId| size_reader.get(doc)
+                        },
+                    ),
+                )?;
+                assert_eq!(top_docs, expected_top_docs);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_field_collector_sorted_index_desc() -> crate::Result<()> {
+        test_top_field_collector_sorted_index_aux(Order::Desc)
+    }
+
+    #[test]
+    fn test_top_field_collector_sorted_index_asc() -> crate::Result<()> {
+        test_top_field_collector_sorted_index_aux(Order::Asc)
+    }
+
+    fn test_search_after_aux(sort_by_field: Option<IndexSortByField>) -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let size = schema_builder.add_i64_field("size", FAST);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field,
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..200i64 {
+            let value = if i % 3 == 0 { "fizz" } else { "buzz" };
+            index_writer.add_document(doc!(text => value, size => (i * 7) % 50 - 20))?;
+            if i % 70 == 69 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "buzz"));
+        index_writer.add_document(doc!(text => "buzz", size => 1000i64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fizz_query = TermQuery::new(
+            Term::from_field_text(text, "fizz"),
+            IndexRecordOption::Basic,
+        );
+        let queries: Vec<Box<dyn Query>> = vec![Box::new(AllQuery), Box::new(fizz_query)];
+        for query in &queries {
+            let expected_top_docs: Vec<(i64, DocAddress)> = searcher.search(
+                query.as_ref(),
+                &TopDocs::with_limit(1_000).order_by_fast_field(size),
+            )?;
+            for &page_len in &[1, 7, 100] {
+                let mut top_docs: Vec<(i64, DocAddress)> = Vec::new();
+                loop {
+                    let mut collector = TopDocs::with_limit(page_len);
+                    if let Some(&(last_size, last_doc_address)) = top_docs.last() {
+                        collector = collector.search_after(last_size, last_doc_address);
+                    }
+                    let (count, page): (usize, Vec<(i64, DocAddress)>) = searcher.search(
+                        query.as_ref(),
+                        &(Count, collector.order_by_fast_field(size)),
+                    )?;
+                    assert_eq!(count, expected_top_docs.len());
+                    if page.is_empty() {
+                        break;
+                    }
+                    top_docs.extend(page);
+                }
+                assert_eq!(top_docs, expected_top_docs);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_after() -> crate::Result<()> {
+        test_search_after_aux(None)?;
+        test_search_after_aux(Some(IndexSortByField {
+            field: "size".to_string(),
+            order: Order::Desc,
+        }))?;
+        test_search_after_aux(Some(IndexSortByField {
+            field: "size".to_string(),
+            order: Order::Asc,
+        }))
+    }
+
+    #[test]
+    fn test_search_after_requires_fast_field_ordering() -> crate::Result<()> {
+        let index = make_index()?;
+        let searcher = index.reader()?.searcher();
+        let collector = TopDocs::with_limit(2).search_after(1u64, DocAddress::new(0, 0));
+        assert!(matches!(
+            searcher.search(&AllQuery, &collector),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_custom_score_top_collector_with_offset() {
         let index = make_index().unwrap();
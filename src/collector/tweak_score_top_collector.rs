@@ -125,3 +125,19 @@ where
         (self)(doc, score)
     }
 }
+
+/// `ScoreTweaker` built from a closure that may fail to build the segment tweaker,
+/// e.g. when opening a fast field reader.
+pub(crate) struct TryScoreTweaker<F>(pub F);
+
+impl<F, TScore, TSegmentScoreTweaker> ScoreTweaker<TScore> for TryScoreTweaker<F>
+where
+    F: 'static + Send + Sync + Fn(&SegmentReader) -> Result<TSegmentScoreTweaker>,
+    TSegmentScoreTweaker: ScoreSegmentTweaker<TScore>,
+{
+    type Child = TSegmentScoreTweaker;
+
+    fn segment_tweaker(&self, segment_reader: &SegmentReader) -> Result<Self::Child> {
+        (self.0)(segment_reader)
+    }
+}
@@ -0,0 +1,643 @@
+use crate::core::{InvertedIndexReader, Segment};
+use crate::directory::error::Incompatibility;
+use crate::directory::{CompositeFile, FileSlice, WritePtr};
+use crate::fastfield::{CompositeFastFieldSerializer, FastFieldReaders};
+use crate::postings::compression::default_postings_codec;
+use crate::postings::{InvertedIndexSerializer, PostingsCodec};
+use crate::schema::{IndexRecordOption, Schema};
+use crate::store::{ExternalDocStore, StoreReader, StoreWriter};
+use crate::termdict::{TermDictionary, TermDictionaryType};
+use crate::{DocId, IndexSettings, TantivyError};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, RwLock};
+
+/// Name of the codec of the segments that do not record any codec,
+/// and of the segments of the indexes that do not configure one.
+pub const DEFAULT_CODEC_NAME: &str = "default";
+
+/// Name of the `SSTableCodec`.
+pub const SSTABLE_CODEC_NAME: &str = "sstable";
+
+/// A `Codec` defines how the components of a segment are written and read:
+/// the postings (and the term dictionary they are written with), the encoding
+/// of their blocks, the term dictionary, the fast fields and the doc store.
+///
+/// The codec used to write a segment is recorded in its `SegmentMeta`, and
+/// the same codec is used to read it. The codec of the new segments is set by
+/// `IndexSettings::codec`, so an experimental format can be tried on an index
+/// while its older segments stay readable with the codec they were written with.
+///
+/// Codecs are looked up by name in the `CodecRegistry` of the `Index`, which must
+/// therefore have them registered before the segments they wrote are opened.
+///
+/// All of the methods default to the format of `DefaultCodec`. Custom codecs
+/// typically transform the bytes of a component (encryption, alternative
+/// compression...) and delegate to these default implementations.
+pub trait Codec: Send + Sync + 'static {
+    /// Name of the codec, as recorded in the segment metas.
+    fn name(&self) -> &str;
+
+    /// Returns the `PostingsCodec` encoding the blocks of the posting lists.
+    ///
+    /// Defaults to the `BitpackedPostingsCodec`.
+    fn postings_codec(&self) -> Arc<dyn PostingsCodec> {
+        default_postings_codec()
+    }
+
+    /// Creates the serializer of the postings, positions and term dictionaries
+    /// of `segment`.
+    fn postings_serializer(&self, segment: &mut Segment) -> crate::Result<InvertedIndexSerializer> {
+        Ok(InvertedIndexSerializer::open(segment)?.with_postings_codec(self.postings_codec()))
+    }
+
+    /// Opens the inverted index of a field, given its term dictionary, postings
+    /// and positions.
+    fn open_inverted_index(
+        &self,
+        termdict: TermDictionary,
+        postings_file: FileSlice,
+        positions_file: FileSlice,
+        record_option: IndexRecordOption,
+        has_payloads: bool,
+    ) -> io::Result<InvertedIndexReader> {
+        Ok(InvertedIndexReader::new(
+            termdict,
+            postings_file,
+            positions_file,
+            record_option,
+            has_payloads,
+        )?
+        .with_postings_codec(self.postings_codec()))
+    }
+
+    /// Opens the term dictionary of a field.
+    fn open_term_dictionary(&self, file: FileSlice) -> crate::Result<TermDictionary> {
+        TermDictionary::open(file)
+    }
+
+    /// Creates the serializer of the fast fields of a segment.
+    fn fast_field_serializer(&self, write: WritePtr) -> io::Result<CompositeFastFieldSerializer> {
+        CompositeFastFieldSerializer::from_write(write)
+    }
+
+    /// Opens the fast fields of a segment with `max_doc` documents.
+    fn open_fast_fields(
+        &self,
+        schema: Schema,
+        file: FileSlice,
+        max_doc: DocId,
+    ) -> crate::Result<FastFieldReaders> {
+        let fast_fields_composite = CompositeFile::open(&file)?;
+        FastFieldReaders::open(schema, fast_fields_composite, max_doc)
+    }
+
+    /// Creates the writer of the doc store of a segment.
+    fn store_writer(&self, write: WritePtr, settings: &IndexSettings) -> StoreWriter {
+        StoreWriter::new(
+            write,
+            settings.docstore_compression,
+            settings.docstore_blocksize,
+        )
+    }
+
+    /// Opens the doc store of a segment.
+    fn open_store(&self, file: FileSlice) -> io::Result<StoreReader> {
+        StoreReader::open(file)
+    }
+}
+
+/// The codec of tantivy's own segment format.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultCodec;
+
+impl Codec for DefaultCodec {
+    fn name(&self) -> &str {
+        DEFAULT_CODEC_NAME
+    }
+}
+
+/// A codec writing the term dictionaries as SSTables
+/// (see [`TermDictionaryType::SSTable`](../termdict/enum.TermDictionaryType.html)),
+/// and the other components in the format of the `DefaultCodec`.
+///
+/// The SSTable term dictionaries are faster to build, at the cost of
+/// slower term lookups.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SSTableCodec;
+
+impl Codec for SSTableCodec {
+    fn name(&self) -> &str {
+        SSTABLE_CODEC_NAME
+    }
+
+    fn postings_serializer(&self, segment: &mut Segment) -> crate::Result<InvertedIndexSerializer> {
+        Ok(InvertedIndexSerializer::open_with_term_dictionary_type(
+            segment,
+            TermDictionaryType::SSTable,
+        )?
+        .with_postings_codec(self.postings_codec()))
+    }
+
+    fn open_term_dictionary(&self, file: FileSlice) -> crate::Result<TermDictionary> {
+        TermDictionary::open_with_type(file, TermDictionaryType::SSTable)
+    }
+}
+
+/// A codec putting the stored fields of the documents in an
+/// [`ExternalDocStore`](../store/trait.ExternalDocStore.html), e.g. a RocksDB instance or
+/// an object storage, and the other components in the format of the `DefaultCodec`.
+///
+/// The doc store of the segments then only holds the keys of the documents in the
+/// external store, which keeps the index small for very large document bodies.
+/// `Searcher::doc()` reads the stored fields from the external store.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::io;
+/// use std::sync::{Arc, RwLock};
+/// use tantivy::schema::{Schema, STORED};
+/// use tantivy::store::ExternalDocStore;
+/// use tantivy::{doc, DocAddress, ExternalStoreCodec, Index, IndexSettings};
+///
+/// #[derive(Default)]
+/// struct MemoryDocStore(RwLock<HashMap<Vec<u8>, Vec<u8>>>);
+///
+/// impl ExternalDocStore for MemoryDocStore {
+///     fn put(&self, key: &[u8], doc_bytes: &[u8]) -> io::Result<()> {
+///         self.0.write().unwrap().insert(key.to_vec(), doc_bytes.to_vec());
+///         Ok(())
+///     }
+///
+///     fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+///         Ok(self.0.read().unwrap().get(key).cloned())
+///     }
+/// }
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", STORED);
+/// let settings = IndexSettings {
+///     codec: Some("external".to_string()),
+///     ..IndexSettings::default()
+/// };
+/// let index = Index::builder()
+///     .schema(schema_builder.build())
+///     .settings(settings)
+///     .create_in_ram()?;
+/// let doc_store = Arc::new(MemoryDocStore::default());
+/// index
+///     .codecs()
+///     .register(ExternalStoreCodec::new("external", doc_store.clone()));
+///
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(body => "a very large body"))?;
+/// index_writer.commit()?;
+/// assert_eq!(doc_store.0.read().unwrap().len(), 1);
+///
+/// let searcher = index.reader()?.searcher();
+/// let doc = searcher.doc(DocAddress::new(0, 0))?;
+/// assert_eq!(doc.get_first(body).and_then(|value| value.text()), Some("a very large body"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ExternalStoreCodec {
+    name: String,
+    external_store: Arc<dyn ExternalDocStore>,
+}
+
+impl ExternalStoreCodec {
+    /// Creates a codec named `name`, putting the stored fields in `external_store`.
+    pub fn new(name: &str, external_store: Arc<dyn ExternalDocStore>) -> ExternalStoreCodec {
+        ExternalStoreCodec {
+            name: name.to_string(),
+            external_store,
+        }
+    }
+}
+
+impl Codec for ExternalStoreCodec {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn store_writer(&self, write: WritePtr, settings: &IndexSettings) -> StoreWriter {
+        DefaultCodec
+            .store_writer(write, settings)
+            .with_external_store(self.external_store.clone())
+    }
+
+    fn open_store(&self, file: FileSlice) -> io::Result<StoreReader> {
+        Ok(StoreReader::open(file)?.with_external_store(self.external_store.clone()))
+    }
+}
+
+/// The codec registry stores the codecs that can be used
+/// to write and read the segments of an index.
+///
+/// It is populated with the `DefaultCodec` and the `SSTableCodec`.
+#[derive(Clone)]
+pub struct CodecRegistry {
+    codecs: Arc<RwLock<HashMap<String, Arc<dyn Codec>>>>,
+}
+
+impl CodecRegistry {
+    /// Registers a codec under its name, replacing any codec
+    /// previously registered with the same name.
+    pub fn register<C: Codec>(&self, codec: C) {
+        self.codecs
+            .write()
+            .expect("Acquiring the lock should never fail")
+            .insert(codec.name().to_string(), Arc::new(codec));
+    }
+
+    /// Accessing a codec given its name.
+    pub fn get(&self, codec_name: &str) -> Option<Arc<dyn Codec>> {
+        self.codecs
+            .read()
+            .expect("Acquiring the lock should never fail")
+            .get(codec_name)
+            .cloned()
+    }
+
+    /// Returns the codec registered under `codec_name`, or an
+    /// `IncompatibleIndex` error if there is none.
+    pub(crate) fn get_or_err(&self, codec_name: &str) -> crate::Result<Arc<dyn Codec>> {
+        self.get(codec_name).ok_or_else(|| {
+            TantivyError::IncompatibleIndex(Incompatibility::UnknownCodec {
+                codec_name: codec_name.to_string(),
+            })
+        })
+    }
+}
+
+impl Default for CodecRegistry {
+    fn default() -> CodecRegistry {
+        let registry = CodecRegistry {
+            codecs: Arc::new(RwLock::new(HashMap::new())),
+        };
+        registry.register(DefaultCodec);
+        registry.register(SSTableCodec);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Codec, ExternalStoreCodec, DEFAULT_CODEC_NAME, SSTABLE_CODEC_NAME};
+    use crate::collector::Count;
+    use crate::directory::{FileSlice, RamDirectory};
+    use crate::postings::{PostingsCodec, COMPRESSED_BLOCK_MAX_SIZE, COMPRESSION_BLOCK_SIZE};
+    use crate::query::{FuzzyTermQuery, PhraseQuery, RangeQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, INDEXED, STORED, TEXT};
+    use crate::store::{ExternalDocStore, StoreReader};
+    use crate::termdict::TermDictionaryType;
+    use crate::{DocAddress, Index, IndexSettings, TantivyError, Term};
+    use futures::executor::block_on;
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, RwLock};
+
+    #[derive(Default)]
+    struct CountingCodec {
+        num_store_opens: Arc<AtomicUsize>,
+    }
+
+    impl Codec for CountingCodec {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn open_store(&self, file: FileSlice) -> io::Result<StoreReader> {
+            self.num_store_opens.fetch_add(1, Ordering::SeqCst);
+            StoreReader::open(file)
+        }
+    }
+
+    #[test]
+    fn test_codec_recorded_in_segment_meta() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let directory = RamDirectory::create();
+        let mut index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "old segment"))?;
+        index_writer.commit()?;
+        drop(index_writer);
+
+        let codec = CountingCodec::default();
+        let num_store_opens = codec.num_store_opens.clone();
+        index.codecs().register(codec);
+        *index.settings_mut() = IndexSettings {
+            codec: Some("counting".to_string()),
+            ..Default::default()
+        };
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "new segment"))?;
+        index_writer.commit()?;
+
+        let mut codec_names: Vec<String> = index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| segment_meta.codec_name().to_string())
+            .collect();
+        codec_names.sort();
+        assert_eq!(codec_names, vec!["counting", DEFAULT_CODEC_NAME]);
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert!(num_store_opens.load(Ordering::SeqCst) > 0);
+
+        let unregistered = Index::open(directory)?;
+        assert!(matches!(
+            unregistered.reader(),
+            Err(TantivyError::IncompatibleIndex(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sstable_codec() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let num = schema_builder.add_u64_field("num", INDEXED);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                codec: Some(SSTABLE_CODEC_NAME.to_string()),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0u64..200 {
+            index_writer.add_document(doc!(text => format!("term{} common", i), num => i))?;
+            if i == 99 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+
+        let check_searcher = || -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader.inverted_index(text)?;
+                assert_eq!(
+                    inverted_index.terms().term_dictionary_type(),
+                    TermDictionaryType::SSTable
+                );
+            }
+            let term_query = TermQuery::new(
+                Term::from_field_text(text, "common"),
+                IndexRecordOption::Basic,
+            );
+            assert_eq!(searcher.search(&term_query, &Count)?, 200);
+            let term_query = TermQuery::new(
+                Term::from_field_text(text, "term150"),
+                IndexRecordOption::Basic,
+            );
+            assert_eq!(searcher.search(&term_query, &Count)?, 1);
+            let fuzzy_query = FuzzyTermQuery::new(Term::from_field_text(text, "term15"), 1, true);
+            // term1, term5, term10...term19, term25...term95, term51,
+            // term105...term195 and term150...term159.
+            assert_eq!(searcher.search(&fuzzy_query, &Count)?, 40);
+            let range_query = RangeQuery::new_u64(num, 50..150);
+            assert_eq!(searcher.search(&range_query, &Count)?, 100);
+            Ok(())
+        };
+        check_searcher()?;
+
+        let segment_ids = index.searchable_segment_ids()?;
+        assert_eq!(segment_ids.len(), 2);
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].codec_name(), SSTABLE_CODEC_NAME);
+        check_searcher()
+    }
+
+    /// Stores the blocks as arrays of little-endian `u32`.
+    #[derive(Default)]
+    struct PlainPostingsCodec {
+        num_decoded_blocks: AtomicUsize,
+    }
+
+    impl PostingsCodec for PlainPostingsCodec {
+        fn compress_block_sorted(
+            &self,
+            block: &[u32],
+            _offset: u32,
+            output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+        ) -> (u8, usize) {
+            self.compress_block_unsorted(block, output)
+        }
+
+        fn compress_block_unsorted(
+            &self,
+            block: &[u32],
+            output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+        ) -> (u8, usize) {
+            for (chunk, val) in output.chunks_exact_mut(4).zip(block) {
+                chunk.copy_from_slice(&val.to_le_bytes());
+            }
+            (32, COMPRESSED_BLOCK_MAX_SIZE)
+        }
+
+        fn uncompress_block_sorted(
+            &self,
+            compressed_data: &[u8],
+            _offset: u32,
+            num_bits: u8,
+            output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+        ) -> usize {
+            self.uncompress_block_unsorted(compressed_data, num_bits, output)
+        }
+
+        fn uncompress_block_unsorted(
+            &self,
+            compressed_data: &[u8],
+            num_bits: u8,
+            output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+        ) -> usize {
+            assert_eq!(num_bits, 32);
+            self.num_decoded_blocks.fetch_add(1, Ordering::SeqCst);
+            for (val, chunk) in output.iter_mut().zip(compressed_data.chunks_exact(4)) {
+                *val = u32::from_le_bytes(chunk.try_into().unwrap());
+            }
+            COMPRESSED_BLOCK_MAX_SIZE
+        }
+    }
+
+    struct PlainCodec(Arc<PlainPostingsCodec>);
+
+    impl Codec for PlainCodec {
+        fn name(&self) -> &str {
+            "plain"
+        }
+
+        fn postings_codec(&self) -> Arc<dyn PostingsCodec> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_postings_codec() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        let postings_codec = Arc::new(PlainPostingsCodec::default());
+        index.codecs().register(PlainCodec(postings_codec.clone()));
+        let add_documents = |index: &Index| -> crate::Result<()> {
+            let mut index_writer = index.writer_for_tests()?;
+            for i in 0..300 {
+                let parity = if i % 2 == 0 { "even" } else { "odd" };
+                index_writer.add_document(doc!(text => format!("common {}", parity)))?;
+            }
+            index_writer.commit()?;
+            Ok(())
+        };
+        add_documents(&index)?;
+        *index.settings_mut() = IndexSettings {
+            codec: Some("plain".to_string()),
+            ..IndexSettings::default()
+        };
+        add_documents(&index)?;
+
+        let check_searcher = || -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            let term_query = TermQuery::new(
+                Term::from_field_text(text, "even"),
+                IndexRecordOption::WithFreqs,
+            );
+            assert_eq!(searcher.search(&term_query, &Count)?, 300);
+            let phrase_query = PhraseQuery::new(vec![
+                Term::from_field_text(text, "common"),
+                Term::from_field_text(text, "odd"),
+            ]);
+            assert_eq!(searcher.search(&phrase_query, &Count)?, 300);
+            Ok(())
+        };
+        check_searcher()?;
+        assert!(postings_codec.num_decoded_blocks.load(Ordering::SeqCst) > 0);
+
+        // The segment written with the default postings codec is merged
+        // into a segment written with the plain postings codec.
+        let mut index_writer = index.writer_for_tests()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        assert_eq!(segment_ids.len(), 2);
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].codec_name(), "plain");
+        check_searcher()
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryDocStore(Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>>);
+
+    impl MemoryDocStore {
+        fn len(&self) -> usize {
+            self.0.read().unwrap().len()
+        }
+    }
+
+    impl ExternalDocStore for MemoryDocStore {
+        fn put(&self, key: &[u8], doc_bytes: &[u8]) -> io::Result<()> {
+            self.0
+                .write()
+                .unwrap()
+                .insert(key.to_vec(), doc_bytes.to_vec());
+            Ok(())
+        }
+
+        fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.0.read().unwrap().get(key).cloned())
+        }
+    }
+
+    #[test]
+    fn test_external_store_codec() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let body = schema_builder.add_text_field("body", TEXT | STORED);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        let doc_store = MemoryDocStore::default();
+        index.codecs().register(ExternalStoreCodec::new(
+            "external",
+            Arc::new(doc_store.clone()),
+        ));
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id => 0u64, body => "local body 0"))?;
+        index_writer.commit()?;
+        drop(index_writer);
+
+        *index.settings_mut() = IndexSettings {
+            codec: Some("external".to_string()),
+            ..IndexSettings::default()
+        };
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 1..5u64 {
+            index_writer.add_document(doc!(id => i, body => format!("external body {}", i)))?;
+            if i % 2 == 0 {
+                index_writer.commit()?;
+            }
+        }
+        assert_eq!(doc_store.len(), 4);
+
+        let check_docs = |num_docs: usize| -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            assert_eq!(searcher.num_docs() as usize, num_docs);
+            for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+                for doc_id in segment_reader.doc_ids_alive() {
+                    let doc = searcher.doc(DocAddress::new(segment_ord as u32, doc_id))?;
+                    let id_value = doc
+                        .get_first(id)
+                        .and_then(|value| value.u64_value())
+                        .unwrap();
+                    let expected_body = if id_value == 0 {
+                        "local body 0".to_string()
+                    } else {
+                        format!("external body {}", id_value)
+                    };
+                    assert_eq!(
+                        doc.get_first(body).and_then(|value| value.text()),
+                        Some(expected_body.as_str())
+                    );
+                }
+            }
+            Ok(())
+        };
+        check_docs(5)?;
+
+        // The keys of the external documents are copied over on merge, and the
+        // local documents are put in the external store.
+        index_writer.delete_term(Term::from_field_u64(id, 3));
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        assert_eq!(segment_ids.len(), 3);
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(doc_store.len(), 5);
+        check_docs(4)?;
+
+        // Without its external store, the documents cannot be read.
+        index.codecs().register(ExternalStoreCodec::new(
+            "external",
+            Arc::new(MemoryDocStore::default()),
+        ));
+        let searcher = index.reader()?.searcher();
+        assert!(matches!(
+            searcher.doc(DocAddress::new(0, 0)),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 use crossbeam::channel;
+use futures::channel::oneshot;
+use futures::future::Future;
 use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 /// Search executor whether search request are single thread or multithread.
 ///
@@ -32,6 +37,9 @@ impl Executor {
 
     /// Perform a map in the thread pool.
     ///
+    /// If there is at most one task, it is executed directly in the calling thread,
+    /// as dispatching it to the thread pool would only add latency.
+    ///
     /// Regardless of the executor (`SingleThread` or `ThreadPool`), panics in the task
     /// will propagate to the caller.
     pub fn map<
@@ -49,6 +57,12 @@ impl Executor {
             Executor::ThreadPool(pool) => {
                 let args_with_indices: Vec<(usize, A)> = args.enumerate().collect();
                 let num_fruits = args_with_indices.len();
+                if num_fruits <= 1 {
+                    return args_with_indices
+                        .into_iter()
+                        .map(|(_, arg)| f(arg))
+                        .collect::<crate::Result<_>>();
+                }
                 let fruit_receiver = {
                     let (fruit_sender, fruit_receiver) = channel::unbounded();
                     pool.scope(|scope| {
@@ -82,6 +96,65 @@ impl Executor {
             }
         }
     }
+
+    /// Runs a task in the thread pool, and returns a future resolving to its result.
+    ///
+    /// The `SingleThread` executor runs the task in the calling thread, and the
+    /// returned future then yields once to the async runtime before resolving.
+    ///
+    /// Panics in the task will propagate to the caller awaiting the future.
+    pub(crate) fn run_async<R, F>(&self, f: F) -> impl Future<Output = crate::Result<R>>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> crate::Result<R> + Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+        let task = move || {
+            let _ = result_sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        };
+        let is_single_thread = match self {
+            Executor::SingleThread => {
+                task();
+                true
+            }
+            Executor::ThreadPool(pool) => {
+                pool.spawn(task);
+                false
+            }
+        };
+        async move {
+            if is_single_thread {
+                YieldNow::default().await;
+            }
+            match result_receiver.await {
+                Ok(Ok(result)) => result,
+                Ok(Err(panic_payload)) => panic::resume_unwind(panic_payload),
+                Err(_) => Err(crate::TantivyError::SystemError(
+                    "A search task was cancelled. This should never happen.".to_string(),
+                )),
+            }
+        }
+    }
+}
+
+/// Future returning `Pending` once, so that the async runtime can run
+/// other tasks, before resolving.
+#[derive(Default)]
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
 }
 
 #[cfg(test)]
@@ -127,6 +200,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_map_multithread_single_task_runs_in_caller_thread() {
+        let caller_thread_id = std::thread::current().id();
+        let result: Vec<bool> = Executor::multi_thread(3, "search-test")
+            .unwrap()
+            .map(
+                |_| Ok(std::thread::current().id() == caller_thread_id),
+                std::iter::once(0),
+            )
+            .unwrap();
+        assert_eq!(result, vec![true]);
+    }
+
+    #[test]
+    fn test_run_async() {
+        use futures::executor::block_on;
+        let caller_thread_id = std::thread::current().id();
+        let single_thread_result = block_on(
+            Executor::single_thread()
+                .run_async(move || Ok(std::thread::current().id() == caller_thread_id)),
+        );
+        assert!(single_thread_result.unwrap());
+        let multi_thread_result = block_on(
+            Executor::multi_thread(2, "search-test")
+                .unwrap()
+                .run_async(move || Ok(std::thread::current().id() == caller_thread_id)),
+        );
+        assert!(!multi_thread_result.unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "panic should propagate")]
+    fn test_run_async_panic_propagates() {
+        let executor = Executor::multi_thread(1, "search-test").unwrap();
+        let _result: crate::Result<()> =
+            futures::executor::block_on(executor.run_async(|| panic!("panic should propagate")));
+    }
+
     #[test]
     fn test_map_multithread() {
         let result: Vec<usize> = Executor::multi_thread(3, "search-test")
@@ -0,0 +1,200 @@
+use crate::core::{InvertedIndexReader, SegmentReader};
+use crate::error::DataCorruption;
+use crate::schema::{Facet, Field, FieldType};
+use crate::termdict::{TermMerger, TermOrdinal};
+use crate::{SegmentOrdinal, TantivyError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Maps the segment local term ordinals of a facet field to global ordinals,
+/// shared by all of the segments of a `Searcher`.
+///
+/// The facet fields are the fast fields holding term ordinals. Their ordinals are
+/// local to each segment, so that aggregating or grouping documents over several
+/// segments requires comparing the terms themselves. The global ordinals are the
+/// positions of the terms in the sorted union of the terms of all of the segments:
+/// once the per-segment ordinals are mapped, they can be compared and counted directly.
+///
+/// Global ordinals are obtained with
+/// [`Searcher::global_ordinals`](../struct.Searcher.html#method.global_ordinals), which
+/// builds them on the first call and caches them for the generation of the `IndexReader`.
+///
+/// ```rust
+/// use tantivy::schema::{Facet, Schema, INDEXED};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let category = schema_builder.add_facet_field("category", INDEXED);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(category => Facet::from("/books")))?;
+/// index_writer.commit()?;
+/// index_writer.add_document(doc!(category => Facet::from("/art")))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let global_ordinals = searcher.global_ordinals(category)?;
+/// // The root facet is a term as well.
+/// assert_eq!(global_ordinals.num_terms(), 3);
+/// let books_ord = global_ordinals.global_ord(&Facet::from("/books"))?.unwrap();
+/// assert_eq!(global_ordinals.facet(books_ord)?, Facet::from("/books"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct GlobalOrdinals {
+    field: Field,
+    segment_mappings: Vec<Vec<TermOrdinal>>,
+    term_locations: Vec<(SegmentOrdinal, TermOrdinal)>,
+    inverted_indexes: Vec<Arc<InvertedIndexReader>>,
+}
+
+impl GlobalOrdinals {
+    /// Builds the global ordinals of the facet field `field`, by merging
+    /// the term dictionaries of the segments.
+    pub(crate) fn build(
+        segment_readers: &[SegmentReader],
+        field: Field,
+    ) -> crate::Result<GlobalOrdinals> {
+        if let Some(segment_reader) = segment_readers.first() {
+            let field_entry = segment_reader.schema().get_field_entry(field);
+            if !matches!(field_entry.field_type(), FieldType::HierarchicalFacet(_)) {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} is not a facet field.",
+                    field_entry.name()
+                )));
+            }
+        }
+        let inverted_indexes: Vec<Arc<InvertedIndexReader>> = segment_readers
+            .iter()
+            .map(|segment_reader| segment_reader.inverted_index(field))
+            .collect::<crate::Result<_>>()?;
+        let mut segment_mappings: Vec<Vec<TermOrdinal>> = inverted_indexes
+            .iter()
+            .map(|inverted_index| Vec::with_capacity(inverted_index.terms().num_terms()))
+            .collect();
+        let mut term_locations = Vec::new();
+        let term_streams = inverted_indexes
+            .iter()
+            .map(|inverted_index| inverted_index.terms().stream())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut term_merger = TermMerger::new(term_streams);
+        while term_merger.advance()? {
+            let global_ord = term_locations.len() as TermOrdinal;
+            let mut first_location = None;
+            for (segment_ord, local_ord) in term_merger.matching_segments() {
+                // the terms are streamed in order, so the local ordinals are contiguous.
+                debug_assert_eq!(segment_mappings[segment_ord].len() as u64, local_ord);
+                segment_mappings[segment_ord].push(global_ord);
+                first_location.get_or_insert((segment_ord as SegmentOrdinal, local_ord));
+            }
+            term_locations.push(first_location.expect("A merged term belongs to a segment"));
+        }
+        Ok(GlobalOrdinals {
+            field,
+            segment_mappings,
+            term_locations,
+            inverted_indexes,
+        })
+    }
+
+    /// Returns the field of the global ordinals.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the number of segments of the global ordinals.
+    pub fn num_segments(&self) -> usize {
+        self.segment_mappings.len()
+    }
+
+    /// Returns the number of distinct terms over all of the segments.
+    ///
+    /// The terms of a facet field include the ancestors of the facets, and the root facet.
+    /// The global ordinals range from `0` to `num_terms() - 1`.
+    pub fn num_terms(&self) -> usize {
+        self.term_locations.len()
+    }
+
+    /// Returns the global ordinals of the terms of the segment `segment_ord`,
+    /// indexed by their local ordinals.
+    pub fn segment_mapping(&self, segment_ord: SegmentOrdinal) -> &[TermOrdinal] {
+        &self.segment_mappings[segment_ord as usize]
+    }
+
+    /// Returns the global ordinal of the term with the local ordinal `local_ord`
+    /// in the segment `segment_ord`.
+    pub fn to_global(&self, segment_ord: SegmentOrdinal, local_ord: TermOrdinal) -> TermOrdinal {
+        self.segment_mappings[segment_ord as usize][local_ord as usize]
+    }
+
+    /// Returns the global ordinal of `facet`, or `None` if no segment contains it.
+    pub fn global_ord(&self, facet: &Facet) -> crate::Result<Option<TermOrdinal>> {
+        for (segment_ord, inverted_index) in self.inverted_indexes.iter().enumerate() {
+            if let Some(local_ord) = inverted_index.terms().term_ord(facet.encoded_str())? {
+                return Ok(Some(
+                    self.to_global(segment_ord as SegmentOrdinal, local_ord),
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Writes the bytes of the term with the global ordinal `global_ord` into `output`.
+    ///
+    /// # Panics
+    /// If `global_ord` is not lower than `num_terms()`.
+    pub fn term_bytes(&self, global_ord: TermOrdinal, output: &mut Vec<u8>) -> crate::Result<()> {
+        let (segment_ord, local_ord) = self.term_locations[global_ord as usize];
+        let found_term = self.inverted_indexes[segment_ord as usize]
+            .terms()
+            .ord_to_term(local_ord, output)?;
+        if !found_term {
+            return Err(DataCorruption::comment_only(format!(
+                "Term ordinal {} is missing from the term dictionary of segment #{}",
+                local_ord, segment_ord
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Returns the facet with the global ordinal `global_ord`.
+    ///
+    /// # Panics
+    /// If `global_ord` is not lower than `num_terms()`.
+    pub fn facet(&self, global_ord: TermOrdinal) -> crate::Result<Facet> {
+        let mut term_bytes = Vec::new();
+        self.term_bytes(global_ord, &mut term_bytes)?;
+        Facet::from_encoded(term_bytes)
+            .map_err(|utf8_err| DataCorruption::comment_only(utf8_err.to_string()).into())
+    }
+}
+
+/// The global ordinals built for the searchers of a generation of an `IndexReader`.
+#[derive(Clone, Default)]
+pub(crate) struct GlobalOrdinalsCache {
+    global_ordinals: Arc<Mutex<HashMap<Field, Arc<GlobalOrdinals>>>>,
+}
+
+impl GlobalOrdinalsCache {
+    /// Returns the global ordinals of `field`, building them if they are not cached yet.
+    pub(crate) fn get_or_build(
+        &self,
+        segment_readers: &[SegmentReader],
+        field: Field,
+    ) -> crate::Result<Arc<GlobalOrdinals>> {
+        // The lock is held while building, so that the global ordinals
+        // of a field are only built once.
+        let mut global_ordinals = self
+            .global_ordinals
+            .lock()
+            .expect("Global ordinals cache lock poisoned. This should never happen.");
+        if let Some(field_global_ordinals) = global_ordinals.get(&field) {
+            return Ok(field_global_ordinals.clone());
+        }
+        let field_global_ordinals = Arc::new(GlobalOrdinals::build(segment_readers, field)?);
+        global_ordinals.insert(field, field_global_ordinals.clone());
+        Ok(field_global_ordinals)
+    }
+}
@@ -1,34 +1,40 @@
 use super::{segment::Segment, IndexSettings};
+use crate::core::CodecRegistry;
 use crate::core::Executor;
 use crate::core::IndexMeta;
 use crate::core::SegmentId;
 use crate::core::SegmentMeta;
 use crate::core::SegmentMetaInventory;
 use crate::core::META_FILEPATH;
+use crate::core::{find_skipped_segments, SkippedSegment};
+use crate::core::{IndexSnapshot, SnapshotManifest};
+use crate::core::{IndexStatistics, SegmentStatistics};
 use crate::directory::error::OpenReadError;
 use crate::directory::ManagedDirectory;
 #[cfg(feature = "mmap")]
 use crate::directory::MmapDirectory;
-use crate::directory::INDEX_WRITER_LOCK;
 use crate::directory::{Directory, RamDirectory};
+use crate::directory::{FileSlice, INDEX_WRITER_LOCK, META_LOCK};
 use crate::error::DataCorruption;
 use crate::error::TantivyError;
 use crate::indexer::index_writer::{HEAP_SIZE_MIN, MAX_NUM_THREAD};
-use crate::indexer::segment_updater::save_new_metas;
+use crate::indexer::segment_updater::{save_metas, save_new_metas};
 use crate::reader::IndexReader;
 use crate::reader::IndexReaderBuilder;
+use crate::schema::Cardinality;
 use crate::schema::Field;
 use crate::schema::FieldType;
 use crate::schema::Schema;
-use crate::tokenizer::{TextAnalyzer, TokenizerManager};
+use crate::schema::DEFAULT_POSITION_GAP;
+use crate::tokenizer::{TextAnalyzer, Token, TokenizerManager};
 use crate::IndexWriter;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 
 #[cfg(feature = "mmap")]
 use std::path::Path;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 fn load_metas(
     directory: &dyn Directory,
@@ -55,6 +61,55 @@ fn load_metas(
         .map_err(From::from)
 }
 
+/// Checks that the tokenizers of all of the indexed text fields of the schema
+/// are registered in `tokenizers`.
+fn check_tokenizers(schema: &Schema, tokenizers: &TokenizerManager) -> crate::Result<()> {
+    for (_, field_entry) in schema.fields() {
+        if let FieldType::Str(text_options) = field_entry.field_type() {
+            if let Some(indexing_options) = text_options.get_indexing_options() {
+                let tokenizer_name = indexing_options.tokenizer();
+                if !tokenizers.contains(tokenizer_name) {
+                    return Err(TantivyError::SchemaError(format!(
+                        "The tokenizer {:?} of the field {:?} is not registered.",
+                        tokenizer_name,
+                        field_entry.name()
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the tenant field of the settings, if any, is a single-valued `u64`
+/// fast field of the schema.
+fn check_tenant_field(schema: &Schema, settings: &IndexSettings) -> crate::Result<()> {
+    let tenant_field_name = if let Some(tenant_field_name) = settings.tenant_field.as_ref() {
+        tenant_field_name
+    } else {
+        return Ok(());
+    };
+    if settings.sort_by_field.is_some() {
+        return Err(TantivyError::InvalidArgument(
+            "The tenant field cannot be combined with sort_by_field.".to_string(),
+        ));
+    }
+    let field = schema.get_field(tenant_field_name).ok_or_else(|| {
+        TantivyError::InvalidArgument(format!("tenant field not found: {:?}", tenant_field_name))
+    })?;
+    match schema.get_field_entry(field).field_type() {
+        FieldType::U64(options)
+            if options.get_fastfield_cardinality() == Some(Cardinality::SingleValue) =>
+        {
+            Ok(())
+        }
+        _ => Err(TantivyError::SchemaError(format!(
+            "The tenant field {:?} is not a single-valued u64 fast field.",
+            tenant_field_name
+        ))),
+    }
+}
+
 /// IndexBuilder can be used to create an index.
 ///
 /// Use in conjunction with `SchemaBuilder`. Global index settings
@@ -83,6 +138,7 @@ fn load_metas(
 pub struct IndexBuilder {
     schema: Option<Schema>,
     index_settings: IndexSettings,
+    tokenizers: Option<TokenizerManager>,
 }
 impl Default for IndexBuilder {
     fn default() -> Self {
@@ -95,6 +151,7 @@ impl IndexBuilder {
         Self {
             schema: None,
             index_settings: IndexSettings::default(),
+            tokenizers: None,
         }
     }
     /// Set the settings
@@ -107,15 +164,22 @@ impl IndexBuilder {
         self.schema = Some(schema);
         self
     }
+    /// Set the tokenizer manager
+    ///
+    /// The text fields of the schema refer to their tokenizer by its name: the
+    /// custom tokenizers they use must be registered in `tokenizers`, or creating
+    /// or opening the index returns a `SchemaError`.
+    pub fn tokenizers(mut self, tokenizers: TokenizerManager) -> Self {
+        self.tokenizers = Some(tokenizers);
+        self
+    }
     /// Creates a new index using the `RAMDirectory`.
     ///
     /// The index will be allocated in anonymous memory.
     /// This should only be used for unit tests.
     pub fn create_in_ram(self) -> Result<Index, TantivyError> {
         let ram_directory = RamDirectory::create();
-        Ok(self
-            .create(ram_directory)
-            .expect("Creating a RAMDirectory should never fail"))
+        self.create(ram_directory)
     }
     /// Creates a new index in a given filepath.
     /// The index will use the `MMapDirectory`.
@@ -154,7 +218,10 @@ impl IndexBuilder {
         if !Index::exists(&*dir)? {
             return self.create(dir);
         }
-        let index = Index::open(dir)?;
+        let index = match self.tokenizers.clone() {
+            Some(tokenizers) => Index::open_with_tokenizers(dir, tokenizers)?,
+            None => Index::open(dir)?,
+        };
         if index.schema() == self.get_expect_schema()? {
             Ok(index)
         } else {
@@ -168,6 +235,10 @@ impl IndexBuilder {
     /// If a directory previously existed, it will be erased.
     fn create<T: Into<Box<dyn Directory>>>(self, dir: T) -> crate::Result<Index> {
         let dir = dir.into();
+        if let Some(tokenizers) = self.tokenizers.as_ref() {
+            check_tokenizers(&self.get_expect_schema()?, tokenizers)?;
+        }
+        check_tenant_field(&self.get_expect_schema()?, &self.index_settings)?;
         let directory = ManagedDirectory::wrap(dir)?;
         save_new_metas(
             self.get_expect_schema()?,
@@ -176,7 +247,10 @@ impl IndexBuilder {
         )?;
         let mut metas = IndexMeta::with_schema(self.get_expect_schema()?);
         metas.index_settings = self.index_settings;
-        let index = Index::open_from_metas(directory, &metas, SegmentMetaInventory::default());
+        let mut index = Index::open_from_metas(directory, &metas, SegmentMetaInventory::default());
+        if let Some(tokenizers) = self.tokenizers {
+            index.set_tokenizers(tokenizers);
+        }
         Ok(index)
     }
 }
@@ -186,10 +260,14 @@ impl IndexBuilder {
 pub struct Index {
     directory: ManagedDirectory,
     schema: Schema,
+    schema_version: u32,
     settings: IndexSettings,
     executor: Arc<Executor>,
     tokenizers: TokenizerManager,
+    codecs: CodecRegistry,
     inventory: SegmentMetaInventory,
+    soft_committed_meta: Arc<RwLock<Option<IndexMeta>>>,
+    skipped_segments: Arc<RwLock<Vec<SkippedSegment>>>,
 }
 
 impl Index {
@@ -298,9 +376,13 @@ impl Index {
             settings: metas.index_settings.clone(),
             directory,
             schema,
+            schema_version: metas.schema_version,
             tokenizers: TokenizerManager::default(),
+            codecs: CodecRegistry::default(),
             executor: Arc::new(Executor::single_thread()),
             inventory,
+            soft_committed_meta: Arc::default(),
+            skipped_segments: Arc::default(),
         }
     }
 
@@ -309,25 +391,61 @@ impl Index {
         &self.tokenizers
     }
 
+    /// Replaces the tokenizer manager of the index.
+    pub(crate) fn set_tokenizers(&mut self, tokenizers: TokenizerManager) {
+        self.tokenizers = tokenizers;
+    }
+
+    /// Accessor for the codec registry.
+    ///
+    /// The codecs of the segments of the index, and the codec set in the
+    /// `IndexSettings`, must be registered before the index is read or written.
+    pub fn codecs(&self) -> &CodecRegistry {
+        &self.codecs
+    }
+
     /// Helper to access the tokenizer associated to a specific field.
     pub fn tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
         let field_entry = self.schema.get_field_entry(field);
         let field_type = field_entry.field_type();
-        let tokenizer_manager: &TokenizerManager = self.tokenizers();
-        let tokenizer_name_opt: Option<TextAnalyzer> = match field_type {
+        let tokenizer_name_opt: Option<&str> = match field_type {
             FieldType::Str(text_options) => text_options
                 .get_indexing_options()
-                .map(|text_indexing_options| text_indexing_options.tokenizer().to_string())
-                .and_then(|tokenizer_name| tokenizer_manager.get(&tokenizer_name)),
+                .map(|text_indexing_options| text_indexing_options.tokenizer()),
             _ => None,
         };
-        match tokenizer_name_opt {
-            Some(tokenizer) => Ok(tokenizer),
-            None => Err(TantivyError::SchemaError(format!(
-                "{:?} is not a text field.",
+        let tokenizer_name = tokenizer_name_opt.ok_or_else(|| {
+            TantivyError::SchemaError(format!("{:?} is not a text field.", field_entry.name()))
+        })?;
+        self.tokenizers().get(tokenizer_name).ok_or_else(|| {
+            TantivyError::SchemaError(format!(
+                "The tokenizer {:?} of the field {:?} is not registered.",
+                tokenizer_name,
                 field_entry.name()
-            ))),
-        }
+            ))
+        })
+    }
+
+    /// Analyzes the values of a text field as they are analyzed when indexed,
+    /// e.g. to re-analyze its stored values for highlighting.
+    ///
+    /// Each token comes with the ordinal of the value it belongs to. Its offsets
+    /// are relative to this value, and its position is the one of the index.
+    /// See [`TextAnalyzer::analyze_texts`](../tokenizer/struct.TextAnalyzer.html#method.analyze_texts).
+    pub fn analyze_field(
+        &self,
+        field: Field,
+        texts: &[&str],
+    ) -> crate::Result<Vec<(usize, Token)>> {
+        let tokenizer = self.tokenizer_for_field(field)?;
+        let position_gap = match self.schema.get_field_entry(field).field_type() {
+            FieldType::Str(text_options) => text_options
+                .get_indexing_options()
+                .map(|indexing_options| indexing_options.position_gap())
+                .unwrap_or(DEFAULT_POSITION_GAP),
+            _ => DEFAULT_POSITION_GAP,
+        };
+        Ok(tokenizer.analyze_texts(texts, position_gap as usize))
     }
 
     /// Create a default `IndexReader` for the given index.
@@ -372,6 +490,9 @@ impl Index {
     }
 
     /// Open the index using the provided directory
+    ///
+    /// The files of the segments are not read until the segments are opened.
+    /// See [`Index::open_checked`](#method.open_checked) to validate them first.
     pub fn open<T: Into<Box<dyn Directory>>>(directory: T) -> crate::Result<Index> {
         let directory = directory.into();
         let directory = ManagedDirectory::wrap(directory)?;
@@ -381,11 +502,140 @@ impl Index {
         Ok(index)
     }
 
+    /// Opens the index using the provided directory, after validating the files of the
+    /// committed segments against their checksums.
+    ///
+    /// The segments with a missing or corrupted file, typically left by a writer that
+    /// crashed, are skipped rather than failing to search the whole index: they are listed
+    /// by [`Index::skipped_segments`](#method.skipped_segments), and are never searched.
+    /// No `IndexWriter` can be created until the damaged segments are removed by
+    /// [`Index::repair`](#method.repair).
+    ///
+    /// All of the files of the committed segments are read, so opening the index this way
+    /// is proportional to its size.
+    pub fn open_checked<T: Into<Box<dyn Directory>>>(directory: T) -> crate::Result<Index> {
+        let directory = ManagedDirectory::wrap(directory.into())?;
+        let inventory = SegmentMetaInventory::default();
+        let metas = load_metas(&directory, &inventory)?;
+        let skipped_segments = find_skipped_segments(&directory, &metas)?;
+        let index = Index::open_from_metas(directory, &metas, inventory);
+        *index.skipped_segments.write().unwrap() = skipped_segments;
+        Ok(index)
+    }
+
+    /// Opens the index using the provided directory, with the given tokenizer manager.
+    ///
+    /// Returns a `SchemaError` if the tokenizer of one of the text fields of the
+    /// index is not registered in `tokenizers`.
+    pub fn open_with_tokenizers<T: Into<Box<dyn Directory>>>(
+        directory: T,
+        tokenizers: TokenizerManager,
+    ) -> crate::Result<Index> {
+        let mut index = Index::open(directory)?;
+        check_tokenizers(&index.schema, &tokenizers)?;
+        index.set_tokenizers(tokenizers);
+        Ok(index)
+    }
+
+    /// Opens the index using the provided directory, with a schema extending the
+    /// schema of the index.
+    ///
+    /// The fields of the index schema must be kept unchanged, and new fields can only
+    /// be appended at the end of the schema (see
+    /// [`Schema::check_extends`](./schema/struct.Schema.html#method.check_extends)).
+    /// The segments written before the new fields were added are read as if none of their
+    /// documents had a value for these fields.
+    ///
+    /// If `schema` has new fields, the new schema is saved right away
+    /// and the schema version of the index is incremented.
+    /// This requires acquiring the index lock: no `IndexWriter` may be working
+    /// on the index at the same time.
+    pub fn open_with_schema<T: Into<Box<dyn Directory>>>(
+        directory: T,
+        schema: Schema,
+    ) -> crate::Result<Index> {
+        let directory = ManagedDirectory::wrap(directory.into())?;
+        let inventory = SegmentMetaInventory::default();
+        let mut metas = load_metas(&directory, &inventory)?;
+        schema.check_extends(&metas.schema)?;
+        if schema != metas.schema {
+            let _directory_lock = directory.acquire_lock(&INDEX_WRITER_LOCK).map_err(|err| {
+                TantivyError::LockFailure(
+                    err,
+                    Some("Failed to acquire index lock to update the schema.".to_string()),
+                )
+            })?;
+            // the metas are reloaded under the lock, as a commit may have happened since.
+            metas = load_metas(&directory, &inventory)?;
+            schema.check_extends(&metas.schema)?;
+            metas.schema = schema;
+            metas.schema_version += 1;
+            save_metas(&metas, &directory)?;
+        }
+        Ok(Index::open_from_metas(directory, &metas, inventory))
+    }
+
     /// Reads the index meta file from the directory.
     pub fn load_metas(&self) -> crate::Result<IndexMeta> {
         load_metas(self.directory(), &self.inventory)
     }
 
+    /// Returns the segments skipped when the index was opened with
+    /// [`Index::open_checked`](#method.open_checked): the committed segments with a
+    /// missing or corrupted file, and the segments left in the directory without
+    /// being committed.
+    ///
+    /// The list is always empty if the index was opened otherwise, and it is cleared by
+    /// [`Index::repair`](#method.repair).
+    pub fn skipped_segments(&self) -> Vec<SkippedSegment> {
+        self.skipped_segments.read().unwrap().clone()
+    }
+
+    /// Rewrites the meta file without the committed segments that have a missing or
+    /// corrupted file, and deletes the files of the segments that are not committed.
+    ///
+    /// The segments are checked again, as a commit may have happened since the index was
+    /// opened. This requires acquiring the index lock: no `IndexWriter` may be working on
+    /// the index at the same time. The documents of the removed segments are lost.
+    ///
+    /// Returns the segments that were removed.
+    pub fn repair(&self) -> crate::Result<Vec<SkippedSegment>> {
+        let _directory_lock = self
+            .directory
+            .acquire_lock(&INDEX_WRITER_LOCK)
+            .map_err(|err| {
+                TantivyError::LockFailure(
+                    err,
+                    Some("Failed to acquire index lock to repair the index.".to_string()),
+                )
+            })?;
+        let mut metas = load_metas(self.directory(), &self.inventory)?;
+        let skipped_segments = find_skipped_segments(self.directory(), &metas)?;
+        let damaged_segment_ids: HashSet<SegmentId> = skipped_segments
+            .iter()
+            .filter(|skipped_segment| skipped_segment.is_damaged())
+            .map(|skipped_segment| skipped_segment.segment_id)
+            .collect();
+        if !damaged_segment_ids.is_empty() {
+            metas
+                .segments
+                .retain(|segment_meta| !damaged_segment_ids.contains(&segment_meta.id()));
+            save_metas(&metas, self.directory())?;
+        }
+        // The segments opened by the readers of this index are kept.
+        let mut living_files: HashSet<PathBuf> = self
+            .list_all_segment_metas()
+            .iter()
+            .filter(|segment_meta| !damaged_segment_ids.contains(&segment_meta.id()))
+            .flat_map(SegmentMeta::list_files)
+            .collect();
+        living_files.extend(metas.files());
+        living_files.insert(META_FILEPATH.to_path_buf());
+        self.directory.clone().garbage_collect(|| living_files)?;
+        self.skipped_segments.write().unwrap().clear();
+        Ok(skipped_segments)
+    }
+
     /// Open a new index writer. Attempts to acquire a lockfile.
     ///
     /// The lockfile should be deleted on drop, but it is possible
@@ -401,14 +651,32 @@ impl Index {
     /// allocated for all indexing thread.
     /// Each thread will receive a budget of  `overall_heap_size_in_bytes / num_threads`.
     ///
+    /// With `IndexWriter::set_background_flush`, the budget of a thread is shared
+    /// by the segment it fills and the segment it flushes in the background.
+    ///
     /// # Errors
-    /// If the lockfile already exists, returns `Error::DirectoryLockBusy` or an `Error::IoError`.
+    /// If the lockfile already exists, returns `TantivyError::LockFailure`.
     /// If the heap size per thread is too small or too big, returns `TantivyError::InvalidArgument`
+    /// If the tokenizer of a text field is not registered, returns `TantivyError::SchemaError`
+    /// If damaged segments were skipped when opening the index, returns
+    /// `TantivyError::DataCorruption` until the index is repaired with `Index::repair`.
     pub fn writer_with_num_threads(
         &self,
         num_threads: usize,
         overall_heap_size_in_bytes: usize,
     ) -> crate::Result<IndexWriter> {
+        check_tokenizers(&self.schema, &self.tokenizers)?;
+        // The writer would commit the metas without the skipped segments,
+        // and delete their files.
+        if let Some(data_corruption) = self
+            .skipped_segments
+            .read()
+            .unwrap()
+            .iter()
+            .find_map(|skipped_segment| skipped_segment.reason.data_corruption())
+        {
+            return Err(TantivyError::DataCorruption(data_corruption));
+        }
         let directory_lock = self
             .directory
             .acquire_lock(&INDEX_WRITER_LOCK)
@@ -450,7 +718,7 @@ impl Index {
     /// between a given number of threads.
     ///
     /// # Errors
-    /// If the lockfile already exists, returns `Error::FileAlreadyExists`.
+    /// If the lockfile already exists, returns `TantivyError::LockFailure`.
     /// If the heap size per thread is too small or too big, returns `TantivyError::InvalidArgument`
     pub fn writer(&self, overall_heap_size_in_bytes: usize) -> crate::Result<IndexWriter> {
         let mut num_threads = std::cmp::min(num_cpus::get(), MAX_NUM_THREAD);
@@ -480,6 +748,14 @@ impl Index {
         self.schema.clone()
     }
 
+    /// Returns the version of the index schema.
+    ///
+    /// It starts at 0, and is incremented every time the schema is extended
+    /// with `Index::open_with_schema`.
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
     /// Returns the list of segments that are searchable
     pub fn searchable_segments(&self) -> crate::Result<Vec<Segment>> {
         Ok(self
@@ -496,9 +772,11 @@ impl Index {
 
     /// Creates a new segment.
     pub fn new_segment(&self) -> Segment {
-        let segment_meta = self
-            .inventory
-            .new_segment_meta(SegmentId::generate_random(), 0);
+        let segment_meta = self.inventory.new_segment_meta_with_settings(
+            SegmentId::generate_random(),
+            0,
+            &self.settings,
+        );
         self.segment(segment_meta)
     }
 
@@ -512,10 +790,51 @@ impl Index {
         &mut self.directory
     }
 
-    /// Reads the meta.json and returns the list of
-    /// `SegmentMeta` from the last commit.
+    /// Returns the list of `SegmentMeta` from the last commit, or from the
+    /// last soft commit if it is more recent.
+    ///
+    /// See [`IndexWriter::soft_commit`](./struct.IndexWriter.html#method.soft_commit).
     pub fn searchable_segment_metas(&self) -> crate::Result<Vec<SegmentMeta>> {
-        Ok(self.load_metas()?.segments)
+        Ok(self.searchable_metas()?.segments)
+    }
+
+    /// Returns the metas of the last commit, read from the meta.json file, unless
+    /// a more recent soft commit was published in memory.
+    ///
+    /// The damaged segments skipped when the index was opened are left out of the metas.
+    pub(crate) fn searchable_metas(&self) -> crate::Result<IndexMeta> {
+        let metas = self.load_metas()?;
+        let mut metas = {
+            let soft_committed_meta = self.soft_committed_meta.read().unwrap();
+            match soft_committed_meta.as_ref() {
+                Some(soft_meta) if soft_meta.opstamp > metas.opstamp => soft_meta.clone(),
+                _ => metas,
+            }
+        };
+        let damaged_segment_ids = self.damaged_segment_ids();
+        if !damaged_segment_ids.is_empty() {
+            metas
+                .segments
+                .retain(|segment_meta| !damaged_segment_ids.contains(&segment_meta.id()));
+        }
+        Ok(metas)
+    }
+
+    /// Returns the ids of the committed segments skipped because they cannot be read.
+    fn damaged_segment_ids(&self) -> HashSet<SegmentId> {
+        self.skipped_segments
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|skipped_segment| skipped_segment.is_damaged())
+            .map(|skipped_segment| skipped_segment.segment_id)
+            .collect()
+    }
+
+    /// Publishes the metas of a soft commit, or discards the last soft commit
+    /// if `None` is given.
+    pub(crate) fn set_soft_committed_meta(&self, index_meta: Option<IndexMeta>) {
+        *self.soft_committed_meta.write().unwrap() = index_meta;
     }
 
     /// Returns the list of segment ids that are searchable.
@@ -527,6 +846,74 @@ impl Index {
             .collect())
     }
 
+    /// Returns the statistics of the searchable segments of the index: their number of
+    /// documents, the size of their files, the number of terms of their fields...
+    ///
+    /// The segments are opened to compute the statistics, so this is not meant to be
+    /// called on each search.
+    pub fn statistics(&self) -> crate::Result<IndexStatistics> {
+        let segments = self
+            .searchable_segments()?
+            .iter()
+            .map(SegmentStatistics::compute)
+            .collect::<crate::Result<_>>()?;
+        Ok(IndexStatistics { segments })
+    }
+
+    /// Takes a consistent snapshot of the last commit of the index, to replicate
+    /// or back it up.
+    ///
+    /// The files of the commit are pinned while holding the meta lock, so that
+    /// the garbage collector does not remove them as long as the snapshot is alive.
+    /// See [`IndexSnapshot`](./struct.IndexSnapshot.html).
+    pub fn snapshot(&self) -> crate::Result<IndexSnapshot> {
+        let _meta_lock = self.directory.acquire_lock(&META_LOCK)?;
+        let meta_json = self.directory.atomic_read(&META_FILEPATH)?;
+        let meta = load_metas(self.directory(), &self.inventory)?;
+        let file_pin = self.directory.pin_files(meta.files().into_iter().collect());
+        let files = meta
+            .files()
+            .into_iter()
+            .map(|path| {
+                let file = self.directory.open_read_with_footer(&path)?;
+                Ok((path, file))
+            })
+            .collect::<crate::Result<BTreeMap<PathBuf, FileSlice>>>()?;
+        Ok(IndexSnapshot::new(meta, meta_json, files, file_pin))
+    }
+
+    /// Rebuilds an index in the directory `target` out of a backup.
+    ///
+    /// `source` is the directory the snapshot described by `manifest` was copied to
+    /// with [`IndexSnapshot::copy_to`](./struct.IndexSnapshot.html#method.copy_to).
+    /// The checksums of the files are verified before they are copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TantivyError::DataCorruption` if a file of the backup does not match
+    /// the manifest, and `TantivyError::InvalidArgument` if `target` already contains
+    /// an index.
+    pub fn restore<T: Into<Box<dyn Directory>>>(
+        manifest: &SnapshotManifest,
+        source: &dyn Directory,
+        target: T,
+    ) -> crate::Result<Index> {
+        let target = target.into();
+        if Index::exists(&*target)? {
+            return Err(TantivyError::InvalidArgument(
+                "Cannot restore a backup in a directory already containing an index".to_string(),
+            ));
+        }
+        manifest.restore_files(source, &*target)?;
+        let index = Index::open(target)?;
+        // Register the restored files, so that they are garbage collected
+        // once they are not used anymore.
+        for path in manifest.files.keys() {
+            index.directory().register_file_as_managed(path)?;
+        }
+        Ok(index)
+    }
+
     /// Returns the set of corrupted files
     pub fn validate_checksum(&self) -> crate::Result<HashSet<PathBuf>> {
         let managed_files = self.directory.list_managed_files();
@@ -556,15 +943,19 @@ impl fmt::Debug for Index {
 
 #[cfg(test)]
 mod tests {
+    use crate::collector::Count;
+    use crate::directory::error::OpenReadError;
+    use crate::query::QueryParser;
     use crate::schema::Field;
-    use crate::schema::{Schema, INDEXED, TEXT};
+    use crate::schema::{Facet, FacetOptions, Schema, FAST, INDEXED, TEXT};
     use crate::IndexReader;
     use crate::ReloadPolicy;
     use crate::{
         directory::{RamDirectory, WatchCallback},
         IndexSettings,
     };
-    use crate::{Directory, Index};
+    use crate::{Directory, Index, TantivyError};
+    use futures::executor::block_on;
 
     #[test]
     fn test_indexer_for_field() {
@@ -580,6 +971,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_analyze_field() -> crate::Result<()> {
+        use crate::schema::{IndexRecordOption, TextFieldIndexing, TextOptions};
+        let mut schema_builder = Schema::builder();
+        let text_indexing = TextFieldIndexing::default()
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+            .set_position_gap(5);
+        let title_field = schema_builder.add_text_field(
+            "title",
+            TextOptions::default().set_indexing_options(text_indexing),
+        );
+        let num_likes_field = schema_builder.add_u64_field("num_likes", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let titles = ["The Old Man", "and the Sea"];
+        let tokens = index.analyze_field(title_field, &titles)?;
+        let positions: Vec<(usize, usize, &str)> = tokens
+            .iter()
+            .map(|(text_ord, token)| {
+                (
+                    *text_ord,
+                    token.position,
+                    token.original_text(titles[*text_ord]),
+                )
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (0, 0, "The"),
+                (0, 1, "Old"),
+                (0, 2, "Man"),
+                (1, 8, "and"),
+                (1, 9, "the"),
+                (1, 10, "Sea")
+            ]
+        );
+        assert!(index.analyze_field(num_likes_field, &["1"]).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_index_exists() {
         let directory: Box<dyn Directory> = Box::new(RamDirectory::create());
@@ -650,6 +1081,248 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_open_missing_index() {
+        let directory = RamDirectory::create();
+        assert!(matches!(
+            Index::open(directory),
+            Err(TantivyError::OpenReadError(
+                OpenReadError::FileDoesNotExist(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_custom_tokenizer() -> crate::Result<()> {
+        use crate::query::TermQuery;
+        use crate::schema::{IndexRecordOption, TextFieldIndexing, TextOptions};
+        use crate::tokenizer::{
+            LowerCaser, NgramTokenizer, TextAnalyzer, Tokenizer, TokenizerManager,
+        };
+        use crate::Term;
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("ngram"));
+        let title_field = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        assert!(serde_json::to_string(&schema)?.contains(r#""tokenizer":"ngram""#));
+
+        let missing_tokenizer_err = |res: crate::Result<Index>| {
+            format!("{:?}", res.err().unwrap())
+                == r#"SchemaError("The tokenizer \"ngram\" of the field \"title\" is not registered.")"#
+        };
+        let directory = RamDirectory::create();
+        assert!(missing_tokenizer_err(
+            Index::builder()
+                .schema(schema.clone())
+                .tokenizers(TokenizerManager::default())
+                .create(directory.clone())
+        ));
+        assert!(!Index::exists(&directory)?);
+
+        let tokenizers = TokenizerManager::default();
+        // The tokenizer is only known at runtime.
+        let ngram_tokenizer: Box<dyn Tokenizer> = Box::new(NgramTokenizer::all_ngrams(2, 3));
+        tokenizers.register(
+            "ngram",
+            TextAnalyzer::from(ngram_tokenizer).filter(LowerCaser),
+        );
+        let index = Index::builder()
+            .schema(schema.clone())
+            .tokenizers(tokenizers.clone())
+            .create(directory.clone())?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title_field => "Tantivy"))?;
+        index_writer.commit()?;
+        drop(index_writer);
+
+        assert!(missing_tokenizer_err(Index::open_with_tokenizers(
+            directory.clone(),
+            TokenizerManager::default()
+        )));
+        let index = Index::open(directory.clone())?;
+        assert!(matches!(
+            index.tokenizer_for_field(title_field),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert!(matches!(
+            index.writer_for_tests(),
+            Err(TantivyError::SchemaError(_))
+        ));
+
+        let index = Index::builder()
+            .schema(schema)
+            .tokenizers(tokenizers)
+            .open_or_create(directory)?;
+        let query = TermQuery::new(
+            Term::from_field_text(title_field, "ant"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(index.reader()?.searcher().search(&query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_with_extended_schema() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(title => "old tale"))?;
+            index_writer.add_document(doc!(title => "old news"))?;
+            index_writer.commit()?;
+        }
+        assert_eq!(index.schema_version(), 0);
+
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let count = schema_builder.add_u64_field("count", FAST);
+        let tags = schema_builder.add_facet_field("tags", FacetOptions::default());
+        let blob = schema_builder.add_bytes_field("blob", FAST);
+        let extended_schema = schema_builder.build();
+        let index = Index::open_with_schema(directory.clone(), extended_schema.clone())?;
+        assert_eq!(index.schema(), extended_schema);
+        assert_eq!(index.schema_version(), 1);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(
+                title => "new tale",
+                body => "once upon a time",
+                count => 3u64,
+                tags => Facet::from("/genre/fairy"),
+                blob => b"abc".to_vec()
+            ))?;
+            index_writer.commit()?;
+        }
+        // the schema and its version are kept on commit.
+        let index = Index::open(directory.clone())?;
+        assert_eq!(index.schema(), extended_schema);
+        assert_eq!(index.schema_version(), 1);
+        // opening with the same schema does not bump the version.
+        let index = Index::open_with_schema(directory.clone(), extended_schema)?;
+        assert_eq!(index.schema_version(), 1);
+
+        let check_searcher = |reader: &IndexReader| -> crate::Result<()> {
+            let searcher = reader.searcher();
+            let query_parser = QueryParser::for_index(&index, vec![title, body]);
+            let count_docs = |query: &str| -> crate::Result<usize> {
+                searcher.search(&query_parser.parse_query(query)?, &Count)
+            };
+            assert_eq!(count_docs("tale")?, 2);
+            assert_eq!(count_docs("body:upon")?, 1);
+            assert_eq!(count_docs("tags:/genre")?, 1);
+            let mut counts = Vec::new();
+            for segment_reader in searcher.segment_readers() {
+                let count_reader = segment_reader.fast_fields().optional::<u64>(count)?;
+                let tags_reader = segment_reader.facet_reader(tags)?;
+                let blob_reader = segment_reader.fast_fields().bytes(blob)?;
+                let mut facet_ords = Vec::new();
+                for doc in 0..segment_reader.max_doc() {
+                    tags_reader.facet_ords(doc, &mut facet_ords);
+                    counts.push((
+                        count_reader.get(doc),
+                        facet_ords.len(),
+                        blob_reader.get_bytes(doc).len(),
+                    ));
+                }
+            }
+            counts.sort_unstable();
+            assert_eq!(counts, vec![(None, 0, 0), (None, 0, 0), (Some(3), 1, 3)]);
+            Ok(())
+        };
+        let reader = index.reader()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 2);
+        check_searcher(&reader)?;
+
+        // old and new segments can be merged together.
+        let mut index_writer = index.writer_for_tests()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 1);
+        check_searcher(&reader)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_with_field_alias() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(body => "old tale"))?;
+            index_writer.commit()?;
+        }
+
+        // `content` is the new name of the `body` field.
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_field_alias("content", body);
+        Index::open_with_schema(directory.clone(), schema_builder.build())?;
+
+        let index = Index::open(directory)?;
+        assert_eq!(index.schema_version(), 1);
+        assert_eq!(index.schema().get_field("content"), Some(body));
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(
+            index
+                .schema()
+                .parse_document(r#"{"content": "new tale"}"#)?,
+        )?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![]);
+        let count_docs = |query: &str| -> crate::Result<usize> {
+            searcher.search(&query_parser.parse_query(query)?, &Count)
+        };
+        assert_eq!(count_docs("content:tale")?, 2);
+        assert_eq!(count_docs("body:new")?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_with_incompatible_schema() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_u64_field("count", FAST);
+        Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+
+        // the type of an existing field cannot change.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_i64_field("count", FAST);
+        let res = Index::open_with_schema(directory.clone(), schema_builder.build());
+        assert!(matches!(res, Err(TantivyError::SchemaError(_))));
+
+        // fields cannot be removed.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        let res = Index::open_with_schema(directory.clone(), schema_builder.build());
+        assert!(matches!(res, Err(TantivyError::SchemaError(_))));
+
+        assert_eq!(Index::open(directory)?.schema_version(), 0);
+        Ok(())
+    }
+
     fn throw_away_schema() -> Schema {
         let mut schema_builder = Schema::builder();
         let _ = schema_builder.add_u64_field("num_likes", INDEXED);
@@ -670,6 +1343,71 @@ mod tests {
         test_index_on_commit_reload_policy_aux(field, &index, &reader)
     }
 
+    #[test]
+    fn test_index_reload_reuses_unchanged_segment_readers() -> crate::Result<()> {
+        let schema = throw_away_schema();
+        let field = schema.get_field("num_likes").unwrap();
+        let index = Index::create_in_ram(schema);
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut writer = index.writer_for_tests()?;
+        writer.add_document(doc!(field=>1u64))?;
+        writer.commit()?;
+        reader.reload()?;
+        let first_inv_idx = reader.searcher().segment_reader(0).inverted_index(field)?;
+        writer.add_document(doc!(field=>2u64))?;
+        writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let reused = searcher.segment_readers().iter().any(|segment_reader| {
+            std::sync::Arc::ptr_eq(
+                &segment_reader.inverted_index(field).unwrap(),
+                &first_inv_idx,
+            )
+        });
+        assert!(reused);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_reload_deletes_are_copy_on_write() -> crate::Result<()> {
+        let schema = throw_away_schema();
+        let field = schema.get_field("num_likes").unwrap();
+        let index = Index::create_in_ram(schema);
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut writer = index.writer_for_tests()?;
+        writer.add_document(doc!(field=>1u64))?;
+        writer.add_document(doc!(field=>2u64))?;
+        writer.add_document(doc!(field=>3u64))?;
+        writer.commit()?;
+        reader.reload()?;
+        let old_searcher = reader.searcher();
+        writer.delete_term(crate::Term::from_field_u64(field, 2u64));
+        writer.commit()?;
+        reader.reload()?;
+        let new_searcher = reader.searcher();
+        assert_eq!(old_searcher.num_docs(), 3);
+        assert!(old_searcher.segment_reader(0).alive_bitset().is_none());
+        assert_eq!(new_searcher.num_docs(), 2);
+        let new_segment_reader = new_searcher.segment_reader(0);
+        assert!(new_segment_reader.is_deleted(1));
+        assert_eq!(
+            new_segment_reader.segment_id(),
+            old_searcher.segment_reader(0).segment_id()
+        );
+        assert!(std::sync::Arc::ptr_eq(
+            &new_segment_reader.inverted_index(field)?,
+            &old_searcher.segment_reader(0).inverted_index(field)?
+        ));
+        Ok(())
+    }
+
     #[cfg(feature = "mmap")]
     mod mmap_specific {
 
@@ -678,6 +1416,28 @@ mod tests {
         use std::path::PathBuf;
         use tempfile::TempDir;
 
+        #[test]
+        fn test_create_in_dir_and_open_in_dir() -> crate::Result<()> {
+            let tempdir = TempDir::new()?;
+            let mut schema_builder = Schema::builder();
+            let num_likes = schema_builder.add_u64_field("num_likes", INDEXED);
+            let schema = schema_builder.build();
+            {
+                let index = Index::create_in_dir(tempdir.path(), schema.clone())?;
+                let mut index_writer = index.writer_for_tests()?;
+                index_writer.add_document(doc!(num_likes => 3u64))?;
+                index_writer.commit()?;
+            }
+            assert!(matches!(
+                Index::create_in_dir(tempdir.path(), schema.clone()),
+                Err(TantivyError::IndexAlreadyExists)
+            ));
+            let index = Index::open_in_dir(tempdir.path())?;
+            assert_eq!(index.schema(), schema);
+            assert_eq!(index.reader()?.searcher().num_docs(), 1);
+            Ok(())
+        }
+
         #[test]
         fn test_index_on_commit_reload_policy_mmap() -> crate::Result<()> {
             let schema = throw_away_schema();
@@ -813,4 +1573,93 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_open_skips_damaged_segments() -> crate::Result<()> {
+        use crate::core::{SegmentComponent, SkipReason, SkippedSegment};
+        use crate::indexer::NoMergePolicy;
+        use common::TerminatingWrite;
+        use std::io::Write;
+
+        let directory = RamDirectory::create();
+        let mut schema_builder = Schema::builder();
+        let num_likes = schema_builder.add_u64_field("num_likes", INDEXED);
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut writer = index.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0u64..3u64 {
+            writer.add_document(doc!(num_likes => i))?;
+            writer.commit()?;
+        }
+        writer.wait_merging_threads()?;
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 3);
+        assert!(Index::open_checked(directory.clone())?
+            .skipped_segments()
+            .is_empty());
+
+        // A corrupted file, a missing file, and a segment left by a crashed writer.
+        let corrupted_path = segment_metas[0].relative_path(SegmentComponent::Terms);
+        let mut corrupted_bytes = directory.open_read(&corrupted_path)?.read_bytes()?.to_vec();
+        corrupted_bytes[0] ^= 1;
+        directory.atomic_write(&corrupted_path, &corrupted_bytes)?;
+        let missing_path = segment_metas[1].relative_path(SegmentComponent::FieldNorms);
+        directory.delete(&missing_path).unwrap();
+        let mut uncommitted_segment = index.new_segment();
+        let mut write = uncommitted_segment.open_write(SegmentComponent::Postings)?;
+        write.write_all(b"partial")?;
+        write.terminate()?;
+
+        // The segments are only validated on request.
+        assert!(Index::open(directory.clone())?
+            .skipped_segments()
+            .is_empty());
+        let index = Index::open_checked(directory.clone())?;
+        let expected_skipped_segments = vec![
+            SkippedSegment {
+                segment_id: segment_metas[0].id(),
+                reason: SkipReason::CorruptedFile(corrupted_path),
+            },
+            SkippedSegment {
+                segment_id: segment_metas[1].id(),
+                reason: SkipReason::MissingFile(missing_path),
+            },
+            SkippedSegment {
+                segment_id: uncommitted_segment.id(),
+                reason: SkipReason::Uncommitted,
+            },
+        ];
+        assert_eq!(index.skipped_segments(), expected_skipped_segments);
+        assert_eq!(index.searchable_segment_ids()?, vec![segment_metas[2].id()]);
+        assert_eq!(index.reader()?.searcher().num_docs(), 1);
+        // The meta file is left untouched until the index is repaired.
+        assert_eq!(index.load_metas()?.segments.len(), 3);
+        assert!(matches!(
+            index.writer_for_tests(),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        assert!(directory.exists(&segment_metas[0].relative_path(SegmentComponent::Postings))?);
+
+        let removed_segments = index.repair()?;
+        assert_eq!(removed_segments.len(), 3);
+        assert!(index.skipped_segments().is_empty());
+        let uncommitted_path = uncommitted_segment.relative_path(SegmentComponent::Postings);
+        assert!(!directory.exists(&uncommitted_path)?);
+        let repaired_index = Index::open_checked(directory)?;
+        assert!(repaired_index.skipped_segments().is_empty());
+        assert_eq!(
+            repaired_index.load_metas()?.segments.len(),
+            1,
+            "the damaged segments must be removed from the meta file"
+        );
+        let mut writer = repaired_index.writer_for_tests()?;
+        writer.add_document(doc!(num_likes => 3u64))?;
+        writer.commit()?;
+        assert_eq!(repaired_index.reader()?.searcher().num_docs(), 2);
+        Ok(())
+    }
 }
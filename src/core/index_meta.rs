@@ -1,9 +1,12 @@
 use super::SegmentComponent;
+use crate::fieldnorm::MissingFieldNorm;
 use crate::schema::Schema;
 use crate::Opstamp;
 use crate::{core::SegmentId, store::Compressor};
 use census::{Inventory, TrackedObject};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeInclusive};
 use std::path::PathBuf;
 use std::{collections::HashSet, sync::atomic::AtomicBool};
 use std::{fmt, sync::Arc};
@@ -14,6 +17,64 @@ struct DeleteMeta {
     opstamp: Opstamp,
 }
 
+/// Bounds of the values of a field within a segment, recorded in the segment meta
+/// when the segment is written.
+///
+/// They make it possible for the `TermQuery` and the `RangeQuery` to skip the segments
+/// that cannot match without opening their term dictionary, e.g. the segments of the
+/// other days in a time-partitioned index.
+///
+/// The bounds include the values of the deleted documents, and the default value of
+/// the documents without any value in a single-valued fast field: they may be wider
+/// than the actual values of the segment, but never narrower.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldBounds {
+    /// Smallest and largest terms of the field in the segment, if the field is indexed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terms: Option<(Vec<u8>, Vec<u8>)>,
+    /// Smallest and largest `u64` representations of the values of the field in the
+    /// segment, if the field is a numeric fast field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<(u64, u64)>,
+}
+
+impl FieldBounds {
+    /// Returns false if the segment does not contain the term `term_bytes`.
+    pub fn may_contain_term(&self, term_bytes: &[u8]) -> bool {
+        match self.terms.as_ref() {
+            Some((first, last)) => first.as_slice() <= term_bytes && term_bytes <= last.as_slice(),
+            None => true,
+        }
+    }
+
+    /// Returns false if the segment does not contain any term within the bounds.
+    pub fn may_contain_term_within(&self, left: &Bound<Vec<u8>>, right: &Bound<Vec<u8>>) -> bool {
+        let (first, last) = match self.terms.as_ref() {
+            Some(terms) => terms,
+            None => return true,
+        };
+        let above_left = match left {
+            Bound::Included(left) => last >= left,
+            Bound::Excluded(left) => last > left,
+            Bound::Unbounded => true,
+        };
+        let below_right = match right {
+            Bound::Included(right) => first <= right,
+            Bound::Excluded(right) => first < right,
+            Bound::Unbounded => true,
+        };
+        above_left && below_right
+    }
+
+    /// Returns false if the segment does not contain any value within `value_range`.
+    pub fn may_contain_value_within(&self, value_range: &RangeInclusive<u64>) -> bool {
+        match self.values {
+            Some((min, max)) => *value_range.start() <= max && min <= *value_range.end(),
+            None => true,
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct SegmentMetaInventory {
     inventory: Inventory<InnerSegmentMeta>,
@@ -30,9 +91,23 @@ impl SegmentMetaInventory {
     }
 
     pub fn new_segment_meta(&self, segment_id: SegmentId, max_doc: u32) -> SegmentMeta {
+        self.new_segment_meta_with_settings(segment_id, max_doc, &IndexSettings::default())
+    }
+
+    /// Creates the meta of a new segment, written with the codec and the
+    /// static rank field of `settings`.
+    pub(crate) fn new_segment_meta_with_settings(
+        &self,
+        segment_id: SegmentId,
+        max_doc: u32,
+        settings: &IndexSettings,
+    ) -> SegmentMeta {
         let inner = InnerSegmentMeta {
             segment_id,
             max_doc,
+            codec: settings.codec.clone(),
+            static_rank_field: settings.static_rank_field.clone(),
+            field_bounds: BTreeMap::new(),
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: None,
         };
@@ -81,6 +156,31 @@ impl SegmentMeta {
         self.tracked.segment_id
     }
 
+    /// Returns the name of the codec the segment was written with.
+    ///
+    /// See [`Codec`](crate::Codec).
+    pub fn codec_name(&self) -> &str {
+        self.tracked
+            .codec
+            .as_deref()
+            .unwrap_or(super::DEFAULT_CODEC_NAME)
+    }
+
+    /// Returns the name of the static rank field of the segment, if the postings
+    /// of the segment record the maximum static rank of each of their blocks.
+    ///
+    /// See `IndexSettings::static_rank_field`.
+    pub fn static_rank_field(&self) -> Option<&str> {
+        self.tracked.static_rank_field.as_deref()
+    }
+
+    /// Returns the bounds of the values of the fields of the segment, by field name.
+    ///
+    /// Segments written before the bounds were recorded do not have any.
+    pub fn field_bounds(&self) -> &BTreeMap<String, FieldBounds> {
+        &self.tracked.field_bounds
+    }
+
     /// Removes the Component::TempStore from the alive list and
     /// therefore marks the temp docstore file to be deleted by
     /// the garbage collection.
@@ -123,6 +223,18 @@ impl SegmentMeta {
         }
     }
 
+    /// Returns the files of the segment once it is committed: unlike `list_files`,
+    /// the temporary doc store is excluded, and so is the delete file if the segment
+    /// has no deletes.
+    pub(crate) fn committed_files(&self) -> HashSet<PathBuf> {
+        let mut files = self.list_files();
+        files.remove(&self.relative_path(SegmentComponent::TempStore));
+        if !self.has_deletes() {
+            files.remove(&self.relative_path(SegmentComponent::Delete));
+        }
+        files
+    }
+
     /// Returns the relative path of a component of our segment.
     ///
     /// It just joins the segment id with the extension
@@ -138,6 +250,8 @@ impl SegmentMeta {
             SegmentComponent::FastFields => ".fast".to_string(),
             SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
             SegmentComponent::Delete => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
+            SegmentComponent::Vectors => ".vec".to_string(),
+            SegmentComponent::Features => ".feat".to_string(),
         });
         PathBuf::from(path)
     }
@@ -182,11 +296,31 @@ impl SegmentMeta {
             segment_id: inner_meta.segment_id,
             max_doc,
             deletes: None,
+            codec: inner_meta.codec.clone(),
+            static_rank_field: inner_meta.static_rank_field.clone(),
+            field_bounds: inner_meta.field_bounds.clone(),
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
         });
         SegmentMeta { tracked }
     }
 
+    /// Records the bounds of the values of the fields of the segment.
+    pub(crate) fn with_field_bounds(
+        self,
+        field_bounds: BTreeMap<String, FieldBounds>,
+    ) -> SegmentMeta {
+        let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
+            segment_id: inner_meta.segment_id,
+            max_doc: inner_meta.max_doc,
+            deletes: inner_meta.deletes.clone(),
+            codec: inner_meta.codec.clone(),
+            static_rank_field: inner_meta.static_rank_field.clone(),
+            field_bounds,
+            include_temp_doc_store: inner_meta.include_temp_doc_store.clone(),
+        });
+        SegmentMeta { tracked }
+    }
+
     #[doc(hidden)]
     pub fn with_delete_meta(self, num_deleted_docs: u32, opstamp: Opstamp) -> SegmentMeta {
         let delete_meta = DeleteMeta {
@@ -198,6 +332,9 @@ impl SegmentMeta {
             max_doc: inner_meta.max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: Some(delete_meta),
+            codec: inner_meta.codec.clone(),
+            static_rank_field: inner_meta.static_rank_field.clone(),
+            field_bounds: inner_meta.field_bounds.clone(),
         });
         SegmentMeta { tracked }
     }
@@ -208,6 +345,15 @@ struct InnerSegmentMeta {
     segment_id: SegmentId,
     max_doc: u32,
     deletes: Option<DeleteMeta>,
+    /// Name of the codec the segment was written with, or `None` for the default codec.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    codec: Option<String>,
+    /// Name of the static rank field whose block maxima are recorded in the postings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    static_rank_field: Option<String>,
+    /// Bounds of the values of the fields of the segment, by field name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    field_bounds: BTreeMap<String, FieldBounds>,
     /// If you want to avoid the SegmentComponent::TempStore file to be covered by
     /// garbage collection and deleted, set this to true. This is used during merge.
     #[serde(skip)]
@@ -230,7 +376,7 @@ impl InnerSegmentMeta {
 ///
 /// Contains settings which are applied on the whole
 /// index, like presort documents.
-#[derive(Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct IndexSettings {
     /// Sorts the documents by information
     /// provided in `IndexSortByField`
@@ -239,6 +385,118 @@ pub struct IndexSettings {
     /// The `Compressor` used to compress the doc store.
     #[serde(default)]
     pub docstore_compression: Compressor,
+    /// The size in bytes above which a block of the doc store is compressed
+    /// and flushed.
+    ///
+    /// Larger blocks compress better, but make the retrieval of a single
+    /// document more expensive.
+    #[serde(default = "default_docstore_blocksize")]
+    pub docstore_blocksize: usize,
+    /// If set, the blocks of the doc store of each segment are compressed with a dictionary
+    /// of at most this size in bytes (typically 100KB), trained on a sample of the documents of
+    /// the segment. The dictionary is persisted in the doc store.
+    ///
+    /// This significantly shrinks the doc store of indexes with many small, similar documents.
+    /// The documents are first written to a temporary doc store, and the dictionary is
+    /// trained when the segment is finalized. Doc stores with a dictionary are always
+    /// recompressed on merge.
+    ///
+    /// Only used with `Compressor::Zstd`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docstore_dictionary_size: Option<usize>,
+    /// Number of decompressed blocks of the doc store kept in the cache of each
+    /// segment reader. Defaults to 100 blocks if not set, and `Some(0)` disables the cache.
+    ///
+    /// Retrieval-heavy workloads, e.g. fetching the stored fields of many hits,
+    /// benefit from a larger cache. Its effectiveness can be monitored with
+    /// [`StoreReader::cache_stats`](crate::store::StoreReader::cache_stats).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docstore_cache_num_blocks: Option<usize>,
+    /// Name of the [`Codec`](crate::Codec) the new segments are written with.
+    /// Defaults to the `DefaultCodec`.
+    ///
+    /// Each segment records the codec it was written with, so changing the codec of an
+    /// index does not affect the readability of its existing segments. The codec must be
+    /// registered in the `CodecRegistry` of the index (see `Index::codecs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    /// Name of a single-valued numeric fast field holding a query-independent
+    /// static rank of the documents (e.g. a pagerank).
+    ///
+    /// The postings of the new segments then record the maximum static rank of the
+    /// documents of each of their blocks, so that a
+    /// [`StaticRankQuery`](crate::query::StaticRankQuery) blending the static rank in the
+    /// scores can skip the blocks that cannot reach the top documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub static_rank_field: Option<String>,
+    /// Name of a single-valued `u64` fast field holding the tenant of the documents,
+    /// for indexes shared by many tenants.
+    ///
+    /// The documents of each segment are then grouped by tenant, so that each tenant
+    /// owns a contiguous range of doc ids within a segment (see
+    /// [`SegmentReader::tenant_doc_range`](crate::SegmentReader::tenant_doc_range)), and
+    /// a [`TenantQuery`](crate::query::TenantQuery) only reads the documents of its tenant.
+    ///
+    /// The tenant field cannot be combined with `sort_by_field`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_field: Option<String>,
+    /// Defines how the documents without any token in a field are accounted for in the
+    /// average fieldnorm used by BM25. Defaults to `MissingFieldNorm::Zero`.
+    ///
+    /// With `MissingFieldNorm::Average`, the fields that are absent from many documents
+    /// are normalized against the average length of the documents that have them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_fieldnorm: Option<MissingFieldNorm>,
+}
+
+impl IndexSettings {
+    /// Returns how the documents without any token in a field are accounted
+    /// for in the average fieldnorm.
+    pub(crate) fn missing_fieldnorm(&self) -> MissingFieldNorm {
+        self.missing_fieldnorm.unwrap_or(MissingFieldNorm::Zero)
+    }
+
+    /// Returns the size of the dictionary to train for the doc store,
+    /// if the compressor supports dictionaries.
+    pub(crate) fn docstore_dictionary_size_for_compressor(&self) -> Option<usize> {
+        if self.docstore_compression == Compressor::Zstd {
+            self.docstore_dictionary_size
+        } else {
+            None
+        }
+    }
+
+    /// Returns the field and order the documents of the segments are sorted by:
+    /// `sort_by_field`, or the tenant field in ascending order.
+    pub(crate) fn segment_sort_by_field(&self) -> Option<IndexSortByField> {
+        if let Some(tenant_field) = self.tenant_field.as_ref() {
+            return Some(IndexSortByField {
+                field: tenant_field.clone(),
+                order: Order::Asc,
+            });
+        }
+        self.sort_by_field.clone()
+    }
+}
+
+fn default_docstore_blocksize() -> usize {
+    16_384
+}
+
+impl Default for IndexSettings {
+    fn default() -> Self {
+        IndexSettings {
+            sort_by_field: None,
+            docstore_compression: Compressor::default(),
+            docstore_blocksize: default_docstore_blocksize(),
+            docstore_dictionary_size: None,
+            docstore_cache_num_blocks: None,
+            codec: None,
+            static_rank_field: None,
+            tenant_field: None,
+            missing_fieldnorm: None,
+        }
+    }
 }
 /// Settings to presort the documents in an index
 ///
@@ -290,6 +548,10 @@ pub struct IndexMeta {
     pub schema: Schema,
     /// Opstamp associated to the last `commit` operation.
     pub opstamp: Opstamp,
+    /// Version of the schema, incremented every time the schema of the index is extended
+    /// with new fields (see `Index::open_with_schema`).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub schema_version: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Payload associated to the last commit.
     ///
@@ -299,6 +561,10 @@ pub struct IndexMeta {
     pub payload: Option<String>,
 }
 
+fn is_zero(val: &u32) -> bool {
+    *val == 0
+}
+
 #[derive(Deserialize)]
 struct UntrackedIndexMeta {
     pub segments: Vec<InnerSegmentMeta>,
@@ -306,6 +572,8 @@ struct UntrackedIndexMeta {
     pub index_settings: IndexSettings,
     pub schema: Schema,
     pub opstamp: Opstamp,
+    #[serde(default)]
+    pub schema_version: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<String>,
 }
@@ -321,6 +589,7 @@ impl UntrackedIndexMeta {
                 .collect::<Vec<SegmentMeta>>(),
             schema: self.schema,
             opstamp: self.opstamp,
+            schema_version: self.schema_version,
             payload: self.payload,
         }
     }
@@ -338,10 +607,22 @@ impl IndexMeta {
             segments: vec![],
             schema,
             opstamp: 0u64,
+            schema_version: 0,
             payload: None,
         }
     }
 
+    /// Returns the relative paths of the files of the segments of this commit.
+    ///
+    /// Together with `meta.json`, which is not part of the list, these are all of
+    /// the files required to open the index at this commit.
+    pub fn files(&self) -> HashSet<PathBuf> {
+        self.segments
+            .iter()
+            .flat_map(SegmentMeta::committed_files)
+            .collect()
+    }
+
     pub(crate) fn deserialize(
         meta_json: &str,
         inventory: &SegmentMetaInventory,
@@ -389,12 +670,13 @@ mod tests {
             segments: Vec::new(),
             schema,
             opstamp: 0u64,
+            schema_version: 0,
             payload: None,
         };
         let json = serde_json::ser::to_string(&index_metas).expect("serialization failed");
         assert_eq!(
             json,
-            r#"{"index_settings":{"sort_by_field":{"field":"text","order":"Asc"},"docstore_compression":"lz4"},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false}}],"opstamp":0}"#
+            r#"{"index_settings":{"sort_by_field":{"field":"text","order":"Asc"},"docstore_compression":"lz4","docstore_blocksize":16384},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false}}],"opstamp":0}"#
         );
     }
 }
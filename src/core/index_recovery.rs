@@ -0,0 +1,144 @@
+use crate::core::{IndexMeta, SegmentComponent, SegmentId, SegmentMeta};
+use crate::directory::error::OpenReadError;
+use crate::directory::ManagedDirectory;
+use crate::error::DataCorruption;
+use std::collections::{BTreeSet, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Reason why a segment is skipped when opening an index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A file of the segment referenced by the committed meta does not exist.
+    MissingFile(PathBuf),
+    /// A file of the segment referenced by the committed meta is truncated, or
+    /// its checksum does not match its content.
+    CorruptedFile(PathBuf),
+    /// The files of the segment exist, but the segment is not referenced by the
+    /// committed meta.
+    ///
+    /// Such segments are left behind by writers that crashed before committing them,
+    /// and are never searched. They may also belong to a writer working on the index
+    /// at the same time.
+    Uncommitted,
+}
+
+impl SkipReason {
+    /// Returns the corruption of the segment, or `None` if the segment is only
+    /// uncommitted.
+    pub(crate) fn data_corruption(&self) -> Option<DataCorruption> {
+        match self {
+            SkipReason::MissingFile(path) => Some(DataCorruption::new(
+                path.clone(),
+                "The file of the segment does not exist".to_string(),
+            )),
+            SkipReason::CorruptedFile(path) => Some(DataCorruption::new(
+                path.clone(),
+                "The file is truncated, or its checksum does not match its content".to_string(),
+            )),
+            SkipReason::Uncommitted => None,
+        }
+    }
+}
+
+/// A segment skipped when opening an index, as listed by
+/// [`Index::skipped_segments`](./struct.Index.html#method.skipped_segments).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedSegment {
+    /// Id of the skipped segment.
+    pub segment_id: SegmentId,
+    /// Reason why the segment was skipped.
+    pub reason: SkipReason,
+}
+
+impl SkippedSegment {
+    /// Returns true if the segment is referenced by the committed meta, but cannot
+    /// be read.
+    pub fn is_damaged(&self) -> bool {
+        self.reason != SkipReason::Uncommitted
+    }
+}
+
+/// Returns true if an error raised while reading a file means that the file is corrupted,
+/// rather than that it cannot be read at the moment.
+pub(crate) fn is_corruption_error(io_error: &io::Error) -> bool {
+    matches!(
+        io_error.kind(),
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Checks the files of a committed segment, returning the reason why it must be
+/// skipped if one of them is missing or corrupted.
+pub(crate) fn check_segment(
+    directory: &ManagedDirectory,
+    segment_meta: &SegmentMeta,
+) -> crate::Result<Option<SkipReason>> {
+    // the files are sorted for the reported file to be deterministic.
+    let files: BTreeSet<PathBuf> = segment_meta.committed_files().into_iter().collect();
+    for path in files {
+        match directory.validate_checksum(&path) {
+            Ok(true) => {}
+            Ok(false) => return Ok(Some(SkipReason::CorruptedFile(path))),
+            Err(OpenReadError::FileDoesNotExist(_)) => {
+                return Ok(Some(SkipReason::MissingFile(path)));
+            }
+            Err(OpenReadError::IoError { io_error, .. }) if is_corruption_error(&io_error) => {
+                return Ok(Some(SkipReason::CorruptedFile(path)));
+            }
+            // Other errors may be transient: the segment must not be removed by a repair.
+            Err(open_read_error) => return Err(open_read_error.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the id of the segment a file belongs to, or `None` if it is not a segment file.
+fn segment_id_of_file(path: &Path) -> Option<SegmentId> {
+    SegmentComponent::from_path(path)?;
+    let file_name = path.file_name()?.to_str()?;
+    let uuid_string = file_name.split('.').next()?;
+    SegmentId::from_uuid_string(uuid_string).ok()
+}
+
+/// Lists the segments of the directory that cannot be searched: the committed segments
+/// with a missing or corrupted file, and the segments with files that are not referenced
+/// by the committed meta.
+pub(crate) fn find_skipped_segments(
+    directory: &ManagedDirectory,
+    metas: &IndexMeta,
+) -> crate::Result<Vec<SkippedSegment>> {
+    let mut skipped_segments = Vec::new();
+    for segment_meta in &metas.segments {
+        if let Some(reason) = check_segment(directory, segment_meta)? {
+            warn!(
+                "Skipping segment {:?}: {:?}",
+                segment_meta.id().uuid_string(),
+                reason
+            );
+            skipped_segments.push(SkippedSegment {
+                segment_id: segment_meta.id(),
+                reason,
+            });
+        }
+    }
+    let committed_segment_ids: HashSet<SegmentId> =
+        metas.segments.iter().map(SegmentMeta::id).collect();
+    let uncommitted_segment_ids: BTreeSet<SegmentId> = directory
+        .list_managed_files()
+        .iter()
+        .filter_map(|path| segment_id_of_file(path))
+        .filter(|segment_id| !committed_segment_ids.contains(segment_id))
+        .collect();
+    for segment_id in uncommitted_segment_ids {
+        info!(
+            "Segment {:?} is not referenced by the committed meta",
+            segment_id.uuid_string()
+        );
+        skipped_segments.push(SkippedSegment {
+            segment_id,
+            reason: SkipReason::Uncommitted,
+        });
+    }
+    Ok(skipped_segments)
+}
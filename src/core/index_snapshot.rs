@@ -0,0 +1,362 @@
+use crate::core::{IndexMeta, META_FILEPATH};
+use crate::directory::{Directory, FilePin, FileSlice, Footer};
+use crate::error::DataCorruption;
+use crate::{Opstamp, TantivyError};
+use common::TerminatingWrite;
+use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Consistent snapshot of a commit of an index, used to replicate it.
+///
+/// A snapshot is taken with [`Index::snapshot`](./struct.Index.html#method.snapshot).
+/// It pins the commit: its files are kept open and protected from garbage collection
+/// until the snapshot is dropped, so that it remains readable and can be copied
+/// while the `IndexWriter` keeps on committing, merging and garbage collecting files.
+///
+/// A snapshot copied to a backup directory can later be restored with
+/// [`Index::restore`](./struct.Index.html#method.restore), given its
+/// [`SnapshotManifest`](./struct.SnapshotManifest.html).
+///
+/// Copying snapshots to a replica directory with
+/// [`IndexSnapshot::copy_to`](#method.copy_to) only ever moves the replica from a commit
+/// to a later commit: `meta.json` is written last, once all of the files it refers to
+/// have been copied. The replica can therefore be opened with `Index::open`, and an
+/// `IndexReader` with the `ReloadPolicy::OnCommit` reload policy follows these checkpoints.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::directory::RamDirectory;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(body => "hello"))?;
+/// index_writer.commit()?;
+///
+/// let replica_directory = RamDirectory::create();
+/// index.snapshot()?.copy_to(&replica_directory)?;
+/// let replica = Index::open(replica_directory)?;
+/// assert_eq!(replica.reader()?.searcher().num_docs(), 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct IndexSnapshot {
+    meta: IndexMeta,
+    meta_json: Vec<u8>,
+    // Files of the commit, footer included.
+    files: BTreeMap<PathBuf, FileSlice>,
+    _file_pin: FilePin,
+}
+
+impl IndexSnapshot {
+    pub(crate) fn new(
+        meta: IndexMeta,
+        meta_json: Vec<u8>,
+        files: BTreeMap<PathBuf, FileSlice>,
+        file_pin: FilePin,
+    ) -> IndexSnapshot {
+        IndexSnapshot {
+            meta,
+            meta_json,
+            files,
+            _file_pin: file_pin,
+        }
+    }
+
+    /// Returns the meta of the commit.
+    pub fn meta(&self) -> &IndexMeta {
+        &self.meta
+    }
+
+    /// Returns the relative paths of the files of the commit,
+    /// `meta.json` excluded.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns the checksum of each file of the commit, `meta.json` excluded.
+    ///
+    /// The checksums are read from the footers of the files, and can be compared
+    /// with those of the files of a replica to detect missing or corrupted files.
+    /// See also [`Index::validate_checksum`](./struct.Index.html#method.validate_checksum).
+    pub fn checksums(&self) -> crate::Result<BTreeMap<PathBuf, u32>> {
+        self.files
+            .iter()
+            .map(|(path, file)| {
+                let (footer, _) = Footer::extract_footer(file.clone())?;
+                Ok((path.clone(), footer.crc()))
+            })
+            .collect()
+    }
+
+    /// Returns the manifest of the snapshot, required to restore it from a backup.
+    pub fn manifest(&self) -> crate::Result<SnapshotManifest> {
+        let meta_json = String::from_utf8(self.meta_json.clone()).map_err(|_| {
+            DataCorruption::new(
+                META_FILEPATH.to_path_buf(),
+                "Meta file does not contain valid utf8 file.".to_string(),
+            )
+        })?;
+        Ok(SnapshotManifest {
+            opstamp: self.meta.opstamp,
+            files: self.checksums()?,
+            meta_json,
+        })
+    }
+
+    /// Copies the commit to a replica directory.
+    ///
+    /// Segment files already present in the directory are not copied again, as
+    /// files are never modified once written. `meta.json` is then atomically
+    /// replaced, so that readers of the replica never see a partially copied commit.
+    ///
+    /// Files of the previous commits of the replica are not deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TantivyError::InvalidArgument` if the replica is already at a later commit
+    /// than this snapshot.
+    pub fn copy_to(&self, directory: &dyn Directory) -> crate::Result<()> {
+        if directory.exists(&META_FILEPATH)? {
+            let replica_meta_json = directory.atomic_read(&META_FILEPATH)?;
+            let replica_opstamp = serde_json::from_slice::<serde_json::Value>(&replica_meta_json)
+                .ok()
+                .and_then(|replica_meta| replica_meta.get("opstamp")?.as_u64());
+            if let Some(replica_opstamp) = replica_opstamp {
+                if replica_opstamp > self.meta.opstamp {
+                    return Err(TantivyError::InvalidArgument(format!(
+                        "The replica is at opstamp {}, after the snapshot at opstamp {}",
+                        replica_opstamp, self.meta.opstamp
+                    )));
+                }
+            }
+        }
+        for (path, file) in &self.files {
+            if directory.exists(path)? {
+                continue;
+            }
+            let mut write = directory.open_write(path)?;
+            write.write_all(file.read_bytes()?.as_slice())?;
+            write.terminate()?;
+        }
+        directory.sync_directory()?;
+        directory.atomic_write(&META_FILEPATH, &self.meta_json)?;
+        Ok(())
+    }
+}
+
+/// Manifest of an [`IndexSnapshot`](./struct.IndexSnapshot.html): the list of the files
+/// of the commit with their checksums, and the content of its `meta.json` file.
+///
+/// The manifest is serializable, so that it can be stored alongside a backup.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Opstamp of the commit.
+    pub opstamp: Opstamp,
+    /// Relative paths of the files of the commit, `meta.json` excluded,
+    /// with their checksums.
+    pub files: BTreeMap<PathBuf, u32>,
+    /// Content of the `meta.json` file of the commit.
+    pub meta_json: String,
+}
+
+impl SnapshotManifest {
+    /// Copies the files of the manifest from `source` to `target`,
+    /// checking their integrity, and writes `meta.json` last.
+    pub(crate) fn restore_files(
+        &self,
+        source: &dyn Directory,
+        target: &dyn Directory,
+    ) -> crate::Result<()> {
+        for (path, &checksum) in &self.files {
+            let file = source.open_read(path)?;
+            let bytes = file.read_bytes()?;
+            let (footer, data) = Footer::extract_footer(file)?;
+            let mut hasher = Hasher::new();
+            hasher.update(data.read_bytes()?.as_slice());
+            if footer.crc() != checksum || hasher.finalize() != checksum {
+                return Err(DataCorruption::new(
+                    path.to_path_buf(),
+                    "The checksum of the file does not match the manifest.".to_string(),
+                )
+                .into());
+            }
+            let mut write = target.open_write(path)?;
+            write.write_all(bytes.as_slice())?;
+            write.terminate()?;
+        }
+        target.sync_directory()?;
+        target.atomic_write(&META_FILEPATH, self.meta_json.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotManifest;
+    use crate::collector::Count;
+    use crate::directory::{Directory, RamDirectory};
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexWriter, ReloadPolicy, TantivyError, Term};
+    use futures::executor::block_on;
+
+    fn add_docs(index_writer: &mut IndexWriter, num_docs: usize) -> crate::Result<()> {
+        let body = index_writer.index().schema().get_field("body").unwrap();
+        for _ in 0..num_docs {
+            index_writer.add_document(doc!(body => "hello"))?;
+        }
+        index_writer.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_files_and_checksums() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(body => "bye"))?;
+        add_docs(&mut index_writer, 3)?;
+        index_writer.delete_term(Term::from_field_text(body, "bye"));
+        add_docs(&mut index_writer, 2)?;
+
+        let snapshot = index.snapshot()?;
+        let files: Vec<_> = snapshot.files().map(|path| path.to_path_buf()).collect();
+        assert_eq!(
+            files
+                .iter()
+                .cloned()
+                .collect::<std::collections::HashSet<_>>(),
+            snapshot.meta().files()
+        );
+        // The first segment has deletes, the second one has none.
+        assert_eq!(
+            files
+                .iter()
+                .filter(|path| path.to_string_lossy().ends_with(".del"))
+                .count(),
+            1
+        );
+        let checksums = snapshot.checksums()?;
+        assert_eq!(checksums.len(), files.len());
+        for path in &files {
+            assert!(index.directory().validate_checksum(path)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_copy_to_replica() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        add_docs(&mut index_writer, 3)?;
+        let first_snapshot = index.snapshot()?;
+        // The writer keeps on running while the snapshot is alive.
+        add_docs(&mut index_writer, 2)?;
+        block_on(index_writer.merge(&index.searchable_segment_ids()?))?;
+        block_on(index_writer.garbage_collect_files())?;
+
+        let replica_directory = RamDirectory::create();
+        first_snapshot.copy_to(&replica_directory)?;
+        let replica = Index::open(replica_directory.clone())?;
+        let replica_reader = replica
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        assert_eq!(replica_reader.searcher().search(&AllQuery, &Count)?, 3);
+
+        let second_snapshot = index.snapshot()?;
+        second_snapshot.copy_to(&replica_directory)?;
+        replica_reader.reload()?;
+        assert_eq!(replica_reader.searcher().search(&AllQuery, &Count)?, 5);
+        assert!(replica.validate_checksum()?.is_empty());
+        for path in second_snapshot.files() {
+            assert!(replica_directory.exists(path)?);
+        }
+
+        // The replica never goes back to an earlier commit.
+        assert!(matches!(
+            first_snapshot.copy_to(&replica_directory),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        second_snapshot.copy_to(&replica_directory)?;
+        replica_reader.reload()?;
+        assert_eq!(replica_reader.searcher().search(&AllQuery, &Count)?, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_pins_files() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        add_docs(&mut index_writer, 1)?;
+        add_docs(&mut index_writer, 1)?;
+        let snapshot = index.snapshot()?;
+        let snapshot_files: Vec<_> = snapshot.files().map(|path| path.to_path_buf()).collect();
+        block_on(index_writer.merge(&index.searchable_segment_ids()?))?;
+        block_on(index_writer.garbage_collect_files())?;
+        for path in &snapshot_files {
+            assert!(index.directory().exists(path)?);
+        }
+        drop(snapshot);
+        block_on(index_writer.garbage_collect_files())?;
+        for path in &snapshot_files {
+            assert!(!index.directory().exists(path)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_backup_and_restore() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        add_docs(&mut index_writer, 3)?;
+        let snapshot = index.snapshot()?;
+        let backup_directory = RamDirectory::create();
+        snapshot.copy_to(&backup_directory)?;
+        let manifest = snapshot.manifest()?;
+        assert_eq!(manifest.opstamp, snapshot.meta().opstamp);
+        let manifest_json = serde_json::to_string(&manifest)?;
+        let manifest: SnapshotManifest = serde_json::from_str(&manifest_json)?;
+
+        let restored = Index::restore(&manifest, &backup_directory, RamDirectory::create())?;
+        assert_eq!(restored.reader()?.searcher().search(&AllQuery, &Count)?, 3);
+        assert!(restored.validate_checksum()?.is_empty());
+        let managed_files = restored.directory().list_managed_files();
+        assert!(manifest
+            .files
+            .keys()
+            .all(|path| managed_files.contains(path)));
+        assert!(matches!(
+            Index::restore(&manifest, &backup_directory, restored.directory().clone()),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+
+        let mut corrupted_manifest = manifest;
+        *corrupted_manifest.files.values_mut().next().unwrap() ^= 1;
+        assert!(matches!(
+            Index::restore(
+                &corrupted_manifest,
+                &backup_directory,
+                RamDirectory::create()
+            ),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        Ok(())
+    }
+}
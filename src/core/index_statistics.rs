@@ -0,0 +1,387 @@
+use crate::core::{InvertedIndexReader, Segment, SegmentComponent, SegmentReader};
+use crate::schema::{Field, Term};
+use crate::space_usage::ByteCount;
+use crate::{DocId, SegmentId};
+use common::HasLen;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::io;
+
+/// Number of longest posting lists kept by `InvertedIndexStatistics`.
+const NUM_LONGEST_POSTINGS: usize = 10;
+
+/// Statistics of the searchable segments of an index, as returned by
+/// [`Index::statistics`](./struct.Index.html#method.statistics).
+///
+/// They are meant for capacity planning and for tuning the merges, e.g.
+/// by looking for the segments with a large share of deleted documents.
+#[derive(Debug, Clone)]
+pub struct IndexStatistics {
+    /// Statistics of each searchable segment.
+    pub segments: Vec<SegmentStatistics>,
+}
+
+impl IndexStatistics {
+    /// Returns the number of alive documents of the index.
+    pub fn num_docs(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| u64::from(segment.num_docs))
+            .sum()
+    }
+
+    /// Returns the number of deleted documents, still present in the segments
+    /// until they are merged.
+    pub fn num_deleted_docs(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| u64::from(segment.num_deleted_docs))
+            .sum()
+    }
+
+    /// Returns the size of all of the files of the segments.
+    pub fn total_size(&self) -> ByteCount {
+        self.segments
+            .iter()
+            .map(SegmentStatistics::total_size)
+            .sum()
+    }
+
+    /// Estimates the size of the segment resulting from the merge of all of the segments.
+    pub fn estimated_merged_size(&self) -> ByteCount {
+        self.segments
+            .iter()
+            .map(SegmentStatistics::estimated_merged_size)
+            .sum()
+    }
+}
+
+/// Statistics of a segment.
+#[derive(Debug, Clone)]
+pub struct SegmentStatistics {
+    /// Id of the segment.
+    pub segment_id: SegmentId,
+    /// Number of alive documents.
+    pub num_docs: DocId,
+    /// Number of deleted documents.
+    pub num_deleted_docs: DocId,
+    /// Size of the file of each component of the segment.
+    /// Components without a file, e.g. the deletes of a segment without deletes, are omitted.
+    pub component_sizes: Vec<(SegmentComponent, ByteCount)>,
+    /// Statistics of each indexed field.
+    pub fields: Vec<FieldStatistics>,
+}
+
+impl SegmentStatistics {
+    pub(crate) fn compute(segment: &Segment) -> crate::Result<SegmentStatistics> {
+        let segment_reader = SegmentReader::open(segment)?;
+        let component_sizes = SegmentComponent::iterator()
+            .filter_map(|&component| {
+                let file = segment.open_read(component).ok()?;
+                Some((component, file.len()))
+            })
+            .collect();
+        let schema = segment.schema();
+        let mut fields = Vec::new();
+        for (field, field_entry) in schema.fields() {
+            if !field_entry.is_indexed() {
+                continue;
+            }
+            let inverted_index = segment_reader.inverted_index(field)?;
+            fields.push(FieldStatistics {
+                field,
+                num_terms: inverted_index.terms().num_terms() as u64,
+                total_num_tokens: inverted_index.total_num_tokens(),
+            });
+        }
+        Ok(SegmentStatistics {
+            segment_id: segment.id(),
+            num_docs: segment_reader.num_docs(),
+            num_deleted_docs: segment_reader.num_deleted_docs(),
+            component_sizes,
+            fields,
+        })
+    }
+
+    /// Returns the number of documents of the segment, including the deleted ones.
+    pub fn max_doc(&self) -> DocId {
+        self.num_docs + self.num_deleted_docs
+    }
+
+    /// Returns the size of the file of `component`, or `None` if the segment does not have it.
+    pub fn component_size(&self, component: SegmentComponent) -> Option<ByteCount> {
+        self.component_sizes
+            .iter()
+            .find(|(segment_component, _)| *segment_component == component)
+            .map(|(_, size)| *size)
+    }
+
+    /// Returns the size of all of the files of the segment.
+    pub fn total_size(&self) -> ByteCount {
+        self.component_sizes.iter().map(|(_, size)| *size).sum()
+    }
+
+    /// Estimates the size of the segment once merged, assuming that the deleted
+    /// documents take as much space as the alive ones.
+    ///
+    /// The deletes bitset is dropped by the merge.
+    pub fn estimated_merged_size(&self) -> ByteCount {
+        let max_doc = self.max_doc();
+        if max_doc == 0 {
+            return 0;
+        }
+        let size_without_deletes =
+            self.total_size() - self.component_size(SegmentComponent::Delete).unwrap_or(0);
+        (size_without_deletes as u64 * u64::from(self.num_docs) / u64::from(max_doc)) as ByteCount
+    }
+}
+
+/// Statistics of an indexed field within a segment.
+#[derive(Debug, Clone)]
+pub struct FieldStatistics {
+    /// The field.
+    pub field: Field,
+    /// Number of distinct terms of the field.
+    pub num_terms: u64,
+    /// Number of tokens indexed in the field, including the ones of the deleted documents.
+    pub total_num_tokens: u64,
+}
+
+/// Statistics of the posting lists of a field within a segment, as returned by
+/// [`SegmentReader::inverted_index_stats`](./struct.SegmentReader.html#method.inverted_index_stats).
+///
+/// They help diagnosing hot terms: the length of the posting list of a term is its
+/// document frequency, the number of documents of the segment containing the term.
+/// Deleted documents are included until their segment is merged.
+#[derive(Debug, Clone)]
+pub struct InvertedIndexStatistics {
+    /// The field.
+    pub field: Field,
+    /// Number of distinct terms of the field.
+    pub num_terms: u64,
+    /// Sum of the lengths of the posting lists of the field.
+    pub total_doc_freq: u64,
+    /// The ten terms with the longest posting lists, with their document frequency,
+    /// by decreasing document frequency.
+    pub longest_postings: Vec<(Term, u32)>,
+    // number of terms for each document frequency.
+    num_terms_per_doc_freq: BTreeMap<u32, u64>,
+}
+
+impl InvertedIndexStatistics {
+    pub(crate) fn compute(
+        field: Field,
+        inverted_index: &InvertedIndexReader,
+    ) -> io::Result<InvertedIndexStatistics> {
+        let mut num_terms_per_doc_freq: BTreeMap<u32, u64> = BTreeMap::new();
+        let mut longest_postings = BinaryHeap::with_capacity(NUM_LONGEST_POSTINGS + 1);
+        let mut term_stream = inverted_index.terms().stream()?;
+        while term_stream.try_advance()? {
+            let doc_freq = term_stream.value().doc_freq;
+            *num_terms_per_doc_freq.entry(doc_freq).or_default() += 1;
+            // min-heap on the document frequency. The terms are streamed in increasing
+            // order, so that among equal document frequencies, the smallest terms are kept.
+            if longest_postings.len() == NUM_LONGEST_POSTINGS {
+                if let Some(Reverse((min_doc_freq, _))) = longest_postings.peek() {
+                    if doc_freq <= *min_doc_freq {
+                        continue;
+                    }
+                }
+                longest_postings.pop();
+            }
+            longest_postings.push(Reverse((doc_freq, Reverse(term_stream.key().to_vec()))));
+        }
+        let longest_postings = longest_postings
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((doc_freq, Reverse(term_bytes)))| {
+                (Term::from_field_bytes(field, &term_bytes), doc_freq)
+            })
+            .collect();
+        Ok(InvertedIndexStatistics {
+            field,
+            num_terms: num_terms_per_doc_freq.values().sum(),
+            total_doc_freq: num_terms_per_doc_freq
+                .iter()
+                .map(|(&doc_freq, &num_terms)| u64::from(doc_freq) * num_terms)
+                .sum(),
+            longest_postings,
+            num_terms_per_doc_freq,
+        })
+    }
+
+    /// Returns the average length of the posting lists, or `0.0` if the field has no terms.
+    pub fn average_doc_freq(&self) -> f64 {
+        if self.num_terms == 0 {
+            return 0.0;
+        }
+        self.total_doc_freq as f64 / self.num_terms as f64
+    }
+
+    /// Returns the length of the posting lists at the given percentile,
+    /// or `0` if the field has no terms.
+    ///
+    /// `doc_freq_percentile(50.0)` is the median length of the posting lists,
+    /// and `doc_freq_percentile(100.0)` the length of the longest one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not within `[0.0, 100.0]`.
+    pub fn doc_freq_percentile(&self, percentile: f64) -> u32 {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "The percentile must be within [0, 100], got {}.",
+            percentile
+        );
+        // nearest-rank percentile
+        let rank = ((percentile / 100.0) * self.num_terms as f64)
+            .ceil()
+            .max(1.0) as u64;
+        let mut num_terms_seen = 0u64;
+        for (&doc_freq, &num_terms) in &self.num_terms_per_doc_freq {
+            num_terms_seen += num_terms;
+            if num_terms_seen >= rank {
+                return doc_freq;
+            }
+        }
+        0
+    }
+
+    /// Returns the histogram of the lengths of the posting lists.
+    ///
+    /// The buckets are the powers of two: each entry `(start, num_terms)` is the
+    /// number of terms with a document frequency within `[start, 2 * start)`.
+    /// Empty buckets are omitted.
+    pub fn doc_freq_histogram(&self) -> Vec<(u32, u64)> {
+        let mut histogram: Vec<(u32, u64)> = Vec::new();
+        for (&doc_freq, &num_terms) in &self.num_terms_per_doc_freq {
+            let bucket_start = if doc_freq == 0 {
+                0
+            } else {
+                1u32 << (31 - doc_freq.leading_zeros())
+            };
+            match histogram.last_mut() {
+                Some((last_bucket_start, bucket_num_terms))
+                    if *last_bucket_start == bucket_start =>
+                {
+                    *bucket_num_terms += num_terms;
+                }
+                _ => histogram.push((bucket_start, num_terms)),
+            }
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvertedIndexStatistics;
+    use crate::schema::{Schema, STORED, STRING, TEXT};
+    use crate::{doc, Index, SegmentComponent, Term};
+
+    #[test]
+    fn test_index_statistics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let stored_only = schema_builder.add_u64_field("stored_only", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let statistics = index.statistics()?;
+        assert!(statistics.segments.is_empty());
+        assert_eq!(statistics.estimated_merged_size(), 0);
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello happy tax payer", stored_only => 1u64))?;
+        index_writer.add_document(doc!(text => "hello world"))?;
+        index_writer.add_document(doc!(text => "goodbye world"))?;
+        index_writer.add_document(doc!(text => "tax payer"))?;
+        index_writer.commit()?;
+        let statistics = index.statistics()?;
+        assert_eq!(statistics.segments.len(), 1);
+        let segment_statistics = &statistics.segments[0];
+        assert_eq!(segment_statistics.num_docs, 4);
+        assert_eq!(segment_statistics.num_deleted_docs, 0);
+        assert!(
+            segment_statistics
+                .component_size(SegmentComponent::Store)
+                .unwrap()
+                > 0
+        );
+        assert_eq!(
+            segment_statistics.component_size(SegmentComponent::Delete),
+            None
+        );
+        assert_eq!(segment_statistics.fields.len(), 1);
+        assert_eq!(segment_statistics.fields[0].field, text);
+        assert_eq!(segment_statistics.fields[0].num_terms, 6);
+        assert_eq!(segment_statistics.fields[0].total_num_tokens, 10);
+        assert_eq!(statistics.estimated_merged_size(), statistics.total_size());
+
+        index_writer.delete_term(Term::from_field_text(text, "tax"));
+        index_writer.commit()?;
+        let statistics = index.statistics()?;
+        assert_eq!(statistics.num_docs(), 2);
+        assert_eq!(statistics.num_deleted_docs(), 2);
+        let segment_statistics = &statistics.segments[0];
+        assert!(segment_statistics
+            .component_size(SegmentComponent::Delete)
+            .is_some());
+        assert!(statistics.estimated_merged_size() < statistics.total_size() / 2);
+        assert!(statistics.estimated_merged_size() > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inverted_index_stats() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // tag `i` appears in `i` documents, for i in 1..=20.
+        for i in 1..=20u32 {
+            for _ in 0..i {
+                index_writer.add_document(doc!(tag => format!("tag{:02}", i)))?;
+            }
+        }
+        for _ in 0..20 {
+            index_writer.add_document(doc!(tag => "tag20bis"))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let stats = searcher.segment_reader(0).inverted_index_stats(tag)?;
+        assert_eq!(stats.field, tag);
+        assert_eq!(stats.num_terms, 21);
+        assert_eq!(stats.total_doc_freq, 210 + 20);
+        assert!((stats.average_doc_freq() - 230.0 / 21.0).abs() < 1e-9);
+        assert_eq!(stats.doc_freq_percentile(0.0), 1);
+        assert_eq!(stats.doc_freq_percentile(50.0), 11);
+        assert_eq!(stats.doc_freq_percentile(100.0), 20);
+        assert_eq!(
+            stats.doc_freq_histogram(),
+            vec![(1, 1), (2, 2), (4, 4), (8, 8), (16, 6)]
+        );
+        assert_eq!(stats.longest_postings.len(), super::NUM_LONGEST_POSTINGS);
+        assert_eq!(
+            &stats.longest_postings[..3],
+            &[
+                (Term::from_field_text(tag, "tag20"), 20),
+                (Term::from_field_text(tag, "tag20bis"), 20),
+                (Term::from_field_text(tag, "tag19"), 19),
+            ]
+        );
+        assert_eq!(
+            stats.longest_postings.last(),
+            Some(&(Term::from_field_text(tag, "tag12"), 12))
+        );
+
+        let empty_field_stats = InvertedIndexStatistics::compute(
+            tag,
+            &crate::InvertedIndexReader::empty(crate::schema::IndexRecordOption::Basic),
+        )?;
+        assert_eq!(empty_field_stats.num_terms, 0);
+        assert_eq!(empty_field_stats.average_doc_freq(), 0.0);
+        assert_eq!(empty_field_stats.doc_freq_percentile(50.0), 0);
+        assert!(empty_field_stats.longest_postings.is_empty());
+        assert!(empty_field_stats.doc_freq_histogram().is_empty());
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 use std::io;
+use std::sync::Arc;
 
 use crate::directory::FileSlice;
-use crate::positions::PositionReader;
+use crate::positions::{PayloadReader, PositionReader};
+use crate::postings::compression::default_postings_codec;
+use crate::postings::PostingsCodec;
 use crate::postings::TermInfo;
 use crate::postings::{BlockSegmentPostings, SegmentPostings};
 use crate::schema::IndexRecordOption;
@@ -21,22 +24,33 @@ use common::BinarySerializable;
 ///
 ///
 /// `InvertedIndexReader` are created by calling
-/// the `SegmentReader`'s [`.inverted_index(...)`] method
+/// the `SegmentReader`'s [`.inverted_index(...)`] method, or
+/// directly from the files of a segment with [`InvertedIndexReader::new`].
 pub struct InvertedIndexReader {
     termdict: TermDictionary,
     postings_file_slice: FileSlice,
     positions_file_slice: FileSlice,
     record_option: IndexRecordOption,
+    has_payloads: bool,
+    has_static_ranks: bool,
+    postings_codec: Arc<dyn PostingsCodec>,
     total_num_tokens: u64,
 }
 
 impl InvertedIndexReader {
+    /// Opens the inverted index of a field from the slices of the segment files
+    /// holding it, in the format of [`INDEX_FORMAT_VERSION`](../constant.INDEX_FORMAT_VERSION.html).
+    ///
+    /// The term dictionary, postings and positions of a field are read from the
+    /// `CompositeFile`s of the `Terms`, `Postings` and `Positions` segment components.
+    /// `record_option` and `has_payloads` are given by the field's entry in the schema.
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))] // for symmetry
-    pub(crate) fn new(
+    pub fn new(
         termdict: TermDictionary,
         postings_file_slice: FileSlice,
         positions_file_slice: FileSlice,
         record_option: IndexRecordOption,
+        has_payloads: bool,
     ) -> io::Result<InvertedIndexReader> {
         let (total_num_tokens_slice, postings_body) = postings_file_slice.split(8);
         let total_num_tokens = u64::deserialize(&mut total_num_tokens_slice.read_bytes()?)?;
@@ -45,10 +59,32 @@ impl InvertedIndexReader {
             postings_file_slice: postings_body,
             positions_file_slice,
             record_option,
+            has_payloads,
+            has_static_ranks: false,
+            postings_codec: default_postings_codec(),
             total_num_tokens,
         })
     }
 
+    /// Marks the postings as recording the maximum static rank of each of their blocks.
+    ///
+    /// See `IndexSettings::static_rank_field`.
+    pub(crate) fn with_static_ranks(mut self) -> InvertedIndexReader {
+        self.has_static_ranks = true;
+        self
+    }
+
+    /// Sets the `PostingsCodec` the blocks of the posting lists were encoded with.
+    ///
+    /// Defaults to the `BitpackedPostingsCodec`.
+    pub fn with_postings_codec(
+        mut self,
+        postings_codec: Arc<dyn PostingsCodec>,
+    ) -> InvertedIndexReader {
+        self.postings_codec = postings_codec;
+        self
+    }
+
     /// Creates an empty `InvertedIndexReader` object, which
     /// contains no terms at all.
     pub fn empty(record_option: IndexRecordOption) -> InvertedIndexReader {
@@ -57,6 +93,9 @@ impl InvertedIndexReader {
             postings_file_slice: FileSlice::empty(),
             positions_file_slice: FileSlice::empty(),
             record_option,
+            has_payloads: false,
+            has_static_ranks: false,
+            postings_codec: default_postings_codec(),
             total_num_tokens: 0u64,
         }
     }
@@ -89,7 +128,11 @@ impl InvertedIndexReader {
         let postings_slice = self
             .postings_file_slice
             .slice(term_info.postings_range.clone());
-        block_postings.reset(term_info.doc_freq, postings_slice.read_bytes()?);
+        block_postings.reset(
+            term_info.doc_freq,
+            postings_slice.read_bytes()?,
+            &self.postings_codec,
+        );
         Ok(())
     }
 
@@ -124,6 +167,8 @@ impl InvertedIndexReader {
             postings_data,
             self.record_option,
             requested_option,
+            self.has_static_ranks,
+            self.postings_codec.clone(),
         )
     }
 
@@ -136,22 +181,30 @@ impl InvertedIndexReader {
         term_info: &TermInfo,
         option: IndexRecordOption,
     ) -> io::Result<SegmentPostings> {
+        let option = option.downgrade(self.record_option);
         let block_postings = self.read_block_postings_from_terminfo(term_info, option)?;
+        let mut payload_reader = None;
         let position_reader = {
             if option.has_positions() {
-                let positions_data = self
+                let mut positions_data = self
                     .positions_file_slice
                     .read_bytes_slice(term_info.positions_range.clone())?;
+                if self.has_payloads {
+                    payload_reader = Some(PayloadReader::open(&mut positions_data)?);
+                }
                 let position_reader = PositionReader::open(positions_data)?;
                 Some(position_reader)
             } else {
                 None
             }
         };
-        Ok(SegmentPostings::from_block_postings(
-            block_postings,
-            position_reader,
-        ))
+        let segment_postings =
+            SegmentPostings::from_block_postings(block_postings, position_reader);
+        Ok(if let Some(payload_reader) = payload_reader {
+            segment_postings.with_payload_reader(payload_reader)
+        } else {
+            segment_postings
+        })
     }
 
     /// Returns the total number of tokens recorded for all documents
@@ -197,4 +250,13 @@ impl InvertedIndexReader {
             .map(|term_info| term_info.doc_freq)
             .unwrap_or(0u32))
     }
+
+    /// Returns the total number of occurrences of the term
+    /// in all documents (including deleted documents).
+    pub fn total_term_freq(&self, term: &Term) -> io::Result<u64> {
+        Ok(self
+            .get_term_info(term)?
+            .map(|term_info| term_info.total_term_freq)
+            .unwrap_or(0u64))
+    }
 }
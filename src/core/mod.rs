@@ -1,24 +1,50 @@
+mod codec;
 mod executor;
+mod global_ordinals;
 pub mod index;
 mod index_meta;
+mod index_recovery;
+mod index_snapshot;
+mod index_statistics;
 mod inverted_index_reader;
+mod multi_searcher;
+mod search_permit;
+mod search_statistics;
 pub mod searcher;
 mod segment;
 mod segment_component;
 mod segment_id;
 mod segment_reader;
+mod warmup;
 
+pub use self::codec::{
+    Codec, CodecRegistry, DefaultCodec, ExternalStoreCodec, SSTableCodec, DEFAULT_CODEC_NAME,
+    SSTABLE_CODEC_NAME,
+};
 pub use self::executor::Executor;
+pub use self::global_ordinals::GlobalOrdinals;
+pub(crate) use self::global_ordinals::GlobalOrdinalsCache;
 pub use self::index::{Index, IndexBuilder};
 pub use self::index_meta::{
-    IndexMeta, IndexSettings, IndexSortByField, Order, SegmentMeta, SegmentMetaInventory,
+    FieldBounds, IndexMeta, IndexSettings, IndexSortByField, Order, SegmentMeta,
+    SegmentMetaInventory,
+};
+pub(crate) use self::index_recovery::{check_segment, find_skipped_segments};
+pub use self::index_recovery::{SkipReason, SkippedSegment};
+pub use self::index_snapshot::{IndexSnapshot, SnapshotManifest};
+pub use self::index_statistics::{
+    FieldStatistics, IndexStatistics, InvertedIndexStatistics, SegmentStatistics,
 };
 pub use self::inverted_index_reader::InvertedIndexReader;
+pub use self::multi_searcher::MultiSearcher;
+pub use self::search_permit::SearchPermit;
+pub use self::search_statistics::{SearchStatistics, SegmentSearchStatistics};
 pub use self::searcher::Searcher;
 pub use self::segment::Segment;
 pub use self::segment_component::SegmentComponent;
 pub use self::segment_id::SegmentId;
 pub use self::segment_reader::SegmentReader;
+pub use self::warmup::{HotCache, WarmupSpec};
 
 use once_cell::sync::Lazy;
 use std::path::Path;
@@ -0,0 +1,269 @@
+use crate::collector::Collector;
+use crate::core::{Executor, Searcher};
+use crate::query::Query;
+use crate::schema::{Document, Schema, Term};
+use crate::{DocAddress, SegmentReader, TantivyError};
+use std::fmt;
+
+/// Searches several independent indexes (shards) as if they were a single one.
+///
+/// All of the shards must share the same schema. Queries are run in two phases:
+/// the `Weight` of the query is first built out of the statistics of all of the shards
+/// (number of documents, document frequencies, number of tokens...), and it then scores
+/// the segments of every shard. A document therefore gets the same score as it would get
+/// if all of the shards were merged into a single index, and the top documents of the shards
+/// can be compared and merged by the usual collectors.
+///
+/// The segments of the shards are numbered one shard after the other, so that the
+/// `DocAddress`es returned by the collectors can be passed to
+/// [`MultiSearcher::doc`](#method.doc), or mapped back to the shard hosting the document
+/// with [`MultiSearcher::shard_doc_address`](#method.shard_doc_address).
+///
+/// The tokenizers and the search executor of the first shard are used for all of the shards.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::{Count, TopDocs};
+/// use tantivy::query::TermQuery;
+/// use tantivy::schema::{IndexRecordOption, Schema, TEXT};
+/// use tantivy::{doc, Index, MultiSearcher, Term};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let schema = schema_builder.build();
+///
+/// let mut shards = Vec::new();
+/// for shard_body in &["hello world", "hello hello tantivy"] {
+///     let index = Index::create_in_ram(schema.clone());
+///     let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+///     index_writer.add_document(doc!(body => *shard_body))?;
+///     index_writer.commit()?;
+///     shards.push(index.reader()?.searcher());
+/// }
+/// let multi_searcher = MultiSearcher::new(&[&*shards[0], &*shards[1]])?;
+/// let query = TermQuery::new(
+///     Term::from_field_text(body, "hello"),
+///     IndexRecordOption::WithFreqs,
+/// );
+/// assert_eq!(multi_searcher.search(&query, &Count)?, 2);
+/// let top_docs = multi_searcher.search(&query, &TopDocs::with_limit(1))?;
+/// let (shard_ord, _doc_address) = multi_searcher.shard_doc_address(top_docs[0].1);
+/// assert_eq!(shard_ord, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct MultiSearcher {
+    searcher: Searcher,
+    // `shard_segment_offsets[shard_ord]` is the ordinal of the first segment of the shard.
+    shard_segment_offsets: Vec<u32>,
+}
+
+impl MultiSearcher {
+    /// Creates a `MultiSearcher` over the given searchers, one per shard.
+    ///
+    /// Returns an error if no searcher is given, or if the searchers do not share
+    /// the same schema.
+    pub fn new(searchers: &[&Searcher]) -> crate::Result<MultiSearcher> {
+        let first_searcher = searchers.first().ok_or_else(|| {
+            TantivyError::InvalidArgument("A MultiSearcher requires at least one shard".to_string())
+        })?;
+        let schema = first_searcher.schema().clone();
+        let mut segment_readers: Vec<SegmentReader> = Vec::new();
+        let mut shard_segment_offsets = Vec::with_capacity(searchers.len());
+        for (shard_ord, searcher) in searchers.iter().enumerate() {
+            if searcher.schema() != &schema {
+                return Err(TantivyError::SchemaError(format!(
+                    "The schema of shard {} differs from the schema of the first shard",
+                    shard_ord
+                )));
+            }
+            shard_segment_offsets.push(segment_readers.len() as u32);
+            segment_readers.extend(searcher.segment_readers().iter().cloned());
+        }
+        let searcher = Searcher::new(schema, first_searcher.index().clone(), segment_readers)?;
+        Ok(MultiSearcher {
+            searcher,
+            shard_segment_offsets,
+        })
+    }
+
+    /// Returns the number of shards.
+    pub fn num_shards(&self) -> usize {
+        self.shard_segment_offsets.len()
+    }
+
+    /// Returns the schema shared by all of the shards.
+    pub fn schema(&self) -> &Schema {
+        self.searcher.schema()
+    }
+
+    /// Returns the `Searcher` holding the segments of all of the shards.
+    pub fn searcher(&self) -> &Searcher {
+        &self.searcher
+    }
+
+    /// Returns the overall number of documents in the shards.
+    pub fn num_docs(&self) -> u64 {
+        self.searcher.num_docs()
+    }
+
+    /// Returns the overall number of documents containing the given term
+    /// in the shards.
+    pub fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        self.searcher.doc_freq(term)
+    }
+
+    /// Fetches a document from the shard hosting it.
+    pub fn doc(&self, doc_address: DocAddress) -> crate::Result<Document> {
+        self.searcher.doc(doc_address)
+    }
+
+    /// Maps a `DocAddress` returned by this searcher to the ordinal of the
+    /// shard hosting the document, and to its `DocAddress` within the `Searcher`
+    /// of this shard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the segment of the `DocAddress` does not belong to this searcher.
+    pub fn shard_doc_address(&self, doc_address: DocAddress) -> (usize, DocAddress) {
+        assert!(
+            (doc_address.segment_ord as usize) < self.searcher.segment_readers().len(),
+            "Segment ordinal {} is out of bounds",
+            doc_address.segment_ord
+        );
+        let shard_ord = self
+            .shard_segment_offsets
+            .iter()
+            .rposition(|&offset| offset <= doc_address.segment_ord)
+            .expect("The first shard starts at segment 0");
+        let shard_doc_address = DocAddress::new(
+            doc_address.segment_ord - self.shard_segment_offsets[shard_ord],
+            doc_address.doc_id,
+        );
+        (shard_ord, shard_doc_address)
+    }
+
+    /// Runs a query on all of the shards, with statistics computed over all of the shards.
+    ///
+    /// See [`Searcher::search`](./struct.Searcher.html#method.search).
+    pub fn search<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<C::Fruit> {
+        self.searcher.search(query, collector)
+    }
+
+    /// Same as [`search(...)`](#method.search) but multithreaded.
+    ///
+    /// See [`Searcher::search_with_executor`](./struct.Searcher.html#method.search_with_executor).
+    pub fn search_with_executor<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        executor: &Executor,
+    ) -> crate::Result<C::Fruit> {
+        self.searcher
+            .search_with_executor(query, collector, executor)
+    }
+}
+
+impl fmt::Debug for MultiSearcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MultiSearcher({} shards, {:?})",
+            self.num_shards(),
+            self.searcher
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiSearcher;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, STORED, TEXT};
+    use crate::{DocAddress, Index, LeasedItem, Searcher, TantivyError, Term};
+
+    fn shard_searcher(schema: &Schema, bodies: &[&[&str]]) -> crate::Result<LeasedItem<Searcher>> {
+        let body = schema.get_field("body").unwrap();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer = index.writer_for_tests()?;
+        for segment_bodies in bodies {
+            for segment_body in segment_bodies.iter() {
+                index_writer.add_document(doc!(body => *segment_body))?;
+            }
+            index_writer.commit()?;
+        }
+        Ok(index.reader()?.searcher())
+    }
+
+    #[test]
+    fn test_multi_searcher_global_statistics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT | STORED);
+        let schema = schema_builder.build();
+        let shard_a = shard_searcher(&schema, &[&["a b", "b"], &["a c"]])?;
+        let shard_b = shard_searcher(&schema, &[&["c", "b c", "a a"]])?;
+        let single_index = shard_searcher(&schema, &[&["a b", "b", "a c", "c", "b c", "a a"]])?;
+
+        let multi_searcher = MultiSearcher::new(&[&*shard_a, &*shard_b])?;
+        assert_eq!(multi_searcher.num_shards(), 2);
+        assert_eq!(multi_searcher.num_docs(), 6);
+        let term_a = Term::from_field_text(body, "a");
+        assert_eq!(multi_searcher.doc_freq(&term_a)?, 3);
+
+        let query = TermQuery::new(term_a, IndexRecordOption::WithFreqs);
+        assert_eq!(multi_searcher.search(&query, &Count)?, 3);
+        let top_docs = multi_searcher.search(&query, &TopDocs::with_limit(3))?;
+        let single_index_top_docs = single_index.search(&query, &TopDocs::with_limit(3))?;
+        let scores = |top_docs: &[(f32, DocAddress)]| -> Vec<f32> {
+            top_docs.iter().map(|(score, _)| *score).collect()
+        };
+        assert_eq!(scores(&top_docs), scores(&single_index_top_docs));
+
+        // "a a" is the best match, and lives in the only segment of the second shard.
+        let (shard_ord, shard_doc_address) = multi_searcher.shard_doc_address(top_docs[0].1);
+        assert_eq!(shard_ord, 1);
+        assert_eq!(shard_doc_address, DocAddress::new(0, 2));
+        let doc = multi_searcher.doc(top_docs[0].1)?;
+        assert_eq!(
+            doc.get_first(body).and_then(|value| value.text()),
+            Some("a a")
+        );
+        assert_eq!(
+            shard_b
+                .doc(shard_doc_address)?
+                .get_first(body)
+                .and_then(|value| value.text()),
+            Some("a a")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_searcher_invalid_shards() -> crate::Result<()> {
+        assert!(matches!(
+            MultiSearcher::new(&[]),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let mut other_schema_builder = Schema::builder();
+        other_schema_builder.add_text_field("body", TEXT);
+        other_schema_builder.add_text_field("title", TEXT);
+        let other_schema = other_schema_builder.build();
+        let shard_a = shard_searcher(&schema, &[&["a"]])?;
+        let shard_b = shard_searcher(&other_schema, &[&["a"]])?;
+        assert!(matches!(
+            MultiSearcher::new(&[&*shard_a, &*shard_b]),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
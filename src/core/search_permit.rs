@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use crate::core::SegmentReader;
+use crate::query::does_not_match;
+use crate::query::{Explanation, Scorer, Weight};
+use crate::{DocId, DocSet, Score, TERMINATED};
+use common::BitSet;
+
+/// Restricts the documents a search is allowed to see, segment by segment.
+///
+/// A permit is passed to
+/// [`Searcher::search_with_permit`](./struct.Searcher.html#method.search_with_permit),
+/// and the documents it does not allow are filtered out of the results of any
+/// query, before reaching the collector. It is typically used to implement
+/// document level security, e.g. with a bitset derived from a fast field holding
+/// the access control list of each document.
+///
+/// It is implemented for the closures taking a `SegmentReader` and returning
+/// its allowed documents.
+pub trait SearchPermit: Send + Sync {
+    /// Returns the bitset of the documents of the segment the search is allowed to see.
+    ///
+    /// The bitset is expected to have been created with a max value of `segment_reader.max_doc()`.
+    /// It is returned in an `Arc` so that it can be cached by the application.
+    fn allowed_docs(&self, segment_reader: &SegmentReader) -> crate::Result<Arc<BitSet>>;
+}
+
+impl<F> SearchPermit for F
+where
+    F: Fn(&SegmentReader) -> crate::Result<Arc<BitSet>> + Send + Sync,
+{
+    fn allowed_docs(&self, segment_reader: &SegmentReader) -> crate::Result<Arc<BitSet>> {
+        (self)(segment_reader)
+    }
+}
+
+/// Wraps the weight of a query so that it only matches the documents
+/// allowed by a `SearchPermit`, within a given segment.
+pub(crate) struct PermittedWeight {
+    weight: Arc<dyn Weight>,
+    allowed_docs: Arc<BitSet>,
+}
+
+impl PermittedWeight {
+    pub(crate) fn new(weight: Arc<dyn Weight>, allowed_docs: Arc<BitSet>) -> PermittedWeight {
+        PermittedWeight {
+            weight,
+            allowed_docs,
+        }
+    }
+}
+
+impl Weight for PermittedWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, boost)?;
+        Ok(Box::new(PermittedScorer::new(
+            scorer,
+            self.allowed_docs.clone(),
+        )))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        if !self.allowed_docs.contains(doc) {
+            return Err(does_not_match(doc));
+        }
+        self.weight.explain(reader, doc)
+    }
+
+    fn for_each(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score),
+    ) -> crate::Result<()> {
+        let allowed_docs = &self.allowed_docs;
+        self.weight.for_each(reader, &mut |doc, score| {
+            if allowed_docs.contains(doc) {
+                callback(doc, score);
+            }
+        })
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        let allowed_docs = &self.allowed_docs;
+        let mut current_threshold = threshold;
+        self.weight
+            .for_each_pruning(threshold, reader, &mut |doc, score| {
+                if allowed_docs.contains(doc) {
+                    current_threshold = callback(doc, score);
+                }
+                current_threshold
+            })
+    }
+}
+
+/// Scorer skipping the documents that are not allowed.
+struct PermittedScorer {
+    scorer: Box<dyn Scorer>,
+    allowed_docs: Arc<BitSet>,
+}
+
+impl PermittedScorer {
+    fn new(scorer: Box<dyn Scorer>, allowed_docs: Arc<BitSet>) -> PermittedScorer {
+        let mut permitted_scorer = PermittedScorer {
+            scorer,
+            allowed_docs,
+        };
+        permitted_scorer.skip_forbidden_docs();
+        permitted_scorer
+    }
+
+    fn skip_forbidden_docs(&mut self) -> DocId {
+        let mut doc = self.scorer.doc();
+        while doc != TERMINATED && !self.allowed_docs.contains(doc) {
+            doc = self.scorer.advance();
+        }
+        doc
+    }
+}
+
+impl DocSet for PermittedScorer {
+    fn advance(&mut self) -> DocId {
+        self.scorer.advance();
+        self.skip_forbidden_docs()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.scorer.seek(target);
+        self.skip_forbidden_docs()
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn cost(&self) -> u64 {
+        self.scorer.cost()
+    }
+}
+
+impl Scorer for PermittedScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::PermittedWeight;
+    use crate::collector::{Count, TopDocs};
+    use crate::fastfield::FastFieldReader;
+    use crate::query::{AllQuery, Query, QueryParser, Weight};
+    use crate::schema::{Schema, FAST, TEXT};
+    use crate::{doc, DocAddress, Index, SegmentReader, Term};
+    use common::BitSet;
+
+    #[test]
+    fn test_search_with_permit() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let group = schema_builder.add_u64_field("group", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello", group => 1u64))?;
+        index_writer.add_document(doc!(text => "hello hello", group => 2u64))?;
+        index_writer.add_document(doc!(text => "hello world", group => 1u64))?;
+        index_writer.add_document(doc!(text => "world", group => 1u64))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(text, "world"));
+        index_writer.add_document(doc!(text => "hello", group => 2u64))?;
+        index_writer.commit()?;
+
+        let group_permit = |allowed_group: u64| {
+            move |segment_reader: &SegmentReader| {
+                let group_reader = segment_reader.fast_fields().u64(group)?;
+                let mut allowed_docs = BitSet::with_max_value(segment_reader.max_doc());
+                for doc in 0..segment_reader.max_doc() {
+                    if group_reader.get(doc) == allowed_group {
+                        allowed_docs.insert(doc);
+                    }
+                }
+                Ok(Arc::new(allowed_docs))
+            }
+        };
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("hello")?;
+        assert_eq!(searcher.search(query.as_ref(), &Count)?, 3);
+        assert_eq!(
+            searcher.search_with_permit(query.as_ref(), &Count, &group_permit(1))?,
+            1
+        );
+        assert_eq!(
+            searcher.search_with_permit(&AllQuery, &Count, &group_permit(2))?,
+            2
+        );
+        let top_docs = searcher.search_with_permit(
+            query.as_ref(),
+            &TopDocs::with_limit(10),
+            &group_permit(2),
+        )?;
+        let mut top_doc_addresses: Vec<DocAddress> = top_docs
+            .into_iter()
+            .map(|(_, doc_address)| doc_address)
+            .collect();
+        top_doc_addresses.sort();
+        let expected: Vec<DocAddress> = searcher
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .flat_map(|(segment_ord, segment_reader)| {
+                let group_reader = segment_reader.fast_fields().u64(group).unwrap();
+                segment_reader
+                    .doc_ids_alive()
+                    .filter(move |&doc| group_reader.get(doc) == 2)
+                    .map(move |doc| DocAddress::new(segment_ord as u32, doc))
+            })
+            .collect();
+        assert_eq!(top_doc_addresses, expected);
+        // The scorer of the weight is filtered as well.
+        let segment_reader = searcher.segment_reader(0);
+        let no_docs = Arc::new(BitSet::with_max_value(segment_reader.max_doc()));
+        let permitted_weight =
+            PermittedWeight::new(Arc::from(query.weight(&searcher, false)?), no_docs);
+        assert_eq!(permitted_weight.count(segment_reader)?, 0);
+        Ok(())
+    }
+}
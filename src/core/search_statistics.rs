@@ -0,0 +1,172 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::SegmentReader;
+use crate::query::{Explanation, Scorer, Weight};
+use crate::{DocId, DocSet, Score, SegmentId, SegmentOrdinal};
+
+/// Timings of a search, as returned by
+/// [`Searcher::search_with_statistics`](./struct.Searcher.html#method.search_with_statistics).
+#[derive(Debug, Clone)]
+pub struct SearchStatistics {
+    /// Time spent creating the `Weight` of the query (e.g. fetching the
+    /// statistics of the terms for BM25).
+    pub weight_duration: Duration,
+    /// Statistics of each segment, ordered by `SegmentOrdinal`.
+    pub segments: Vec<SegmentSearchStatistics>,
+    /// Time spent merging the fruits of the segments.
+    pub merge_duration: Duration,
+    /// Total time of the search.
+    pub total_duration: Duration,
+}
+
+/// Timings of the search of a single segment.
+///
+/// Within a segment, `scorer_setup_duration + scoring_duration + collection_duration`
+/// is equal to `total_duration`.
+#[derive(Debug, Clone)]
+pub struct SegmentSearchStatistics {
+    /// Ordinal of the segment in the searcher.
+    pub segment_ord: SegmentOrdinal,
+    /// Id of the segment.
+    pub segment_id: SegmentId,
+    /// Time spent creating the scorer of the query, e.g. opening the posting lists.
+    pub scorer_setup_duration: Duration,
+    /// Time spent in the scorer, advancing through the matching documents
+    /// and computing their scores.
+    pub scoring_duration: Duration,
+    /// Time spent in the collector, including the creation of its segment collector.
+    pub collection_duration: Duration,
+    /// Total time spent on the segment.
+    pub total_duration: Duration,
+}
+
+#[derive(Default)]
+struct ScorerTimings {
+    setup_nanos: AtomicU64,
+    scoring_nanos: AtomicU64,
+}
+
+fn elapsed_nanos(start: Instant) -> u64 {
+    start.elapsed().as_nanos() as u64
+}
+
+/// Wraps the weight of a query to measure the time spent in its scorers.
+///
+/// Specialized implementations of `for_each_pruning` (e.g. BlockWAND) are
+/// not used by the profiled weight, as they bypass the scorer.
+pub(crate) struct ProfiledWeight {
+    weight: Arc<dyn Weight>,
+    timings: Arc<ScorerTimings>,
+}
+
+impl ProfiledWeight {
+    pub(crate) fn new(weight: Arc<dyn Weight>) -> ProfiledWeight {
+        ProfiledWeight {
+            weight,
+            timings: Arc::default(),
+        }
+    }
+
+    /// Returns the time spent creating the scorers, and
+    /// the time spent in the scorers.
+    fn durations(&self) -> (Duration, Duration) {
+        (
+            Duration::from_nanos(self.timings.setup_nanos.load(Ordering::Relaxed)),
+            Duration::from_nanos(self.timings.scoring_nanos.load(Ordering::Relaxed)),
+        )
+    }
+
+    /// Builds the statistics of a segment searched with this weight.
+    pub(crate) fn segment_statistics(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+        total_duration: Duration,
+    ) -> SegmentSearchStatistics {
+        let (scorer_setup_duration, scoring_duration) = self.durations();
+        let collection_duration = total_duration
+            .checked_sub(scorer_setup_duration + scoring_duration)
+            .unwrap_or_default();
+        SegmentSearchStatistics {
+            segment_ord,
+            segment_id: segment_reader.segment_id(),
+            scorer_setup_duration,
+            scoring_duration,
+            collection_duration,
+            total_duration,
+        }
+    }
+}
+
+impl Weight for ProfiledWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let start = Instant::now();
+        let scorer = self.weight.scorer(reader, boost)?;
+        self.timings
+            .setup_nanos
+            .fetch_add(elapsed_nanos(start), Ordering::Relaxed);
+        Ok(Box::new(ProfiledScorer {
+            scorer,
+            scoring_nanos: 0,
+            timings: self.timings.clone(),
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+}
+
+struct ProfiledScorer {
+    scorer: Box<dyn Scorer>,
+    // accumulated locally, and published on drop.
+    scoring_nanos: u64,
+    timings: Arc<ScorerTimings>,
+}
+
+impl DocSet for ProfiledScorer {
+    fn advance(&mut self) -> DocId {
+        let start = Instant::now();
+        let doc = self.scorer.advance();
+        self.scoring_nanos += elapsed_nanos(start);
+        doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let start = Instant::now();
+        let doc = self.scorer.seek(target);
+        self.scoring_nanos += elapsed_nanos(start);
+        doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn cost(&self) -> u64 {
+        self.scorer.cost()
+    }
+}
+
+impl Scorer for ProfiledScorer {
+    fn score(&mut self) -> Score {
+        let start = Instant::now();
+        let score = self.scorer.score();
+        self.scoring_nanos += elapsed_nanos(start);
+        score
+    }
+}
+
+impl Drop for ProfiledScorer {
+    fn drop(&mut self) {
+        self.timings
+            .scoring_nanos
+            .fetch_add(self.scoring_nanos, Ordering::Relaxed);
+    }
+}
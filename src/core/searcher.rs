@@ -1,16 +1,25 @@
-use crate::collector::Collector;
-use crate::core::Executor;
+use crate::collector::{Collector, SegmentCollector, TopHit, TopHitsCollector};
+use crate::core::search_permit::{PermittedWeight, SearchPermit};
+use crate::core::search_statistics::{ProfiledWeight, SearchStatistics};
+use crate::core::{Executor, GlobalOrdinals, GlobalOrdinalsCache};
 
 use crate::core::SegmentReader;
-use crate::query::Query;
+use crate::query::{BooleanQuery, BoostQuery, Query, TermSetQuery, Weight};
 use crate::schema::Document;
+use crate::schema::Field;
 use crate::schema::Schema;
 use crate::schema::Term;
 use crate::space_usage::SearcherSpaceUsage;
 use crate::store::StoreReader;
 use crate::DocAddress;
 use crate::Index;
+use crate::SegmentOrdinal;
+use crate::TantivyError;
 
+use futures::future::Future;
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
 use std::{fmt, io};
 
 /// Holds a list of `SegmentReader`s ready for search.
@@ -18,11 +27,14 @@ use std::{fmt, io};
 /// It guarantees that the `Segment` will not be removed before
 /// the destruction of the `Searcher`.
 ///
+/// Cloning a `Searcher` is cheap: the clones share the same segment readers.
+#[derive(Clone)]
 pub struct Searcher {
     schema: Schema,
     index: Index,
     segment_readers: Vec<SegmentReader>,
     store_readers: Vec<StoreReader>,
+    global_ordinals_cache: GlobalOrdinalsCache,
 }
 
 impl Searcher {
@@ -41,23 +53,78 @@ impl Searcher {
             index,
             segment_readers,
             store_readers,
+            global_ordinals_cache: GlobalOrdinalsCache::default(),
         })
     }
 
+    /// Shares the global ordinals built by the searcher with other searchers
+    /// of the same segments.
+    pub(crate) fn with_global_ordinals_cache(
+        mut self,
+        global_ordinals_cache: GlobalOrdinalsCache,
+    ) -> Searcher {
+        self.global_ordinals_cache = global_ordinals_cache;
+        self
+    }
+
     /// Returns the `Index` associated to the `Searcher`
     pub fn index(&self) -> &Index {
         &self.index
     }
 
+    fn store_reader(&self, doc_address: DocAddress) -> crate::Result<&StoreReader> {
+        self.store_readers
+            .get(doc_address.segment_ord as usize)
+            .ok_or_else(|| {
+                TantivyError::InvalidArgument(format!(
+                    "Segment ordinal {} is out of bounds: the searcher has {} segments.",
+                    doc_address.segment_ord,
+                    self.store_readers.len()
+                ))
+            })
+    }
+
     /// Fetches a document from tantivy's store given a `DocAddress`.
     ///
     /// The searcher uses the segment ordinal to route the
     /// the request to the right `Segment`.
+    ///
+    /// Returns an error if the `DocAddress` does not belong to this searcher.
     pub fn doc(&self, doc_address: DocAddress) -> crate::Result<Document> {
-        let store_reader = &self.store_readers[doc_address.segment_ord as usize];
+        let store_reader = self.store_reader(doc_address)?;
         store_reader.get(doc_address.doc_id)
     }
 
+    /// Fetches a document from tantivy's store given a `DocAddress`,
+    /// only keeping the values of the given `fields`.
+    ///
+    /// This is cheaper than [`doc(...)`](#method.doc) when only a few fields are needed,
+    /// as the values of the other fields are not deserialized.
+    pub fn doc_partial(
+        &self,
+        doc_address: DocAddress,
+        fields: &[Field],
+    ) -> crate::Result<Document> {
+        let store_reader = self.store_reader(doc_address)?;
+        store_reader.get_fields(doc_address.doc_id, fields)
+    }
+
+    /// Iterates over all of the documents of the doc store, segment after segment.
+    ///
+    /// Deleted documents are skipped. Within a segment, documents are returned
+    /// in the order of their `DocId`.
+    ///
+    /// This makes it possible to reindex the documents into an index with a new schema,
+    /// or to export them, without keeping the original source around.
+    pub fn iter_docs(&self) -> impl Iterator<Item = crate::Result<Document>> + '_ {
+        self.segment_readers
+            .iter()
+            .zip(self.store_readers.iter())
+            .flat_map(|(segment_reader, store_reader)| {
+                store_reader.iter(segment_reader.alive_bitset())
+            })
+    }
+
     /// Access the schema associated to the index of this searcher.
     pub fn schema(&self) -> &Schema {
         &self.schema
@@ -83,6 +150,52 @@ impl Searcher {
         Ok(total_doc_freq)
     }
 
+    /// Return the overall number of occurrences of the given term.
+    ///
+    /// Occurrences in deleted documents are included.
+    pub fn total_term_freq(&self, term: &Term) -> crate::Result<u64> {
+        let mut total_term_freq = 0;
+        for segment_reader in &self.segment_readers {
+            let inverted_index = segment_reader.inverted_index(term.field())?;
+            total_term_freq += inverted_index.total_term_freq(term)?;
+        }
+        Ok(total_term_freq)
+    }
+
+    /// Return the overall number of tokens indexed in the given field.
+    ///
+    /// Tokens of deleted documents are included.
+    pub fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        let mut total_num_tokens = 0;
+        for segment_reader in &self.segment_readers {
+            let inverted_index = segment_reader.inverted_index(field)?;
+            total_num_tokens += inverted_index.total_num_tokens();
+        }
+        Ok(total_num_tokens)
+    }
+
+    /// Return the overall number of documents with at least one token in the given field.
+    ///
+    /// Deleted documents are included.
+    pub fn num_docs_with_tokens(&self, field: Field) -> crate::Result<u64> {
+        let mut num_docs_with_tokens = 0u64;
+        for segment_reader in &self.segment_readers {
+            num_docs_with_tokens += u64::from(segment_reader.num_docs_with_tokens(field)?);
+        }
+        Ok(num_docs_with_tokens)
+    }
+
+    /// Returns the global ordinals of the facet field `field` over the segments
+    /// of the searcher. See [`GlobalOrdinals`](../struct.GlobalOrdinals.html).
+    ///
+    /// They are built on the first call, and shared by all of the searchers of the
+    /// same generation of the `IndexReader` until its next reload. A
+    /// [`Warmer`](../trait.Warmer.html) can build them ahead of the searches.
+    pub fn global_ordinals(&self, field: Field) -> crate::Result<Arc<GlobalOrdinals>> {
+        self.global_ordinals_cache
+            .get_or_build(&self.segment_readers, field)
+    }
+
     /// Return the list of segment readers
     pub fn segment_readers(&self) -> &[SegmentReader] {
         &self.segment_readers
@@ -116,6 +229,58 @@ impl Searcher {
         self.search_with_executor(query, collector, executor)
     }
 
+    /// Same as [`search(...)`](#method.search), but returns a future, for the
+    /// async runtimes.
+    ///
+    /// The blocking work, i.e. the creation of the weight and the search of
+    /// each segment, is run in the search executor of the index (see
+    /// [`Index::set_multithread_executor`](../struct.Index.html#method.set_multithread_executor)),
+    /// one segment after the other. The future yields to the runtime between
+    /// these tasks, so that a search does not block the runtime threads.
+    ///
+    /// With the default single thread executor, the tasks are run in the
+    /// thread polling the future.
+    pub fn search_async<C: Collector + 'static>(
+        &self,
+        query: &dyn Query,
+        collector: C,
+    ) -> impl Future<Output = crate::Result<C::Fruit>> + Send + 'static {
+        let searcher = Arc::new(self.clone());
+        let query = query.box_clone();
+        let collector = Arc::new(collector);
+        async move {
+            let executor = searcher.index.search_executor();
+            let weight: Arc<dyn Weight> = {
+                let searcher = searcher.clone();
+                let scoring_enabled = collector.requires_scoring();
+                executor
+                    .run_async(move || {
+                        searcher
+                            .create_weight(query.as_ref(), scoring_enabled)
+                            .map(Arc::from)
+                    })
+                    .await?
+            };
+            let mut fruits = Vec::with_capacity(searcher.segment_readers.len());
+            for segment_ord in 0..searcher.segment_readers.len() as SegmentOrdinal {
+                let searcher = searcher.clone();
+                let weight = weight.clone();
+                let segment_collector = collector.clone();
+                let fruit = executor
+                    .run_async(move || {
+                        searcher.search_in_segment(
+                            weight.as_ref(),
+                            segment_collector.as_ref(),
+                            segment_ord,
+                        )
+                    })
+                    .await?;
+                fruits.push(fruit);
+            }
+            collector.merge_fruits(fruits)
+        }
+    }
+
     /// Same as [`search(...)`](#method.search) but multithreaded.
     ///
     /// The current implementation is rather naive :
@@ -134,8 +299,7 @@ impl Searcher {
         collector: &C,
         executor: &Executor,
     ) -> crate::Result<C::Fruit> {
-        let scoring_enabled = collector.requires_scoring();
-        let weight = query.weight(self, scoring_enabled)?;
+        let weight = self.create_weight(query, collector.requires_scoring())?;
         let segment_readers = self.segment_readers();
         let fruits = executor.map(
             |(segment_ord, segment_reader)| {
@@ -146,6 +310,213 @@ impl Searcher {
         collector.merge_fruits(fruits)
     }
 
+    /// Returns the number of documents matching the query.
+    ///
+    /// This is cheaper than searching with the [`Count`](../collector/struct.Count.html)
+    /// collector: the documents are not scored, and the queries able to count their
+    /// documents without iterating over them do so. For instance, the number of
+    /// documents matching a single term is read from its document frequency
+    /// on the segments without deletes.
+    pub fn count(&self, query: &dyn Query) -> crate::Result<usize> {
+        let weight = self.create_weight(query, false)?;
+        let executor = self.index.search_executor();
+        let segment_counts = executor.map(
+            |segment_reader| weight.count(segment_reader),
+            self.segment_readers.iter(),
+        )?;
+        Ok(segment_counts
+            .into_iter()
+            .map(|segment_count| segment_count as usize)
+            .sum())
+    }
+
+    /// Collapses the documents matching the query on the values of `group_field`,
+    /// and returns the `num_docs_per_group` best documents of the `num_groups` best groups.
+    ///
+    /// `group_field` must be an indexed, single-valued fast field. The groups are
+    /// returned with their value, ordered by the score of their best document, and their
+    /// hits are sorted by decreasing score. As for the
+    /// [`TopHitsCollector`](../collector/struct.TopHitsCollector.html), the values are
+    /// given as `u64`, and the documents without any value do not belong to any group.
+    ///
+    /// The search runs in two passes. The first pass only keeps the best document of each
+    /// group, to select the best groups. The second pass then collects the best documents
+    /// of these groups only, the query being restricted to their values with a
+    /// [`TermSetQuery`](../query/struct.TermSetQuery.html). The second pass is skipped
+    /// if a single document per group is requested.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_groups` or `num_docs_per_group` is 0.
+    pub fn search_grouped(
+        &self,
+        query: &dyn Query,
+        group_field: Field,
+        num_groups: usize,
+        num_docs_per_group: usize,
+    ) -> crate::Result<Vec<(u64, Vec<TopHit>)>> {
+        assert!(
+            num_groups >= 1,
+            "The number of groups must be strictly greater than 0."
+        );
+        let field_entry = self.schema.get_field_entry(group_field);
+        if !field_entry.is_indexed() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not indexed.",
+                field_entry.name()
+            )));
+        }
+        let best_hit_per_group = self.search(query, &TopHitsCollector::new(group_field, 1))?;
+        let mut groups: Vec<(u64, Vec<TopHit>)> = best_hit_per_group.into_iter().collect();
+        groups.sort_by(|(_, left_hits), (_, right_hits)| {
+            let (left, right) = (&left_hits[0], &right_hits[0]);
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.doc_address.cmp(&right.doc_address))
+        });
+        groups.truncate(num_groups);
+        let top_hits_collector = TopHitsCollector::new(group_field, num_docs_per_group);
+        if num_docs_per_group == 1 || groups.is_empty() {
+            return Ok(groups);
+        }
+
+        let group_terms = groups
+            .iter()
+            .map(|&(group, _)| Term::from_field_u64(group_field, group));
+        // The filter does not contribute to the score of the documents.
+        let group_filter: Box<dyn Query> = Box::new(BoostQuery::new(
+            Box::new(TermSetQuery::new(group_terms)),
+            0.0,
+        ));
+        let restricted_query = BooleanQuery::intersection(vec![query.box_clone(), group_filter]);
+        let mut hits_per_group = self.search(&restricted_query, &top_hits_collector)?;
+        Ok(groups
+            .into_iter()
+            .map(|(group, _)| (group, hits_per_group.remove(&group).unwrap_or_default()))
+            .collect())
+    }
+
+    /// Rewrites the query and creates its weight, as done by
+    /// [`search(...)`](#method.search) before searching the segments.
+    ///
+    /// The weight can then be shared by the searches of the different segments,
+    /// see [`search_in_segment(...)`](#method.search_in_segment).
+    pub fn create_weight(
+        &self,
+        query: &dyn Query,
+        scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let rewritten_query = query.rewrite(self)?;
+        let query = rewritten_query.as_deref().unwrap_or(query);
+        query.weight(self, scoring_enabled)
+    }
+
+    /// Searches a single segment, and returns the fruit of the segment collector.
+    ///
+    /// This is the building block of [`search(...)`](#method.search) for the runtimes
+    /// that schedule the search of the segments themselves, e.g. actor-based or
+    /// async executors: the weight is created once with
+    /// [`create_weight(...)`](#method.create_weight), each segment is searched
+    /// independently, and the fruits are merged with
+    /// [`Collector::merge_fruits`](../collector/trait.Collector.html#tymethod.merge_fruits)
+    /// or with a custom policy.
+    ///
+    /// The weight must have been created with scoring enabled if the collector
+    /// requires scoring.
+    pub fn search_in_segment<C: Collector>(
+        &self,
+        weight: &dyn Weight,
+        collector: &C,
+        segment_ord: SegmentOrdinal,
+    ) -> crate::Result<<C::Child as SegmentCollector>::Fruit> {
+        let segment_reader = self
+            .segment_readers
+            .get(segment_ord as usize)
+            .ok_or_else(|| {
+                TantivyError::InvalidArgument(format!(
+                    "Segment ordinal {} is out of bounds: the searcher has {} segments.",
+                    segment_ord,
+                    self.segment_readers.len()
+                ))
+            })?;
+        collector.collect_segment(weight, segment_ord, segment_reader)
+    }
+
+    /// Same as [`search(...)`](#method.search), but only the documents allowed
+    /// by the `permit` can be matched.
+    ///
+    /// The allowed documents of each segment are fetched from the permit before the
+    /// segment is searched, and the documents that are not allowed are filtered out
+    /// whatever the query and the collector are.
+    pub fn search_with_permit<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        permit: &dyn SearchPermit,
+    ) -> crate::Result<C::Fruit> {
+        let weight: Arc<dyn Weight> =
+            Arc::from(self.create_weight(query, collector.requires_scoring())?);
+        let executor = self.index.search_executor();
+        let fruits = executor.map(
+            |(segment_ord, segment_reader)| {
+                let allowed_docs = permit.allowed_docs(segment_reader)?;
+                let permitted_weight = PermittedWeight::new(weight.clone(), allowed_docs);
+                collector.collect_segment(&permitted_weight, segment_ord as u32, segment_reader)
+            },
+            self.segment_readers().iter().enumerate(),
+        )?;
+        collector.merge_fruits(fruits)
+    }
+
+    /// Same as [`search(...)`](#method.search), but also returns the time spent
+    /// in the different phases of the search, for each segment.
+    ///
+    /// This is meant to diagnose slow queries. Measuring the time spent in the scorer
+    /// adds an overhead to each matching document, and specialized collection strategies
+    /// (e.g. the BlockWAND pruning of `TopDocs`) are not used: the timings are
+    /// therefore an upper bound of the cost of a regular search.
+    pub fn search_with_statistics<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<(C::Fruit, SearchStatistics)> {
+        let start = Instant::now();
+        let weight: Arc<dyn Weight> =
+            Arc::from(self.create_weight(query, collector.requires_scoring())?);
+        let weight_duration = start.elapsed();
+        let executor = self.index.search_executor();
+        let segment_results = executor.map(
+            |(segment_ord, segment_reader)| {
+                let segment_start = Instant::now();
+                let profiled_weight = ProfiledWeight::new(weight.clone());
+                let fruit = collector.collect_segment(
+                    &profiled_weight,
+                    segment_ord as u32,
+                    segment_reader,
+                )?;
+                let segment_statistics = profiled_weight.segment_statistics(
+                    segment_ord as u32,
+                    segment_reader,
+                    segment_start.elapsed(),
+                );
+                Ok((fruit, segment_statistics))
+            },
+            self.segment_readers().iter().enumerate(),
+        )?;
+        let (fruits, segments): (Vec<_>, Vec<_>) = segment_results.into_iter().unzip();
+        let merge_start = Instant::now();
+        let fruit = collector.merge_fruits(fruits)?;
+        let statistics = SearchStatistics {
+            weight_duration,
+            segments,
+            merge_duration: merge_start.elapsed(),
+            total_duration: start.elapsed(),
+        };
+        Ok((fruit, statistics))
+    }
+
     /// Summarize total space usage of this searcher.
     pub fn space_usage(&self) -> io::Result<SearcherSpaceUsage> {
         let mut space_usage = SearcherSpaceUsage::new();
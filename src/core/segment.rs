@@ -1,20 +1,26 @@
 use super::SegmentComponent;
+use crate::core::Codec;
 use crate::core::Index;
 use crate::core::SegmentId;
 use crate::core::SegmentMeta;
+use crate::core::SegmentReader;
 use crate::directory::error::{OpenReadError, OpenWriteError};
 use crate::directory::Directory;
 use crate::directory::{FileSlice, WritePtr};
+use crate::indexer::merge_throttle::{MergeLimiter, ThrottledWrite};
 use crate::schema::Schema;
 use crate::Opstamp;
 use std::fmt;
+use std::io::BufWriter;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// A segment is a piece of the index.
 #[derive(Clone)]
 pub struct Segment {
     index: Index,
     meta: SegmentMeta,
+    merge_limiter: Option<Arc<MergeLimiter>>,
 }
 
 impl fmt::Debug for Segment {
@@ -26,7 +32,17 @@ impl fmt::Debug for Segment {
 impl Segment {
     /// Creates a new segment given an `Index` and a `SegmentId`
     pub(crate) fn for_index(index: Index, meta: SegmentMeta) -> Segment {
-        Segment { index, meta }
+        Segment {
+            index,
+            meta,
+            merge_limiter: None,
+        }
+    }
+
+    /// Rate limits the writes of the segment, written by a merge.
+    pub(crate) fn with_merge_limiter(mut self, merge_limiter: Arc<MergeLimiter>) -> Segment {
+        self.merge_limiter = Some(merge_limiter);
+        self
     }
 
     /// Returns the index the segment belongs to.
@@ -52,6 +68,7 @@ impl Segment {
         Segment {
             index: self.index,
             meta: self.meta.with_max_doc(max_doc),
+            merge_limiter: self.merge_limiter,
         }
     }
 
@@ -60,9 +77,28 @@ impl Segment {
         Segment {
             index: self.index,
             meta: self.meta.with_delete_meta(num_deleted_docs, opstamp),
+            merge_limiter: self.merge_limiter,
         }
     }
 
+    /// Records the bounds of the values of the fields of the segment in its meta.
+    pub(crate) fn with_computed_field_bounds(self) -> crate::Result<Segment> {
+        let field_bounds = SegmentReader::open(&self)?.compute_field_bounds()?;
+        Ok(Segment {
+            index: self.index,
+            meta: self.meta.with_field_bounds(field_bounds),
+            merge_limiter: self.merge_limiter,
+        })
+    }
+
+    /// Returns the codec the segment is written and read with.
+    ///
+    /// Returns an `IncompatibleIndex` error if the codec recorded in the
+    /// segment meta is not registered in the index.
+    pub fn codec(&self) -> crate::Result<Arc<dyn Codec>> {
+        self.index.codecs().get_or_err(self.meta.codec_name())
+    }
+
     /// Returns the segment's id.
     pub fn id(&self) -> SegmentId {
         self.meta.id()
@@ -86,6 +122,10 @@ impl Segment {
     pub fn open_write(&mut self, component: SegmentComponent) -> Result<WritePtr, OpenWriteError> {
         let path = self.relative_path(component);
         let write = self.index.directory_mut().open_write(&path)?;
+        if let Some(merge_limiter) = self.merge_limiter.as_ref() {
+            let throttled_write = ThrottledWrite::new(write, Arc::clone(merge_limiter));
+            return Ok(BufWriter::new(Box::new(throttled_write)));
+        }
         Ok(write)
     }
 }
@@ -1,24 +1,37 @@
+use crate::core::Codec;
+use crate::core::FieldBounds;
+use crate::core::IndexSortByField;
 use crate::core::InvertedIndexReader;
+use crate::core::InvertedIndexStatistics;
 use crate::core::Segment;
 use crate::core::SegmentComponent;
 use crate::core::SegmentId;
+use crate::core::{HotCache, WarmupSpec};
 use crate::directory::CompositeFile;
 use crate::directory::FileSlice;
 use crate::error::DataCorruption;
 use crate::fastfield::intersect_alive_bitsets;
 use crate::fastfield::AliveBitSet;
 use crate::fastfield::FacetReader;
-use crate::fastfield::FastFieldReaders;
+use crate::fastfield::{FastFieldReader, FastFieldReaders};
+use crate::features::{FeaturesReader, FeaturesReaders};
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders};
+use crate::schema::Cardinality;
+use crate::schema::Document;
 use crate::schema::FieldType;
 use crate::schema::Schema;
 use crate::schema::{Field, IndexRecordOption};
 use crate::space_usage::SegmentSpaceUsage;
-use crate::store::StoreReader;
+use crate::store::{StoreReader, DEFAULT_CACHE_NUM_BLOCKS};
 use crate::termdict::TermDictionary;
+use crate::vector::{VectorReader, VectorReaders};
 use crate::DocId;
+use crate::Opstamp;
 use fail::fail_point;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::{collections::HashMap, io};
@@ -36,8 +49,10 @@ use std::{collections::HashMap, io};
 #[derive(Clone)]
 pub struct SegmentReader {
     inv_idx_reader_cache: Arc<RwLock<HashMap<Field, Arc<InvertedIndexReader>>>>,
+    num_docs_with_tokens_cache: Arc<RwLock<HashMap<Field, u32>>>,
 
     segment_id: SegmentId,
+    delete_opstamp: Option<Opstamp>,
     max_doc: DocId,
     num_docs: DocId,
 
@@ -46,10 +61,19 @@ pub struct SegmentReader {
     positions_composite: CompositeFile,
     fast_fields_readers: Arc<FastFieldReaders>,
     fieldnorm_readers: FieldNormReaders,
+    vector_readers: VectorReaders,
+    features_readers: FeaturesReaders,
+    codec: Arc<dyn Codec>,
 
-    store_file: FileSlice,
+    store_reader: StoreReader,
     alive_bitset_opt: Option<AliveBitSet>,
     schema: Schema,
+    sort_by_field: Option<IndexSortByField>,
+    static_rank_field: Option<Field>,
+    tenant_field: Option<Field>,
+    field_bounds: Arc<HashMap<Field, FieldBounds>>,
+    // Relative paths of the components of the segment that can be warmed up.
+    warmup_paths: Arc<Vec<(SegmentComponent, PathBuf)>>,
 }
 
 impl SegmentReader {
@@ -105,7 +129,7 @@ impl SegmentReader {
                 let termdict = self
                     .termdict_composite
                     .open_read(field)
-                    .map(TermDictionary::open)
+                    .map(|termdict_file| self.codec.open_term_dictionary(termdict_file))
                     .unwrap_or_else(|| Ok(TermDictionary::empty()))?;
                 Ok(FacetReader::new(term_ords_reader, termdict))
             }
@@ -123,24 +147,190 @@ impl SegmentReader {
     ///
     /// They are simply stored as a fast field, serialized in
     /// the `.fieldnorm` file of the segment.
+    ///
+    /// If the field was added to the schema after the segment was written,
+    /// all of the field norms of the segment are 0.
     pub fn get_fieldnorms_reader(&self, field: Field) -> crate::Result<FieldNormReader> {
-        self.fieldnorm_readers.get_field(field)?.ok_or_else(|| {
-            let field_name = self.schema.get_field_name(field);
-            let err_msg = format!(
-                "Field norm not found for field {:?}. Was the field set to record norm during indexing?",
-                field_name
-            );
-            crate::TantivyError::SchemaError(err_msg)
-        })
+        if let Some(fieldnorm_reader) = self.fieldnorm_readers.get_field(field)? {
+            return Ok(fieldnorm_reader);
+        }
+        let field_entry = self.schema.get_field_entry(field);
+        if field_entry.is_indexed() && field_entry.has_fieldnorms() {
+            return Ok(FieldNormReader::constant(self.max_doc, 0));
+        }
+        let err_msg = format!(
+            "Field norm not found for field {:?}. Was the field set to record norm during indexing?",
+            field_entry.name()
+        );
+        Err(crate::TantivyError::SchemaError(err_msg))
     }
 
     pub(crate) fn fieldnorms_readers(&self) -> &FieldNormReaders {
         &self.fieldnorm_readers
     }
 
+    /// Accessor to the segment's `VectorReader` for a vector field.
+    ///
+    /// If the segment does not have any vector for this field, the returned
+    /// reader is empty.
+    pub fn vector_reader(&self, field: Field) -> crate::Result<VectorReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        let vector_options = match field_entry.field_type() {
+            FieldType::Vector(vector_options) => vector_options,
+            _ => {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "Field {:?} is not a vector field.",
+                    field_entry.name()
+                )));
+            }
+        };
+        let vector_reader = self
+            .vector_readers
+            .get_field(field, vector_options.similarity())?
+            .unwrap_or_else(|| {
+                VectorReader::empty(vector_options.dimension(), vector_options.similarity())
+            });
+        Ok(vector_reader)
+    }
+
+    /// Accessor to the segment's `FeaturesReader` for a features field.
+    ///
+    /// If the segment does not have any features for this field, the returned
+    /// reader is empty.
+    pub fn features_reader(&self, field: Field) -> crate::Result<FeaturesReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        if !matches!(field_entry.field_type(), FieldType::Features(_)) {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Field {:?} is not a features field.",
+                field_entry.name()
+            )));
+        }
+        self.features_readers.get_field(field)
+    }
+
     /// Accessor to the segment's `StoreReader`.
+    ///
+    /// All of the store readers returned for a given `SegmentReader`
+    /// share the same cache of decompressed blocks.
     pub fn get_store_reader(&self) -> io::Result<StoreReader> {
-        StoreReader::open(self.store_file.clone())
+        Ok(self.store_reader.clone())
+    }
+
+    /// Fetches a document of this segment from the doc store.
+    ///
+    /// Returns an error if `doc_id` is not a valid `DocId` of the segment.
+    /// Deleted documents can still be fetched, until the segment gets merged.
+    pub fn doc(&self, doc_id: DocId) -> crate::Result<Document> {
+        self.store_reader.get(doc_id)
+    }
+
+    /// Returns the field and order the documents of the segment are sorted by,
+    /// if the index is sorted. See `IndexSettings`.
+    pub fn sort_by_field(&self) -> Option<&IndexSortByField> {
+        self.sort_by_field.as_ref()
+    }
+
+    /// Returns the static rank field whose maximum value over each block of postings
+    /// is recorded in the postings of the segment. See `IndexSettings::static_rank_field`.
+    pub fn static_rank_field(&self) -> Option<Field> {
+        self.static_rank_field
+    }
+
+    /// Returns the bounds of the values of `field` within the segment, as recorded
+    /// in the segment meta. See [`FieldBounds`](../struct.FieldBounds.html).
+    ///
+    /// Returns `None` if no bounds were recorded for the field.
+    pub fn field_bounds(&self, field: Field) -> Option<&FieldBounds> {
+        self.field_bounds.get(&field)
+    }
+
+    /// Computes the bounds of the values of the fields of the segment, by field name.
+    ///
+    /// The terms of the indexed fields and the values of the numeric fast fields
+    /// are bounded. The fields without any term nor value are omitted.
+    pub(crate) fn compute_field_bounds(&self) -> crate::Result<BTreeMap<String, FieldBounds>> {
+        let mut field_bounds = BTreeMap::new();
+        for (field, field_entry) in self.schema.fields() {
+            let mut bounds = FieldBounds::default();
+            if field_entry.is_indexed() {
+                let inverted_index = self.inverted_index(field)?;
+                let term_dict = inverted_index.terms();
+                let num_terms = term_dict.num_terms() as u64;
+                if num_terms > 0 {
+                    let mut first_term = Vec::new();
+                    let mut last_term = Vec::new();
+                    term_dict.ord_to_term(0, &mut first_term)?;
+                    term_dict.ord_to_term(num_terms - 1, &mut last_term)?;
+                    bounds.terms = Some((first_term, last_term));
+                }
+            }
+            let cardinality_opt = match field_entry.field_type() {
+                FieldType::U64(options)
+                | FieldType::I64(options)
+                | FieldType::F64(options)
+                | FieldType::Date(options) => options.get_fastfield_cardinality(),
+                _ => None,
+            };
+            match cardinality_opt {
+                Some(Cardinality::SingleValue) if self.max_doc > 0 => {
+                    let fast_field_reader = self.fast_fields().u64_lenient(field)?;
+                    bounds.values =
+                        Some((fast_field_reader.min_value(), fast_field_reader.max_value()));
+                }
+                Some(Cardinality::MultiValues) => {
+                    let fast_field_reader = self.fast_fields().u64s_lenient(field)?;
+                    if fast_field_reader.total_num_vals() > 0 {
+                        bounds.values =
+                            Some((fast_field_reader.min_value(), fast_field_reader.max_value()));
+                    }
+                }
+                _ => {}
+            }
+            if bounds != FieldBounds::default() {
+                field_bounds.insert(field_entry.name().to_string(), bounds);
+            }
+        }
+        Ok(field_bounds)
+    }
+
+    /// Returns the range of doc ids of the documents of `tenant` within the segment,
+    /// including the deleted ones. See `IndexSettings::tenant_field`.
+    ///
+    /// The documents of a segment are grouped by tenant, so that the range is found
+    /// by a binary search over the tenant fast field. An empty range is returned if the
+    /// segment does not contain any document of `tenant`.
+    ///
+    /// Returns an error if the index does not have a tenant field.
+    pub fn tenant_doc_range(&self, tenant: u64) -> crate::Result<Range<DocId>> {
+        let tenant_field = self.tenant_field.ok_or_else(|| {
+            crate::TantivyError::InvalidArgument(
+                "The index does not have a tenant field.".to_string(),
+            )
+        })?;
+        let tenants = self.fast_fields().u64(tenant_field)?;
+        if self.max_doc == 0 || tenant < tenants.min_value() || tenant > tenants.max_value() {
+            return Ok(0..0);
+        }
+        // returns the first doc whose tenant is greater or equal to `target`.
+        let lower_bound = |target: u64| {
+            let (mut start, mut end) = (0, self.max_doc);
+            while start < end {
+                let mid = start + (end - start) / 2;
+                if tenants.get(mid) < target {
+                    start = mid + 1;
+                } else {
+                    end = mid;
+                }
+            }
+            start
+        };
+        let start = lower_bound(tenant);
+        let end = if tenant == u64::MAX {
+            self.max_doc
+        } else {
+            lower_bound(tenant + 1)
+        };
+        Ok(start..end)
     }
 
     /// Open a new segment for reading.
@@ -153,10 +343,20 @@ impl SegmentReader {
         segment: &Segment,
         custom_bitset: Option<AliveBitSet>,
     ) -> crate::Result<SegmentReader> {
+        let codec = segment.codec()?;
         let termdict_file = segment.open_read(SegmentComponent::Terms)?;
         let termdict_composite = CompositeFile::open(&termdict_file)?;
 
         let store_file = segment.open_read(SegmentComponent::Store)?;
+        let cache_num_blocks = segment
+            .index()
+            .settings()
+            .docstore_cache_num_blocks
+            .unwrap_or(DEFAULT_CACHE_NUM_BLOCKS);
+        let store_reader = codec
+            .open_store(store_file)?
+            .with_filepath(segment.relative_path(SegmentComponent::Store))
+            .with_cache_num_blocks(cache_num_blocks);
 
         fail_point!("SegmentReader::open#middle");
 
@@ -174,44 +374,166 @@ impl SegmentReader {
         let schema = segment.schema();
 
         let fast_fields_data = segment.open_read(SegmentComponent::FastFields)?;
-        let fast_fields_composite = CompositeFile::open(&fast_fields_data)?;
+        let max_doc = segment.meta().max_doc();
         let fast_field_readers =
-            Arc::new(FastFieldReaders::new(schema.clone(), fast_fields_composite));
+            Arc::new(codec.open_fast_fields(schema.clone(), fast_fields_data, max_doc)?);
         let fieldnorm_data = segment.open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
 
-        let original_bitset = if segment.meta().has_deletes() {
-            let delete_file_slice = segment.open_read(SegmentComponent::Delete)?;
-            let delete_data = delete_file_slice.read_bytes()?;
-            Some(AliveBitSet::open(delete_data))
+        // Segments written before vector fields existed do not have a vectors component.
+        let vector_readers = if let Ok(vectors_data) = segment.open_read(SegmentComponent::Vectors)
+        {
+            VectorReaders::open(vectors_data)?
         } else {
-            None
+            VectorReaders::empty()
         };
+        let features_readers =
+            if let Ok(features_data) = segment.open_read(SegmentComponent::Features) {
+                FeaturesReaders::open(features_data)?
+            } else {
+                FeaturesReaders::empty()
+            };
+
+        let original_bitset = open_alive_bitset(segment)?;
 
         let alive_bitset_opt = intersect_alive_bitset(original_bitset, custom_bitset);
 
-        let max_doc = segment.meta().max_doc();
+        let warmup_paths = [
+            SegmentComponent::Terms,
+            SegmentComponent::FastFields,
+            SegmentComponent::FieldNorms,
+            SegmentComponent::Store,
+        ]
+        .iter()
+        .map(|&component| (component, segment.relative_path(component)))
+        .collect();
+
         let num_docs = alive_bitset_opt
             .as_ref()
             .map(|alive_bitset| alive_bitset.num_alive_docs() as u32)
             .unwrap_or(max_doc);
 
+        let static_rank_field = segment
+            .meta()
+            .static_rank_field()
+            .and_then(|field_name| schema.get_field(field_name));
+        let tenant_field = segment
+            .index()
+            .settings()
+            .tenant_field
+            .as_ref()
+            .and_then(|field_name| schema.get_field(field_name));
+        let field_bounds = segment
+            .meta()
+            .field_bounds()
+            .iter()
+            .filter_map(|(field_name, bounds)| {
+                schema
+                    .get_field(field_name)
+                    .map(|field| (field, bounds.clone()))
+            })
+            .collect();
+
         Ok(SegmentReader {
             inv_idx_reader_cache: Default::default(),
+            num_docs_with_tokens_cache: Default::default(),
             num_docs,
             max_doc,
             termdict_composite,
             postings_composite,
             fast_fields_readers: fast_field_readers,
             fieldnorm_readers,
+            vector_readers,
+            features_readers,
+            codec,
             segment_id: segment.id(),
-            store_file,
+            delete_opstamp: segment.meta().delete_opstamp(),
+            store_reader,
             alive_bitset_opt,
             positions_composite,
             schema,
+            sort_by_field: segment.index().settings().segment_sort_by_field(),
+            static_rank_field,
+            tenant_field,
+            field_bounds: Arc::new(field_bounds),
+            warmup_paths: Arc::new(warmup_paths),
         })
     }
 
+    /// Returns a reader of `segment`, sharing everything but the deletes with this reader.
+    ///
+    /// `segment` must be the segment of this reader, with possibly more recent deletes.
+    /// The deletes are copy-on-write: this reader, and the searchers holding it, keep
+    /// their own view of the deleted documents.
+    pub(crate) fn with_deletes_of(&self, segment: &Segment) -> crate::Result<SegmentReader> {
+        assert_eq!(segment.id(), self.segment_id);
+        let alive_bitset_opt = open_alive_bitset(segment)?;
+        let num_docs = alive_bitset_opt
+            .as_ref()
+            .map(|alive_bitset| alive_bitset.num_alive_docs() as u32)
+            .unwrap_or(self.max_doc);
+        Ok(SegmentReader {
+            num_docs,
+            delete_opstamp: segment.meta().delete_opstamp(),
+            alive_bitset_opt,
+            ..self.clone()
+        })
+    }
+
+    /// Preloads the data described in the `WarmupSpec`.
+    ///
+    /// This is useful with a `Directory` for which reads are costly:
+    /// the first queries do not have to pay for the loading of this data.
+    pub fn warmup(&self, warmup_spec: &WarmupSpec) -> crate::Result<()> {
+        for (_, file_slice) in self.warmup_file_slices(warmup_spec) {
+            file_slice.read_bytes()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the byte ranges of the segment files that hold the
+    /// data described in the `WarmupSpec`.
+    pub fn hot_cache(&self, warmup_spec: &WarmupSpec) -> crate::Result<HotCache> {
+        let ranges = self
+            .warmup_file_slices(warmup_spec)
+            .into_iter()
+            .map(|(component, file_slice)| {
+                let path = self
+                    .warmup_paths
+                    .iter()
+                    .find(|(warmup_component, _)| *warmup_component == component)
+                    .map(|(_, path)| path.clone())
+                    .expect("All of the warmup components should have a path.");
+                (path, file_slice.byte_range())
+            })
+            .collect();
+        Ok(HotCache::new(ranges))
+    }
+
+    fn warmup_file_slices(&self, warmup_spec: &WarmupSpec) -> Vec<(SegmentComponent, FileSlice)> {
+        let mut file_slices = Vec::new();
+        for &field in &warmup_spec.term_dict_fields {
+            if let Some(file_slice) = self.termdict_composite.open_read(field) {
+                file_slices.push((SegmentComponent::Terms, file_slice));
+            }
+        }
+        for &field in &warmup_spec.fast_fields {
+            for file_slice in self.fast_fields_readers.fast_field_data_slices(field) {
+                file_slices.push((SegmentComponent::FastFields, file_slice));
+            }
+        }
+        for &field in &warmup_spec.fieldnorm_fields {
+            if let Some(file_slice) = self.fieldnorm_readers.field_data(field) {
+                file_slices.push((SegmentComponent::FieldNorms, file_slice));
+            }
+        }
+        if warmup_spec.store {
+            let file_slice = self.store_reader.offset_index_file().clone();
+            file_slices.push((SegmentComponent::Store, file_slice));
+        }
+        file_slices
+    }
+
     /// Returns a field reader associated to the field given in argument.
     /// If the field was not present in the index during indexing time,
     /// the InvertedIndexReader is empty.
@@ -268,12 +590,17 @@ impl SegmentReader {
                DataCorruption::comment_only(error_msg)
             })?;
 
-        let inv_idx_reader = Arc::new(InvertedIndexReader::new(
-            TermDictionary::open(termdict_file)?,
+        let mut inv_idx_reader = self.codec.open_inverted_index(
+            self.codec.open_term_dictionary(termdict_file)?,
             postings_file,
             positions_file,
             record_option,
-        )?);
+            field_type.has_payloads(),
+        )?;
+        if self.static_rank_field.is_some() {
+            inv_idx_reader = inv_idx_reader.with_static_ranks();
+        }
+        let inv_idx_reader = Arc::new(inv_idx_reader);
 
         // by releasing the lock in between, we may end up opening the inverting index
         // twice, but this is fine.
@@ -285,11 +612,53 @@ impl SegmentReader {
         Ok(inv_idx_reader)
     }
 
+    /// Returns the number of documents with at least one token in `field`,
+    /// deleted documents included.
+    ///
+    /// It is computed from the fieldnorms on the first call, and cached. If the field
+    /// does not have fieldnorms, all of the documents are counted.
+    pub fn num_docs_with_tokens(&self, field: Field) -> crate::Result<u32> {
+        if let Some(&num_docs_with_tokens) = self
+            .num_docs_with_tokens_cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&field)
+        {
+            return Ok(num_docs_with_tokens);
+        }
+        let num_docs_with_tokens = self
+            .fieldnorm_readers
+            .get_field(field)?
+            .map(|fieldnorm_reader| fieldnorm_reader.num_docs_with_tokens())
+            .unwrap_or(self.max_doc);
+        self.num_docs_with_tokens_cache
+            .write()
+            .expect("Lock poisoned. This should never happen")
+            .insert(field, num_docs_with_tokens);
+        Ok(num_docs_with_tokens)
+    }
+
+    /// Computes the statistics of the posting lists of `field`: number of terms,
+    /// average and percentiles of the lengths of the posting lists, and the terms
+    /// with the longest ones.
+    ///
+    /// The whole term dictionary of the field is scanned.
+    pub fn inverted_index_stats(&self, field: Field) -> crate::Result<InvertedIndexStatistics> {
+        let inverted_index = self.inverted_index(field)?;
+        Ok(InvertedIndexStatistics::compute(field, &inverted_index)?)
+    }
+
     /// Returns the segment id
     pub fn segment_id(&self) -> SegmentId {
         self.segment_id
     }
 
+    /// Returns the opstamp of the last delete operation taken into
+    /// account in this segment, or `None` if the segment has no deletes.
+    pub fn delete_opstamp(&self) -> Option<Opstamp> {
+        self.delete_opstamp
+    }
+
     /// Returns the bitset representing
     /// the documents that have been deleted.
     pub fn alive_bitset(&self) -> Option<&AliveBitSet> {
@@ -322,6 +691,8 @@ impl SegmentReader {
             self.positions_composite.space_usage(),
             self.fast_fields_readers.space_usage(),
             self.fieldnorm_readers.space_usage(),
+            self.vector_readers.space_usage(),
+            self.features_readers.space_usage(),
             self.get_store_reader()?.space_usage(),
             self.alive_bitset_opt
                 .as_ref()
@@ -331,6 +702,15 @@ impl SegmentReader {
     }
 }
 
+fn open_alive_bitset(segment: &Segment) -> crate::Result<Option<AliveBitSet>> {
+    if !segment.meta().has_deletes() {
+        return Ok(None);
+    }
+    let delete_file_slice = segment.open_read(SegmentComponent::Delete)?;
+    let delete_data = delete_file_slice.read_bytes()?;
+    Ok(Some(AliveBitSet::open(delete_data)))
+}
+
 fn intersect_alive_bitset(
     left_opt: Option<AliveBitSet>,
     right_opt: Option<AliveBitSet>,
@@ -355,8 +735,13 @@ impl fmt::Debug for SegmentReader {
 #[cfg(test)]
 mod test {
     use crate::core::Index;
-    use crate::schema::{Schema, Term, STORED, TEXT};
-    use crate::DocId;
+    use crate::core::InvertedIndexReader;
+    use crate::directory::{CompositeFile, Directory, FileSlice, Footer, RamDirectory};
+    use crate::fastfield::{FastFieldReader, FastFieldReaders};
+    use crate::schema::{IndexRecordOption, Schema, Term, FAST, STORED, TEXT};
+    use crate::store::StoreReader;
+    use crate::termdict::TermDictionary;
+    use crate::{DocId, DocSet, IndexSettings, Postings, SegmentComponent, INDEX_FORMAT_VERSION};
 
     #[test]
     fn test_num_alive() -> crate::Result<()> {
@@ -415,4 +800,95 @@ mod test {
         assert_eq!(vec![0u32, 2u32], docs);
         Ok(())
     }
+
+    #[test]
+    fn test_postings_without_positions_do_not_read_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello happy tax payer"))?;
+        index_writer.add_document(doc!(text => "happy happy"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        // Positions are a separate component: an inverted index without
+        // access to them still serves the postings and term frequencies.
+        let inverted_index = InvertedIndexReader::new(
+            TermDictionary::open(segment_reader.termdict_composite.open_read(text).unwrap())?,
+            segment_reader.postings_composite.open_read(text).unwrap(),
+            FileSlice::empty(),
+            IndexRecordOption::WithFreqsAndPositions,
+            false,
+        )?;
+        let term = Term::from_field_text(text, "happy");
+        let mut postings = inverted_index
+            .read_postings(&term, IndexRecordOption::WithFreqs)?
+            .unwrap();
+        assert_eq!(postings.doc(), 0);
+        assert_eq!(postings.term_freq(), 1);
+        assert_eq!(postings.advance(), 1);
+        assert_eq!(postings.term_freq(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_segment_components_from_raw_files() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let num = schema_builder.add_u64_field("num", FAST);
+        let schema = schema_builder.build();
+        let directory = RamDirectory::create();
+        let index = Index::create(directory.clone(), schema.clone(), IndexSettings::default())?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello happy tax payer", num => 3u64))?;
+        index_writer.add_document(doc!(text => "happy happy", num => 7u64))?;
+        index_writer.commit()?;
+        let segment_meta = index.searchable_segment_metas()?[0].clone();
+
+        // The files are read directly from the directory, footer included.
+        let open_component = |component: SegmentComponent| -> crate::Result<FileSlice> {
+            let file = directory.open_read(&segment_meta.relative_path(component))?;
+            let (footer, body) = Footer::extract_footer(file)?;
+            assert_eq!(footer.version.index_format_version(), INDEX_FORMAT_VERSION);
+            Ok(body)
+        };
+        let termdict_composite = CompositeFile::open(&open_component(SegmentComponent::Terms)?)?;
+        let postings_composite = CompositeFile::open(&open_component(SegmentComponent::Postings)?)?;
+        let positions_composite =
+            CompositeFile::open(&open_component(SegmentComponent::Positions)?)?;
+        let inverted_index = InvertedIndexReader::new(
+            TermDictionary::open(termdict_composite.open_read(text).unwrap())?,
+            postings_composite.open_read(text).unwrap(),
+            positions_composite.open_read(text).unwrap(),
+            IndexRecordOption::WithFreqsAndPositions,
+            false,
+        )?;
+        assert_eq!(inverted_index.terms().num_terms(), 4);
+        let mut postings = inverted_index
+            .read_postings(
+                &Term::from_field_text(text, "happy"),
+                IndexRecordOption::WithFreqsAndPositions,
+            )?
+            .unwrap();
+        assert_eq!(postings.advance(), 1);
+        let mut positions = Vec::new();
+        postings.positions(&mut positions);
+        assert_eq!(positions, vec![0, 1]);
+
+        let fast_field_readers = FastFieldReaders::open(
+            schema,
+            CompositeFile::open(&open_component(SegmentComponent::FastFields)?)?,
+            segment_meta.max_doc(),
+        )?;
+        assert_eq!(fast_field_readers.u64(num)?.get(1), 7);
+
+        let store_reader = StoreReader::open(open_component(SegmentComponent::Store)?)?;
+        let doc = store_reader.get(0)?;
+        assert_eq!(
+            doc.get_first(text).and_then(|value| value.text()),
+            Some("hello happy tax payer")
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,210 @@
+use crate::schema::Field;
+use common::{BinarySerializable, VInt};
+use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Describes the data of a segment that should be preloaded
+/// when warming up a `SegmentReader`.
+///
+/// See [`SegmentReader::warmup`](./struct.SegmentReader.html#method.warmup) and
+/// [`SegmentReader::hot_cache`](./struct.SegmentReader.html#method.hot_cache).
+#[derive(Clone, Debug, Default)]
+pub struct WarmupSpec {
+    pub(crate) term_dict_fields: Vec<Field>,
+    pub(crate) fast_fields: Vec<Field>,
+    pub(crate) fieldnorm_fields: Vec<Field>,
+    pub(crate) store: bool,
+}
+
+impl WarmupSpec {
+    /// Creates an empty `WarmupSpec`.
+    pub fn new() -> WarmupSpec {
+        WarmupSpec::default()
+    }
+
+    /// Preloads the term dictionary of the given field.
+    pub fn term_dict(mut self, field: Field) -> WarmupSpec {
+        self.term_dict_fields.push(field);
+        self
+    }
+
+    /// Preloads the fast field columns of the given field.
+    pub fn fast_field(mut self, field: Field) -> WarmupSpec {
+        self.fast_fields.push(field);
+        self
+    }
+
+    /// Preloads the fieldnorms of the given field.
+    pub fn fieldnorms(mut self, field: Field) -> WarmupSpec {
+        self.fieldnorm_fields.push(field);
+        self
+    }
+
+    /// Preloads the index of the doc store.
+    pub fn store(mut self) -> WarmupSpec {
+        self.store = true;
+        self
+    }
+}
+
+/// List of byte ranges of the segment files that are needed to serve
+/// the data described in a `WarmupSpec`.
+///
+/// A `HotCache` can be serialized and stored alongside the index.
+/// A `Directory` for which reads are costly (e.g. a directory backed
+/// by an object storage) can then use it to prefetch these ranges
+/// before the index is opened.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HotCache {
+    ranges: Vec<(PathBuf, Range<usize>)>,
+}
+
+impl HotCache {
+    /// Creates a `HotCache` from a list of byte ranges.
+    ///
+    /// Ranges are sorted, and overlapping ranges of the same file are merged.
+    pub fn new(mut ranges: Vec<(PathBuf, Range<usize>)>) -> HotCache {
+        ranges.sort_by(|(left_path, left_range), (right_path, right_range)| {
+            (left_path, left_range.start, left_range.end).cmp(&(
+                right_path,
+                right_range.start,
+                right_range.end,
+            ))
+        });
+        let mut merged_ranges: Vec<(PathBuf, Range<usize>)> = Vec::with_capacity(ranges.len());
+        for (path, range) in ranges {
+            if range.start >= range.end {
+                continue;
+            }
+            if let Some((last_path, last_range)) = merged_ranges.last_mut() {
+                if *last_path == path && range.start <= last_range.end {
+                    last_range.end = last_range.end.max(range.end);
+                    continue;
+                }
+            }
+            merged_ranges.push((path, range));
+        }
+        HotCache {
+            ranges: merged_ranges,
+        }
+    }
+
+    /// Returns the byte ranges, sorted by file path and offset.
+    pub fn ranges(&self) -> &[(PathBuf, Range<usize>)] {
+        &self.ranges
+    }
+
+    /// Appends the ranges of another `HotCache`.
+    pub fn merge(self, other: HotCache) -> HotCache {
+        let mut ranges = self.ranges;
+        ranges.extend(other.ranges);
+        HotCache::new(ranges)
+    }
+
+    /// Serializes the `HotCache` into a byte blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)
+            .expect("Writing in a Vec<u8> should never fail");
+        buffer
+    }
+
+    /// Deserializes a `HotCache` from a byte blob produced by `to_bytes`.
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<HotCache> {
+        let num_ranges = VInt::deserialize(&mut bytes)?.val() as usize;
+        let mut ranges = Vec::with_capacity(num_ranges);
+        for _ in 0..num_ranges {
+            let path = PathBuf::from(String::deserialize(&mut bytes)?);
+            let start = VInt::deserialize(&mut bytes)?.val() as usize;
+            let len = VInt::deserialize(&mut bytes)?.val() as usize;
+            ranges.push((path, start..start + len));
+        }
+        Ok(HotCache { ranges })
+    }
+
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.ranges.len() as u64).serialize(writer)?;
+        for (path, range) in &self.ranges {
+            path.to_string_lossy().to_string().serialize(writer)?;
+            VInt(range.start as u64).serialize(writer)?;
+            VInt(range.len() as u64).serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HotCache, WarmupSpec};
+    use crate::schema::{Schema, FAST, STORED, TEXT};
+    use crate::{doc, Directory, HasLen, Index};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_hot_cache_merges_ranges() {
+        let hot_cache = HotCache::new(vec![
+            (PathBuf::from("b"), 10..20),
+            (PathBuf::from("a"), 15..30),
+            (PathBuf::from("a"), 0..10),
+            (PathBuf::from("a"), 5..12),
+            (PathBuf::from("a"), 40..40),
+        ]);
+        assert_eq!(
+            hot_cache.ranges(),
+            &[
+                (PathBuf::from("a"), 0..12),
+                (PathBuf::from("a"), 15..30),
+                (PathBuf::from("b"), 10..20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hot_cache_serialization() {
+        let hot_cache = HotCache::new(vec![
+            (PathBuf::from("a.term"), 0..12),
+            (PathBuf::from("a.fast"), 1_000..1_000_000),
+        ]);
+        let bytes = hot_cache.to_bytes();
+        assert_eq!(HotCache::from_bytes(&bytes).unwrap(), hot_cache);
+    }
+
+    #[test]
+    fn test_segment_reader_warmup() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let num = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello happy tax payer", num => 3u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+
+        assert!(segment_reader
+            .hot_cache(&WarmupSpec::new())?
+            .ranges()
+            .is_empty());
+
+        let spec = WarmupSpec::new()
+            .term_dict(text)
+            .fieldnorms(text)
+            .fast_field(num)
+            .store();
+        segment_reader.warmup(&spec)?;
+        let hot_cache = segment_reader.hot_cache(&spec)?;
+        let mut extensions: Vec<String> = hot_cache
+            .ranges()
+            .iter()
+            .map(|(path, _)| path.extension().unwrap().to_string_lossy().to_string())
+            .collect();
+        extensions.sort();
+        assert_eq!(extensions, &["fast", "fieldnorm", "store", "term"]);
+        for (path, range) in hot_cache.ranges() {
+            let file = index.directory().open_read(path)?;
+            assert!(range.end <= file.len());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+//! Inspection of the content of an index.
+//!
+//! The functions of this module are the building blocks of an index doctor tool:
+//! they dump the term dictionary of a field, the posting list of a term or a stored
+//! document in a human readable form, and check the integrity of the files of an index.
+//!
+//! ```rust
+//! use tantivy::debug;
+//! use tantivy::schema::{Schema, STORED, TEXT};
+//! use tantivy::{doc, DocAddress, Index, Term};
+//!
+//! # fn main() -> tantivy::Result<()> {
+//! let mut schema_builder = Schema::builder();
+//! let title = schema_builder.add_text_field("title", TEXT | STORED);
+//! let index = Index::create_in_ram(schema_builder.build());
+//! let mut index_writer = index.writer(50_000_000)?;
+//! index_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+//! index_writer.commit()?;
+//!
+//! let searcher = index.reader()?.searcher();
+//! let segment_reader = searcher.segment_reader(0);
+//! let mut output = Vec::new();
+//! debug::dump_terms(segment_reader, title, &mut output)?;
+//! debug::dump_postings(segment_reader, &Term::from_field_text(title, "the"), &mut output)?;
+//! debug::dump_doc(&searcher, DocAddress::new(0, 0), &mut output)?;
+//! assert!(debug::check_files(&index)?
+//!     .iter()
+//!     .all(|file_check| file_check.status == debug::FileStatus::Valid));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{TimeZone, Utc};
+
+use crate::directory::error::OpenReadError;
+use crate::postings::TermInfo;
+use crate::schema::{Facet, Field, FieldType, IndexRecordOption, Term};
+use crate::{
+    Directory, DocAddress, DocSet, Index, Postings, Searcher, SegmentComponent, SegmentMeta,
+    SegmentReader, TERMINATED,
+};
+
+/// Writes the terms of `field` in the segment, one per line, followed by
+/// their document frequency and separated by a tab.
+///
+/// Terms are written as their value, decoded according to the type of the field.
+/// The values of the bytes fields, as well as the terms that cannot be decoded, are
+/// written in hexadecimal.
+pub fn dump_terms<W: Write>(
+    segment_reader: &SegmentReader,
+    field: Field,
+    output: &mut W,
+) -> crate::Result<()> {
+    let field_type = segment_reader.schema().get_field_entry(field).field_type();
+    let inverted_index = segment_reader.inverted_index(field)?;
+    let mut stream = inverted_index.terms().stream()?;
+    while stream.try_advance()? {
+        let term_info: &TermInfo = stream.value();
+        writeln!(
+            output,
+            "{}\t{}",
+            format_term_value(field_type, stream.key()),
+            term_info.doc_freq
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the posting list of `term` in the segment, one document per line.
+///
+/// Each line holds the document id, the term frequency and the positions, as
+/// far as they are indexed, separated by tabs. The deleted documents are flagged
+/// as `deleted`.
+pub fn dump_postings<W: Write>(
+    segment_reader: &SegmentReader,
+    term: &Term,
+    output: &mut W,
+) -> crate::Result<()> {
+    let inverted_index = segment_reader.inverted_index(term.field())?;
+    let mut postings =
+        match inverted_index.read_postings(term, IndexRecordOption::WithFreqsAndPositions)? {
+            Some(postings) => postings,
+            None => return Ok(()),
+        };
+    let mut positions = Vec::new();
+    let mut doc = postings.doc();
+    while doc != TERMINATED {
+        postings.positions(&mut positions);
+        write!(output, "{}\t{}\t{:?}", doc, postings.term_freq(), positions)?;
+        if segment_reader.is_deleted(doc) {
+            write!(output, "\tdeleted")?;
+        }
+        writeln!(output)?;
+        doc = postings.advance();
+    }
+    Ok(())
+}
+
+/// Writes the stored fields of a document as a line of JSON.
+pub fn dump_doc<W: Write>(
+    searcher: &Searcher,
+    doc_address: DocAddress,
+    output: &mut W,
+) -> crate::Result<()> {
+    let doc = searcher.doc(doc_address)?;
+    writeln!(output, "{}", searcher.schema().to_json(&doc))?;
+    Ok(())
+}
+
+/// Integrity of a file of the index, as checked by [`check_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    /// The checksum of the file matches the one recorded in its footer.
+    Valid,
+    /// The checksum of the file does not match the one recorded in its footer.
+    Corrupted,
+    /// The file is referenced by the index but does not exist.
+    Missing,
+    /// The file could not be read, e.g. because its footer is damaged.
+    Unreadable(String),
+}
+
+/// Result of the check of a file of the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCheck {
+    /// Path of the file, relative to the directory of the index.
+    pub path: PathBuf,
+    /// Integrity of the file.
+    pub status: FileStatus,
+}
+
+/// Checks the checksums of all of the files of the searchable segments of the index.
+///
+/// Unlike [`Index::validate_checksum`](../struct.Index.html#method.validate_checksum),
+/// the files that are missing or unreadable are reported rather than failing the check.
+/// The optional components a segment does not have, e.g. the deletes of a segment
+/// without deleted documents, are skipped.
+pub fn check_files(index: &Index) -> crate::Result<Vec<FileCheck>> {
+    let directory = index.directory();
+    let mut file_checks = Vec::new();
+    for segment_meta in index.searchable_segment_metas()? {
+        for &component in SegmentComponent::iterator() {
+            let path = segment_meta.relative_path(component);
+            let status = if !directory.exists(&path)? {
+                if !is_required_component(&segment_meta, component) {
+                    continue;
+                }
+                FileStatus::Missing
+            } else {
+                match directory.validate_checksum(&path) {
+                    Ok(true) => FileStatus::Valid,
+                    Ok(false) => FileStatus::Corrupted,
+                    Err(OpenReadError::FileDoesNotExist(_)) => FileStatus::Missing,
+                    Err(error) => FileStatus::Unreadable(error.to_string()),
+                }
+            };
+            file_checks.push(FileCheck { path, status });
+        }
+    }
+    Ok(file_checks)
+}
+
+fn is_required_component(segment_meta: &SegmentMeta, component: SegmentComponent) -> bool {
+    match component {
+        SegmentComponent::Postings
+        | SegmentComponent::FastFields
+        | SegmentComponent::FieldNorms
+        | SegmentComponent::Terms
+        | SegmentComponent::Store => true,
+        SegmentComponent::Delete => segment_meta.has_deletes(),
+        // Positions, vectors and features are missing from the segments
+        // written by older versions.
+        SegmentComponent::Positions
+        | SegmentComponent::TempStore
+        | SegmentComponent::Vectors
+        | SegmentComponent::Features => false,
+    }
+}
+
+fn format_term_value(field_type: &FieldType, value_bytes: &[u8]) -> String {
+    let as_u64 = || -> Option<u64> {
+        let mut buffer = [0u8; 8];
+        if value_bytes.len() != buffer.len() {
+            return None;
+        }
+        buffer.copy_from_slice(value_bytes);
+        Some(u64::from_be_bytes(buffer))
+    };
+    let value_opt = match field_type {
+        FieldType::Str(_) => std::str::from_utf8(value_bytes).ok().map(str::to_string),
+        FieldType::U64(_) => as_u64().map(|val| val.to_string()),
+        FieldType::I64(_) => as_u64().map(|val| common::u64_to_i64(val).to_string()),
+        FieldType::F64(_) => as_u64().map(|val| common::u64_to_f64(val).to_string()),
+        FieldType::Date(_) => as_u64()
+            .and_then(|val| Utc.timestamp_opt(common::u64_to_i64(val), 0).single())
+            .map(|date| date.to_rfc3339()),
+        FieldType::HierarchicalFacet(_) => Facet::from_encoded(value_bytes.to_vec())
+            .ok()
+            .map(|facet| facet.to_string()),
+        FieldType::Bytes(_) | FieldType::Vector(_) | FieldType::Features(_) => None,
+    };
+    value_opt.unwrap_or_else(|| {
+        value_bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_files, dump_doc, dump_postings, dump_terms, FileStatus};
+    use crate::schema::{Facet, Schema, INDEXED, STORED, TEXT};
+    use crate::{doc, Directory, DocAddress, Index, SegmentComponent, Term};
+
+    #[test]
+    fn test_dump_terms_and_postings() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let num = schema_builder.add_i64_field("num", INDEXED);
+        let facet = schema_builder.add_facet_field("facet", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello happy world", num => -3i64))?;
+        index_writer.add_document(doc!(
+            text => "happy happy",
+            num => 7i64,
+            facet => Facet::from("/category/book")
+        ))?;
+        index_writer.add_document(doc!(text => "bye happy"))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(text, "bye"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+
+        let dump_terms_string = |field| {
+            let mut output = Vec::new();
+            dump_terms(segment_reader, field, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(
+            dump_terms_string(text),
+            "bye\t1\nhappy\t3\nhello\t1\nworld\t1\n"
+        );
+        assert_eq!(dump_terms_string(num), "-3\t1\n7\t1\n");
+        assert_eq!(
+            dump_terms_string(facet),
+            "/\t1\n/category\t1\n/category/book\t1\n"
+        );
+
+        let mut output = Vec::new();
+        dump_postings(
+            segment_reader,
+            &Term::from_field_text(text, "happy"),
+            &mut output,
+        )?;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0\t1\t[1]\n1\t2\t[0, 1]\n2\t1\t[1]\tdeleted\n"
+        );
+        let mut output = Vec::new();
+        dump_postings(
+            segment_reader,
+            &Term::from_field_text(text, "unknown"),
+            &mut output,
+        )?;
+        assert!(output.is_empty());
+
+        let mut output = Vec::new();
+        dump_doc(&searcher, DocAddress::new(0, 1), &mut output)?;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"text\":[\"happy happy\"]}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_files() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello"))?;
+        index_writer.commit()?;
+        let file_checks = check_files(&index)?;
+        assert!(!file_checks.is_empty());
+        assert!(file_checks
+            .iter()
+            .all(|file_check| file_check.status == FileStatus::Valid));
+
+        let segment_meta = index.searchable_segment_metas()?[0].clone();
+        let store_path = segment_meta.relative_path(SegmentComponent::Store);
+        let mut corrupted_bytes = index.directory().atomic_read(&store_path)?;
+        corrupted_bytes[0] ^= 1;
+        index
+            .directory()
+            .atomic_write(&store_path, &corrupted_bytes)?;
+        index
+            .directory()
+            .delete(&segment_meta.relative_path(SegmentComponent::FieldNorms))
+            .unwrap();
+        let file_checks = check_files(&index)?;
+        let status = |component| {
+            let path = segment_meta.relative_path(component);
+            file_checks
+                .iter()
+                .find(|file_check| file_check.path == path)
+                .map(|file_check| file_check.status.clone())
+                .unwrap()
+        };
+        assert_eq!(status(SegmentComponent::Store), FileStatus::Corrupted);
+        assert_eq!(status(SegmentComponent::FieldNorms), FileStatus::Missing);
+        assert_eq!(status(SegmentComponent::Terms), FileStatus::Valid);
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use crate::directory::{FileHandle, OwnedBytes};
+use common::HasLen;
+use futures::future::{self, BoxFuture};
+use lru::LruCache;
+use std::fmt;
+use std::io;
+use std::ops::{Range, RangeInclusive};
+use std::sync::Mutex;
+
+/// `FileHandle` wrapper that reads the underlying file by fixed size blocks,
+/// and keeps the most recently used blocks in memory.
+///
+/// It is meant to be used by `Directory` implementations for which a read is
+/// costly, for instance a read-only directory backed by an object storage (S3, GCS...)
+/// where every read is a byte-range request.
+/// Their `Directory::get_file_handle` can simply wrap the file handle
+/// they would return in a `CachingFileHandle`.
+///
+/// The blocks missing from the cache are read with `FileHandle::read_bytes_async`
+/// by `read_bytes_async`, and concurrently. A read spanning more blocks than the
+/// cache holds bypasses the cache: it is forwarded as is to the underlying file
+/// handle, and does not evict the cached blocks.
+pub struct CachingFileHandle {
+    underlying: Box<dyn FileHandle>,
+    block_len: usize,
+    num_blocks: usize,
+    cache: Mutex<LruCache<usize, OwnedBytes>>,
+}
+
+impl CachingFileHandle {
+    /// Wraps a `FileHandle`, caching at most `num_blocks` blocks of `block_len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_len` or `num_blocks` is 0.
+    pub fn new(
+        underlying: Box<dyn FileHandle>,
+        block_len: usize,
+        num_blocks: usize,
+    ) -> CachingFileHandle {
+        assert!(block_len > 0, "The block length must be greater than 0.");
+        assert!(
+            num_blocks > 0,
+            "The number of cached blocks must be greater than 0."
+        );
+        CachingFileHandle {
+            underlying,
+            block_len,
+            num_blocks,
+            cache: Mutex::new(LruCache::new(num_blocks)),
+        }
+    }
+
+    /// Returns the ids of the blocks covering the non-empty `range`.
+    fn block_ids(&self, range: &Range<usize>) -> RangeInclusive<usize> {
+        range.start / self.block_len..=(range.end - 1) / self.block_len
+    }
+
+    /// Returns true if the blocks covering `range` do not fit in the cache.
+    fn bypasses_cache(&self, block_ids: &RangeInclusive<usize>) -> bool {
+        block_ids.end() - block_ids.start() >= self.num_blocks
+    }
+
+    fn block_range(&self, block_id: usize) -> Range<usize> {
+        let start = block_id * self.block_len;
+        start..(start + self.block_len).min(self.underlying.len())
+    }
+
+    fn cached_block(&self, block_id: usize) -> Option<OwnedBytes> {
+        self.cache
+            .lock()
+            .expect("Block cache lock poisoned.")
+            .get(&block_id)
+            .cloned()
+    }
+
+    fn cache_block(&self, block_id: usize, block: OwnedBytes) {
+        self.cache
+            .lock()
+            .expect("Block cache lock poisoned.")
+            .put(block_id, block);
+    }
+
+    fn read_block(&self, block_id: usize) -> io::Result<OwnedBytes> {
+        if let Some(block) = self.cached_block(block_id) {
+            return Ok(block);
+        }
+        let block = self.underlying.read_bytes(self.block_range(block_id))?;
+        self.cache_block(block_id, block.clone());
+        Ok(block)
+    }
+
+    async fn read_block_async(&self, block_id: usize) -> io::Result<OwnedBytes> {
+        if let Some(block) = self.cached_block(block_id) {
+            return Ok(block);
+        }
+        let block = self
+            .underlying
+            .read_bytes_async(self.block_range(block_id))
+            .await?;
+        self.cache_block(block_id, block.clone());
+        Ok(block)
+    }
+
+    /// Extracts `range` from the consecutive `blocks` covering it.
+    fn extract_range(&self, range: Range<usize>, blocks: &[OwnedBytes]) -> OwnedBytes {
+        let offset = (range.start / self.block_len) * self.block_len;
+        if let [block] = blocks {
+            // The common case: the range is contained in a single block.
+            // We can return a slice of the block without any copy.
+            return block.slice(range.start - offset..range.end - offset);
+        }
+        let mut buffer = Vec::with_capacity(range.len());
+        for (block_ord, block) in blocks.iter().enumerate() {
+            let block_start = offset + block_ord * self.block_len;
+            let start = range.start.max(block_start) - block_start;
+            let end = range.end.min(block_start + block.len()) - block_start;
+            buffer.extend_from_slice(&block.as_slice()[start..end]);
+        }
+        OwnedBytes::new(buffer)
+    }
+
+    async fn read_bytes_cached_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        let blocks = future::try_join_all(
+            self.block_ids(&range)
+                .map(|block_id| self.read_block_async(block_id)),
+        )
+        .await?;
+        Ok(self.extract_range(range, &blocks))
+    }
+}
+
+impl fmt::Debug for CachingFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CachingFileHandle({:?}, block_len={})",
+            self.underlying, self.block_len
+        )
+    }
+}
+
+impl HasLen for CachingFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+impl FileHandle for CachingFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.start >= range.end {
+            return Ok(OwnedBytes::empty());
+        }
+        let block_ids = self.block_ids(&range);
+        if self.bypasses_cache(&block_ids) {
+            return self.underlying.read_bytes(range);
+        }
+        let blocks = block_ids
+            .map(|block_id| self.read_block(block_id))
+            .collect::<io::Result<Vec<OwnedBytes>>>()?;
+        Ok(self.extract_range(range, &blocks))
+    }
+
+    fn read_bytes_async(&self, range: Range<usize>) -> BoxFuture<'_, io::Result<OwnedBytes>> {
+        if range.start >= range.end {
+            return Box::pin(future::ok(OwnedBytes::empty()));
+        }
+        if self.bypasses_cache(&self.block_ids(&range)) {
+            return self.underlying.read_bytes_async(range);
+        }
+        Box::pin(self.read_bytes_cached_async(range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachingFileHandle;
+    use crate::directory::{FileHandle, FileSlice, OwnedBytes};
+    use common::HasLen;
+    use futures::executor::block_on;
+    use std::io;
+    use std::ops::Range;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct CountingFileHandle {
+        data: OwnedBytes,
+        num_reads: Arc<AtomicUsize>,
+    }
+
+    impl HasLen for CountingFileHandle {
+        fn len(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl FileHandle for CountingFileHandle {
+        fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+            self.num_reads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.data.slice(range))
+        }
+    }
+
+    fn make_file_handle(num_bytes: usize) -> (Vec<u8>, Arc<AtomicUsize>, CachingFileHandle) {
+        let data: Vec<u8> = (0..num_bytes).map(|i| (i % 251) as u8).collect();
+        let num_reads = Arc::new(AtomicUsize::default());
+        let underlying = CountingFileHandle {
+            data: OwnedBytes::new(data.clone()),
+            num_reads: num_reads.clone(),
+        };
+        let file_handle = CachingFileHandle::new(Box::new(underlying), 10, 2);
+        (data, num_reads, file_handle)
+    }
+
+    #[test]
+    fn test_caching_file_handle_reads() -> io::Result<()> {
+        let (data, _, file_handle) = make_file_handle(95);
+        assert_eq!(file_handle.len(), 95);
+        for start in 0..95 {
+            for end in start..=95 {
+                assert_eq!(
+                    file_handle.read_bytes(start..end)?.as_slice(),
+                    &data[start..end]
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_handle_caches_blocks() -> io::Result<()> {
+        let (_, num_reads, file_handle) = make_file_handle(95);
+        file_handle.read_bytes(3..5)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 1);
+        file_handle.read_bytes(0..10)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 1);
+        file_handle.read_bytes(5..15)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 2);
+        // Block 0 is evicted.
+        file_handle.read_bytes(25..26)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 3);
+        file_handle.read_bytes(12..14)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 3);
+        file_handle.read_bytes(0..1)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_handle_reads_async() -> io::Result<()> {
+        let (data, _, file_handle) = make_file_handle(95);
+        for start in 0..95 {
+            for end in start..=95 {
+                let bytes = block_on(file_handle.read_bytes_async(start..end))?;
+                assert_eq!(bytes.as_slice(), &data[start..end]);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_handle_caches_blocks_async() -> io::Result<()> {
+        let (_, num_reads, file_handle) = make_file_handle(95);
+        block_on(file_handle.read_bytes_async(5..15))?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 2);
+        block_on(file_handle.read_bytes_async(3..12))?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 2);
+        file_handle.read_bytes(0..20)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_handle_bypasses_cache_for_large_reads() -> io::Result<()> {
+        let (data, num_reads, file_handle) = make_file_handle(95);
+        file_handle.read_bytes(3..15)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 2);
+        assert_eq!(file_handle.read_bytes(0..35)?.as_slice(), &data[0..35]);
+        assert_eq!(num_reads.load(Ordering::SeqCst), 3);
+        let bytes = block_on(file_handle.read_bytes_async(0..35))?;
+        assert_eq!(bytes.as_slice(), &data[0..35]);
+        assert_eq!(num_reads.load(Ordering::SeqCst), 4);
+        // The cached blocks were not evicted.
+        file_handle.read_bytes(0..20)?;
+        assert_eq!(num_reads.load(Ordering::SeqCst), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_file_handle_in_file_slice() -> io::Result<()> {
+        let (data, _, file_handle) = make_file_handle(95);
+        let file_slice = FileSlice::new(Box::new(file_handle));
+        let (left, right) = file_slice.split(42);
+        assert_eq!(left.read_bytes()?.as_slice(), &data[..42]);
+        assert_eq!(right.read_bytes_slice(8..20)?.as_slice(), &data[50..62]);
+        let bytes = block_on(right.read_bytes_slice_async(8..20))?;
+        assert_eq!(bytes.as_slice(), &data[50..62]);
+        Ok(())
+    }
+}
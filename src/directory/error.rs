@@ -90,6 +90,12 @@ pub enum Incompatibility {
         /// Version the index was built with
         index_version: Version,
     },
+    /// A segment was written with a codec that is not registered in the
+    /// `CodecRegistry` of the index
+    UnknownCodec {
+        /// Name of the codec recorded in the segment meta
+        codec_name: String,
+    },
 }
 
 impl fmt::Debug for Incompatibility {
@@ -126,6 +132,14 @@ impl fmt::Debug for Incompatibility {
                 );
                 write!(f, "{}. {}", err, advice)?;
             }
+            Incompatibility::UnknownCodec { codec_name } => {
+                write!(
+                    f,
+                    "Segment was written with the codec {:?}, which is not registered. Register \
+                     it in the codec registry of the index before opening its segments",
+                    codec_name
+                )?;
+            }
         }
 
         Ok(())
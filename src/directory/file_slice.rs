@@ -2,6 +2,7 @@ use stable_deref_trait::StableDeref;
 
 use crate::directory::OwnedBytes;
 use common::HasLen;
+use futures::future::BoxFuture;
 use std::fmt;
 use std::ops::Range;
 use std::sync::{Arc, Weak};
@@ -23,6 +24,15 @@ pub trait FileHandle: 'static + Send + Sync + HasLen + fmt::Debug {
     ///
     /// This method may panic if the range requested is invalid.
     fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes>;
+
+    /// Reads a slice of bytes asynchronously.
+    ///
+    /// The default implementation calls `read_bytes` when polled. File handles whose
+    /// reads are slow, e.g. byte-range requests to an object storage, should
+    /// override it so that the reads do not block the executor.
+    fn read_bytes_async(&self, range: Range<usize>) -> BoxFuture<'_, io::Result<OwnedBytes>> {
+        Box::pin(async move { self.read_bytes(range) })
+    }
 }
 
 impl FileHandle for &'static [u8] {
@@ -73,6 +83,11 @@ impl FileSlice {
         }
     }
 
+    /// Returns the range of bytes of the underlying `FileHandle` covered by this slice.
+    pub(crate) fn byte_range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
     /// Creates a fileslice that is just a view over a slice of the data.
     ///
     /// # Panics
@@ -102,18 +117,32 @@ impl FileSlice {
         self.data.read_bytes(self.range.clone())
     }
 
+    /// Asynchronous version of `read_bytes`.
+    pub async fn read_bytes_async(&self) -> io::Result<OwnedBytes> {
+        self.data.read_bytes_async(self.range.clone()).await
+    }
+
     /// Reads a specific slice of data.
     ///
     /// This is equivalent to running `file_slice.slice(from, to).read_bytes()`.
     pub fn read_bytes_slice(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.data.read_bytes(self.absolute_range(range))
+    }
+
+    /// Asynchronous version of `read_bytes_slice`.
+    pub async fn read_bytes_slice_async(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.data.read_bytes_async(self.absolute_range(range)).await
+    }
+
+    /// Returns the range of the underlying `FileHandle` of a range of this slice.
+    fn absolute_range(&self, range: Range<usize>) -> Range<usize> {
         assert!(
             range.end <= self.len(),
             "end of requested range exceeds the fileslice length ({} > {})",
             range.end,
             self.len()
         );
-        self.data
-            .read_bytes(self.range.start + range.start..self.range.start + range.end)
+        self.range.start + range.start..self.range.start + range.end
     }
 
     /// Splits the FileSlice at the given offset and return two file slices.
@@ -161,6 +190,10 @@ impl FileHandle for FileSlice {
     fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
         self.read_bytes_slice(range)
     }
+
+    fn read_bytes_async(&self, range: Range<usize>) -> BoxFuture<'_, io::Result<OwnedBytes>> {
+        Box::pin(self.read_bytes_slice_async(range))
+    }
 }
 
 impl HasLen for FileSlice {
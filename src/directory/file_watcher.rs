@@ -40,11 +40,17 @@ impl FileWatcher {
         let callbacks = self.callbacks.clone();
         let state = self.state.clone();
 
+        // The file content at the time the watcher is started is considered as
+        // already seen. Callbacks are only triggered by subsequent modifications.
+        let mut current_checksum = if path.exists() {
+            FileWatcher::compute_checksum(&path).ok()
+        } else {
+            None
+        };
+
         thread::Builder::new()
             .name("thread-tantivy-meta-file-watcher".to_string())
             .spawn(move || {
-                let mut current_checksum = None;
-
                 while state.load(Ordering::SeqCst) == 1 {
                     if let Ok(checksum) = FileWatcher::compute_checksum(&path) {
                         // `None.unwrap_or_else(|| !checksum) != checksum` evaluates to `true`
@@ -144,6 +150,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_file_watcher_ignores_existing_content() -> crate::Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let tmp_file = tmp_dir.path().join("watched.txt");
+        atomic_write(&tmp_file, b"foo")?;
+
+        let (tx, rx) = crossbeam::channel::unbounded();
+        let timeout = Duration::from_millis(100);
+
+        let watcher = FileWatcher::new(&tmp_file);
+        let _handle = watcher.watch(WatchCallback::new(move || {
+            tx.send(()).unwrap();
+        }));
+
+        assert!(rx.recv_timeout(timeout).is_err());
+
+        atomic_write(&tmp_file, b"bar")?;
+        assert_eq!(rx.recv_timeout(timeout), Ok(()));
+
+        Ok(())
+    }
+
     #[test]
     fn test_file_watcher_drop_handle() -> crate::Result<()> {
         let tmp_dir = tempfile::TempDir::new()?;
@@ -19,22 +19,30 @@ const FOOTER_MAGIC_NUMBER: u32 = 1337;
 type CrcHashU32 = u32;
 
 /// A Footer is appended to every file
+///
+/// The files opened through an `Index` have their footer stripped. External tools
+/// reading the files of a segment directly must strip it themselves, with
+/// [`Footer::extract_footer`], before handing them to the readers of the segment components.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Footer {
+    /// Version of tantivy that wrote the file.
     pub version: Version,
+    /// Checksum of the body of the file.
     pub crc: CrcHashU32,
 }
 
 impl Footer {
-    pub fn new(crc: CrcHashU32) -> Self {
+    pub(crate) fn new(crc: CrcHashU32) -> Self {
         let version = crate::VERSION.clone();
         Footer { version, crc }
     }
 
+    /// Returns the checksum of the body of the file.
     pub fn crc(&self) -> CrcHashU32 {
         self.crc
     }
-    pub fn append_footer<W: io::Write>(&self, mut write: &mut W) -> io::Result<()> {
+
+    pub(crate) fn append_footer<W: io::Write>(&self, mut write: &mut W) -> io::Result<()> {
         let mut counting_write = CountingWriter::wrap(&mut write);
         counting_write.write_all(serde_json::to_string(&self)?.as_ref())?;
         let footer_payload_len = counting_write.written_bytes();
@@ -43,18 +51,20 @@ impl Footer {
         Ok(())
     }
 
+    /// Splits a file into its footer and its body.
     pub fn extract_footer(file: FileSlice) -> io::Result<(Footer, FileSlice)> {
-        if file.len() < 4 {
+        let footer_metadata_len = <(u32, u32)>::SIZE_IN_BYTES;
+        if file.len() < footer_metadata_len {
             return Err(io::Error::new(
                 io::ErrorKind::UnexpectedEof,
                 format!(
-                    "File corrupted. The file is smaller than 4 bytes (len={}).",
+                    "File corrupted. The file is smaller than {} bytes (len={}).",
+                    footer_metadata_len,
                     file.len()
                 ),
             ));
         }
 
-        let footer_metadata_len = <(u32, u32)>::SIZE_IN_BYTES;
         let (footer_len, footer_magic_byte): (u32, u32) = file
             .slice_from_end(footer_metadata_len)
             .read_bytes()?
@@ -101,7 +111,7 @@ impl Footer {
     /// Has to be called after `extract_footer` to make sure it's not accessing uninitialised memory
     pub fn is_compatible(&self) -> Result<(), Incompatibility> {
         let library_version = crate::version();
-        if self.version.index_format_version < 4
+        if self.version.index_format_version < 6
             || self.version.index_format_version > INDEX_FORMAT_VERSION
         {
             return Err(Incompatibility::IndexMismatch {
@@ -154,6 +164,7 @@ impl<W: TerminatingWrite> TerminatingWrite for FooterProxy<W> {
 #[cfg(test)]
 mod tests {
 
+    use crate::directory::error::Incompatibility;
     use crate::directory::footer::Footer;
     use crate::directory::OwnedBytes;
     use crate::directory::{footer::FOOTER_MAGIC_NUMBER, FileSlice};
@@ -170,6 +181,19 @@ mod tests {
         let (footer_deser, _body) = Footer::extract_footer(fileslice).unwrap();
         assert_eq!(footer_deser.crc(), footer.crc());
     }
+
+    #[test]
+    fn test_footer_of_previous_index_format_is_incompatible() {
+        let mut footer = Footer::new(123);
+        assert!(footer.is_compatible().is_ok());
+        // The layout of the term infos changed in the index format 6.
+        footer.version.index_format_version = 5;
+        assert!(matches!(
+            footer.is_compatible(),
+            Err(Incompatibility::IndexMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_deserialize_footer_missing_magic_byte() {
         let mut buf: Vec<u8> = vec![];
@@ -204,6 +228,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_footer_truncated_file() {
+        let owned_bytes = OwnedBytes::new(vec![0u8; 5]);
+        let fileslice = FileSlice::new(Box::new(owned_bytes));
+        let err = Footer::extract_footer(fileslice).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            "File corrupted. The file is smaller than 8 bytes (len=5)."
+        );
+    }
+
     #[test]
     fn test_deserialize_too_large_footer() {
         let mut buf: Vec<u8> = vec![];
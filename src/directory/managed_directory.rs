@@ -11,7 +11,7 @@ use crate::error::DataCorruption;
 use crate::Directory;
 
 use crc32fast::Hasher;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -48,6 +48,34 @@ pub struct ManagedDirectory {
 #[derive(Debug, Default)]
 struct MetaInformation {
     managed_paths: HashSet<PathBuf>,
+    // Files that must not be garbage collected, with the number of pins
+    // held on each of them.
+    pinned_paths: HashMap<PathBuf, usize>,
+}
+
+/// Prevents the garbage collection of a set of files until it is dropped.
+///
+/// See [`ManagedDirectory::pin_files`](./struct.ManagedDirectory.html#method.pin_files).
+pub(crate) struct FilePin {
+    meta_informations: Arc<RwLock<MetaInformation>>,
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for FilePin {
+    fn drop(&mut self) {
+        let mut meta_wlock = self
+            .meta_informations
+            .write()
+            .expect("Managed file lock poisoned");
+        for path in &self.paths {
+            if let Some(num_pins) = meta_wlock.pinned_paths.get_mut(path) {
+                *num_pins -= 1;
+                if *num_pins == 0 {
+                    meta_wlock.pinned_paths.remove(path);
+                }
+            }
+        }
+    }
 }
 
 /// Saves the file containing the list of existing files
@@ -79,6 +107,7 @@ impl ManagedDirectory {
                     directory,
                     meta_informations: Arc::new(RwLock::new(MetaInformation {
                         managed_paths: managed_files,
+                        pinned_paths: HashMap::new(),
                     })),
                 })
             }
@@ -142,7 +171,11 @@ impl ManagedDirectory {
                 Ok(_meta_lock) => {
                     let living_files = get_living_files();
                     for managed_path in &meta_informations_rlock.managed_paths {
-                        if !living_files.contains(managed_path) {
+                        if !living_files.contains(managed_path)
+                            && !meta_informations_rlock
+                                .pinned_paths
+                                .contains_key(managed_path)
+                        {
                             files_to_delete.push(managed_path.clone());
                         }
                     }
@@ -213,7 +246,7 @@ impl ManagedDirectory {
     /// File starting by "." are reserved to locks.
     /// They are not managed and cannot be subjected
     /// to garbage collection.
-    fn register_file_as_managed(&self, filepath: &Path) -> io::Result<()> {
+    pub(crate) fn register_file_as_managed(&self, filepath: &Path) -> io::Result<()> {
         // Files starting by "." (e.g. lock files) are not managed.
         if !is_managed(filepath) {
             return Ok(());
@@ -262,6 +295,31 @@ impl ManagedDirectory {
         Ok(footer.crc() == crc)
     }
 
+    /// Protects files from garbage collection until the returned `FilePin`
+    /// is dropped.
+    pub(crate) fn pin_files(&self, paths: Vec<PathBuf>) -> FilePin {
+        let mut meta_wlock = self
+            .meta_informations
+            .write()
+            .expect("Managed file lock poisoned");
+        for path in &paths {
+            *meta_wlock.pinned_paths.entry(path.clone()).or_insert(0) += 1;
+        }
+        FilePin {
+            meta_informations: self.meta_informations.clone(),
+            paths,
+        }
+    }
+
+    /// Opens a file without stripping its footer, so that it can be copied
+    /// verbatim to another directory.
+    pub(crate) fn open_read_with_footer(
+        &self,
+        path: &Path,
+    ) -> result::Result<FileSlice, OpenReadError> {
+        self.directory.open_read(path)
+    }
+
     /// List all managed files
     pub fn list_managed_files(&self) -> HashSet<PathBuf> {
         let managed_paths = self
@@ -1,3 +1,4 @@
+use crate::core::SegmentComponent;
 use crate::core::META_FILEPATH;
 use crate::directory::error::LockError;
 use crate::directory::error::{DeleteError, OpenDirectoryError, OpenReadError, OpenWriteError};
@@ -14,17 +15,18 @@ use fs2::FileExt;
 use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
 use stable_deref_trait::StableDeref;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::fmt;
 use std::fs::OpenOptions;
 use std::fs::{self, File};
 use std::io::{self, Seek, SeekFrom};
 use std::io::{BufWriter, Read, Write};
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::Arc;
 use std::sync::RwLock;
-use std::{collections::HashMap, ops::Deref};
 use tempfile::TempDir;
 
 /// Create a default io error given a string.
@@ -32,9 +34,109 @@ pub(crate) fn make_io_err(msg: String) -> io::Error {
     io::Error::new(io::ErrorKind::Other, msg)
 }
 
+/// Access pattern hint given to the kernel with `madvise`, for the memory mapped
+/// files of a segment component.
+///
+/// The hints are ignored on platforms other than unix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MmapAdvice {
+    /// No special treatment. This is the default.
+    Normal,
+    /// The pages are read in sequential order: they can be aggressively read ahead,
+    /// and freed soon after being read. This suits components read by merges,
+    /// e.g. the postings on an indexing node.
+    Sequential,
+    /// The pages are read in random order, so that reading ahead is useless.
+    /// This suits the doc store, read one document at a time.
+    Random,
+    /// The pages will be read soon, and are read ahead right away.
+    WillNeed,
+}
+
+#[cfg(unix)]
+impl From<MmapAdvice> for memmap2::Advice {
+    fn from(advice: MmapAdvice) -> memmap2::Advice {
+        match advice {
+            MmapAdvice::Normal => memmap2::Advice::Normal,
+            MmapAdvice::Sequential => memmap2::Advice::Sequential,
+            MmapAdvice::Random => memmap2::Advice::Random,
+            MmapAdvice::WillNeed => memmap2::Advice::WillNeed,
+        }
+    }
+}
+
+/// Settings of the memory mapping of the files of a `MmapDirectory`,
+/// per segment component.
+///
+/// They apply to the files mapped by the directory, which are the files of the segments.
+#[derive(Clone, Debug, Default)]
+pub struct MmapSettings {
+    advices: HashMap<SegmentComponent, MmapAdvice>,
+    locked_components: HashSet<SegmentComponent>,
+}
+
+impl MmapSettings {
+    /// Sets the `madvise` hint of the files of `component`.
+    pub fn with_advice(mut self, component: SegmentComponent, advice: MmapAdvice) -> MmapSettings {
+        self.advices.insert(component, advice);
+        self
+    }
+
+    /// Locks the files of `component` in RAM with `mlock`, so that they are never
+    /// paged out, e.g. for the term dictionaries of a large index that is
+    /// rarely searched.
+    ///
+    /// Locking is limited by `RLIMIT_MEMLOCK`: if a file cannot be locked, a warning is
+    /// logged and the file is read without being locked.
+    /// Locking is ignored on platforms other than unix.
+    pub fn with_locked(mut self, component: SegmentComponent) -> MmapSettings {
+        self.locked_components.insert(component);
+        self
+    }
+
+    /// Returns the `madvise` hint of the files of `component`.
+    pub fn advice(&self, component: SegmentComponent) -> MmapAdvice {
+        self.advices
+            .get(&component)
+            .copied()
+            .unwrap_or(MmapAdvice::Normal)
+    }
+
+    /// Returns true iff the files of `component` are locked in RAM.
+    pub fn is_locked(&self, component: SegmentComponent) -> bool {
+        self.locked_components.contains(&component)
+    }
+}
+
+#[cfg(unix)]
+fn apply_mmap_settings(mmap: &mut Mmap, full_path: &Path, mmap_settings: &MmapSettings) {
+    let component = if let Some(component) = SegmentComponent::from_path(full_path) {
+        component
+    } else {
+        return;
+    };
+    let advice = mmap_settings.advice(component);
+    if advice != MmapAdvice::Normal {
+        if let Err(io_err) = mmap.advise(advice.into()) {
+            warn!("Failed to madvise {:?}: {:?}", full_path, io_err);
+        }
+    }
+    if mmap_settings.is_locked(component) {
+        if let Err(io_err) = mmap.lock() {
+            warn!("Failed to lock {:?} in RAM: {:?}", full_path, io_err);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_mmap_settings(_mmap: &mut Mmap, _full_path: &Path, _mmap_settings: &MmapSettings) {}
+
 /// Returns None iff the file exists, can be read, but is empty (and hence
 /// cannot be mmapped)
-fn open_mmap(full_path: &Path) -> result::Result<Option<Mmap>, OpenReadError> {
+fn open_mmap(
+    full_path: &Path,
+    mmap_settings: &MmapSettings,
+) -> result::Result<Option<Mmap>, OpenReadError> {
     let file = File::open(full_path).map_err(|io_err| {
         if io_err.kind() == io::ErrorKind::NotFound {
             OpenReadError::FileDoesNotExist(full_path.to_path_buf())
@@ -52,11 +154,12 @@ fn open_mmap(full_path: &Path) -> result::Result<Option<Mmap>, OpenReadError> {
         // instead.
         return Ok(None);
     }
-    unsafe {
+    let mut mmap = unsafe {
         memmap2::Mmap::map(&file)
-            .map(Some)
-            .map_err(|io_err| OpenReadError::wrap_io_error(io_err, full_path.to_path_buf()))
-    }
+            .map_err(|io_err| OpenReadError::wrap_io_error(io_err, full_path.to_path_buf()))?
+    };
+    apply_mmap_settings(&mut mmap, full_path, mmap_settings);
+    Ok(Some(mmap))
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -102,7 +205,11 @@ impl MmapCache {
     }
 
     // Returns None if the file exists but as a len of 0 (and hence is not mmappable).
-    fn get_mmap(&mut self, full_path: &Path) -> Result<Option<ArcBytes>, OpenReadError> {
+    fn get_mmap(
+        &mut self,
+        full_path: &Path,
+        mmap_settings: &MmapSettings,
+    ) -> Result<Option<ArcBytes>, OpenReadError> {
         if let Some(mmap_weak) = self.cache.get(full_path) {
             if let Some(mmap_arc) = mmap_weak.upgrade() {
                 self.counters.hit += 1;
@@ -111,7 +218,7 @@ impl MmapCache {
         }
         self.cache.remove(full_path);
         self.counters.miss += 1;
-        let mmap_opt = open_mmap(full_path)?;
+        let mmap_opt = open_mmap(full_path, mmap_settings)?;
         Ok(mmap_opt.map(|mmap| {
             let mmap_arc: ArcBytes = Arc::new(mmap);
             let mmap_weak = Arc::downgrade(&mmap_arc);
@@ -140,14 +247,20 @@ pub struct MmapDirectory {
 
 struct MmapDirectoryInner {
     root_path: PathBuf,
+    mmap_settings: MmapSettings,
     mmap_cache: RwLock<MmapCache>,
     _temp_directory: Option<TempDir>,
     watcher: FileWatcher,
 }
 
 impl MmapDirectoryInner {
-    fn new(root_path: PathBuf, temp_directory: Option<TempDir>) -> MmapDirectoryInner {
+    fn new(
+        root_path: PathBuf,
+        temp_directory: Option<TempDir>,
+        mmap_settings: MmapSettings,
+    ) -> MmapDirectoryInner {
         MmapDirectoryInner {
+            mmap_settings,
             mmap_cache: Default::default(),
             _temp_directory: temp_directory,
             watcher: FileWatcher::new(&root_path.join(*META_FILEPATH)),
@@ -167,8 +280,12 @@ impl fmt::Debug for MmapDirectory {
 }
 
 impl MmapDirectory {
-    fn new(root_path: PathBuf, temp_directory: Option<TempDir>) -> MmapDirectory {
-        let inner = MmapDirectoryInner::new(root_path, temp_directory);
+    fn new(
+        root_path: PathBuf,
+        temp_directory: Option<TempDir>,
+        mmap_settings: MmapSettings,
+    ) -> MmapDirectory {
+        let inner = MmapDirectoryInner::new(root_path, temp_directory, mmap_settings);
         MmapDirectory {
             inner: Arc::new(inner),
         }
@@ -183,6 +300,7 @@ impl MmapDirectory {
         Ok(MmapDirectory::new(
             tempdir.path().to_path_buf(),
             Some(tempdir),
+            MmapSettings::default(),
         ))
     }
 
@@ -191,6 +309,18 @@ impl MmapDirectory {
     /// Returns an error if the `directory_path` does not
     /// exist or if it is not a directory.
     pub fn open<P: AsRef<Path>>(directory_path: P) -> Result<MmapDirectory, OpenDirectoryError> {
+        MmapDirectory::open_with_settings(directory_path, MmapSettings::default())
+    }
+
+    /// Opens a MmapDirectory in a directory, mapping the segment files
+    /// according to `mmap_settings`.
+    ///
+    /// Returns an error if the `directory_path` does not
+    /// exist or if it is not a directory.
+    pub fn open_with_settings<P: AsRef<Path>>(
+        directory_path: P,
+        mmap_settings: MmapSettings,
+    ) -> Result<MmapDirectory, OpenDirectoryError> {
         let directory_path: &Path = directory_path.as_ref();
         if !directory_path.exists() {
             return Err(OpenDirectoryError::DoesNotExist(PathBuf::from(
@@ -205,7 +335,7 @@ impl MmapDirectory {
                 directory_path,
             )));
         }
-        Ok(MmapDirectory::new(canonical_path, None))
+        Ok(MmapDirectory::new(canonical_path, None, mmap_settings))
     }
 
     /// Joins a relative_path to the directory `root_path`
@@ -329,7 +459,7 @@ impl Directory for MmapDirectory {
         })?;
 
         let owned_bytes = mmap_cache
-            .get_mmap(&full_path)?
+            .get_mmap(&full_path, &self.inner.mmap_settings)?
             .map(|mmap_arc| {
                 let mmap_arc_obj = MmapArc(mmap_arc);
                 OwnedBytes::new(mmap_arc_obj)
@@ -432,7 +562,15 @@ impl Directory for MmapDirectory {
         if lock.is_blocking {
             file.lock_exclusive().map_err(LockError::IoError)?;
         } else {
-            file.try_lock_exclusive().map_err(|_| LockError::LockBusy)?
+            file.try_lock_exclusive().map_err(|io_error| {
+                // Only a contended lock is reported as busy. Other errors (e.g. a
+                // filesystem that does not support locking) are surfaced as is.
+                if io_error.kind() == fs2::lock_contended_error().kind() {
+                    LockError::LockBusy
+                } else {
+                    LockError::IoError(io_error)
+                }
+            })?
         }
         // dropping the file handle will release the lock.
         Ok(DirectoryLock::from(Box::new(ReleaseLockFile {
@@ -477,13 +615,18 @@ mod tests {
     // The following tests are specific to the MmapDirectory
 
     use super::*;
+    use crate::collector::Count;
+    use crate::fastfield::FastFieldReader;
     use crate::indexer::LogMergePolicy;
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, FAST, STORED};
     use crate::Index;
     use crate::ReloadPolicy;
     use crate::{
         schema::{Schema, SchemaBuilder, TEXT},
         IndexSettings,
     };
+    use crate::{DocAddress, Term};
     use common::HasLen;
 
     #[test]
@@ -620,4 +763,66 @@ mod tests {
         assert!(mmap_directory.get_cache_info().mmapped.is_empty());
         Ok(())
     }
+
+    #[test]
+    fn test_segment_component_from_path() {
+        let component_of = |path: &str| SegmentComponent::from_path(Path::new(path));
+        assert_eq!(component_of("0123.idx"), Some(SegmentComponent::Postings));
+        assert_eq!(
+            component_of("dir/0123.store"),
+            Some(SegmentComponent::Store)
+        );
+        assert_eq!(
+            component_of("0123.store.temp"),
+            Some(SegmentComponent::TempStore)
+        );
+        assert_eq!(component_of("0123.12.del"), Some(SegmentComponent::Delete));
+        assert_eq!(component_of("meta.json"), None);
+        assert_eq!(component_of(".managed.json"), None);
+    }
+
+    #[test]
+    fn test_mmap_settings() -> crate::Result<()> {
+        let mmap_settings = MmapSettings::default()
+            .with_advice(SegmentComponent::Store, MmapAdvice::Random)
+            .with_advice(SegmentComponent::Postings, MmapAdvice::Sequential)
+            .with_advice(SegmentComponent::Terms, MmapAdvice::WillNeed)
+            .with_locked(SegmentComponent::FastFields);
+        assert_eq!(
+            mmap_settings.advice(SegmentComponent::Store),
+            MmapAdvice::Random
+        );
+        assert_eq!(
+            mmap_settings.advice(SegmentComponent::Positions),
+            MmapAdvice::Normal
+        );
+        assert!(mmap_settings.is_locked(SegmentComponent::FastFields));
+        assert!(!mmap_settings.is_locked(SegmentComponent::Store));
+
+        let tempdir = TempDir::new()?;
+        let mmap_directory = MmapDirectory::open_with_settings(tempdir.path(), mmap_settings)?;
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create(mmap_directory, schema_builder.build(), Default::default())?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(text_field => "hello", num_field => i))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "hello"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&query, &Count)?, 10);
+        let doc = searcher.doc(DocAddress::new(0, 3))?;
+        assert_eq!(
+            doc.get_first(text_field).and_then(|v| v.text()),
+            Some("hello")
+        );
+        let num_reader = searcher.segment_reader(0).fast_fields().u64(num_field)?;
+        assert_eq!(num_reader.get(3), 3);
+        Ok(())
+    }
 }
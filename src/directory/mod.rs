@@ -7,6 +7,7 @@ WORM directory abstraction.
 #[cfg(feature = "mmap")]
 mod mmap_directory;
 
+mod caching_file_handle;
 mod directory;
 mod directory_lock;
 mod file_slice;
@@ -22,12 +23,15 @@ pub mod error;
 
 mod composite_file;
 
-pub(crate) use self::composite_file::{CompositeFile, CompositeWrite};
+pub use self::caching_file_handle::CachingFileHandle;
+pub use self::composite_file::CompositeFile;
+pub(crate) use self::composite_file::CompositeWrite;
 pub use self::directory::DirectoryLock;
 pub use self::directory::{Directory, DirectoryClone};
 pub use self::directory_lock::{Lock, INDEX_WRITER_LOCK, META_LOCK};
 pub(crate) use self::file_slice::{ArcBytes, WeakArcBytes};
 pub use self::file_slice::{FileHandle, FileSlice};
+pub use self::footer::Footer;
 pub use self::owned_bytes::OwnedBytes;
 pub use self::ram_directory::RamDirectory;
 pub use self::watch_event_router::{WatchCallback, WatchCallbackList, WatchHandle};
@@ -51,15 +55,16 @@ pub struct GarbageCollectionResult {
 }
 
 #[cfg(feature = "mmap")]
-pub use self::mmap_directory::MmapDirectory;
+pub use self::mmap_directory::{MmapAdvice, MmapDirectory, MmapSettings};
 
+pub(crate) use self::managed_directory::FilePin;
 pub use self::managed_directory::ManagedDirectory;
 
 /// Write object for Directory.
 ///
 /// `WritePtr` are required to implement both Write
 /// and Seek.
-pub type WritePtr = BufWriter<Box<dyn TerminatingWrite>>;
+pub type WritePtr = BufWriter<Box<dyn TerminatingWrite + Send>>;
 
 #[cfg(test)]
 mod tests;
@@ -6,7 +6,7 @@ use crate::directory::{Directory, FileSlice, WatchCallback, WatchHandle};
 use crate::directory::{TerminatingWrite, WritePtr};
 use common::HasLen;
 use fail::fail_point;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
@@ -77,6 +77,8 @@ impl TerminatingWrite for VecWriter {
 #[derive(Default)]
 struct InnerDirectory {
     fs: HashMap<PathBuf, FileSlice>,
+    // Files that were written using `atomic_write`.
+    atomic_files: HashSet<PathBuf>,
     watch_router: WatchCallbackList,
 }
 
@@ -86,6 +88,11 @@ impl InnerDirectory {
         self.fs.insert(path, data).is_some()
     }
 
+    fn atomic_write(&mut self, path: PathBuf, data: &[u8]) {
+        self.atomic_files.insert(path.clone());
+        self.write(path, data);
+    }
+
     fn open_read(&self, path: &Path) -> Result<FileSlice, OpenReadError> {
         self.fs
             .get(path)
@@ -94,6 +101,7 @@ impl InnerDirectory {
     }
 
     fn delete(&mut self, path: &Path) -> result::Result<(), DeleteError> {
+        self.atomic_files.remove(path);
         match self.fs.remove(path) {
             Some(_) => Ok(()),
             None => Err(DeleteError::FileDoesNotExist(PathBuf::from(path))),
@@ -143,17 +151,26 @@ impl RamDirectory {
 
     /// Write a copy of all of the files saved in the RamDirectory in the target `Directory`.
     ///
-    /// Files are all written using the `Directory::write` meaning, even if they were
-    /// written using the `atomic_write` api.
+    /// Files written using the `atomic_write` api (e.g. `meta.json`) are persisted
+    /// using `atomic_write` as well, once all of the other files have been written.
+    /// This way, the persisted `meta.json` never points to a segment that is not
+    /// fully persisted yet.
     ///
     /// If an error is encounterred, files may be persisted partially.
     pub fn persist(&self, dest: &dyn Directory) -> crate::Result<()> {
-        let wlock = self.fs.write().unwrap();
-        for (path, file) in wlock.fs.iter() {
+        let rlock = self.fs.read().unwrap();
+        for (path, file) in rlock.fs.iter() {
+            if rlock.atomic_files.contains(path) {
+                continue;
+            }
             let mut dest_wrt = dest.open_write(path)?;
             dest_wrt.write_all(file.read_bytes()?.as_slice())?;
             dest_wrt.terminate()?;
         }
+        for path in rlock.atomic_files.iter() {
+            let file = rlock.open_read(path)?;
+            dest.atomic_write(path, file.read_bytes()?.as_slice())?;
+        }
         Ok(())
     }
 }
@@ -215,7 +232,7 @@ impl Directory for RamDirectory {
 
     fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
         let path_buf = PathBuf::from(path);
-        self.fs.write().unwrap().write(path_buf, data);
+        self.fs.write().unwrap().atomic_write(path_buf, data);
         if path == *META_FILEPATH {
             let _ = self.fs.write().unwrap().watch_router.broadcast();
         }
@@ -254,4 +271,37 @@ mod tests {
         assert_eq!(directory_copy.atomic_read(path_atomic).unwrap(), msg_atomic);
         assert_eq!(directory_copy.atomic_read(path_seq).unwrap(), msg_seq);
     }
+
+    #[test]
+    fn test_persist_overwrites_atomic_files() {
+        let path_atomic: &'static Path = Path::new("atomic");
+        let directory = RamDirectory::create();
+        directory.atomic_write(path_atomic, b"new").unwrap();
+        let directory_copy = RamDirectory::create();
+        directory_copy.atomic_write(path_atomic, b"old").unwrap();
+        assert!(directory.persist(&directory_copy).is_ok());
+        assert_eq!(directory_copy.atomic_read(path_atomic).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_persist_index() -> crate::Result<()> {
+        use crate::schema::{Schema, TEXT};
+        use crate::{doc, Index};
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let directory = RamDirectory::create();
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello"))?;
+        index_writer.commit()?;
+        let directory_copy = RamDirectory::create();
+        directory.persist(&directory_copy)?;
+        let index_copy = Index::open(directory_copy)?;
+        assert_eq!(index_copy.reader()?.searcher().num_docs(), 1);
+        Ok(())
+    }
 }
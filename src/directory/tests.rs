@@ -1,4 +1,5 @@
 use super::*;
+use crate::directory::error::LockError;
 use futures::channel::oneshot;
 use futures::executor::block_on;
 use std::io::Write;
@@ -226,7 +227,7 @@ fn test_lock_non_blocking(directory: &dyn Directory) {
             filepath: PathBuf::from("a.lock"),
             is_blocking: false,
         });
-        assert!(lock_a_res2.is_err());
+        assert!(matches!(lock_a_res2, Err(LockError::LockBusy)));
     }
     let lock_a_res = directory.acquire_lock(&Lock {
         filepath: PathBuf::from("a.lock"),
@@ -73,6 +73,16 @@ pub trait DocSet: Send {
         buffer.len()
     }
 
+    /// Returns the doc ids the `DocSet` has already decoded, starting with
+    /// the current document, e.g. the rest of the current block of a posting list.
+    ///
+    /// The default implementation returns an empty slice. It is used by the
+    /// intersection of `DocSet`s to intersect their decoded blocks at once,
+    /// rather than seeking them doc by doc.
+    fn buffered_docs(&self) -> &[DocId] {
+        &[]
+    }
+
     /// Returns the current document
     /// Right after creating a new DocSet, the docset points to the first document.
     ///
@@ -83,6 +93,16 @@ pub trait DocSet: Send {
     /// length of the docset.
     fn size_hint(&self) -> u32;
 
+    /// Returns a best-effort estimate of the cost of going through the
+    /// entire docset, in number of docs visited.
+    ///
+    /// It is the `size_hint` for the docsets iterating over a single list of docs,
+    /// but it is larger for the docsets combining other docsets, e.g. a union visits
+    /// the docs of all of its docsets. It is used to plan the execution of queries.
+    fn cost(&self) -> u64 {
+        u64::from(self.size_hint())
+    }
+
     /// Returns the number documents matching.
     /// Calling this method consumes the `DocSet`.
     fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
@@ -122,6 +142,10 @@ impl<'a> DocSet for &'a mut dyn DocSet {
         (**self).seek(target)
     }
 
+    fn buffered_docs(&self) -> &[DocId] {
+        (**self).buffered_docs()
+    }
+
     fn doc(&self) -> u32 {
         (**self).doc()
     }
@@ -130,6 +154,10 @@ impl<'a> DocSet for &'a mut dyn DocSet {
         (**self).size_hint()
     }
 
+    fn cost(&self) -> u64 {
+        (**self).cost()
+    }
+
     fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
         (**self).count(alive_bitset)
     }
@@ -150,6 +178,11 @@ impl<TDocSet: DocSet + ?Sized> DocSet for Box<TDocSet> {
         unboxed.seek(target)
     }
 
+    fn buffered_docs(&self) -> &[DocId] {
+        let unboxed: &TDocSet = self.borrow();
+        unboxed.buffered_docs()
+    }
+
     fn doc(&self) -> DocId {
         let unboxed: &TDocSet = self.borrow();
         unboxed.doc()
@@ -160,6 +193,11 @@ impl<TDocSet: DocSet + ?Sized> DocSet for Box<TDocSet> {
         unboxed.size_hint()
     }
 
+    fn cost(&self) -> u64 {
+        let unboxed: &TDocSet = self.borrow();
+        unboxed.cost()
+    }
+
     fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
         let unboxed: &mut TDocSet = self.borrow_mut();
         unboxed.count(alive_bitset)
@@ -4,20 +4,23 @@ use std::io;
 
 use crate::directory::error::{Incompatibility, LockError};
 use crate::fastfield::FastFieldNotAvailableError;
+use crate::indexer::merger::MAX_DOC_LIMIT;
 use crate::query;
 use crate::{
     directory::error::{OpenDirectoryError, OpenReadError, OpenWriteError},
     schema,
 };
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::PoisonError;
 
 /// Represents a `DataCorruption` error.
 ///
 /// When facing data corruption, tantivy  actually panic or return this error.
+#[derive(Clone)]
 pub struct DataCorruption {
     filepath: Option<PathBuf>,
+    offset: Option<u64>,
     comment: String,
 }
 
@@ -26,6 +29,7 @@ impl DataCorruption {
     pub fn new(filepath: PathBuf, comment: String) -> DataCorruption {
         DataCorruption {
             filepath: Some(filepath),
+            offset: None,
             comment,
         }
     }
@@ -34,9 +38,37 @@ impl DataCorruption {
     pub fn comment_only<TStr: ToString>(comment: TStr) -> DataCorruption {
         DataCorruption {
             filepath: None,
+            offset: None,
             comment: comment.to_string(),
         }
     }
+
+    /// Sets the path of the corrupted file.
+    pub fn with_filepath(mut self, filepath: PathBuf) -> DataCorruption {
+        self.filepath = Some(filepath);
+        self
+    }
+
+    /// Sets the offset, in bytes, of the corrupted data within the file.
+    pub fn with_offset(mut self, offset: u64) -> DataCorruption {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the path of the corrupted file, if known.
+    pub fn filepath(&self) -> Option<&Path> {
+        self.filepath.as_deref()
+    }
+
+    /// Returns the offset, in bytes, of the corrupted data within the file, if known.
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Returns the description of the corruption.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
 }
 
 impl fmt::Debug for DataCorruption {
@@ -45,6 +77,9 @@ impl fmt::Debug for DataCorruption {
         if let Some(ref filepath) = &self.filepath {
             write!(f, "(in file `{:?}`)", filepath)?;
         }
+        if let Some(offset) = self.offset {
+            write!(f, "(at offset {})", offset)?;
+        }
         write!(f, ": {}.", self.comment)?;
         Ok(())
     }
@@ -77,6 +112,9 @@ pub enum TantivyError {
     /// A thread holding the locked panicked and poisoned the lock.
     #[error("A thread holding the locked panicked and poisoned the lock")]
     Poisoned,
+    /// The query could not be parsed.
+    #[error("Query is invalid: '{0}'")]
+    QueryParserError(#[from] query::QueryParserError),
     /// Invalid argument was passed by the user.
     #[error("An invalid argument was passed: '{0}'")]
     InvalidArgument(String),
@@ -95,6 +133,13 @@ pub enum TantivyError {
     /// Index incompatible with current version of tantivy
     #[error("{0:?}")]
     IncompatibleIndex(Incompatibility),
+    /// A segment would contain the given number of documents,
+    /// exceeding the limit of `2^31` documents per segment.
+    #[error(
+        "A segment cannot contain {0} documents, the limit is {limit}",
+        limit = MAX_DOC_LIMIT
+    )]
+    DocLimitExceeded(u64),
 }
 
 impl From<DataCorruption> for TantivyError {
@@ -113,12 +158,6 @@ impl From<LockError> for TantivyError {
     }
 }
 
-impl From<query::QueryParserError> for TantivyError {
-    fn from(parsing_error: query::QueryParserError) -> TantivyError {
-        TantivyError::InvalidArgument(format!("Query is invalid. {:?}", parsing_error))
-    }
-}
-
 impl<Guard> From<PoisonError<Guard>> for TantivyError {
     fn from(_: PoisonError<Guard>) -> TantivyError {
         TantivyError::Poisoned
@@ -148,3 +187,55 @@ impl From<rayon::ThreadPoolBuildError> for TantivyError {
         TantivyError::SystemError(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DataCorruption, TantivyError};
+    use crate::query::{QueryParserError, QuerySyntaxError};
+    use std::path::Path;
+
+    #[test]
+    fn test_query_parser_error_conversion() {
+        let syntax_error = QuerySyntaxError {
+            span: 3..4,
+            message: "Unexpected `)`".to_string(),
+        };
+        let error: TantivyError = QueryParserError::SyntaxError(syntax_error).into();
+        assert!(matches!(
+            error,
+            TantivyError::QueryParserError(QueryParserError::SyntaxError(_))
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Query is invalid: 'Syntax error at 3..4: Unexpected `)`'"
+        );
+    }
+
+    #[test]
+    fn test_data_corruption_accessors() {
+        let data_corruption = DataCorruption::new("a.store".into(), "bad footer".to_string());
+        assert_eq!(data_corruption.filepath(), Some(Path::new("a.store")));
+        assert_eq!(data_corruption.comment(), "bad footer");
+        assert_eq!(data_corruption.offset(), None);
+        let data_corruption = DataCorruption::comment_only("bad footer");
+        assert_eq!(data_corruption.filepath(), None);
+        let data_corruption = data_corruption
+            .with_filepath("a.store".into())
+            .with_offset(42);
+        assert_eq!(data_corruption.filepath(), Some(Path::new("a.store")));
+        assert_eq!(data_corruption.offset(), Some(42));
+        assert_eq!(
+            format!("{:?}", data_corruption),
+            "Data corruption: (in file `\"a.store\"`)(at offset 42): bad footer."
+        );
+    }
+
+    #[test]
+    fn test_doc_limit_exceeded() {
+        let error = TantivyError::DocLimitExceeded(1u64 << 32);
+        assert_eq!(
+            error.to_string(),
+            "A segment cannot contain 4294967296 documents, the limit is 2147483648"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::directory::FileSlice;
 use crate::directory::OwnedBytes;
-use crate::fastfield::{BitpackedFastFieldReader, FastFieldReader, MultiValueLength};
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader, MultiValueLength};
 use crate::DocId;
 
 /// Reader for byte array fast fields
@@ -15,13 +15,16 @@ use crate::DocId;
 /// and the start index for the next document, and keeping the bytes in between.
 #[derive(Clone)]
 pub struct BytesFastFieldReader {
-    idx_reader: BitpackedFastFieldReader<u64>,
+    idx_reader: DynamicFastFieldReader<u64>,
     values: OwnedBytes,
 }
 
 impl BytesFastFieldReader {
-    pub(crate) fn open(
-        idx_reader: BitpackedFastFieldReader<u64>,
+    /// Creates a reader from the reader of the index of the field and from the
+    /// file of its values, stored respectively with the indexes 0 and 1 of the field
+    /// in the fast fields `CompositeFile`.
+    pub fn open(
+        idx_reader: DynamicFastFieldReader<u64>,
         values_file: FileSlice,
     ) -> crate::Result<BytesFastFieldReader> {
         let values = values_file.read_bytes()?;
@@ -0,0 +1,632 @@
+use super::{FastValue, OptionalFastFieldReader};
+use crate::collector::{CustomScorer, CustomSegmentScorer, ScoreSegmentTweaker, ScoreTweaker};
+use crate::schema::{Cardinality, Field, FieldType};
+use crate::{DateTime, DocId, Score, SegmentReader, TantivyError};
+use std::ops;
+
+/// Unit of the difference between two dates, see
+/// [`Expression::date_diff`](./struct.Expression.html#method.date_diff).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DateUnit {
+    /// Seconds.
+    Seconds,
+    /// Minutes.
+    Minutes,
+    /// Hours.
+    Hours,
+    /// Days, of 24 hours.
+    Days,
+}
+
+impl DateUnit {
+    fn num_seconds(self) -> f64 {
+        match self {
+            DateUnit::Seconds => 1.0,
+            DateUnit::Minutes => 60.0,
+            DateUnit::Hours => 3_600.0,
+            DateUnit::Days => 86_400.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Neg,
+    Ln,
+    Log10,
+    Log1p,
+    Exp,
+    Sqrt,
+    Abs,
+}
+
+impl UnaryOp {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            UnaryOp::Neg => -value,
+            UnaryOp::Ln => value.ln(),
+            UnaryOp::Log10 => value.log10(),
+            UnaryOp::Log1p => value.ln_1p(),
+            UnaryOp::Exp => value.exp(),
+            UnaryOp::Sqrt => value.sqrt(),
+            UnaryOp::Abs => value.abs(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Min,
+    Max,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+}
+
+/// Returns 1 if `condition` holds, 0 otherwise.
+fn indicator(condition: bool) -> f64 {
+    if condition {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl BinaryOp {
+    fn apply(self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Sub => left - right,
+            BinaryOp::Mul => left * right,
+            BinaryOp::Div => left / right,
+            BinaryOp::Pow => left.powf(right),
+            BinaryOp::Min => left.min(right),
+            BinaryOp::Max => left.max(right),
+            BinaryOp::Gt => indicator(left > right),
+            BinaryOp::Ge => indicator(left >= right),
+            BinaryOp::Lt => indicator(left < right),
+            BinaryOp::Le => indicator(left <= right),
+            BinaryOp::Eq => indicator(left == right),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Constant(f64),
+    Value(Field),
+    HasValue(Field),
+    Score,
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    IfThenElse(Box<Node>, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn requires_scoring(&self) -> bool {
+        match self {
+            Node::Constant(_) | Node::Value(_) | Node::HasValue(_) => false,
+            Node::Score => true,
+            Node::Unary(_, operand) => operand.requires_scoring(),
+            Node::Binary(_, left, right) => left.requires_scoring() || right.requires_scoring(),
+            Node::IfThenElse(condition, then, otherwise) => {
+                condition.requires_scoring()
+                    || then.requires_scoring()
+                    || otherwise.requires_scoring()
+            }
+        }
+    }
+
+    fn for_segment(&self, segment_reader: &SegmentReader) -> crate::Result<SegmentNode> {
+        Ok(match self {
+            Node::Constant(value) => SegmentNode::Constant(*value),
+            Node::Value(field) => {
+                let (values, to_f64) = open_f64_values(segment_reader, *field)?;
+                SegmentNode::Value(values, to_f64)
+            }
+            Node::HasValue(field) => {
+                let (values, _) = open_f64_values(segment_reader, *field)?;
+                SegmentNode::HasValue(values)
+            }
+            Node::Score => SegmentNode::Score,
+            Node::Unary(op, operand) => {
+                SegmentNode::Unary(*op, Box::new(operand.for_segment(segment_reader)?))
+            }
+            Node::Binary(op, left, right) => SegmentNode::Binary(
+                *op,
+                Box::new(left.for_segment(segment_reader)?),
+                Box::new(right.for_segment(segment_reader)?),
+            ),
+            Node::IfThenElse(condition, then, otherwise) => SegmentNode::IfThenElse(
+                Box::new(condition.for_segment(segment_reader)?),
+                Box::new(then.for_segment(segment_reader)?),
+                Box::new(otherwise.for_segment(segment_reader)?),
+            ),
+        })
+    }
+}
+
+/// Converts the `u64` representation of a fast field value to `f64`.
+pub(crate) type ToF64 = fn(u64) -> f64;
+
+/// Opens the values of the single-valued numeric or date fast field `field`,
+/// with the function converting them to `f64`.
+///
+/// Dates are converted to their timestamp in seconds.
+pub(crate) fn open_f64_values(
+    segment_reader: &SegmentReader,
+    field: Field,
+) -> crate::Result<(OptionalFastFieldReader<u64>, ToF64)> {
+    let field_entry = segment_reader.schema().get_field_entry(field);
+    let (options, to_f64): (_, ToF64) = match field_entry.field_type() {
+        FieldType::U64(options) => (options, |value| value as f64),
+        FieldType::I64(options) | FieldType::Date(options) => {
+            (options, |value| i64::from_u64(value) as f64)
+        }
+        FieldType::F64(options) => (options, f64::from_u64),
+        _ => {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a numeric or date fast field.",
+                field_entry.name()
+            )));
+        }
+    };
+    if options.get_fastfield_cardinality() != Some(Cardinality::SingleValue) {
+        return Err(TantivyError::SchemaError(format!(
+            "Field {:?} is not a single-valued fast field.",
+            field_entry.name()
+        )));
+    }
+    let fast_fields = segment_reader.fast_fields();
+    let values = OptionalFastFieldReader::new(
+        fast_fields.u64_lenient(field)?,
+        fast_fields.presence_bitset(field)?,
+    );
+    Ok((values, to_f64))
+}
+
+/// An `Expression` computes a value for each document, from the values of its fast
+/// fields and its score, e.g. to rank the documents with a formula mixing their
+/// popularity, their recency and their relevance, without writing a custom collector.
+///
+/// Expressions are built with the constructors and the methods of `Expression`, and
+/// the `+`, `-`, `*`, `/` operators. They are evaluated as `f64`, and follow
+/// the `f64` semantics: e.g. the logarithm of 0 is minus infinity.
+///
+/// The fields must be single-valued `u64`, `i64`, `f64` or date fast fields. Dates
+/// are evaluated as their timestamp in seconds, and the documents without any value
+/// for a field are evaluated as 0 (see [`has_value`](#method.has_value)).
+///
+/// An expression can be used:
+/// - to tweak the score of the documents, with `TopDocs::tweak_score`.
+/// - to sort the documents, with `TopDocs::custom_score`, as long as it
+///   does not depend on the score.
+/// - in aggregations, with `StatsCollector::for_expression`.
+///
+/// ```rust
+/// use tantivy::collector::{StatsCollector, TopDocs};
+/// use tantivy::fastfield::Expression;
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, DocAddress, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let popularity = schema_builder.add_u64_field("popularity", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(title => "the diary of a diarist", popularity => 1u64))?;
+/// index_writer.add_document(doc!(title => "a diary", popularity => 100u64))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+/// // score * ln(2 + popularity)
+/// let ranking = Expression::score() * (Expression::field(popularity) + 2.0).ln();
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1).tweak_score(ranking))?;
+/// assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+///
+/// let doubled_popularity = Expression::field(popularity) * 2.0;
+/// let stats = searcher.search(&query, &StatsCollector::for_expression(doubled_popularity))?;
+/// assert_eq!(stats.max, Some(200.0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    node: Node,
+}
+
+impl Expression {
+    fn unary(self, op: UnaryOp) -> Expression {
+        Expression {
+            node: Node::Unary(op, Box::new(self.node)),
+        }
+    }
+
+    fn binary<T: Into<Expression>>(self, op: BinaryOp, other: T) -> Expression {
+        Expression {
+            node: Node::Binary(op, Box::new(self.node), Box::new(other.into().node)),
+        }
+    }
+
+    /// A constant.
+    pub fn constant(value: f64) -> Expression {
+        Expression {
+            node: Node::Constant(value),
+        }
+    }
+
+    /// A constant date, evaluated as its timestamp in seconds.
+    pub fn date(date: DateTime) -> Expression {
+        Expression::constant(date.timestamp() as f64)
+    }
+
+    /// The value of the fast field `field`, or 0 for the documents without any value.
+    pub fn field(field: Field) -> Expression {
+        Expression {
+            node: Node::Value(field),
+        }
+    }
+
+    /// 1 if the document has a value for the fast field `field`, 0 otherwise.
+    pub fn has_value(field: Field) -> Expression {
+        Expression {
+            node: Node::HasValue(field),
+        }
+    }
+
+    /// The score of the document.
+    ///
+    /// It is 0 when the expression is evaluated without scoring.
+    pub fn score() -> Expression {
+        Expression { node: Node::Score }
+    }
+
+    /// `then` if `condition` is neither 0 nor NaN, `otherwise` otherwise.
+    pub fn if_then_else<T: Into<Expression>, U: Into<Expression>>(
+        condition: Expression,
+        then: T,
+        otherwise: U,
+    ) -> Expression {
+        Expression {
+            node: Node::IfThenElse(
+                Box::new(condition.node),
+                Box::new(then.into().node),
+                Box::new(otherwise.into().node),
+            ),
+        }
+    }
+
+    /// Natural logarithm.
+    pub fn ln(self) -> Expression {
+        self.unary(UnaryOp::Ln)
+    }
+
+    /// Base 10 logarithm.
+    pub fn log10(self) -> Expression {
+        self.unary(UnaryOp::Log10)
+    }
+
+    /// Natural logarithm of 1 plus the value, more accurate than `(x + 1).ln()`
+    /// for the small values.
+    pub fn log1p(self) -> Expression {
+        self.unary(UnaryOp::Log1p)
+    }
+
+    /// Exponential function.
+    pub fn exp(self) -> Expression {
+        self.unary(UnaryOp::Exp)
+    }
+
+    /// Square root.
+    pub fn sqrt(self) -> Expression {
+        self.unary(UnaryOp::Sqrt)
+    }
+
+    /// Absolute value.
+    pub fn abs(self) -> Expression {
+        self.unary(UnaryOp::Abs)
+    }
+
+    /// The value to the power of `exponent`.
+    pub fn pow<T: Into<Expression>>(self, exponent: T) -> Expression {
+        self.binary(BinaryOp::Pow, exponent)
+    }
+
+    /// The minimum of the two values.
+    pub fn min<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Min, other)
+    }
+
+    /// The maximum of the two values.
+    pub fn max<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Max, other)
+    }
+
+    /// 1 if the value is greater than `other`, 0 otherwise.
+    pub fn gt<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Gt, other)
+    }
+
+    /// 1 if the value is greater than or equal to `other`, 0 otherwise.
+    pub fn ge<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Ge, other)
+    }
+
+    /// 1 if the value is lower than `other`, 0 otherwise.
+    pub fn lt<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Lt, other)
+    }
+
+    /// 1 if the value is lower than or equal to `other`, 0 otherwise.
+    pub fn le<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Le, other)
+    }
+
+    /// 1 if the value is equal to `other`, 0 otherwise.
+    pub fn equals<T: Into<Expression>>(self, other: T) -> Expression {
+        self.binary(BinaryOp::Eq, other)
+    }
+
+    /// The time elapsed from the date `start` to this date, in `unit`.
+    ///
+    /// For instance, the age of the documents in days is
+    /// `Expression::date(now).date_diff(Expression::field(published), DateUnit::Days)`.
+    pub fn date_diff<T: Into<Expression>>(self, start: T, unit: DateUnit) -> Expression {
+        (self - start) / unit.num_seconds()
+    }
+
+    /// Returns true iff the expression depends on the score of the documents.
+    pub fn requires_scoring(&self) -> bool {
+        self.node.requires_scoring()
+    }
+
+    /// Binds the expression to the fast fields of a segment.
+    ///
+    /// Returns a `SchemaError` if one of its fields is not a single-valued
+    /// numeric or date fast field.
+    pub fn for_segment(&self, segment_reader: &SegmentReader) -> crate::Result<SegmentExpression> {
+        Ok(SegmentExpression {
+            node: self.node.for_segment(segment_reader)?,
+        })
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Expression {
+        Expression::constant(value)
+    }
+}
+
+impl<T: Into<Expression>> ops::Add<T> for Expression {
+    type Output = Expression;
+
+    fn add(self, other: T) -> Expression {
+        self.binary(BinaryOp::Add, other)
+    }
+}
+
+impl<T: Into<Expression>> ops::Sub<T> for Expression {
+    type Output = Expression;
+
+    fn sub(self, other: T) -> Expression {
+        self.binary(BinaryOp::Sub, other)
+    }
+}
+
+impl<T: Into<Expression>> ops::Mul<T> for Expression {
+    type Output = Expression;
+
+    fn mul(self, other: T) -> Expression {
+        self.binary(BinaryOp::Mul, other)
+    }
+}
+
+impl<T: Into<Expression>> ops::Div<T> for Expression {
+    type Output = Expression;
+
+    fn div(self, other: T) -> Expression {
+        self.binary(BinaryOp::Div, other)
+    }
+}
+
+impl ops::Neg for Expression {
+    type Output = Expression;
+
+    fn neg(self) -> Expression {
+        self.unary(UnaryOp::Neg)
+    }
+}
+
+enum SegmentNode {
+    Constant(f64),
+    Value(OptionalFastFieldReader<u64>, ToF64),
+    HasValue(OptionalFastFieldReader<u64>),
+    Score,
+    Unary(UnaryOp, Box<SegmentNode>),
+    Binary(BinaryOp, Box<SegmentNode>, Box<SegmentNode>),
+    IfThenElse(Box<SegmentNode>, Box<SegmentNode>, Box<SegmentNode>),
+}
+
+impl SegmentNode {
+    fn eval(&self, doc: DocId, score: Score) -> f64 {
+        match self {
+            SegmentNode::Constant(value) => *value,
+            SegmentNode::Value(values, to_f64) => values.get(doc).map(to_f64).unwrap_or(0.0),
+            SegmentNode::HasValue(values) => indicator(values.has_value(doc)),
+            SegmentNode::Score => f64::from(score),
+            SegmentNode::Unary(op, operand) => op.apply(operand.eval(doc, score)),
+            SegmentNode::Binary(op, left, right) => {
+                op.apply(left.eval(doc, score), right.eval(doc, score))
+            }
+            SegmentNode::IfThenElse(condition, then, otherwise) => {
+                let condition = condition.eval(doc, score);
+                if condition != 0.0 && !condition.is_nan() {
+                    then.eval(doc, score)
+                } else {
+                    otherwise.eval(doc, score)
+                }
+            }
+        }
+    }
+}
+
+/// An [`Expression`](./struct.Expression.html) bound to the fast fields of a segment.
+pub struct SegmentExpression {
+    node: SegmentNode,
+}
+
+impl SegmentExpression {
+    /// Evaluates the expression for the document `doc`, with the given `score`.
+    pub fn eval(&self, doc: DocId, score: Score) -> f64 {
+        self.node.eval(doc, score)
+    }
+}
+
+impl ScoreSegmentTweaker<f64> for SegmentExpression {
+    fn score(&mut self, doc: DocId, score: Score) -> f64 {
+        self.eval(doc, score)
+    }
+}
+
+impl ScoreTweaker<f64> for Expression {
+    type Child = SegmentExpression;
+
+    fn segment_tweaker(&self, segment_reader: &SegmentReader) -> crate::Result<SegmentExpression> {
+        self.for_segment(segment_reader)
+    }
+}
+
+impl CustomSegmentScorer<f64> for SegmentExpression {
+    fn score(&mut self, doc: DocId) -> f64 {
+        self.eval(doc, 0.0)
+    }
+}
+
+impl CustomScorer<f64> for Expression {
+    type Child = SegmentExpression;
+
+    /// Returns an `InvalidArgument` error if the expression depends on the score,
+    /// as the documents are not scored.
+    fn segment_scorer(&self, segment_reader: &SegmentReader) -> crate::Result<SegmentExpression> {
+        if self.requires_scoring() {
+            return Err(TantivyError::InvalidArgument(
+                "An expression depending on the score cannot be used without scoring.".to_string(),
+            ));
+        }
+        self.for_segment(segment_reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateUnit, Expression};
+    use crate::collector::TopDocs;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, INDEXED, STRING, TEXT};
+    use crate::{DateTime, DocAddress, Index, TantivyError, Term};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_expression_eval() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let rating = schema_builder.add_f64_field("rating", FAST);
+        let delta = schema_builder.add_i64_field("delta", FAST);
+        let published = schema_builder.add_date_field("published", FAST);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let date = |day: u32| -> DateTime { Utc.ymd(2021, 6, day).and_hms(12, 0, 0) };
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            popularity => 9u64,
+            rating => 4.5,
+            delta => -3i64,
+            published => date(1)
+        ))?;
+        index_writer.add_document(doc!(rating => 2.0))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let eval = |expression: Expression, doc| -> crate::Result<f64> {
+            Ok(expression.for_segment(segment_reader)?.eval(doc, 2.0))
+        };
+
+        assert_eq!(eval(Expression::field(popularity) + 1.0, 0)?, 10.0);
+        assert_eq!(eval((Expression::field(popularity) + 1.0).log10(), 0)?, 1.0);
+        assert_eq!(
+            eval(Expression::field(delta).abs() * Expression::score(), 0)?,
+            6.0
+        );
+        assert_eq!(eval(-Expression::field(rating) / 2.0, 0)?, -2.25);
+        assert_eq!(eval(Expression::field(rating).min(3.0).max(2.5), 1)?, 2.5);
+        assert_eq!(
+            eval(Expression::field(popularity).sqrt().pow(3.0), 0)?,
+            27.0
+        );
+        // The documents without values are evaluated as 0.
+        assert_eq!(eval(Expression::field(popularity), 1)?, 0.0);
+        assert_eq!(eval(Expression::has_value(popularity), 1)?, 0.0);
+        let boosted = Expression::if_then_else(
+            Expression::field(rating).ge(4.0),
+            Expression::score() * 10.0,
+            Expression::score(),
+        );
+        assert_eq!(eval(boosted.clone(), 0)?, 20.0);
+        assert_eq!(eval(boosted, 1)?, 2.0);
+        let age =
+            Expression::date(date(11)).date_diff(Expression::field(published), DateUnit::Days);
+        assert_eq!(eval(age.clone(), 0)?, 10.0);
+        assert_eq!(eval(age.lt(7.0), 0)?, 0.0);
+        assert!(eval(Expression::field(popularity).ln(), 1)?.is_infinite());
+
+        assert!(matches!(
+            Expression::field(text).for_segment(segment_reader),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_expression_collectors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let popularity = schema_builder.add_u64_field("popularity", FAST | INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(tag => "a", popularity => i))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // Sorts by the distance to 4.
+        let distance = (Expression::field(popularity) - 4.0).abs();
+        let top_docs =
+            searcher.search(&AllQuery, &TopDocs::with_limit(3).custom_score(-distance))?;
+        let top_docs: Vec<(f64, DocAddress)> = top_docs;
+        assert_eq!(top_docs[0], (0.0, DocAddress::new(0, 4)));
+        assert_eq!(top_docs[1].0, -1.0);
+        assert_eq!(top_docs[2].0, -1.0);
+
+        let query = TermQuery::new(Term::from_field_text(tag, "a"), IndexRecordOption::Basic);
+        let ranking = Expression::score() * Expression::field(popularity);
+        let top_docs =
+            searcher.search(&query, &TopDocs::with_limit(1).tweak_score(ranking.clone()))?;
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 9));
+        assert!(matches!(
+            searcher.search(&query, &TopDocs::with_limit(1).custom_score(ranking)),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
@@ -28,9 +28,11 @@ pub use self::alive_bitset::write_alive_bitset;
 pub use self::alive_bitset::AliveBitSet;
 pub use self::bytes::{BytesFastFieldReader, BytesFastFieldWriter};
 pub use self::error::{FastFieldNotAvailableError, Result};
+pub(crate) use self::expression::{open_f64_values, ToF64};
+pub use self::expression::{DateUnit, Expression, SegmentExpression};
 pub use self::facet_reader::FacetReader;
 pub use self::multivalued::{MultiValuedFastFieldReader, MultiValuedFastFieldWriter};
-pub(crate) use self::reader::BitpackedFastFieldReader;
+pub use self::optional_reader::OptionalFastFieldReader;
 pub use self::reader::DynamicFastFieldReader;
 pub use self::reader::FastFieldReader;
 pub use self::readers::FastFieldReaders;
@@ -50,8 +52,10 @@ use crate::{
 mod alive_bitset;
 mod bytes;
 mod error;
+mod expression;
 mod facet_reader;
 mod multivalued;
+mod optional_reader;
 mod reader;
 mod readers;
 mod serializer;
@@ -221,7 +225,7 @@ mod tests {
     use crate::schema::Schema;
     use crate::schema::FAST;
     use crate::schema::{Document, IntOptions};
-    use crate::{Index, SegmentId, SegmentReader};
+    use crate::{Index, IndexSettings, IndexSortByField, Order, SegmentId, SegmentReader};
     use common::HasLen;
     use once_cell::sync::Lazy;
     use rand::prelude::SliceRandom;
@@ -246,6 +250,23 @@ mod tests {
         assert_eq!(test_fastfield.get(2), 300);
     }
 
+    #[test]
+    pub fn test_fastfield_gcd() {
+        let vals: Vec<u64> = (0..10_000u64)
+            .map(|i| 1_600_000_000_000 + (i * 7 % 1_000) * 1_000)
+            .collect();
+        let test_fastfield = DynamicFastFieldReader::<u64>::from(vals.clone());
+        assert!(matches!(
+            test_fastfield,
+            DynamicFastFieldReader::BitpackedGCD(_)
+        ));
+        for (doc, val) in vals.iter().enumerate() {
+            assert_eq!(test_fastfield.get(doc as DocId), *val);
+        }
+        assert_eq!(test_fastfield.min_value(), 1_600_000_000_000);
+        assert_eq!(test_fastfield.max_value(), 1_600_000_999_000);
+    }
+
     #[test]
     pub fn test_fastfield_i64_u64() {
         let datetime = crate::DateTime::from_utc(NaiveDateTime::from_timestamp(0i64, 0), Utc);
@@ -523,6 +544,114 @@ mod tests {
         Ok(())
     }
 
+    fn test_optional_fast_field_aux(index_settings: IndexSettings) -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let i64_field = schema_builder.add_i64_field("i64", FAST);
+        let rank_field = schema_builder.add_u64_field("rank", FAST);
+        let schema = schema_builder.build();
+        let index = Index::builder()
+            .schema(schema)
+            .settings(index_settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(rank_field => 2u64, i64_field => 0i64))?;
+        index_writer.add_document(doc!(rank_field => 1u64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(rank_field => 4u64, i64_field => -3i64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(rank_field => 3u64))?;
+        index_writer.add_document(doc!(rank_field => 5u64, i64_field => 5i64))?;
+        index_writer.commit()?;
+
+        let check = |searcher: &crate::Searcher| -> crate::Result<()> {
+            let mut values: Vec<(u64, Option<i64>)> = Vec::new();
+            for segment_reader in searcher.segment_readers() {
+                let rank_reader = segment_reader.fast_fields().u64(rank_field)?;
+                let i64_reader = segment_reader.fast_fields().optional::<i64>(i64_field)?;
+                for doc in 0..segment_reader.max_doc() {
+                    values.push((rank_reader.get(doc), i64_reader.get(doc)));
+                    assert_eq!(
+                        i64_reader.values().get(doc),
+                        i64_reader.get(doc).unwrap_or(0)
+                    );
+                }
+            }
+            values.sort_unstable();
+            assert_eq!(
+                values,
+                vec![
+                    (1, None),
+                    (2, Some(0)),
+                    (3, None),
+                    (4, Some(-3)),
+                    (5, Some(5))
+                ]
+            );
+            Ok(())
+        };
+        let reader = index.reader()?;
+        check(&reader.searcher())?;
+        let segment_ids = index.searchable_segment_ids()?;
+        futures::executor::block_on(index_writer.merge(&segment_ids))?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 1);
+        check(&reader.searcher())?;
+
+        let searcher = reader.searcher();
+        let fast_fields = searcher.segment_reader(0).fast_fields();
+        assert!(fast_fields.optional::<u64>(i64_field).is_err());
+        assert!(fast_fields.optional::<u64>(rank_field)?.has_value(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_optional_fast_field() -> crate::Result<()> {
+        test_optional_fast_field_aux(IndexSettings::default())
+    }
+
+    #[test]
+    fn test_optional_fast_field_sorted_index() -> crate::Result<()> {
+        test_optional_fast_field_aux(IndexSettings {
+            sort_by_field: Some(IndexSortByField {
+                field: "rank".to_string(),
+                order: Order::Desc,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_typed_fast_field_readers() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let i64_field = schema_builder.add_i64_field("i64", FAST);
+        let f64s_field = schema_builder.add_f64_field(
+            "f64s",
+            IntOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(i64_field => -3i64, f64s_field => 1.5f64))?;
+        index_writer.add_document(doc!(i64_field => 7i64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fast_fields = searcher.segment_reader(0).fast_fields();
+        let i64_reader = fast_fields.typed::<i64>(i64_field)?;
+        assert_eq!(i64_reader.get(0), -3i64);
+        assert_eq!(i64_reader.min_value(), -3i64);
+        assert_eq!(i64_reader.max_value(), 7i64);
+        let mut vals = Vec::new();
+        fast_fields
+            .typed_multi::<f64>(f64s_field)?
+            .get_vals(0, &mut vals);
+        assert_eq!(&vals, &[1.5f64]);
+        assert!(fast_fields.typed::<u64>(i64_field).is_err());
+        assert!(fast_fields.typed::<f64>(f64s_field).is_err());
+        assert!(fast_fields.typed_multi::<i64>(i64_field).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_default_datetime() {
         assert_eq!(crate::DateTime::make_zero().timestamp(), 0i64);
@@ -18,7 +18,10 @@ pub struct MultiValuedFastFieldReader<Item: FastValue> {
 }
 
 impl<Item: FastValue> MultiValuedFastFieldReader<Item> {
-    pub(crate) fn open(
+    /// Creates a reader from the readers of the index and of the values of
+    /// the field, stored respectively with the indexes 0 and 1 of the field in the
+    /// fast fields `CompositeFile`.
+    pub fn open(
         idx_reader: DynamicFastFieldReader<u64>,
         vals_reader: DynamicFastFieldReader<Item>,
     ) -> MultiValuedFastFieldReader<Item> {
@@ -0,0 +1,52 @@
+use super::{DynamicFastFieldReader, FastFieldReader, FastValue};
+use crate::DocId;
+use common::ReadOnlyBitSet;
+
+/// Reader of a single-valued fast field, telling apart the documents without any value.
+///
+/// `DynamicFastFieldReader` returns the default value of the field (`0`, `0.0`, ...)
+/// for a document without any value. The `OptionalFastFieldReader` returns `None` instead.
+///
+/// Segments written before the presence of values was recorded are considered
+/// to have a value for all of their documents.
+#[derive(Clone)]
+pub struct OptionalFastFieldReader<Item: FastValue> {
+    values: DynamicFastFieldReader<Item>,
+    presence_bitset: Option<ReadOnlyBitSet>,
+}
+
+impl<Item: FastValue> OptionalFastFieldReader<Item> {
+    pub(crate) fn new(
+        values: DynamicFastFieldReader<Item>,
+        presence_bitset: Option<ReadOnlyBitSet>,
+    ) -> OptionalFastFieldReader<Item> {
+        OptionalFastFieldReader {
+            values,
+            presence_bitset,
+        }
+    }
+
+    /// Returns true iff the document has a value for the field.
+    pub fn has_value(&self, doc: DocId) -> bool {
+        self.presence_bitset
+            .as_ref()
+            .map(|presence_bitset| presence_bitset.contains(doc))
+            .unwrap_or(true)
+    }
+
+    /// Returns the value associated to the document, or `None` if
+    /// the document does not have any value for the field.
+    pub fn get(&self, doc: DocId) -> Option<Item> {
+        if self.has_value(doc) {
+            Some(self.values.get(doc))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the underlying reader, returning the default value
+    /// of the field for the documents without any value.
+    pub fn values(&self) -> &DynamicFastFieldReader<Item> {
+        &self.values
+    }
+}
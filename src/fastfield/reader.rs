@@ -10,6 +10,7 @@ use crate::DocId;
 use common::BinarySerializable;
 use fastfield_codecs::bitpacked::BitpackedFastFieldReader as BitpackedReader;
 use fastfield_codecs::bitpacked::BitpackedFastFieldSerializer;
+use fastfield_codecs::gcd::{GCDFastFieldCodecReader, GCD_CODEC_ID};
 use fastfield_codecs::linearinterpol::LinearInterpolFastFieldReader;
 use fastfield_codecs::linearinterpol::LinearInterpolFastFieldSerializer;
 use fastfield_codecs::multilinearinterpol::MultiLinearInterpolFastFieldReader;
@@ -72,6 +73,20 @@ pub enum DynamicFastFieldReader<Item: FastValue> {
     LinearInterpol(FastFieldReaderCodecWrapper<Item, LinearInterpolFastFieldReader>),
     /// Blockwise linear interpolated values + bitpacked
     MultiLinearInterpol(FastFieldReaderCodecWrapper<Item, MultiLinearInterpolFastFieldReader>),
+
+    /// GCD and Bitpacked compressed fastfield data.
+    BitpackedGCD(FastFieldReaderCodecWrapper<Item, GCDFastFieldCodecReader<BitpackedReader>>),
+    /// GCD and Linear interpolated values + bitpacked
+    LinearInterpolGCD(
+        FastFieldReaderCodecWrapper<Item, GCDFastFieldCodecReader<LinearInterpolFastFieldReader>>,
+    ),
+    /// GCD and Blockwise linear interpolated values + bitpacked
+    MultiLinearInterpolGCD(
+        FastFieldReaderCodecWrapper<
+            Item,
+            GCDFastFieldCodecReader<MultiLinearInterpolFastFieldReader>,
+        >,
+    ),
 }
 
 impl<Item: FastValue> DynamicFastFieldReader<Item> {
@@ -101,6 +116,41 @@ impl<Item: FastValue> DynamicFastFieldReader<Item> {
                     bytes
                 )?)
             }
+            GCD_CODEC_ID => {
+                let inner_id = bytes.read_u8();
+                match inner_id {
+                    BitpackedFastFieldSerializer::ID => {
+                        DynamicFastFieldReader::BitpackedGCD(FastFieldReaderCodecWrapper::<
+                            Item,
+                            GCDFastFieldCodecReader<BitpackedReader>,
+                        >::open_from_bytes(
+                            bytes
+                        )?)
+                    }
+                    LinearInterpolFastFieldSerializer::ID => {
+                        DynamicFastFieldReader::LinearInterpolGCD(FastFieldReaderCodecWrapper::<
+                            Item,
+                            GCDFastFieldCodecReader<LinearInterpolFastFieldReader>,
+                        >::open_from_bytes(
+                            bytes
+                        )?)
+                    }
+                    MultiLinearInterpolFastFieldSerializer::ID => {
+                        DynamicFastFieldReader::MultiLinearInterpolGCD(
+                            FastFieldReaderCodecWrapper::<
+                                Item,
+                                GCDFastFieldCodecReader<MultiLinearInterpolFastFieldReader>,
+                            >::open_from_bytes(bytes)?,
+                        )
+                    }
+                    _ => {
+                        panic!(
+                            "unknown fastfield id {:?} after gcd. Data corrupted or using old tantivy version.",
+                            inner_id
+                        )
+                    }
+                }
+            }
             _ => {
                 panic!(
                     "unknown fastfield id {:?}. Data corrupted or using old tantivy version.",
@@ -118,6 +168,9 @@ impl<Item: FastValue> FastFieldReader<Item> for DynamicFastFieldReader<Item> {
             Self::Bitpacked(reader) => reader.get(doc),
             Self::LinearInterpol(reader) => reader.get(doc),
             Self::MultiLinearInterpol(reader) => reader.get(doc),
+            Self::BitpackedGCD(reader) => reader.get(doc),
+            Self::LinearInterpolGCD(reader) => reader.get(doc),
+            Self::MultiLinearInterpolGCD(reader) => reader.get(doc),
         }
     }
     fn get_range(&self, start: u64, output: &mut [Item]) {
@@ -125,6 +178,9 @@ impl<Item: FastValue> FastFieldReader<Item> for DynamicFastFieldReader<Item> {
             Self::Bitpacked(reader) => reader.get_range(start, output),
             Self::LinearInterpol(reader) => reader.get_range(start, output),
             Self::MultiLinearInterpol(reader) => reader.get_range(start, output),
+            Self::BitpackedGCD(reader) => reader.get_range(start, output),
+            Self::LinearInterpolGCD(reader) => reader.get_range(start, output),
+            Self::MultiLinearInterpolGCD(reader) => reader.get_range(start, output),
         }
     }
     fn min_value(&self) -> Item {
@@ -132,6 +188,9 @@ impl<Item: FastValue> FastFieldReader<Item> for DynamicFastFieldReader<Item> {
             Self::Bitpacked(reader) => reader.min_value(),
             Self::LinearInterpol(reader) => reader.min_value(),
             Self::MultiLinearInterpol(reader) => reader.min_value(),
+            Self::BitpackedGCD(reader) => reader.min_value(),
+            Self::LinearInterpolGCD(reader) => reader.min_value(),
+            Self::MultiLinearInterpolGCD(reader) => reader.min_value(),
         }
     }
     fn max_value(&self) -> Item {
@@ -139,6 +198,9 @@ impl<Item: FastValue> FastFieldReader<Item> for DynamicFastFieldReader<Item> {
             Self::Bitpacked(reader) => reader.max_value(),
             Self::LinearInterpol(reader) => reader.max_value(),
             Self::MultiLinearInterpol(reader) => reader.max_value(),
+            Self::BitpackedGCD(reader) => reader.max_value(),
+            Self::LinearInterpolGCD(reader) => reader.max_value(),
+            Self::MultiLinearInterpolGCD(reader) => reader.max_value(),
         }
     }
 }
@@ -249,8 +311,6 @@ impl<Item: FastValue, C: FastFieldCodecReader + Clone> FastFieldReader<Item>
     }
 }
 
-pub(crate) type BitpackedFastFieldReader<Item> = FastFieldReaderCodecWrapper<Item, BitpackedReader>;
-
 impl<Item: FastValue> From<Vec<Item>> for DynamicFastFieldReader<Item> {
     fn from(vals: Vec<Item>) -> DynamicFastFieldReader<Item> {
         let mut schema_builder = Schema::builder();
@@ -1,13 +1,19 @@
-use crate::directory::CompositeFile;
 use crate::directory::FileSlice;
+use crate::directory::{CompositeFile, Directory, RamDirectory};
+use crate::fastfield::CompositeFastFieldSerializer;
+use crate::fastfield::FastFieldNotAvailableError;
 use crate::fastfield::MultiValuedFastFieldReader;
-use crate::fastfield::{BitpackedFastFieldReader, FastFieldNotAvailableError};
+use crate::fastfield::OptionalFastFieldReader;
 use crate::fastfield::{BytesFastFieldReader, FastValue};
-use crate::schema::{Cardinality, Field, FieldType, Schema};
+use crate::schema::{Cardinality, Field, FieldEntry, FieldType, Schema};
 use crate::space_usage::PerFieldSpaceUsage;
-use crate::TantivyError;
+use crate::{DocId, TantivyError};
+use common::{BitSet, ReadOnlyBitSet};
+use std::io;
+use std::path::Path;
 
 use super::reader::DynamicFastFieldReader;
+use super::writer::fast_field_default_value;
 
 /// Provides access to all of the BitpackedFastFieldReader.
 ///
@@ -17,6 +23,9 @@ use super::reader::DynamicFastFieldReader;
 pub struct FastFieldReaders {
     schema: Schema,
     fast_fields_composite: CompositeFile,
+    // Fast fields of the documents of a segment written before the
+    // field was added to the schema. All of these documents are empty.
+    missing_fast_fields_composite: CompositeFile,
 }
 #[derive(Eq, PartialEq, Debug)]
 enum FastType {
@@ -45,11 +54,109 @@ fn type_and_cardinality(field_type: &FieldType) -> Option<(FastType, Cardinality
     }
 }
 
+fn has_fast_field_data(field_entry: &FieldEntry) -> bool {
+    match field_entry.field_type() {
+        FieldType::Bytes(bytes_options) => bytes_options.is_fast(),
+        field_type => type_and_cardinality(field_type).is_some(),
+    }
+}
+
+/// Writes a column holding `val` for every document.
+///
+/// All of the values fit on 0 bits, so the size of the column does not depend
+/// on the number of documents.
+fn write_constant_column(
+    serializer: &mut CompositeFastFieldSerializer,
+    field: Field,
+    val: u64,
+    idx: usize,
+) -> io::Result<()> {
+    serializer
+        .new_u64_fast_field_with_idx(field, val, val, idx)?
+        .close_field()
+}
+
+/// Serializes the fast fields of `schema` that are missing in `fast_fields_composite`,
+/// as if none of the `max_doc` documents of the segment had a value.
+fn missing_fast_fields_composite(
+    schema: &Schema,
+    fast_fields_composite: &CompositeFile,
+    max_doc: DocId,
+) -> crate::Result<CompositeFile> {
+    let missing_fields: Vec<(Field, &FieldEntry)> = schema
+        .fields()
+        .filter(|(field, field_entry)| {
+            has_fast_field_data(field_entry)
+                && fast_fields_composite
+                    .open_read_with_idx(*field, 0)
+                    .is_none()
+        })
+        .collect();
+    if missing_fields.is_empty() {
+        return Ok(CompositeFile::empty());
+    }
+    let directory = RamDirectory::create();
+    let path = Path::new("missing_fast_fields");
+    let mut serializer = CompositeFastFieldSerializer::from_write(directory.open_write(path)?)?;
+    for (field, field_entry) in missing_fields {
+        match field_entry.field_type() {
+            FieldType::Bytes(_) => {
+                // All of the documents start at offset 0 of empty data.
+                write_constant_column(&mut serializer, field, 0, 0)?;
+                serializer.new_bytes_fast_field_with_idx(field, 1).flush()?;
+            }
+            field_type => match type_and_cardinality(field_type) {
+                Some((_, Cardinality::SingleValue)) => {
+                    let default_value = fast_field_default_value(field_entry);
+                    write_constant_column(&mut serializer, field, default_value, 0)?;
+                    serializer.write_presence_bitset(field, &BitSet::with_max_value(max_doc))?;
+                }
+                Some((_, Cardinality::MultiValues)) => {
+                    // All of the documents start at offset 0 of an empty column of values.
+                    write_constant_column(&mut serializer, field, 0, 0)?;
+                    write_constant_column(&mut serializer, field, 0, 1)?;
+                }
+                None => {}
+            },
+        }
+    }
+    serializer.close()?;
+    let file = directory.open_read(path)?;
+    Ok(CompositeFile::open(&file)?)
+}
+
 impl FastFieldReaders {
-    pub(crate) fn new(schema: Schema, fast_fields_composite: CompositeFile) -> FastFieldReaders {
-        FastFieldReaders {
+    /// Opens the fast fields of a segment with `max_doc` documents.
+    ///
+    /// The fast fields of the schema that are missing in the segment, because they were
+    /// added to the schema after the segment was written, are read as if none of the
+    /// documents had a value.
+    ///
+    /// `fast_fields_composite` is the `CompositeFile` of the `FastFields` segment component.
+    pub fn open(
+        schema: Schema,
+        fast_fields_composite: CompositeFile,
+        max_doc: DocId,
+    ) -> crate::Result<FastFieldReaders> {
+        let missing_fast_fields_composite =
+            missing_fast_fields_composite(&schema, &fast_fields_composite, max_doc)?;
+        Ok(FastFieldReaders {
             schema,
             fast_fields_composite,
+            missing_fast_fields_composite,
+        })
+    }
+
+    fn open_read_with_idx(&self, field: Field, idx: usize) -> Option<FileSlice> {
+        if self
+            .fast_fields_composite
+            .open_read_with_idx(field, 0)
+            .is_some()
+        {
+            self.fast_fields_composite.open_read_with_idx(field, idx)
+        } else {
+            self.missing_fast_fields_composite
+                .open_read_with_idx(field, idx)
         }
     }
 
@@ -57,13 +164,20 @@ impl FastFieldReaders {
         self.fast_fields_composite.space_usage()
     }
 
+    /// Returns the data of all of the columns associated to `field`.
+    pub(crate) fn fast_field_data_slices(&self, field: Field) -> Vec<FileSlice> {
+        (0..)
+            .map(|idx| self.open_read_with_idx(field, idx))
+            .take_while(Option::is_some)
+            .flatten()
+            .collect()
+    }
+
     fn fast_field_data(&self, field: Field, idx: usize) -> crate::Result<FileSlice> {
-        self.fast_fields_composite
-            .open_read_with_idx(field, idx)
-            .ok_or_else(|| {
-                let field_name = self.schema.get_field_entry(field).name();
-                TantivyError::SchemaError(format!("Field({}) data was not found", field_name))
-            })
+        self.open_read_with_idx(field, idx).ok_or_else(|| {
+            let field_name = self.schema.get_field_entry(field).name();
+            TantivyError::SchemaError(format!("Field({}) data was not found", field_name))
+        })
     }
 
     fn check_type(
@@ -123,6 +237,81 @@ impl FastFieldReaders {
         Ok(MultiValuedFastFieldReader::open(idx_reader, vals_reader))
     }
 
+    /// Returns the set of the documents having a value for the single-valued
+    /// fast field `field`, or `None` if all of the documents have a value.
+    pub(crate) fn presence_bitset(&self, field: Field) -> crate::Result<Option<ReadOnlyBitSet>> {
+        let presence_bitset_opt = self
+            .open_read_with_idx(field, 1)
+            .map(|file_slice| file_slice.read_bytes().map(ReadOnlyBitSet::open))
+            .transpose()?;
+        Ok(presence_bitset_opt)
+    }
+
+    fn check_typed<TFastValue: FastValue>(
+        &self,
+        field: Field,
+        expected_cardinality: Cardinality,
+    ) -> crate::Result<()> {
+        let field_entry = self.schema.get_field_entry(field);
+        match TFastValue::fast_field_cardinality(field_entry.field_type()) {
+            Some(cardinality) if cardinality == expected_cardinality => Ok(()),
+            Some(cardinality) => Err(crate::TantivyError::SchemaError(format!(
+                "Field {:?} is of cardinality {:?}, expected {:?}.",
+                field_entry.name(),
+                cardinality,
+                expected_cardinality
+            ))),
+            None => Err(crate::TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field of type {:?}.",
+                field_entry.name(),
+                TFastValue::to_type()
+            ))),
+        }
+    }
+
+    /// Returns the fast field reader associated to `field`, for any `FastValue` type.
+    ///
+    /// This is handy for collectors or scoring functions that are generic over the
+    /// type of the column they read.
+    ///
+    /// If `field` is not a single-valued fast field of type `TFastValue`,
+    /// this method returns an Error.
+    pub fn typed<TFastValue: FastValue>(
+        &self,
+        field: Field,
+    ) -> crate::Result<DynamicFastFieldReader<TFastValue>> {
+        self.check_typed::<TFastValue>(field, Cardinality::SingleValue)?;
+        self.typed_fast_field_reader(field)
+    }
+
+    /// Returns a reader of the single-valued fast field `field`, returning `None`
+    /// for the documents that do not have any value.
+    ///
+    /// If `field` is not a single-valued fast field of type `TFastValue`,
+    /// this method returns an Error.
+    pub fn optional<TFastValue: FastValue>(
+        &self,
+        field: Field,
+    ) -> crate::Result<OptionalFastFieldReader<TFastValue>> {
+        self.check_typed::<TFastValue>(field, Cardinality::SingleValue)?;
+        let values = self.typed_fast_field_reader(field)?;
+        let presence_bitset = self.presence_bitset(field)?;
+        Ok(OptionalFastFieldReader::new(values, presence_bitset))
+    }
+
+    /// Returns the multi-valued fast field reader associated to `field`,
+    /// for any `FastValue` type.
+    ///
+    /// If `field` is not a multi-valued fast field of type `TFastValue`,
+    /// this method returns an Error.
+    pub fn typed_multi<TFastValue: FastValue>(
+        &self,
+        field: Field,
+    ) -> crate::Result<MultiValuedFastFieldReader<TFastValue>> {
+        self.check_typed::<TFastValue>(field, Cardinality::MultiValues)?;
+        self.typed_fast_field_multi_reader(field)
+    }
+
     /// Returns the `u64` fast field reader reader associated to `field`.
     ///
     /// If `field` is not a u64 fast field, this method returns an Error.
@@ -219,7 +408,7 @@ impl FastFieldReaders {
                 )));
             }
             let fast_field_idx_file = self.fast_field_data(field, 0)?;
-            let idx_reader = BitpackedFastFieldReader::open(fast_field_idx_file)?;
+            let idx_reader = DynamicFastFieldReader::open(fast_field_idx_file)?;
             let data = self.fast_field_data(field, 1)?;
             BytesFastFieldReader::open(idx_reader, data)
         } else {
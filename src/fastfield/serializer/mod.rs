@@ -2,9 +2,13 @@ use crate::directory::CompositeWrite;
 use crate::directory::WritePtr;
 use crate::schema::Field;
 use common::BinarySerializable;
+use common::BitSet;
 use common::CountingWriter;
 pub use fastfield_codecs::bitpacked::BitpackedFastFieldSerializer;
 pub use fastfield_codecs::bitpacked::BitpackedFastFieldSerializerLegacy;
+use fastfield_codecs::gcd::{
+    find_gcd, gcd_stats, write_gcd_header, GCDFastFieldDataAccess, GCD_CODEC_ID,
+};
 use fastfield_codecs::linearinterpol::LinearInterpolFastFieldSerializer;
 use fastfield_codecs::multilinearinterpol::MultiLinearInterpolFastFieldSerializer;
 pub use fastfield_codecs::FastFieldCodecSerializer;
@@ -64,7 +68,7 @@ impl CompositeFastFieldSerializer {
         field: Field,
         stats: FastFieldStats,
         fastfield_accessor: impl FastFieldDataAccess,
-        data_iter_1: impl Iterator<Item = u64>,
+        data_iter_1: impl Iterator<Item = u64> + Clone,
         data_iter_2: impl Iterator<Item = u64>,
     ) -> io::Result<()> {
         self.create_auto_detect_u64_fast_field_with_idx(
@@ -77,17 +81,53 @@ impl CompositeFastFieldSerializer {
         )
     }
     /// Serialize data into a new u64 fast field. The best compression codec will be chosen automatically.
+    ///
+    /// If the values (minus their minimum) share a common divisor, they are divided by it
+    /// before being handed to the codec. This typically happens with timestamps rounded to the
+    /// second or with prices expressed in cents.
     pub fn create_auto_detect_u64_fast_field_with_idx(
         &mut self,
         field: Field,
         stats: FastFieldStats,
         fastfield_accessor: impl FastFieldDataAccess,
-        data_iter_1: impl Iterator<Item = u64>,
+        data_iter_1: impl Iterator<Item = u64> + Clone,
         data_iter_2: impl Iterator<Item = u64>,
         idx: usize,
     ) -> io::Result<()> {
         let field_write = self.composite_write.for_field_with_idx(field, idx);
+        if let Some(gcd) = find_gcd(data_iter_1.clone(), &stats) {
+            let min_value = stats.min_value;
+            GCD_CODEC_ID.serialize(field_write)?;
+            return Self::write_auto_detect_u64_fast_field(
+                field_write,
+                field,
+                gcd_stats(&stats, gcd),
+                GCDFastFieldDataAccess::new(&fastfield_accessor, gcd, min_value),
+                data_iter_1.map(move |val| (val - min_value) / gcd),
+                data_iter_2.map(move |val| (val - min_value) / gcd),
+                |field_write| write_gcd_header(field_write, gcd, min_value),
+            );
+        }
+        Self::write_auto_detect_u64_fast_field(
+            field_write,
+            field,
+            stats,
+            fastfield_accessor,
+            data_iter_1,
+            data_iter_2,
+            |_| Ok(()),
+        )
+    }
 
+    fn write_auto_detect_u64_fast_field(
+        field_write: &mut CountingWriter<WritePtr>,
+        field: Field,
+        stats: FastFieldStats,
+        fastfield_accessor: impl FastFieldDataAccess,
+        data_iter_1: impl Iterator<Item = u64>,
+        data_iter_2: impl Iterator<Item = u64>,
+        write_header: impl FnOnce(&mut CountingWriter<WritePtr>) -> io::Result<()>,
+    ) -> io::Result<()> {
         let mut estimations = vec![];
 
         codec_estimation::<BitpackedFastFieldSerializer, _>(
@@ -121,6 +161,7 @@ impl CompositeFastFieldSerializer {
             name, field
         ); // todo print actual field name
         id.serialize(field_write)?;
+        write_header(field_write)?;
         match name {
             BitpackedFastFieldSerializer::NAME => {
                 BitpackedFastFieldSerializer::serialize(
@@ -183,6 +224,19 @@ impl CompositeFastFieldSerializer {
         BitpackedFastFieldSerializerLegacy::open(field_write, min_value, max_value)
     }
 
+    /// Writes the set of the documents having a value for the
+    /// single-valued fast field `field`.
+    ///
+    /// It is only written if some documents do not have any value.
+    pub fn write_presence_bitset(
+        &mut self,
+        field: Field,
+        presence_bitset: &BitSet,
+    ) -> io::Result<()> {
+        let field_write = self.composite_write.for_field_with_idx(field, 1);
+        presence_bitset.serialize(field_write)
+    }
+
     /// Start serializing a new [u8] fast field
     pub fn new_bytes_fast_field_with_idx(
         &mut self,
@@ -6,7 +6,9 @@ use crate::indexer::doc_id_mapping::DocIdMapping;
 use crate::postings::UnorderedTermId;
 use crate::schema::{Cardinality, Document, Field, FieldEntry, FieldType, Schema};
 use crate::termdict::TermOrdinal;
+use crate::DocId;
 use common;
+use common::BitSet;
 use fnv::FnvHashMap;
 use std::collections::HashMap;
 use std::io;
@@ -19,7 +21,7 @@ pub struct FastFieldsWriter {
     bytes_value_writers: Vec<BytesFastFieldWriter>,
 }
 
-fn fast_field_default_value(field_entry: &FieldEntry) -> u64 {
+pub(crate) fn fast_field_default_value(field_entry: &FieldEntry) -> u64 {
     match *field_entry.field_type() {
         FieldType::I64(_) | FieldType::Date(_) => common::i64_to_u64(0i64),
         FieldType::F64(_) => common::f64_to_u64(0.0f64),
@@ -189,6 +191,8 @@ pub struct IntFastFieldWriter {
     vals: BlockedBitpacker,
     val_count: usize,
     val_if_missing: u64,
+    // Documents without any value, recorded with `val_if_missing`.
+    missing_docs: Vec<DocId>,
     val_min: u64,
     val_max: u64,
 }
@@ -201,6 +205,7 @@ impl IntFastFieldWriter {
             vals: BlockedBitpacker::new(),
             val_count: 0,
             val_if_missing: 0u64,
+            missing_docs: Vec::new(),
             val_min: u64::max_value(),
             val_max: 0,
         }
@@ -208,7 +213,7 @@ impl IntFastFieldWriter {
 
     /// The memory used (inclusive childs)
     pub fn mem_usage(&self) -> usize {
-        self.vals.mem_usage()
+        self.vals.mem_usage() + self.missing_docs.capacity() * std::mem::size_of::<DocId>()
     }
 
     /// Returns the field that this writer is targetting.
@@ -248,21 +253,26 @@ impl IntFastFieldWriter {
     /// i64 and f64 are remapped to u64 using the logic
     /// in `common::i64_to_u64` and `common::f64_to_u64`.
     ///
-    /// If the value is missing, then the default value is used
-    /// instead.
+    /// Returns `None` if the value is missing.
     /// If the document has more than one value for the given field,
     /// only the first one is taken in account.
-    fn extract_val(&self, doc: &Document) -> u64 {
-        match doc.get_first(self.field) {
-            Some(v) => super::value_to_u64(v),
-            None => self.val_if_missing,
-        }
+    fn extract_val(&self, doc: &Document) -> Option<u64> {
+        doc.get_first(self.field).map(super::value_to_u64)
     }
 
     /// Extract the fast field value from the document
     /// (or use the default value) and records it.
+    ///
+    /// Documents without any value are recorded as such, so that they can be
+    /// told apart with an `OptionalFastFieldReader`.
     pub fn add_document(&mut self, doc: &Document) {
-        let val = self.extract_val(doc);
+        let val = match self.extract_val(doc) {
+            Some(val) => val,
+            None => {
+                self.missing_docs.push(self.val_count as DocId);
+                self.val_if_missing
+            }
+        };
         self.add_val(val);
     }
 
@@ -312,6 +322,16 @@ impl IntFastFieldWriter {
                 self.vals.iter(),
             )?;
         };
+        if !self.missing_docs.is_empty() {
+            let mut presence_bitset = BitSet::with_max_value_and_full(self.val_count as u32);
+            for &missing_doc in &self.missing_docs {
+                let doc = doc_id_map
+                    .map(|doc_id_map| doc_id_map.get_new_doc_id(missing_doc))
+                    .unwrap_or(missing_doc);
+                presence_bitset.remove(doc);
+            }
+            serializer.write_presence_bitset(self.field, &presence_bitset)?;
+        }
         Ok(())
     }
 }
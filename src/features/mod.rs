@@ -0,0 +1,15 @@
+//! Features fields, holding sparse vectors of weighted features.
+//!
+//! Each document can have a set of named features, with a weight each, per features field.
+//! They are written to the `.feat` component of the segment as a small inverted index:
+//! a dictionary of the features, and for each feature the documents having it along with
+//! their weight. A [`FeaturesQuery`](crate::query::FeaturesQuery) scores the documents
+//! with the dot product of their features and of the weights of the query.
+
+mod reader;
+mod serializer;
+mod writer;
+
+pub use self::reader::{FeaturePostings, FeaturesReader, FeaturesReaders};
+pub use self::serializer::FeaturesSerializer;
+pub use self::writer::FeaturesWriter;
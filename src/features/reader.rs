@@ -0,0 +1,168 @@
+use super::serializer::{DICTIONARY_IDX, POSTINGS_IDX};
+use crate::directory::{CompositeFile, FileSlice, OwnedBytes};
+use crate::postings::TermInfo;
+use crate::schema::Field;
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::termdict::TermDictionary;
+use crate::DocId;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+/// Number of bytes of a `(doc, weight)` pair.
+const ENTRY_NUM_BYTES: usize = 8;
+
+/// Data of the features fields of a segment.
+#[derive(Clone)]
+pub struct FeaturesReaders {
+    data: Arc<CompositeFile>,
+}
+
+impl FeaturesReaders {
+    /// Creates a features readers from a file.
+    pub fn open(file: FileSlice) -> crate::Result<FeaturesReaders> {
+        let data = CompositeFile::open(&file)?;
+        Ok(FeaturesReaders {
+            data: Arc::new(data),
+        })
+    }
+
+    /// Returns the features readers of a segment without any features.
+    pub fn empty() -> FeaturesReaders {
+        FeaturesReaders {
+            data: Arc::new(CompositeFile::empty()),
+        }
+    }
+
+    /// Returns the `FeaturesReader` of `field`. If the segment does not have
+    /// any data for this field, the reader is empty.
+    pub fn get_field(&self, field: Field) -> crate::Result<FeaturesReader> {
+        let dictionary_file = self.data.open_read_with_idx(field, DICTIONARY_IDX);
+        let postings_file = self.data.open_read_with_idx(field, POSTINGS_IDX);
+        if let (Some(dictionary_file), Some(postings_file)) = (dictionary_file, postings_file) {
+            Ok(FeaturesReader {
+                dictionary: Arc::new(TermDictionary::open(dictionary_file)?),
+                postings: postings_file.read_bytes()?,
+            })
+        } else {
+            Ok(FeaturesReader::empty())
+        }
+    }
+
+    /// Return a break down of the space usage per field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.data.space_usage()
+    }
+}
+
+/// Reads the features of a features field of a segment.
+///
+/// For each feature, the documents having it are sorted by doc,
+/// along with the weight of the feature in the document.
+#[derive(Clone)]
+pub struct FeaturesReader {
+    dictionary: Arc<TermDictionary>,
+    postings: OwnedBytes,
+}
+
+impl FeaturesReader {
+    /// Returns a reader without any feature.
+    pub fn empty() -> FeaturesReader {
+        FeaturesReader {
+            dictionary: Arc::new(TermDictionary::empty()),
+            postings: OwnedBytes::empty(),
+        }
+    }
+
+    /// Returns the number of distinct features of the segment.
+    pub fn num_features(&self) -> usize {
+        self.dictionary.num_terms()
+    }
+
+    /// Returns the dictionary of the features, associating each
+    /// feature to the location of its postings.
+    pub fn dictionary(&self) -> &TermDictionary {
+        &self.dictionary
+    }
+
+    /// Returns the postings of a feature, or `None` if no document of the segment has it.
+    pub fn postings(&self, feature: &str) -> crate::Result<Option<FeaturePostings>> {
+        Ok(self
+            .dictionary
+            .get(feature.as_bytes())?
+            .map(|term_info| self.postings_from_term_info(&term_info)))
+    }
+
+    /// Returns the postings of the feature associated to `term_info` in the dictionary.
+    pub fn postings_from_term_info(&self, term_info: &TermInfo) -> FeaturePostings {
+        FeaturePostings {
+            data: self.postings.slice(term_info.postings_range.clone()),
+        }
+    }
+
+    /// Returns the weight of `feature` in `doc`, or `None` if the document does not have it.
+    pub fn weight(&self, feature: &str, doc: DocId) -> crate::Result<Option<f32>> {
+        Ok(self
+            .postings(feature)?
+            .and_then(|postings| postings.weight(doc)))
+    }
+}
+
+/// The `(doc, weight)` pairs of the documents having a feature, sorted by doc.
+#[derive(Clone)]
+pub struct FeaturePostings {
+    data: OwnedBytes,
+}
+
+impl FeaturePostings {
+    /// Returns the number of documents having the feature.
+    pub fn len(&self) -> usize {
+        self.data.len() / ENTRY_NUM_BYTES
+    }
+
+    /// Returns true iff no document has the feature.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the doc of the `idx`-th entry.
+    pub fn doc(&self, idx: usize) -> DocId {
+        let start = idx * ENTRY_NUM_BYTES;
+        u32::from_le_bytes(self.data.as_slice()[start..start + 4].try_into().unwrap())
+    }
+
+    /// Returns the weight of the `idx`-th entry.
+    pub fn weight_at(&self, idx: usize) -> f32 {
+        let start = idx * ENTRY_NUM_BYTES + 4;
+        f32::from_le_bytes(self.data.as_slice()[start..start + 4].try_into().unwrap())
+    }
+
+    /// Returns the index of the first entry whose doc is greater or equal to `target`,
+    /// searching from the entry `from`.
+    pub fn seek(&self, from: usize, target: DocId) -> usize {
+        let (mut start, mut end) = (from, self.len());
+        while start < end {
+            let mid = (start + end) / 2;
+            if self.doc(mid) < target {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        start
+    }
+
+    /// Returns the weight of the feature in `doc`, or `None` if the document does not have it.
+    pub fn weight(&self, doc: DocId) -> Option<f32> {
+        let idx = self.seek(0, doc);
+        if idx < self.len() && self.doc(idx) == doc {
+            Some(self.weight_at(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the `(doc, weight)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (DocId, f32)> + '_ {
+        (0..self.len()).map(move |idx| (self.doc(idx), self.weight_at(idx)))
+    }
+}
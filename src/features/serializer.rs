@@ -0,0 +1,79 @@
+use crate::directory::CompositeWrite;
+use crate::directory::WritePtr;
+use crate::postings::TermInfo;
+use crate::schema::Field;
+use crate::termdict::TermDictionaryBuilder;
+use crate::DocId;
+use common::BinarySerializable;
+use std::io;
+use std::io::Write;
+
+/// Index, within the composite file, of the dictionary of the features of a field.
+pub(crate) const DICTIONARY_IDX: usize = 0;
+/// Index, within the composite file, of the postings of the features of a field.
+pub(crate) const POSTINGS_IDX: usize = 1;
+
+/// The features serializer is in charge of the serialization
+/// of the features of all of the features fields.
+///
+/// The features of a field are written as two parts:
+/// - the postings: for each feature, the `(doc, weight)` pairs of the documents having it,
+///   sorted by doc, as a little endian `u32` and `f32`,
+/// - the dictionary, a term dictionary associating each feature to the byte range of
+///   its postings.
+pub struct FeaturesSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl FeaturesSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<FeaturesSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(FeaturesSerializer { composite_write })
+    }
+
+    /// Serializes the features of a field.
+    ///
+    /// `features` yields the features, sorted by name, along with the
+    /// `(doc, weight)` pairs of the documents having them, sorted by doc.
+    /// Features without any document are skipped.
+    pub fn serialize_field<I>(&mut self, field: Field, features: I) -> io::Result<()>
+    where I: IntoIterator<Item = io::Result<(String, Vec<(DocId, f32)>)>> {
+        let mut dictionary_builder = TermDictionaryBuilder::create(Vec::<u8>::new())?;
+        let write = self.composite_write.for_field_with_idx(field, POSTINGS_IDX);
+        let mut postings_start = 0;
+        for feature_res in features {
+            let (feature, postings) = feature_res?;
+            if postings.is_empty() {
+                continue;
+            }
+            for (doc, weight) in &postings {
+                doc.serialize(write)?;
+                weight.serialize(write)?;
+            }
+            let postings_end = postings_start + postings.len() * 8;
+            let term_info = TermInfo {
+                doc_freq: postings.len() as u32,
+                total_term_freq: postings.len() as u64,
+                postings_range: postings_start..postings_end,
+                positions_range: 0..0,
+            };
+            dictionary_builder.insert(feature.as_bytes(), &term_info)?;
+            postings_start = postings_end;
+        }
+        write.flush()?;
+        let dictionary_data = dictionary_builder.finish()?;
+        let write = self
+            .composite_write
+            .for_field_with_idx(field, DICTIONARY_IDX);
+        write.write_all(&dictionary_data)?;
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use super::FeaturesSerializer;
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::{Document, Field, FieldType, Schema};
+use crate::DocId;
+use std::collections::BTreeMap;
+use std::io;
+
+struct FieldFeaturesWriter {
+    field: Field,
+    postings: BTreeMap<String, Vec<(DocId, f32)>>,
+    mem_usage: usize,
+}
+
+/// The `FeaturesWriter` is in charge of accumulating the features
+/// of the features fields of the documents of a segment being written.
+pub struct FeaturesWriter {
+    field_writers: Vec<FieldFeaturesWriter>,
+}
+
+impl FeaturesWriter {
+    /// Initialize with state for tracking the features of the features fields of `schema`.
+    pub fn for_schema(schema: &Schema) -> FeaturesWriter {
+        let field_writers = schema
+            .fields()
+            .filter(|(_, field_entry)| matches!(field_entry.field_type(), FieldType::Features(_)))
+            .map(|(field, _)| FieldFeaturesWriter {
+                field,
+                postings: BTreeMap::new(),
+                mem_usage: 0,
+            })
+            .collect();
+        FeaturesWriter { field_writers }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.field_writers
+            .iter()
+            .map(|field_writer| field_writer.mem_usage)
+            .sum()
+    }
+
+    /// Records the features of a document.
+    ///
+    /// Returns a `SchemaError` if a weight is not finite, or if the
+    /// document has the same feature twice in the same field.
+    pub fn add_document(&mut self, doc_id: DocId, doc: &Document) -> crate::Result<()> {
+        // The document is checked entirely before any of its features is recorded.
+        let mut doc_features: Vec<(usize, &str, f32)> = Vec::new();
+        for (field_ord, field_writer) in self.field_writers.iter().enumerate() {
+            let num_doc_features = doc_features.len();
+            for features in doc
+                .get_all(field_writer.field)
+                .filter_map(|value| value.features_value())
+            {
+                for (feature, weight) in features {
+                    if !weight.is_finite() {
+                        return Err(crate::TantivyError::SchemaError(format!(
+                            "The weight of the feature {:?} of the field {:?} is not finite",
+                            feature, field_writer.field
+                        )));
+                    }
+                    doc_features.push((field_ord, feature.as_str(), *weight));
+                }
+            }
+            let field_features = &mut doc_features[num_doc_features..];
+            field_features.sort_unstable_by_key(|(_, feature, _)| *feature);
+            if let Some(window) = field_features
+                .windows(2)
+                .find(|window| window[0].1 == window[1].1)
+            {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "The feature {:?} appears twice in the field {:?}",
+                    window[0].1, field_writer.field
+                )));
+            }
+        }
+        for (field_ord, feature, weight) in doc_features {
+            let field_writer = &mut self.field_writers[field_ord];
+            if let Some(postings) = field_writer.postings.get_mut(feature) {
+                postings.push((doc_id, weight));
+            } else {
+                field_writer.mem_usage += feature.len() + std::mem::size_of::<String>();
+                field_writer
+                    .postings
+                    .insert(feature.to_string(), vec![(doc_id, weight)]);
+            }
+            field_writer.mem_usage += std::mem::size_of::<(DocId, f32)>();
+        }
+        Ok(())
+    }
+
+    /// Serialize the features of all of the features fields, remapping
+    /// the doc ids with `doc_id_map` if the segment is sorted.
+    pub fn serialize(
+        &self,
+        mut features_serializer: FeaturesSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> io::Result<()> {
+        for field_writer in &self.field_writers {
+            let features = field_writer.postings.iter().map(|(feature, postings)| {
+                let postings = if let Some(doc_id_map) = doc_id_map {
+                    let mut remapped: Vec<(DocId, f32)> = postings
+                        .iter()
+                        .map(|&(doc_id, weight)| (doc_id_map.get_new_doc_id(doc_id), weight))
+                        .collect();
+                    remapped.sort_unstable_by_key(|(doc_id, _)| *doc_id);
+                    remapped
+                } else {
+                    postings.clone()
+                };
+                Ok((feature.clone(), postings))
+            });
+            features_serializer.serialize_field(field_writer.field, features)?;
+        }
+        features_serializer.close()
+    }
+}
@@ -24,8 +24,55 @@ mod writer;
 pub use self::reader::{FieldNormReader, FieldNormReaders};
 pub use self::serializer::FieldNormsSerializer;
 pub use self::writer::FieldNormsWriter;
+pub(crate) use self::writer::MISSING_FIELDNORM_ID;
 
 use self::code::{fieldnorm_to_id, id_to_fieldnorm};
+use crate::Score;
+use serde::{Deserialize, Serialize};
+
+/// Defines how the documents without any token in an indexed field are accounted for
+/// in the average fieldnorm of the field, which BM25 uses to normalize the length of
+/// the fields.
+///
+/// Such documents have a fieldnorm of 0 in the segment, whether the field is absent
+/// from the document or empty. It can be set with `IndexSettings::missing_fieldnorm`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingFieldNorm {
+    /// The documents without the field count as empty fields: the average fieldnorm
+    /// is the number of tokens of the field divided by the number of documents.
+    ///
+    /// This is the default. In an index where many documents do not have the field,
+    /// the average fieldnorm is underestimated, and long fields are overly penalized.
+    #[serde(rename = "zero")]
+    Zero,
+    /// The documents without the field are ignored, as if their fieldnorm was the
+    /// average fieldnorm: the average fieldnorm is the number of tokens of the field
+    /// divided by the number of documents with at least one token in the field.
+    #[serde(rename = "average")]
+    Average,
+}
+
+impl MissingFieldNorm {
+    /// Returns the average fieldnorm of a field with `total_num_tokens` tokens.
+    ///
+    /// `num_docs_with_tokens` is only called with `MissingFieldNorm::Average`, as it
+    /// may require reading the fieldnorms.
+    pub(crate) fn average_fieldnorm<F>(
+        self,
+        total_num_tokens: u64,
+        num_docs: u64,
+        num_docs_with_tokens: F,
+    ) -> crate::Result<Score>
+    where
+        F: FnOnce() -> crate::Result<u64>,
+    {
+        let num_docs = match self {
+            MissingFieldNorm::Zero => num_docs,
+            MissingFieldNorm::Average => num_docs_with_tokens()?.max(1),
+        };
+        Ok(total_num_tokens as Score / num_docs as Score)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -40,6 +87,7 @@ mod tests {
     use crate::schema::TextFieldIndexing;
     use crate::schema::TextOptions;
     use crate::schema::TEXT;
+    use crate::HasLen;
     use crate::Index;
     use crate::Term;
     use crate::TERMINATED;
@@ -98,7 +146,47 @@ mod tests {
             assert_eq!(fieldnorm_reader.fieldnorm(1u32), 0u32);
             assert_eq!(fieldnorm_reader.fieldnorm(2u32), 5u32);
             assert_eq!(fieldnorm_reader.fieldnorm(3u32), 3u32);
+            let mut fieldnorms = [1u32; 3];
+            fieldnorm_reader.get_range(1u32, &mut fieldnorms[..]);
+            assert_eq!(fieldnorms, [0u32, 5u32, 3u32]);
+        }
+        let mut fieldnorms = [0u32; 2];
+        FieldNormReader::constant(3u32, 7u32).get_range(1u32, &mut fieldnorms[..]);
+        assert_eq!(fieldnorms, [7u32, 7u32]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fieldnorm_one_byte_per_doc() -> crate::Result<()> {
+        let path = Path::new("test");
+        let directory: RamDirectory = RamDirectory::create();
+        {
+            let write: WritePtr = directory.open_write(path)?;
+            let serializer = FieldNormsSerializer::from_write(write)?;
+            let mut fieldnorm_writers = FieldNormsWriter::for_schema(&SCHEMA);
+            for (doc, fieldnorm) in [1u32, 24, 1_000, 100_000].iter().enumerate() {
+                fieldnorm_writers.record(doc as u32, *TXT_FIELD, *fieldnorm);
+            }
+            fieldnorm_writers.serialize(serializer, None)?;
+        }
+        let file = directory.open_read(path)?;
+        let fields_composite = CompositeFile::open(&file)?;
+        let data = fields_composite.open_read(*TXT_FIELD).unwrap();
+        assert_eq!(data.len(), 4);
+        let fieldnorm_reader = FieldNormReader::open(data)?;
+        assert_eq!(fieldnorm_reader.fieldnorm(0u32), 1u32);
+        assert_eq!(fieldnorm_reader.fieldnorm(1u32), 24u32);
+        // Large fieldnorms are quantized to the closest lower value of the decode table.
+        for doc in 2u32..4u32 {
+            let fieldnorm_id = fieldnorm_reader.fieldnorm_id(doc);
+            let fieldnorm = fieldnorm_reader.fieldnorm(doc);
+            assert_eq!(FieldNormReader::id_to_fieldnorm(fieldnorm_id), fieldnorm);
+            assert_eq!(FieldNormReader::fieldnorm_to_id(fieldnorm), fieldnorm_id);
         }
+        assert!(fieldnorm_reader.fieldnorm(2u32) <= 1_000u32);
+        assert!(fieldnorm_reader.fieldnorm(2u32) > 900u32);
+        assert!(fieldnorm_reader.fieldnorm(3u32) <= 100_000u32);
+        assert!(fieldnorm_reader.fieldnorm(3u32) > 90_000u32);
         Ok(())
     }
 
@@ -1,4 +1,4 @@
-use super::{fieldnorm_to_id, id_to_fieldnorm};
+use super::{fieldnorm_to_id, id_to_fieldnorm, MISSING_FIELDNORM_ID};
 use crate::directory::CompositeFile;
 use crate::directory::FileSlice;
 use crate::directory::OwnedBytes;
@@ -37,6 +37,11 @@ impl FieldNormReaders {
         }
     }
 
+    /// Returns the data of the fieldnorms of `field`.
+    pub(crate) fn field_data(&self, field: Field) -> Option<FileSlice> {
+        self.data.open_read(field)
+    }
+
     /// Return a break down of the space usage per field.
     pub fn space_usage(&self) -> PerFieldSpaceUsage {
         self.data.space_usage()
@@ -101,7 +106,7 @@ impl FieldNormReader {
         Ok(FieldNormReader::new(data))
     }
 
-    fn new(data: OwnedBytes) -> Self {
+    pub(crate) fn new(data: OwnedBytes) -> Self {
         ReaderImplEnum::FromData(data).into()
     }
 
@@ -113,6 +118,31 @@ impl FieldNormReader {
         }
     }
 
+    /// Returns the number of documents with at least one token in the field.
+    ///
+    /// The documents without the field, as well as the documents with an empty field,
+    /// are not counted. This requires a scan of the fieldnorms.
+    pub fn num_docs_with_tokens(&self) -> u32 {
+        match &self.0 {
+            ReaderImplEnum::FromData(data) => data
+                .as_slice()
+                .iter()
+                .filter(|&&fieldnorm_id| fieldnorm_id != MISSING_FIELDNORM_ID)
+                .count() as u32,
+            ReaderImplEnum::Const {
+                num_docs,
+                fieldnorm_id,
+                ..
+            } => {
+                if *fieldnorm_id == MISSING_FIELDNORM_ID {
+                    0
+                } else {
+                    *num_docs
+                }
+            }
+        }
+    }
+
     /// Returns the `fieldnorm` associated to a doc id.
     /// The fieldnorm is a value approximating the number
     /// of tokens in a given field of the `doc_id`.
@@ -132,6 +162,37 @@ impl FieldNormReader {
         }
     }
 
+    /// Fills an output buffer with the fieldnorms associated with the `DocId`s
+    /// going from `start` to `start + output.len()`.
+    ///
+    /// This is handy to compute length-based features over a range of documents
+    /// without paying for a per-document dispatch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start + output.len()` is greater than the segment's `num_docs`.
+    pub fn get_range(&self, start: DocId, output: &mut [u32]) {
+        match &self.0 {
+            ReaderImplEnum::FromData(data) => {
+                let start = start as usize;
+                let fieldnorm_ids = &data.as_slice()[start..start + output.len()];
+                for (output_mut, &fieldnorm_id) in output.iter_mut().zip(fieldnorm_ids) {
+                    *output_mut = id_to_fieldnorm(fieldnorm_id);
+                }
+            }
+            ReaderImplEnum::Const {
+                num_docs,
+                fieldnorm,
+                ..
+            } => {
+                assert!(start as usize + output.len() <= *num_docs as usize);
+                for output_mut in output.iter_mut() {
+                    *output_mut = *fieldnorm;
+                }
+            }
+        }
+    }
+
     /// Returns the `fieldnorm_id` associated to a document.
     #[inline]
     pub fn fieldnorm_id(&self, doc_id: DocId) -> u8 {
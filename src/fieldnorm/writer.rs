@@ -7,6 +7,13 @@ use crate::schema::Schema;
 use std::cmp::Ordering;
 use std::{io, iter};
 
+/// The `fieldnorm_id` of the documents that do not have any token in a field.
+///
+/// It is the `fieldnorm_id` of the fieldnorm 0, so the documents without the field and
+/// the documents with an empty field cannot be told apart. How they are accounted for in
+/// the average fieldnorm is defined by [`MissingFieldNorm`](./enum.MissingFieldNorm.html).
+pub(crate) const MISSING_FIELDNORM_ID: u8 = 0u8;
+
 /// The `FieldNormsWriter` is in charge of tracking the fieldnorm byte
 /// of each document for each field with field norms.
 ///
@@ -55,11 +62,11 @@ impl FieldNormsWriter {
     /// Ensure that all documents in 0..max_doc have a byte associated with them
     /// in each of the fieldnorm vectors.
     ///
-    /// Will extend with 0-bytes for documents that have not been seen.
+    /// Will extend with `MISSING_FIELDNORM_ID` for documents that have not been seen.
     pub fn fill_up_to_max_doc(&mut self, max_doc: DocId) {
         for fieldnorms_buffer_opt in self.fieldnorms_buffers.iter_mut() {
             if let Some(fieldnorms_buffer) = fieldnorms_buffer_opt.as_mut() {
-                fieldnorms_buffer.resize(max_doc as usize, 0u8);
+                fieldnorms_buffer.resize(max_doc as usize, MISSING_FIELDNORM_ID);
             }
         }
     }
@@ -81,8 +88,8 @@ impl FieldNormsWriter {
         {
             match fieldnorm_buffer.len().cmp(&(doc as usize)) {
                 Ordering::Less => {
-                    // we fill intermediary `DocId` as  having a fieldnorm of 0.
-                    fieldnorm_buffer.resize(doc as usize, 0u8);
+                    // we fill intermediary `DocId` as not having the field.
+                    fieldnorm_buffer.resize(doc as usize, MISSING_FIELDNORM_ID);
                 }
                 Ordering::Equal => {}
                 Ordering::Greater => {
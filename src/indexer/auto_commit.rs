@@ -0,0 +1,349 @@
+use crate::schema::{Document, Term};
+use crate::{IndexWriter, Opstamp, TantivyError};
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A `CommitListener` is notified of the outcome of every commit run by an
+/// [`AutoCommitWriter`](./struct.AutoCommitWriter.html).
+///
+/// See [`AutoCommitWriter::set_commit_listener`](./struct.AutoCommitWriter.html#method.set_commit_listener).
+pub trait CommitListener: Send + Sync + 'static {
+    /// Called after the commit completed, on the thread that ran it.
+    ///
+    /// `commit_result` holds the opstamp of the commit, or the error that made it fail.
+    fn on_commit(&self, commit_result: &crate::Result<Opstamp>);
+}
+
+/// Defines when an [`AutoCommitWriter`](./struct.AutoCommitWriter.html) commits
+/// its pending changes.
+///
+/// A commit is triggered as soon as one of the configured thresholds is reached.
+/// With no threshold, the changes are only committed by explicit calls to
+/// [`AutoCommitWriter::commit`](./struct.AutoCommitWriter.html#method.commit).
+#[derive(Clone, Debug, Default)]
+pub struct AutoCommitPolicy {
+    max_pending_operations: Option<u64>,
+    interval: Option<Duration>,
+}
+
+impl AutoCommitPolicy {
+    /// Commits as soon as `max_pending_operations` documents were added
+    /// or deleted since the last commit.
+    pub fn with_max_pending_operations(mut self, max_pending_operations: u64) -> Self {
+        assert!(
+            max_pending_operations > 0,
+            "The maximum number of pending operations must be greater than 0."
+        );
+        self.max_pending_operations = Some(max_pending_operations);
+        self
+    }
+
+    /// Commits the pending changes, if any, every `interval`.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Returns the number of pending operations that triggers a commit.
+    pub fn max_pending_operations(&self) -> Option<u64> {
+        self.max_pending_operations
+    }
+
+    /// Returns the delay between two periodic commits.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+}
+
+enum CommitSignal {
+    Commit,
+    Stop,
+}
+
+struct InnerAutoCommitWriter {
+    index_writer: RwLock<IndexWriter>,
+    num_pending_operations: AtomicU64,
+    commit_listener: RwLock<Option<Arc<dyn CommitListener>>>,
+}
+
+impl InnerAutoCommitWriter {
+    fn commit(&self) -> crate::Result<Opstamp> {
+        let commit_result = {
+            // Holding the write lock ensures no operation is added while committing.
+            let mut index_writer = self.index_writer.write().unwrap();
+            self.num_pending_operations.store(0, Ordering::SeqCst);
+            index_writer.commit()
+        };
+        let commit_listener_opt = self.commit_listener.read().unwrap().clone();
+        if let Some(commit_listener) = commit_listener_opt {
+            commit_listener.on_commit(&commit_result);
+        }
+        commit_result
+    }
+}
+
+/// Wraps an `IndexWriter` to commit its pending changes in the background,
+/// according to an [`AutoCommitPolicy`](./struct.AutoCommitPolicy.html).
+///
+/// The commits run on a dedicated thread. Documents can be added concurrently from
+/// several threads, as the `AutoCommitWriter` is `Sync`.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, AutoCommitPolicy, AutoCommitWriter, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let auto_commit_policy = AutoCommitPolicy::default()
+///     .with_max_pending_operations(10_000)
+///     .with_interval(Duration::from_secs(1));
+/// let auto_commit_writer =
+///     AutoCommitWriter::new(index.writer(50_000_000)?, auto_commit_policy)?;
+/// auto_commit_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+/// let _index_writer = auto_commit_writer.into_inner()?;
+/// assert_eq!(index.reader()?.searcher().num_docs(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AutoCommitWriter {
+    inner: Arc<InnerAutoCommitWriter>,
+    policy: AutoCommitPolicy,
+    signal_sender: Sender<CommitSignal>,
+    commit_thread: Option<JoinHandle<()>>,
+}
+
+impl AutoCommitWriter {
+    /// Creates an `AutoCommitWriter`, and starts its commit thread.
+    pub fn new(
+        index_writer: IndexWriter,
+        policy: AutoCommitPolicy,
+    ) -> crate::Result<AutoCommitWriter> {
+        let inner = Arc::new(InnerAutoCommitWriter {
+            index_writer: RwLock::new(index_writer),
+            num_pending_operations: AtomicU64::new(0),
+            commit_listener: RwLock::new(None),
+        });
+        let (signal_sender, signal_receiver) = channel::unbounded();
+        let thread_inner = inner.clone();
+        let interval = policy.interval;
+        let commit_thread = thread::Builder::new()
+            .name("thrd-tantivy-autocommit".to_string())
+            .spawn(move || run_commit_loop(&thread_inner, &signal_receiver, interval))
+            .map_err(|_| {
+                TantivyError::SystemError("Failed to spawn the auto commit thread".to_string())
+            })?;
+        Ok(AutoCommitWriter {
+            inner,
+            policy,
+            signal_sender,
+            commit_thread: Some(commit_thread),
+        })
+    }
+
+    /// Sets a listener notified of the outcome of every commit.
+    pub fn set_commit_listener(&self, commit_listener: Box<dyn CommitListener>) {
+        *self.inner.commit_listener.write().unwrap() = Some(Arc::from(commit_listener));
+    }
+
+    /// Returns the policy of the `AutoCommitWriter`.
+    pub fn policy(&self) -> &AutoCommitPolicy {
+        &self.policy
+    }
+
+    /// Adds a document, see [`IndexWriter::add_document`](./struct.IndexWriter.html#method.add_document).
+    pub fn add_document(&self, document: Document) -> crate::Result<Opstamp> {
+        let opstamp = self.index_writer().add_document(document)?;
+        self.record_operation();
+        Ok(opstamp)
+    }
+
+    /// Deletes the documents containing `term`, see
+    /// [`IndexWriter::delete_term`](./struct.IndexWriter.html#method.delete_term).
+    pub fn delete_term(&self, term: Term) -> Opstamp {
+        let opstamp = self.index_writer().delete_term(term);
+        self.record_operation();
+        opstamp
+    }
+
+    /// Returns the number of operations added since the last commit.
+    pub fn num_pending_operations(&self) -> u64 {
+        self.inner.num_pending_operations.load(Ordering::SeqCst)
+    }
+
+    /// Commits the pending changes right away, on the calling thread.
+    ///
+    /// The commit listener is notified as for the commits run in the background.
+    pub fn commit(&self) -> crate::Result<Opstamp> {
+        self.inner.commit()
+    }
+
+    /// Gives access to the wrapped `IndexWriter`, e.g. to change its merge policy.
+    ///
+    /// Background commits wait for the returned guard to be dropped.
+    pub fn index_writer(&self) -> RwLockReadGuard<'_, IndexWriter> {
+        self.inner.index_writer.read().unwrap()
+    }
+
+    /// Stops the commit thread, commits the pending changes if any, and returns
+    /// the wrapped `IndexWriter`.
+    pub fn into_inner(mut self) -> crate::Result<IndexWriter> {
+        self.stop_commit_thread()?;
+        if self.num_pending_operations() > 0 {
+            self.inner.commit()?;
+        }
+        let inner = self.inner.clone();
+        drop(self);
+        let inner = Arc::try_unwrap(inner).map_err(|_| {
+            TantivyError::SystemError("The auto commit writer is still in use".to_string())
+        })?;
+        Ok(inner.index_writer.into_inner().unwrap())
+    }
+
+    fn record_operation(&self) {
+        let num_pending_operations = self
+            .inner
+            .num_pending_operations
+            .fetch_add(1, Ordering::SeqCst)
+            + 1;
+        if self.policy.max_pending_operations == Some(num_pending_operations) {
+            let _ = self.signal_sender.send(CommitSignal::Commit);
+        }
+    }
+
+    fn stop_commit_thread(&mut self) -> crate::Result<()> {
+        if let Some(commit_thread) = self.commit_thread.take() {
+            let _ = self.signal_sender.send(CommitSignal::Stop);
+            commit_thread
+                .join()
+                .map_err(|e| TantivyError::ErrorInThread(format!("{:?}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AutoCommitWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop_commit_thread() {
+            warn!("Failed to stop the auto commit thread: {:?}", err);
+        }
+    }
+}
+
+fn run_commit_loop(
+    inner: &InnerAutoCommitWriter,
+    signal_receiver: &Receiver<CommitSignal>,
+    interval: Option<Duration>,
+) {
+    loop {
+        let signal = match interval {
+            Some(interval) => match signal_receiver.recv_timeout(interval) {
+                Ok(signal) => signal,
+                Err(RecvTimeoutError::Timeout) => CommitSignal::Commit,
+                Err(RecvTimeoutError::Disconnected) => CommitSignal::Stop,
+            },
+            None => signal_receiver.recv().unwrap_or(CommitSignal::Stop),
+        };
+        match signal {
+            CommitSignal::Commit => {
+                if inner.num_pending_operations.load(Ordering::SeqCst) == 0 {
+                    continue;
+                }
+                if let Err(err) = inner.commit() {
+                    warn!("Auto commit failed: {:?}", err);
+                }
+            }
+            CommitSignal::Stop => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AutoCommitPolicy, AutoCommitWriter, CommitListener};
+    use crate::schema::{Schema, TEXT};
+    use crate::{doc, Index, Opstamp, Term};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[derive(Clone, Default)]
+    struct RecordingCommitListener(Arc<Mutex<Vec<Opstamp>>>);
+
+    impl RecordingCommitListener {
+        fn wait_for_commits(&self, num_commits: usize) -> Vec<Opstamp> {
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(10) {
+                let commits = self.0.lock().unwrap().clone();
+                if commits.len() >= num_commits {
+                    return commits;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            panic!("Timed out waiting for {} commits", num_commits);
+        }
+    }
+
+    impl CommitListener for RecordingCommitListener {
+        fn on_commit(&self, commit_result: &crate::Result<Opstamp>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(*commit_result.as_ref().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_auto_commit_max_pending_operations() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let auto_commit_writer = AutoCommitWriter::new(
+            index.writer_for_tests()?,
+            AutoCommitPolicy::default().with_max_pending_operations(3),
+        )?;
+        let commit_listener = RecordingCommitListener::default();
+        auto_commit_writer.set_commit_listener(Box::new(commit_listener.clone()));
+        auto_commit_writer.add_document(doc!(text => "a"))?;
+        auto_commit_writer.add_document(doc!(text => "b"))?;
+        assert_eq!(auto_commit_writer.num_pending_operations(), 2);
+        auto_commit_writer.delete_term(Term::from_field_text(text, "a"));
+        let commits = commit_listener.wait_for_commits(1);
+        assert_eq!(commits, vec![index.load_metas()?.opstamp]);
+        assert_eq!(auto_commit_writer.num_pending_operations(), 0);
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+
+        auto_commit_writer.add_document(doc!(text => "c"))?;
+        let _index_writer = auto_commit_writer.into_inner()?;
+        let commits = commit_listener.wait_for_commits(2);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[1], index.load_metas()?.opstamp);
+        assert_eq!(index.reader()?.searcher().num_docs(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_commit_interval() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let auto_commit_writer = AutoCommitWriter::new(
+            index.writer_for_tests()?,
+            AutoCommitPolicy::default().with_interval(Duration::from_millis(20)),
+        )?;
+        let commit_listener = RecordingCommitListener::default();
+        auto_commit_writer.set_commit_listener(Box::new(commit_listener.clone()));
+        auto_commit_writer.add_document(doc!(text => "a"))?;
+        commit_listener.wait_for_commits(1);
+        // No commit is run when there is nothing to commit.
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(commit_listener.wait_for_commits(1).len(), 1);
+        assert_eq!(index.reader()?.searcher().num_docs(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use crate::schema::{Document, Field, FieldType, IndexRecordOption, Schema, Term};
+use crate::{DocSet, IndexReader, IndexWriter, Opstamp, ReloadPolicy, TantivyError, TERMINATED};
+use common::BinarySerializable;
+use fnv::FnvHasher;
+use std::collections::HashSet;
+use std::hash::Hasher;
+use std::sync::Mutex;
+
+/// What a [`DeduplicatingWriter`](./struct.DeduplicatingWriter.html) does with
+/// a document whose content hash is already in the index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OnDuplicate {
+    /// The document is not added.
+    Reject,
+    /// The documents with the same content hash are deleted, and the document is added.
+    Update,
+}
+
+/// Defines how a [`DeduplicatingWriter`](./struct.DeduplicatingWriter.html) detects
+/// duplicate documents.
+#[derive(Clone, Debug)]
+pub struct DeduplicationPolicy {
+    hash_field: Field,
+    hashed_fields: Vec<Field>,
+    on_duplicate: OnDuplicate,
+}
+
+impl DeduplicationPolicy {
+    /// Creates a policy hashing the values of `hashed_fields`, and recording the
+    /// hash in `hash_field`, which must be an indexed `u64` field.
+    ///
+    /// Duplicate documents are rejected by default.
+    pub fn new(hash_field: Field, hashed_fields: Vec<Field>) -> DeduplicationPolicy {
+        DeduplicationPolicy {
+            hash_field,
+            hashed_fields,
+            on_duplicate: OnDuplicate::Reject,
+        }
+    }
+
+    /// Sets what is done with the duplicate documents.
+    pub fn with_on_duplicate(mut self, on_duplicate: OnDuplicate) -> Self {
+        self.on_duplicate = on_duplicate;
+        self
+    }
+
+    /// Returns the field recording the content hash of the documents.
+    pub fn hash_field(&self) -> Field {
+        self.hash_field
+    }
+
+    /// Returns the fields whose values are hashed.
+    pub fn hashed_fields(&self) -> &[Field] {
+        &self.hashed_fields
+    }
+
+    /// Returns what is done with the duplicate documents.
+    pub fn on_duplicate(&self) -> OnDuplicate {
+        self.on_duplicate
+    }
+
+    fn validate(&self, schema: &Schema) -> crate::Result<()> {
+        if self.hashed_fields.is_empty() {
+            return Err(TantivyError::InvalidArgument(
+                "At least one field must be hashed to detect duplicates.".to_string(),
+            ));
+        }
+        let field_entry = schema.get_field_entry(self.hash_field);
+        match field_entry.field_type() {
+            FieldType::U64(_) if field_entry.is_indexed() => Ok(()),
+            _ => Err(TantivyError::SchemaError(format!(
+                "The hash field {:?} is not an indexed u64 field.",
+                field_entry.name()
+            ))),
+        }
+    }
+}
+
+/// Wraps an `IndexWriter` to keep ingestion idempotent, by detecting the documents
+/// that were already added, according to a
+/// [`DeduplicationPolicy`](./struct.DeduplicationPolicy.html).
+///
+/// A content hash is computed over the values of the hashed fields of each added
+/// document, and recorded in the hash field of the document. A document is a duplicate
+/// if its hash is found in the term dictionary of the hash field, for a document that
+/// is not deleted, or if a document with the same hash was added since the last commit.
+/// The duplicate documents are either rejected, or replace the documents they duplicate.
+///
+/// The committed documents are looked up with a reader reloaded on every commit, so
+/// the documents deleted with [`delete_term`](#method.delete_term) are only forgotten
+/// after the next commit.
+///
+/// ```rust
+/// use tantivy::schema::{Schema, INDEXED, TEXT};
+/// use tantivy::{doc, DeduplicatingWriter, DeduplicationPolicy, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let url = schema_builder.add_text_field("url", TEXT);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let content_hash = schema_builder.add_u64_field("content_hash", INDEXED);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let policy = DeduplicationPolicy::new(content_hash, vec![url, body]);
+/// let mut writer = DeduplicatingWriter::new(index.writer(50_000_000)?, policy)?;
+/// let page = doc!(url => "https://example.com", body => "Example Domain");
+/// assert!(writer.add_document(page.clone())?.is_some());
+/// writer.commit()?;
+/// // The page was crawled again.
+/// assert!(writer.add_document(page)?.is_none());
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeduplicatingWriter {
+    index_writer: IndexWriter,
+    policy: DeduplicationPolicy,
+    reader: IndexReader,
+    // Hashes of the documents added since the last commit.
+    pending_hashes: Mutex<HashSet<u64>>,
+}
+
+impl DeduplicatingWriter {
+    /// Creates a `DeduplicatingWriter`.
+    ///
+    /// Returns an error if the hash field is not an indexed `u64` field,
+    /// or if there is no field to hash.
+    pub fn new(
+        index_writer: IndexWriter,
+        policy: DeduplicationPolicy,
+    ) -> crate::Result<DeduplicatingWriter> {
+        policy.validate(&index_writer.index().schema())?;
+        let reader = index_writer
+            .index()
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(DeduplicatingWriter {
+            index_writer,
+            policy,
+            reader,
+            pending_hashes: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Returns the policy of the `DeduplicatingWriter`.
+    pub fn policy(&self) -> &DeduplicationPolicy {
+        &self.policy
+    }
+
+    /// Returns the content hash of `document`, as recorded in the hash field.
+    pub fn content_hash(&self, document: &Document) -> u64 {
+        let mut hasher = FnvHasher::default();
+        let mut buffer = Vec::new();
+        for &field in &self.policy.hashed_fields {
+            buffer.clear();
+            // Serializing the field and the number of values keeps the hashes
+            // of distinct documents apart when the values are moved across fields.
+            let values: Vec<_> = document.get_all(field).collect();
+            field
+                .field_id()
+                .serialize(&mut buffer)
+                .and_then(|_| (values.len() as u32).serialize(&mut buffer))
+                .expect("Writing to a Vec should never fail");
+            for value in values {
+                value
+                    .serialize(&mut buffer)
+                    .expect("Writing to a Vec should never fail");
+            }
+            hasher.write(&buffer);
+        }
+        hasher.finish()
+    }
+
+    /// Adds `document`, unless it is a duplicate and the policy rejects duplicates.
+    ///
+    /// The content hash is added to the hash field of the document. Returns the opstamp
+    /// of the operation, or `None` if the document was rejected.
+    pub fn add_document(&self, mut document: Document) -> crate::Result<Option<Opstamp>> {
+        let content_hash = self.content_hash(&document);
+        let hash_term = Term::from_field_u64(self.policy.hash_field, content_hash);
+        // The lock is held until the document is added, so that two concurrent
+        // duplicates cannot both be added.
+        let mut pending_hashes = self.pending_hashes.lock().unwrap();
+        let is_duplicate =
+            pending_hashes.contains(&content_hash) || self.is_committed(&hash_term)?;
+        if is_duplicate {
+            match self.policy.on_duplicate {
+                OnDuplicate::Reject => return Ok(None),
+                OnDuplicate::Update => {
+                    self.index_writer.delete_term(hash_term);
+                }
+            }
+        }
+        document.add_u64(self.policy.hash_field, content_hash);
+        let opstamp = self.index_writer.add_document(document)?;
+        pending_hashes.insert(content_hash);
+        Ok(Some(opstamp))
+    }
+
+    /// Deletes the documents containing `term`, see
+    /// [`IndexWriter::delete_term`](./struct.IndexWriter.html#method.delete_term).
+    pub fn delete_term(&self, term: Term) -> Opstamp {
+        self.index_writer.delete_term(term)
+    }
+
+    /// Commits the pending changes, see
+    /// [`IndexWriter::commit`](./struct.IndexWriter.html#method.commit).
+    pub fn commit(&mut self) -> crate::Result<Opstamp> {
+        let opstamp = self.index_writer.commit()?;
+        self.reader.reload()?;
+        self.pending_hashes.lock().unwrap().clear();
+        Ok(opstamp)
+    }
+
+    /// Rolls back the pending changes, see
+    /// [`IndexWriter::rollback`](./struct.IndexWriter.html#method.rollback).
+    pub fn rollback(&mut self) -> crate::Result<Opstamp> {
+        let opstamp = self.index_writer.rollback()?;
+        self.pending_hashes.lock().unwrap().clear();
+        Ok(opstamp)
+    }
+
+    /// Gives access to the wrapped `IndexWriter`.
+    pub fn index_writer(&self) -> &IndexWriter {
+        &self.index_writer
+    }
+
+    /// Returns the wrapped `IndexWriter`, dropping the pending changes unless they are committed.
+    pub fn into_inner(self) -> IndexWriter {
+        self.index_writer
+    }
+
+    /// Returns true if a committed document that is not deleted contains `hash_term`.
+    fn is_committed(&self, hash_term: &Term) -> crate::Result<bool> {
+        let searcher = self.reader.searcher();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(hash_term.field())?;
+            let mut postings =
+                match inverted_index.read_postings(hash_term, IndexRecordOption::Basic)? {
+                    Some(postings) => postings,
+                    None => continue,
+                };
+            let mut doc = postings.doc();
+            while doc != TERMINATED {
+                if !segment_reader.is_deleted(doc) {
+                    return Ok(true);
+                }
+                doc = postings.advance();
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeduplicatingWriter, DeduplicationPolicy, OnDuplicate};
+    use crate::collector::Count;
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, INDEXED, STORED, STRING, TEXT};
+    use crate::{doc, Index, TantivyError, Term};
+
+    #[test]
+    fn test_deduplicating_writer_rejects_duplicates() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let url = schema_builder.add_text_field("url", STRING);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let crawl_id = schema_builder.add_u64_field("crawl_id", STORED);
+        let content_hash = schema_builder.add_u64_field("content_hash", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let policy = DeduplicationPolicy::new(content_hash, vec![url, body]);
+        let mut writer = DeduplicatingWriter::new(index.writer_for_tests()?, policy)?;
+
+        let page =
+            |body_text: &str, id: u64| doc!(url => "a.com", body => body_text, crawl_id => id);
+        assert!(writer.add_document(page("a", 1))?.is_some());
+        // Duplicate of a pending document.
+        assert!(writer.add_document(page("a", 2))?.is_none());
+        assert!(writer.add_document(page("b", 3))?.is_some());
+        writer.commit()?;
+        // Duplicate of a committed document.
+        assert!(writer.add_document(page("b", 4))?.is_none());
+        // The values are not mistaken for the values of another field.
+        assert!(writer
+            .add_document(doc!(url => "a", body => "a.com"))?
+            .is_some());
+        writer.commit()?;
+        assert_eq!(index.reader()?.searcher().num_docs(), 3);
+
+        let hash_term = Term::from_field_u64(content_hash, writer.content_hash(&page("b", 5)));
+        writer.delete_term(hash_term.clone());
+        writer.commit()?;
+        assert!(writer.add_document(page("b", 6))?.is_some());
+        writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let hash_query = TermQuery::new(hash_term, IndexRecordOption::Basic);
+        assert_eq!(searcher.search(&hash_query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduplicating_writer_updates_duplicates() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let url = schema_builder.add_text_field("url", STRING);
+        let crawl_id = schema_builder.add_u64_field("crawl_id", INDEXED);
+        let content_hash = schema_builder.add_u64_field("content_hash", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let policy = DeduplicationPolicy::new(content_hash, vec![url])
+            .with_on_duplicate(OnDuplicate::Update);
+        let mut writer = DeduplicatingWriter::new(index.writer_for_tests()?, policy)?;
+        assert!(writer
+            .add_document(doc!(url => "a.com", crawl_id => 1u64))?
+            .is_some());
+        writer.commit()?;
+        assert!(writer
+            .add_document(doc!(url => "a.com", crawl_id => 2u64))?
+            .is_some());
+        assert!(writer
+            .add_document(doc!(url => "a.com", crawl_id => 3u64))?
+            .is_some());
+        writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+        let crawl_query =
+            TermQuery::new(Term::from_field_u64(crawl_id, 3), IndexRecordOption::Basic);
+        assert_eq!(searcher.search(&crawl_query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduplication_policy_validation() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let url = schema_builder.add_text_field("url", STRING);
+        let stored_hash = schema_builder.add_u64_field("stored_hash", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let policy = DeduplicationPolicy::new(stored_hash, vec![url]);
+        assert!(matches!(
+            DeduplicatingWriter::new(index.writer_for_tests()?, policy),
+            Err(TantivyError::SchemaError(_))
+        ));
+        let policy = DeduplicationPolicy::new(url, vec![]);
+        assert!(matches!(
+            DeduplicatingWriter::new(index.writer_for_tests()?, policy),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
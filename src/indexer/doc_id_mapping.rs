@@ -10,8 +10,12 @@ use crate::{
 use std::{cmp::Reverse, ops::Index};
 
 /// Struct to provide mapping from new doc_id to old doc_id and segment.
-#[derive(Clone)]
-pub(crate) struct SegmentDocIdMapping {
+///
+/// It describes how the documents of the segments being merged are laid out in the
+/// merged segment: the `i`-th element is the `(DocId, SegmentOrdinal)` of the
+/// document that gets the `DocId` `i` in the merged segment.
+#[derive(Clone, Debug)]
+pub struct SegmentDocIdMapping {
     new_doc_id_to_old_and_segment: Vec<(DocId, SegmentOrdinal)>,
     is_trivial: bool,
 }
@@ -26,17 +30,28 @@ impl SegmentDocIdMapping {
             is_trivial,
         }
     }
-    pub(crate) fn iter(&self) -> impl Iterator<Item = &(DocId, SegmentOrdinal)> {
+    /// Iterates over the `(old DocId, SegmentOrdinal)` of the documents,
+    /// in the order of their new `DocId`.
+    pub fn iter(&self) -> impl Iterator<Item = &(DocId, SegmentOrdinal)> + Clone {
         self.new_doc_id_to_old_and_segment.iter()
     }
-    pub(crate) fn len(&self) -> usize {
+    /// Number of documents in the merged segment.
+    pub fn len(&self) -> usize {
         self.new_doc_id_to_old_and_segment.len()
     }
+    /// Returns true iff the merged segment has no documents.
+    pub fn is_empty(&self) -> bool {
+        self.new_doc_id_to_old_and_segment.is_empty()
+    }
+    /// Returns the `(old DocId, SegmentOrdinal)` of the document with the given new `DocId`.
+    pub fn old_doc_id_and_segment(&self, new_doc_id: DocId) -> (DocId, SegmentOrdinal) {
+        self.new_doc_id_to_old_and_segment[new_doc_id as usize]
+    }
     /// This flags means the segments are simply stacked in the order of their ordinal.
     /// e.g. [(0, 1), .. (n, 1), (0, 2)..., (m, 2)]
     ///
     /// This allows for some optimization.
-    pub(crate) fn is_trivial(&self) -> bool {
+    pub fn is_trivial(&self) -> bool {
         self.is_trivial
     }
 }
@@ -15,11 +15,15 @@ use crate::fastfield::write_alive_bitset;
 use crate::indexer::delete_queue::{DeleteCursor, DeleteQueue};
 use crate::indexer::doc_opstamp_mapping::DocToOpstampMapping;
 use crate::indexer::index_writer_status::IndexWriterStatus;
-use crate::indexer::operation::DeleteOperation;
+use crate::indexer::merger::MAX_DOC_LIMIT;
+use crate::indexer::operation::{DeleteOperation, DeleteTarget};
 use crate::indexer::stamper::Stamper;
-use crate::indexer::MergePolicy;
 use crate::indexer::SegmentEntry;
-use crate::indexer::SegmentWriter;
+use crate::indexer::{IndexingObserver, SegmentIndexingStatistics};
+use crate::indexer::{IngestLineError, IngestReport};
+use crate::indexer::{MergeListener, MergePlan, MergePolicy, MergeThrottle, MergeThroughput};
+use crate::indexer::{SegmentWriter, SegmentWriterMemoryUsage};
+use crate::schema::DocParsingError;
 use crate::schema::Document;
 use crate::schema::IndexRecordOption;
 use crate::schema::Term;
@@ -29,10 +33,13 @@ use crossbeam::channel;
 use futures::executor::block_on;
 use futures::future::Future;
 use smallvec::smallvec;
+use std::io::BufRead;
 use std::ops::Range;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use super::{AddBatch, AddBatchReceiver, AddBatchSender};
 
@@ -51,6 +58,15 @@ pub const MAX_NUM_THREAD: usize = 8;
 // reaches `PIPELINE_MAX_SIZE_IN_DOCS`
 const PIPELINE_MAX_SIZE_IN_DOCS: usize = 10_000;
 
+// `ingest_ndjson` adds the parsed documents by batches of `NDJSON_BATCH_SIZE`.
+const NDJSON_BATCH_SIZE: usize = 256;
+
+// Maximum number of documents of a batch given to `add_documents`.
+//
+// Segments are flushed once they hold that many documents, so that the following
+// batch cannot make them exceed `MAX_DOC_LIMIT`.
+const MAX_DOCS_PER_BATCH: u32 = MAX_DOC_LIMIT / 2;
+
 fn error_in_index_worker_thread(context: &str) -> TantivyError {
     TantivyError::ErrorInThread(format!(
         "{}. A worker thread encounterred an error (io::Error most likely) or panicked.",
@@ -73,8 +89,19 @@ pub struct IndexWriter {
 
     heap_size_in_bytes_per_thread: usize,
 
+    // whether the indexing threads flush their segments in the background.
+    background_flush: Arc<AtomicBool>,
+
     workers_join_handle: Vec<JoinHandle<crate::Result<()>>>,
 
+    // memory used by the segment writer of each indexing thread.
+    workers_mem_usage: Vec<Arc<Mutex<SegmentWriterMemoryUsage>>>,
+    // highest memory usage reached by the segment writer of each indexing thread.
+    workers_peak_mem_usage: Vec<Arc<AtomicUsize>>,
+    // number of terms of the last segment of each indexing thread, or 0 before its
+    // first segment. The term hash table of its next segment is sized accordingly.
+    workers_num_terms: Vec<Arc<AtomicUsize>>,
+
     index_writer_status: IndexWriterStatus,
     operation_sender: AddBatchSender,
 
@@ -105,17 +132,29 @@ fn compute_deleted_bitset(
 
         // A delete operation should only affect
         // document that were inserted before it.
-        let inverted_index = segment_reader.inverted_index(delete_op.term.field())?;
-        if let Some(mut docset) =
-            inverted_index.read_postings(&delete_op.term, IndexRecordOption::Basic)?
-        {
-            let mut doc_matching_deleted_term = docset.doc();
-            while doc_matching_deleted_term != TERMINATED {
-                if doc_opstamps.is_deleted(doc_matching_deleted_term, delete_op.opstamp) {
-                    alive_bitset.remove(doc_matching_deleted_term);
-                    might_have_changed = true;
+        match delete_op.target {
+            DeleteTarget::Term(ref term) => {
+                let inverted_index = segment_reader.inverted_index(term.field())?;
+                if let Some(mut docset) =
+                    inverted_index.read_postings(term, IndexRecordOption::Basic)?
+                {
+                    let mut doc_matching_deleted_term = docset.doc();
+                    while doc_matching_deleted_term != TERMINATED {
+                        if doc_opstamps.is_deleted(doc_matching_deleted_term, delete_op.opstamp) {
+                            alive_bitset.remove(doc_matching_deleted_term);
+                            might_have_changed = true;
+                        }
+                        doc_matching_deleted_term = docset.advance();
+                    }
+                }
+            }
+            DeleteTarget::AllDocuments => {
+                for doc in 0..segment_reader.max_doc() {
+                    if doc_opstamps.is_deleted(doc, delete_op.opstamp) {
+                        alive_bitset.remove(doc);
+                        might_have_changed = true;
+                    }
                 }
-                doc_matching_deleted_term = docset.advance();
             }
         }
         delete_cursor.advance();
@@ -180,21 +219,47 @@ pub(crate) fn advance_deletes(
     Ok(())
 }
 
+/// Fills a `SegmentWriter` with the documents of `grouped_document_iterator`,
+/// until the memory budget or the document limit is reached, or until
+/// the iterator is exhausted.
+///
+/// The returned segment writer still needs to be flushed, see `flush_segment`.
+/// It is returned with the time spent adding the documents.
 fn index_documents(
     memory_budget: usize,
     segment: Segment,
     grouped_document_iterator: &mut dyn Iterator<Item = AddBatch>,
-    segment_updater: &mut SegmentUpdater,
-    mut delete_cursor: DeleteCursor,
-) -> crate::Result<()> {
+    worker_mem_usage: &Mutex<SegmentWriterMemoryUsage>,
+    worker_peak_mem_usage: &AtomicUsize,
+    worker_num_terms: &AtomicUsize,
+    flush_join_handle: &mut Option<JoinHandle<crate::Result<()>>>,
+) -> crate::Result<(SegmentWriter, Duration)> {
     let schema = segment.schema();
 
-    let mut segment_writer = SegmentWriter::for_segment(memory_budget, segment.clone(), &schema)?;
+    let previous_num_terms = Some(worker_num_terms.load(Ordering::Relaxed)).filter(|&n| n > 0);
+    let mut segment_writer =
+        SegmentWriter::for_segment(memory_budget, segment.clone(), &schema, previous_num_terms)?;
+    let mut published_mem_usage = 0;
+    let mut tokenizing_time = Duration::default();
     for document_group in grouped_document_iterator {
+        check_finished_flush(flush_join_handle)?;
+        let start = Instant::now();
         for doc in document_group {
             segment_writer.add_document(doc, &schema)?;
         }
+        tokenizing_time += start.elapsed();
         let mem_usage = segment_writer.mem_usage();
+        if mem_usage != published_mem_usage {
+            // The breakdown is more expensive to compute,
+            // so it is only refreshed when the memory usage changes.
+            let mut mem_usage_breakdown = segment_writer.mem_usage_breakdown();
+            let mut worker_mem_usage = worker_mem_usage.lock().unwrap();
+            let flushing = worker_mem_usage.flushing();
+            mem_usage_breakdown.set_flushing(flushing);
+            *worker_mem_usage = mem_usage_breakdown;
+            worker_peak_mem_usage.fetch_max(mem_usage + flushing, Ordering::Relaxed);
+            published_mem_usage = mem_usage;
+        }
         if mem_usage >= memory_budget - MARGIN_IN_BYTES {
             info!(
                 "Buffer limit reached, flushing segment with maxdoc={}.",
@@ -202,8 +267,29 @@ fn index_documents(
             );
             break;
         }
+        if segment_writer.max_doc() >= MAX_DOCS_PER_BATCH {
+            info!(
+                "Document limit reached, flushing segment with maxdoc={}.",
+                segment_writer.max_doc()
+            );
+            break;
+        }
     }
+    worker_num_terms.store(segment_writer.num_terms(), Ordering::Relaxed);
+    Ok((segment_writer, tokenizing_time))
+}
 
+/// Serializes the segment of `segment_writer`, applies the deletes that
+/// happened since its documents were added, and hands it to the `SegmentUpdater`.
+///
+/// The `IndexingObserver`, if any, is notified of the statistics of the segment.
+fn flush_segment(
+    segment_writer: SegmentWriter,
+    segment: Segment,
+    segment_updater: &mut SegmentUpdater,
+    mut delete_cursor: DeleteCursor,
+    tokenizing_time: Duration,
+) -> crate::Result<()> {
     if !segment_updater.is_alive() {
         return Ok(());
     }
@@ -214,9 +300,28 @@ fn index_documents(
     // the worker thread.
     assert!(max_doc > 0);
 
+    let indexing_observer_opt = segment_updater.get_indexing_observer();
+    let field_statistics = if indexing_observer_opt.is_some() {
+        segment_writer
+            .multifield_postings
+            .per_field_indexing_statistics()
+    } else {
+        Vec::new()
+    };
+
+    let start = Instant::now();
     let doc_opstamps: Vec<Opstamp> = segment_writer.finalize()?;
+    if let Some(indexing_observer) = indexing_observer_opt {
+        indexing_observer.on_segment_flushed(&SegmentIndexingStatistics {
+            segment_id: segment.id(),
+            num_docs: max_doc,
+            fields: field_statistics,
+            tokenizing_time,
+            serializing_time: start.elapsed(),
+        });
+    }
 
-    let segment_with_max_doc = segment.with_max_doc(max_doc);
+    let segment_with_max_doc = segment.with_max_doc(max_doc).with_computed_field_bounds()?;
 
     let alive_bitset_opt = apply_deletes(&segment_with_max_doc, &mut delete_cursor, &doc_opstamps)?;
 
@@ -228,6 +333,30 @@ fn index_documents(
     Ok(())
 }
 
+/// Waits for the flush of the previous segment of an indexing worker, if any.
+fn wait_for_flush(flush_join_handle: Option<JoinHandle<crate::Result<()>>>) -> crate::Result<()> {
+    if let Some(flush_join_handle) = flush_join_handle {
+        flush_join_handle
+            .join()
+            .map_err(|_| error_in_index_worker_thread("Flushing thread panicked."))??;
+    }
+    Ok(())
+}
+
+/// Joins the flushing thread if it is over, so that a failed flush
+/// is reported before more documents are indexed.
+fn check_finished_flush(
+    flush_join_handle: &mut Option<JoinHandle<crate::Result<()>>>,
+) -> crate::Result<()> {
+    if flush_join_handle
+        .as_ref()
+        .is_some_and(JoinHandle::is_finished)
+    {
+        wait_for_flush(flush_join_handle.take())?;
+    }
+    Ok(())
+}
+
 /// `doc_opstamps` is required to be non-empty.
 fn apply_deletes(
     segment: &Segment,
@@ -312,6 +441,7 @@ impl IndexWriter {
             _directory_lock: Some(directory_lock),
 
             heap_size_in_bytes_per_thread,
+            background_flush: Arc::default(),
             index: index.clone(),
 
             index_writer_status: IndexWriterStatus::from(document_receiver),
@@ -320,6 +450,9 @@ impl IndexWriter {
             segment_updater,
 
             workers_join_handle: vec![],
+            workers_mem_usage: (0..num_threads).map(|_| Arc::default()).collect(),
+            workers_peak_mem_usage: (0..num_threads).map(|_| Arc::default()).collect(),
+            workers_num_terms: (0..num_threads).map(|_| Arc::default()).collect(),
             num_threads,
 
             delete_queue,
@@ -370,7 +503,11 @@ impl IndexWriter {
         result
     }
 
-    #[doc(hidden)]
+    /// Adds a segment built outside of the index writer, e.g. by an
+    /// [`InvertedIndexWriter`](../struct.InvertedIndexWriter.html).
+    ///
+    /// The segment files must already be in the directory of the index. The segment
+    /// becomes searchable after the next commit.
     pub fn add_segment(&self, segment_meta: SegmentMeta) -> crate::Result<()> {
         let delete_cursor = self.delete_queue.cursor();
         let segment_entry = SegmentEntry::new(segment_meta, delete_cursor, None);
@@ -401,15 +538,30 @@ impl IndexWriter {
         let document_receiver_clone = self.operation_receiver()?;
         let index_writer_bomb = self.index_writer_status.create_bomb();
 
-        let mut segment_updater = self.segment_updater.clone();
+        let segment_updater = self.segment_updater.clone();
 
         let mut delete_cursor = self.delete_queue.cursor();
 
-        let mem_budget = self.heap_size_in_bytes_per_thread;
+        // While a segment is flushed in the background, the worker fills the next one:
+        // each of them gets half of the budget of the thread, the margin being shared.
+        // A budget too small to be split is used by one segment at a time.
+        let heap_size_in_bytes_per_thread = self.heap_size_in_bytes_per_thread;
+        let split_mem_budget = (heap_size_in_bytes_per_thread + MARGIN_IN_BYTES) / 2;
+        let background_flush = self.background_flush.clone();
+        let worker_mem_usage = self.workers_mem_usage[self.worker_id % self.num_threads].clone();
+        let worker_peak_mem_usage =
+            self.workers_peak_mem_usage[self.worker_id % self.num_threads].clone();
+        let worker_num_terms = self.workers_num_terms[self.worker_id % self.num_threads].clone();
         let index = self.index.clone();
+        let worker_id = self.worker_id;
         let join_handle: JoinHandle<crate::Result<()>> = thread::Builder::new()
-            .name(format!("thrd-tantivy-index{}", self.worker_id))
+            .name(format!("thrd-tantivy-index{}", worker_id))
             .spawn(move || {
+                // The segments are flushed on a separate thread, so that the worker
+                // can start filling a new segment right away.
+                // At most one flush is in flight per worker, which keeps the memory
+                // used by a worker within its budget.
+                let mut flush_join_handle: Option<JoinHandle<crate::Result<()>>> = None;
                 loop {
                     let mut document_iterator = document_receiver_clone
                         .clone()
@@ -429,17 +581,65 @@ impl IndexWriter {
                         // No more documents.
                         // It happens when there is a commit, or if the `IndexWriter`
                         // was dropped.
+                        wait_for_flush(flush_join_handle.take())?;
                         index_writer_bomb.defuse();
                         return Ok(());
                     }
 
-                    index_documents(
-                        mem_budget,
-                        index.new_segment(),
+                    let flush_in_background = background_flush.load(Ordering::Relaxed)
+                        && split_mem_budget >= HEAP_SIZE_MIN;
+                    let segment_mem_budget = if flush_in_background {
+                        split_mem_budget
+                    } else {
+                        // The segment gets the whole budget, so the previous flush
+                        // has to be over.
+                        wait_for_flush(flush_join_handle.take())?;
+                        heap_size_in_bytes_per_thread
+                    };
+                    let segment = index.new_segment();
+                    let index_result = index_documents(
+                        segment_mem_budget,
+                        segment.clone(),
                         &mut document_iterator,
-                        &mut segment_updater,
-                        delete_cursor.clone(),
-                    )?;
+                        &worker_mem_usage,
+                        &worker_peak_mem_usage,
+                        &worker_num_terms,
+                        &mut flush_join_handle,
+                    );
+                    // The memory of the previous segment is only released
+                    // once its flush is over.
+                    wait_for_flush(flush_join_handle.take())?;
+                    // the segment writer is handed to the flushing thread, which holds
+                    // its memory until the end of the flush.
+                    let mut mem_usage = SegmentWriterMemoryUsage::default();
+                    if let Ok((segment_writer, _)) = &index_result {
+                        mem_usage.set_flushing(segment_writer.mem_usage());
+                    }
+                    *worker_mem_usage.lock().unwrap() = mem_usage;
+                    let (segment_writer, tokenizing_time) = index_result?;
+
+                    let mut flush_segment_updater = segment_updater.clone();
+                    let flush_delete_cursor = delete_cursor.clone();
+                    let flush_worker_mem_usage = worker_mem_usage.clone();
+                    flush_join_handle = Some(
+                        thread::Builder::new()
+                            .name(format!("thrd-tantivy-flush{}", worker_id))
+                            .spawn(move || {
+                                let flush_result = flush_segment(
+                                    segment_writer,
+                                    segment,
+                                    &mut flush_segment_updater,
+                                    flush_delete_cursor,
+                                    tokenizing_time,
+                                );
+                                // the segment writer has been dropped.
+                                flush_worker_mem_usage.lock().unwrap().set_flushing(0);
+                                flush_result
+                            })?,
+                    );
+                    if !flush_in_background {
+                        wait_for_flush(flush_join_handle.take())?;
+                    }
                 }
             })?;
         self.worker_id += 1;
@@ -447,6 +647,55 @@ impl IndexWriter {
         Ok(())
     }
 
+    /// Returns the memory currently used by each indexing thread to buffer
+    /// the documents of its in-progress segment, and of the segment it flushes
+    /// in the background if any.
+    ///
+    /// A thread flushes its segment when its memory usage, excluding the term arena,
+    /// gets close to the memory budget per thread given when creating the
+    /// `IndexWriter`. With [`IndexWriter::set_background_flush`], the threshold is
+    /// half of the budget: the other half is left for the segment being flushed.
+    ///
+    /// The breakdown of a thread is refreshed whenever its total memory usage changes:
+    /// the number of terms per field may lag behind the documents indexed.
+    pub fn mem_usage_per_thread(&self) -> Vec<SegmentWriterMemoryUsage> {
+        self.workers_mem_usage
+            .iter()
+            .map(|mem_usage| mem_usage.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Returns the memory currently used by all of the indexing threads, in bytes.
+    ///
+    /// See [`mem_usage_per_thread`](#method.mem_usage_per_thread).
+    pub fn mem_usage(&self) -> usize {
+        self.workers_mem_usage
+            .iter()
+            .map(|mem_usage| mem_usage.lock().unwrap().total())
+            .sum()
+    }
+
+    /// Returns the highest memory used by each indexing thread to buffer the documents
+    /// of a segment, since the creation of the `IndexWriter`.
+    ///
+    /// Like the memory budget per thread, it excludes the term arena.
+    /// The memory of a segment is released once it is flushed, so that the memory
+    /// currently used, as returned by [`mem_usage_per_thread`](#method.mem_usage_per_thread),
+    /// can be much lower. It is the figure to look at to size the memory budget.
+    pub fn peak_mem_usage_per_thread(&self) -> Vec<usize> {
+        self.workers_peak_mem_usage
+            .iter()
+            .map(|peak_mem_usage| peak_mem_usage.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Registers an `IndexingObserver`, notified of the statistics of the
+    /// segments flushed by the indexing threads after this call.
+    pub fn set_indexing_observer(&self, indexing_observer: Box<dyn IndexingObserver>) {
+        self.segment_updater
+            .set_indexing_observer(indexing_observer);
+    }
+
     /// Accessor to the merge policy.
     pub fn get_merge_policy(&self) -> Arc<dyn MergePolicy> {
         self.segment_updater.get_merge_policy()
@@ -457,6 +706,65 @@ impl IndexWriter {
         self.segment_updater.set_merge_policy(merge_policy);
     }
 
+    /// Returns the merges the merge policy would start given the current segments,
+    /// without starting them.
+    ///
+    /// The segments being merged are not considered. Each plan estimates the number
+    /// of bytes read and written by the merge, so that the heavy merges can be
+    /// scheduled during off-peak hours, e.g. with a `NoMergePolicy` set on the writer
+    /// and explicit calls to [`merge`](#method.merge).
+    ///
+    /// Note that the merge policy set on the writer is used, so the plans are empty
+    /// with a `NoMergePolicy`: `plan_merges` can be called after setting the merge
+    /// policy whose decisions should be inspected.
+    pub fn plan_merges(&self) -> crate::Result<Vec<MergePlan>> {
+        self.segment_updater.plan_merges()
+    }
+
+    /// Returns true if the indexing threads flush their segments in the background.
+    pub fn background_flush(&self) -> bool {
+        self.background_flush.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the indexing threads flush their full segments on a separate
+    /// thread, and keep on indexing in the meantime. Disabled by default.
+    ///
+    /// The memory budget of a thread is then split between the segment it fills
+    /// and the segment it flushes, so the segments are about half as large.
+    /// A budget lower than 5MB is not split, and the thread waits for the flush
+    /// of its segment before filling the next one.
+    ///
+    /// The setting applies from the next segment of each thread.
+    pub fn set_background_flush(&self, background_flush: bool) {
+        self.background_flush
+            .store(background_flush, Ordering::Relaxed);
+    }
+
+    /// Accessor to the merge throttle.
+    pub fn get_merge_throttle(&self) -> MergeThrottle {
+        self.segment_updater.get_merge_throttle()
+    }
+
+    /// Setter for the merge throttle, limiting the write throughput and the number
+    /// of concurrent merges.
+    ///
+    /// The new limits apply right away, including to the merges already running.
+    pub fn set_merge_throttle(&self, merge_throttle: MergeThrottle) {
+        self.segment_updater.set_merge_throttle(merge_throttle);
+    }
+
+    /// Returns the counters of the merges: number of running and waiting merges,
+    /// and bytes written.
+    pub fn merge_throughput(&self) -> MergeThroughput {
+        self.segment_updater.merge_throughput()
+    }
+
+    /// Registers a `MergeListener`, notified of the doc id mapping of the
+    /// merges started after this call.
+    pub fn set_merge_listener(&self, merge_listener: Box<dyn MergeListener>) {
+        self.segment_updater.set_merge_listener(merge_listener);
+    }
+
     fn start_workers(&mut self) -> crate::Result<()> {
         for _ in 0..self.num_threads {
             self.add_indexing_worker()?;
@@ -475,6 +783,14 @@ impl IndexWriter {
     /// Enables users to rebuild the index,
     /// by clearing and resubmitting necessary documents
     ///
+    /// The documents added before the call, including the ones that were not
+    /// committed yet, are deleted, while the documents added after the call are kept.
+    /// The next commit then atomically replaces the index by the documents added
+    /// since the call: the schema and the index settings are preserved, and the files
+    /// of the former segments are garbage collected.
+    ///
+    /// Returns the opstamp of the delete operation.
+    ///
     /// ```rust
     /// use tantivy::collector::TopDocs;
     /// use tantivy::query::QueryParser;
@@ -506,11 +822,17 @@ impl IndexWriter {
     /// }
     /// ```
     pub fn delete_all_documents(&self) -> crate::Result<Opstamp> {
+        // The documents still buffered by the indexing workers are deleted
+        // when their segment is flushed.
+        let opstamp = self.stamper.stamp();
+        let delete_operation = DeleteOperation {
+            opstamp,
+            target: DeleteTarget::AllDocuments,
+        };
+        self.delete_queue.push(delete_operation);
         // Delete segments
         self.segment_updater.remove_all_segments();
-        // Return new stamp - reverted stamp
-        self.stamper.revert(self.committed_opstamp);
-        Ok(self.committed_opstamp)
+        Ok(opstamp)
     }
 
     /// Merges a given list of segments
@@ -547,14 +869,41 @@ impl IndexWriter {
     /// After calling rollback, the index is in the same
     /// state as it was after the last commit.
     ///
+    /// The files of the segments created since the last commit
+    /// are garbage collected.
+    ///
     /// The opstamp at the last commit is returned.
     pub fn rollback(&mut self) -> crate::Result<Opstamp> {
         info!("Rolling back to opstamp {}", self.committed_opstamp);
         // marks the segment updater as killed. From now on, all
         // segment updates will be ignored.
         self.segment_updater.kill();
+        self.index.set_soft_committed_meta(None);
         let document_receiver_res = self.operation_receiver();
 
+        // This will drop the document queue, and the threads
+        // should terminate.
+        self.drop_sender();
+
+        // Drains the document receiver pipeline :
+        // Workers don't need to index the pending documents.
+        //
+        // This will reach an end as the only document_sender
+        // was dropped.
+        if let Ok(document_receiver) = document_receiver_res {
+            for _ in document_receiver {}
+        }
+
+        // The files of the segments the workers are still writing are alive:
+        // the workers, and their flushes, have to be over before the garbage collection.
+        for worker_handle in std::mem::take(&mut self.workers_join_handle) {
+            match worker_handle.join() {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => warn!("Indexing worker failed before rollback: {:?}", err),
+                Err(_) => warn!("Indexing worker panicked before rollback."),
+            }
+        }
+
         // take the directory lock to create a new index_writer.
         let directory_lock = self
             ._directory_lock
@@ -569,18 +918,12 @@ impl IndexWriter {
         )?;
 
         // the current `self` is dropped right away because of this call.
-        //
-        // This will drop the document queue, and the thread
-        // should terminate.
         *self = new_index_writer;
 
-        // Drains the document receiver pipeline :
-        // Workers don't need to index the pending documents.
-        //
-        // This will reach an end as the only document_sender
-        // was dropped with the index_writer.
-        if let Ok(document_receiver) = document_receiver_res {
-            for _ in document_receiver {}
+        // The segments created since the last commit are not referenced
+        // anymore, we can get rid of their files right away.
+        if let Err(err) = block_on(self.garbage_collect_files()) {
+            warn!("Failed to garbage collect files after rollback: {:?}", err);
         }
 
         Ok(self.committed_opstamp)
@@ -594,6 +937,10 @@ impl IndexWriter {
     /// of the size of their current segment and flush their
     /// work on disk.
     ///
+    /// If background flushing is enabled, the segments that filled up before
+    /// are flushed while the workers keep on indexing, so that only the flushes
+    /// still in flight are waited for.
+    ///
     /// Once a commit is "prepared", you can either
     /// call
     /// * `.commit()`: to accept this commit
@@ -620,19 +967,7 @@ impl IndexWriter {
         // committed segments.
         info!("Preparing commit");
 
-        // this will drop the current document channel
-        // and recreate a new one.
-        self.recreate_document_channel();
-
-        let former_workers_join_handle = std::mem::take(&mut self.workers_join_handle);
-
-        for worker_handle in former_workers_join_handle {
-            let indexing_worker_result = worker_handle
-                .join()
-                .map_err(|e| TantivyError::ErrorInThread(format!("{:?}", e)))?;
-            indexing_worker_result?;
-            self.add_indexing_worker()?;
-        }
+        self.flush_indexing_workers()?;
 
         let commit_opstamp = self.stamper.stamp();
         let prepared_commit = PreparedCommit::new(self, commit_opstamp);
@@ -658,6 +993,71 @@ impl IndexWriter {
         self.prepare_commit()?.commit()
     }
 
+    /// Commits all of the pending changes, and attaches an arbitrary `payload` to
+    /// the commit.
+    ///
+    /// The payload is persisted atomically with the commit, in the `meta.json` file.
+    /// It is typically used by ingestion pipelines to record their checkpoint
+    /// (e.g. `"kafka_offset=12345"`), and resume from it after a restart.
+    /// It can be read back with
+    /// [`IndexReader::commit_payload`](../struct.IndexReader.html#method.commit_payload),
+    /// or from [`Index::load_metas`](../struct.Index.html#method.load_metas).
+    ///
+    /// This is a shortcut for `prepare_commit` followed by
+    /// [`PreparedCommit::set_payload`](./struct.PreparedCommit.html#method.set_payload).
+    pub fn commit_with_payload(&mut self, payload: &str) -> crate::Result<Opstamp> {
+        let mut prepared_commit = self.prepare_commit()?;
+        prepared_commit.set_payload(payload);
+        prepared_commit.commit()
+    }
+
+    /// Makes all of the pending changes searchable, without persisting them.
+    ///
+    /// The indexing threads flush their segments, and the segments added since
+    /// the last commit are published in memory alongside the committed ones, with
+    /// the deletes applied. The `meta.json` file is not written: the soft committed
+    /// changes are lost in case of a crash, and discarded by a
+    /// [`rollback`](#method.rollback). A subsequent [`commit`](#method.commit)
+    /// persists them.
+    ///
+    /// This makes it possible to shorten the delay between the indexing of a document
+    /// and its visibility, and to commit durably less often.
+    ///
+    /// Soft commits are only visible through the `Index` this writer was created from,
+    /// or its clones. Readers are not notified: the new documents are visible after a call
+    /// to [`IndexReader::reload`](../struct.IndexReader.html#method.reload).
+    ///
+    /// Returns the opstamp of the soft commit.
+    pub fn soft_commit(&mut self) -> crate::Result<Opstamp> {
+        info!("Soft commit");
+        self.flush_indexing_workers()?;
+        let soft_commit_opstamp = self.stamper.stamp();
+        block_on(
+            self.segment_updater
+                .schedule_soft_commit(soft_commit_opstamp),
+        )?;
+        Ok(soft_commit_opstamp)
+    }
+
+    /// Waits for the indexing threads to flush their segments, and
+    /// spawns new ones in their place.
+    fn flush_indexing_workers(&mut self) -> crate::Result<()> {
+        // this will drop the current document channel
+        // and recreate a new one.
+        self.recreate_document_channel();
+
+        let former_workers_join_handle = std::mem::take(&mut self.workers_join_handle);
+
+        for worker_handle in former_workers_join_handle {
+            let indexing_worker_result = worker_handle
+                .join()
+                .map_err(|e| TantivyError::ErrorInThread(format!("{:?}", e)))?;
+            indexing_worker_result?;
+            self.add_indexing_worker()?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn segment_updater(&self) -> &SegmentUpdater {
         &self.segment_updater
     }
@@ -672,7 +1072,10 @@ impl IndexWriter {
     /// only after calling `commit()`.
     pub fn delete_term(&self, term: Term) -> Opstamp {
         let opstamp = self.stamper.stamp();
-        let delete_operation = DeleteOperation { opstamp, term };
+        let delete_operation = DeleteOperation {
+            opstamp,
+            target: DeleteTarget::Term(term),
+        };
         self.delete_queue.push(delete_operation);
         opstamp
     }
@@ -701,6 +1104,93 @@ impl IndexWriter {
         Ok(opstamp)
     }
 
+    /// Adds a batch of documents.
+    ///
+    /// All of the documents of the batch share the returned opstamp, and are
+    /// guaranteed to be added to the same segment, with contiguous `DocId`s
+    /// following the order of the `Vec` (unless the index is sorted, see `IndexSettings`).
+    /// They are therefore made visible to readers by the same `commit()`.
+    ///
+    /// This is required to index blocks of related documents (e.g. parent-child documents),
+    /// and is cheaper than adding the documents one by one.
+    ///
+    /// If the indexing pipeline is full, this call may block.
+    ///
+    /// # Errors
+    ///
+    /// As a segment cannot contain more than `2^31` documents, a batch cannot
+    /// contain more than `2^30` documents: larger batches are rejected with
+    /// `TantivyError::DocLimitExceeded`.
+    pub fn add_documents(&self, documents: Vec<Document>) -> crate::Result<Opstamp> {
+        if documents.len() as u64 > u64::from(MAX_DOCS_PER_BATCH) {
+            return Err(TantivyError::DocLimitExceeded(documents.len() as u64));
+        }
+        let opstamp = self.stamper.stamp();
+        if documents.is_empty() {
+            return Ok(opstamp);
+        }
+        let add_ops: AddBatch = documents
+            .into_iter()
+            .map(|document| AddOperation { opstamp, document })
+            .collect();
+        self.send_add_documents_batch(add_ops)?;
+        Ok(opstamp)
+    }
+
+    /// Adds the documents of a stream of newline-delimited JSON (NDJSON).
+    ///
+    /// Each non-empty line is parsed against the schema of the index,
+    /// as in `Schema::parse_document`. A line that cannot be parsed is
+    /// reported in the returned `IngestReport` and skipped, without
+    /// interrupting the ingestion of the following lines.
+    ///
+    /// Documents are added by batches of a few hundred, as with `add_documents`.
+    /// Only an I/O error on `reader` interrupts the ingestion. In that case, the
+    /// documents of the batches added before the error are not rolled back.
+    ///
+    /// The added documents are made visible to readers by the next `commit()`.
+    pub fn ingest_ndjson<R: BufRead>(&self, mut reader: R) -> crate::Result<IngestReport> {
+        let schema = self.index.schema();
+        let mut report = IngestReport {
+            num_docs_added: 0,
+            errors: Vec::new(),
+            opstamp: self.stamper.stamp(),
+        };
+        let mut batch: Vec<Document> = Vec::with_capacity(NDJSON_BATCH_SIZE);
+        let mut line: Vec<u8> = Vec::new();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_number += 1;
+            let parse_res = match std::str::from_utf8(&line) {
+                Ok(doc_json) if doc_json.trim().is_empty() => continue,
+                Ok(doc_json) => schema.parse_document(doc_json),
+                Err(_) => {
+                    let sample = String::from_utf8_lossy(&line).chars().take(20).collect();
+                    Err(DocParsingError::NotJson(sample))
+                }
+            };
+            match parse_res {
+                Ok(document) => {
+                    batch.push(document);
+                    if batch.len() == NDJSON_BATCH_SIZE {
+                        report.num_docs_added += batch.len();
+                        report.opstamp = self.add_documents(std::mem::take(&mut batch))?;
+                    }
+                }
+                Err(error) => report.errors.push(IngestLineError { line_number, error }),
+            }
+        }
+        if !batch.is_empty() {
+            report.num_docs_added += batch.len();
+            report.opstamp = self.add_documents(batch)?;
+        }
+        Ok(report)
+    }
+
     /// Gets a range of stamps from the stamper and "pops" the last stamp
     /// from the range returning a tuple of the last optstamp and the popped
     /// range.
@@ -746,7 +1236,10 @@ impl IndexWriter {
         for (user_op, opstamp) in user_operations_it.zip(stamps) {
             match user_op {
                 UserOperation::Delete(term) => {
-                    let delete_operation = DeleteOperation { opstamp, term };
+                    let delete_operation = DeleteOperation {
+                        opstamp,
+                        target: DeleteTarget::Term(term),
+                    };
                     self.delete_queue.push(delete_operation);
                 }
                 UserOperation::Add(document) => {
@@ -782,6 +1275,7 @@ impl Drop for IndexWriter {
 mod tests {
     use std::collections::HashMap;
     use std::collections::HashSet;
+    use std::path::PathBuf;
 
     use futures::executor::block_on;
     use proptest::prelude::*;
@@ -789,7 +1283,9 @@ mod tests {
     use proptest::strategy::Strategy;
 
     use super::super::operation::UserOperation;
-    use crate::collector::TopDocs;
+    use super::HEAP_SIZE_MIN;
+    use crate::collector::{Count, TopDocs};
+    use crate::core::{SegmentId, META_FILEPATH};
     use crate::directory::error::LockError;
     use crate::error::*;
     use crate::fastfield::FastFieldReader;
@@ -805,11 +1301,15 @@ mod tests {
     use crate::schema::STORED;
     use crate::schema::TEXT;
     use crate::schema::{self, IndexRecordOption, FAST, INDEXED, STRING};
+    use crate::Directory;
     use crate::DocAddress;
     use crate::Index;
     use crate::ReloadPolicy;
     use crate::Term;
+    use crate::{DocId, MergeListener, Searcher, SegmentDocIdMapping, SegmentOrdinal};
+    use crate::{FieldIndexingStatistics, IndexingObserver, SegmentIndexingStatistics};
     use crate::{IndexSettings, IndexSortByField, Order};
+    use std::sync::{Arc, Mutex};
 
     const LOREM: &str = "Doc Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed \
              do eiusmod tempor incididunt ut labore et dolore magna aliqua. \
@@ -835,6 +1335,208 @@ mod tests {
         assert_eq!(batch_opstamp1, 2u64);
     }
 
+    #[test]
+    fn test_add_documents() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(4, 12_000_000)?;
+        let opstamp_empty = index_writer.add_documents(Vec::new())?;
+        let opstamp_a = index_writer.add_document(doc!(text_field => "a"))?;
+        let batch_opstamp = index_writer.add_documents(vec![
+            doc!(text_field => "b"),
+            doc!(text_field => "c"),
+            doc!(text_field => "d"),
+        ])?;
+        assert_eq!(opstamp_empty, 0);
+        assert_eq!(opstamp_a, 1);
+        assert_eq!(batch_opstamp, 2);
+        index_writer.add_document(doc!(text_field => "e"))?;
+        index_writer.delete_term(Term::from_field_text(text_field, "c"));
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let batch_segment = searcher
+            .segment_readers()
+            .iter()
+            .position(|segment_reader| {
+                let store_reader = segment_reader.get_store_reader().unwrap();
+                let texts: Vec<String> = store_reader
+                    .iter(None)
+                    .map(|doc| {
+                        let doc = doc.unwrap();
+                        doc.get_first(text_field)
+                            .unwrap()
+                            .text()
+                            .unwrap()
+                            .to_string()
+                    })
+                    .collect();
+                texts.windows(3).any(|window| window == ["b", "c", "d"])
+            });
+        assert!(batch_segment.is_some());
+        assert_eq!(searcher.num_docs(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_writer_mem_usage() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(2, 12_000_000)?;
+        assert_eq!(index_writer.mem_usage_per_thread().len(), 2);
+        assert_eq!(index_writer.mem_usage(), 0);
+        for i in 0..1_000 {
+            index_writer.add_document(doc!(text_field => format!("hello{}", i)))?;
+        }
+        // the documents are indexed asynchronously by the indexing threads.
+        let mut mem_usage = index_writer.mem_usage();
+        for _ in 0..500 {
+            if mem_usage > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            mem_usage = index_writer.mem_usage();
+        }
+        assert!(mem_usage > 0);
+        let mem_usage_per_thread = index_writer.mem_usage_per_thread();
+        assert!(mem_usage_per_thread
+            .iter()
+            .all(|thread_mem_usage| thread_mem_usage.total() < 12_000_000));
+        let num_terms: u64 = mem_usage_per_thread
+            .iter()
+            .flat_map(|thread_mem_usage| thread_mem_usage.per_field_terms().get(&text_field))
+            .map(|field_terms_mem_usage| field_terms_mem_usage.num_terms())
+            .sum();
+        assert!(num_terms > 0);
+        index_writer.commit()?;
+        assert_eq!(index_writer.mem_usage(), 0);
+        // the peak memory usage outlives the flush of the segments.
+        let peak_mem_usage_per_thread = index_writer.peak_mem_usage_per_thread();
+        assert_eq!(peak_mem_usage_per_thread.len(), 2);
+        assert!(peak_mem_usage_per_thread
+            .iter()
+            .all(|&thread_peak_mem_usage| thread_peak_mem_usage < 12_000_000));
+        assert!(peak_mem_usage_per_thread.iter().sum::<usize>() > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_segments_flushed_in_background() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mem_budget = 2 * HEAP_SIZE_MIN;
+        let mut index_writer = index.writer_with_num_threads(1, mem_budget)?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.set_background_flush(true);
+        for i in 0..30_000u32 {
+            index_writer.add_document(doc!(text_field => format!("hello term{}", i)))?;
+        }
+        // The deletes apply to the segments flushing in the background too.
+        for i in (0..30_000u32).step_by(10) {
+            index_writer.delete_term(Term::from_field_text(text_field, &format!("term{}", i)));
+        }
+        index_writer.commit()?;
+        // The segment being filled and the segment being flushed share the budget.
+        assert!(index_writer.peak_mem_usage_per_thread()[0] < mem_budget);
+        assert_eq!(index_writer.mem_usage(), 0);
+        let searcher = index.reader()?.searcher();
+        assert!(searcher.segment_readers().len() > 1);
+        assert_eq!(searcher.num_docs(), 27_000);
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "hello"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&term_query, &Count)?, 27_000);
+        index_writer.add_document(doc!(text_field => "hello"))?;
+        index_writer.commit()?;
+        assert_eq!(index.reader()?.searcher().num_docs(), 27_001);
+        Ok(())
+    }
+
+    #[test]
+    fn test_background_flush_splits_the_budget() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let schema = schema_builder.build();
+        let num_segments = |background_flush: bool| -> crate::Result<usize> {
+            let index = Index::create_in_ram(schema.clone());
+            let mut index_writer = index.writer_with_num_threads(1, 2 * HEAP_SIZE_MIN)?;
+            index_writer.set_merge_policy(Box::new(NoMergePolicy));
+            index_writer.set_background_flush(background_flush);
+            assert_eq!(index_writer.background_flush(), background_flush);
+            for i in 0..30_000u32 {
+                index_writer.add_document(doc!(text_field => format!("hello term{}", i)))?;
+            }
+            index_writer.commit()?;
+            Ok(index.searchable_segment_ids()?.len())
+        };
+        assert!(num_segments(false)? < num_segments(true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ingest_ndjson() -> crate::Result<()> {
+        use crate::collector::Count;
+        use crate::schema::DocParsingError;
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING | STORED);
+        schema_builder.add_u64_field("count", schema::FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let mut ndjson: Vec<u8> = Vec::new();
+        for i in 0..600 {
+            ndjson.extend_from_slice(
+                format!("{{\"text\": \"doc{}\", \"count\": {}}}\n", i, i).as_bytes(),
+            );
+            if i == 10 {
+                ndjson.extend_from_slice(b"{\"text\": \n");
+                ndjson.extend_from_slice(b"\n");
+                ndjson.extend_from_slice(b"{\"count\": \"not a number\"}\r\n");
+                ndjson.extend_from_slice(b"{\"text\": \"\xff\"}\n");
+            }
+        }
+        ndjson.extend_from_slice(b"{\"text\": \"last\"}");
+        let report = index_writer.ingest_ndjson(&ndjson[..])?;
+        assert_eq!(report.num_docs_added, 601);
+        let error_line_numbers: Vec<usize> = report
+            .errors
+            .iter()
+            .map(|line_error| line_error.line_number)
+            .collect();
+        assert_eq!(&error_line_numbers, &[12, 14, 15]);
+        assert!(matches!(
+            report.errors[0].error,
+            DocParsingError::NotJson(_)
+        ));
+        assert!(matches!(
+            report.errors[1].error,
+            DocParsingError::ValueError(_, _)
+        ));
+        assert!(matches!(
+            report.errors[2].error,
+            DocParsingError::NotJson(_)
+        ));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 601);
+        let term_query = |text: &str| {
+            TermQuery::new(
+                Term::from_field_text(text_field, text),
+                IndexRecordOption::Basic,
+            )
+        };
+        assert_eq!(searcher.search(&term_query("doc599"), &Count)?, 1);
+        assert_eq!(searcher.search(&term_query("last"), &Count)?, 1);
+
+        let report = index_writer.ingest_ndjson(&b""[..])?;
+        assert_eq!(report.num_docs_added, 0);
+        assert!(report.errors.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_no_need_to_rewrite_delete_file_if_no_new_deletes() {
         let mut schema_builder = schema::Schema::builder();
@@ -984,6 +1686,142 @@ mod tests {
         );
     }
 
+    #[derive(Default)]
+    struct RecordingMergeListener {
+        merges: Arc<Mutex<Vec<(Vec<SegmentId>, SegmentId, Vec<(DocId, SegmentOrdinal)>)>>>,
+    }
+
+    impl MergeListener for RecordingMergeListener {
+        fn on_merge(
+            &self,
+            source_segment_ids: &[SegmentId],
+            target_segment_id: SegmentId,
+            doc_id_mapping: &SegmentDocIdMapping,
+        ) -> crate::Result<()> {
+            self.merges.lock().unwrap().push((
+                source_segment_ids.to_vec(),
+                target_segment_id,
+                doc_id_mapping.iter().cloned().collect(),
+            ));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingIndexingObserver {
+        flushed_segments: Arc<Mutex<Vec<SegmentIndexingStatistics>>>,
+    }
+
+    impl IndexingObserver for RecordingIndexingObserver {
+        fn on_segment_flushed(&self, statistics: &SegmentIndexingStatistics) {
+            self.flushed_segments
+                .lock()
+                .unwrap()
+                .push(statistics.clone());
+        }
+    }
+
+    #[test]
+    fn test_indexing_observer() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let id_field = schema_builder.add_u64_field("id", INDEXED);
+        let stored_field = schema_builder.add_text_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 12_000_000)?;
+        let indexing_observer = RecordingIndexingObserver::default();
+        let flushed_segments = indexing_observer.flushed_segments.clone();
+        index_writer.set_indexing_observer(Box::new(indexing_observer));
+        index_writer.add_document(doc!(text_field => "hello happy tax payer", id_field => 1u64))?;
+        index_writer.add_document(doc!(text_field => "hello world", id_field => 2u64))?;
+        index_writer.add_document(doc!(stored_field => "not indexed"))?;
+        index_writer.commit()?;
+
+        let flushed_segments = flushed_segments.lock().unwrap();
+        assert_eq!(flushed_segments.len(), 1);
+        let statistics = &flushed_segments[0];
+        assert_eq!(statistics.segment_id, index.searchable_segment_ids()?[0]);
+        assert_eq!(statistics.num_docs, 3);
+        assert_eq!(
+            statistics.fields,
+            vec![
+                FieldIndexingStatistics {
+                    field: text_field,
+                    num_tokens: 6,
+                    num_terms: 5,
+                },
+                FieldIndexingStatistics {
+                    field: id_field,
+                    num_tokens: 2,
+                    num_terms: 2,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_listener() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        let merge_listener = RecordingMergeListener::default();
+        let merges = merge_listener.merges.clone();
+        index_writer.set_merge_listener(Box::new(merge_listener));
+        index_writer.add_document(doc!(text_field => "a"))?;
+        index_writer.add_document(doc!(text_field => "b"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text_field => "c"))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.commit()?;
+
+        let reader = index.reader()?;
+        let text_of = |searcher: &Searcher, doc_address: DocAddress| -> crate::Result<String> {
+            let doc = searcher.doc(doc_address)?;
+            Ok(doc
+                .get_first(text_field)
+                .unwrap()
+                .text()
+                .unwrap()
+                .to_string())
+        };
+        let searcher_before_merge = reader.searcher();
+        let segment_ids = index.searchable_segment_ids()?;
+        let segment_meta = block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(searcher.segment_reader(0).segment_id(), segment_meta.id());
+
+        let merges = merges.lock().unwrap();
+        assert_eq!(merges.len(), 1);
+        let (source_segment_ids, target_segment_id, doc_id_mapping) = &merges[0];
+        assert_eq!(source_segment_ids, &segment_ids);
+        assert_eq!(*target_segment_id, segment_meta.id());
+        // "a" was deleted and is dropped from the mapping.
+        assert_eq!(doc_id_mapping.len(), 2);
+        for (new_doc_id, &(old_doc_id, segment_ord)) in doc_id_mapping.iter().enumerate() {
+            let old_segment_id = source_segment_ids[segment_ord as usize];
+            let old_segment_ord = searcher_before_merge
+                .segment_readers()
+                .iter()
+                .position(|segment_reader| segment_reader.segment_id() == old_segment_id)
+                .unwrap();
+            assert_eq!(
+                text_of(&searcher, DocAddress::new(0, new_doc_id as DocId))?,
+                text_of(
+                    &searcher_before_merge,
+                    DocAddress::new(old_segment_ord as SegmentOrdinal, old_doc_id)
+                )?
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_lockfile_released_on_drop() {
         let schema_builder = schema::Schema::builder();
@@ -1136,6 +1974,111 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rollback_garbage_collects_uncommitted_segments() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "a"))?;
+        index_writer.commit()?;
+        let committed_segment_ids: HashSet<SegmentId> = index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| segment_meta.id())
+            .collect();
+        index_writer.add_document(doc!(text_field => "b"))?;
+        let mut prepared_commit = index_writer.prepare_commit()?;
+        let uncommitted_files: Vec<PathBuf> = index
+            .list_all_segment_metas()
+            .into_iter()
+            .filter(|segment_meta| !committed_segment_ids.contains(&segment_meta.id()))
+            .flat_map(|segment_meta| segment_meta.list_files())
+            .filter(|path| index.directory().exists(path).unwrap())
+            .collect();
+        assert!(!uncommitted_files.is_empty());
+        prepared_commit.abort()?;
+        for path in &uncommitted_files {
+            assert!(!index.directory().exists(path)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback_garbage_collects_segments_being_written() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(4, 12_000_000)?;
+        index_writer.add_document(doc!(text_field => "a"))?;
+        index_writer.commit()?;
+        let committed_files: HashSet<PathBuf> = index
+            .searchable_segment_metas()?
+            .iter()
+            .flat_map(|segment_meta| segment_meta.list_files())
+            .collect();
+        for _ in 0..10_000 {
+            index_writer.add_document(doc!(text_field => "b"))?;
+        }
+        index_writer.rollback()?;
+        for path in index.directory().list_managed_files() {
+            assert!(
+                committed_files.contains(&path) || path == *META_FILEPATH,
+                "{:?} was not garbage collected",
+                path
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_soft_commit() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let num_docs_containing = |s: &str| -> crate::Result<u64> {
+            reader.reload()?;
+            let searcher = reader.searcher();
+            let term_query = TermQuery::new(
+                Term::from_field_text(text_field, s),
+                IndexRecordOption::Basic,
+            );
+            Ok(searcher.search(&term_query, &Count)? as u64)
+        };
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "a"))?;
+        index_writer.add_document(doc!(text_field => "a"))?;
+        let commit_opstamp = index_writer.commit()?;
+
+        index_writer.add_document(doc!(text_field => "b"))?;
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        let soft_commit_opstamp = index_writer.soft_commit()?;
+        assert!(soft_commit_opstamp > commit_opstamp);
+        assert_eq!(num_docs_containing("a")?, 0);
+        assert_eq!(num_docs_containing("b")?, 1);
+        // Nothing was persisted.
+        assert_eq!(index.load_metas()?.opstamp, commit_opstamp);
+        assert_eq!(index.load_metas()?.segments[0].num_deleted_docs(), 0);
+
+        index_writer.rollback()?;
+        assert_eq!(num_docs_containing("a")?, 2);
+        assert_eq!(num_docs_containing("b")?, 0);
+
+        index_writer.add_document(doc!(text_field => "b"))?;
+        index_writer.soft_commit()?;
+        assert_eq!(num_docs_containing("b")?, 1);
+        index_writer.add_document(doc!(text_field => "b"))?;
+        let commit_opstamp = index_writer.commit()?;
+        assert_eq!(index.load_metas()?.opstamp, commit_opstamp);
+        assert_eq!(num_docs_containing("a")?, 2);
+        assert_eq!(num_docs_containing("b")?, 2);
+        Ok(())
+    }
+
     #[test]
     fn test_add_then_delete_all_documents() {
         let mut schema_builder = schema::Schema::builder();
@@ -1181,7 +2124,7 @@ mod tests {
 
         // delete_all_documents the index
         let clear_tstamp = index_writer.delete_all_documents().unwrap();
-        assert_eq!(clear_tstamp, add_tstamp);
+        assert!(clear_tstamp > first_commit_tstamp);
 
         // commit the clear command - now documents aren't available
         let second_commit = index_writer.commit();
@@ -1255,8 +2198,8 @@ mod tests {
 
         // clear but don't commit!
         let clear_tstamp = index_writer.delete_all_documents().unwrap();
-        // clear_tstamp should reset to before the last commit
-        assert!(clear_tstamp < commit_tstamp);
+        // the clear operation is stamped after the last commit
+        assert!(clear_tstamp > commit_tstamp);
 
         // rollback
         let _rollback_tstamp = index_writer.rollback().unwrap();
@@ -1274,6 +2217,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_delete_all_documents_deletes_uncommitted_documents() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(2, 12_000_000)?;
+        index_writer.add_document(doc!(text_field => "a"))?;
+        index_writer.commit()?;
+        for _ in 0..100 {
+            index_writer.add_document(doc!(text_field => "b"))?;
+        }
+        index_writer.delete_all_documents()?;
+        for _ in 0..10 {
+            index_writer.add_document(doc!(text_field => "c"))?;
+        }
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 10);
+        assert_eq!(
+            searcher.doc_freq(&Term::from_field_text(text_field, "a"))?,
+            0
+        );
+        assert_eq!(
+            searcher.doc_freq(&Term::from_field_text(text_field, "c"))?,
+            10
+        );
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "b"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&term_query, &Count)?, 0);
+        Ok(())
+    }
+
     #[test]
     fn test_delete_all_documents_empty_index() {
         let schema_builder = schema::Schema::builder();
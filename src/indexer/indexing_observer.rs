@@ -0,0 +1,43 @@
+use crate::schema::Field;
+use crate::{DocId, SegmentId};
+use std::time::Duration;
+
+/// An `IndexingObserver` is notified of the statistics of every segment
+/// flushed by the indexing threads of an `IndexWriter`.
+///
+/// It makes it possible to monitor the quality of the ingested data,
+/// e.g. to detect a field that suddenly stops receiving tokens.
+///
+/// See [`IndexWriter::set_indexing_observer`](../struct.IndexWriter.html#method.set_indexing_observer).
+pub trait IndexingObserver: Send + Sync + 'static {
+    /// Called on the flushing thread, after the segment has been serialized
+    /// and before it is handed to the segment updater.
+    fn on_segment_flushed(&self, statistics: &SegmentIndexingStatistics);
+}
+
+/// Statistics of a segment flushed by an indexing thread.
+#[derive(Debug, Clone)]
+pub struct SegmentIndexingStatistics {
+    /// Id of the flushed segment.
+    pub segment_id: SegmentId,
+    /// Number of documents of the segment.
+    pub num_docs: DocId,
+    /// Statistics of each field having at least one term in the segment.
+    pub fields: Vec<FieldIndexingStatistics>,
+    /// Time spent adding the documents to the segment writer, which is
+    /// dominated by the tokenization of the text fields.
+    pub tokenizing_time: Duration,
+    /// Time spent serializing the segment.
+    pub serializing_time: Duration,
+}
+
+/// Statistics of an indexed field within a flushed segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldIndexingStatistics {
+    /// The field.
+    pub field: Field,
+    /// Number of tokens indexed in the field.
+    pub num_tokens: u64,
+    /// Number of distinct terms of the field.
+    pub num_terms: u64,
+}
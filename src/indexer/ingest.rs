@@ -0,0 +1,24 @@
+use crate::schema::DocParsingError;
+use crate::Opstamp;
+
+/// Error on one of the lines given to
+/// [`IndexWriter::ingest_ndjson`](../struct.IndexWriter.html#method.ingest_ndjson).
+#[derive(Debug, PartialEq)]
+pub struct IngestLineError {
+    /// Line number, starting at 1.
+    pub line_number: usize,
+    /// Reason why the line could not be parsed as a document.
+    pub error: DocParsingError,
+}
+
+/// Outcome of an
+/// [`IndexWriter::ingest_ndjson`](../struct.IndexWriter.html#method.ingest_ndjson) call.
+#[derive(Debug)]
+pub struct IngestReport {
+    /// Number of documents added to the index writer.
+    pub num_docs_added: usize,
+    /// Lines that were skipped because they could not be parsed, in order.
+    pub errors: Vec<IngestLineError>,
+    /// Opstamp of the last batch of documents added.
+    pub opstamp: Opstamp,
+}
@@ -0,0 +1,500 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{FieldBounds, Index, SegmentMeta};
+use crate::directory::OwnedBytes;
+use crate::fastfield::FastFieldsWriter;
+use crate::fieldnorm::{FieldNormReader, FieldNormsSerializer};
+use crate::indexer::merger::MAX_DOC_LIMIT;
+use crate::indexer::SegmentSerializer;
+use crate::postings::FieldSerializer;
+use crate::schema::{Document, Field, Schema, Term};
+use crate::{DocId, TantivyError};
+
+/// Builds a segment directly from its postings, without going through `Document`s.
+///
+/// This is meant for external systems (e.g. a batch job aggregating the postings of a
+/// corpus) producing segments of an index. The postings of each indexed field are written
+/// through a [`FieldPostingsWriter`](./struct.FieldPostingsWriter.html):
+/// `new_term` → `add_doc` → ... → `finish`. Once all of the fields are written, `finish`
+/// returns the meta of the segment, to be added to the index with
+/// [`IndexWriter::add_segment`](./struct.IndexWriter.html#method.add_segment) and committed.
+///
+/// The segment only holds postings: its documents are not stored, and the values of its
+/// fast fields are the default values. The fields without any postings do not have any term.
+///
+/// The index must not sort its segments, group them by tenant or record static ranks.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::TermQuery;
+/// use tantivy::schema::{IndexRecordOption, Schema, TEXT};
+/// use tantivy::{Index, InvertedIndexWriter, Term};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+///
+/// // Two documents: "hello world" and "hello hello".
+/// let mut inverted_index_writer = InvertedIndexWriter::new(&index, 2)?;
+/// let mut field_writer = inverted_index_writer.field(body, &[2, 2])?;
+/// field_writer.new_term(&Term::from_field_text(body, "hello"), 2)?;
+/// field_writer.add_doc(0, 1, &[0])?;
+/// field_writer.add_doc(1, 2, &[0, 1])?;
+/// field_writer.new_term(&Term::from_field_text(body, "world"), 1)?;
+/// field_writer.add_doc(0, 1, &[1])?;
+/// field_writer.finish()?;
+/// let segment_meta = inverted_index_writer.finish()?;
+///
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_segment(segment_meta)?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = TermQuery::new(Term::from_field_text(body, "hello"), IndexRecordOption::Basic);
+/// assert_eq!(searcher.search(&query, &Count)?, 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct InvertedIndexWriter {
+    segment_serializer: SegmentSerializer,
+    fieldnorms_serializer: FieldNormsSerializer,
+    schema: Schema,
+    max_doc: DocId,
+    written_fields: HashSet<Field>,
+}
+
+impl InvertedIndexWriter {
+    /// Creates a writer of a new segment of `index`, holding `max_doc` documents.
+    pub fn new(index: &Index, max_doc: DocId) -> crate::Result<InvertedIndexWriter> {
+        let settings = index.settings();
+        if settings.sort_by_field.is_some()
+            || settings.tenant_field.is_some()
+            || settings.static_rank_field.is_some()
+        {
+            return Err(TantivyError::InvalidArgument(
+                "Segments cannot be built from postings for an index sorting its segments, \
+                 grouping them by tenant or recording static ranks."
+                    .to_string(),
+            ));
+        }
+        if max_doc == 0 || max_doc >= MAX_DOC_LIMIT {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The number of documents of the segment must be within [1, {}), got {}.",
+                MAX_DOC_LIMIT, max_doc
+            )));
+        }
+        let mut segment_serializer = SegmentSerializer::for_segment(index.new_segment(), true)?;
+        let fieldnorms_serializer = segment_serializer
+            .extract_fieldnorms_serializer()
+            .expect("The fieldnorms serializer of a new segment serializer should be available");
+        Ok(InvertedIndexWriter {
+            segment_serializer,
+            fieldnorms_serializer,
+            schema: index.schema(),
+            max_doc,
+            written_fields: HashSet::new(),
+        })
+    }
+
+    /// Starts writing the postings of the indexed `field`.
+    ///
+    /// `field_lengths` holds the number of tokens of the field in each document, 0 for the
+    /// documents without the field. It is used for the fieldnorms and for the average length
+    /// of the field in BM25.
+    ///
+    /// Each field can only be written once, and the returned writer must be finished
+    /// before writing another field.
+    pub fn field(
+        &mut self,
+        field: Field,
+        field_lengths: &[u32],
+    ) -> crate::Result<FieldPostingsWriter<'_>> {
+        let field_entry = self.schema.get_field_entry(field);
+        let index_record_option = field_entry
+            .field_type()
+            .get_index_record_option()
+            .ok_or_else(|| {
+                TantivyError::SchemaError(format!("Field {:?} is not indexed.", field_entry.name()))
+            })?;
+        if field_entry.field_type().has_payloads() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} records payloads, which cannot be written from postings.",
+                field_entry.name()
+            )));
+        }
+        if field_lengths.len() != self.max_doc as usize {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Expected the lengths of field {:?} in {} documents, got {}.",
+                field_entry.name(),
+                self.max_doc,
+                field_lengths.len()
+            )));
+        }
+        if !self.written_fields.insert(field) {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The postings of field {:?} were already written.",
+                field_entry.name()
+            )));
+        }
+        let fieldnorm_reader = if field_entry.has_fieldnorms() {
+            let fieldnorm_ids: Vec<u8> = field_lengths
+                .iter()
+                .map(|&field_length| FieldNormReader::fieldnorm_to_id(field_length))
+                .collect();
+            self.fieldnorms_serializer
+                .serialize_field(field, &fieldnorm_ids)?;
+            Some(FieldNormReader::new(OwnedBytes::new(fieldnorm_ids)))
+        } else {
+            None
+        };
+        let total_num_tokens: u64 = field_lengths
+            .iter()
+            .map(|&field_length| u64::from(field_length))
+            .sum();
+        let field_serializer = self
+            .segment_serializer
+            .get_postings_serializer()
+            .new_field(field, total_num_tokens, fieldnorm_reader)?;
+        Ok(FieldPostingsWriter {
+            field,
+            max_doc: self.max_doc,
+            field_serializer,
+            has_positions: index_record_option.has_positions(),
+            previous_term: None,
+            term_doc_freq: 0,
+            num_term_docs: 0,
+            previous_doc: None,
+            position_deltas: Vec::new(),
+        })
+    }
+
+    /// Writes the rest of the segment, and returns its meta.
+    pub fn finish(mut self) -> crate::Result<SegmentMeta> {
+        // The fields with fieldnorms but without postings have
+        // the fieldnorms of documents without the field.
+        let missing_fieldnorms = vec![0u8; self.max_doc as usize];
+        for (field, field_entry) in self.schema.fields() {
+            if field_entry.is_indexed()
+                && field_entry.has_fieldnorms()
+                && !self.written_fields.contains(&field)
+            {
+                self.fieldnorms_serializer
+                    .serialize_field(field, &missing_fieldnorms)?;
+            }
+        }
+        self.fieldnorms_serializer.close()?;
+
+        let empty_doc = Document::default();
+        let mut fast_field_writers = FastFieldsWriter::from_schema(&self.schema);
+        for _ in 0..self.max_doc {
+            fast_field_writers.add_document(&empty_doc);
+            self.segment_serializer
+                .get_store_writer()
+                .store(&empty_doc)?;
+        }
+        fast_field_writers.serialize(
+            self.segment_serializer.get_fast_field_serializer(),
+            &HashMap::new(),
+            None,
+        )?;
+        let segment = self.segment_serializer.segment().clone();
+        self.segment_serializer.close()?;
+        let segment = segment
+            .with_max_doc(self.max_doc)
+            .with_computed_field_bounds()?;
+        // The value bounds are computed from the fast fields, which only hold
+        // default values here, and would rule out the indexed values.
+        let field_bounds = segment
+            .meta()
+            .field_bounds()
+            .iter()
+            .filter(|(_, bounds)| bounds.terms.is_some())
+            .map(|(field_name, bounds)| {
+                let bounds = FieldBounds {
+                    terms: bounds.terms.clone(),
+                    values: None,
+                };
+                (field_name.clone(), bounds)
+            })
+            .collect();
+        let segment_meta = segment.meta().clone().with_field_bounds(field_bounds);
+        segment_meta.untrack_temp_docstore();
+        Ok(segment_meta)
+    }
+}
+
+/// Writes the postings of a field of a segment built by an
+/// [`InvertedIndexWriter`](./struct.InvertedIndexWriter.html).
+///
+/// The terms must be written in increasing order of their value bytes, and the
+/// documents of each term in increasing order of doc id.
+pub struct FieldPostingsWriter<'a> {
+    field: Field,
+    max_doc: DocId,
+    field_serializer: FieldSerializer<'a>,
+    has_positions: bool,
+    previous_term: Option<Vec<u8>>,
+    term_doc_freq: u32,
+    num_term_docs: u32,
+    previous_doc: Option<DocId>,
+    position_deltas: Vec<u32>,
+}
+
+impl<'a> FieldPostingsWriter<'a> {
+    /// Starts the postings of `term`, contained in `doc_freq` documents.
+    pub fn new_term(&mut self, term: &Term, doc_freq: u32) -> crate::Result<()> {
+        if term.field() != self.field {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The term {:?} does not belong to the field {:?}.",
+                term, self.field
+            )));
+        }
+        if doc_freq == 0 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The term {:?} must be contained in at least one document.",
+                term
+            )));
+        }
+        let term_bytes = term.value_bytes();
+        if let Some(previous_term) = self.previous_term.as_ref() {
+            if previous_term.as_slice() >= term_bytes {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "The terms must be written in increasing order, got {:?} after {:?}.",
+                    term_bytes, previous_term
+                )));
+            }
+        }
+        self.close_term()?;
+        self.field_serializer.new_term(term_bytes, doc_freq)?;
+        self.previous_term = Some(term_bytes.to_vec());
+        self.term_doc_freq = doc_freq;
+        self.num_term_docs = 0;
+        self.previous_doc = None;
+        Ok(())
+    }
+
+    /// Adds the document `doc` to the postings of the current term, which it contains
+    /// `term_freq` times, at the given `positions`.
+    ///
+    /// For the fields recording positions, there must be `term_freq` positions, in
+    /// increasing order. They are ignored for the other fields.
+    pub fn add_doc(&mut self, doc: DocId, term_freq: u32, positions: &[u32]) -> crate::Result<()> {
+        if self.previous_term.is_none() {
+            return Err(TantivyError::InvalidArgument(
+                "A term must be started before adding documents.".to_string(),
+            ));
+        }
+        if doc >= self.max_doc
+            || self.previous_doc.map(|previous_doc| doc <= previous_doc) == Some(true)
+        {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The documents must be added in increasing order, and below {}, got {}.",
+                self.max_doc, doc
+            )));
+        }
+        if self.num_term_docs == self.term_doc_freq {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The term was declared to be contained in {} documents.",
+                self.term_doc_freq
+            )));
+        }
+        if term_freq == 0 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The term frequency in document {} must be at least 1.",
+                doc
+            )));
+        }
+        self.position_deltas.clear();
+        if self.has_positions {
+            if positions.len() != term_freq as usize {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "Expected {} positions in document {}, got {}.",
+                    term_freq,
+                    doc,
+                    positions.len()
+                )));
+            }
+            let mut previous_position = 0u32;
+            for (ord, &position) in positions.iter().enumerate() {
+                if ord > 0 && position <= previous_position {
+                    return Err(TantivyError::InvalidArgument(format!(
+                        "The positions in document {} must be increasing.",
+                        doc
+                    )));
+                }
+                self.position_deltas.push(position - previous_position);
+                previous_position = position;
+            }
+        }
+        self.field_serializer
+            .write_doc(doc, term_freq, &self.position_deltas);
+        self.num_term_docs += 1;
+        self.previous_doc = Some(doc);
+        Ok(())
+    }
+
+    fn close_term(&mut self) -> crate::Result<()> {
+        if self.num_term_docs != self.term_doc_freq {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The term was declared to be contained in {} documents, got {}.",
+                self.term_doc_freq, self.num_term_docs
+            )));
+        }
+        self.field_serializer.close_term()?;
+        Ok(())
+    }
+
+    /// Finishes the postings of the field.
+    pub fn finish(mut self) -> crate::Result<()> {
+        self.close_term()?;
+        self.field_serializer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvertedIndexWriter;
+    use crate::collector::{Count, TopDocs};
+    use crate::fastfield::FastFieldReader;
+    use crate::query::{PhraseQuery, QueryParser, RangeQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, INDEXED, STRING, TEXT};
+    use crate::{Index, IndexSettings, IndexSortByField, Order, TantivyError, Term};
+
+    #[test]
+    fn test_inverted_index_writer() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let year = schema_builder.add_u64_field("year", INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+
+        // The same documents, indexed the regular way.
+        let regular_index = Index::create_in_ram(index.schema());
+        let mut regular_writer = regular_index.writer_for_tests()?;
+        regular_writer.add_document(doc!(body => "a b a", tag => "x", year => 2020u64))?;
+        regular_writer.add_document(doc!(tag => "y"))?;
+        regular_writer.add_document(doc!(body => "b c", year => 2021u64))?;
+        regular_writer.commit()?;
+
+        let mut inverted_index_writer = InvertedIndexWriter::new(&index, 3)?;
+        let mut field_writer = inverted_index_writer.field(body, &[3, 0, 2])?;
+        field_writer.new_term(&Term::from_field_text(body, "a"), 1)?;
+        field_writer.add_doc(0, 2, &[0, 2])?;
+        field_writer.new_term(&Term::from_field_text(body, "b"), 2)?;
+        field_writer.add_doc(0, 1, &[1])?;
+        field_writer.add_doc(2, 1, &[0])?;
+        field_writer.new_term(&Term::from_field_text(body, "c"), 1)?;
+        field_writer.add_doc(2, 1, &[1])?;
+        field_writer.finish()?;
+        let mut field_writer = inverted_index_writer.field(year, &[1, 0, 1])?;
+        field_writer.new_term(&Term::from_field_u64(year, 2020), 1)?;
+        field_writer.add_doc(0, 1, &[])?;
+        field_writer.new_term(&Term::from_field_u64(year, 2021), 1)?;
+        field_writer.add_doc(2, 1, &[])?;
+        field_writer.finish()?;
+        let mut field_writer = inverted_index_writer.field(tag, &[1, 1, 0])?;
+        field_writer.new_term(&Term::from_field_text(tag, "x"), 1)?;
+        field_writer.add_doc(0, 1, &[])?;
+        field_writer.new_term(&Term::from_field_text(tag, "y"), 1)?;
+        field_writer.add_doc(1, 1, &[])?;
+        field_writer.finish()?;
+        let segment_meta = inverted_index_writer.finish()?;
+        assert_eq!(segment_meta.max_doc(), 3);
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_segment(segment_meta)?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let regular_searcher = regular_index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 3);
+        assert_eq!(
+            searcher.total_num_tokens(body)?,
+            regular_searcher.total_num_tokens(body)?
+        );
+
+        // The scores are the ones of the regular index.
+        let query_parser = QueryParser::for_index(&index, vec![body, tag]);
+        for query_str in &["a", "b", "a OR c", "\"b c\"", "tag:y", "d"] {
+            let query = query_parser.parse_query(query_str)?;
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            let regular_top_docs = regular_searcher.search(&query, &TopDocs::with_limit(10))?;
+            assert_eq!(top_docs, regular_top_docs, "{}", query_str);
+        }
+        let phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(body, "a"),
+            Term::from_field_text(body, "b"),
+        ]);
+        assert_eq!(searcher.search(&phrase_query, &Count)?, 1);
+        let year_query = TermQuery::new(Term::from_field_u64(year, 2021), IndexRecordOption::Basic);
+        assert_eq!(searcher.search(&year_query, &Count)?, 1);
+
+        // The fast fields hold the default values.
+        let year_reader = searcher.segment_reader(0).fast_fields().u64(year)?;
+        assert_eq!(year_reader.get(2), 0);
+        let year_range_query = RangeQuery::new_u64(year, 2021..2022);
+        assert_eq!(searcher.search(&year_range_query, &Count)?, 1);
+
+        // The segment can be merged with a regular segment.
+        index_writer.add_document(doc!(body => "a", year => 2022u64))?;
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        futures::executor::block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(Term::from_field_text(body, "a"), IndexRecordOption::Basic);
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(searcher.search(&query, &Count)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inverted_index_writer_invalid_postings() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let year = schema_builder.add_u64_field("year", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let is_invalid =
+            |result: crate::Result<()>| matches!(result, Err(TantivyError::InvalidArgument(_)));
+
+        let mut inverted_index_writer = InvertedIndexWriter::new(&index, 2)?;
+        assert!(matches!(
+            inverted_index_writer.field(year, &[0, 0]),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert!(inverted_index_writer.field(body, &[1]).is_err());
+        let mut field_writer = inverted_index_writer.field(body, &[1, 1])?;
+        assert!(is_invalid(field_writer.add_doc(0, 1, &[0])));
+        assert!(is_invalid(
+            field_writer.new_term(&Term::from_field_u64(year, 1), 1)
+        ));
+        field_writer.new_term(&Term::from_field_text(body, "b"), 1)?;
+        assert!(is_invalid(field_writer.add_doc(2, 1, &[0])));
+        assert!(is_invalid(field_writer.add_doc(0, 2, &[0])));
+        assert!(is_invalid(field_writer.add_doc(0, 2, &[1, 1])));
+        field_writer.add_doc(1, 1, &[0])?;
+        assert!(is_invalid(field_writer.add_doc(1, 1, &[0])));
+        assert!(is_invalid(
+            field_writer.new_term(&Term::from_field_text(body, "a"), 1)
+        ));
+        field_writer.new_term(&Term::from_field_text(body, "c"), 2)?;
+        field_writer.add_doc(0, 1, &[0])?;
+        assert!(is_invalid(field_writer.finish()));
+        assert!(inverted_index_writer.field(body, &[1, 1]).is_err());
+
+        let sorted_index = Index::builder()
+            .schema(index.schema())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "year".to_string(),
+                    order: Order::Asc,
+                }),
+                ..IndexSettings::default()
+            })
+            .create_in_ram()?;
+        assert!(InvertedIndexWriter::new(&sorted_index, 2).is_err());
+        assert!(InvertedIndexWriter::new(&index, 0).is_err());
+        Ok(())
+    }
+}
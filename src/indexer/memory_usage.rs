@@ -0,0 +1,154 @@
+use crate::schema::Field;
+use crate::space_usage::ByteCount;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Memory used by the terms of a field in the term hashmap of a `SegmentWriter`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldTermsMemoryUsage {
+    num_terms: u64,
+    term_bytes: ByteCount,
+}
+
+impl FieldTermsMemoryUsage {
+    pub(crate) fn new(num_terms: u64, term_bytes: ByteCount) -> FieldTermsMemoryUsage {
+        FieldTermsMemoryUsage {
+            num_terms,
+            term_bytes,
+        }
+    }
+
+    /// Number of distinct terms of the field.
+    pub fn num_terms(&self) -> u64 {
+        self.num_terms
+    }
+
+    /// Bytes used in the term arena by the terms of the field
+    /// and the head of their posting list.
+    pub fn term_bytes(&self) -> ByteCount {
+        self.term_bytes
+    }
+}
+
+/// Memory used by the segment writer of an indexing thread,
+/// broken down by component.
+///
+/// See [`IndexWriter::mem_usage_per_thread`](./struct.IndexWriter.html#method.mem_usage_per_thread).
+///
+/// The memory arenas are allocated by pages of 1MB: their usage is an upper bound
+/// of the bytes actually written in them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SegmentWriterMemoryUsage {
+    postings_arena: ByteCount,
+    term_hashmap_table: ByteCount,
+    term_arena: ByteCount,
+    per_field_terms: HashMap<Field, FieldTermsMemoryUsage>,
+    fieldnorms: ByteCount,
+    fast_fields: ByteCount,
+    vectors: ByteCount,
+    features: ByteCount,
+    store: ByteCount,
+    flushing: ByteCount,
+}
+
+impl SegmentWriterMemoryUsage {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        postings_arena: ByteCount,
+        term_hashmap_table: ByteCount,
+        term_arena: ByteCount,
+        per_field_terms: HashMap<Field, FieldTermsMemoryUsage>,
+        fieldnorms: ByteCount,
+        fast_fields: ByteCount,
+        vectors: ByteCount,
+        features: ByteCount,
+        store: ByteCount,
+    ) -> SegmentWriterMemoryUsage {
+        SegmentWriterMemoryUsage {
+            postings_arena,
+            term_hashmap_table,
+            term_arena,
+            per_field_terms,
+            fieldnorms,
+            fast_fields,
+            vectors,
+            features,
+            store,
+            flushing: 0,
+        }
+    }
+
+    pub(crate) fn set_flushing(&mut self, flushing: ByteCount) {
+        self.flushing = flushing;
+    }
+
+    /// Memory arena storing the posting lists being built.
+    pub fn postings_arena(&self) -> ByteCount {
+        self.postings_arena
+    }
+
+    /// Table of the term hashmap.
+    pub fn term_hashmap_table(&self) -> ByteCount {
+        self.term_hashmap_table
+    }
+
+    /// Memory arena storing the terms of the term hashmap.
+    ///
+    /// Unlike the other components, the term arena is not accounted for
+    /// in the memory budget of the indexing thread.
+    pub fn term_arena(&self) -> ByteCount {
+        self.term_arena
+    }
+
+    /// Breakdown of the terms of the term hashmap, for the fields having at least one term.
+    pub fn per_field_terms(&self) -> &HashMap<Field, FieldTermsMemoryUsage> {
+        &self.per_field_terms
+    }
+
+    /// Buffers of the fieldnorms.
+    pub fn fieldnorms(&self) -> ByteCount {
+        self.fieldnorms
+    }
+
+    /// Buffers of the fast field values.
+    pub fn fast_fields(&self) -> ByteCount {
+        self.fast_fields
+    }
+
+    /// Buffers of the vectors of the vector fields.
+    pub fn vectors(&self) -> ByteCount {
+        self.vectors
+    }
+
+    /// Buffers of the features of the features fields.
+    pub fn features(&self) -> ByteCount {
+        self.features
+    }
+
+    /// Buffers of the doc store, before they are compressed and written.
+    pub fn store(&self) -> ByteCount {
+        self.store
+    }
+
+    /// Memory still held by the previous segment of the indexing thread,
+    /// while it is flushed in the background.
+    ///
+    /// It is not part of the breakdown above.
+    pub fn flushing(&self) -> ByteCount {
+        self.flushing
+    }
+
+    /// Total memory used by the segment writer, and by the segment being flushed
+    /// in the background if any.
+    pub fn total(&self) -> ByteCount {
+        self.postings_arena
+            + self.term_hashmap_table
+            + self.term_arena
+            + self.fieldnorms
+            + self.fast_fields
+            + self.vectors
+            + self.features
+            + self.store
+            + self.flushing
+    }
+}
@@ -0,0 +1,30 @@
+use crate::indexer::SegmentDocIdMapping;
+use crate::SegmentId;
+
+/// A `MergeListener` is notified of the doc id mapping of every merge
+/// run by an `IndexWriter`.
+///
+/// It makes it possible to keep external data keyed by `(SegmentId, DocId)`
+/// (e.g. a vector index) consistent with the index, as segments get merged
+/// and deleted documents get dropped.
+///
+/// See [`IndexWriter::set_merge_listener`](../struct.IndexWriter.html#method.set_merge_listener).
+pub trait MergeListener: Send + Sync + 'static {
+    /// Called after the merged segment has been written, and before it is
+    /// published in place of the segments it was merged from.
+    ///
+    /// * `source_segment_ids` - the ids of the merged segments. The `SegmentOrdinal`s
+    ///   in `doc_id_mapping` are indexes in this slice.
+    /// * `target_segment_id` - the id of the resulting segment.
+    /// * `doc_id_mapping` - for each `DocId` of the resulting segment, its `DocId` and
+    ///   `SegmentOrdinal` in the source segments. Deleted documents do not appear in
+    ///   the mapping.
+    ///
+    /// If an error is returned, the merge is cancelled and the source segments are kept.
+    fn on_merge(
+        &self,
+        source_segment_ids: &[SegmentId],
+        target_segment_id: SegmentId,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()>;
+}
@@ -0,0 +1,130 @@
+use crate::core::{SegmentId, SegmentMeta};
+use crate::directory::error::OpenReadError;
+use crate::directory::Directory;
+use common::HasLen;
+
+/// A merge the merge policy would start, as returned by
+/// [`IndexWriter::plan_merges`](../struct.IndexWriter.html#method.plan_merges).
+///
+/// The sizes are measured on the files of the segments, and the size of the
+/// merged segment is estimated by assuming that each document takes the same
+/// number of bytes: the deleted documents are dropped by the merge.
+#[derive(Clone, Debug)]
+pub struct MergePlan {
+    /// Ids of the segments to be merged.
+    pub segment_ids: Vec<SegmentId>,
+    /// Whether the segments are committed. Committed and uncommitted segments
+    /// are never merged together.
+    pub committed: bool,
+    /// Number of documents of the merged segment.
+    pub num_docs: u32,
+    /// Number of deleted documents purged by the merge.
+    pub num_deleted_docs: u32,
+    /// Number of bytes of the segments to be merged, read by the merge.
+    pub num_bytes: u64,
+    /// Estimated number of bytes of the merged segment, written by the merge.
+    pub estimated_merged_num_bytes: u64,
+}
+
+impl MergePlan {
+    pub(crate) fn new(
+        segment_metas: &[SegmentMeta],
+        committed: bool,
+        directory: &dyn Directory,
+    ) -> crate::Result<MergePlan> {
+        let mut num_bytes = 0u64;
+        for segment_meta in segment_metas {
+            num_bytes += segment_num_bytes(segment_meta, directory)?;
+        }
+        let max_doc: u64 = segment_metas
+            .iter()
+            .map(|segment_meta| u64::from(segment_meta.max_doc()))
+            .sum();
+        let num_docs: u32 = segment_metas.iter().map(SegmentMeta::num_docs).sum();
+        let num_deleted_docs: u32 = segment_metas
+            .iter()
+            .map(SegmentMeta::num_deleted_docs)
+            .sum();
+        let estimated_merged_num_bytes = if max_doc == 0 {
+            0
+        } else {
+            (num_bytes as f64 * num_docs as f64 / max_doc as f64).round() as u64
+        };
+        Ok(MergePlan {
+            segment_ids: segment_metas.iter().map(SegmentMeta::id).collect(),
+            committed,
+            num_docs,
+            num_deleted_docs,
+            num_bytes,
+            estimated_merged_num_bytes,
+        })
+    }
+
+    /// Estimated number of bytes read and written by the merge.
+    pub fn estimated_io_num_bytes(&self) -> u64 {
+        self.num_bytes + self.estimated_merged_num_bytes
+    }
+}
+
+/// Returns the number of bytes of the files of a segment.
+fn segment_num_bytes(segment_meta: &SegmentMeta, directory: &dyn Directory) -> crate::Result<u64> {
+    let mut num_bytes = 0u64;
+    for path in segment_meta.list_files() {
+        match directory.open_read(&path) {
+            Ok(file_slice) => num_bytes += file_slice.len() as u64,
+            // Some of the components are optional.
+            Err(OpenReadError::FileDoesNotExist(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(num_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::indexer::merge_policy::tests::MergeWheneverPossible;
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{Schema, STORED, STRING};
+    use crate::{Index, Term};
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_plan_merges() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0..300 {
+            let parity = if i % 2 == 0 { "even" } else { "odd" };
+            index_writer.add_document(doc!(text => parity))?;
+            if i % 100 == 99 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "odd"));
+        index_writer.commit()?;
+        assert!(index_writer.plan_merges()?.is_empty());
+
+        index_writer.set_merge_policy(Box::new(MergeWheneverPossible));
+        let merge_plans = index_writer.plan_merges()?;
+        assert_eq!(merge_plans.len(), 1);
+        let merge_plan = &merge_plans[0];
+        assert!(merge_plan.committed);
+        assert_eq!(merge_plan.segment_ids.len(), 3);
+        assert_eq!(merge_plan.num_docs, 150);
+        assert_eq!(merge_plan.num_deleted_docs, 150);
+        assert!(merge_plan.num_bytes > 0);
+        assert!(merge_plan.estimated_merged_num_bytes < merge_plan.num_bytes);
+        assert_eq!(
+            merge_plan.estimated_io_num_bytes(),
+            merge_plan.num_bytes + merge_plan.estimated_merged_num_bytes
+        );
+        // Planning the merges does not start them.
+        assert_eq!(index.searchable_segment_ids()?.len(), 3);
+
+        block_on(index_writer.merge(&merge_plan.segment_ids))?;
+        assert!(index_writer.plan_merges()?.is_empty());
+        Ok(())
+    }
+}
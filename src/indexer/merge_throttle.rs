@@ -0,0 +1,358 @@
+use common::{AntiCallToken, TerminatingWrite};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Duration of the windows the merge throughput is measured on.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits the resources used by the background merges of an `IndexWriter`,
+/// so that they do not starve the searches running on the same disk.
+///
+/// By default, the merges are not throttled.
+///
+/// See [`IndexWriter::set_merge_throttle`](../struct.IndexWriter.html#method.set_merge_throttle).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeThrottle {
+    max_bytes_per_sec: Option<f64>,
+    max_concurrent_merges: Option<usize>,
+}
+
+impl MergeThrottle {
+    /// Limits the number of bytes written per second by all of the merges together,
+    /// in megabytes (10^6 bytes) per second.
+    ///
+    /// The merges write the merged segment while reading the segments being merged,
+    /// so limiting their writes also limits their reads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_mb_per_sec` is not strictly positive.
+    pub fn with_max_mb_per_sec(mut self, max_mb_per_sec: f64) -> MergeThrottle {
+        assert!(
+            max_mb_per_sec > 0.0,
+            "The merge rate limit must be strictly positive, got {}.",
+            max_mb_per_sec
+        );
+        self.max_bytes_per_sec = Some(max_mb_per_sec * 1_000_000.0);
+        self
+    }
+
+    /// Limits the number of merges running at the same time.
+    /// The merges exceeding the limit wait for a running merge to end.
+    ///
+    /// The merges of an `IndexWriter` run on 4 threads, so that a limit above
+    /// 4 has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrent_merges` is 0.
+    pub fn with_max_concurrent_merges(mut self, max_concurrent_merges: usize) -> MergeThrottle {
+        assert!(
+            max_concurrent_merges > 0,
+            "The maximum number of concurrent merges must be at least 1."
+        );
+        self.max_concurrent_merges = Some(max_concurrent_merges);
+        self
+    }
+
+    /// Returns the limit of the number of bytes written per second by the merges, if any.
+    pub fn max_bytes_per_sec(&self) -> Option<f64> {
+        self.max_bytes_per_sec
+    }
+
+    /// Returns the limit of the number of merges running at the same time, if any.
+    pub fn max_concurrent_merges(&self) -> Option<usize> {
+        self.max_concurrent_merges
+    }
+}
+
+/// Counters of the merges of an `IndexWriter`, as returned by
+/// [`IndexWriter::merge_throughput`](../struct.IndexWriter.html#method.merge_throughput).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeThroughput {
+    /// Number of merges currently running.
+    pub num_running_merges: usize,
+    /// Number of merges waiting for a running merge to end, because of the
+    /// `max_concurrent_merges` limit.
+    pub num_waiting_merges: usize,
+    /// Number of bytes written by the merges since the creation of the writer.
+    pub num_bytes_written: u64,
+    /// Number of bytes written per second by the merges, measured on the last second.
+    pub bytes_per_sec: f64,
+}
+
+#[derive(Default)]
+struct MergeSlots {
+    num_running: usize,
+    num_waiting: usize,
+}
+
+struct Pacing {
+    // instant at which the bytes written so far are paid for.
+    next_write: Instant,
+    window_start: Instant,
+    window_num_bytes: u64,
+    last_window_bytes_per_sec: f64,
+}
+
+/// Enforces a `MergeThrottle` over the merges of a `SegmentUpdater`,
+/// and measures their throughput.
+pub(crate) struct MergeLimiter {
+    throttle: RwLock<MergeThrottle>,
+    slots: Mutex<MergeSlots>,
+    slot_released: Condvar,
+    num_bytes_written: AtomicU64,
+    pacing: Mutex<Pacing>,
+}
+
+impl Default for MergeLimiter {
+    fn default() -> MergeLimiter {
+        let now = Instant::now();
+        MergeLimiter {
+            throttle: RwLock::default(),
+            slots: Mutex::default(),
+            slot_released: Condvar::new(),
+            num_bytes_written: AtomicU64::default(),
+            pacing: Mutex::new(Pacing {
+                next_write: now,
+                window_start: now,
+                window_num_bytes: 0,
+                last_window_bytes_per_sec: 0.0,
+            }),
+        }
+    }
+}
+
+impl MergeLimiter {
+    pub fn throttle(&self) -> MergeThrottle {
+        self.throttle
+            .read()
+            .expect("Merge throttle lock poisoned. This should never happen.")
+            .clone()
+    }
+
+    pub fn set_throttle(&self, throttle: MergeThrottle) {
+        *self
+            .throttle
+            .write()
+            .expect("Merge throttle lock poisoned. This should never happen.") = throttle;
+        // the waiting merges may be allowed to start with the new limit.
+        // The slots lock is taken so that no merge is between checking the limit and waiting.
+        let _slots = self
+            .slots
+            .lock()
+            .expect("Merge slots lock poisoned. This should never happen.");
+        self.slot_released.notify_all();
+    }
+
+    /// Blocks until the merge is allowed to run. The merge is considered
+    /// running until the returned `MergeSlot` is dropped.
+    pub fn acquire_slot(self: &Arc<Self>) -> MergeSlot {
+        let mut slots = self
+            .slots
+            .lock()
+            .expect("Merge slots lock poisoned. This should never happen.");
+        slots.num_waiting += 1;
+        while let Some(max_concurrent_merges) = self.throttle().max_concurrent_merges() {
+            if slots.num_running < max_concurrent_merges {
+                break;
+            }
+            slots = self
+                .slot_released
+                .wait(slots)
+                .expect("Merge slots lock poisoned. This should never happen.");
+        }
+        slots.num_waiting -= 1;
+        slots.num_running += 1;
+        MergeSlot {
+            limiter: Arc::clone(self),
+        }
+    }
+
+    fn release_slot(&self) {
+        self.slots
+            .lock()
+            .expect("Merge slots lock poisoned. This should never happen.")
+            .num_running -= 1;
+        self.slot_released.notify_all();
+    }
+
+    /// Records `num_bytes` written by a merge, and sleeps as long as required
+    /// to stay within the rate limit.
+    fn record_write(&self, num_bytes: usize) {
+        self.num_bytes_written
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+        let max_bytes_per_sec_opt = self.throttle().max_bytes_per_sec();
+        let now = Instant::now();
+        let sleep_duration = {
+            let mut pacing = self
+                .pacing
+                .lock()
+                .expect("Merge pacing lock poisoned. This should never happen.");
+            let window_duration = now.duration_since(pacing.window_start);
+            if window_duration >= THROUGHPUT_WINDOW {
+                pacing.last_window_bytes_per_sec =
+                    pacing.window_num_bytes as f64 / window_duration.as_secs_f64();
+                pacing.window_start = now;
+                pacing.window_num_bytes = 0;
+            }
+            pacing.window_num_bytes += num_bytes as u64;
+            if let Some(max_bytes_per_sec) = max_bytes_per_sec_opt {
+                let write_start = pacing.next_write.max(now);
+                pacing.next_write =
+                    write_start + Duration::from_secs_f64(num_bytes as f64 / max_bytes_per_sec);
+                pacing.next_write.duration_since(now)
+            } else {
+                pacing.next_write = now;
+                Duration::default()
+            }
+        };
+        if sleep_duration > Duration::default() {
+            thread::sleep(sleep_duration);
+        }
+    }
+
+    pub fn throughput(&self) -> MergeThroughput {
+        let (num_running_merges, num_waiting_merges) = {
+            let slots = self
+                .slots
+                .lock()
+                .expect("Merge slots lock poisoned. This should never happen.");
+            (slots.num_running, slots.num_waiting)
+        };
+        let bytes_per_sec = {
+            let pacing = self
+                .pacing
+                .lock()
+                .expect("Merge pacing lock poisoned. This should never happen.");
+            let window_duration = pacing.window_start.elapsed();
+            if window_duration >= THROUGHPUT_WINDOW {
+                // no write has closed the current window yet.
+                pacing.window_num_bytes as f64 / window_duration.as_secs_f64()
+            } else {
+                pacing.last_window_bytes_per_sec
+            }
+        };
+        MergeThroughput {
+            num_running_merges,
+            num_waiting_merges,
+            num_bytes_written: self.num_bytes_written.load(Ordering::Relaxed),
+            bytes_per_sec,
+        }
+    }
+}
+
+/// A merge allowed to run by a `MergeLimiter`.
+pub(crate) struct MergeSlot {
+    limiter: Arc<MergeLimiter>,
+}
+
+impl Drop for MergeSlot {
+    fn drop(&mut self) {
+        self.limiter.release_slot();
+    }
+}
+
+/// Wraps the writes of a merge, to rate limit them.
+pub(crate) struct ThrottledWrite<W: TerminatingWrite> {
+    wrt: W,
+    limiter: Arc<MergeLimiter>,
+}
+
+impl<W: TerminatingWrite> ThrottledWrite<W> {
+    pub fn new(wrt: W, limiter: Arc<MergeLimiter>) -> ThrottledWrite<W> {
+        ThrottledWrite { wrt, limiter }
+    }
+}
+
+impl<W: TerminatingWrite> Write for ThrottledWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = self.wrt.write(buf)?;
+        self.limiter.record_write(num_bytes);
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.wrt.flush()
+    }
+}
+
+impl<W: TerminatingWrite> TerminatingWrite for ThrottledWrite<W> {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.wrt.terminate_ref(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MergeLimiter, MergeThrottle, ThrottledWrite};
+    use common::TerminatingWrite;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_merge_limiter_rate_limit() -> std::io::Result<()> {
+        let limiter = Arc::new(MergeLimiter::default());
+        limiter.set_throttle(MergeThrottle::default().with_max_mb_per_sec(1.0));
+        let mut buffer = Vec::new();
+        let mut wrt = ThrottledWrite::new(&mut buffer, Arc::clone(&limiter));
+        let start = Instant::now();
+        for _ in 0..4 {
+            wrt.write_all(&[0u8; 50_000])?;
+        }
+        wrt.terminate()?;
+        // 200KB at 1MB/s take 200ms.
+        assert!(start.elapsed() >= Duration::from_millis(190));
+        assert_eq!(buffer.len(), 200_000);
+        assert_eq!(limiter.throughput().num_bytes_written, 200_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_limiter_max_concurrent_merges() {
+        let limiter = Arc::new(MergeLimiter::default());
+        limiter.set_throttle(MergeThrottle::default().with_max_concurrent_merges(1));
+        let slot = limiter.acquire_slot();
+        let waiting_merge = {
+            let limiter = Arc::clone(&limiter);
+            thread::spawn(move || {
+                let _slot = limiter.acquire_slot();
+            })
+        };
+        while limiter.throughput().num_waiting_merges == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(limiter.throughput().num_running_merges, 1);
+        drop(slot);
+        waiting_merge.join().unwrap();
+        let throughput = limiter.throughput();
+        assert_eq!(throughput.num_running_merges, 0);
+        assert_eq!(throughput.num_waiting_merges, 0);
+
+        // raising the limit releases the waiting merges.
+        let slot = limiter.acquire_slot();
+        let waiting_merge = {
+            let limiter = Arc::clone(&limiter);
+            thread::spawn(move || {
+                let _slot = limiter.acquire_slot();
+            })
+        };
+        while limiter.throughput().num_waiting_merges == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        limiter.set_throttle(MergeThrottle::default());
+        waiting_merge.join().unwrap();
+        drop(slot);
+    }
+
+    #[test]
+    #[should_panic(expected = "The merge rate limit must be strictly positive")]
+    fn test_merge_throttle_invalid_rate() {
+        MergeThrottle::default().with_max_mb_per_sec(0.0);
+    }
+}
@@ -7,6 +7,7 @@ use crate::fastfield::FastFieldReader;
 use crate::fastfield::FastFieldStats;
 use crate::fastfield::MultiValueLength;
 use crate::fastfield::MultiValuedFastFieldReader;
+use crate::features::{FeaturesReader, FeaturesSerializer};
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::fieldnorm::FieldNormsWriter;
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders};
@@ -14,12 +15,14 @@ use crate::indexer::doc_id_mapping::SegmentDocIdMapping;
 use crate::indexer::SegmentSerializer;
 use crate::postings::Postings;
 use crate::postings::{InvertedIndexSerializer, SegmentPostings};
+use crate::query::static_rank_field;
 use crate::schema::Cardinality;
 use crate::schema::FieldType;
 use crate::schema::{Field, Schema};
-use crate::store::StoreWriter;
-use crate::termdict::TermMerger;
+use crate::store::{StoreDictionary, StoreWriter};
 use crate::termdict::TermOrdinal;
+use crate::termdict::{TermMerger, TermStreamer};
+use crate::vector::{VectorReader, VectorsSerializer};
 use crate::IndexSettings;
 use crate::IndexSortByField;
 use crate::{core::Segment, indexer::doc_id_mapping::expect_field_id_for_sort_field};
@@ -29,16 +32,19 @@ use crate::{
     SegmentOrdinal,
 };
 use crate::{DocId, InvertedIndexReader, SegmentComponent};
+use common::BitSet;
 use itertools::Itertools;
 use measure_time::debug_time;
 use std::cmp;
 use std::collections::HashMap;
+use std::io;
 use std::sync::Arc;
 use tantivy_bitpacker::minmax;
 
 /// Segment's max doc must be `< MAX_DOC_LIMIT`.
 ///
-/// We do not allow segments with more than
+/// We do not allow segments with more than `2^31` documents, so that
+/// `DocId`s always fit in an `i32` and never reach `TERMINATED`.
 pub const MAX_DOC_LIMIT: u32 = 1 << 31;
 
 fn estimate_total_num_tokens_in_single_segment(
@@ -211,24 +217,23 @@ impl IndexMerger {
                 readers.push(reader);
             }
         }
-        let max_doc = readers.iter().map(|reader| reader.num_docs()).sum();
-        if let Some(sort_by_field) = index_settings.sort_by_field.as_ref() {
-            readers = Self::sort_readers_by_min_sort_field(readers, sort_by_field)?;
+        // Summed as u64, so that the limit check below cannot be defeated by an overflow.
+        let max_doc: u64 = readers
+            .iter()
+            .map(|reader| u64::from(reader.num_docs()))
+            .sum();
+        if let Some(sort_by_field) = index_settings.segment_sort_by_field() {
+            readers = Self::sort_readers_by_min_sort_field(readers, &sort_by_field)?;
         }
         // sort segments by their natural sort setting
-        if max_doc >= MAX_DOC_LIMIT {
-            let err_msg = format!(
-                "The segment resulting from this merge would have {} docs,\
-                 which exceeds the limit {}.",
-                max_doc, MAX_DOC_LIMIT
-            );
-            return Err(crate::TantivyError::InvalidArgument(err_msg));
+        if max_doc >= u64::from(MAX_DOC_LIMIT) {
+            return Err(crate::TantivyError::DocLimitExceeded(max_doc));
         }
         Ok(IndexMerger {
             index_settings,
             schema,
             readers,
-            max_doc,
+            max_doc: max_doc as u32,
         })
     }
 
@@ -283,6 +288,106 @@ impl IndexMerger {
         Ok(())
     }
 
+    fn write_vectors(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        for (field, field_entry) in self.schema.fields() {
+            let vector_options = if let FieldType::Vector(vector_options) = field_entry.field_type()
+            {
+                vector_options
+            } else {
+                continue;
+            };
+            let vector_readers: Vec<VectorReader> = self
+                .readers
+                .iter()
+                .map(|reader| reader.vector_reader(field))
+                .collect::<Result<_, _>>()?;
+            let mut doc_ids = Vec::new();
+            let mut vectors = Vec::new();
+            for (new_doc_id, (old_doc_id, reader_ordinal)) in doc_id_mapping.iter().enumerate() {
+                let vector_reader = &vector_readers[*reader_ordinal as usize];
+                if let Some(vector) = vector_reader.vector(*old_doc_id) {
+                    doc_ids.push(new_doc_id as DocId);
+                    vectors.extend(vector);
+                }
+            }
+            vectors_serializer.serialize_field(
+                field,
+                vector_options.similarity(),
+                vector_options.dimension(),
+                &doc_ids,
+                &vectors,
+            )?;
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+
+    fn write_features(
+        &self,
+        mut features_serializer: FeaturesSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        let features_fields: Vec<Field> = self
+            .schema
+            .fields()
+            .filter(|(_, field_entry)| matches!(field_entry.field_type(), FieldType::Features(_)))
+            .map(|(field, _)| field)
+            .collect();
+        if !features_fields.is_empty() {
+            // Mapping from the doc ids of the merged segments to the doc ids of the resulting
+            // segment. Deleted documents are mapped to `None`.
+            let mut new_doc_ids: Vec<Vec<Option<DocId>>> = self
+                .readers
+                .iter()
+                .map(|reader| vec![None; reader.max_doc() as usize])
+                .collect();
+            for (new_doc_id, (old_doc_id, reader_ordinal)) in doc_id_mapping.iter().enumerate() {
+                new_doc_ids[*reader_ordinal as usize][*old_doc_id as usize] =
+                    Some(new_doc_id as DocId);
+            }
+            for field in features_fields {
+                let features_readers: Vec<FeaturesReader> = self
+                    .readers
+                    .iter()
+                    .map(|reader| reader.features_reader(field))
+                    .collect::<Result<_, _>>()?;
+                let feature_streams = features_readers
+                    .iter()
+                    .map(|features_reader| features_reader.dictionary().stream())
+                    .collect::<io::Result<Vec<TermStreamer>>>()?;
+                let mut feature_merger = TermMerger::new(feature_streams);
+                let features = std::iter::from_fn(|| {
+                    match feature_merger.advance() {
+                        Ok(true) => {}
+                        Ok(false) => return None,
+                        Err(io_error) => return Some(Err(io_error)),
+                    }
+                    let feature = String::from_utf8_lossy(feature_merger.key()).to_string();
+                    let mut postings = Vec::new();
+                    for (reader_ordinal, term_info) in
+                        feature_merger.current_segment_ords_and_term_infos()
+                    {
+                        let segment_new_doc_ids = &new_doc_ids[reader_ordinal];
+                        let segment_postings =
+                            features_readers[reader_ordinal].postings_from_term_info(&term_info);
+                        postings.extend(segment_postings.iter().filter_map(|(doc, weight)| {
+                            segment_new_doc_ids[doc as usize].map(|new_doc| (new_doc, weight))
+                        }));
+                    }
+                    postings.sort_unstable_by_key(|(doc, _)| *doc);
+                    Some(Ok((feature, postings)))
+                });
+                features_serializer.serialize_field(field, features)?;
+            }
+        }
+        features_serializer.close()?;
+        Ok(())
+    }
+
     fn write_fast_fields(
         &self,
         fast_field_serializer: &mut CompositeFastFieldSerializer,
@@ -328,6 +433,12 @@ impl IndexMerger {
                         self.write_bytes_fast_field(field, fast_field_serializer, doc_id_mapping)?;
                     }
                 }
+                FieldType::Vector(_) => {
+                    // Vectors are written to their own component, see `write_vectors`.
+                }
+                FieldType::Features(_) => {
+                    // Features are written to their own component, see `write_features`.
+                }
             }
         }
         Ok(())
@@ -400,6 +511,28 @@ impl IndexMerger {
             iter2,
         )?;
 
+        let presence_bitsets = self
+            .readers
+            .iter()
+            .map(|reader| reader.fast_fields().presence_bitset(field))
+            .collect::<crate::Result<Vec<_>>>()?;
+        if presence_bitsets.iter().any(Option::is_some) {
+            let num_docs = doc_id_mapping.len() as u32;
+            let mut presence_bitset = BitSet::with_max_value(num_docs);
+            for (new_doc_id, (old_doc_id, reader_ordinal)) in doc_id_mapping.iter().enumerate() {
+                let has_value = presence_bitsets[*reader_ordinal as usize]
+                    .as_ref()
+                    .map(|presence_bitset| presence_bitset.contains(*old_doc_id))
+                    .unwrap_or(true);
+                if has_value {
+                    presence_bitset.insert(new_doc_id as DocId);
+                }
+            }
+            if presence_bitset.len() < num_docs as usize {
+                fast_field_serializer.write_presence_bitset(field, &presence_bitset)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -904,7 +1037,7 @@ impl IndexMerger {
         let mut segment_postings_containing_the_term: Vec<(usize, SegmentPostings)> = vec![];
         let mut doc_id_and_positions = vec![];
 
-        while merged_terms.advance() {
+        while merged_terms.advance()? {
             segment_postings_containing_the_term.clear();
             let term_bytes: &[u8] = merged_terms.key();
 
@@ -973,10 +1106,17 @@ impl IndexMerger {
                                 remapped_doc_id,
                                 term_freq,
                                 positions_buffer.to_vec(),
+                                segment_postings
+                                    .payloads()
+                                    .map(<[u8]>::to_vec)
+                                    .collect::<Vec<_>>(),
                             ));
                         } else {
                             let delta_positions = delta_computer.compute_delta(&positions_buffer);
                             field_serializer.write_doc(remapped_doc_id, term_freq, delta_positions);
+                            for payload in segment_postings.payloads() {
+                                field_serializer.write_payload(payload);
+                            }
                         }
                     }
 
@@ -984,11 +1124,14 @@ impl IndexMerger {
                 }
             }
             if !doc_id_mapping.is_trivial() {
-                doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _)| doc_id);
+                doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _, _)| doc_id);
 
-                for (doc_id, term_freq, positions) in &doc_id_and_positions {
+                for (doc_id, term_freq, positions, payloads) in &doc_id_and_positions {
                     let delta_positions = delta_computer.compute_delta(positions);
                     field_serializer.write_doc(*doc_id, *term_freq, delta_positions);
+                    for payload in payloads {
+                        field_serializer.write_payload(payload);
+                    }
                 }
                 doc_id_and_positions.clear();
             }
@@ -1035,17 +1178,43 @@ impl IndexMerger {
             .iter()
             .map(|reader| reader.get_store_reader())
             .collect::<Result<_, _>>()?;
+        // The keys of the documents in the same external store are copied over,
+        // instead of putting the documents again.
+        let copies_entries: Vec<bool> = store_readers
+            .iter()
+            .map(|store| store_writer.copies_entries_of(store))
+            .collect();
         let mut document_iterators: Vec<_> = store_readers
             .iter()
             .enumerate()
-            .map(|(i, store)| store.iter_raw(self.readers[i].alive_bitset()))
+            .map(|(i, store)| store.iter_entries(self.readers[i].alive_bitset()))
             .collect();
+        if let Some(dictionary_size) = self
+            .index_settings
+            .docstore_dictionary_size_for_compressor()
+        {
+            let docs = store_readers
+                .iter()
+                .zip(&self.readers)
+                .flat_map(|(store_reader, reader)| store_reader.iter_raw(reader.alive_bitset()));
+            if let Some(dictionary) =
+                StoreDictionary::train(docs, self.max_doc as usize, dictionary_size)?
+            {
+                store_writer.set_dictionary(dictionary);
+            }
+        }
         if !doc_id_mapping.is_trivial() {
             for (old_doc_id, reader_ordinal) in doc_id_mapping.iter() {
                 let doc_bytes_it = &mut document_iterators[*reader_ordinal as usize];
-                if let Some(doc_bytes_res) = doc_bytes_it.next() {
-                    let doc_bytes = doc_bytes_res?;
-                    store_writer.store_bytes(&doc_bytes)?;
+                if let Some(entry_res) = doc_bytes_it.next() {
+                    let (doc_id, entry) = entry_res?;
+                    if copies_entries[*reader_ordinal as usize] {
+                        store_writer.store_entry(&entry)?;
+                    } else {
+                        let store_reader = &store_readers[*reader_ordinal as usize];
+                        let doc_bytes = store_reader.resolve_entry(doc_id, entry)?;
+                        store_writer.store_bytes(&doc_bytes)?;
+                    }
                 } else {
                     return Err(DataCorruption::comment_only(&format!(
                         "unexpected missing document in docstore on merge, doc id {:?}",
@@ -1073,7 +1242,18 @@ impl IndexMerger {
                     // take 7 in order to not walk over all checkpoints.
                     || store_reader.block_checkpoints().take(7).count() < 6
                     || store_reader.compressor() != store_writer.compressor()
+                    || store_reader.has_dictionary()
+                    || store_writer.has_dictionary()
+                    || store_reader.has_raw_values() != store_writer.has_raw_fields()
+                    || !store_writer.shares_entries_with(&store_reader)
                 {
+                    if store_writer.copies_entries_of(&store_reader) {
+                        for entry_res in store_reader.iter_entries(reader.alive_bitset()) {
+                            let (_, entry) = entry_res?;
+                            store_writer.store_entry(&entry)?;
+                        }
+                        continue;
+                    }
                     for doc_bytes_res in store_reader.iter_raw(reader.alive_bitset()) {
                         let doc_bytes = doc_bytes_res?;
                         store_writer.store_bytes(&doc_bytes)?;
@@ -1091,19 +1271,49 @@ impl IndexMerger {
     ///
     /// # Returns
     /// The number of documents in the resulting segment.
-    pub fn write(&self, mut serializer: SegmentSerializer) -> crate::Result<u32> {
-        let doc_id_mapping = if let Some(sort_by_field) = self.index_settings.sort_by_field.as_ref()
-        {
-            // If the documents are already sorted and stackable, we ignore the mapping and execute
-            // it as if there was no sorting
-            if self.is_disjunct_and_sorted_on_sort_property(sort_by_field)? {
-                self.get_doc_id_from_concatenated_data()?
+    pub fn write(&self, serializer: SegmentSerializer) -> crate::Result<u32> {
+        self.write_and_get_doc_id_mapping(serializer)
+            .map(|(num_docs, _)| num_docs)
+    }
+
+    /// Same as `write`, but also returns the mapping from the doc ids of the
+    /// resulting segment to the doc ids of the merged segments.
+    /// Returns the values of the static rank field of the merged documents,
+    /// indexed by their new doc id.
+    fn static_ranks(
+        &self,
+        static_rank_field: Field,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<Vec<u64>> {
+        let fast_field_readers = self
+            .readers
+            .iter()
+            .map(|reader| reader.fast_fields().u64_lenient(static_rank_field))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(doc_id_mapping
+            .iter()
+            .map(|&(old_doc_id, reader_ordinal)| {
+                fast_field_readers[reader_ordinal as usize].get(old_doc_id)
+            })
+            .collect())
+    }
+
+    pub(crate) fn write_and_get_doc_id_mapping(
+        &self,
+        mut serializer: SegmentSerializer,
+    ) -> crate::Result<(u32, SegmentDocIdMapping)> {
+        let doc_id_mapping =
+            if let Some(sort_by_field) = self.index_settings.segment_sort_by_field() {
+                // If the documents are already sorted and stackable, we ignore the mapping and execute
+                // it as if there was no sorting
+                if self.is_disjunct_and_sorted_on_sort_property(&sort_by_field)? {
+                    self.get_doc_id_from_concatenated_data()?
+                } else {
+                    self.generate_doc_id_mapping(&sort_by_field)?
+                }
             } else {
-                self.generate_doc_id_mapping(sort_by_field)?
-            }
-        } else {
-            self.get_doc_id_from_concatenated_data()?
-        };
+                self.get_doc_id_from_concatenated_data()?
+            };
 
         if let Some(fieldnorms_serializer) = serializer.extract_fieldnorms_serializer() {
             self.write_fieldnorms(fieldnorms_serializer, &doc_id_mapping)?;
@@ -1112,6 +1322,13 @@ impl IndexMerger {
             .segment()
             .open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
+        if let Some(static_rank_field_name) = serializer.segment().meta().static_rank_field() {
+            let static_rank_field = static_rank_field(&self.schema, static_rank_field_name)?;
+            let static_ranks = self.static_ranks(static_rank_field, &doc_id_mapping)?;
+            serializer
+                .get_postings_serializer()
+                .set_static_ranks(static_ranks);
+        }
         let term_ord_mappings = self.write_postings(
             serializer.get_postings_serializer(),
             fieldnorm_readers,
@@ -1122,9 +1339,15 @@ impl IndexMerger {
             term_ord_mappings,
             &doc_id_mapping,
         )?;
+        if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+            self.write_vectors(vectors_serializer, &doc_id_mapping)?;
+        }
+        if let Some(features_serializer) = serializer.extract_features_serializer() {
+            self.write_features(features_serializer, &doc_id_mapping)?;
+        }
         self.write_storable_fields(serializer.get_store_writer(), &doc_id_mapping)?;
         serializer.close()?;
-        Ok(self.max_doc)
+        Ok((self.max_doc, doc_id_mapping))
     }
 }
 
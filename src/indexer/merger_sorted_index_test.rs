@@ -8,7 +8,7 @@ mod tests {
     use crate::schema::{
         self, BytesOptions, Cardinality, Facet, FacetOptions, IndexRecordOption, TextFieldIndexing,
     };
-    use crate::schema::{IntOptions, TextOptions};
+    use crate::schema::{FeaturesOptions, IntOptions, TextOptions, VectorOptions};
     use crate::DocAddress;
     use crate::IndexSortByField;
     use crate::Order;
@@ -478,6 +478,59 @@ mod tests {
             assert_eq!(doc.get_first(int_field).unwrap().u64_value(), Some(1_000));
         }
     }
+
+    #[test]
+    fn test_merge_sorted_index_vectors_and_features() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let int_options = IntOptions::default()
+            .set_fast(Cardinality::SingleValue)
+            .set_indexed();
+        let int_field = schema_builder.add_u64_field("intval", int_options);
+        let vector_field = schema_builder.add_vector_field("vector", VectorOptions::new(2));
+        let features_field =
+            schema_builder.add_features_field("features", FeaturesOptions::default());
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "intval".to_string(),
+                    order: Order::Asc,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            // The sort values of the two segments are interleaved.
+            for segment_values in &[[3u64, 1u64], [4u64, 2u64]] {
+                for &val in segment_values {
+                    let mut doc = doc!(int_field => val);
+                    doc.add_vector(vector_field, vec![1.0, val as f32]);
+                    doc.add_features(features_field, vec![("rank", val as f32)]);
+                    index_writer.add_document(doc)?;
+                }
+                index_writer.commit()?;
+            }
+            let segment_ids = index.searchable_segment_ids()?;
+            block_on(index_writer.merge(&segment_ids))?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        let fast_field = segment_reader.fast_fields().u64(int_field)?;
+        let vector_reader = segment_reader.vector_reader(vector_field)?;
+        let features_reader = segment_reader.features_reader(features_field)?;
+        for doc in 0..4 {
+            let val = fast_field.get(doc);
+            assert_eq!(val, doc as u64 + 1);
+            let vector = vector_reader.vector(doc).unwrap();
+            // The vectors may be normalized, their direction identifies the document.
+            assert!((vector[1] / vector[0] - val as f32).abs() < 1e-4);
+            assert_eq!(features_reader.weight("rank", doc)?, Some(val as f32));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "unstable"))]
@@ -1,3 +1,5 @@
+mod auto_commit;
+mod deduplicating_writer;
 pub mod delete_queue;
 
 pub mod demuxer;
@@ -5,9 +7,16 @@ pub mod doc_id_mapping;
 mod doc_opstamp_mapping;
 pub mod index_writer;
 mod index_writer_status;
+mod indexing_observer;
+mod ingest;
+mod inverted_index_writer;
 mod log_merge_policy;
+mod memory_usage;
+mod merge_listener;
 mod merge_operation;
+mod merge_plan;
 pub mod merge_policy;
+pub(crate) mod merge_throttle;
 pub mod merger;
 mod merger_sorted_index_test;
 pub mod operation;
@@ -25,10 +34,22 @@ use smallvec::SmallVec;
 
 use crate::indexer::operation::AddOperation;
 
+pub use self::auto_commit::{AutoCommitPolicy, AutoCommitWriter, CommitListener};
+pub use self::deduplicating_writer::{DeduplicatingWriter, DeduplicationPolicy, OnDuplicate};
+pub use self::doc_id_mapping::SegmentDocIdMapping;
 pub use self::index_writer::IndexWriter;
+pub use self::indexing_observer::{
+    FieldIndexingStatistics, IndexingObserver, SegmentIndexingStatistics,
+};
+pub use self::ingest::{IngestLineError, IngestReport};
+pub use self::inverted_index_writer::{FieldPostingsWriter, InvertedIndexWriter};
 pub use self::log_merge_policy::LogMergePolicy;
+pub use self::memory_usage::{FieldTermsMemoryUsage, SegmentWriterMemoryUsage};
+pub use self::merge_listener::MergeListener;
 pub use self::merge_operation::MergeOperation;
+pub use self::merge_plan::MergePlan;
 pub use self::merge_policy::{MergeCandidate, MergePolicy, NoMergePolicy};
+pub use self::merge_throttle::{MergeThrottle, MergeThroughput};
 pub use self::prepared_commit::PreparedCommit;
 pub use self::segment_entry::SegmentEntry;
 pub use self::segment_manager::SegmentManager;
@@ -2,18 +2,27 @@ use crate::schema::Document;
 use crate::schema::Term;
 use crate::Opstamp;
 
+/// Documents targeted by a delete operation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DeleteTarget {
+    /// The documents containing the term.
+    Term(Term),
+    /// All of the documents.
+    AllDocuments,
+}
+
 /// Timestamped Delete operation.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct DeleteOperation {
     pub opstamp: Opstamp,
-    pub term: Term,
+    pub target: DeleteTarget,
 }
 
 impl Default for DeleteOperation {
     fn default() -> Self {
         DeleteOperation {
             opstamp: 0u64,
-            term: Term::new(),
+            target: DeleteTarget::Term(Term::new()),
         }
     }
 }
@@ -1,17 +1,23 @@
 use crate::core::Segment;
 use crate::core::SegmentComponent;
 use crate::fastfield::CompositeFastFieldSerializer;
+use crate::features::FeaturesSerializer;
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::postings::InvertedIndexSerializer;
+use crate::schema::{Field, Schema};
 use crate::store::StoreWriter;
+use crate::vector::VectorsSerializer;
 
 /// Segment serializer is in charge of laying out on disk
 /// the data accumulated and sorted by the `SegmentWriter`.
 pub struct SegmentSerializer {
     segment: Segment,
     pub(crate) store_writer: StoreWriter,
+    has_temp_store: bool,
     fast_field_serializer: CompositeFastFieldSerializer,
     fieldnorms_serializer: Option<FieldNormsSerializer>,
+    vectors_serializer: Option<VectorsSerializer>,
+    features_serializer: Option<FeaturesSerializer>,
     postings_serializer: InvertedIndexSerializer,
 }
 
@@ -21,30 +27,45 @@ impl SegmentSerializer {
         mut segment: Segment,
         is_in_merge: bool,
     ) -> crate::Result<SegmentSerializer> {
-        // If the segment is going to be sorted, we stream the docs first to a temporary file.
+        // If the segment is going to be sorted, or if its doc store is compressed with a
+        // dictionary trained on its documents, we stream the docs first to a temporary file.
         // In the merge case this is not necessary because we can kmerge the already sorted
-        // segments
-        let remapping_required = segment.index().settings().sort_by_field.is_some() && !is_in_merge;
-        let store_component = if remapping_required {
+        // segments, and train the dictionary on the documents of the merged segments.
+        let settings = segment.index().settings();
+        let has_temp_store = (settings.segment_sort_by_field().is_some()
+            || settings.docstore_dictionary_size_for_compressor().is_some())
+            && !is_in_merge;
+        let store_component = if has_temp_store {
             SegmentComponent::TempStore
         } else {
             SegmentComponent::Store
         };
+        let codec = segment.codec()?;
         let store_write = segment.open_write(store_component)?;
 
         let fast_field_write = segment.open_write(SegmentComponent::FastFields)?;
-        let fast_field_serializer = CompositeFastFieldSerializer::from_write(fast_field_write)?;
+        let fast_field_serializer = codec.fast_field_serializer(fast_field_write)?;
 
         let fieldnorms_write = segment.open_write(SegmentComponent::FieldNorms)?;
         let fieldnorms_serializer = FieldNormsSerializer::from_write(fieldnorms_write)?;
 
-        let postings_serializer = InvertedIndexSerializer::open(&mut segment)?;
-        let compressor = segment.index().settings().docstore_compression;
+        let vectors_write = segment.open_write(SegmentComponent::Vectors)?;
+        let vectors_serializer = VectorsSerializer::from_write(vectors_write)?;
+
+        let features_write = segment.open_write(SegmentComponent::Features)?;
+        let features_serializer = FeaturesSerializer::from_write(features_write)?;
+
+        let postings_serializer = codec.postings_serializer(&mut segment)?;
+        let mut store_writer = codec.store_writer(store_write, segment.index().settings());
+        store_writer.set_raw_fields(raw_stored_fields(&segment.schema()));
         Ok(SegmentSerializer {
             segment,
-            store_writer: StoreWriter::new(store_write, compressor),
+            store_writer,
+            has_temp_store,
             fast_field_serializer,
             fieldnorms_serializer: Some(fieldnorms_serializer),
+            vectors_serializer: Some(vectors_serializer),
+            features_serializer: Some(features_serializer),
             postings_serializer,
         })
     }
@@ -79,6 +100,26 @@ impl SegmentSerializer {
         self.fieldnorms_serializer.take()
     }
 
+    /// Extract the vectors serializer.
+    ///
+    /// Note the vectors serializer can only be extracted once.
+    pub fn extract_vectors_serializer(&mut self) -> Option<VectorsSerializer> {
+        self.vectors_serializer.take()
+    }
+
+    /// Extract the features serializer.
+    ///
+    /// Note the features serializer can only be extracted once.
+    pub fn extract_features_serializer(&mut self) -> Option<FeaturesSerializer> {
+        self.features_serializer.take()
+    }
+
+    /// Returns true iff the documents are written to the temporary doc store,
+    /// to be rewritten to the doc store when the segment is finalized.
+    pub(crate) fn has_temp_store(&self) -> bool {
+        self.has_temp_store
+    }
+
     /// Accessor to the `StoreWriter`.
     pub fn get_store_writer(&mut self) -> &mut StoreWriter {
         &mut self.store_writer
@@ -89,9 +130,24 @@ impl SegmentSerializer {
         if let Some(fieldnorms_serializer) = self.extract_fieldnorms_serializer() {
             fieldnorms_serializer.close()?;
         }
+        if let Some(vectors_serializer) = self.extract_vectors_serializer() {
+            vectors_serializer.close()?;
+        }
+        if let Some(features_serializer) = self.extract_features_serializer() {
+            features_serializer.close()?;
+        }
         self.fast_field_serializer.close()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
         Ok(())
     }
 }
+
+/// Returns the fields whose values are stored uncompressed in the doc store.
+pub(crate) fn raw_stored_fields(schema: &Schema) -> Vec<Field> {
+    schema
+        .fields()
+        .filter(|(_, field_entry)| field_entry.is_stored_raw())
+        .map(|(field, _)| field)
+        .collect()
+}
@@ -11,13 +11,14 @@ use crate::fastfield::AliveBitSet;
 use crate::indexer::delete_queue::DeleteCursor;
 use crate::indexer::index_writer::advance_deletes;
 use crate::indexer::merge_operation::MergeOperationInventory;
+use crate::indexer::merge_throttle::MergeLimiter;
 use crate::indexer::merger::IndexMerger;
 use crate::indexer::segment_manager::SegmentsStatus;
 use crate::indexer::stamper::Stamper;
 use crate::indexer::SegmentEntry;
 use crate::indexer::SegmentSerializer;
-use crate::indexer::{DefaultMergePolicy, MergePolicy};
-use crate::indexer::{MergeCandidate, MergeOperation};
+use crate::indexer::{DefaultMergePolicy, IndexingObserver, MergeListener, MergePolicy};
+use crate::indexer::{MergeCandidate, MergeOperation, MergePlan, MergeThrottle, MergeThroughput};
 use crate::schema::Schema;
 use crate::Opstamp;
 use crate::TantivyError;
@@ -27,7 +28,7 @@ use futures::executor::{ThreadPool, ThreadPoolBuilder};
 use futures::future::Future;
 use futures::future::TryFutureExt;
 use std::borrow::BorrowMut;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::Write;
 use std::ops::Deref;
@@ -58,6 +59,7 @@ pub fn save_new_metas(
             segments: Vec::new(),
             schema,
             opstamp: 0u64,
+            schema_version: 0,
             payload: None,
         },
         directory,
@@ -75,7 +77,7 @@ pub fn save_new_metas(
 /// and flushed.
 ///
 /// This method is not part of tantivy's public API
-fn save_metas(metas: &IndexMeta, directory: &dyn Directory) -> crate::Result<()> {
+pub(crate) fn save_metas(metas: &IndexMeta, directory: &dyn Directory) -> crate::Result<()> {
     info!("save metas");
     let mut buffer = serde_json::to_vec_pretty(metas)?;
     // Just adding a new line at the end of the buffer.
@@ -110,6 +112,10 @@ impl Deref for SegmentUpdater {
     }
 }
 
+fn sort_segment_metas(segment_metas: &mut [SegmentMeta]) {
+    segment_metas.sort_by_key(|segment_meta| -(segment_meta.max_doc() as i32));
+}
+
 async fn garbage_collect_files(
     segment_updater: SegmentUpdater,
 ) -> crate::Result<GarbageCollectionResult> {
@@ -126,9 +132,11 @@ fn merge(
     index: &Index,
     mut segment_entries: Vec<SegmentEntry>,
     target_opstamp: Opstamp,
+    merge_listener_opt: Option<Arc<dyn MergeListener>>,
+    merge_limiter: Arc<MergeLimiter>,
 ) -> crate::Result<SegmentEntry> {
     // first we need to apply deletes to our segment.
-    let merged_segment = index.new_segment();
+    let merged_segment = index.new_segment().with_merge_limiter(merge_limiter);
 
     // First we apply all of the delete to the merged segment, up to the target opstamp.
     for segment_entry in &mut segment_entries {
@@ -150,11 +158,20 @@ fn merge(
     // ... we just serialize this index merger in our new segment to merge the segments.
     let segment_serializer = SegmentSerializer::for_segment(merged_segment.clone(), true)?;
 
-    let num_docs = merger.write(segment_serializer)?;
+    let (num_docs, doc_id_mapping) = merger.write_and_get_doc_id_mapping(segment_serializer)?;
 
     let merged_segment_id = merged_segment.id();
 
-    let segment_meta = index.new_segment_meta(merged_segment_id, num_docs);
+    if let Some(merge_listener) = merge_listener_opt {
+        let source_segment_ids: Vec<SegmentId> = segments.iter().map(Segment::id).collect();
+        merge_listener.on_merge(&source_segment_ids, merged_segment_id, &doc_id_mapping)?;
+    }
+
+    let segment_meta = merged_segment
+        .with_max_doc(num_docs)
+        .with_computed_field_bounds()?
+        .meta()
+        .clone();
     Ok(SegmentEntry::new(segment_meta, delete_cursor, None))
 }
 
@@ -248,17 +265,20 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
         target_settings.clone(),
     )?;
     let merged_segment = merged_index.new_segment();
-    let merged_segment_id = merged_segment.id();
     let merger: IndexMerger = IndexMerger::open_with_custom_alive_set(
         merged_index.schema(),
         merged_index.settings().clone(),
         segments,
         filter_doc_ids,
     )?;
-    let segment_serializer = SegmentSerializer::for_segment(merged_segment, true)?;
+    let segment_serializer = SegmentSerializer::for_segment(merged_segment.clone(), true)?;
     let num_docs = merger.write(segment_serializer)?;
 
-    let segment_meta = merged_index.new_segment_meta(merged_segment_id, num_docs);
+    let segment_meta = merged_segment
+        .with_max_doc(num_docs)
+        .with_computed_field_bounds()?
+        .meta()
+        .clone();
 
     let stats = format!(
         "Segments Merge: [{}]",
@@ -277,6 +297,7 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
         segments: vec![segment_meta],
         schema: target_schema,
         opstamp: 0u64,
+        schema_version: 0,
         payload: Some(stats),
     };
 
@@ -300,9 +321,12 @@ pub(crate) struct InnerSegmentUpdater {
     index: Index,
     segment_manager: SegmentManager,
     merge_policy: RwLock<Arc<dyn MergePolicy>>,
+    merge_listener: RwLock<Option<Arc<dyn MergeListener>>>,
+    indexing_observer: RwLock<Option<Arc<dyn IndexingObserver>>>,
     killed: AtomicBool,
     stamper: Stamper,
     merge_operations: MergeOperationInventory,
+    merge_limiter: Arc<MergeLimiter>,
 }
 
 impl SegmentUpdater {
@@ -311,7 +335,7 @@ impl SegmentUpdater {
         stamper: Stamper,
         delete_cursor: &DeleteCursor,
     ) -> crate::Result<SegmentUpdater> {
-        let segments = index.searchable_segment_metas()?;
+        let segments = index.load_metas()?.segments;
         let segment_manager = SegmentManager::from_segments(segments, delete_cursor);
         let pool = ThreadPoolBuilder::new()
             .name_prefix("segment_updater")
@@ -339,9 +363,12 @@ impl SegmentUpdater {
             index,
             segment_manager,
             merge_policy: RwLock::new(Arc::new(DefaultMergePolicy::default())),
+            merge_listener: RwLock::new(None),
+            indexing_observer: RwLock::new(None),
             killed: AtomicBool::new(false),
             stamper,
             merge_operations: Default::default(),
+            merge_limiter: Arc::default(),
         })))
     }
 
@@ -354,6 +381,34 @@ impl SegmentUpdater {
         *self.merge_policy.write().unwrap() = arc_merge_policy;
     }
 
+    pub fn get_merge_throttle(&self) -> MergeThrottle {
+        self.merge_limiter.throttle()
+    }
+
+    pub fn set_merge_throttle(&self, merge_throttle: MergeThrottle) {
+        self.merge_limiter.set_throttle(merge_throttle);
+    }
+
+    pub fn merge_throughput(&self) -> MergeThroughput {
+        self.merge_limiter.throughput()
+    }
+
+    pub fn get_merge_listener(&self) -> Option<Arc<dyn MergeListener>> {
+        self.merge_listener.read().unwrap().clone()
+    }
+
+    pub fn set_merge_listener(&self, merge_listener: Box<dyn MergeListener>) {
+        *self.merge_listener.write().unwrap() = Some(Arc::from(merge_listener));
+    }
+
+    pub fn get_indexing_observer(&self) -> Option<Arc<dyn IndexingObserver>> {
+        self.indexing_observer.read().unwrap().clone()
+    }
+
+    pub fn set_indexing_observer(&self, indexing_observer: Box<dyn IndexingObserver>) {
+        *self.indexing_observer.write().unwrap() = Some(Arc::from(indexing_observer));
+    }
+
     async fn schedule_task<
         T: 'static + Send,
         F: Future<Output = crate::Result<T>> + 'static + Send,
@@ -438,12 +493,13 @@ impl SegmentUpdater {
             // from the different drives.
             //
             // Segment 1 from disk 1, Segment 1 from disk 2, etc.
-            commited_segment_metas.sort_by_key(|segment_meta| -(segment_meta.max_doc() as i32));
+            sort_segment_metas(&mut commited_segment_metas);
             let index_meta = IndexMeta {
                 index_settings: index.settings().clone(),
                 segments: commited_segment_metas,
                 schema: index.schema(),
                 opstamp,
+                schema_version: index.schema_version(),
                 payload: commit_message,
             };
             // TODO add context to the error.
@@ -453,6 +509,32 @@ impl SegmentUpdater {
         Ok(())
     }
 
+    /// Publishes all of the segments, committed or not, in an in-memory `IndexMeta`
+    /// held by the index, without writing the meta.json file.
+    ///
+    /// The deletes are applied on copies of the segment entries: the segment manager
+    /// is left untouched, so that the next commit purges them again at its own opstamp.
+    fn publish_soft_commit(&self, segment_entries: Vec<SegmentEntry>, opstamp: Opstamp) {
+        if self.is_alive() {
+            let index = &self.index;
+            let mut segment_metas: Vec<SegmentMeta> = segment_entries
+                .iter()
+                .map(|segment_entry| segment_entry.meta().clone())
+                .collect();
+            sort_segment_metas(&mut segment_metas);
+            let index_meta = IndexMeta {
+                index_settings: index.settings().clone(),
+                segments: segment_metas,
+                schema: index.schema(),
+                opstamp,
+                schema_version: index.schema_version(),
+                // the payload is the one of the last durable commit.
+                payload: self.load_meta().payload.clone(),
+            };
+            index.set_soft_committed_meta(Some(index_meta));
+        }
+    }
+
     pub async fn schedule_garbage_collect(&self) -> crate::Result<GarbageCollectionResult> {
         let garbage_collect_future = garbage_collect_files(self.clone());
         self.schedule_task(garbage_collect_future).await
@@ -483,6 +565,8 @@ impl SegmentUpdater {
             let segment_entries = segment_updater.purge_deletes(opstamp)?;
             segment_updater.segment_manager.commit(segment_entries);
             segment_updater.save_metas(opstamp, payload)?;
+            // The soft committed segments are now part of the commit.
+            segment_updater.index.set_soft_committed_meta(None);
             let _ = garbage_collect_files(segment_updater.clone()).await;
             segment_updater.consider_merge_options().await;
             Ok(())
@@ -490,6 +574,16 @@ impl SegmentUpdater {
         .await
     }
 
+    pub(crate) async fn schedule_soft_commit(&self, opstamp: Opstamp) -> crate::Result<()> {
+        let segment_updater: SegmentUpdater = self.clone();
+        self.schedule_task(async move {
+            let segment_entries = segment_updater.purge_deletes(opstamp)?;
+            segment_updater.publish_soft_commit(segment_entries, opstamp);
+            Ok(())
+        })
+        .await
+    }
+
     fn store_meta(&self, index_meta: &IndexMeta) {
         *self.active_index_meta.write().unwrap() = Arc::new(index_meta.clone());
     }
@@ -530,6 +624,8 @@ impl SegmentUpdater {
         );
 
         let segment_updater = self.clone();
+        let merge_listener_opt = self.get_merge_listener();
+        let merge_limiter = Arc::clone(&self.merge_limiter);
         let segment_entries: Vec<SegmentEntry> = self
             .segment_manager
             .start_merge(merge_operation.segment_ids())?;
@@ -544,11 +640,16 @@ impl SegmentUpdater {
             // Its lifetime is used to track how many merging thread are currently running,
             // as well as which segment is currently in merge and therefore should not be
             // candidate for another merge.
-            match merge(
+            let merge_slot = merge_limiter.acquire_slot();
+            let merge_result = merge(
                 &segment_updater.index,
                 segment_entries,
                 merge_operation.target_opstamp(),
-            ) {
+                merge_listener_opt,
+                merge_limiter,
+            );
+            drop(merge_slot);
+            match merge_result {
                 Ok(after_merge_segment_entry) => {
                     let segment_meta = segment_updater
                         .end_merge(merge_operation, after_merge_segment_entry)
@@ -580,6 +681,35 @@ impl SegmentUpdater {
             .get_mergeable_segments(&merge_segment_ids)
     }
 
+    /// Returns the merges the merge policy would start, without starting them.
+    pub(crate) fn plan_merges(&self) -> crate::Result<Vec<MergePlan>> {
+        let (committed_segments, uncommitted_segments) = self.get_mergeable_segments();
+        let merge_policy = self.get_merge_policy();
+        let mut merge_plans = Vec::new();
+        for (segment_metas, committed) in
+            [(committed_segments, true), (uncommitted_segments, false)]
+        {
+            let segment_metas_by_id: HashMap<SegmentId, &SegmentMeta> = segment_metas
+                .iter()
+                .map(|segment_meta| (segment_meta.id(), segment_meta))
+                .collect();
+            for merge_candidate in merge_policy.compute_merge_candidates(&segment_metas) {
+                let merged_segment_metas: Vec<SegmentMeta> = merge_candidate
+                    .0
+                    .iter()
+                    .flat_map(|segment_id| segment_metas_by_id.get(segment_id))
+                    .map(|&segment_meta| segment_meta.clone())
+                    .collect();
+                merge_plans.push(MergePlan::new(
+                    &merged_segment_metas,
+                    committed,
+                    self.index.directory(),
+                )?);
+            }
+        }
+        Ok(merge_plans)
+    }
+
     async fn consider_merge_options(&self) {
         let (committed_segments, uncommitted_segments) = self.get_mergeable_segments();
 
@@ -700,12 +830,14 @@ mod tests {
     use crate::indexer::merge_policy::tests::MergeWheneverPossible;
     use crate::indexer::merger::IndexMerger;
     use crate::indexer::segment_updater::merge_filtered_segments;
+    use crate::indexer::{MergeThrottle, NoMergePolicy};
     use crate::query::QueryParser;
     use crate::schema::*;
     use crate::Directory;
     use crate::DocAddress;
     use crate::Index;
     use crate::Segment;
+    use futures::executor::block_on;
 
     #[test]
     fn test_delete_during_merge() -> crate::Result<()> {
@@ -845,6 +977,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_merge_throttle() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        let merge_throttle = MergeThrottle::default()
+            .with_max_mb_per_sec(100.0)
+            .with_max_concurrent_merges(1);
+        index_writer.set_merge_throttle(merge_throttle.clone());
+        assert_eq!(index_writer.get_merge_throttle(), merge_throttle);
+        for i in 0..3 {
+            for _ in 0..100 {
+                index_writer.add_document(doc!(text_field => format!("hello{}", i)))?;
+            }
+            index_writer.commit()?;
+        }
+        assert_eq!(index_writer.merge_throughput().num_bytes_written, 0);
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        let merge_throughput = index_writer.merge_throughput();
+        assert_eq!(merge_throughput.num_running_merges, 0);
+        assert_eq!(merge_throughput.num_waiting_merges, 0);
+        assert!(merge_throughput.num_bytes_written > 0);
+        let reader = index.reader()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 1);
+        assert_eq!(reader.searcher().num_docs(), 300);
+        Ok(())
+    }
+
     #[test]
     fn test_remove_all_segments() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
@@ -2,28 +2,42 @@ use super::{
     doc_id_mapping::{get_doc_id_mapping_from_field, DocIdMapping},
     operation::AddOperation,
 };
+use crate::core::Segment;
 use crate::fastfield::FastFieldsWriter;
+use crate::features::FeaturesWriter;
 use crate::fieldnorm::{FieldNormReaders, FieldNormsWriter};
-use crate::indexer::segment_serializer::SegmentSerializer;
-use crate::postings::compute_table_size;
+use crate::indexer::merger::MAX_DOC_LIMIT;
+use crate::indexer::segment_serializer::{raw_stored_fields, SegmentSerializer};
+use crate::indexer::SegmentWriterMemoryUsage;
 use crate::postings::MultiFieldPostingsWriter;
+use crate::postings::{compute_table_num_bits, compute_table_size};
+use crate::query::static_rank_field;
+use crate::schema::Document;
 use crate::schema::FieldType;
 use crate::schema::Schema;
 use crate::schema::Term;
 use crate::schema::Value;
+use crate::schema::DEFAULT_POSITION_GAP;
 use crate::schema::{Field, FieldEntry};
-use crate::store::StoreReader;
-use crate::tokenizer::{BoxTokenStream, PreTokenizedStream};
+use crate::store::StoreDictionary;
+use crate::tokenizer::{BoxTokenStream, PreTokenizedStream, ResolvedLanguageRouter};
 use crate::tokenizer::{FacetTokenizer, TextAnalyzer};
 use crate::tokenizer::{TokenStreamChain, Tokenizer};
+use crate::vector::VectorsWriter;
 use crate::Opstamp;
-use crate::{core::Segment, store::StoreWriter};
 use crate::{DocId, SegmentComponent};
 
-/// Computes the initial size of the hash table.
+/// Minimum number of bits of the initial size of the hash table, unless the memory
+/// budget is too small.
 ///
-/// Returns a number of bit `b`, such that the recommended initial table size is 2^b.
-fn initial_table_size(per_thread_memory_budget: usize) -> crate::Result<usize> {
+/// The table then doubles as terms get added, so that the segments with few terms
+/// do not allocate the table that the memory budget would allow.
+const INITIAL_TABLE_NUM_BITS: usize = 12;
+
+/// Computes the largest size of the hash table allowed by the memory budget.
+///
+/// Returns a number of bit `b`, such that the maximum initial table size is 2^b.
+fn max_table_size(per_thread_memory_budget: usize) -> crate::Result<usize> {
     let table_memory_upper_bound = per_thread_memory_budget / 3;
     if let Some(limit) = (10..)
         .take_while(|num_bits: &usize| compute_table_size(*num_bits) < table_memory_upper_bound)
@@ -36,6 +50,27 @@ fn initial_table_size(per_thread_memory_budget: usize) -> crate::Result<usize> {
     }
 }
 
+/// Computes the initial size of the hash table, given the number of terms
+/// of the previous segment of the indexing thread, if any.
+///
+/// The table starts with the size that the terms of the previous segment required,
+/// so that the thread does not go through the same resizes for every segment, and
+/// shrinks back when the previous segment had fewer terms. The tables of the flushed
+/// segments are freed, so a burst of terms does not hold memory for the segments after it.
+///
+/// Returns a number of bit `b`, such that the recommended initial table size is 2^b.
+fn initial_table_size(
+    per_thread_memory_budget: usize,
+    previous_num_terms: Option<usize>,
+) -> crate::Result<usize> {
+    let max_num_bits = max_table_size(per_thread_memory_budget)?;
+    let num_bits = previous_num_terms
+        .map(compute_table_num_bits)
+        .unwrap_or(0)
+        .max(INITIAL_TABLE_NUM_BITS);
+    Ok(num_bits.min(max_num_bits))
+}
+
 fn remap_doc_opstamps(
     opstamps: Vec<Opstamp>,
     doc_id_mapping_opt: Option<&DocIdMapping>,
@@ -61,8 +96,11 @@ pub struct SegmentWriter {
     pub(crate) segment_serializer: SegmentSerializer,
     pub(crate) fast_field_writers: FastFieldsWriter,
     pub(crate) fieldnorms_writer: FieldNormsWriter,
+    pub(crate) vectors_writer: VectorsWriter,
+    pub(crate) features_writer: FeaturesWriter,
     pub(crate) doc_opstamps: Vec<Opstamp>,
     tokenizers: Vec<Option<TextAnalyzer>>,
+    language_routers: Vec<Option<ResolvedLanguageRouter>>,
     term_buffer: Term,
 }
 
@@ -76,13 +114,15 @@ impl SegmentWriter {
     /// the flushing behavior as a buffer limit
     /// - segment: The segment being written
     /// - schema
+    /// - previous_num_terms: number of terms of the previous segment, sizing the term table
     pub fn for_segment(
         memory_budget: usize,
         segment: Segment,
         schema: &Schema,
+        previous_num_terms: Option<usize>,
     ) -> crate::Result<SegmentWriter> {
         let tokenizer_manager = segment.index().tokenizers().clone();
-        let table_num_bits = initial_table_size(memory_budget)?;
+        let table_num_bits = initial_table_size(memory_budget, previous_num_terms)?;
         let segment_serializer = SegmentSerializer::for_segment(segment, false)?;
         let multifield_postings = MultiFieldPostingsWriter::new(schema, table_num_bits);
         let tokenizers = schema
@@ -99,14 +139,31 @@ impl SegmentWriter {
                 },
             )
             .collect();
+        let language_routers = schema
+            .fields()
+            .map(
+                |(_, field_entry): (Field, &FieldEntry)| match field_entry.field_type() {
+                    FieldType::Str(ref text_options) => text_options
+                        .get_indexing_options()
+                        .and_then(|text_index_option| {
+                            tokenizer_manager.language_router(text_index_option.tokenizer())
+                        })
+                        .map(|language_router| language_router.resolve(&tokenizer_manager)),
+                    _ => None,
+                },
+            )
+            .collect();
         Ok(SegmentWriter {
             max_doc: 0,
             multifield_postings,
             fieldnorms_writer: FieldNormsWriter::for_schema(schema),
+            vectors_writer: VectorsWriter::for_schema(schema),
+            features_writer: FeaturesWriter::for_schema(schema),
             segment_serializer,
             fast_field_writers: FastFieldsWriter::from_schema(schema),
             doc_opstamps: Vec::with_capacity(1_000),
             tokenizers,
+            language_routers,
             term_buffer: Term::new(),
         })
     }
@@ -122,14 +179,15 @@ impl SegmentWriter {
             .segment()
             .index()
             .settings()
-            .sort_by_field
-            .clone()
+            .segment_sort_by_field()
             .map(|sort_by_field| get_doc_id_mapping_from_field(sort_by_field, &self))
             .transpose()?;
         remap_and_write(
             &self.multifield_postings,
             &self.fast_field_writers,
             &self.fieldnorms_writer,
+            &self.vectors_writer,
+            &self.features_writer,
             self.segment_serializer,
             mapping.as_ref(),
         )?;
@@ -137,13 +195,42 @@ impl SegmentWriter {
         Ok(doc_opstamps)
     }
 
+    /// Returns the memory used by the segment writer, in bytes,
+    /// as accounted for in the memory budget.
+    ///
+    /// See [`mem_usage_breakdown`](#method.mem_usage_breakdown) for the details.
     pub fn mem_usage(&self) -> usize {
         self.multifield_postings.mem_usage()
             + self.fieldnorms_writer.mem_usage()
             + self.fast_field_writers.mem_usage()
+            + self.vectors_writer.mem_usage()
+            + self.features_writer.mem_usage()
             + self.segment_serializer.mem_usage()
     }
 
+    /// Returns the number of distinct terms of the segment, over all of the fields.
+    pub(crate) fn num_terms(&self) -> usize {
+        self.multifield_postings.num_terms()
+    }
+
+    /// Returns the memory used by the segment writer, broken down by component.
+    ///
+    /// Computing the breakdown is slightly more expensive than
+    /// [`mem_usage`](#method.mem_usage), as it allocates the per field breakdown.
+    pub fn mem_usage_breakdown(&self) -> SegmentWriterMemoryUsage {
+        SegmentWriterMemoryUsage::new(
+            self.multifield_postings.postings_arena_mem_usage(),
+            self.multifield_postings.term_hashmap_mem_usage(),
+            self.multifield_postings.term_arena_mem_usage(),
+            self.multifield_postings.per_field_terms_mem_usage(),
+            self.fieldnorms_writer.mem_usage(),
+            self.fast_field_writers.mem_usage(),
+            self.vectors_writer.mem_usage(),
+            self.features_writer.mem_usage(),
+            self.segment_serializer.mem_usage(),
+        )
+    }
+
     /// Indexes a new document
     ///
     /// As a user, you should rather use `IndexWriter`'s add_document.
@@ -152,8 +239,16 @@ impl SegmentWriter {
         add_operation: AddOperation,
         schema: &Schema,
     ) -> crate::Result<()> {
+        if self.max_doc >= MAX_DOC_LIMIT - 1 {
+            return Err(crate::TantivyError::DocLimitExceeded(
+                u64::from(self.max_doc) + 1,
+            ));
+        }
         let doc_id = self.max_doc;
         let mut doc = add_operation.document;
+        let detected_languages = detect_languages(&self.language_routers, &mut doc, schema)?;
+        self.vectors_writer.add_document(doc_id, &doc)?;
+        self.features_writer.add_document(doc_id, &doc)?;
         self.doc_opstamps.push(add_operation.opstamp);
 
         self.fast_field_writers.add_document(&doc);
@@ -195,10 +290,16 @@ impl SegmentWriter {
                         }
                     }
                 }
-                FieldType::Str(_) => {
+                FieldType::Str(ref text_options) => {
                     let mut token_streams: Vec<BoxTokenStream> = vec![];
                     let mut offsets = vec![];
                     let mut total_offset = 0;
+                    let language_router = &self.language_routers[field.field_id() as usize];
+                    let default_analyzer = &self.tokenizers[field.field_id() as usize];
+                    let routed_analyzer = detected_languages
+                        .iter()
+                        .find(|(routed_field, _)| *routed_field == field)
+                        .and_then(|(_, language)| language_router.as_ref()?.analyzer(language));
 
                     for field_value in field_values {
                         match field_value.value() {
@@ -211,8 +312,8 @@ impl SegmentWriter {
                                     .push(PreTokenizedStream::from(tok_str.clone()).into());
                             }
                             Value::Str(ref text) => {
-                                if let Some(ref mut tokenizer) =
-                                    self.tokenizers[field.field_id() as usize]
+                                if let Some(tokenizer) =
+                                    routed_analyzer.or(default_analyzer.as_ref())
                                 {
                                     offsets.push(total_offset);
                                     total_offset += text.len();
@@ -226,7 +327,12 @@ impl SegmentWriter {
                     let num_tokens = if token_streams.is_empty() {
                         0
                     } else {
-                        let mut token_stream = TokenStreamChain::new(offsets, token_streams);
+                        let position_gap = text_options
+                            .get_indexing_options()
+                            .map(|indexing_options| indexing_options.position_gap())
+                            .unwrap_or(DEFAULT_POSITION_GAP);
+                        let mut token_stream = TokenStreamChain::new(offsets, token_streams)
+                            .with_position_gap(position_gap as usize);
                         multifield_postings.index_text(
                             doc_id,
                             field,
@@ -291,6 +397,9 @@ impl SegmentWriter {
                         self.multifield_postings.subscribe(doc_id, term_buffer);
                     }
                 }
+                FieldType::Vector(_) | FieldType::Features(_) => {
+                    // Vector and features fields are never indexed.
+                }
             }
         }
         doc.filter_fields(|field| schema.get_field_entry(field).is_stored());
@@ -322,6 +431,51 @@ impl SegmentWriter {
     }
 }
 
+/// Detects the language of the text fields analyzed by a `LanguageRouter`, and
+/// records it in the language field of the router, if any.
+///
+/// Returns the fields and their detected language.
+fn detect_languages(
+    language_routers: &[Option<ResolvedLanguageRouter>],
+    doc: &mut Document,
+    schema: &Schema,
+) -> crate::Result<Vec<(Field, String)>> {
+    let mut detected_languages = Vec::new();
+    for (field_id, language_router) in language_routers.iter().enumerate() {
+        let language_router = match language_router {
+            Some(language_router) => language_router.router(),
+            None => continue,
+        };
+        let field = Field::from_field_id(field_id as u32);
+        let texts: Vec<&str> = doc
+            .get_all(field)
+            .filter_map(|value| value.text())
+            .collect();
+        if texts.is_empty() {
+            continue;
+        }
+        let language = match language_router.detect(&texts.join("\n")) {
+            Some(language) => language,
+            None => continue,
+        };
+        if let Some(language_field) = language_router.language_field() {
+            let field_entry = schema.get_field_entry(language_field);
+            match field_entry.field_type() {
+                FieldType::Bytes(_) => doc.add_bytes(language_field, language.as_bytes()),
+                FieldType::Str(_) => doc.add_text(language_field, &language),
+                _ => {
+                    return Err(crate::TantivyError::SchemaError(format!(
+                        "The language field {:?} is not a bytes or text field.",
+                        field_entry.name()
+                    )));
+                }
+            }
+        }
+        detected_languages.push((field, language));
+    }
+    Ok(detected_languages)
+}
+
 /// This method is used as a trick to workaround the borrow checker
 /// Writes a view of a segment by pushing information
 /// to the `SegmentSerializer`.
@@ -331,6 +485,8 @@ fn remap_and_write(
     multifield_postings: &MultiFieldPostingsWriter,
     fast_field_writers: &FastFieldsWriter,
     fieldnorms_writer: &FieldNormsWriter,
+    vectors_writer: &VectorsWriter,
+    features_writer: &FeaturesWriter,
     mut serializer: SegmentSerializer,
     doc_id_map: Option<&DocIdMapping>,
 ) -> crate::Result<()> {
@@ -341,6 +497,24 @@ fn remap_and_write(
         .segment()
         .open_read(SegmentComponent::FieldNorms)?;
     let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
+    if let Some(static_rank_field_name) = serializer.segment().meta().static_rank_field() {
+        let static_rank_field =
+            static_rank_field(&serializer.segment().schema(), static_rank_field_name)?;
+        let static_rank_writer = fast_field_writers
+            .get_field_writer(static_rank_field)
+            .expect("the static rank field should be a single-valued fast field");
+        let static_ranks: Vec<u64> = static_rank_writer.iter().collect();
+        let static_ranks = match doc_id_map {
+            Some(doc_id_map) => doc_id_map
+                .iter_old_doc_ids()
+                .map(|old_doc_id| static_ranks[old_doc_id as usize])
+                .collect(),
+            None => static_ranks,
+        };
+        serializer
+            .get_postings_serializer()
+            .set_static_ranks(static_ranks);
+    }
     let term_ord_map = multifield_postings.serialize(
         serializer.get_postings_serializer(),
         fieldnorm_readers,
@@ -351,27 +525,49 @@ fn remap_and_write(
         &term_ord_map,
         doc_id_map,
     )?;
+    if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+        vectors_writer.serialize(vectors_serializer, doc_id_map)?;
+    }
+    if let Some(features_serializer) = serializer.extract_features_serializer() {
+        features_writer.serialize(features_serializer, doc_id_map)?;
+    }
 
     // finalize temp docstore and create version, which reflects the doc_id_map
-    if let Some(doc_id_map) = doc_id_map {
+    // and is compressed with a dictionary trained on the documents, if required.
+    if serializer.has_temp_store() {
         let store_write = serializer
             .segment_mut()
             .open_write(SegmentComponent::Store)?;
-        let compressor = serializer.segment().index().settings().docstore_compression;
-        let old_store_writer = std::mem::replace(
-            &mut serializer.store_writer,
-            StoreWriter::new(store_write, compressor),
-        );
+        let codec = serializer.segment().codec()?;
+        let settings = serializer.segment().index().settings();
+        let dictionary_size_opt = settings.docstore_dictionary_size_for_compressor();
+        let mut store_writer = codec.store_writer(store_write, settings);
+        store_writer.set_raw_fields(raw_stored_fields(&serializer.segment().schema()));
+        let old_store_writer = std::mem::replace(&mut serializer.store_writer, store_writer);
         old_store_writer.close()?;
-        let store_read = StoreReader::open(
+        let store_read = codec.open_store(
             serializer
                 .segment()
                 .open_read(SegmentComponent::TempStore)?,
         )?;
 
-        for old_doc_id in doc_id_map.iter_old_doc_ids() {
-            let doc_bytes = store_read.get_document_bytes(old_doc_id)?;
-            serializer.get_store_writer().store_bytes(&doc_bytes)?;
+        if let Some(dictionary_size) = dictionary_size_opt {
+            let num_docs = store_read.max_doc() as usize;
+            let dictionary_opt =
+                StoreDictionary::train(store_read.iter_raw(None), num_docs, dictionary_size)?;
+            if let Some(dictionary) = dictionary_opt {
+                serializer.get_store_writer().set_dictionary(dictionary);
+            }
+        }
+        if let Some(doc_id_map) = doc_id_map {
+            for old_doc_id in doc_id_map.iter_old_doc_ids() {
+                let doc_bytes = store_read.get_document_bytes(old_doc_id)?;
+                serializer.get_store_writer().store_bytes(&doc_bytes)?;
+            }
+        } else {
+            for doc_bytes_res in store_read.iter_raw(None) {
+                serializer.get_store_writer().store_bytes(&doc_bytes_res?)?;
+            }
         }
     }
 
@@ -382,13 +578,72 @@ fn remap_and_write(
 
 #[cfg(test)]
 mod tests {
-    use super::initial_table_size;
+    use super::{initial_table_size, max_table_size, SegmentWriter};
+    use crate::indexer::operation::AddOperation;
+    use crate::schema::{Schema, STORED, STRING, TEXT};
+    use crate::Index;
 
     #[test]
     fn test_hashmap_size() {
-        assert_eq!(initial_table_size(100_000).unwrap(), 11);
-        assert_eq!(initial_table_size(1_000_000).unwrap(), 14);
-        assert_eq!(initial_table_size(10_000_000).unwrap(), 17);
-        assert_eq!(initial_table_size(1_000_000_000).unwrap(), 19);
+        assert_eq!(max_table_size(100_000).unwrap(), 11);
+        assert_eq!(max_table_size(1_000_000).unwrap(), 14);
+        assert_eq!(max_table_size(10_000_000).unwrap(), 18);
+        assert_eq!(max_table_size(1_000_000_000).unwrap(), 19);
+        assert!(max_table_size(10_000).is_err());
+    }
+
+    #[test]
+    fn test_initial_hashmap_size() {
+        // Without a previous segment, the table starts small.
+        assert_eq!(initial_table_size(100_000, None).unwrap(), 11);
+        assert_eq!(initial_table_size(1_000_000_000, None).unwrap(), 12);
+        // The table is sized for the terms of the previous segment,
+        // within the memory budget...
+        assert_eq!(initial_table_size(1_000_000_000, Some(5_000)).unwrap(), 14);
+        assert_eq!(
+            initial_table_size(1_000_000_000, Some(100_000)).unwrap(),
+            19
+        );
+        assert_eq!(initial_table_size(10_000_000, Some(100_000)).unwrap(), 18);
+        // ... and shrinks back when the previous segment had few terms.
+        assert_eq!(initial_table_size(1_000_000_000, Some(10)).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_segment_writer_mem_usage_breakdown() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let id = schema_builder.add_text_field("id", STRING | STORED);
+        let unused = schema_builder.add_text_field("unused", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut segment_writer =
+            SegmentWriter::for_segment(10_000_000, index.new_segment(), &schema, None)?;
+        for i in 0..100u64 {
+            let document = doc!(text => "hello happy tax payer", id => format!("id{}", i));
+            segment_writer.add_document(
+                AddOperation {
+                    opstamp: i,
+                    document,
+                },
+                &schema,
+            )?;
+        }
+        let mem_usage = segment_writer.mem_usage_breakdown();
+        assert_eq!(
+            mem_usage.total(),
+            segment_writer.mem_usage() + mem_usage.term_arena()
+        );
+        assert!(mem_usage.postings_arena() > 0);
+        assert!(mem_usage.term_hashmap_table() > 0);
+        assert!(mem_usage.term_arena() > 0);
+        assert!(mem_usage.fieldnorms() >= 200);
+        let per_field_terms = mem_usage.per_field_terms();
+        assert_eq!(per_field_terms.len(), 2);
+        assert_eq!(per_field_terms[&text].num_terms(), 4);
+        assert_eq!(per_field_terms[&id].num_terms(), 100);
+        assert!(per_field_terms[&id].term_bytes() > per_field_terms[&text].term_bytes());
+        assert!(!per_field_terms.contains_key(&unused));
+        Ok(())
     }
 }
@@ -20,11 +20,6 @@ mod atomic_impl {
         pub fn fetch_add(&self, val: u64, order: Ordering) -> u64 {
             self.0.fetch_add(val as u64, order) as u64
         }
-
-        pub fn revert(&self, val: u64, order: Ordering) -> u64 {
-            self.0.store(val, order);
-            val
-        }
     }
 }
 
@@ -50,12 +45,6 @@ mod atomic_impl {
             *lock = previous_val + incr;
             previous_val
         }
-
-        pub fn revert(&self, val: u64, _order: Ordering) -> u64 {
-            let mut lock = self.0.write().unwrap();
-            *lock = val;
-            val
-        }
     }
 }
 
@@ -86,11 +75,6 @@ impl Stamper {
             end: start + n,
         }
     }
-
-    /// Reverts the stamper to a given `Opstamp` value and returns it
-    pub fn revert(&self, to_opstamp: Opstamp) -> Opstamp {
-        self.0.revert(to_opstamp, Ordering::SeqCst)
-    }
 }
 
 #[cfg(test)]
@@ -112,18 +96,4 @@ mod test {
         assert_eq!(stamper.stamps(3u64), (12..15));
         assert_eq!(stamper.stamp(), 15u64);
     }
-
-    #[test]
-    fn test_stamper_revert() {
-        let stamper = Stamper::new(7u64);
-        assert_eq!(stamper.stamp(), 7u64);
-        assert_eq!(stamper.stamp(), 8u64);
-
-        let stamper_clone = stamper.clone();
-        assert_eq!(stamper_clone.stamp(), 9u64);
-
-        stamper.revert(6);
-        assert_eq!(stamper.stamp(), 6);
-        assert_eq!(stamper_clone.stamp(), 7);
-    }
 }
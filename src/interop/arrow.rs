@@ -0,0 +1,403 @@
+//! Export of the columns of a segment as Apache Arrow record batches.
+
+use crate::fastfield::{FastFieldReader, FastValue, MultiValuedFastFieldReader};
+use crate::schema::{Cardinality, Facet, Field, FieldEntry, FieldType, Value};
+use crate::{DocId, Searcher, SegmentReader, TantivyError};
+use arrow_array::builder::{
+    ArrayBuilder, BinaryBuilder, Float32Builder, Float64Builder, Int64Builder, ListBuilder,
+    MapBuilder, PrimitiveBuilder, StringBuilder, TimestampSecondBuilder, UInt64Builder,
+};
+use arrow_array::types::ArrowPrimitiveType;
+use arrow_array::{ArrayRef, RecordBatch};
+use std::sync::Arc;
+
+const UTC: &str = "UTC";
+
+/// Exports columns of a segment as an Arrow `RecordBatch`.
+///
+/// The record batch has one row per alive document of the segment, in `DocId` order,
+/// and one column per field of `fields`, named after the field.
+///
+/// Fast fields are read from their columns:
+/// - single-valued `u64`, `i64`, `f64` and date fast fields are exported as `UInt64`,
+///   `Int64`, `Float64` and `Timestamp(Second, "UTC")` arrays,
+/// - multi-valued fast fields as `List` arrays of these types,
+/// - bytes fast fields as `Binary` arrays,
+/// - facet fields as `List` arrays of `Utf8` facet paths.
+///
+/// Vector fields are read from the vectors of the segment, as `List` arrays of `Float32`,
+/// null for the documents without a vector.
+///
+/// Other fields must be stored, and are read from the doc store, which is much slower.
+/// Their first value is exported (`Utf8` for text fields), or null if the document
+/// does not have any.
+///
+/// Returns a `SchemaError` if a field is neither fast nor stored.
+pub fn segment_to_record_batch(
+    segment_reader: &SegmentReader,
+    fields: &[Field],
+) -> crate::Result<RecordBatch> {
+    let schema = segment_reader.schema();
+    let alive_docs: Vec<DocId> = segment_reader.doc_ids_alive().collect();
+    let mut stored_fields = Vec::new();
+    let mut columns: Vec<Option<ArrayRef>> = Vec::with_capacity(fields.len());
+    for &field in fields {
+        let field_entry = schema.get_field_entry(field);
+        let column = fast_field_column(segment_reader, field, field_entry, &alive_docs)?;
+        if column.is_none() {
+            if !field_entry.is_stored() {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} cannot be exported, as it is neither fast nor stored",
+                    field_entry.name()
+                )));
+            }
+            stored_fields.push(field);
+        }
+        columns.push(column);
+    }
+    if !stored_fields.is_empty() {
+        let mut stored_columns = stored_field_columns(segment_reader, &stored_fields, &alive_docs)?;
+        for (field, column) in fields.iter().zip(columns.iter_mut()) {
+            if column.is_none() {
+                *column = stored_columns.remove(field);
+            }
+        }
+    }
+    let columns = fields
+        .iter()
+        .zip(columns)
+        .map(|(&field, column)| {
+            let column = column.expect("All of the columns have been built");
+            (schema.get_field_name(field).to_string(), column)
+        })
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return Err(TantivyError::InvalidArgument(
+            "At least one field must be exported".to_string(),
+        ));
+    }
+    RecordBatch::try_from_iter(columns)
+        .map_err(|arrow_err| TantivyError::InvalidArgument(arrow_err.to_string()))
+}
+
+/// Exports columns of all of the segments of a searcher, as one Arrow `RecordBatch`
+/// per segment, following the order of `Searcher::segment_readers`.
+///
+/// See [`segment_to_record_batch`](./fn.segment_to_record_batch.html).
+pub fn searcher_to_record_batches(
+    searcher: &Searcher,
+    fields: &[Field],
+) -> crate::Result<Vec<RecordBatch>> {
+    searcher
+        .segment_readers()
+        .iter()
+        .map(|segment_reader| segment_to_record_batch(segment_reader, fields))
+        .collect()
+}
+
+fn fast_field_column(
+    segment_reader: &SegmentReader,
+    field: Field,
+    field_entry: &FieldEntry,
+    alive_docs: &[DocId],
+) -> crate::Result<Option<ArrayRef>> {
+    let fast_fields = segment_reader.fast_fields();
+    let column: ArrayRef = match field_entry.field_type() {
+        FieldType::U64(options)
+        | FieldType::I64(options)
+        | FieldType::F64(options)
+        | FieldType::Date(options) => match options.get_fastfield_cardinality() {
+            Some(Cardinality::SingleValue) => match field_entry.field_type() {
+                FieldType::U64(_) => {
+                    let reader = fast_fields.u64(field)?;
+                    single_value_column(alive_docs, UInt64Builder::new(), |doc| reader.get(doc))
+                }
+                FieldType::I64(_) => {
+                    let reader = fast_fields.i64(field)?;
+                    single_value_column(alive_docs, Int64Builder::new(), |doc| reader.get(doc))
+                }
+                FieldType::F64(_) => {
+                    let reader = fast_fields.f64(field)?;
+                    single_value_column(alive_docs, Float64Builder::new(), |doc| reader.get(doc))
+                }
+                _ => {
+                    let reader = fast_fields.date(field)?;
+                    single_value_column(
+                        alive_docs,
+                        TimestampSecondBuilder::new().with_timezone(UTC),
+                        |doc| reader.get(doc).timestamp(),
+                    )
+                }
+            },
+            Some(Cardinality::MultiValues) => match field_entry.field_type() {
+                FieldType::U64(_) => multi_value_column(
+                    alive_docs,
+                    UInt64Builder::new(),
+                    &fast_fields.u64s(field)?,
+                    |val| val,
+                ),
+                FieldType::I64(_) => multi_value_column(
+                    alive_docs,
+                    Int64Builder::new(),
+                    &fast_fields.i64s(field)?,
+                    |val| val,
+                ),
+                FieldType::F64(_) => multi_value_column(
+                    alive_docs,
+                    Float64Builder::new(),
+                    &fast_fields.f64s(field)?,
+                    |val| val,
+                ),
+                _ => multi_value_column(
+                    alive_docs,
+                    TimestampSecondBuilder::new().with_timezone(UTC),
+                    &fast_fields.dates(field)?,
+                    |val| val.timestamp(),
+                ),
+            },
+            None => return Ok(None),
+        },
+        FieldType::Bytes(options) if options.is_fast() => {
+            let reader = fast_fields.bytes(field)?;
+            let mut builder = BinaryBuilder::new();
+            for &doc in alive_docs {
+                builder.append_value(reader.get_bytes(doc));
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::HierarchicalFacet(_) => {
+            let mut facet_reader = segment_reader.facet_reader(field)?;
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            let mut facet_ords = Vec::new();
+            let mut facet = Facet::root();
+            for &doc in alive_docs {
+                facet_reader.facet_ords(doc, &mut facet_ords);
+                for &facet_ord in &facet_ords {
+                    facet_reader.facet_from_ord(facet_ord, &mut facet)?;
+                    builder.values().append_value(facet.to_path_string());
+                }
+                builder.append(true);
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::Vector(_) => {
+            let vector_reader = segment_reader.vector_reader(field)?;
+            let mut builder = ListBuilder::new(Float32Builder::new());
+            for &doc in alive_docs {
+                if let Some(vector) = vector_reader.vector(doc) {
+                    builder.values().append_slice(&vector);
+                    builder.append(true);
+                } else {
+                    builder.append(false);
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(column))
+}
+
+fn single_value_column<T: ArrowPrimitiveType>(
+    alive_docs: &[DocId],
+    mut builder: PrimitiveBuilder<T>,
+    get_val: impl Fn(DocId) -> T::Native,
+) -> ArrayRef {
+    for &doc in alive_docs {
+        builder.append_value(get_val(doc));
+    }
+    Arc::new(builder.finish())
+}
+
+fn multi_value_column<T: ArrowPrimitiveType, Item: FastValue>(
+    alive_docs: &[DocId],
+    values_builder: PrimitiveBuilder<T>,
+    reader: &MultiValuedFastFieldReader<Item>,
+    to_native: impl Fn(Item) -> T::Native,
+) -> ArrayRef {
+    let mut builder = ListBuilder::new(values_builder);
+    let mut vals = Vec::new();
+    for &doc in alive_docs {
+        reader.get_vals(doc, &mut vals);
+        for &val in &vals {
+            builder.values().append_value(to_native(val));
+        }
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+fn stored_field_columns(
+    segment_reader: &SegmentReader,
+    fields: &[Field],
+    alive_docs: &[DocId],
+) -> crate::Result<std::collections::HashMap<Field, ArrayRef>> {
+    let schema = segment_reader.schema();
+    let mut builders: Vec<Box<dyn ArrayBuilder>> = fields
+        .iter()
+        .map(|&field| -> Box<dyn ArrayBuilder> {
+            match schema.get_field_entry(field).field_type() {
+                FieldType::U64(_) => Box::new(UInt64Builder::new()),
+                FieldType::I64(_) => Box::new(Int64Builder::new()),
+                FieldType::F64(_) => Box::new(Float64Builder::new()),
+                FieldType::Date(_) => Box::new(TimestampSecondBuilder::new().with_timezone(UTC)),
+                FieldType::Bytes(_) => Box::new(BinaryBuilder::new()),
+                FieldType::Str(_) | FieldType::HierarchicalFacet(_) => {
+                    Box::new(StringBuilder::new())
+                }
+                FieldType::Vector(_) => {
+                    unreachable!("Vector fields are exported from the vectors of the segment")
+                }
+                FieldType::Features(_) => Box::new(MapBuilder::new(
+                    None,
+                    StringBuilder::new(),
+                    Float32Builder::new(),
+                )),
+            }
+        })
+        .collect();
+    let store_reader = segment_reader.get_store_reader()?;
+    for &doc in alive_docs {
+        let document = store_reader.get(doc)?;
+        for (&field, builder) in fields.iter().zip(builders.iter_mut()) {
+            append_stored_value(builder.as_any_mut(), document.get_first(field));
+        }
+    }
+    Ok(fields
+        .iter()
+        .cloned()
+        .zip(builders.iter_mut().map(|builder| builder.finish()))
+        .collect())
+}
+
+fn append_stored_value(builder: &mut dyn std::any::Any, value: Option<&Value>) {
+    if let Some(builder) = builder.downcast_mut::<UInt64Builder>() {
+        builder.append_option(value.and_then(Value::u64_value));
+    } else if let Some(builder) = builder.downcast_mut::<Int64Builder>() {
+        builder.append_option(value.and_then(Value::i64_value));
+    } else if let Some(builder) = builder.downcast_mut::<Float64Builder>() {
+        builder.append_option(value.and_then(Value::f64_value));
+    } else if let Some(builder) = builder.downcast_mut::<TimestampSecondBuilder>() {
+        builder.append_option(
+            value
+                .and_then(Value::date_value)
+                .map(|date| date.timestamp()),
+        );
+    } else if let Some(builder) = builder.downcast_mut::<BinaryBuilder>() {
+        builder.append_option(value.and_then(Value::bytes_value));
+    } else if let Some(builder) = builder.downcast_mut::<StringBuilder>() {
+        builder.append_option(value.and_then(|value| match value {
+            Value::Str(text) => Some(text.clone()),
+            Value::PreTokStr(pre_tokenized) => Some(pre_tokenized.text.clone()),
+            Value::Facet(facet) => Some(facet.to_path_string()),
+            _ => None,
+        }));
+    } else if let Some(builder) =
+        builder.downcast_mut::<MapBuilder<StringBuilder, Float32Builder>>()
+    {
+        let features = value.and_then(Value::features_value);
+        for (feature, weight) in features.unwrap_or_default() {
+            builder.keys().append_value(feature);
+            builder.values().append_value(*weight);
+        }
+        builder
+            .append(features.is_some())
+            .expect("The keys and values of the map have the same length");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{searcher_to_record_batches, segment_to_record_batch};
+    use crate::schema::{
+        Cardinality, Facet, FacetOptions, IntOptions, Schema, STORED, STRING, TEXT,
+    };
+    use crate::{Index, TantivyError, Term};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type, TimestampSecondType, UInt64Type};
+    use arrow_array::Array;
+
+    #[test]
+    fn test_segment_to_record_batch() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field(
+            "id",
+            IntOptions::default().set_fast(Cardinality::SingleValue),
+        );
+        let score = schema_builder.add_f64_field(
+            "score",
+            IntOptions::default().set_fast(Cardinality::SingleValue),
+        );
+        let date = schema_builder.add_date_field(
+            "date",
+            IntOptions::default().set_fast(Cardinality::SingleValue),
+        );
+        let tags = schema_builder.add_i64_field(
+            "tags",
+            IntOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let category = schema_builder.add_facet_field("category", FacetOptions::default());
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let key = schema_builder.add_text_field("key", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let epoch = crate::DateTime::from(std::time::UNIX_EPOCH);
+        for i in 0..4u64 {
+            let mut doc = doc!(
+                id => i,
+                score => i as f64 / 2.0,
+                date => epoch + chrono::Duration::seconds(i as i64),
+                category => Facet::from(if i % 2 == 0 { "/even" } else { "/odd" }),
+                key => format!("key{}", i)
+            );
+            for tag in 0..i as i64 {
+                doc.add_i64(tags, -tag);
+            }
+            if i != 2 {
+                doc.add_text(title, format!("title {}", i));
+            }
+            index_writer.add_document(doc)?;
+        }
+        index_writer.delete_term(Term::from_field_text(key, "key1"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+
+        let batch = segment_to_record_batch(
+            searcher.segment_reader(0),
+            &[id, score, date, tags, category, title],
+        )?;
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 6);
+        assert_eq!(batch.schema().field(0).name(), "id");
+        let ids = batch.column(0).as_primitive::<UInt64Type>();
+        assert_eq!(ids.values().to_vec(), vec![0, 2, 3]);
+        let scores = batch.column(1).as_primitive::<Float64Type>();
+        assert_eq!(scores.values().to_vec(), vec![0.0, 1.0, 1.5]);
+        let dates = batch.column(2).as_primitive::<TimestampSecondType>();
+        assert_eq!(dates.values().to_vec(), vec![0, 2, 3]);
+        let tags_column = batch.column(3).as_list::<i32>();
+        assert_eq!(
+            tags_column
+                .value(2)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![0, -1, -2]
+        );
+        assert!(tags_column.value(0).is_empty());
+        let categories = batch.column(4).as_list::<i32>();
+        assert_eq!(categories.value(1).as_string::<i32>().value(0), "/even");
+        let titles = batch.column(5).as_string::<i32>();
+        assert_eq!(titles.value(0), "title 0");
+        assert!(titles.is_null(1));
+        assert_eq!(titles.value(2), "title 3");
+
+        assert!(matches!(
+            segment_to_record_batch(searcher.segment_reader(0), &[key]),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert_eq!(searcher_to_record_batches(&searcher, &[id])?.len(), 1);
+        Ok(())
+    }
+}
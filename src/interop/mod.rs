@@ -0,0 +1,9 @@
+//! Interoperability with other data processing libraries.
+//!
+//! The [`arrow`](./arrow/index.html) module, enabled by the `arrow` feature, exports
+//! the columns of the segments of an index as Apache Arrow record batches, so that
+//! they can be scanned by analytics engines (DataFusion, Polars...) without going
+//! through an intermediary format such as JSON.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
@@ -143,10 +143,14 @@ mod indexer;
 pub mod error;
 pub mod tokenizer;
 
+pub mod bench;
 pub mod collector;
+pub mod debug;
 pub mod directory;
 pub mod fastfield;
+pub mod features;
 pub mod fieldnorm;
+pub mod interop;
 pub mod positions;
 pub mod postings;
 pub mod query;
@@ -154,27 +158,50 @@ pub mod schema;
 pub mod space_usage;
 pub mod store;
 pub mod termdict;
+pub mod vector;
 
 mod reader;
 
-pub use self::reader::{IndexReader, IndexReaderBuilder, ReloadPolicy};
+pub use self::reader::{IndexReader, IndexReaderBuilder, PinnedSearcher, ReloadPolicy, Warmer};
 mod snippet;
-pub use self::snippet::{Snippet, SnippetGenerator};
+pub use self::snippet::{
+    FragmentBoundary, FragmenterConfig, HighlightedFragment, MultiFieldSnippetGenerator, Snippet,
+    SnippetGenerator,
+};
+mod suggester;
+pub use self::suggester::{
+    CompletionSuggester, SpellingDictionary, SpellingSuggester, Suggestion,
+};
+mod percolator;
+pub use self::percolator::Percolator;
 
 mod docset;
 pub use self::docset::{DocSet, TERMINATED};
 pub use crate::core::{Executor, SegmentComponent};
+pub use crate::core::{HotCache, WarmupSpec};
+pub use crate::core::{
+    Codec, CodecRegistry, DefaultCodec, ExternalStoreCodec, SSTableCodec, DEFAULT_CODEC_NAME,
+    SSTABLE_CODEC_NAME,
+};
 pub use crate::core::{
-    Index, IndexBuilder, IndexMeta, IndexSettings, IndexSortByField, Order, Searcher, Segment,
-    SegmentId, SegmentMeta,
+    FieldBounds, FieldStatistics, Index, IndexBuilder, IndexMeta, IndexSettings, IndexSnapshot,
+    IndexSortByField, IndexStatistics, InvertedIndexStatistics, MultiSearcher, Order,
+    SearchPermit, SearchStatistics, Searcher, Segment, SegmentId, SegmentMeta,
+    SegmentSearchStatistics, SegmentStatistics, SkipReason, SkippedSegment, SnapshotManifest,
 };
-pub use crate::core::{InvertedIndexReader, SegmentReader};
+pub use crate::core::{GlobalOrdinals, InvertedIndexReader, SegmentReader};
 pub use crate::directory::Directory;
 pub use crate::indexer::demuxer::*;
 pub use crate::indexer::merge_filtered_segments;
 pub use crate::indexer::merge_indices;
 pub use crate::indexer::operation::UserOperation;
-pub use crate::indexer::{IndexWriter, PreparedCommit};
+pub use crate::indexer::{
+    AutoCommitPolicy, AutoCommitWriter, CommitListener, DeduplicatingWriter, DeduplicationPolicy,
+    FieldIndexingStatistics, FieldPostingsWriter, FieldTermsMemoryUsage, IndexWriter,
+    IndexingObserver, IngestLineError, IngestReport, InvertedIndexWriter, MergeListener,
+    MergePlan, MergeThrottle, MergeThroughput, OnDuplicate, PreparedCommit, SegmentDocIdMapping,
+    SegmentIndexingStatistics, SegmentWriterMemoryUsage,
+};
 pub use crate::postings::Postings;
 pub use crate::reader::LeasedItem;
 pub use crate::schema::{Document, Term};
@@ -185,8 +212,15 @@ use std::fmt;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-/// Index format version.
-const INDEX_FORMAT_VERSION: u32 = 4;
+/// Version of the on-disk format of the index, i.e. of the meta.json file
+/// and of the segment files.
+///
+/// It is bumped whenever a change makes the files unreadable by the previous
+/// versions of tantivy. The readers of the segment components, e.g.
+/// `TermDictionary::open`, `InvertedIndexReader::new`, `FastFieldReaders::open`
+/// or `StoreReader::open`, can be used by external tools to read the files of
+/// a segment without opening an `Index`. They expect the files to be in this format.
+pub const INDEX_FORMAT_VERSION: u32 = 6;
 
 /// Structure version for the index.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -197,6 +231,13 @@ pub struct Version {
     index_format_version: u32,
 }
 
+impl Version {
+    /// Returns the version of the on-disk format of the index.
+    pub fn index_format_version(&self) -> u32 {
+        self.index_format_version
+    }
+}
+
 impl fmt::Debug for Version {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.to_string())
@@ -297,15 +338,17 @@ pub struct DocAddress {
 #[cfg(test)]
 pub mod tests {
     use crate::collector::tests::TEST_COLLECTOR_WITH_SCORE;
+    use crate::collector::DocSetCollector;
     use crate::core::SegmentReader;
     use crate::docset::{DocSet, TERMINATED};
     use crate::fastfield::FastFieldReader;
-    use crate::query::BooleanQuery;
+    use crate::query::{BooleanQuery, TermQuery};
     use crate::schema::*;
     use crate::DocAddress;
     use crate::Index;
     use crate::Postings;
     use crate::ReloadPolicy;
+    use crate::TantivyError;
     use common::{BinarySerializable, FixedSize};
     use rand::distributions::Bernoulli;
     use rand::distributions::Uniform;
@@ -424,6 +467,330 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_corpus_statistics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field=>"a b c", title_field=>"a"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text_field=>"a"))?;
+        index_writer.add_document(doc!(text_field=>"a a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.total_num_tokens(text_field)?, 6);
+        assert_eq!(searcher.total_num_tokens(title_field)?, 1);
+        let term_a = Term::from_field_text(text_field, "a");
+        assert_eq!(searcher.doc_freq(&term_a)?, 3);
+        assert_eq!(searcher.total_term_freq(&term_a)?, 4);
+        let term_d = Term::from_field_text(text_field, "d");
+        assert_eq!(searcher.total_term_freq(&term_d)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_iter_docs() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field=>"a"))?;
+        index_writer.add_document(doc!(text_field=>"b"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text_field=>"c"))?;
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let mut texts: Vec<String> = searcher
+            .iter_docs()
+            .map(|doc_res| {
+                doc_res.map(|doc| {
+                    doc.get_first(text_field)
+                        .unwrap()
+                        .text()
+                        .unwrap()
+                        .to_string()
+                })
+            })
+            .collect::<crate::Result<_>>()?;
+        texts.sort();
+        assert_eq!(texts, vec!["b".to_string(), "c".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_doc_by_doc_address() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field=>"hello"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text_field=>"happy"))?;
+        index_writer.add_document(doc!(text_field=>"hello happy"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "happy"),
+            IndexRecordOption::Basic,
+        );
+        let doc_addresses = searcher.search(&query, &DocSetCollector)?;
+        assert_eq!(doc_addresses.len(), 2);
+        for doc_address in doc_addresses {
+            let doc = searcher.doc(doc_address)?;
+            let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+            assert_eq!(segment_reader.doc(doc_address.doc_id)?, doc);
+            let text = doc.get_first(text_field).unwrap().text().unwrap();
+            assert!(text.contains("happy"));
+        }
+        assert!(matches!(
+            searcher.doc(DocAddress::new(2, 0)),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(searcher.segment_reader(0).doc(10).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_with_statistics() -> crate::Result<()> {
+        use crate::collector::{Count, TopDocs};
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let text = if i % 3 == 0 { "hello happy" } else { "hello" };
+            index_writer.add_document(doc!(text_field => text))?;
+            if i % 50 == 49 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query = crate::query::QueryParser::for_index(&index, vec![text_field])
+            .parse_query("hello happy")?;
+        let collector = (Count, TopDocs::with_limit(5));
+        let expected_fruit = searcher.search(query.as_ref(), &collector)?;
+        let (fruit, statistics) = searcher.search_with_statistics(query.as_ref(), &collector)?;
+        assert_eq!(fruit, expected_fruit);
+        assert_eq!(statistics.segments.len(), 2);
+        for (segment_ord, segment_statistics) in statistics.segments.iter().enumerate() {
+            assert_eq!(segment_statistics.segment_ord, segment_ord as u32);
+            assert_eq!(
+                segment_statistics.segment_id,
+                searcher.segment_reader(segment_ord as u32).segment_id()
+            );
+            assert_eq!(
+                segment_statistics.scorer_setup_duration
+                    + segment_statistics.scoring_duration
+                    + segment_statistics.collection_duration,
+                segment_statistics.total_duration
+            );
+        }
+        assert!(
+            statistics.total_duration >= statistics.weight_duration + statistics.merge_duration
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_in_segment() -> crate::Result<()> {
+        use crate::collector::{Collector, Count, TopDocs};
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let text = if i % 3 == 0 { "hello happy" } else { "hello" };
+            index_writer.add_document(doc!(text_field => text))?;
+            if i % 40 == 39 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        let query = crate::query::QueryParser::for_index(&index, vec![text_field])
+            .parse_query("hello happy")?;
+        let collector = (Count, TopDocs::with_limit(5));
+        let weight = searcher.create_weight(query.as_ref(), collector.requires_scoring())?;
+        // The segments can be searched in any order.
+        let mut fruits = (0..searcher.segment_readers().len() as u32)
+            .rev()
+            .map(|segment_ord| searcher.search_in_segment(weight.as_ref(), &collector, segment_ord))
+            .collect::<crate::Result<Vec<_>>>()?;
+        assert_eq!(fruits.iter().map(|fruit| fruit.0).sum::<usize>(), 100);
+        // The top docs having the same score are ordered by segment.
+        fruits.reverse();
+        assert_eq!(
+            collector.merge_fruits(fruits)?,
+            searcher.search(query.as_ref(), &collector)?
+        );
+        assert!(matches!(
+            searcher.search_in_segment(weight.as_ref(), &collector, 3),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_count() -> crate::Result<()> {
+        use crate::collector::Count;
+        use crate::query::{AllQuery, Query, QueryParser};
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let text = if i % 3 == 0 { "hello happy" } else { "hello" };
+            index_writer.add_document(doc!(text_field => text))?;
+            if i % 40 == 39 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let queries: Vec<Box<dyn Query>> = vec![
+            Box::new(AllQuery),
+            query_parser.parse_query("happy")?,
+            query_parser.parse_query("hello happy")?,
+            query_parser.parse_query("+hello +happy")?,
+            query_parser.parse_query("hello -happy")?,
+            query_parser.parse_query("\"hello happy\"")?,
+            query_parser.parse_query("sad")?,
+        ];
+        let expected_counts = [100, 34, 100, 34, 66, 34, 0];
+        let searcher = index.reader()?.searcher();
+        for (query, &expected_count) in queries.iter().zip(expected_counts.iter()) {
+            assert_eq!(searcher.count(query.as_ref())?, expected_count);
+            assert_eq!(searcher.search(query.as_ref(), &Count)?, expected_count);
+        }
+        // The deleted documents are not counted.
+        index_writer.delete_term(Term::from_field_text(text_field, "happy"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let expected_counts = [66, 0, 66, 0, 66, 0, 0];
+        for (query, &expected_count) in queries.iter().zip(expected_counts.iter()) {
+            assert_eq!(searcher.count(query.as_ref())?, expected_count);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_search_grouped() -> crate::Result<()> {
+        use crate::collector::TopHitsCollector;
+        use crate::query::QueryParser;
+        use crate::schema::{FAST, INDEXED};
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let brand_field = schema_builder.add_i64_field("brand", INDEXED | FAST);
+        let price_field = schema_builder.add_i64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..60i64 {
+            // the more "hello" tokens, the higher the score.
+            let text = "hello ".repeat(1 + (i % 7) as usize) + "world";
+            if i % 10 == 9 {
+                index_writer.add_document(doc!(text_field => text))?;
+            } else {
+                index_writer.add_document(doc!(text_field => text, brand_field => i % 5 - 2))?;
+            }
+            if i % 20 == 19 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("hello")?;
+
+        // The expected groups are computed with a single pass collecting all of the groups.
+        let mut all_groups: Vec<_> = searcher
+            .search(&query, &TopHitsCollector::new(brand_field, 3))?
+            .into_iter()
+            .collect();
+        all_groups.sort_by(|(_, left), (_, right)| {
+            right[0]
+                .score
+                .partial_cmp(&left[0].score)
+                .unwrap()
+                .then_with(|| left[0].doc_address.cmp(&right[0].doc_address))
+        });
+        assert_eq!(all_groups.len(), 5);
+        let groups = searcher.search_grouped(&query, brand_field, 3, 3)?;
+        assert_eq!(groups, all_groups[..3].to_vec());
+        let groups = searcher.search_grouped(&query, brand_field, 10, 1)?;
+        assert_eq!(groups.len(), 5);
+        for ((group, hits), (expected_group, expected_hits)) in groups.iter().zip(&all_groups) {
+            assert_eq!(group, expected_group);
+            assert_eq!(hits[..], expected_hits[..1]);
+        }
+
+        // The group field needs to be indexed.
+        assert!(matches!(
+            searcher.search_grouped(&query, price_field, 3, 3),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_async() -> crate::Result<()> {
+        use crate::collector::{Count, TopDocs};
+        use futures::executor::block_on;
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..100 {
+            let text = if i % 3 == 0 { "hello happy" } else { "hello" };
+            index_writer.add_document(doc!(text_field => text))?;
+            if i % 40 == 39 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        let query = crate::query::QueryParser::for_index(&index, vec![text_field])
+            .parse_query("hello happy")?;
+        let collector = || (Count, TopDocs::with_limit(5));
+        let searcher = index.reader()?.searcher();
+        let expected_fruit = searcher.search(query.as_ref(), &collector())?;
+        let fruit = block_on(searcher.search_async(query.as_ref(), collector()))?;
+        assert_eq!(fruit, expected_fruit);
+        index.set_multithread_executor(2)?;
+        let searcher = index.reader()?.searcher();
+        let fruit = block_on(searcher.search_async(query.as_ref(), collector()))?;
+        assert_eq!(fruit, expected_fruit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_doc_partial() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", STORED);
+        let count_field = schema_builder.add_u64_field("count", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            title_field => "hello",
+            body_field => "a long body",
+            count_field => 3u64
+        ))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let doc_address = DocAddress::new(0, 0);
+        let doc = searcher.doc_partial(doc_address, &[count_field, title_field])?;
+        assert_eq!(doc, doc!(title_field => "hello", count_field => 3u64));
+        assert!(doc.get_first(body_field).is_none());
+        assert!(matches!(
+            searcher.doc_partial(DocAddress::new(1, 0), &[title_field]),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_fieldnorm_no_docs_with_field() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
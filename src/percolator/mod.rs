@@ -0,0 +1,252 @@
+/*!
+Reverse search: finding the stored queries matching a document.
+
+The `Percolator` keeps a set of registered queries, and returns the
+queries matching a given document. This is typically used for alerting,
+where a large number of saved searches are run against every new document.
+*/
+
+mod required_terms;
+
+use self::required_terms::required_terms;
+use crate::collector::Count;
+use crate::indexer::index_writer::HEAP_SIZE_MIN;
+use crate::query::Query;
+use crate::schema::{Document, Schema};
+use crate::tokenizer::TokenizerManager;
+use crate::{Index, ReloadPolicy, Searcher, Term};
+use std::collections::{BTreeSet, HashMap};
+
+struct RegisteredQuery {
+    query: Box<dyn Query>,
+    required_terms: Option<Vec<Term>>,
+}
+
+/// The `Percolator` finds which of a set of registered queries match a document.
+///
+/// The document is indexed in a temporary in-memory index, with the schema
+/// and the tokenizers of the index the percolator was created for, and the
+/// queries are run against it.
+///
+/// To avoid running every query, the terms that a document must contain to
+/// match a query are extracted when the query is registered, and only the
+/// queries sharing a term with the document are evaluated. The terms can be
+/// extracted from `TermQuery`, `PhraseQuery`, and `BooleanQuery` combining them.
+/// The other queries are evaluated against every document.
+///
+/// ```rust
+/// # use tantivy::query::QueryParser;
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, Index, Percolator};
+/// # fn main() -> tantivy::Result<()> {
+/// # let mut schema_builder = Schema::builder();
+/// # let title = schema_builder.add_text_field("title", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// let query_parser = QueryParser::for_index(&index, vec![title]);
+/// let mut percolator = Percolator::new(&index);
+/// percolator.register(1, query_parser.parse_query("diary")?);
+/// percolator.register(2, query_parser.parse_query("\"young girl\"")?);
+/// percolator.register(3, query_parser.parse_query("+diary +boy")?);
+/// let matching_queries =
+///     percolator.percolate(&doc!(title => "The Diary of a Young Girl"))?;
+/// assert_eq!(matching_queries, vec![1, 2]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Percolator {
+    schema: Schema,
+    tokenizers: TokenizerManager,
+    queries: HashMap<u64, RegisteredQuery>,
+    queries_by_term: HashMap<Term, Vec<u64>>,
+    unfiltered_queries: BTreeSet<u64>,
+}
+
+impl Percolator {
+    /// Creates a percolator for the documents of `index`.
+    pub fn new(index: &Index) -> Percolator {
+        Percolator {
+            schema: index.schema(),
+            tokenizers: index.tokenizers().clone(),
+            queries: HashMap::new(),
+            queries_by_term: HashMap::new(),
+            unfiltered_queries: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the number of registered queries.
+    pub fn num_queries(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Registers a query under `query_id`, replacing the query
+    /// previously registered under the same id, if any.
+    pub fn register(&mut self, query_id: u64, query: Box<dyn Query>) {
+        self.unregister(query_id);
+        let required_terms = required_terms(query.as_ref());
+        match &required_terms {
+            Some(terms) => {
+                for term in terms {
+                    self.queries_by_term
+                        .entry(term.clone())
+                        .or_default()
+                        .push(query_id);
+                }
+            }
+            None => {
+                self.unfiltered_queries.insert(query_id);
+            }
+        }
+        self.queries.insert(
+            query_id,
+            RegisteredQuery {
+                query,
+                required_terms,
+            },
+        );
+    }
+
+    /// Unregisters the query registered under `query_id`.
+    ///
+    /// Returns false if there was no such query.
+    pub fn unregister(&mut self, query_id: u64) -> bool {
+        let registered_query = match self.queries.remove(&query_id) {
+            Some(registered_query) => registered_query,
+            None => return false,
+        };
+        match registered_query.required_terms {
+            Some(terms) => {
+                for term in terms {
+                    if let Some(query_ids) = self.queries_by_term.get_mut(&term) {
+                        query_ids.retain(|&id| id != query_id);
+                        if query_ids.is_empty() {
+                            self.queries_by_term.remove(&term);
+                        }
+                    }
+                }
+            }
+            None => {
+                self.unfiltered_queries.remove(&query_id);
+            }
+        }
+        true
+    }
+
+    /// Returns the ids of the registered queries matching `doc`, in increasing order.
+    pub fn percolate(&self, doc: &Document) -> crate::Result<Vec<u64>> {
+        let searcher = self.index_document(doc)?;
+        let mut matching_query_ids = Vec::new();
+        for query_id in self.candidates(&searcher)? {
+            let query = &self.queries[&query_id].query;
+            if searcher.search(query.as_ref(), &Count)? > 0 {
+                matching_query_ids.push(query_id);
+            }
+        }
+        Ok(matching_query_ids)
+    }
+
+    /// Returns a searcher over an in-memory index containing only `doc`.
+    fn index_document(&self, doc: &Document) -> crate::Result<Searcher> {
+        let mut index = Index::create_in_ram(self.schema.clone());
+        index.set_tokenizers(self.tokenizers.clone());
+        let mut index_writer = index.writer_with_num_threads(1, HEAP_SIZE_MIN)?;
+        index_writer.add_document(doc.clone())?;
+        index_writer.commit()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher().clone();
+        Ok(searcher)
+    }
+
+    /// Returns the ids of the queries that may match the document of `searcher`:
+    /// the queries sharing a term with the document, and the unfiltered queries.
+    fn candidates(&self, searcher: &Searcher) -> crate::Result<BTreeSet<u64>> {
+        let mut candidates = self.unfiltered_queries.clone();
+        for segment_reader in searcher.segment_readers() {
+            for (field, field_entry) in self.schema.fields() {
+                if !field_entry.is_indexed() {
+                    continue;
+                }
+                let inverted_index = segment_reader.inverted_index(field)?;
+                let mut term_stream = inverted_index.terms().stream()?;
+                while term_stream.try_advance()? {
+                    let term = Term::from_field_bytes(field, term_stream.key());
+                    if let Some(query_ids) = self.queries_by_term.get(&term) {
+                        candidates.extend(query_ids.iter().copied());
+                    }
+                }
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Percolator;
+    use crate::query::{QueryParser, RangeQuery};
+    use crate::schema::{Schema, TextFieldIndexing, TextOptions, INDEXED, TEXT};
+    use crate::tokenizer::{LowerCaser, NgramTokenizer, TextAnalyzer};
+    use crate::Index;
+
+    #[test]
+    fn test_percolator() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let year = schema_builder.add_u64_field("year", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let mut percolator = Percolator::new(&index);
+        percolator.register(1, query_parser.parse_query("diary")?);
+        percolator.register(2, query_parser.parse_query("\"young girl\"")?);
+        percolator.register(3, query_parser.parse_query("+striped +boy")?);
+        percolator.register(4, query_parser.parse_query("boy OR girl")?);
+        percolator.register(5, query_parser.parse_query("diary -boy")?);
+        percolator.register(6, Box::new(RangeQuery::new_u64(year, 1940..1950)));
+        assert_eq!(percolator.num_queries(), 6);
+
+        let doc = doc!(title => "The Diary of a Young Girl", year => 1947u64);
+        let searcher = percolator.index_document(&doc)?;
+        let candidates: Vec<u64> = percolator.candidates(&searcher)?.into_iter().collect();
+        // Query 3 is filtered out, as the document does not contain "striped".
+        assert_eq!(candidates, vec![1, 2, 4, 5, 6]);
+        assert_eq!(percolator.percolate(&doc)?, vec![1, 2, 4, 5, 6]);
+
+        let doc = doc!(title => "The Boy in the Striped Pyjamas", year => 2006u64);
+        assert_eq!(percolator.percolate(&doc)?, vec![3, 4]);
+
+        assert!(percolator.unregister(4));
+        assert!(!percolator.unregister(4));
+        assert_eq!(percolator.num_queries(), 5);
+        assert_eq!(percolator.percolate(&doc)?, vec![3]);
+
+        percolator.register(1, query_parser.parse_query("striped")?);
+        assert_eq!(percolator.num_queries(), 5);
+        assert_eq!(percolator.percolate(&doc)?, vec![1, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_percolator_uses_index_tokenizers() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field(
+            "title",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("ngram")),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "ngram",
+            TextAnalyzer::from(NgramTokenizer::new(3, 3, false)).filter(LowerCaser),
+        );
+        let mut percolator = Percolator::new(&index);
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        percolator.register(1, query_parser.parse_query("iar")?);
+        assert_eq!(
+            percolator.percolate(&doc!(title => "The Diary of a Young Girl"))?,
+            vec![1]
+        );
+        Ok(())
+    }
+}
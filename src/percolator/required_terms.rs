@@ -0,0 +1,145 @@
+use crate::query::{BooleanQuery, Occur, PhraseQuery, Query, TermQuery};
+use crate::Term;
+
+/// Returns a set of terms such that a document can only match `query`
+/// if it contains at least one of them.
+///
+/// Returns `None` if no such set is known for `query`, in which case the
+/// query has to be evaluated against every document.
+pub(crate) fn required_terms(query: &dyn Query) -> Option<Vec<Term>> {
+    if let Some(term_query) = query.downcast_ref::<TermQuery>() {
+        return Some(vec![term_query.term().clone()]);
+    }
+    if let Some(phrase_query) = query.downcast_ref::<PhraseQuery>() {
+        // Any term of the phrase is required, the first one will do.
+        return phrase_query
+            .phrase_terms()
+            .into_iter()
+            .next()
+            .map(|term| vec![term]);
+    }
+    if let Some(boolean_query) = query.downcast_ref::<BooleanQuery>() {
+        return boolean_required_terms(boolean_query);
+    }
+    None
+}
+
+fn boolean_required_terms(boolean_query: &BooleanQuery) -> Option<Vec<Term>> {
+    let mut has_must_clause = false;
+    let mut must_terms: Option<Vec<Term>> = None;
+    let mut should_terms: Option<Vec<Term>> = Some(Vec::new());
+    let mut num_should_clauses = 0;
+    for (occur, subquery) in boolean_query.clauses() {
+        match occur {
+            Occur::Must => {
+                has_must_clause = true;
+                // All of the `Must` clauses are required, the most selective one is kept.
+                if let Some(terms) = required_terms(subquery.as_ref()) {
+                    let is_more_selective = must_terms
+                        .as_ref()
+                        .map(|must_terms| terms.len() < must_terms.len())
+                        .unwrap_or(true);
+                    if is_more_selective {
+                        must_terms = Some(terms);
+                    }
+                }
+            }
+            Occur::Should => {
+                num_should_clauses += 1;
+                should_terms = should_terms.and_then(|mut should_terms| {
+                    should_terms.extend(required_terms(subquery.as_ref())?);
+                    Some(should_terms)
+                });
+            }
+            Occur::MustNot => {}
+        }
+    }
+    if must_terms.is_some() {
+        return must_terms;
+    }
+    // `Should` clauses are only required if there is no `Must` clause,
+    // or if a minimum number of them has to match.
+    let should_is_required = if has_must_clause {
+        boolean_query
+            .minimum_should_match()
+            .map(|minimum_should_match| minimum_should_match.resolve(num_should_clauses) > 0)
+            .unwrap_or(false)
+    } else {
+        true
+    };
+    if num_should_clauses == 0 || !should_is_required {
+        return None;
+    }
+    should_terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::required_terms;
+    use crate::query::{
+        AllQuery, BooleanQuery, MinimumShouldMatch, Occur, PhraseQuery, Query, TermQuery,
+    };
+    use crate::schema::{Field, IndexRecordOption};
+    use crate::Term;
+
+    fn term(text: &str) -> Term {
+        Term::from_field_text(Field::from_field_id(0), text)
+    }
+
+    fn term_query(text: &str) -> Box<dyn Query> {
+        Box::new(TermQuery::new(term(text), IndexRecordOption::Basic))
+    }
+
+    #[test]
+    fn test_required_terms() {
+        assert_eq!(
+            required_terms(term_query("a").as_ref()),
+            Some(vec![term("a")])
+        );
+        let phrase_query = PhraseQuery::new(vec![term("a"), term("b")]);
+        assert_eq!(required_terms(&phrase_query), Some(vec![term("a")]));
+        assert_eq!(required_terms(&AllQuery), None);
+
+        let should_query = || {
+            BooleanQuery::new(vec![
+                (Occur::Should, term_query("a")),
+                (Occur::Should, term_query("b")),
+                (Occur::MustNot, term_query("c")),
+            ])
+        };
+        assert_eq!(
+            required_terms(&should_query()),
+            Some(vec![term("a"), term("b")])
+        );
+        let should_with_all_query = BooleanQuery::new(vec![
+            (Occur::Should, term_query("a")),
+            (Occur::Should, Box::new(AllQuery)),
+        ]);
+        assert_eq!(required_terms(&should_with_all_query), None);
+
+        let must_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery)),
+            (Occur::Must, Box::new(should_query())),
+            (Occur::Must, term_query("d")),
+            (Occur::Should, term_query("e")),
+        ]);
+        assert_eq!(required_terms(&must_query), Some(vec![term("d")]));
+
+        let optional_should_query = || {
+            BooleanQuery::new(vec![
+                (Occur::Must, Box::new(AllQuery)),
+                (Occur::Should, term_query("e")),
+            ])
+        };
+        assert_eq!(required_terms(&optional_should_query()), None);
+        let required_should_query =
+            optional_should_query().with_minimum_should_match(MinimumShouldMatch::Count(1));
+        assert_eq!(
+            required_terms(&required_should_query),
+            Some(vec![term("e")])
+        );
+
+        let must_not_query = BooleanQuery::new(vec![(Occur::MustNot, term_query("a"))]);
+        assert_eq!(required_terms(&must_not_query), None);
+    }
+}
@@ -24,9 +24,25 @@
 //! * *VIntPosDeltas* := *VIntPosDelta*^(*P* % 128).
 //!
 //! The skip widths encoded separately makes it easy and fast to rapidly skip over n positions.
+//!
+//! The positions of a document are located by their offset within the positions of the term,
+//! i.e. the sum of the term frequencies of the previous documents of the posting list.
+//! The skip list of the postings stores that sum for each block of documents
+//! (see `SkipReader::position_offset`), so that the postings can skip blocks of documents
+//! without decoding their term frequencies. The `PositionReader` then skips the position
+//! blocks preceding that offset by only reading their bit widths, and only decodes the blocks
+//! containing the positions of the documents that are actually read, e.g. by a phrase query.
+//!
+//! If the field records payloads, the positions of each term are preceded by their payloads:
+//! * *PayloadsAndPositions* := *NumBytes* *Payloads* *Positions*
+//! * *NumBytes* := number of bytes of *Payloads* encoded as a variable byte integer.
+//! * *Payloads* := *P* encoded as a variable byte integer, *NumBits*: u8, the bitpacked
+//!   end offsets of the *P* payloads, followed by the concatenated payload bytes.
+mod payloads;
 mod reader;
 mod serializer;
 
+pub use self::payloads::PayloadReader;
 pub use self::reader::PositionReader;
 pub use self::serializer::PositionSerializer;
 use bitpacking::{BitPacker, BitPacker4x};
@@ -164,6 +180,22 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_position_read_long_document() -> crate::Result<()> {
+        let position_deltas: Vec<u32> = (0..200_000).map(|i| i % 1_000).collect();
+        let positions_data = create_positions_data(&position_deltas[..])?;
+        let mut position_reader = PositionReader::open(positions_data)?;
+        let mut buf = vec![0u32; 150_000];
+        position_reader.read(1_000, &mut buf);
+        assert_eq!(&buf[..], &position_deltas[1_000..151_000]);
+        // The anchor moved along with the blocks read.
+        position_reader.read(151_000, &mut buf[..49_000]);
+        assert_eq!(&buf[..49_000], &position_deltas[151_000..]);
+        position_reader.read(300, &mut buf[..10]);
+        assert_eq!(&buf[..10], &position_deltas[300..310]);
+        Ok(())
+    }
+
     #[test]
     fn test_position_reread_anchor_different_than_block() -> crate::Result<()> {
         let positions_delta: Vec<u32> = (0..2_000_000).collect();
@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use crate::directory::OwnedBytes;
+use common::{BinarySerializable, VInt};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+/// Buffers the payloads of the positions of a term, and serializes them
+/// when the term is closed.
+#[derive(Default)]
+pub(crate) struct PayloadSerializer {
+    end_offsets: Vec<u64>,
+    payloads: Vec<u8>,
+}
+
+impl PayloadSerializer {
+    /// Records the payload of the next position of the term.
+    pub fn write_payload(&mut self, payload: &[u8]) {
+        self.payloads.extend_from_slice(payload);
+        self.end_offsets.push(self.payloads.len() as u64);
+    }
+
+    /// Writes the payloads of the current term, prefixed with their length in bytes,
+    /// and resets the serializer for the next term.
+    pub fn close_term<W: Write>(&mut self, wrt: &mut W) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        VInt(self.end_offsets.len() as u64).serialize(&mut buffer)?;
+        let num_bits = compute_num_bits(self.payloads.len() as u64);
+        num_bits.serialize(&mut buffer)?;
+        let mut bit_packer = BitPacker::new();
+        for &end_offset in &self.end_offsets {
+            bit_packer.write(end_offset, num_bits, &mut buffer)?;
+        }
+        bit_packer.close(&mut buffer)?;
+        buffer.extend_from_slice(&self.payloads);
+        VInt(buffer.len() as u64).serialize(wrt)?;
+        wrt.write_all(&buffer)?;
+        self.end_offsets.clear();
+        self.payloads.clear();
+        Ok(())
+    }
+}
+
+/// Gives random access to the payloads of the positions of a term.
+///
+/// Payloads are identified by the offset of their position,
+/// in the same way as in the `PositionReader`.
+#[derive(Clone)]
+pub struct PayloadReader {
+    num_payloads: u64,
+    bit_unpacker: BitUnpacker,
+    end_offsets: OwnedBytes,
+    payloads: OwnedBytes,
+}
+
+impl PayloadReader {
+    /// Reads the payloads written in front of the positions of a term,
+    /// and advances `positions_data` to the beginning of the positions.
+    pub fn open(positions_data: &mut OwnedBytes) -> io::Result<PayloadReader> {
+        let num_bytes = VInt::deserialize(positions_data)?.val() as usize;
+        let (mut payloads_data, positions) = positions_data.clone().split(num_bytes);
+        *positions_data = positions;
+        let num_payloads = VInt::deserialize(&mut payloads_data)?.val();
+        let num_bits = u8::deserialize(&mut payloads_data)?;
+        let end_offsets_num_bytes = (num_payloads * u64::from(num_bits) + 7) as usize / 8 + 7;
+        let (end_offsets, payloads) = payloads_data.split(end_offsets_num_bytes);
+        Ok(PayloadReader {
+            num_payloads,
+            bit_unpacker: BitUnpacker::new(num_bits),
+            end_offsets,
+            payloads,
+        })
+    }
+
+    /// Returns the payload of the position at the given offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is out of bounds.
+    pub fn payload(&self, offset: u64) -> &[u8] {
+        assert!(offset < self.num_payloads, "Payload offset out of bounds.");
+        let start = if offset == 0 {
+            0
+        } else {
+            self.bit_unpacker
+                .get(offset - 1, self.end_offsets.as_slice())
+        };
+        let end = self.bit_unpacker.get(offset, self.end_offsets.as_slice());
+        &self.payloads.as_slice()[start as usize..end as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PayloadReader, PayloadSerializer};
+    use crate::directory::OwnedBytes;
+
+    #[test]
+    fn test_payloads_serialization() -> crate::Result<()> {
+        let payloads: Vec<Vec<u8>> = (0..300u32)
+            .map(|i| (0..i % 7).map(|j| (i + j) as u8).collect())
+            .collect();
+        let mut payload_serializer = PayloadSerializer::default();
+        let mut buffer = Vec::new();
+        for payload in &payloads {
+            payload_serializer.write_payload(payload);
+        }
+        payload_serializer.close_term(&mut buffer)?;
+        payload_serializer.write_payload(b"hello");
+        payload_serializer.close_term(&mut buffer)?;
+        buffer.extend_from_slice(b"positions");
+
+        let mut data = OwnedBytes::new(buffer);
+        let payload_reader = PayloadReader::open(&mut data)?;
+        for (offset, payload) in payloads.iter().enumerate() {
+            assert_eq!(payload_reader.payload(offset as u64), &payload[..]);
+        }
+        let payload_reader = PayloadReader::open(&mut data)?;
+        assert_eq!(payload_reader.payload(0), b"hello");
+        assert_eq!(data.as_slice(), b"positions");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_payloads() -> crate::Result<()> {
+        let mut payload_serializer = PayloadSerializer::default();
+        let mut buffer = Vec::new();
+        payload_serializer.write_payload(b"");
+        payload_serializer.write_payload(b"");
+        payload_serializer.close_term(&mut buffer)?;
+        let payload_reader = PayloadReader::open(&mut OwnedBytes::new(buffer))?;
+        assert!(payload_reader.payload(0).is_empty());
+        assert!(payload_reader.payload(1).is_empty());
+        Ok(())
+    }
+}
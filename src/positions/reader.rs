@@ -127,8 +127,8 @@ impl PositionReader {
 
         // At this point, the block containing offset is loaded, and anchor has
         // been updated to point to it as well.
-        for i in 1.. {
-            // we copy the part from block i - 1 that is relevant.
+        loop {
+            // we copy the part of the loaded block that is relevant.
             let offset_in_block = (offset as usize) % COMPRESSION_BLOCK_SIZE;
             let remaining_in_block = COMPRESSION_BLOCK_SIZE - offset_in_block;
             if remaining_in_block >= output.len() {
@@ -140,9 +140,12 @@ impl PositionReader {
             output[..remaining_in_block]
                 .copy_from_slice(&self.block_decoder.output_array()[offset_in_block..]);
             output = &mut output[remaining_in_block..];
-            // we load block #i if necessary.
+            // we load the next block, moving the anchor along so that the positions
+            // of a long document are read in linear time.
+            // The loaded block is followed by another one, so it is bitpacked.
             offset += remaining_in_block as u64;
-            self.load_block(i);
+            self.advance_num_blocks(1);
+            self.load_block(0);
         }
     }
 }
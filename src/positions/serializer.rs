@@ -1,3 +1,4 @@
+use crate::positions::payloads::PayloadSerializer;
 use crate::positions::COMPRESSION_BLOCK_SIZE;
 use crate::postings::compression::BlockEncoder;
 use crate::postings::compression::VIntEncoder;
@@ -8,12 +9,16 @@ use std::io::{self, Write};
 /// of all of the terms of a given field.
 ///
 /// It is valid to call write_position_delta more than once per term.
+///
+/// If the field records payloads, the payloads of a term are written
+/// in front of its positions.
 pub struct PositionSerializer<W: io::Write> {
     block_encoder: BlockEncoder,
     positions_wrt: CountingWriter<W>,
     positions_buffer: Vec<u8>,
     block: Vec<u32>,
     bit_widths: Vec<u8>,
+    payload_serializer_opt: Option<PayloadSerializer>,
 }
 
 impl<W: io::Write> PositionSerializer<W> {
@@ -25,9 +30,20 @@ impl<W: io::Write> PositionSerializer<W> {
             positions_buffer: Vec::with_capacity(128_000),
             block: Vec::with_capacity(128),
             bit_widths: Vec::new(),
+            payload_serializer_opt: None,
         }
     }
 
+    /// Creates a new PositionSerializer writing the positions and their
+    /// payloads into the given positions_wrt.
+    ///
+    /// `write_payload` must then be called once per position.
+    pub fn with_payloads(positions_wrt: W) -> PositionSerializer<W> {
+        let mut position_serializer = PositionSerializer::new(positions_wrt);
+        position_serializer.payload_serializer_opt = Some(PayloadSerializer::default());
+        position_serializer
+    }
+
     /// Returns the number of bytes written in the positions write object
     /// at this point.
     /// When called before writing the positions of a term, this value is used as
@@ -55,6 +71,15 @@ impl<W: io::Write> PositionSerializer<W> {
         }
     }
 
+    /// Writes the payload of the next position.
+    ///
+    /// This is a no-op if the serializer was not created with `with_payloads`.
+    pub fn write_payload(&mut self, payload: &[u8]) {
+        if let Some(payload_serializer) = self.payload_serializer_opt.as_mut() {
+            payload_serializer.write_payload(payload);
+        }
+    }
+
     fn flush_block(&mut self) {
         // encode the positions in the block
         if self.block.is_empty() {
@@ -76,6 +101,9 @@ impl<W: io::Write> PositionSerializer<W> {
     /// Close the positions for the given term.
     pub fn close_term(&mut self) -> io::Result<()> {
         self.flush_block();
+        if let Some(payload_serializer) = self.payload_serializer_opt.as_mut() {
+            payload_serializer.close_term(&mut self.positions_wrt)?;
+        }
         VInt(self.bit_widths.len() as u64).serialize(&mut self.positions_wrt)?;
         self.positions_wrt.write_all(&self.bit_widths[..])?;
         self.positions_wrt.write_all(&self.positions_buffer)?;
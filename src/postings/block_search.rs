@@ -1,4 +1,5 @@
 use crate::postings::compression::COMPRESSION_BLOCK_SIZE;
+use std::cmp::Ordering;
 
 /// Search the first index containing an element greater or equal to
 /// the target.
@@ -33,9 +34,173 @@ pub fn branchless_binary_search(arr: &[u32; COMPRESSION_BLOCK_SIZE], target: u32
     start
 }
 
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use crate::postings::compression::COMPRESSION_BLOCK_SIZE;
+    use std::arch::x86_64::__m256i as DataType;
+    use std::arch::x86_64::_mm256_add_epi32 as op_add;
+    use std::arch::x86_64::_mm256_cmpgt_epi32 as op_gt;
+    use std::arch::x86_64::_mm256_loadu_si256 as op_load;
+    use std::arch::x86_64::_mm256_set1_epi32 as set1;
+    use std::arch::x86_64::_mm256_setzero_si256 as set0;
+    use std::arch::x86_64::_mm256_sub_epi32 as op_sub;
+    use std::arch::x86_64::{
+        _mm256_castsi256_si128, _mm256_cmpeq_epi32, _mm256_extracti128_si256, _mm256_movemask_epi8,
+        _mm_add_epi32, _mm_cvtsi128_si32, _mm_shuffle_epi32,
+    };
+
+    const MASK1: i32 = 78;
+    const MASK2: i32 = 177;
+
+    /// Performs an exhaustive linear search over the block.
+    ///
+    /// There is no early exit here. We simply count the
+    /// number of elements that are `< target`.
+    ///
+    /// Comparisons are signed, which is fine as doc ids (and `TERMINATED`)
+    /// are all lower or equal to `i32::MAX`.
+    ///
+    /// # Safety
+    ///
+    /// The caller has to make sure the CPU supports AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn linear_search_avx2_128(
+        arr: &[u32; COMPRESSION_BLOCK_SIZE],
+        target: u32,
+    ) -> usize {
+        let ptr = arr.as_ptr() as *const DataType;
+        let vkey = set1(target as i32);
+        let mut cnt = set0();
+        // We work over 4 `__m256i` at a time.
+        // A single `__m256i` actually contains 8 `u32`.
+        for i in 0..COMPRESSION_BLOCK_SIZE / (8 * 4) {
+            let cmp1 = op_gt(vkey, op_load(ptr.add(i * 4)));
+            let cmp2 = op_gt(vkey, op_load(ptr.add(i * 4 + 1)));
+            let cmp3 = op_gt(vkey, op_load(ptr.add(i * 4 + 2)));
+            let cmp4 = op_gt(vkey, op_load(ptr.add(i * 4 + 3)));
+            let sum = op_add(op_add(cmp1, cmp2), op_add(cmp3, cmp4));
+            cnt = op_sub(cnt, sum);
+        }
+        let mut cnt = _mm_add_epi32(
+            _mm256_castsi256_si128(cnt),
+            _mm256_extracti128_si256(cnt, 1),
+        );
+        cnt = _mm_add_epi32(cnt, _mm_shuffle_epi32(cnt, MASK1));
+        cnt = _mm_add_epi32(cnt, _mm_shuffle_epi32(cnt, MASK2));
+        _mm_cvtsi128_si32(cnt) as usize
+    }
+
+    /// Returns the first element contained in both sorted slices.
+    ///
+    /// Each element of `left` is compared at once to the 8 elements of `right`
+    /// that may contain it. When the element is beyond these 8 elements, `right` is
+    /// galloped to the next element greater or equal to it, and `left` is galloped
+    /// in the same way when `right` is ahead.
+    ///
+    /// # Safety
+    ///
+    /// The caller has to make sure the CPU supports AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn first_common_doc_avx2(left: &[u32], right: &[u32]) -> Option<u32> {
+        let (mut left_start, mut right_start) = (0, 0);
+        while left_start < left.len() {
+            // All of the elements of `right[..right_start]` are lower than `left[left_start]`.
+            if right_start + 8 > right.len() {
+                return super::first_common_doc_scalar(&left[left_start..], &right[right_start..]);
+            }
+            let doc = left[left_start];
+            if right[right_start + 7] < doc {
+                right_start = super::gallop(right, right_start + 8, doc);
+                continue;
+            }
+            if doc < right[right_start] {
+                left_start = super::gallop(left, left_start, right[right_start]);
+                continue;
+            }
+            let chunk = op_load(right.as_ptr().add(right_start) as *const DataType);
+            let cmp = _mm256_cmpeq_epi32(chunk, set1(doc as i32));
+            if _mm256_movemask_epi8(cmp) != 0 {
+                return Some(doc);
+            }
+            left_start += 1;
+        }
+        None
+    }
+}
+
+/// Search the first index containing an element greater or equal to
+/// the target.
+///
+/// This has the same semantics and assumptions as
+/// [`branchless_binary_search`], but uses a vectorized linear scan
+/// when the CPU supports it. The implementation is picked at runtime,
+/// and falls back to `branchless_binary_search` otherwise.
+#[inline]
+pub fn search_in_block(arr: &[u32; COMPRESSION_BLOCK_SIZE], target: u32) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safe as we checked that AVX2 is available.
+            return unsafe { avx2::linear_search_avx2_128(arr, target) };
+        }
+    }
+    branchless_binary_search(arr, target)
+}
+
+/// Returns the index of the first element of `docs[from..]` greater or equal to `target`,
+/// or `docs.len()` if there is none.
+///
+/// The elements are probed at exponentially growing distances from `from`, before a
+/// binary search within the last range, so that the cost is logarithmic in the distance
+/// between `from` and the result.
+fn gallop(docs: &[u32], from: usize, target: u32) -> usize {
+    let mut bound = 1;
+    while from + bound <= docs.len() && docs[from + bound - 1] < target {
+        bound *= 2;
+    }
+    // All of the elements of `docs[from..low]` are lower than `target`.
+    let low = from + bound / 2;
+    let high = (from + bound).min(docs.len());
+    low + docs[low..high].partition_point(|&doc| doc < target)
+}
+
+fn first_common_doc_scalar(left: &[u32], right: &[u32]) -> Option<u32> {
+    let (mut left_idx, mut right_idx) = (0, 0);
+    while left_idx < left.len() && right_idx < right.len() {
+        let (left_doc, right_doc) = (left[left_idx], right[right_idx]);
+        match left_doc.cmp(&right_doc) {
+            Ordering::Less => left_idx = gallop(left, left_idx, right_doc),
+            Ordering::Greater => right_idx = gallop(right, right_idx, left_doc),
+            Ordering::Equal => return Some(left_doc),
+        }
+    }
+    None
+}
+
+/// Returns the first element contained in both `left` and `right`,
+/// or `None` if they have no element in common.
+///
+/// Both slices are assumed sorted, e.g. blocks of doc ids already decoded.
+/// Both slices are galloped through, so that long runs of elements of one slice
+/// missing in the other are skipped in logarithmic time. A vectorized implementation
+/// is used when the CPU supports it, and the implementation is picked at runtime.
+#[inline]
+pub fn first_common_doc(left: &[u32], right: &[u32]) -> Option<u32> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safe as we checked that AVX2 is available.
+            return unsafe { avx2::first_common_doc_avx2(left, right) };
+        }
+    }
+    first_common_doc_scalar(left, right)
+}
+
 #[cfg(test)]
 mod tests {
     use super::branchless_binary_search;
+    use super::search_in_block;
+    use super::{first_common_doc, first_common_doc_scalar, gallop};
     use crate::docset::TERMINATED;
     use crate::postings::compression::COMPRESSION_BLOCK_SIZE;
     use proptest::prelude::*;
@@ -56,6 +221,14 @@ mod tests {
         let mut output_buffer = [TERMINATED; COMPRESSION_BLOCK_SIZE];
         output_buffer[..block.len()].copy_from_slice(block);
         assert_eq!(branchless_binary_search(&output_buffer, target), cursor);
+        assert_eq!(search_in_block(&output_buffer, target), cursor);
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                let res = unsafe { super::avx2::linear_search_avx2_128(&output_buffer, target) };
+                assert_eq!(res, cursor);
+            }
+        }
     }
 
     fn util_test_search_in_block_all(block: &[u32]) {
@@ -95,4 +268,55 @@ mod tests {
             util_test_search_in_block_all(&block[..]);
         }
     }
+
+    fn util_test_first_common_doc(left: &[u32], right: &[u32]) {
+        let expected = left.iter().cloned().find(|doc| right.contains(doc));
+        assert_eq!(first_common_doc(left, right), expected);
+        assert_eq!(first_common_doc_scalar(left, right), expected);
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                let res = unsafe { super::avx2::first_common_doc_avx2(left, right) };
+                assert_eq!(res, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_common_doc() {
+        let even: Vec<u32> = (0..100).map(|i| i * 2).collect();
+        let odd: Vec<u32> = (0..100).map(|i| i * 2 + 1).collect();
+        util_test_first_common_doc(&[], &even);
+        util_test_first_common_doc(&even, &[]);
+        util_test_first_common_doc(&even, &odd);
+        util_test_first_common_doc(&even, &[3, 5, 150]);
+        util_test_first_common_doc(&[197, 198], &even);
+        util_test_first_common_doc(&odd[50..], &(0..200).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_gallop() {
+        let docs: Vec<u32> = (0..100).map(|i| i * 2).collect();
+        for from in 0..docs.len() {
+            for target in 0..210 {
+                let expected = from + docs[from..].iter().take_while(|&&doc| doc < target).count();
+                assert_eq!(gallop(&docs, from, target), expected);
+            }
+        }
+        assert_eq!(gallop(&docs, docs.len(), 0), docs.len());
+        assert_eq!(gallop(&[], 0, 3), 0);
+    }
+
+    fn sorted_docs() -> impl Strategy<Value = Vec<u32>> {
+        prop::collection::btree_set(0u32..300u32, 0..COMPRESSION_BLOCK_SIZE)
+            .prop_map(|docs| docs.into_iter().collect())
+    }
+
+    proptest! {
+        #[test]
+        fn test_proptest_first_common_doc(left in sorted_docs(), right in sorted_docs()) {
+            util_test_first_common_doc(&left[..], &right[..]);
+            util_test_first_common_doc(&right[..], &left[..]);
+        }
+    }
 }
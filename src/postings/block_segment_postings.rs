@@ -1,9 +1,12 @@
 use std::io;
+use std::sync::Arc;
 
 use crate::directory::FileSlice;
 use crate::directory::OwnedBytes;
 use crate::fieldnorm::FieldNormReader;
-use crate::postings::compression::{BlockDecoder, VIntDecoder, COMPRESSION_BLOCK_SIZE};
+use crate::postings::compression::{
+    BlockDecoder, PostingsCodec, VIntDecoder, COMPRESSION_BLOCK_SIZE,
+};
 use crate::postings::{BlockInfo, FreqReadingOption, SkipReader};
 use crate::query::Bm25Weight;
 use crate::schema::IndexRecordOption;
@@ -85,6 +88,8 @@ impl BlockSegmentPostings {
         data: FileSlice,
         record_option: IndexRecordOption,
         requested_option: IndexRecordOption,
+        has_static_ranks: bool,
+        postings_codec: Arc<dyn PostingsCodec>,
     ) -> io::Result<BlockSegmentPostings> {
         let freq_reading_option = match (record_option, requested_option) {
             (IndexRecordOption::Basic, _) => FreqReadingOption::NoFreq,
@@ -94,15 +99,14 @@ impl BlockSegmentPostings {
 
         let (skip_data_opt, postings_data) =
             split_into_skips_and_postings(doc_freq, data.read_bytes()?);
-        let skip_reader = match skip_data_opt {
-            Some(skip_data) => SkipReader::new(skip_data, doc_freq, record_option),
-            None => SkipReader::new(OwnedBytes::empty(), doc_freq, record_option),
-        };
+        let skip_data = skip_data_opt.unwrap_or_else(OwnedBytes::empty);
+        let skip_reader = SkipReader::new(skip_data, doc_freq, record_option, has_static_ranks)
+            .with_postings_codec(postings_codec.clone());
 
         let mut block_segment_postings = BlockSegmentPostings {
-            doc_decoder: BlockDecoder::with_val(TERMINATED),
+            doc_decoder: BlockDecoder::with_codec_and_val(postings_codec.clone(), TERMINATED),
             loaded_offset: std::usize::MAX,
-            freq_decoder: BlockDecoder::with_val(1),
+            freq_decoder: BlockDecoder::with_codec_and_val(postings_codec, 1),
             freq_reading_option,
             block_max_score_cache: None,
             doc_freq,
@@ -152,6 +156,12 @@ impl BlockSegmentPostings {
         bm25_weight.max_score()
     }
 
+    /// Returns the maximum static rank of the documents of the current block, if the
+    /// segment records it. Like `block_max_score`, it does not require the block to be loaded.
+    pub(crate) fn block_max_static_rank(&self) -> Option<u64> {
+        self.skip_reader.block_max_static_rank()
+    }
+
     pub(crate) fn freq_reading_option(&self) -> FreqReadingOption {
         self.freq_reading_option
     }
@@ -166,8 +176,16 @@ impl BlockSegmentPostings {
     // # Warning
     //
     // This does not reset the positions list.
-    pub(crate) fn reset(&mut self, doc_freq: u32, postings_data: OwnedBytes) {
+    pub(crate) fn reset(
+        &mut self,
+        doc_freq: u32,
+        postings_data: OwnedBytes,
+        postings_codec: &Arc<dyn PostingsCodec>,
+    ) {
         let (skip_data_opt, postings_data) = split_into_skips_and_postings(doc_freq, postings_data);
+        self.doc_decoder.set_codec(postings_codec.clone());
+        self.freq_decoder.set_codec(postings_codec.clone());
+        self.skip_reader.set_postings_codec(postings_codec.clone());
         self.data = postings_data;
         self.block_max_score_cache = None;
         self.loaded_offset = std::usize::MAX;
@@ -339,7 +357,7 @@ impl BlockSegmentPostings {
             block_max_score_cache: None,
             doc_freq: 0,
             data: OwnedBytes::empty(),
-            skip_reader: SkipReader::new(OwnedBytes::empty(), 0, IndexRecordOption::Basic),
+            skip_reader: SkipReader::new(OwnedBytes::empty(), 0, IndexRecordOption::Basic, false),
         }
     }
 }
@@ -1,8 +1,25 @@
+//! Compression of the doc ids and term frequencies of posting lists.
+//!
+//! Posting lists are encoded by blocks of `COMPRESSION_BLOCK_SIZE` documents:
+//! - doc ids are delta-encoded and bitpacked (`BitPacker4x`, SIMD accelerated when available),
+//! - term frequencies are bitpacked as is,
+//! - the last, incomplete block is encoded using variable-length integers (see `VIntEncoder`).
+//!
+//! The number of bits of each block is recorded in the skip list.
+//!
+//! The encoding of the full blocks of doc ids and term frequencies is defined
+//! by a [`PostingsCodec`], the one of the `Codec` of the segment.
+//! `BitpackedPostingsCodec` is the default.
+
 use bitpacking::{BitPacker, BitPacker4x};
 use common::FixedSize;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
 
+/// Number of documents of the full blocks of a posting list.
 pub const COMPRESSION_BLOCK_SIZE: usize = BitPacker4x::BLOCK_LEN;
-const COMPRESSED_BLOCK_MAX_SIZE: usize = COMPRESSION_BLOCK_SIZE * u32::SIZE_IN_BYTES;
+/// Maximum size in bytes of an encoded block.
+pub const COMPRESSED_BLOCK_MAX_SIZE: usize = COMPRESSION_BLOCK_SIZE * u32::SIZE_IN_BYTES;
 
 mod vint;
 
@@ -11,8 +28,135 @@ pub fn compressed_block_size(num_bits: u8) -> usize {
     (num_bits as usize) * COMPRESSION_BLOCK_SIZE / 8
 }
 
-pub struct BlockEncoder {
+/// A `PostingsCodec` defines the encoding of the full blocks of
+/// `COMPRESSION_BLOCK_SIZE` doc ids and term frequencies of the posting lists.
+///
+/// Each block is encoded along with a number of bits, recorded in the skip list of
+/// the posting list, that is given back to decode it and to compute its size with
+/// `compressed_block_size`. The last, incomplete block of a posting list is always
+/// encoded using variable-length integers.
+///
+/// The postings codec is chosen by the [`Codec`](crate::Codec) of the segment, which
+/// is recorded in its `SegmentMeta`: the segments written with a former postings
+/// codec stay readable as long as their `Codec` is registered.
+pub trait PostingsCodec: Send + Sync + 'static {
+    /// Encodes a block of sorted doc ids into `output`, given the value `offset`
+    /// of the last doc id of the previous block.
+    ///
+    /// Returns the number of bits of the block and the number of bytes written.
+    fn compress_block_sorted(
+        &self,
+        block: &[u32],
+        offset: u32,
+        output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+    ) -> (u8, usize);
+
+    /// Encodes a block of term frequencies into `output`.
+    ///
+    /// Returns the number of bits of the block and the number of bytes written.
+    fn compress_block_unsorted(
+        &self,
+        block: &[u32],
+        output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+    ) -> (u8, usize);
+
+    /// Decodes a block of sorted doc ids encoded by `compress_block_sorted`.
+    ///
+    /// Returns the number of bytes read.
+    fn uncompress_block_sorted(
+        &self,
+        compressed_data: &[u8],
+        offset: u32,
+        num_bits: u8,
+        output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+    ) -> usize;
+
+    /// Decodes a block of term frequencies encoded by `compress_block_unsorted`.
+    ///
+    /// Returns the number of bytes read.
+    fn uncompress_block_unsorted(
+        &self,
+        compressed_data: &[u8],
+        num_bits: u8,
+        output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+    ) -> usize;
+
+    /// Returns the size in bytes of an encoded block, given its number of bits.
+    fn compressed_block_size(&self, num_bits: u8) -> usize {
+        compressed_block_size(num_bits)
+    }
+}
+
+/// The default `PostingsCodec`: doc ids are delta-encoded and bitpacked,
+/// term frequencies are bitpacked as is, using `BitPacker4x`.
+#[derive(Clone, Copy)]
+pub struct BitpackedPostingsCodec {
     bitpacker: BitPacker4x,
+}
+
+impl Default for BitpackedPostingsCodec {
+    fn default() -> Self {
+        BitpackedPostingsCodec {
+            bitpacker: BitPacker4x::new(),
+        }
+    }
+}
+
+impl PostingsCodec for BitpackedPostingsCodec {
+    fn compress_block_sorted(
+        &self,
+        block: &[u32],
+        offset: u32,
+        output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+    ) -> (u8, usize) {
+        let num_bits = self.bitpacker.num_bits_sorted(offset, block);
+        let written_size = self
+            .bitpacker
+            .compress_sorted(offset, block, &mut output[..], num_bits);
+        (num_bits, written_size)
+    }
+
+    fn compress_block_unsorted(
+        &self,
+        block: &[u32],
+        output: &mut [u8; COMPRESSED_BLOCK_MAX_SIZE],
+    ) -> (u8, usize) {
+        let num_bits = self.bitpacker.num_bits(block);
+        let written_size = self.bitpacker.compress(block, &mut output[..], num_bits);
+        (num_bits, written_size)
+    }
+
+    fn uncompress_block_sorted(
+        &self,
+        compressed_data: &[u8],
+        offset: u32,
+        num_bits: u8,
+        output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+    ) -> usize {
+        self.bitpacker
+            .decompress_sorted(offset, compressed_data, output, num_bits)
+    }
+
+    fn uncompress_block_unsorted(
+        &self,
+        compressed_data: &[u8],
+        num_bits: u8,
+        output: &mut [u32; COMPRESSION_BLOCK_SIZE],
+    ) -> usize {
+        self.bitpacker.decompress(compressed_data, output, num_bits)
+    }
+}
+
+static DEFAULT_POSTINGS_CODEC: Lazy<Arc<dyn PostingsCodec>> =
+    Lazy::new(|| Arc::new(BitpackedPostingsCodec::default()));
+
+/// Returns the shared instance of the default `BitpackedPostingsCodec`.
+pub(crate) fn default_postings_codec() -> Arc<dyn PostingsCodec> {
+    DEFAULT_POSTINGS_CODEC.clone()
+}
+
+pub struct BlockEncoder {
+    codec: Arc<dyn PostingsCodec>,
     pub output: [u8; COMPRESSED_BLOCK_MAX_SIZE],
     pub output_len: usize,
 }
@@ -25,33 +169,33 @@ impl Default for BlockEncoder {
 
 impl BlockEncoder {
     pub fn new() -> BlockEncoder {
+        BlockEncoder::with_codec(default_postings_codec())
+    }
+
+    pub fn with_codec(codec: Arc<dyn PostingsCodec>) -> BlockEncoder {
         BlockEncoder {
-            bitpacker: BitPacker4x::new(),
+            codec,
             output: [0u8; COMPRESSED_BLOCK_MAX_SIZE],
             output_len: 0,
         }
     }
 
     pub fn compress_block_sorted(&mut self, block: &[u32], offset: u32) -> (u8, &[u8]) {
-        let num_bits = self.bitpacker.num_bits_sorted(offset, block);
-        let written_size =
-            self.bitpacker
-                .compress_sorted(offset, block, &mut self.output[..], num_bits);
+        let (num_bits, written_size) =
+            self.codec
+                .compress_block_sorted(block, offset, &mut self.output);
         (num_bits, &self.output[..written_size])
     }
 
     pub fn compress_block_unsorted(&mut self, block: &[u32]) -> (u8, &[u8]) {
-        let num_bits = self.bitpacker.num_bits(block);
-        let written_size = self
-            .bitpacker
-            .compress(block, &mut self.output[..], num_bits);
+        let (num_bits, written_size) = self.codec.compress_block_unsorted(block, &mut self.output);
         (num_bits, &self.output[..written_size])
     }
 }
 
 #[derive(Clone)]
 pub struct BlockDecoder {
-    bitpacker: BitPacker4x,
+    codec: Arc<dyn PostingsCodec>,
     output: [u32; COMPRESSION_BLOCK_SIZE],
     pub output_len: usize,
 }
@@ -64,8 +208,12 @@ impl Default for BlockDecoder {
 
 impl BlockDecoder {
     pub fn with_val(val: u32) -> BlockDecoder {
+        BlockDecoder::with_codec_and_val(default_postings_codec(), val)
+    }
+
+    pub fn with_codec_and_val(codec: Arc<dyn PostingsCodec>, val: u32) -> BlockDecoder {
         BlockDecoder {
-            bitpacker: BitPacker4x::new(),
+            codec,
             output: [val; COMPRESSION_BLOCK_SIZE],
             output_len: 0,
         }
@@ -78,14 +226,19 @@ impl BlockDecoder {
         num_bits: u8,
     ) -> usize {
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        self.bitpacker
-            .decompress_sorted(offset, compressed_data, &mut self.output, num_bits)
+        self.codec
+            .uncompress_block_sorted(compressed_data, offset, num_bits, &mut self.output)
     }
 
     pub fn uncompress_block_unsorted(&mut self, compressed_data: &[u8], num_bits: u8) -> usize {
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        self.bitpacker
-            .decompress(compressed_data, &mut self.output, num_bits)
+        self.codec
+            .uncompress_block_unsorted(compressed_data, num_bits, &mut self.output)
+    }
+
+    /// Sets the codec of the blocks to decode.
+    pub(crate) fn set_codec(&mut self, codec: Arc<dyn PostingsCodec>) {
+        self.codec = codec;
     }
 
     #[inline]
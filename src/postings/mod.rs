@@ -4,7 +4,7 @@ Postings module (also called inverted index)
 
 mod block_search;
 
-pub(crate) use self::block_search::branchless_binary_search;
+pub(crate) use self::block_search::{first_common_doc, search_in_block};
 
 mod block_segment_postings;
 pub(crate) mod compression;
@@ -18,12 +18,15 @@ mod stacker;
 mod term_info;
 
 pub use self::block_segment_postings::BlockSegmentPostings;
+pub use self::compression::{
+    BitpackedPostingsCodec, PostingsCodec, COMPRESSED_BLOCK_MAX_SIZE, COMPRESSION_BLOCK_SIZE,
+};
 pub use self::postings::Postings;
 pub(crate) use self::postings_writer::MultiFieldPostingsWriter;
 pub use self::segment_postings::SegmentPostings;
 pub use self::serializer::{FieldSerializer, InvertedIndexSerializer};
 pub(crate) use self::skip::{BlockInfo, SkipReader};
-pub(crate) use self::stacker::compute_table_size;
+pub(crate) use self::stacker::{compute_table_num_bits, compute_table_size};
 pub use self::term_info::TermInfo;
 
 pub(crate) type UnorderedTermId = u64;
@@ -44,17 +47,20 @@ pub mod tests {
     use crate::core::SegmentComponent;
     use crate::core::SegmentReader;
     use crate::docset::{DocSet, TERMINATED};
+    use crate::fastfield::FastFieldReader;
     use crate::fieldnorm::FieldNormReader;
     use crate::indexer::operation::AddOperation;
     use crate::indexer::SegmentWriter;
     use crate::query::Scorer;
+    use crate::schema::{Document, Schema, Term, FAST, INDEXED, TEXT};
     use crate::schema::{Field, TextOptions};
     use crate::schema::{IndexRecordOption, TextFieldIndexing};
-    use crate::schema::{Schema, Term, INDEXED, TEXT};
-    use crate::tokenizer::{SimpleTokenizer, MAX_TOKEN_LEN};
+    use crate::tokenizer::{PreTokenizedString, SimpleTokenizer, Token, MAX_TOKEN_LEN};
     use crate::DocId;
     use crate::HasLen;
     use crate::Score;
+    use crate::{IndexSettings, IndexSortByField, IndexWriter, Order, Searcher};
+    use futures::executor::block_on;
     use std::mem;
 
     #[test]
@@ -79,6 +85,290 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_read_postings_requesting_more_than_indexed() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default().set_index_option(IndexRecordOption::WithFreqs),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => r#"abc be abc"#))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let inverted_index = searcher.segment_reader(0u32).inverted_index(title)?;
+        let term = Term::from_field_text(title, "abc");
+        let mut postings = inverted_index
+            .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)?
+            .unwrap();
+        assert_eq!(postings.doc(), 0);
+        assert_eq!(postings.term_freq(), 2);
+        let mut positions = vec![1, 2, 3];
+        postings.positions(&mut positions);
+        assert!(positions.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_postings_dedup_repeated_values_in_doc() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_u64_field("num", INDEXED);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(
+            doc!(num => 3u64, num => 3u64, num => 4u64, text => "a", text => "b a"),
+        )?;
+        index_writer.add_document(doc!(num => 3u64, text => "b"))?;
+        index_writer.add_document(doc!(num => 4u64, num => 4u64, text => "a a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0u32);
+        let postings_docs = |term: &Term| -> crate::Result<Vec<DocId>> {
+            let mut postings = segment_reader
+                .inverted_index(term.field())?
+                .read_postings(term, IndexRecordOption::Basic)?
+                .unwrap();
+            let mut docs = vec![];
+            while postings.doc() != TERMINATED {
+                docs.push(postings.doc());
+                postings.advance();
+            }
+            Ok(docs)
+        };
+        let term_3 = Term::from_field_u64(num, 3u64);
+        let term_4 = Term::from_field_u64(num, 4u64);
+        let term_a = Term::from_field_text(text, "a");
+        assert_eq!(searcher.doc_freq(&term_3)?, 2);
+        assert_eq!(searcher.doc_freq(&term_4)?, 2);
+        assert_eq!(searcher.doc_freq(&term_a)?, 2);
+        assert_eq!(postings_docs(&term_3)?, vec![0, 1]);
+        assert_eq!(postings_docs(&term_4)?, vec![0, 2]);
+        assert_eq!(postings_docs(&term_a)?, vec![0, 2]);
+        let term_info_3 = segment_reader
+            .inverted_index(num)?
+            .get_term_info(&term_3)?
+            .unwrap();
+        assert_eq!(term_info_3.doc_freq, 2);
+        assert_eq!(term_info_3.total_term_freq, 2);
+        let term_info_a = segment_reader
+            .inverted_index(text)?
+            .get_term_info(&term_a)?
+            .unwrap();
+        assert_eq!(term_info_a.doc_freq, 2);
+        assert_eq!(term_info_a.total_term_freq, 4);
+        let mut postings = segment_reader
+            .inverted_index(text)?
+            .read_postings(&term_a, IndexRecordOption::WithFreqs)?
+            .unwrap();
+        assert_eq!(postings.term_freq(), 2);
+        assert_eq!(postings.advance(), 2);
+        assert_eq!(postings.term_freq(), 2);
+        Ok(())
+    }
+
+    fn payload_token(position: usize, text: &str, payload: &[u8]) -> Token {
+        Token {
+            offset_from: 0,
+            offset_to: 0,
+            position,
+            text: text.to_string(),
+            position_length: 1,
+            payload: payload.to_vec(),
+        }
+    }
+
+    fn test_postings_payloads_aux(index_settings: IndexSettings) -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_payloads(true),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let num = schema_builder.add_u64_field("num", FAST);
+        let schema = schema_builder.build();
+        let index = Index::builder()
+            .schema(schema)
+            .settings(index_settings)
+            .create_in_ram()?;
+        let add_doc = |index_writer: &mut IndexWriter,
+                       num_val: u64,
+                       tokens: Vec<Token>|
+         -> crate::Result<()> {
+            let mut doc = Document::default();
+            doc.add_pre_tokenized_text(
+                text,
+                &PreTokenizedString {
+                    text: String::new(),
+                    tokens,
+                },
+            );
+            doc.add_u64(num, num_val);
+            index_writer.add_document(doc)?;
+            Ok(())
+        };
+        let mut index_writer = index.writer_for_tests()?;
+        add_doc(
+            &mut index_writer,
+            2,
+            vec![
+                payload_token(0, "a", b"0.9"),
+                payload_token(1, "b", b""),
+                payload_token(2, "a", b"0.25"),
+            ],
+        )?;
+        add_doc(&mut index_writer, 1, vec![payload_token(0, "b", b"x")])?;
+        index_writer.commit()?;
+        add_doc(&mut index_writer, 0, vec![payload_token(3, "a", b"0.5")])?;
+        index_writer.commit()?;
+
+        let payloads_of =
+            |searcher: &Searcher| -> crate::Result<Vec<(u64, Vec<u32>, Vec<Vec<u8>>)>> {
+                let term = Term::from_field_text(text, "a");
+                let mut payloads = Vec::new();
+                for segment_reader in searcher.segment_readers() {
+                    let num_reader = segment_reader.fast_fields().u64(num)?;
+                    let mut postings = segment_reader
+                        .inverted_index(text)?
+                        .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)?
+                        .unwrap();
+                    while postings.doc() != TERMINATED {
+                        let mut positions = Vec::new();
+                        postings.positions(&mut positions);
+                        payloads.push((
+                            num_reader.get(postings.doc()),
+                            positions,
+                            postings.payloads().map(<[u8]>::to_vec).collect(),
+                        ));
+                        postings.advance();
+                    }
+                }
+                payloads.sort();
+                Ok(payloads)
+            };
+        let expected = vec![
+            (0, vec![3], vec![b"0.5".to_vec()]),
+            (2, vec![0, 2], vec![b"0.9".to_vec(), b"0.25".to_vec()]),
+        ];
+        let reader = index.reader()?;
+        assert_eq!(payloads_of(&reader.searcher())?, expected);
+
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(payloads_of(&searcher)?, expected);
+
+        // Payloads are not available if positions are not requested.
+        let postings = searcher
+            .segment_reader(0)
+            .inverted_index(text)?
+            .read_postings(
+                &Term::from_field_text(text, "a"),
+                IndexRecordOption::WithFreqs,
+            )?
+            .unwrap();
+        assert_eq!(postings.payloads().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_postings_payloads() -> crate::Result<()> {
+        test_postings_payloads_aux(IndexSettings::default())
+    }
+
+    #[test]
+    pub fn test_postings_payloads_sorted_index() -> crate::Result<()> {
+        test_postings_payloads_aux(IndexSettings {
+            sort_by_field: Some(IndexSortByField {
+                field: "num".to_string(),
+                order: Order::Asc,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_postings_max_term_freq() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_max_term_freq(2),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a b a c a a", text => "a b"))?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "b b b"))?;
+        index_writer.commit()?;
+
+        let term_freqs_and_positions = |searcher: &Searcher, text_value: &str| {
+            let term = Term::from_field_text(text, text_value);
+            let mut result = Vec::new();
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader.inverted_index(text).unwrap();
+                if let Some(mut postings) = inverted_index
+                    .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
+                {
+                    while postings.doc() != TERMINATED {
+                        let mut positions = Vec::new();
+                        postings.positions(&mut positions);
+                        result.push((postings.term_freq(), positions));
+                        postings.advance();
+                    }
+                }
+            }
+            result.sort();
+            result
+        };
+        let reader = index.reader()?;
+        let searcher = reader.searcher();
+        assert_eq!(
+            term_freqs_and_positions(&searcher, "a"),
+            vec![(1, vec![0]), (2, vec![0, 2])]
+        );
+        assert_eq!(
+            term_freqs_and_positions(&searcher, "b"),
+            vec![(2, vec![0, 1]), (2, vec![1, 8])]
+        );
+        // The capped occurrences still count in the fieldnorm.
+        let segment_reader = searcher
+            .segment_readers()
+            .iter()
+            .find(|segment_reader| segment_reader.max_doc() == 2)
+            .unwrap();
+        let fieldnorm_reader = segment_reader.get_fieldnorms_reader(text)?;
+        assert_eq!(
+            (0..2)
+                .map(|doc| fieldnorm_reader.fieldnorm(doc))
+                .collect::<Vec<_>>(),
+            vec![8, 1]
+        );
+
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(
+            term_freqs_and_positions(&searcher, "b"),
+            vec![(2, vec![0, 1]), (2, vec![1, 8])]
+        );
+        Ok(())
+    }
+
     #[test]
     pub fn test_skip_positions() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
@@ -228,7 +518,7 @@ pub mod tests {
 
         {
             let mut segment_writer =
-                SegmentWriter::for_segment(3_000_000, segment.clone(), &schema).unwrap();
+                SegmentWriter::for_segment(3_000_000, segment.clone(), &schema, None).unwrap();
             {
                 // checking that position works if the field has two values
                 let op = AddOperation {
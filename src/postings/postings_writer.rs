@@ -1,7 +1,9 @@
 use super::stacker::{Addr, MemoryArena, TermHashMap};
 
+use crate::indexer::{FieldIndexingStatistics, FieldTermsMemoryUsage};
 use crate::postings::recorder::{
     BufferLender, NothingRecorder, Recorder, TermFrequencyRecorder, TfAndPositionRecorder,
+    TfPositionAndPayloadRecorder,
 };
 use crate::postings::UnorderedTermId;
 use crate::postings::{FieldSerializer, InvertedIndexSerializer};
@@ -22,15 +24,27 @@ fn posting_from_field_entry(field_entry: &FieldEntry) -> Box<dyn PostingsWriter>
     match *field_entry.field_type() {
         FieldType::Str(ref text_options) => text_options
             .get_indexing_options()
-            .map(|indexing_options| match indexing_options.index_option() {
-                IndexRecordOption::Basic => {
-                    SpecializedPostingsWriter::<NothingRecorder>::new_boxed()
-                }
-                IndexRecordOption::WithFreqs => {
-                    SpecializedPostingsWriter::<TermFrequencyRecorder>::new_boxed()
-                }
-                IndexRecordOption::WithFreqsAndPositions => {
-                    SpecializedPostingsWriter::<TfAndPositionRecorder>::new_boxed()
+            .map(|indexing_options| {
+                let max_term_freq = indexing_options.max_term_freq();
+                match indexing_options.index_option() {
+                    IndexRecordOption::Basic => {
+                        SpecializedPostingsWriter::<NothingRecorder>::new_boxed()
+                    }
+                    IndexRecordOption::WithFreqs => {
+                        SpecializedPostingsWriter::<TermFrequencyRecorder>::with_max_term_freq(
+                            max_term_freq,
+                        )
+                    }
+                    IndexRecordOption::WithFreqsAndPositions if indexing_options.payloads() => {
+                        SpecializedPostingsWriter::<TfPositionAndPayloadRecorder>::with_max_term_freq(
+                            max_term_freq,
+                        )
+                    }
+                    IndexRecordOption::WithFreqsAndPositions => {
+                        SpecializedPostingsWriter::<TfAndPositionRecorder>::with_max_term_freq(
+                            max_term_freq,
+                        )
+                    }
                 }
             })
             .unwrap_or_else(SpecializedPostingsWriter::<NothingRecorder>::new_boxed),
@@ -39,6 +53,8 @@ fn posting_from_field_entry(field_entry: &FieldEntry) -> Box<dyn PostingsWriter>
         | FieldType::F64(_)
         | FieldType::Date(_)
         | FieldType::Bytes(_)
+        | FieldType::Vector(_)
+        | FieldType::Features(_)
         | FieldType::HierarchicalFacet(_) => {
             SpecializedPostingsWriter::<NothingRecorder>::new_boxed()
         }
@@ -94,8 +110,61 @@ impl MultiFieldPostingsWriter {
         }
     }
 
+    /// Memory accounted for in the memory budget of the indexing thread.
+    ///
+    /// The arena storing the terms is not accounted for.
     pub fn mem_usage(&self) -> usize {
-        self.term_index.mem_usage() + self.heap.mem_usage()
+        self.term_hashmap_mem_usage() + self.postings_arena_mem_usage()
+    }
+
+    /// Number of terms of all of the fields.
+    pub(crate) fn num_terms(&self) -> usize {
+        self.term_index.len()
+    }
+
+    /// Memory used by the table of the term hashmap.
+    pub(crate) fn term_hashmap_mem_usage(&self) -> usize {
+        self.term_index.mem_usage()
+    }
+
+    /// Memory used by the arena storing the terms.
+    pub(crate) fn term_arena_mem_usage(&self) -> usize {
+        self.term_index.heap.mem_usage()
+    }
+
+    /// Memory used by the arena storing the posting lists.
+    pub(crate) fn postings_arena_mem_usage(&self) -> usize {
+        self.heap.mem_usage()
+    }
+
+    /// Memory used by the terms of each field having at least one term.
+    pub(crate) fn per_field_terms_mem_usage(&self) -> HashMap<Field, FieldTermsMemoryUsage> {
+        self.per_field_postings_writers
+            .iter()
+            .enumerate()
+            .filter(|(_, postings_writer)| postings_writer.num_terms() > 0)
+            .map(|(field_id, postings_writer)| {
+                let field_terms_mem_usage = FieldTermsMemoryUsage::new(
+                    postings_writer.num_terms(),
+                    postings_writer.term_mem_usage(),
+                );
+                (Field::from_field_id(field_id as u32), field_terms_mem_usage)
+            })
+            .collect()
+    }
+
+    /// Indexing statistics of each field having at least one term.
+    pub(crate) fn per_field_indexing_statistics(&self) -> Vec<FieldIndexingStatistics> {
+        self.per_field_postings_writers
+            .iter()
+            .enumerate()
+            .filter(|(_, postings_writer)| postings_writer.num_terms() > 0)
+            .map(|(field_id, postings_writer)| FieldIndexingStatistics {
+                field: Field::from_field_id(field_id as u32),
+                num_tokens: postings_writer.total_num_tokens(),
+                num_terms: postings_writer.num_terms(),
+            })
+            .collect()
     }
 
     pub fn index_text(
@@ -120,7 +189,7 @@ impl MultiFieldPostingsWriter {
     pub fn subscribe(&mut self, doc: DocId, term: &Term) -> UnorderedTermId {
         let postings_writer =
             self.per_field_postings_writers[term.field().field_id() as usize].deref_mut();
-        postings_writer.subscribe(&mut self.term_index, doc, 0u32, term, &mut self.heap)
+        postings_writer.subscribe(&mut self.term_index, doc, 0u32, &[], term, &mut self.heap)
     }
 
     /// Serialize the inverted index.
@@ -161,7 +230,7 @@ impl MultiFieldPostingsWriter {
                     unordered_term_mappings.insert(field, mapping);
                 }
                 FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) | FieldType::Date(_) => {}
-                FieldType::Bytes(_) => {}
+                FieldType::Bytes(_) | FieldType::Vector(_) | FieldType::Features(_) => {}
             }
 
             let postings_writer =
@@ -189,11 +258,17 @@ impl MultiFieldPostingsWriter {
 /// and building a `Segment` in anonymous memory.
 ///
 /// `PostingsWriter` writes in a `MemoryArena`.
-pub trait PostingsWriter {
+pub trait PostingsWriter: Send {
     /// Record that a document contains a term at a given position.
     ///
+    /// A term can be subscribed several times for the same document
+    /// (repeated tokens, multi-valued fields containing the same value twice...).
+    /// The document is then recorded only once, and the extra occurrences only
+    /// contribute to its term frequency.
+    ///
     /// * doc  - the document id
     /// * pos  - the term position (expressed in tokens)
+    /// * payload - the payload attached to this position
     /// * term - the term
     /// * heap - heap used to store the postings informations as well as the terms
     /// in the hashmap.
@@ -202,6 +277,7 @@ pub trait PostingsWriter {
         term_index: &mut TermHashMap,
         doc: DocId,
         pos: u32,
+        payload: &[u8],
         term: &Term,
         heap: &mut MemoryArena,
     ) -> UnorderedTermId;
@@ -228,11 +304,27 @@ pub trait PostingsWriter {
         term_buffer: &mut Term,
     ) -> u32 {
         term_buffer.set_field(field);
+        let max_term_freq = self.max_term_freq();
+        let mut term_freqs: FnvHashMap<String, u32> = FnvHashMap::default();
         let mut sink = |token: &Token| {
+            if let Some(max_term_freq) = max_term_freq {
+                let term_freq = term_freqs.entry(token.text.clone()).or_insert(0);
+                if *term_freq >= max_term_freq {
+                    return;
+                }
+                *term_freq += 1;
+            }
             // We skip all tokens with a len greater than u16.
             if token.text.len() <= MAX_TOKEN_LEN {
                 term_buffer.set_text(token.text.as_str());
-                self.subscribe(term_index, doc_id, token.position as u32, term_buffer, heap);
+                self.subscribe(
+                    term_index,
+                    doc_id,
+                    token.position as u32,
+                    &token.payload,
+                    term_buffer,
+                    heap,
+                );
             } else {
                 warn!(
                     "A token exceeding MAX_TOKEN_LEN ({}>{}) was dropped. Search for \
@@ -246,12 +338,26 @@ pub trait PostingsWriter {
     }
 
     fn total_num_tokens(&self) -> u64;
+
+    /// Number of distinct terms subscribed.
+    fn num_terms(&self) -> u64;
+
+    /// Bytes used in the term hashmap arena by the terms subscribed, and their recorder.
+    fn term_mem_usage(&self) -> usize;
+
+    /// Maximum number of occurrences of a term recorded for a document by `index_text`.
+    fn max_term_freq(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// The `SpecializedPostingsWriter` is just here to remove dynamic
 /// dispatch to the recorder information.
 pub(crate) struct SpecializedPostingsWriter<Rec: Recorder + 'static> {
     total_num_tokens: u64,
+    num_terms: u64,
+    term_mem_usage: usize,
+    max_term_freq: Option<u32>,
     _recorder_type: PhantomData<Rec>,
 }
 
@@ -260,6 +366,9 @@ impl<Rec: Recorder + 'static> SpecializedPostingsWriter<Rec> {
     pub fn new() -> SpecializedPostingsWriter<Rec> {
         SpecializedPostingsWriter {
             total_num_tokens: 0u64,
+            num_terms: 0u64,
+            term_mem_usage: 0,
+            max_term_freq: None,
             _recorder_type: PhantomData,
         }
     }
@@ -268,6 +377,15 @@ impl<Rec: Recorder + 'static> SpecializedPostingsWriter<Rec> {
     pub fn new_boxed() -> Box<dyn PostingsWriter> {
         Box::new(SpecializedPostingsWriter::<Rec>::new())
     }
+
+    /// Builds a `SpecializedPostingsWriter` recording at most `max_term_freq`
+    /// occurrences of a term per document.
+    pub fn with_max_term_freq(max_term_freq: Option<u32>) -> Box<dyn PostingsWriter> {
+        Box::new(SpecializedPostingsWriter::<Rec> {
+            max_term_freq,
+            ..SpecializedPostingsWriter::new()
+        })
+    }
 }
 
 impl<Rec: Recorder + 'static> PostingsWriter for SpecializedPostingsWriter<Rec> {
@@ -276,27 +394,37 @@ impl<Rec: Recorder + 'static> PostingsWriter for SpecializedPostingsWriter<Rec>
         term_index: &mut TermHashMap,
         doc: DocId,
         position: u32,
+        payload: &[u8],
         term: &Term,
         heap: &mut MemoryArena,
     ) -> UnorderedTermId {
         debug_assert!(term.as_slice().len() >= 4);
         self.total_num_tokens += 1;
-        term_index.mutate_or_create(term, |opt_recorder: Option<Rec>| {
+        let mut is_new_term = false;
+        let term_id = term_index.mutate_or_create(term, |opt_recorder: Option<Rec>| {
             if let Some(mut recorder) = opt_recorder {
                 let current_doc = recorder.current_doc();
                 if current_doc != doc {
                     recorder.close_doc(heap);
                     recorder.new_doc(doc, heap);
                 }
-                recorder.record_position(position, heap);
+                recorder.record_position(position, payload, heap);
                 recorder
             } else {
+                is_new_term = true;
                 let mut recorder = Rec::new();
                 recorder.new_doc(doc, heap);
-                recorder.record_position(position, heap);
+                recorder.record_position(position, payload, heap);
                 recorder
             }
-        }) as UnorderedTermId
+        });
+        if is_new_term {
+            // mirrors the layout of the key-value pairs in the term hashmap arena.
+            self.num_terms += 1;
+            self.term_mem_usage +=
+                std::mem::size_of::<u16>() + term.as_slice().len() + std::mem::size_of::<Rec>();
+        }
+        term_id as UnorderedTermId
     }
 
     fn serialize(
@@ -321,4 +449,16 @@ impl<Rec: Recorder + 'static> PostingsWriter for SpecializedPostingsWriter<Rec>
     fn total_num_tokens(&self) -> u64 {
         self.total_num_tokens
     }
+
+    fn num_terms(&self) -> u64 {
+        self.num_terms
+    }
+
+    fn term_mem_usage(&self) -> usize {
+        self.term_mem_usage
+    }
+
+    fn max_term_freq(&self) -> Option<u32> {
+        self.max_term_freq
+    }
 }
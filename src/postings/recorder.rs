@@ -55,7 +55,8 @@ impl<'a> Iterator for VInt32Reader<'a> {
 ///   * the document id
 ///   * the term frequency
 ///   * the term positions
-pub(crate) trait Recorder: Copy + 'static {
+///   * the payloads of the term positions
+pub(crate) trait Recorder: Copy + Send + 'static {
     ///
     fn new() -> Self;
     /// Returns the current document
@@ -63,9 +64,9 @@ pub(crate) trait Recorder: Copy + 'static {
     /// Starts recording information about a new document
     /// This method shall only be called if the term is within the document.
     fn new_doc(&mut self, doc: DocId, heap: &mut MemoryArena);
-    /// Record the position of a term. For each document,
+    /// Record the position of a term, and its payload. For each document,
     /// this method will be called `term_freq` times.
-    fn record_position(&mut self, position: u32, heap: &mut MemoryArena);
+    fn record_position(&mut self, position: u32, payload: &[u8], heap: &mut MemoryArena);
     /// Close the document. It will help record the term frequency.
     fn close_doc(&mut self, heap: &mut MemoryArena);
     /// Pushes the postings information to the serializer.
@@ -106,7 +107,7 @@ impl Recorder for NothingRecorder {
         let _ = write_u32_vint(doc, &mut self.stack.writer(heap));
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &mut MemoryArena) {}
+    fn record_position(&mut self, _position: u32, _payload: &[u8], _heap: &mut MemoryArena) {}
 
     fn close_doc(&mut self, _heap: &mut MemoryArena) {}
 
@@ -171,7 +172,7 @@ impl Recorder for TermFrequencyRecorder {
         let _ = write_u32_vint(doc, &mut self.stack.writer(heap));
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &mut MemoryArena) {
+    fn record_position(&mut self, _position: u32, _payload: &[u8], _heap: &mut MemoryArena) {
         self.current_tf += 1;
     }
 
@@ -241,7 +242,7 @@ impl Recorder for TfAndPositionRecorder {
         let _ = write_u32_vint(doc, &mut self.stack.writer(heap));
     }
 
-    fn record_position(&mut self, position: u32, heap: &mut MemoryArena) {
+    fn record_position(&mut self, position: u32, _payload: &[u8], heap: &mut MemoryArena) {
         let _ = write_u32_vint(position + 1u32, &mut self.stack.writer(heap));
     }
 
@@ -296,6 +297,103 @@ impl Recorder for TfAndPositionRecorder {
     }
 }
 
+/// Recorder encoding term frequencies, positions, and the payload
+/// attached to each position.
+#[derive(Clone, Copy)]
+pub struct TfPositionAndPayloadRecorder {
+    stack: ExpUnrolledLinkedList,
+    current_doc: DocId,
+    term_doc_freq: u32,
+}
+impl Recorder for TfPositionAndPayloadRecorder {
+    fn new() -> Self {
+        TfPositionAndPayloadRecorder {
+            stack: ExpUnrolledLinkedList::new(),
+            current_doc: u32::MAX,
+            term_doc_freq: 0u32,
+        }
+    }
+
+    fn current_doc(&self) -> DocId {
+        self.current_doc
+    }
+
+    fn new_doc(&mut self, doc: DocId, heap: &mut MemoryArena) {
+        self.current_doc = doc;
+        self.term_doc_freq += 1u32;
+        let _ = write_u32_vint(doc, &mut self.stack.writer(heap));
+    }
+
+    fn record_position(&mut self, position: u32, payload: &[u8], heap: &mut MemoryArena) {
+        let mut writer = self.stack.writer(heap);
+        let _ = write_u32_vint(position + 1u32, &mut writer);
+        let _ = write_u32_vint(payload.len() as u32, &mut writer);
+        writer.extend_from_slice(payload);
+    }
+
+    fn close_doc(&mut self, heap: &mut MemoryArena) {
+        let _ = write_u32_vint(POSITION_END, &mut self.stack.writer(heap));
+    }
+
+    fn serialize(
+        &self,
+        buffer_lender: &mut BufferLender,
+        serializer: &mut FieldSerializer<'_>,
+        heap: &MemoryArena,
+        doc_id_map: Option<&DocIdMapping>,
+    ) {
+        let (buffer_u8, buffer_positions) = buffer_lender.lend_all();
+        self.stack.read_to_end(heap, buffer_u8);
+        let mut data = &buffer_u8[..];
+        let mut payloads: Vec<&[u8]> = Vec::new();
+        let mut doc_id_and_positions = vec![];
+        while !data.is_empty() {
+            let doc = read_u32_vint(&mut data);
+            let mut prev_position_plus_one = 1u32;
+            buffer_positions.clear();
+            payloads.clear();
+            while !data.is_empty() {
+                let position_plus_one = read_u32_vint(&mut data);
+                if position_plus_one == POSITION_END {
+                    break;
+                }
+                buffer_positions.push(position_plus_one - prev_position_plus_one);
+                prev_position_plus_one = position_plus_one;
+                let payload_len = read_u32_vint(&mut data) as usize;
+                let (payload, remaining) = data.split_at(payload_len);
+                payloads.push(payload);
+                data = remaining;
+            }
+            if let Some(doc_id_map) = doc_id_map {
+                // this simple variant to remap may consume to much memory
+                doc_id_and_positions.push((
+                    doc_id_map.get_new_doc_id(doc),
+                    buffer_positions.to_vec(),
+                    payloads.clone(),
+                ));
+            } else {
+                serializer.write_doc(doc, buffer_positions.len() as u32, buffer_positions);
+                for payload in &payloads {
+                    serializer.write_payload(payload);
+                }
+            }
+        }
+        if doc_id_map.is_some() {
+            doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _)| doc_id);
+            for (doc_id, positions, payloads) in doc_id_and_positions {
+                serializer.write_doc(doc_id, positions.len() as u32, &positions);
+                for payload in payloads {
+                    serializer.write_payload(payload);
+                }
+            }
+        }
+    }
+
+    fn term_doc_freq(&self) -> Option<u32> {
+        Some(self.term_doc_freq)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1,23 +1,36 @@
 use crate::docset::DocSet;
 use crate::fastfield::AliveBitSet;
-use crate::positions::PositionReader;
-use crate::postings::branchless_binary_search;
+use crate::positions::{PayloadReader, PositionReader};
 use crate::postings::compression::COMPRESSION_BLOCK_SIZE;
+use crate::postings::search_in_block;
 use crate::postings::BlockSegmentPostings;
 use crate::postings::Postings;
 use crate::{DocId, TERMINATED};
 use common::HasLen;
 
+/// Offset of the first position of the document at `cur` in the current block,
+/// among all of the positions of the term.
+fn position_read_offset(block_cursor: &BlockSegmentPostings, cur: usize) -> u64 {
+    block_cursor.position_offset()
+        + (block_cursor.freqs()[..cur].iter().cloned().sum::<u32>() as u64)
+}
+
 /// `SegmentPostings` represents the inverted list or postings associated to
 /// a term in a `Segment`.
 ///
 /// As we iterate through the `SegmentPostings`, the frequencies are optionally decoded.
 /// Positions on the other hand, are optionally entirely decoded upfront.
+///
+/// The blocks are decoded straight from the bytes of the postings file into the
+/// fixed-size buffers of the block cursor, which live inline in the `SegmentPostings`.
+/// With a directory mapping its files in memory, the bytes are not copied either,
+/// so that opening and reading the postings of a term does not allocate.
 #[derive(Clone)]
 pub struct SegmentPostings {
     pub(crate) block_cursor: BlockSegmentPostings,
     cur: usize,
     position_reader: Option<PositionReader>,
+    payload_reader: Option<PayloadReader>,
 }
 
 impl SegmentPostings {
@@ -27,6 +40,7 @@ impl SegmentPostings {
             block_cursor: BlockSegmentPostings::empty(),
             cur: 0,
             position_reader: None,
+            payload_reader: None,
         }
     }
 
@@ -66,6 +80,7 @@ impl SegmentPostings {
     #[cfg(test)]
     pub fn create_from_docs(docs: &[u32]) -> SegmentPostings {
         use crate::directory::FileSlice;
+        use crate::postings::compression::default_postings_codec;
         use crate::postings::serializer::PostingsSerializer;
         use crate::schema::IndexRecordOption;
         let mut buffer = Vec::new();
@@ -85,6 +100,8 @@ impl SegmentPostings {
             FileSlice::from(buffer),
             IndexRecordOption::Basic,
             IndexRecordOption::Basic,
+            false,
+            default_postings_codec(),
         )
         .unwrap();
         SegmentPostings::from_block_postings(block_segment_postings, None)
@@ -98,6 +115,7 @@ impl SegmentPostings {
     ) -> SegmentPostings {
         use crate::directory::FileSlice;
         use crate::fieldnorm::FieldNormReader;
+        use crate::postings::compression::default_postings_codec;
         use crate::postings::serializer::PostingsSerializer;
         use crate::schema::IndexRecordOption;
         use crate::Score;
@@ -133,6 +151,8 @@ impl SegmentPostings {
             FileSlice::from(buffer),
             IndexRecordOption::WithFreqs,
             IndexRecordOption::WithFreqs,
+            false,
+            default_postings_codec(),
         )
         .unwrap();
         SegmentPostings::from_block_postings(block_segment_postings, None)
@@ -152,8 +172,34 @@ impl SegmentPostings {
             block_cursor: segment_block_postings,
             cur: 0, // cursor within the block
             position_reader,
+            payload_reader: None,
         }
     }
+
+    pub(crate) fn with_payload_reader(mut self, payload_reader: PayloadReader) -> SegmentPostings {
+        self.payload_reader = Some(payload_reader);
+        self
+    }
+
+    /// Returns the payloads attached to the positions of the term in the current
+    /// document, in the same order as the positions.
+    ///
+    /// The iterator is empty if the field does not record payloads
+    /// (see `TextFieldIndexing::set_payloads`), or if the postings were not
+    /// opened with `IndexRecordOption::WithFreqsAndPositions`.
+    pub fn payloads(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let (payload_reader_opt, read_offset, term_freq) = match self.payload_reader.as_ref() {
+            Some(payload_reader) => (
+                Some(payload_reader),
+                position_read_offset(&self.block_cursor, self.cur),
+                self.term_freq() as u64,
+            ),
+            None => (None, 0, 0),
+        };
+        (read_offset..read_offset + term_freq).filter_map(move |offset| {
+            payload_reader_opt.map(|payload_reader| payload_reader.payload(offset))
+        })
+    }
 }
 
 impl DocSet for SegmentPostings {
@@ -181,7 +227,7 @@ impl DocSet for SegmentPostings {
 
         // At this point we are on the block, that might contain our document.
         let output = self.block_cursor.full_block();
-        self.cur = branchless_binary_search(output, target);
+        self.cur = search_in_block(output, target);
 
         // The last block is not full and padded with the value TERMINATED,
         // so that we are guaranteed to have at least doc in the block (a real one or the padding)
@@ -200,6 +246,17 @@ impl DocSet for SegmentPostings {
         doc
     }
 
+    /// Returns the docs of the current block, from the current one.
+    ///
+    /// The padding of the last block is excluded.
+    fn buffered_docs(&self) -> &[DocId] {
+        if !self.block_cursor.block_is_loaded() {
+            return &[];
+        }
+        let docs = self.block_cursor.docs();
+        &docs[self.cur.min(docs.len())..]
+    }
+
     /// Return the current document's `DocId`.
     #[inline]
     fn doc(&self) -> DocId {
@@ -243,11 +300,7 @@ impl Postings for SegmentPostings {
     fn positions_with_offset(&mut self, offset: u32, output: &mut Vec<u32>) {
         let term_freq = self.term_freq() as usize;
         if let Some(position_reader) = self.position_reader.as_mut() {
-            let read_offset = self.block_cursor.position_offset()
-                + (self.block_cursor.freqs()[..self.cur]
-                    .iter()
-                    .cloned()
-                    .sum::<u32>() as u64);
+            let read_offset = position_read_offset(&self.block_cursor, self.cur);
             output.resize(term_freq, 0u32);
             position_reader.read(read_offset, &mut output[..]);
             let mut cum = offset;
@@ -2,20 +2,23 @@ use super::TermInfo;
 use crate::core::Segment;
 use crate::directory::CompositeWrite;
 use crate::directory::WritePtr;
-use crate::fieldnorm::FieldNormReader;
+use crate::fieldnorm::{FieldNormReader, MissingFieldNorm};
 use crate::positions::PositionSerializer;
-use crate::postings::compression::{BlockEncoder, VIntEncoder, COMPRESSION_BLOCK_SIZE};
+use crate::postings::compression::{
+    default_postings_codec, BlockEncoder, PostingsCodec, VIntEncoder, COMPRESSION_BLOCK_SIZE,
+};
 use crate::postings::skip::SkipSerializer;
 use crate::query::Bm25Weight;
 use crate::schema::{Field, FieldEntry, FieldType};
 use crate::schema::{IndexRecordOption, Schema};
-use crate::termdict::{TermDictionaryBuilder, TermOrdinal};
+use crate::termdict::{TermDictionaryBuilder, TermDictionaryType, TermOrdinal};
 use crate::{DocId, Score};
 use common::CountingWriter;
 use common::{BinarySerializable, VInt};
 use fail::fail_point;
 use std::cmp::Ordering;
 use std::io::{self, Write};
+use std::sync::Arc;
 
 /// `InvertedIndexSerializer` is in charge of serializing
 /// postings on disk, in the
@@ -53,21 +56,57 @@ pub struct InvertedIndexSerializer {
     postings_write: CompositeWrite<WritePtr>,
     positions_write: CompositeWrite<WritePtr>,
     schema: Schema,
+    term_dictionary_type: TermDictionaryType,
+    has_static_ranks: bool,
+    static_ranks: Option<Arc<[u64]>>,
+    missing_fieldnorm: MissingFieldNorm,
+    postings_codec: Arc<dyn PostingsCodec>,
 }
 
 impl InvertedIndexSerializer {
     /// Open a new `PostingsSerializer` for the given segment
     pub fn open(segment: &mut Segment) -> crate::Result<InvertedIndexSerializer> {
+        InvertedIndexSerializer::open_with_term_dictionary_type(segment, TermDictionaryType::Fst)
+    }
+
+    /// Open a new `PostingsSerializer` for the given segment, writing
+    /// term dictionaries of the given type.
+    pub fn open_with_term_dictionary_type(
+        segment: &mut Segment,
+        term_dictionary_type: TermDictionaryType,
+    ) -> crate::Result<InvertedIndexSerializer> {
         use crate::SegmentComponent::{Positions, Postings, Terms};
         let inv_index_serializer = InvertedIndexSerializer {
             terms_write: CompositeWrite::wrap(segment.open_write(Terms)?),
             postings_write: CompositeWrite::wrap(segment.open_write(Postings)?),
             positions_write: CompositeWrite::wrap(segment.open_write(Positions)?),
             schema: segment.schema(),
+            term_dictionary_type,
+            has_static_ranks: segment.meta().static_rank_field().is_some(),
+            static_ranks: None,
+            missing_fieldnorm: segment.index().settings().missing_fieldnorm(),
+            postings_codec: default_postings_codec(),
         };
         Ok(inv_index_serializer)
     }
 
+    /// Sets the `PostingsCodec` encoding the blocks of the posting lists.
+    ///
+    /// Defaults to the `BitpackedPostingsCodec`.
+    pub fn with_postings_codec(
+        mut self,
+        postings_codec: Arc<dyn PostingsCodec>,
+    ) -> InvertedIndexSerializer {
+        self.postings_codec = postings_codec;
+        self
+    }
+
+    /// Sets the static ranks of the documents of the segment, indexed by doc id, as the
+    /// `u64` representation of the values of the static rank field.
+    pub(crate) fn set_static_ranks(&mut self, static_ranks: Vec<u64>) {
+        self.static_ranks = Some(static_ranks.into());
+    }
+
     /// Must be called before starting pushing terms of
     /// a given field.
     ///
@@ -83,13 +122,23 @@ impl InvertedIndexSerializer {
         let postings_write = self.postings_write.for_field(field);
         let positions_write = self.positions_write.for_field(field);
         let field_type: FieldType = (*field_entry.field_type()).clone();
+        if self.has_static_ranks && self.static_ranks.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The static ranks must be set before serializing the postings.",
+            ));
+        }
         FieldSerializer::create(
             &field_type,
+            self.term_dictionary_type,
             total_num_tokens,
             term_dictionary_write,
             postings_write,
             positions_write,
             fieldnorm_reader,
+            self.static_ranks.clone(),
+            self.missing_fieldnorm,
+            self.postings_codec.clone(),
         )
     }
 
@@ -109,18 +158,24 @@ pub struct FieldSerializer<'a> {
     postings_serializer: PostingsSerializer<&'a mut CountingWriter<WritePtr>>,
     positions_serializer_opt: Option<PositionSerializer<&'a mut CountingWriter<WritePtr>>>,
     current_term_info: TermInfo,
+    has_freqs: bool,
     term_open: bool,
     num_terms: TermOrdinal,
 }
 
 impl<'a> FieldSerializer<'a> {
+    #[allow(clippy::too_many_arguments)]
     fn create(
         field_type: &FieldType,
+        term_dictionary_type: TermDictionaryType,
         total_num_tokens: u64,
         term_dictionary_write: &'a mut CountingWriter<WritePtr>,
         postings_write: &'a mut CountingWriter<WritePtr>,
         positions_write: &'a mut CountingWriter<WritePtr>,
         fieldnorm_reader: Option<FieldNormReader>,
+        static_ranks: Option<Arc<[u64]>>,
+        missing_fieldnorm: MissingFieldNorm,
+        postings_codec: Arc<dyn PostingsCodec>,
     ) -> io::Result<FieldSerializer<'a>> {
         total_num_tokens.serialize(postings_write)?;
         let mode = match field_type {
@@ -133,14 +188,25 @@ impl<'a> FieldSerializer<'a> {
             }
             _ => IndexRecordOption::Basic,
         };
-        let term_dictionary_builder = TermDictionaryBuilder::create(term_dictionary_write)?;
-        let average_fieldnorm = fieldnorm_reader
-            .as_ref()
-            .map(|ff_reader| (total_num_tokens as Score / ff_reader.num_docs() as Score))
-            .unwrap_or(0.0);
+        let term_dictionary_builder =
+            TermDictionaryBuilder::create_with_type(term_dictionary_write, term_dictionary_type)?;
+        // The block max scores are computed with the same average fieldnorm
+        // as the one of the queries on this segment alone.
+        let average_fieldnorm = match fieldnorm_reader.as_ref() {
+            Some(ff_reader) => missing_fieldnorm
+                .average_fieldnorm(total_num_tokens, u64::from(ff_reader.num_docs()), || {
+                    Ok(u64::from(ff_reader.num_docs_with_tokens()))
+                })
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?,
+            None => 0.0,
+        };
         let postings_serializer =
-            PostingsSerializer::new(postings_write, average_fieldnorm, mode, fieldnorm_reader);
-        let positions_serializer_opt = if mode.has_positions() {
+            PostingsSerializer::new(postings_write, average_fieldnorm, mode, fieldnorm_reader)
+                .with_static_ranks(static_ranks)
+                .with_postings_codec(postings_codec);
+        let positions_serializer_opt = if field_type.has_payloads() {
+            Some(PositionSerializer::with_payloads(positions_write))
+        } else if mode.has_positions() {
             Some(PositionSerializer::new(positions_write))
         } else {
             None
@@ -151,6 +217,7 @@ impl<'a> FieldSerializer<'a> {
             postings_serializer,
             positions_serializer_opt,
             current_term_info: TermInfo::default(),
+            has_freqs: mode.has_freq(),
             term_open: false,
             num_terms: TermOrdinal::default(),
         })
@@ -166,6 +233,7 @@ impl<'a> FieldSerializer<'a> {
         let addr = self.postings_serializer.written_bytes() as usize;
         TermInfo {
             doc_freq: 0,
+            total_term_freq: 0,
             postings_range: addr..addr,
             positions_range: positions_start..positions_start,
         }
@@ -202,12 +270,27 @@ impl<'a> FieldSerializer<'a> {
     /// on the configuration of the field in the `Schema`.
     pub fn write_doc(&mut self, doc_id: DocId, term_freq: u32, position_deltas: &[u32]) {
         self.current_term_info.doc_freq += 1;
+        self.current_term_info.total_term_freq += if self.has_freqs {
+            u64::from(term_freq)
+        } else {
+            1u64
+        };
         self.postings_serializer.write_doc(doc_id, term_freq);
         if let Some(ref mut positions_serializer) = self.positions_serializer_opt.as_mut() {
             positions_serializer.write_positions_delta(position_deltas);
         }
     }
 
+    /// Serialize the payload of the next position of the current document.
+    ///
+    /// For fields recording payloads, this method has to be called once per position,
+    /// after the call to `write_doc`. It is ignored for other fields.
+    pub fn write_payload(&mut self, payload: &[u8]) {
+        if let Some(positions_serializer) = self.positions_serializer_opt.as_mut() {
+            positions_serializer.write_payload(payload);
+        }
+    }
+
     /// Finish the serialization for this term postings.
     ///
     /// If the current block is incomplete, it need to be encoded
@@ -307,6 +390,8 @@ pub struct PostingsSerializer<W: Write> {
     mode: IndexRecordOption,
     fieldnorm_reader: Option<FieldNormReader>,
 
+    static_ranks: Option<Arc<[u64]>>,
+
     bm25_weight: Option<Bm25Weight>,
     avg_fieldnorm: Score, // Average number of term in the field for that segment.
                           // this value is used to compute the block wand information.
@@ -334,9 +419,29 @@ impl<W: Write> PostingsSerializer<W> {
             fieldnorm_reader,
             bm25_weight: None,
             avg_fieldnorm,
+            static_ranks: None,
         }
     }
 
+    /// Records the maximum of `static_ranks` over the documents of each block
+    /// in the skip data.
+    pub(crate) fn with_static_ranks(
+        mut self,
+        static_ranks: Option<Arc<[u64]>>,
+    ) -> PostingsSerializer<W> {
+        self.static_ranks = static_ranks;
+        self
+    }
+
+    /// Encodes the full blocks with `postings_codec`.
+    pub(crate) fn with_postings_codec(
+        mut self,
+        postings_codec: Arc<dyn PostingsCodec>,
+    ) -> PostingsSerializer<W> {
+        self.block_encoder = BlockEncoder::with_codec(postings_codec);
+        self
+    }
+
     pub fn new_term(&mut self, term_doc_freq: u32) {
         self.bm25_weight = None;
 
@@ -412,6 +517,17 @@ impl<W: Write> PostingsSerializer<W> {
             let (fieldnorm_id, term_freq) = blockwand_params;
             self.skip_write.write_blockwand_max(fieldnorm_id, term_freq);
         }
+        if let Some(static_ranks) = self.static_ranks.as_ref() {
+            let block_max_static_rank = self
+                .block
+                .doc_ids()
+                .iter()
+                .map(|&doc| static_ranks[doc as usize])
+                .max()
+                .unwrap_or(0u64);
+            self.skip_write
+                .write_block_max_static_rank(block_max_static_rank);
+        }
         self.block.clear();
     }
 
@@ -1,7 +1,8 @@
 use std::convert::TryInto;
+use std::sync::Arc;
 
 use crate::directory::OwnedBytes;
-use crate::postings::compression::{compressed_block_size, COMPRESSION_BLOCK_SIZE};
+use crate::postings::compression::{default_postings_codec, PostingsCodec, COMPRESSION_BLOCK_SIZE};
 use crate::query::Bm25Weight;
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, TERMINATED};
@@ -30,6 +31,11 @@ fn write_u32(val: u32, buf: &mut Vec<u8>) {
     buf.extend_from_slice(&val.to_le_bytes());
 }
 
+#[inline]
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
 pub struct SkipSerializer {
     buffer: Vec<u8>,
 }
@@ -58,6 +64,12 @@ impl SkipSerializer {
             .extend_from_slice(&[fieldnorm_id, block_wand_tf]);
     }
 
+    /// Writes the maximum static rank of the documents of the block,
+    /// as the `u64` representation of the static rank fast field values.
+    pub fn write_block_max_static_rank(&mut self, static_rank: u64) {
+        self.buffer.extend_from_slice(&static_rank.to_le_bytes());
+    }
+
     pub fn data(&self) -> &[u8] {
         &self.buffer[..]
     }
@@ -77,6 +89,11 @@ pub(crate) struct SkipReader {
     remaining_docs: u32, // number of docs remaining, including the
     // documents in the current block.
     block_info: BlockInfo,
+    // True if the blocks record the maximum static rank of their documents.
+    has_static_ranks: bool,
+    block_max_static_rank: Option<u64>,
+    // Codec of the blocks, giving their size in bytes.
+    postings_codec: Arc<dyn PostingsCodec>,
 
     position_offset: u64,
 }
@@ -102,7 +119,12 @@ impl Default for BlockInfo {
 }
 
 impl SkipReader {
-    pub fn new(data: OwnedBytes, doc_freq: u32, skip_info: IndexRecordOption) -> SkipReader {
+    pub fn new(
+        data: OwnedBytes,
+        doc_freq: u32,
+        skip_info: IndexRecordOption,
+        has_static_ranks: bool,
+    ) -> SkipReader {
         let mut skip_reader = SkipReader {
             last_doc_in_block: if doc_freq >= COMPRESSION_BLOCK_SIZE as u32 {
                 0
@@ -113,6 +135,9 @@ impl SkipReader {
             owned_read: data,
             skip_info,
             block_info: BlockInfo::VInt { num_docs: doc_freq },
+            has_static_ranks,
+            block_max_static_rank: None,
+            postings_codec: default_postings_codec(),
             byte_offset: 0,
             remaining_docs: doc_freq,
             position_offset: 0u64,
@@ -123,6 +148,19 @@ impl SkipReader {
         skip_reader
     }
 
+    /// Sets the codec of the blocks of the posting list.
+    pub(crate) fn with_postings_codec(
+        mut self,
+        postings_codec: Arc<dyn PostingsCodec>,
+    ) -> SkipReader {
+        self.set_postings_codec(postings_codec);
+        self
+    }
+
+    pub(crate) fn set_postings_codec(&mut self, postings_codec: Arc<dyn PostingsCodec>) {
+        self.postings_codec = postings_codec;
+    }
+
     pub fn reset(&mut self, data: OwnedBytes, doc_freq: u32) {
         self.last_doc_in_block = if doc_freq >= COMPRESSION_BLOCK_SIZE as u32 {
             0
@@ -132,6 +170,7 @@ impl SkipReader {
         self.last_doc_in_previous_block = 0u32;
         self.owned_read = data;
         self.block_info = BlockInfo::VInt { num_docs: doc_freq };
+        self.block_max_static_rank = None;
         self.byte_offset = 0;
         self.remaining_docs = doc_freq;
         self.position_offset = 0u64;
@@ -155,6 +194,14 @@ impl SkipReader {
         }
     }
 
+    /// Returns the maximum static rank of the documents of the block, if available.
+    ///
+    /// Like the block max score, it is only available for the full bitpacked blocks,
+    /// and if the segment records the static ranks.
+    pub fn block_max_static_rank(&self) -> Option<u64> {
+        self.block_max_static_rank
+    }
+
     pub(crate) fn last_doc_in_block(&self) -> DocId {
         self.last_doc_in_block
     }
@@ -170,7 +217,7 @@ impl SkipReader {
 
     fn read_block_info(&mut self) {
         let bytes = self.owned_read.as_slice();
-        let advance_len: usize;
+        let mut advance_len: usize;
         self.last_doc_in_block = read_u32(bytes);
         let doc_num_bits = bytes[4];
         match self.skip_info {
@@ -212,6 +259,10 @@ impl SkipReader {
                 };
             }
         }
+        if self.has_static_ranks {
+            self.block_max_static_rank = Some(read_u64(&bytes[advance_len..]));
+            advance_len += 8;
+        }
         self.owned_read.advance(advance_len);
     }
 
@@ -244,7 +295,8 @@ impl SkipReader {
                 ..
             } => {
                 self.remaining_docs -= COMPRESSION_BLOCK_SIZE as u32;
-                self.byte_offset += compressed_block_size(doc_num_bits + tf_num_bits);
+                self.byte_offset += self.postings_codec.compressed_block_size(doc_num_bits)
+                    + self.postings_codec.compressed_block_size(tf_num_bits);
                 self.position_offset += tf_sum as u64;
             }
             BlockInfo::VInt { num_docs } => {
@@ -261,6 +313,7 @@ impl SkipReader {
             self.block_info = BlockInfo::VInt {
                 num_docs: self.remaining_docs,
             };
+            self.block_max_static_rank = None;
         }
     }
 }
@@ -305,8 +358,12 @@ mod tests {
             skip_serializer.data().to_owned()
         };
         let doc_freq = 3u32 + (COMPRESSION_BLOCK_SIZE * 2) as u32;
-        let mut skip_reader =
-            SkipReader::new(OwnedBytes::new(buf), doc_freq, IndexRecordOption::WithFreqs);
+        let mut skip_reader = SkipReader::new(
+            OwnedBytes::new(buf),
+            doc_freq,
+            IndexRecordOption::WithFreqs,
+            false,
+        );
         assert_eq!(skip_reader.last_doc_in_block(), 1u32);
         assert_eq!(
             skip_reader.block_info,
@@ -338,6 +395,47 @@ mod tests {
         assert_eq!(skip_reader.block_info(), BlockInfo::VInt { num_docs: 0u32 });
     }
 
+    #[test]
+    fn test_skip_with_static_ranks() {
+        let buf = {
+            let mut skip_serializer = SkipSerializer::new();
+            skip_serializer.write_doc(1u32, 2u8);
+            skip_serializer.write_term_freq(3u8);
+            skip_serializer.write_blockwand_max(13u8, 3u32);
+            skip_serializer.write_block_max_static_rank(1_000u64);
+            skip_serializer.write_doc(5u32, 5u8);
+            skip_serializer.write_term_freq(2u8);
+            skip_serializer.write_blockwand_max(8u8, 2u32);
+            skip_serializer.write_block_max_static_rank(u64::MAX);
+            skip_serializer.data().to_owned()
+        };
+        let doc_freq = 3u32 + (COMPRESSION_BLOCK_SIZE * 2) as u32;
+        let mut skip_reader = SkipReader::new(
+            OwnedBytes::new(buf),
+            doc_freq,
+            IndexRecordOption::WithFreqs,
+            true,
+        );
+        assert_eq!(skip_reader.last_doc_in_block(), 1u32);
+        assert_eq!(skip_reader.block_max_static_rank(), Some(1_000u64));
+        skip_reader.advance();
+        assert_eq!(skip_reader.last_doc_in_block(), 5u32);
+        assert_eq!(
+            skip_reader.block_info(),
+            BlockInfo::BitPacked {
+                doc_num_bits: 5u8,
+                tf_num_bits: 2u8,
+                tf_sum: 0,
+                block_wand_fieldnorm_id: 8,
+                block_wand_term_freq: 2
+            }
+        );
+        assert_eq!(skip_reader.block_max_static_rank(), Some(u64::MAX));
+        skip_reader.advance();
+        assert_eq!(skip_reader.block_info(), BlockInfo::VInt { num_docs: 3u32 });
+        assert_eq!(skip_reader.block_max_static_rank(), None);
+    }
+
     #[test]
     fn test_skip_no_freq() {
         let buf = {
@@ -347,8 +445,12 @@ mod tests {
             skip_serializer.data().to_owned()
         };
         let doc_freq = 3u32 + (COMPRESSION_BLOCK_SIZE * 2) as u32;
-        let mut skip_reader =
-            SkipReader::new(OwnedBytes::new(buf), doc_freq, IndexRecordOption::Basic);
+        let mut skip_reader = SkipReader::new(
+            OwnedBytes::new(buf),
+            doc_freq,
+            IndexRecordOption::Basic,
+            false,
+        );
         assert_eq!(skip_reader.last_doc_in_block(), 1u32);
         assert_eq!(
             skip_reader.block_info(),
@@ -388,8 +490,12 @@ mod tests {
             skip_serializer.data().to_owned()
         };
         let doc_freq = COMPRESSION_BLOCK_SIZE as u32;
-        let mut skip_reader =
-            SkipReader::new(OwnedBytes::new(buf), doc_freq, IndexRecordOption::Basic);
+        let mut skip_reader = SkipReader::new(
+            OwnedBytes::new(buf),
+            doc_freq,
+            IndexRecordOption::Basic,
+            false,
+        );
         assert_eq!(skip_reader.last_doc_in_block(), 1u32);
         assert_eq!(
             skip_reader.block_info(),
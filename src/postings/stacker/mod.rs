@@ -4,4 +4,4 @@ mod term_hashmap;
 
 pub use self::expull::ExpUnrolledLinkedList;
 pub use self::memory_arena::{Addr, MemoryArena};
-pub use self::term_hashmap::{compute_table_size, TermHashMap};
+pub use self::term_hashmap::{compute_table_num_bits, compute_table_size, TermHashMap};
@@ -14,14 +14,25 @@ pub fn compute_table_size(num_bits: usize) -> usize {
     (1 << num_bits) * mem::size_of::<KeyValue>()
 }
 
+/// Returns the smallest number of bits `b` such that a table of size $2^b$
+/// holds `num_keys` keys without having to be resized.
+pub fn compute_table_num_bits(num_keys: usize) -> usize {
+    // The table is resized once it is more than a third full.
+    let min_table_len = num_keys.saturating_mul(3).max(1);
+    min_table_len.next_power_of_two().trailing_zeros() as usize
+}
+
 /// `KeyValue` is the item stored in the hash table.
 /// The key is actually a `BytesRef` object stored in an external heap.
 /// The `value_addr` also points to an address in the heap.
+///
+/// The keys are addressed with 32 bits in the heap, so there are less than `2^32`
+/// of them and the term id is stored on 32 bits: a bucket takes 12 bytes.
 #[derive(Copy, Clone)]
 struct KeyValue {
     key_value_addr: Addr,
     hash: u32,
-    unordered_term_id: UnorderedTermId,
+    unordered_term_id: u32,
 }
 
 impl Default for KeyValue {
@@ -29,7 +40,7 @@ impl Default for KeyValue {
         KeyValue {
             key_value_addr: Addr::null_pointer(),
             hash: 0u32,
-            unordered_term_id: UnorderedTermId::default(),
+            unordered_term_id: 0u32,
         }
     }
 }
@@ -53,7 +64,7 @@ pub struct TermHashMap {
     table: Box<[KeyValue]>,
     pub heap: MemoryArena,
     mask: usize,
-    occupied: Vec<usize>,
+    occupied: Vec<u32>,
     len: usize,
 }
 
@@ -77,17 +88,17 @@ impl QuadraticProbing {
 
 pub struct Iter<'a> {
     hashmap: &'a TermHashMap,
-    inner: slice::Iter<'a, usize>,
+    inner: slice::Iter<'a, u32>,
 }
 
 impl<'a> Iterator for Iter<'a> {
     type Item = (&'a [u8], Addr, UnorderedTermId);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().cloned().map(move |bucket: usize| {
-            let kv = self.hashmap.table[bucket];
+        self.inner.next().cloned().map(move |bucket: u32| {
+            let kv = self.hashmap.table[bucket as usize];
             let (key, offset): (&'a [u8], Addr) = self.hashmap.get_key_value(kv.key_value_addr);
-            (key, offset, kv.unordered_term_id)
+            (key, offset, UnorderedTermId::from(kv.unordered_term_id))
         })
     }
 }
@@ -137,15 +148,15 @@ impl TermHashMap {
     }
 
     fn set_bucket(&mut self, hash: u32, key_value_addr: Addr, bucket: usize) -> UnorderedTermId {
-        self.occupied.push(bucket);
-        let unordered_term_id = self.len as UnorderedTermId;
+        self.occupied.push(bucket as u32);
+        let unordered_term_id = self.len as u32;
         self.len += 1;
         self.table[bucket] = KeyValue {
             key_value_addr,
             hash,
             unordered_term_id,
         };
-        unordered_term_id
+        UnorderedTermId::from(unordered_term_id)
     }
 
     pub fn len(&self) -> usize {
@@ -166,12 +177,12 @@ impl TermHashMap {
         let new_table = vec![KeyValue::default(); new_len].into_boxed_slice();
         let old_table = mem::replace(&mut self.table, new_table);
         for old_pos in self.occupied.iter_mut() {
-            let key_value: KeyValue = old_table[*old_pos];
+            let key_value: KeyValue = old_table[*old_pos as usize];
             let mut probe = QuadraticProbing::compute(key_value.hash as usize, mask);
             loop {
                 let bucket = probe.next_probe();
                 if self.table[bucket].is_empty() {
-                    *old_pos = bucket;
+                    *old_pos = bucket as u32;
                     self.table[bucket] = key_value;
                     break;
                 }
@@ -229,7 +240,7 @@ impl TermHashMap {
                     let v = self.heap.read(val_addr);
                     let new_v = updater(Some(v));
                     self.heap.write_at(val_addr, new_v);
-                    return kv.unordered_term_id;
+                    return UnorderedTermId::from(kv.unordered_term_id);
                 }
             }
         }
@@ -239,7 +250,7 @@ impl TermHashMap {
 #[cfg(test)]
 mod tests {
 
-    use super::TermHashMap;
+    use super::{compute_table_num_bits, TermHashMap};
     use std::collections::HashMap;
 
     #[test]
@@ -272,4 +283,39 @@ mod tests {
         }
         assert_eq!(vanilla_hash_map.len(), 2);
     }
+
+    #[test]
+    fn test_compute_table_num_bits() {
+        assert_eq!(compute_table_num_bits(0), 0);
+        assert_eq!(compute_table_num_bits(1), 2);
+        assert_eq!(compute_table_num_bits(1_000), 12);
+        assert_eq!(compute_table_num_bits(100_000), 19);
+        let mut hash_map: TermHashMap = TermHashMap::new(compute_table_num_bits(1_000));
+        let initial_mem_usage = hash_map.mem_usage();
+        for i in 0..1_000u32 {
+            hash_map.mutate_or_create(format!("term{}", i), |_: Option<u32>| i);
+        }
+        assert_eq!(hash_map.mem_usage(), initial_mem_usage);
+    }
+
+    #[test]
+    fn test_hash_map_grows() {
+        let mut hash_map: TermHashMap = TermHashMap::new(4);
+        let initial_mem_usage = hash_map.mem_usage();
+        for i in 0..1_000u32 {
+            hash_map.mutate_or_create(format!("term{}", i), |opt_val: Option<u32>| {
+                assert_eq!(opt_val, None);
+                i
+            });
+        }
+        assert_eq!(hash_map.len(), 1_000);
+        assert!(hash_map.mem_usage() > initial_mem_usage);
+        for i in 0..1_000u32 {
+            hash_map.mutate_or_create(format!("term{}", i), |opt_val: Option<u32>| {
+                assert_eq!(opt_val, Some(i));
+                i
+            });
+        }
+        assert_eq!(hash_map.len(), 1_000);
+    }
 }
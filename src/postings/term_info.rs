@@ -9,6 +9,10 @@ use std::ops::Range;
 pub struct TermInfo {
     /// Number of documents in the segment containing the term
     pub doc_freq: u32,
+    /// Sum of the term frequencies of the term over all of the documents of the segment.
+    ///
+    /// If term frequencies are not indexed, this is equal to `doc_freq`.
+    pub total_term_freq: u64,
     /// Byte range of the posting list within the postings (`.idx`) file.
     pub postings_range: Range<usize>,
     /// Byte range of the positions of this terms in the positions (`.pos`) file.
@@ -34,12 +38,13 @@ impl FixedSize for TermInfo {
     /// This is large, but in practise, `TermInfo` are encoded in blocks and
     /// only the first `TermInfo` of a block is serialized uncompressed.
     /// The subsequent `TermInfo` are delta encoded and bitpacked.
-    const SIZE_IN_BYTES: usize = 3 * u32::SIZE_IN_BYTES + 2 * u64::SIZE_IN_BYTES;
+    const SIZE_IN_BYTES: usize = 3 * u32::SIZE_IN_BYTES + 3 * u64::SIZE_IN_BYTES;
 }
 
 impl BinarySerializable for TermInfo {
     fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
         self.doc_freq.serialize(writer)?;
+        self.total_term_freq.serialize(writer)?;
         (self.postings_range.start as u64).serialize(writer)?;
         self.posting_num_bytes().serialize(writer)?;
         (self.positions_range.start as u64).serialize(writer)?;
@@ -49,6 +54,7 @@ impl BinarySerializable for TermInfo {
 
     fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
         let doc_freq = u32::deserialize(reader)?;
+        let total_term_freq = u64::deserialize(reader)?;
         let postings_start_offset = u64::deserialize(reader)? as usize;
         let postings_num_bytes = u32::deserialize(reader)? as usize;
         let postings_end_offset = postings_start_offset + postings_num_bytes;
@@ -57,6 +63,7 @@ impl BinarySerializable for TermInfo {
         let positions_end_offset = positions_start_offset + positions_num_bytes;
         Ok(TermInfo {
             doc_freq,
+            total_term_freq,
             postings_range: postings_start_offset..postings_end_offset,
             positions_range: positions_start_offset..positions_end_offset,
         })
@@ -69,10 +76,25 @@ mod tests {
     use super::TermInfo;
     use crate::tests::fixed_size_test;
 
-    // TODO add serialize/deserialize test for terminfo
+    use common::BinarySerializable;
 
     #[test]
     fn test_fixed_size() {
         fixed_size_test::<TermInfo>();
     }
+
+    #[test]
+    fn test_term_info_serialization() {
+        let term_info = TermInfo {
+            doc_freq: 13,
+            total_term_freq: 1_000_000_000_000,
+            postings_range: 100..130,
+            positions_range: 1_000..1_200,
+        };
+        let mut buffer = Vec::new();
+        term_info.serialize(&mut buffer).unwrap();
+        let mut cursor: &[u8] = &buffer[..];
+        assert_eq!(TermInfo::deserialize(&mut cursor).unwrap(), term_info);
+        assert!(cursor.is_empty());
+    }
 }
@@ -5,6 +5,7 @@ use crate::Searcher;
 use crate::Term;
 use serde::Deserialize;
 use serde::Serialize;
+use std::sync::Arc;
 
 const K1: Score = 1.2;
 const B: Score = 0.75;
@@ -36,7 +37,9 @@ pub struct Bm25Params {
 
 #[derive(Clone)]
 pub struct Bm25Weight {
-    idf_explain: Explanation,
+    // Shared, so that creating a scorer for each segment does not
+    // reallocate the explanation tree.
+    idf_explain: Arc<Explanation>,
     weight: Score,
     cache: [Score; 256],
     average_fieldnorm: Score,
@@ -63,14 +66,19 @@ impl Bm25Weight {
             );
         }
 
-        let mut total_num_tokens = 0u64;
-        let mut total_num_docs = 0u64;
-        for segment_reader in searcher.segment_readers() {
-            let inverted_index = segment_reader.inverted_index(field)?;
-            total_num_tokens += inverted_index.total_num_tokens();
-            total_num_docs += u64::from(segment_reader.max_doc());
-        }
-        let average_fieldnorm = total_num_tokens as Score / total_num_docs as Score;
+        let total_num_tokens = searcher.total_num_tokens(field)?;
+        let total_num_docs: u64 = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| u64::from(segment_reader.max_doc()))
+            .sum();
+        let average_fieldnorm = searcher
+            .index()
+            .settings()
+            .missing_fieldnorm()
+            .average_fieldnorm(total_num_tokens, total_num_docs, || {
+                searcher.num_docs_with_tokens(field)
+            })?;
 
         if terms.len() == 1 {
             let term_doc_freq = searcher.doc_freq(&terms[0])?;
@@ -109,7 +117,7 @@ impl Bm25Weight {
     pub(crate) fn new(idf_explain: Explanation, average_fieldnorm: Score) -> Bm25Weight {
         let weight = idf_explain.value() * (1.0 + K1);
         Bm25Weight {
-            idf_explain,
+            idf_explain: Arc::new(idf_explain),
             weight,
             cache: compute_tf_cache(average_fieldnorm),
             average_fieldnorm,
@@ -157,7 +165,7 @@ impl Bm25Weight {
 
         let mut explanation = Explanation::new("TermQuery, product of...", score);
         explanation.add_detail(Explanation::new("(K1+1)", K1 + 1.0));
-        explanation.add_detail(self.idf_explain.clone());
+        explanation.add_detail(self.idf_explain.as_ref().clone());
         explanation.add_detail(tf_explanation);
         explanation
     }
@@ -166,12 +174,75 @@ impl Bm25Weight {
 #[cfg(test)]
 mod tests {
 
-    use super::idf;
-    use crate::{assert_nearly_equals, Score};
+    use super::{idf, Bm25Weight};
+    use crate::collector::TopDocs;
+    use crate::fieldnorm::MissingFieldNorm;
+    use crate::query::{QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{assert_nearly_equals, Index, IndexSettings, Score, Term};
 
     #[test]
     fn test_idf() {
         let score: Score = 2.0;
         assert_nearly_equals!(idf(1, 2), score.ln());
     }
+
+    #[test]
+    fn test_bm25_missing_fieldnorm() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let build_index = |missing_fieldnorm: Option<MissingFieldNorm>| -> crate::Result<Index> {
+            let index = Index::builder()
+                .schema(schema.clone())
+                .settings(IndexSettings {
+                    missing_fieldnorm,
+                    ..IndexSettings::default()
+                })
+                .create_in_ram()?;
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(title => "a", body => "hello world"))?;
+            index_writer.add_document(doc!(title => "b", body => "hello a b c d e f g"))?;
+            index_writer.commit()?;
+            for _ in 0..8 {
+                index_writer.add_document(doc!(title => "c"))?;
+            }
+            // An empty field does not have any token either.
+            index_writer.add_document(doc!(title => "d", body => ""))?;
+            index_writer.commit()?;
+            Ok(index)
+        };
+        let hello = Term::from_field_text(body, "hello");
+
+        let index = build_index(None)?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs_with_tokens(body)?, 2);
+        assert_eq!(searcher.num_docs_with_tokens(title)?, 11);
+        let bm25_weight = Bm25Weight::for_terms(&searcher, &[hello.clone()])?;
+        assert_nearly_equals!(bm25_weight.average_fieldnorm, 10.0 / 11.0);
+        let zero_top_docs = searcher.search(
+            &QueryParser::for_index(&index, vec![body]).parse_query("hello")?,
+            &TopDocs::with_limit(2),
+        )?;
+
+        let index = build_index(Some(MissingFieldNorm::Average))?;
+        assert_eq!(
+            index.load_metas()?.index_settings.missing_fieldnorm,
+            Some(MissingFieldNorm::Average)
+        );
+        let searcher = index.reader()?.searcher();
+        let bm25_weight = Bm25Weight::for_terms(&searcher, &[hello.clone()])?;
+        assert_nearly_equals!(bm25_weight.average_fieldnorm, 5.0);
+        let term_query = TermQuery::new(hello, IndexRecordOption::WithFreqs);
+        let average_top_docs = searcher.search(&term_query, &TopDocs::with_limit(2))?;
+        // The idf is the same, but the long body is less penalized.
+        assert_eq!(zero_top_docs[0].1, average_top_docs[0].1);
+        assert_eq!(zero_top_docs[1].1, average_top_docs[1].1);
+        assert!(average_top_docs[1].0 > zero_top_docs[1].0);
+        assert!(
+            average_top_docs[1].0 / average_top_docs[0].0 > zero_top_docs[1].0 / zero_top_docs[0].0
+        );
+        Ok(())
+    }
 }
@@ -1,5 +1,5 @@
 use crate::query::term_query::TermScorer;
-use crate::query::Scorer;
+use crate::query::{Scorer, StaticRankReader};
 use crate::{DocId, DocSet, Score, TERMINATED};
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -13,11 +13,15 @@ use std::ops::DerefMut;
 /// We always have `before_pivot_len` < `pivot_len`.
 ///
 /// None is returned if we establish that no document can exceed the threshold.
+///
+/// `static_max_score` is the maximum weighted static rank of the documents,
+/// which is added to the score of any document.
 fn find_pivot_doc(
     term_scorers: &[TermScorerWithMaxScore],
     threshold: Score,
+    static_max_score: Score,
 ) -> Option<(usize, usize, DocId)> {
-    let mut max_score = 0.0;
+    let mut max_score = static_max_score;
     let mut before_pivot_len = 0;
     let mut pivot_doc = TERMINATED;
     while before_pivot_len < term_scorers.len() {
@@ -145,11 +149,16 @@ fn advance_all_scorers_on_pivot(term_scorers: &mut Vec<TermScorerWithMaxScore>,
 /// Implements the WAND (Weak AND) algorithm for dynamic pruning
 /// described in the paper "Faster Top-k Document Retrieval Using Block-Max Indexes".
 /// Link: http://engineering.nyu.edu/~suel/papers/bmw.pdf
+///
+/// If `static_ranks` is given, the weighted static ranks of the documents are added to
+/// their scores, and the maximum static ranks of the blocks to their block max scores.
 pub fn block_wand(
     mut scorers: Vec<TermScorer>,
     mut threshold: Score,
+    static_ranks: Option<&StaticRankReader>,
     callback: &mut dyn FnMut(u32, Score) -> Score,
 ) {
+    let static_max_score = static_ranks.map(StaticRankReader::max_score).unwrap_or(0.0);
     let mut scorers: Vec<TermScorerWithMaxScore> = scorers
         .iter_mut()
         .map(TermScorerWithMaxScore::from)
@@ -158,19 +167,27 @@ pub fn block_wand(
     // At this point we need to ensure that the scorers are sorted!
     debug_assert!(is_sorted(scorers.iter().map(|scorer| scorer.doc())));
     while let Some((before_pivot_len, pivot_len, pivot_doc)) =
-        find_pivot_doc(&scorers[..], threshold)
+        find_pivot_doc(&scorers[..], threshold, static_max_score)
     {
         debug_assert!(is_sorted(scorers.iter().map(|scorer| scorer.doc())));
         debug_assert_ne!(pivot_doc, TERMINATED);
         debug_assert!(before_pivot_len < pivot_len);
 
-        let block_max_score_upperbound: Score = scorers[..pivot_len]
+        let mut block_max_score_upperbound: Score = scorers[..pivot_len]
             .iter_mut()
             .map(|scorer| {
                 scorer.shallow_seek(pivot_doc);
                 scorer.block_max_score()
             })
             .sum();
+        if let Some(static_ranks) = static_ranks {
+            // The documents up to the end of the current blocks belong to one of these blocks,
+            // so their static rank is bounded by the largest block max static rank.
+            block_max_score_upperbound += scorers[..pivot_len]
+                .iter()
+                .map(|scorer| static_ranks.block_max_score(scorer.block_max_static_rank()))
+                .fold(0.0, Score::max);
+        }
 
         // Beware after shallow advance, skip readers can be in advance compared to
         // the segment posting lists.
@@ -198,10 +215,13 @@ pub fn block_wand(
         }
 
         // At this point, all scorers are positioned on the doc.
-        let score = scorers[..pivot_len]
+        let mut score: Score = scorers[..pivot_len]
             .iter_mut()
             .map(|scorer| scorer.score())
             .sum();
+        if let Some(static_ranks) = static_ranks {
+            score += static_ranks.score(pivot_doc);
+        }
 
         if score > threshold {
             threshold = callback(pivot_doc, score);
@@ -223,13 +243,19 @@ pub fn block_wand(
 pub fn block_wand_single_scorer(
     mut scorer: TermScorer,
     mut threshold: Score,
+    static_ranks: Option<&StaticRankReader>,
     callback: &mut dyn FnMut(u32, Score) -> Score,
 ) {
+    let block_max_static_score = |scorer: &TermScorer| {
+        static_ranks
+            .map(|static_ranks| static_ranks.block_max_score(scorer.block_max_static_rank()))
+            .unwrap_or(0.0)
+    };
     let mut doc = scorer.doc();
     loop {
         // We position the scorer on a block that can reach
         // the threshold.
-        while scorer.block_max_score() < threshold {
+        while scorer.block_max_score() + block_max_static_score(&scorer) < threshold {
             let last_doc_in_block = scorer.last_doc_in_block();
             if last_doc_in_block == TERMINATED {
                 return;
@@ -243,7 +269,10 @@ pub fn block_wand_single_scorer(
             break;
         }
         loop {
-            let score = scorer.score();
+            let mut score = scorer.score();
+            if let Some(static_ranks) = static_ranks {
+                score += static_ranks.score(doc);
+            }
             if score > threshold {
                 threshold = callback(doc, score);
             }
@@ -361,9 +390,9 @@ mod tests {
 
         if term_scorers.len() == 1 {
             let scorer = term_scorers.pop().unwrap();
-            super::block_wand_single_scorer(scorer, Score::MIN, callback);
+            super::block_wand_single_scorer(scorer, Score::MIN, None, callback);
         } else {
-            super::block_wand(term_scorers, Score::MIN, callback);
+            super::block_wand(term_scorers, Score::MIN, None, callback);
         }
         checkpoints
     }
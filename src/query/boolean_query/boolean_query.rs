@@ -1,4 +1,5 @@
 use super::boolean_weight::BooleanWeight;
+use crate::query::BoostQuery;
 use crate::query::Occur;
 use crate::query::Query;
 use crate::query::TermQuery;
@@ -6,7 +7,73 @@ use crate::query::Weight;
 use crate::schema::IndexRecordOption;
 use crate::schema::Term;
 use crate::Searcher;
-use std::collections::BTreeMap;
+use crate::{Score, TantivyError};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Minimum number of `Should` clauses of a [`BooleanQuery`] a document must match.
+///
+/// It is either an absolute number of clauses, or a percentage of the number of
+/// `Should` clauses of the query, rounded down.
+/// It can also be parsed from a string such as `"2"` or `"75%"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimumShouldMatch {
+    /// Absolute number of `Should` clauses.
+    Count(usize),
+    /// Percentage of the number of `Should` clauses, between 0 and 100.
+    Percentage(u8),
+}
+
+impl MinimumShouldMatch {
+    /// Returns the minimum number of clauses to match, out of `num_should_clauses`.
+    pub fn resolve(self, num_should_clauses: usize) -> usize {
+        match self {
+            MinimumShouldMatch::Count(count) => count,
+            MinimumShouldMatch::Percentage(percentage) => {
+                num_should_clauses * (percentage.min(100) as usize) / 100
+            }
+        }
+    }
+}
+
+/// How a [`BooleanQuery`] combines the scores of the clauses matching a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreMode {
+    /// Sums the scores of the matching clauses. This is the default.
+    Sum,
+    /// Keeps the best score among the matching `Should` clauses and the `Must` clauses.
+    ///
+    /// The scores of several `Must` clauses are still summed together, as all of them
+    /// match. This mode disables the block WAND optimization of the unions of terms.
+    Max,
+}
+
+impl FromStr for MinimumShouldMatch {
+    type Err = TantivyError;
+
+    fn from_str(minimum_should_match: &str) -> crate::Result<MinimumShouldMatch> {
+        let invalid_argument = || {
+            TantivyError::InvalidArgument(format!(
+                "Invalid minimum should match {:?}",
+                minimum_should_match
+            ))
+        };
+        let minimum_should_match = minimum_should_match.trim();
+        if let Some(percentage) = minimum_should_match.strip_suffix('%') {
+            let percentage: u8 = percentage.trim().parse().map_err(|_| invalid_argument())?;
+            if percentage > 100 {
+                return Err(invalid_argument());
+            }
+            Ok(MinimumShouldMatch::Percentage(percentage))
+        } else {
+            let count: usize = minimum_should_match
+                .parse()
+                .map_err(|_| invalid_argument())?;
+            Ok(MinimumShouldMatch::Count(count))
+        }
+    }
+}
 
 /// The boolean query returns a set of documents
 /// that matches the Boolean combination of constituent subqueries.
@@ -20,6 +87,10 @@ use std::collections::BTreeMap;
 /// * match at least one of the subqueries that is not
 /// a `MustNot` occurence.
 ///
+/// A [`MinimumShouldMatch`] can be set, so that the matching documents are also
+/// required to match at least a number, or a percentage, of the `Should` subqueries.
+/// In that case, `Should` subqueries are not optional anymore when the query
+/// has `Must` subqueries.
 ///
 /// You can combine other query types and their `Occur`ances into one `BooleanQuery`
 ///
@@ -126,18 +197,37 @@ use std::collections::BTreeMap;
 ///    Ok(())
 ///}
 /// ```
-#[derive(Debug)]
 pub struct BooleanQuery {
     subqueries: Vec<(Occur, Box<dyn Query>)>,
+    minimum_should_match: Option<MinimumShouldMatch>,
+    score_mode: ScoreMode,
 }
 
 impl Clone for BooleanQuery {
     fn clone(&self) -> Self {
-        self.subqueries
-            .iter()
-            .map(|(occur, subquery)| (*occur, subquery.box_clone()))
-            .collect::<Vec<_>>()
-            .into()
+        BooleanQuery {
+            subqueries: self
+                .subqueries
+                .iter()
+                .map(|(occur, subquery)| (*occur, subquery.box_clone()))
+                .collect(),
+            minimum_should_match: self.minimum_should_match,
+            score_mode: self.score_mode,
+        }
+    }
+}
+
+impl fmt::Debug for BooleanQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug_struct = f.debug_struct("BooleanQuery");
+        debug_struct.field("subqueries", &self.subqueries);
+        if let Some(minimum_should_match) = self.minimum_should_match {
+            debug_struct.field("minimum_should_match", &minimum_should_match);
+        }
+        if self.score_mode != ScoreMode::Sum {
+            debug_struct.field("score_mode", &self.score_mode);
+        }
+        debug_struct.finish()
     }
 }
 
@@ -156,7 +246,16 @@ impl Query for BooleanQuery {
                 Ok((*occur, subquery.weight(searcher, scoring_enabled)?))
             })
             .collect::<crate::Result<_>>()?;
-        Ok(Box::new(BooleanWeight::new(sub_weights, scoring_enabled)))
+        let minimum_should_match = self
+            .minimum_should_match
+            .map(|minimum_should_match| minimum_should_match.resolve(self.num_should_clauses()))
+            .unwrap_or(0);
+        Ok(Box::new(BooleanWeight::new(
+            sub_weights,
+            scoring_enabled,
+            minimum_should_match,
+            self.score_mode,
+        )))
     }
 
     fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
@@ -164,12 +263,212 @@ impl Query for BooleanQuery {
             subquery.query_terms(terms);
         }
     }
+
+    /// Rewrites the subqueries, and then:
+    /// - flattens the nested boolean queries that are equivalent to their clauses,
+    /// - merges the duplicate term clauses into a boosted one,
+    /// - replaces a boolean query with a single clause by this clause.
+    fn rewrite(&self, searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        let rewritten_subqueries: Vec<Option<Box<dyn Query>>> = self
+            .subqueries
+            .iter()
+            .map(|(_occur, subquery)| subquery.rewrite(searcher))
+            .collect::<crate::Result<_>>()?;
+        let needs_rewrite = rewritten_subqueries.iter().any(Option::is_some)
+            || self.is_single_clause(&self.subqueries)
+            || self.has_duplicate_term_clauses(&self.subqueries)
+            || self.subqueries.iter().any(|(occur, subquery)| {
+                self.flattened_clauses(*occur, subquery.as_ref()).is_some()
+            });
+        if !needs_rewrite {
+            return Ok(None);
+        }
+        let mut subqueries: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for ((occur, subquery), rewritten_subquery) in
+            self.subqueries.iter().zip(rewritten_subqueries)
+        {
+            let subquery = rewritten_subquery.unwrap_or_else(|| subquery.box_clone());
+            match self.flattened_clauses(*occur, subquery.as_ref()) {
+                Some(nested_clauses) => {
+                    for (nested_occur, nested_subquery) in nested_clauses {
+                        let flattened_occur = if *occur == Occur::MustNot {
+                            Occur::MustNot
+                        } else {
+                            *nested_occur
+                        };
+                        subqueries.push((flattened_occur, nested_subquery.box_clone()));
+                    }
+                }
+                None => subqueries.push((*occur, subquery)),
+            }
+        }
+        let mut subqueries = self.merge_duplicate_term_clauses(subqueries);
+        if self.is_single_clause(&subqueries) {
+            return Ok(subqueries.pop().map(|(_occur, subquery)| subquery));
+        }
+        Ok(Some(Box::new(BooleanQuery {
+            subqueries,
+            minimum_should_match: self.minimum_should_match,
+            score_mode: self.score_mode,
+        })))
+    }
 }
 
 impl BooleanQuery {
     /// Creates a new boolean query.
     pub fn new(subqueries: Vec<(Occur, Box<dyn Query>)>) -> BooleanQuery {
-        BooleanQuery { subqueries }
+        BooleanQuery {
+            subqueries,
+            minimum_should_match: None,
+            score_mode: ScoreMode::Sum,
+        }
+    }
+
+    /// Sets the minimum number of `Should` subqueries a document must match.
+    pub fn with_minimum_should_match(
+        mut self,
+        minimum_should_match: MinimumShouldMatch,
+    ) -> BooleanQuery {
+        self.minimum_should_match = Some(minimum_should_match);
+        self
+    }
+
+    /// Returns the minimum number of `Should` subqueries a document must match, if set.
+    pub fn minimum_should_match(&self) -> Option<MinimumShouldMatch> {
+        self.minimum_should_match
+    }
+
+    /// Sets how the scores of the matching clauses are combined.
+    pub fn with_score_mode(mut self, score_mode: ScoreMode) -> BooleanQuery {
+        self.score_mode = score_mode;
+        self
+    }
+
+    /// Returns how the scores of the matching clauses are combined.
+    pub fn score_mode(&self) -> ScoreMode {
+        self.score_mode
+    }
+
+    fn num_should_clauses(&self) -> usize {
+        self.subqueries
+            .iter()
+            .filter(|(occur, _)| *occur == Occur::Should)
+            .count()
+    }
+
+    /// Returns the clauses replacing the clause `(occur, subquery)`, if `subquery` is a
+    /// boolean query that can be flattened into this one without changing its matches
+    /// nor its scores.
+    fn flattened_clauses<'a>(
+        &self,
+        occur: Occur,
+        subquery: &'a dyn Query,
+    ) -> Option<&'a [(Occur, Box<dyn Query>)]> {
+        let nested_query = subquery.downcast_ref::<BooleanQuery>()?;
+        if nested_query.minimum_should_match.is_some() || nested_query.subqueries.is_empty() {
+            return None;
+        }
+        if nested_query.score_mode != self.score_mode && occur != Occur::MustNot {
+            return None;
+        }
+        let has_only = |occurs: &[Occur]| {
+            nested_query
+                .subqueries
+                .iter()
+                .all(|(nested_occur, _)| occurs.contains(nested_occur))
+        };
+        let can_flatten = match occur {
+            // +(+a -b) is equivalent to +a -b, as long as there is a required clause.
+            Occur::Must => {
+                has_only(&[Occur::Must, Occur::MustNot])
+                    && nested_query
+                        .subqueries
+                        .iter()
+                        .any(|(nested_occur, _)| *nested_occur == Occur::Must)
+            }
+            // (a b) as an optional clause is equivalent to a b, unless the number
+            // of optional clauses matters.
+            Occur::Should => self.minimum_should_match.is_none() && has_only(&[Occur::Should]),
+            // -(a b) is equivalent to -a -b.
+            Occur::MustNot => has_only(&[Occur::Should]),
+        };
+        if can_flatten {
+            Some(&nested_query.subqueries[..])
+        } else {
+            None
+        }
+    }
+
+    fn is_single_clause(&self, subqueries: &[(Occur, Box<dyn Query>)]) -> bool {
+        self.minimum_should_match.is_none()
+            && subqueries.len() == 1
+            && subqueries[0].0 != Occur::MustNot
+    }
+
+    /// Returns the key identifying the term clauses that can be merged, if the clause
+    /// is a term query.
+    ///
+    /// The `Should` clauses are not merged if the number of optional clauses matters.
+    fn term_clause_key(
+        &self,
+        occur: Occur,
+        subquery: &dyn Query,
+    ) -> Option<(Occur, Term, IndexRecordOption)> {
+        if occur == Occur::Should && self.minimum_should_match.is_some() {
+            return None;
+        }
+        let term_query = subquery.downcast_ref::<TermQuery>()?;
+        Some((
+            occur,
+            term_query.term().clone(),
+            term_query.index_record_option(),
+        ))
+    }
+
+    fn has_duplicate_term_clauses(&self, subqueries: &[(Occur, Box<dyn Query>)]) -> bool {
+        let mut term_clause_keys = HashSet::new();
+        subqueries
+            .iter()
+            .filter_map(|(occur, subquery)| self.term_clause_key(*occur, subquery.as_ref()))
+            .any(|term_clause_key| !term_clause_keys.insert(term_clause_key))
+    }
+
+    /// Merges the identical term clauses. As the scores of the clauses are summed,
+    /// `n` identical scoring clauses are replaced by a single one boosted by `n`.
+    /// With `ScoreMode::Max`, identical `Should` clauses are replaced by a single one.
+    fn merge_duplicate_term_clauses(
+        &self,
+        subqueries: Vec<(Occur, Box<dyn Query>)>,
+    ) -> Vec<(Occur, Box<dyn Query>)> {
+        let mut merged_subqueries: Vec<(Occur, Box<dyn Query>, usize)> = Vec::new();
+        let mut term_clause_ords: HashMap<(Occur, Term, IndexRecordOption), usize> = HashMap::new();
+        for (occur, subquery) in subqueries {
+            if let Some(term_clause_key) = self.term_clause_key(occur, subquery.as_ref()) {
+                if let Some(&ord) = term_clause_ords.get(&term_clause_key) {
+                    merged_subqueries[ord].2 += 1;
+                    continue;
+                }
+                term_clause_ords.insert(term_clause_key, merged_subqueries.len());
+            }
+            merged_subqueries.push((occur, subquery, 1));
+        }
+        merged_subqueries
+            .into_iter()
+            .map(|(occur, subquery, count)| {
+                let is_summed = match occur {
+                    Occur::Must => true,
+                    Occur::Should => self.score_mode == ScoreMode::Sum,
+                    Occur::MustNot => false,
+                };
+                if count > 1 && is_summed {
+                    let boosted_subquery: Box<dyn Query> =
+                        Box::new(BoostQuery::new(subquery, count as Score));
+                    (occur, boosted_subquery)
+                } else {
+                    (occur, subquery)
+                }
+            })
+            .collect()
     }
 
     /// Returns the intersection of the queries.
@@ -206,11 +505,11 @@ impl BooleanQuery {
 
 #[cfg(test)]
 mod tests {
-    use super::BooleanQuery;
-    use crate::collector::DocSetCollector;
-    use crate::query::{QueryClone, TermQuery};
+    use super::{BooleanQuery, MinimumShouldMatch, ScoreMode};
+    use crate::collector::{Count, DocSetCollector, TopDocs};
+    use crate::query::{BoostQuery, Occur, Query, QueryClone, TermQuery};
     use crate::schema::{IndexRecordOption, Schema, TEXT};
-    use crate::{DocAddress, Index, Term};
+    use crate::{DocAddress, DocId, DocSet, Index, Score, Searcher, Term, TERMINATED};
 
     fn create_test_index() -> crate::Result<Index> {
         let mut schema_builder = Schema::builder();
@@ -285,4 +584,257 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_minimum_should_match_parse() {
+        assert_eq!(
+            "2".parse::<MinimumShouldMatch>().ok(),
+            Some(MinimumShouldMatch::Count(2))
+        );
+        assert_eq!(
+            "75%".parse::<MinimumShouldMatch>().ok(),
+            Some(MinimumShouldMatch::Percentage(75))
+        );
+        assert!("101%".parse::<MinimumShouldMatch>().is_err());
+        assert!("-1".parse::<MinimumShouldMatch>().is_err());
+        assert!("most".parse::<MinimumShouldMatch>().is_err());
+        assert_eq!(MinimumShouldMatch::Percentage(75).resolve(3), 2);
+        assert_eq!(MinimumShouldMatch::Percentage(100).resolve(3), 3);
+        assert_eq!(MinimumShouldMatch::Count(2).resolve(3), 2);
+    }
+
+    #[test]
+    fn test_minimum_should_match() -> crate::Result<()> {
+        let index = create_test_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term_query = |text_val: &str| {
+            TermQuery::new(
+                Term::from_field_text(text, text_val),
+                IndexRecordOption::WithFreqs,
+            )
+            .box_clone()
+        };
+        let matching_docs = |query: &BooleanQuery| -> crate::Result<Vec<u32>> {
+            let mut docs: Vec<u32> = searcher
+                .search(query, &DocSetCollector)?
+                .into_iter()
+                .map(|doc_address| doc_address.doc_id)
+                .collect();
+            docs.sort_unstable();
+            // The scored path has to agree with the unscored one.
+            let mut scored_docs: Vec<u32> = searcher
+                .search(query, &TopDocs::with_limit(10))?
+                .into_iter()
+                .map(|(_, doc_address)| doc_address.doc_id)
+                .collect();
+            scored_docs.sort_unstable();
+            assert_eq!(docs, scored_docs);
+            Ok(docs)
+        };
+        let union_abc =
+            BooleanQuery::union(vec![term_query("a"), term_query("b"), term_query("c")]);
+        assert_eq!(matching_docs(&union_abc)?, vec![0, 1, 2, 3]);
+        assert_eq!(
+            matching_docs(
+                &union_abc
+                    .clone()
+                    .with_minimum_should_match(MinimumShouldMatch::Count(2))
+            )?,
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            matching_docs(
+                &union_abc
+                    .clone()
+                    .with_minimum_should_match(MinimumShouldMatch::Percentage(75))
+            )?,
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            matching_docs(
+                &union_abc
+                    .clone()
+                    .with_minimum_should_match(MinimumShouldMatch::Percentage(100))
+            )?,
+            Vec::<u32>::new()
+        );
+        assert_eq!(
+            matching_docs(
+                &union_abc
+                    .clone()
+                    .with_minimum_should_match(MinimumShouldMatch::Count(4))
+            )?,
+            Vec::<u32>::new()
+        );
+
+        // With `Must` clauses, the `Should` clauses are optional unless
+        // a minimum should match is set.
+        let must_c_should_bd = BooleanQuery::new(vec![
+            (Occur::Must, term_query("c")),
+            (Occur::Should, term_query("b")),
+            (Occur::Should, term_query("d")),
+        ]);
+        assert_eq!(matching_docs(&must_c_should_bd)?, vec![0, 1]);
+        let must_c_should_bd =
+            must_c_should_bd.with_minimum_should_match(MinimumShouldMatch::Count(1));
+        assert_eq!(matching_docs(&must_c_should_bd)?, vec![0]);
+        assert_eq!(
+            must_c_should_bd.minimum_should_match(),
+            Some(MinimumShouldMatch::Count(1))
+        );
+        Ok(())
+    }
+
+    fn scored_docs(query: &dyn Query, searcher: &Searcher) -> crate::Result<Vec<(DocId, Score)>> {
+        let weight = query.weight(searcher, true)?;
+        let mut scorer = weight.scorer(searcher.segment_reader(0), 1.0)?;
+        let mut scored_docs = Vec::new();
+        while scorer.doc() != TERMINATED {
+            scored_docs.push((scorer.doc(), scorer.score()));
+            scorer.advance();
+        }
+        Ok(scored_docs)
+    }
+
+    #[test]
+    fn test_boolean_query_rewrite() -> crate::Result<()> {
+        let index = create_test_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term_query = |text_str: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, text_str),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, term_query("a")),
+                    (Occur::MustNot, term_query("d")),
+                ])),
+            ),
+            (
+                Occur::Should,
+                Box::new(BooleanQuery::union(vec![term_query("b"), term_query("c")])),
+            ),
+            (Occur::Should, term_query("b")),
+            (
+                Occur::MustNot,
+                Box::new(BooleanQuery::union(vec![term_query("x"), term_query("y")])),
+            ),
+        ]);
+        let rewritten_query = query.rewrite(&searcher)?.unwrap();
+        let rewritten_boolean_query = rewritten_query.downcast_ref::<BooleanQuery>().unwrap();
+        let occurs: Vec<Occur> = rewritten_boolean_query
+            .clauses()
+            .iter()
+            .map(|(occur, _)| *occur)
+            .collect();
+        assert_eq!(
+            occurs,
+            vec![
+                Occur::Must,
+                Occur::MustNot,
+                Occur::Should,
+                Occur::Should,
+                Occur::MustNot,
+                Occur::MustNot
+            ]
+        );
+        // The duplicate `b` clauses are merged into a boosted one.
+        assert!(rewritten_boolean_query.clauses()[2]
+            .1
+            .downcast_ref::<BoostQuery>()
+            .is_some());
+        assert!(rewritten_boolean_query.rewrite(&searcher)?.is_none());
+        let original_scored_docs = scored_docs(&query, &searcher)?;
+        assert_eq!(original_scored_docs.len(), 2);
+        assert_eq!(
+            original_scored_docs,
+            scored_docs(rewritten_query.as_ref(), &searcher)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_boolean_query_rewrite_single_clause() -> crate::Result<()> {
+        let index = create_test_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term_a: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::Basic,
+        ));
+        let must_a = BooleanQuery::new(vec![(Occur::Must, term_a.box_clone())]);
+        let rewritten_query = must_a.rewrite(&searcher)?.unwrap();
+        assert!(rewritten_query.downcast_ref::<TermQuery>().is_some());
+        // A lone `MustNot` clause matches nothing.
+        let must_not_a = BooleanQuery::new(vec![(Occur::MustNot, term_a.box_clone())]);
+        assert!(must_not_a.rewrite(&searcher)?.is_none());
+        // The number of optional clauses matters with a minimum number of
+        // should match clauses.
+        let should_a_a = BooleanQuery::union(vec![term_a.box_clone(), term_a.box_clone()])
+            .with_minimum_should_match(MinimumShouldMatch::Count(2));
+        assert!(should_a_a.rewrite(&searcher)?.is_none());
+        assert_eq!(searcher.search(&should_a_a, &Count)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_boolean_query_score_mode() -> crate::Result<()> {
+        let index = create_test_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term_query = |text_str: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, text_str),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let score = |query: &dyn Query, doc_id: DocId| -> crate::Result<Score> {
+            let top_docs = searcher.search(query, &TopDocs::with_limit(4))?;
+            Ok(top_docs
+                .iter()
+                .find(|(_, doc_address)| *doc_address == DocAddress::new(0, doc_id))
+                .map(|(score, _)| *score)
+                .unwrap())
+        };
+        let score_a = score(term_query("a").as_ref(), 1)?;
+        let score_c = score(term_query("c").as_ref(), 1)?;
+        let sum_query = BooleanQuery::union(vec![term_query("a"), term_query("c")]);
+        let max_query = sum_query.clone().with_score_mode(ScoreMode::Max);
+        assert_eq!(max_query.score_mode(), ScoreMode::Max);
+        crate::assert_nearly_equals!(score(&sum_query, 1)?, score_a + score_c);
+        crate::assert_nearly_equals!(score(&max_query, 1)?, score_a.max(score_c));
+        crate::assert_nearly_equals!(score(&max_query, 3)?, score_a);
+        assert_eq!(scored_docs(&max_query, &searcher)?.len(), 4);
+
+        // With a required clause, the best score among the clauses is kept.
+        let must_max_query = BooleanQuery::new(vec![
+            (Occur::Must, term_query("a")),
+            (Occur::Should, term_query("c")),
+        ])
+        .with_score_mode(ScoreMode::Max);
+        crate::assert_nearly_equals!(score(&must_max_query, 1)?, score_a.max(score_c));
+
+        // Rewriting does not change the scores.
+        let max_query_with_duplicates = BooleanQuery::new(vec![
+            (Occur::Should, term_query("a")),
+            (Occur::Should, term_query("a")),
+            (
+                Occur::Should,
+                Box::new(BooleanQuery::union(vec![term_query("b"), term_query("c")])),
+            ),
+        ])
+        .with_score_mode(ScoreMode::Max);
+        let rewritten_query = max_query_with_duplicates.rewrite(&searcher)?.unwrap();
+        assert_eq!(
+            scored_docs(&max_query_with_duplicates, &searcher)?,
+            scored_docs(rewritten_query.as_ref(), &searcher)?
+        );
+        Ok(())
+    }
 }
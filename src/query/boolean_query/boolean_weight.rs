@@ -1,7 +1,13 @@
+use super::min_should_match_scorer::MinShouldMatchScorer;
+use super::ScoreMode;
 use crate::core::SegmentReader;
 use crate::postings::FreqReadingOption;
 use crate::query::explanation::does_not_match;
-use crate::query::score_combiner::{DoNothingCombiner, ScoreCombiner, SumWithCoordsCombiner};
+use crate::query::intersection::intersect_in_bitset;
+use crate::query::score_combiner::{
+    DoNothingCombiner, MaxCombiner, ScoreCombiner, SumWithCoordsCombiner,
+};
+use crate::query::static_rank_query::StaticRankScorer;
 use crate::query::term_query::TermScorer;
 use crate::query::weight::{for_each_pruning_scorer, for_each_scorer};
 use crate::query::EmptyScorer;
@@ -9,14 +15,18 @@ use crate::query::Exclude;
 use crate::query::Occur;
 use crate::query::RequiredOptionalScorer;
 use crate::query::Scorer;
+use crate::query::StaticRankReader;
 use crate::query::Union;
 use crate::query::Weight;
 use crate::query::{intersect_scorers, Explanation};
+use crate::query::{BufferedUnion, ConstScorer};
 use crate::{DocId, Score};
 use std::collections::HashMap;
 
 enum SpecializedScorer {
     TermUnion(Vec<TermScorer>),
+    // Union of term scorers, minus the documents of the exclusion scorer.
+    TermUnionExclude(Vec<TermScorer>, Box<dyn Scorer>),
     Other(Box<dyn Scorer>),
 }
 
@@ -52,12 +62,55 @@ where
     SpecializedScorer::Other(Box::new(Union::<_, TScoreCombiner>::from(scorers)))
 }
 
+// From this number of clauses, unions that do not need to be scored
+// are computed eagerly with a `BufferedUnion`.
+const BUFFERED_UNION_MIN_NUM_SCORERS: usize = 16;
+
+fn unscored_union(scorers: Vec<Box<dyn Scorer>>, max_doc: DocId) -> SpecializedScorer {
+    if scorers.len() >= BUFFERED_UNION_MIN_NUM_SCORERS {
+        let buffered_union = BufferedUnion::build(scorers, max_doc);
+        return SpecializedScorer::Other(Box::new(ConstScorer::from(buffered_union)));
+    }
+    scorer_union::<DoNothingCombiner>(scorers)
+}
+
+// Seeking a docset costs more than advancing it: it goes through
+// its skip list, and decodes a new block for most of the targets.
+const SEEK_COST: u64 = 4;
+
+/// Returns true if the unscored intersection of `scorers` is cheaper to compute
+/// by going through all of the scorers into a bitset, than by driving the
+/// intersection from its rarest scorer and seeking the other ones.
+///
+/// This is the case when the scorers have comparable numbers of docs.
+fn prefers_bitset_intersection(scorers: &[Box<dyn Scorer>], max_doc: DocId) -> bool {
+    let driving_cost = match scorers.iter().map(|scorer| scorer.cost()).min() {
+        Some(driving_cost) => driving_cost,
+        None => return false,
+    };
+    let seeking_cost: u64 = driving_cost * (1 + SEEK_COST * (scorers.len() as u64 - 1));
+    let bitset_cost: u64 = scorers.iter().map(|scorer| scorer.cost()).sum::<u64>()
+        + u64::from(max_doc) / 64 * scorers.len() as u64;
+    bitset_cost < seeking_cost
+}
+
+fn unscored_intersection(scorers: Vec<Box<dyn Scorer>>, max_doc: DocId) -> Box<dyn Scorer> {
+    if scorers.len() > 1 && prefers_bitset_intersection(&scorers, max_doc) {
+        return Box::new(ConstScorer::from(intersect_in_bitset(scorers, max_doc)));
+    }
+    intersect_scorers(scorers)
+}
+
 fn into_box_scorer<TScoreCombiner: ScoreCombiner>(scorer: SpecializedScorer) -> Box<dyn Scorer> {
     match scorer {
         SpecializedScorer::TermUnion(term_scorers) => {
             let union_scorer = Union::<TermScorer, TScoreCombiner>::from(term_scorers);
             Box::new(union_scorer)
         }
+        SpecializedScorer::TermUnionExclude(term_scorers, exclude_scorer) => {
+            let union_scorer = Union::<TermScorer, TScoreCombiner>::from(term_scorers);
+            Box::new(Exclude::new(union_scorer, exclude_scorer))
+        }
         SpecializedScorer::Other(scorer) => scorer,
     }
 }
@@ -65,16 +118,37 @@ fn into_box_scorer<TScoreCombiner: ScoreCombiner>(scorer: SpecializedScorer) ->
 pub struct BooleanWeight {
     weights: Vec<(Occur, Box<dyn Weight>)>,
     scoring_enabled: bool,
+    // Minimum number of `Should` clauses a document must match.
+    // If it is 0, the `Should` clauses are optional when there are `Must` clauses.
+    minimum_should_match: usize,
+    score_mode: ScoreMode,
 }
 
 impl BooleanWeight {
-    pub fn new(weights: Vec<(Occur, Box<dyn Weight>)>, scoring_enabled: bool) -> BooleanWeight {
+    pub fn new(
+        weights: Vec<(Occur, Box<dyn Weight>)>,
+        scoring_enabled: bool,
+        minimum_should_match: usize,
+        score_mode: ScoreMode,
+    ) -> BooleanWeight {
         BooleanWeight {
             weights,
             scoring_enabled,
+            minimum_should_match,
+            score_mode,
         }
     }
 
+    /// Returns true iff no document can match the minimum number of `Should` clauses.
+    fn minimum_should_match_unreachable(&self) -> bool {
+        let num_should_clauses = self
+            .weights
+            .iter()
+            .filter(|(occur, _)| *occur == Occur::Should)
+            .count();
+        self.minimum_should_match > num_should_clauses
+    }
+
     fn per_occur_scorers(
         &self,
         reader: &SegmentReader,
@@ -96,24 +170,52 @@ impl BooleanWeight {
         reader: &SegmentReader,
         boost: Score,
     ) -> crate::Result<SpecializedScorer> {
+        if self.minimum_should_match_unreachable() {
+            return Ok(SpecializedScorer::Other(Box::new(EmptyScorer)));
+        }
         let mut per_occur_scorers = self.per_occur_scorers(reader, boost)?;
 
-        let should_scorer_opt: Option<SpecializedScorer> = per_occur_scorers
-            .remove(&Occur::Should)
-            .map(scorer_union::<TScoreCombiner>);
+        let max_doc = reader.max_doc();
+
+        let should_scorer_opt: Option<SpecializedScorer> =
+            per_occur_scorers.remove(&Occur::Should).map(|scorers| {
+                if self.minimum_should_match > 1 {
+                    SpecializedScorer::Other(Box::new(MinShouldMatchScorer::<
+                        Box<dyn Scorer>,
+                        TScoreCombiner,
+                    >::new(
+                        scorers, self.minimum_should_match
+                    )))
+                } else if self.scoring_enabled {
+                    scorer_union::<TScoreCombiner>(scorers)
+                } else {
+                    unscored_union(scorers, max_doc)
+                }
+            });
 
         let exclude_scorer_opt: Option<Box<dyn Scorer>> = per_occur_scorers
             .remove(&Occur::MustNot)
-            .map(scorer_union::<DoNothingCombiner>)
+            .map(|scorers| unscored_union(scorers, max_doc))
             .map(into_box_scorer::<DoNothingCombiner>);
 
-        let must_scorer_opt: Option<Box<dyn Scorer>> = per_occur_scorers
-            .remove(&Occur::Must)
-            .map(intersect_scorers);
+        let must_scorer_opt: Option<Box<dyn Scorer>> =
+            per_occur_scorers.remove(&Occur::Must).map(|scorers| {
+                if self.scoring_enabled {
+                    intersect_scorers(scorers)
+                } else {
+                    unscored_intersection(scorers, max_doc)
+                }
+            });
 
         let positive_scorer: SpecializedScorer = match (should_scorer_opt, must_scorer_opt) {
             (Some(should_scorer), Some(must_scorer)) => {
-                if self.scoring_enabled {
+                if self.minimum_should_match > 0 {
+                    // The `Should` clauses are required.
+                    SpecializedScorer::Other(intersect_scorers(vec![
+                        must_scorer,
+                        into_box_scorer::<TScoreCombiner>(should_scorer),
+                    ]))
+                } else if self.scoring_enabled {
                     SpecializedScorer::Other(Box::new(RequiredOptionalScorer::<
                         Box<dyn Scorer>,
                         Box<dyn Scorer>,
@@ -133,22 +235,88 @@ impl BooleanWeight {
             }
         };
 
-        if let Some(exclude_scorer) = exclude_scorer_opt {
-            let positive_scorer_boxed: Box<dyn Scorer> =
-                into_box_scorer::<TScoreCombiner>(positive_scorer);
-            Ok(SpecializedScorer::Other(Box::new(Exclude::new(
-                positive_scorer_boxed,
-                exclude_scorer,
-            ))))
-        } else {
-            Ok(positive_scorer)
+        match (positive_scorer, exclude_scorer_opt) {
+            (SpecializedScorer::TermUnion(term_scorers), Some(exclude_scorer)) => {
+                // Kept specialized, so that block wand can be used for the union.
+                Ok(SpecializedScorer::TermUnionExclude(
+                    term_scorers,
+                    exclude_scorer,
+                ))
+            }
+            (positive_scorer, Some(exclude_scorer)) => {
+                let positive_scorer_boxed: Box<dyn Scorer> =
+                    into_box_scorer::<TScoreCombiner>(positive_scorer);
+                Ok(SpecializedScorer::Other(Box::new(Exclude::new(
+                    positive_scorer_boxed,
+                    exclude_scorer,
+                ))))
+            }
+            (positive_scorer, None) => Ok(positive_scorer),
         }
     }
+
+    /// Returns the scorer combining the clause scores according to the score mode.
+    ///
+    /// The unions of terms are only kept specialized, for block wand, when the scores are summed.
+    fn scoring_complex_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<SpecializedScorer> {
+        match self.score_mode {
+            ScoreMode::Sum => self.complex_scorer::<SumWithCoordsCombiner>(reader, boost),
+            ScoreMode::Max => {
+                let specialized_scorer = self.complex_scorer::<MaxCombiner>(reader, boost)?;
+                Ok(SpecializedScorer::Other(into_box_scorer::<MaxCombiner>(
+                    specialized_scorer,
+                )))
+            }
+        }
+    }
+
+    fn for_each_pruning_with_optional_static_ranks(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        static_ranks: Option<&StaticRankReader>,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        let scorer = self.scoring_complex_scorer(reader, 1.0)?;
+        match scorer {
+            SpecializedScorer::TermUnion(term_scorers) => {
+                super::block_wand(term_scorers, threshold, static_ranks, callback);
+            }
+            SpecializedScorer::TermUnionExclude(term_scorers, mut exclude_scorer) => {
+                // Block wand emits the candidate documents in increasing order,
+                // so excluded documents can be filtered out by seeking the exclusion scorer.
+                let mut current_threshold = threshold;
+                super::block_wand(term_scorers, threshold, static_ranks, &mut |doc, score| {
+                    if exclude_scorer.doc() < doc {
+                        exclude_scorer.seek(doc);
+                    }
+                    if exclude_scorer.doc() != doc {
+                        current_threshold = callback(doc, score);
+                    }
+                    current_threshold
+                });
+            }
+            SpecializedScorer::Other(scorer) => {
+                if let Some(static_ranks) = static_ranks {
+                    let mut scorer = StaticRankScorer::new(scorer, static_ranks.clone());
+                    for_each_pruning_scorer(&mut scorer, threshold, callback);
+                } else {
+                    let mut scorer = scorer;
+                    for_each_pruning_scorer(scorer.as_mut(), threshold, callback);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Weight for BooleanWeight {
     fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
-        if self.weights.is_empty() {
+        if self.weights.is_empty() || self.minimum_should_match_unreachable() {
             Ok(Box::new(EmptyScorer))
         } else if self.weights.len() == 1 {
             let &(occur, ref weight) = &self.weights[0];
@@ -158,10 +326,8 @@ impl Weight for BooleanWeight {
                 weight.scorer(reader, boost)
             }
         } else if self.scoring_enabled {
-            self.complex_scorer::<SumWithCoordsCombiner>(reader, boost)
-                .map(|specialized_scorer| {
-                    into_box_scorer::<SumWithCoordsCombiner>(specialized_scorer)
-                })
+            self.scoring_complex_scorer(reader, boost)
+                .map(into_box_scorer::<SumWithCoordsCombiner>)
         } else {
             self.complex_scorer::<DoNothingCombiner>(reader, boost)
                 .map(into_box_scorer::<DoNothingCombiner>)
@@ -177,7 +343,11 @@ impl Weight for BooleanWeight {
             return Ok(Explanation::new("BooleanQuery with no scoring", 1.0));
         }
 
-        let mut explanation = Explanation::new("BooleanClause. Sum of ...", scorer.score());
+        let description = match self.score_mode {
+            ScoreMode::Sum => "BooleanClause. Sum of ...",
+            ScoreMode::Max => "BooleanClause. Max of ...",
+        };
+        let mut explanation = Explanation::new(description, scorer.score());
         for &(ref occur, ref subweight) in &self.weights {
             if is_positive_occur(*occur) {
                 if let Ok(child_explanation) = subweight.explain(reader, doc) {
@@ -193,13 +363,17 @@ impl Weight for BooleanWeight {
         reader: &SegmentReader,
         callback: &mut dyn FnMut(DocId, Score),
     ) -> crate::Result<()> {
-        let scorer = self.complex_scorer::<SumWithCoordsCombiner>(reader, 1.0)?;
+        let scorer = self.scoring_complex_scorer(reader, 1.0)?;
         match scorer {
             SpecializedScorer::TermUnion(term_scorers) => {
                 let mut union_scorer =
                     Union::<TermScorer, SumWithCoordsCombiner>::from(term_scorers);
                 for_each_scorer(&mut union_scorer, callback);
             }
+            specialized_scorer @ SpecializedScorer::TermUnionExclude(..) => {
+                let mut scorer = into_box_scorer::<SumWithCoordsCombiner>(specialized_scorer);
+                for_each_scorer(scorer.as_mut(), callback);
+            }
             SpecializedScorer::Other(mut scorer) => {
                 for_each_scorer(scorer.as_mut(), callback);
             }
@@ -223,16 +397,22 @@ impl Weight for BooleanWeight {
         reader: &SegmentReader,
         callback: &mut dyn FnMut(DocId, Score) -> Score,
     ) -> crate::Result<()> {
-        let scorer = self.complex_scorer::<SumWithCoordsCombiner>(reader, 1.0)?;
-        match scorer {
-            SpecializedScorer::TermUnion(term_scorers) => {
-                super::block_wand(term_scorers, threshold, callback);
-            }
-            SpecializedScorer::Other(mut scorer) => {
-                for_each_pruning_scorer(scorer.as_mut(), threshold, callback);
-            }
-        }
-        Ok(())
+        self.for_each_pruning_with_optional_static_ranks(threshold, reader, None, callback)
+    }
+
+    fn for_each_pruning_with_static_ranks(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        static_ranks: &StaticRankReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        self.for_each_pruning_with_optional_static_ranks(
+            threshold,
+            reader,
+            Some(static_ranks),
+            callback,
+        )
     }
 }
 
@@ -0,0 +1,221 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::query::score_combiner::ScoreCombiner;
+use crate::query::Scorer;
+use crate::{DocId, Score};
+
+/// Scorer matching the documents matched by at least `min_num_matches`
+/// of its underlying scorers.
+///
+/// A document cannot match unless it is greater or equal to the `min_num_matches`-th
+/// smallest doc of the underlying scorers, so the scorers behind are directly
+/// seeked to that candidate, without enumerating the combinations of scorers.
+pub(crate) struct MinShouldMatchScorer<TScorer, TScoreCombiner> {
+    scorers: Vec<TScorer>,
+    min_num_matches: usize,
+    doc: DocId,
+    score_combiner: TScoreCombiner,
+    docs_buffer: Vec<DocId>,
+}
+
+impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> MinShouldMatchScorer<TScorer, TScoreCombiner> {
+    /// Creates a scorer matching the documents matched by at least
+    /// `min_num_matches` of `scorers`.
+    ///
+    /// `min_num_matches` is expected to be at least 1.
+    pub fn new(
+        scorers: Vec<TScorer>,
+        min_num_matches: usize,
+    ) -> MinShouldMatchScorer<TScorer, TScoreCombiner> {
+        debug_assert!(min_num_matches >= 1);
+        let mut scorer = MinShouldMatchScorer {
+            docs_buffer: Vec::with_capacity(scorers.len()),
+            scorers,
+            min_num_matches,
+            doc: TERMINATED,
+            score_combiner: TScoreCombiner::default(),
+        };
+        scorer.go_to_next_match();
+        scorer
+    }
+
+    /// Positions the scorer on the first document, greater or equal to the
+    /// current position of the underlying scorers, matched by enough of them.
+    fn go_to_next_match(&mut self) -> DocId {
+        if self.min_num_matches > self.scorers.len() {
+            self.doc = TERMINATED;
+            return TERMINATED;
+        }
+        loop {
+            self.docs_buffer.clear();
+            self.docs_buffer
+                .extend(self.scorers.iter().map(|scorer| scorer.doc()));
+            self.docs_buffer.sort_unstable();
+            let candidate = self.docs_buffer[self.min_num_matches - 1];
+            if candidate == TERMINATED {
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            let mut num_matches = 0;
+            for scorer in &mut self.scorers {
+                if scorer.doc() < candidate {
+                    scorer.seek(candidate);
+                }
+                if scorer.doc() == candidate {
+                    num_matches += 1;
+                }
+            }
+            if num_matches >= self.min_num_matches {
+                self.doc = candidate;
+                self.score_combiner.clear();
+                for scorer in &mut self.scorers {
+                    if scorer.doc() == candidate {
+                        self.score_combiner.update(scorer);
+                    }
+                }
+                return candidate;
+            }
+            // Fewer than `min_num_matches` scorers are on the candidate, so the
+            // `min_num_matches`-th smallest doc is now greater than the candidate.
+        }
+    }
+}
+
+impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> DocSet
+    for MinShouldMatchScorer<TScorer, TScoreCombiner>
+{
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        let doc = self.doc;
+        for scorer in &mut self.scorers {
+            if scorer.doc() == doc {
+                scorer.advance();
+            }
+        }
+        self.go_to_next_match()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        for scorer in &mut self.scorers {
+            if scorer.doc() < target {
+                scorer.seek(target);
+            }
+        }
+        self.go_to_next_match()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+
+    fn cost(&self) -> u64 {
+        self.scorers.iter().map(|scorer| scorer.cost()).sum()
+    }
+}
+
+impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Scorer
+    for MinShouldMatchScorer<TScorer, TScoreCombiner>
+{
+    fn score(&mut self) -> Score {
+        self.score_combiner.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinShouldMatchScorer;
+    use crate::docset::{DocSet, TERMINATED};
+    use crate::postings::tests::test_skip_against_unoptimized;
+    use crate::query::score_combiner::{DoNothingCombiner, SumCombiner};
+    use crate::query::{ConstScorer, Scorer, VecDocSet};
+    use crate::DocId;
+
+    fn min_should_match_scorer(
+        docs_list: &[Vec<DocId>],
+        min_num_matches: usize,
+    ) -> MinShouldMatchScorer<ConstScorer<VecDocSet>, SumCombiner> {
+        let scorers = docs_list
+            .iter()
+            .cloned()
+            .map(VecDocSet::from)
+            .map(ConstScorer::from)
+            .collect();
+        MinShouldMatchScorer::new(scorers, min_num_matches)
+    }
+
+    fn expected_docs(docs_list: &[Vec<DocId>], min_num_matches: usize) -> Vec<DocId> {
+        let mut all_docs: Vec<DocId> = docs_list.iter().flatten().cloned().collect();
+        all_docs.sort_unstable();
+        all_docs.dedup();
+        all_docs
+            .into_iter()
+            .filter(|doc| {
+                docs_list.iter().filter(|docs| docs.contains(doc)).count() >= min_num_matches
+            })
+            .collect()
+    }
+
+    fn aux_test_min_should_match(docs_list: &[Vec<DocId>], min_num_matches: usize) {
+        let expected = expected_docs(docs_list, min_num_matches);
+        let mut scorer = min_should_match_scorer(docs_list, min_num_matches);
+        let mut docs = Vec::new();
+        while scorer.doc() != TERMINATED {
+            let num_matches = docs_list
+                .iter()
+                .filter(|docs| docs.contains(&scorer.doc()))
+                .count();
+            assert_eq!(scorer.score(), num_matches as f32);
+            docs.push(scorer.doc());
+            scorer.advance();
+        }
+        assert_eq!(docs, expected);
+        let seek_targets: Vec<DocId> = (0..30).step_by(3).collect();
+        test_skip_against_unoptimized(
+            || {
+                let scorers = docs_list
+                    .iter()
+                    .cloned()
+                    .map(VecDocSet::from)
+                    .map(ConstScorer::from)
+                    .collect();
+                Box::new(MinShouldMatchScorer::<_, DoNothingCombiner>::new(
+                    scorers,
+                    min_num_matches,
+                ))
+            },
+            seek_targets,
+        );
+    }
+
+    #[test]
+    fn test_min_should_match_scorer() {
+        let docs_list = vec![
+            vec![1, 3, 5, 7, 9, 11],
+            vec![2, 3, 5, 8, 11, 20],
+            vec![3, 4, 5, 11, 21],
+            vec![0, 5, 9, 20, 21, 28],
+        ];
+        for min_num_matches in 1..=5 {
+            aux_test_min_should_match(&docs_list, min_num_matches);
+        }
+    }
+
+    #[test]
+    fn test_min_should_match_scorer_empty() {
+        let mut scorer = min_should_match_scorer(&[vec![], vec![1, 2]], 2);
+        assert_eq!(scorer.doc(), TERMINATED);
+        assert_eq!(scorer.advance(), TERMINATED);
+    }
+}
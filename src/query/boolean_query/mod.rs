@@ -1,10 +1,11 @@
 mod block_wand;
 mod boolean_query;
 mod boolean_weight;
+mod min_should_match_scorer;
 
 pub(crate) use self::block_wand::block_wand;
 pub(crate) use self::block_wand::block_wand_single_scorer;
-pub use self::boolean_query::BooleanQuery;
+pub use self::boolean_query::{BooleanQuery, MinimumShouldMatch, ScoreMode};
 
 #[cfg(test)]
 mod tests {
@@ -22,6 +23,7 @@ mod tests {
     use crate::query::RequiredOptionalScorer;
     use crate::query::Scorer;
     use crate::query::TermQuery;
+    use crate::query::{BitSetDocSet, BufferedUnion, ConstScorer};
     use crate::schema::*;
     use crate::Index;
     use crate::{DocAddress, DocId, Score};
@@ -222,6 +224,138 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_boolean_query_top_docs_with_exclusion() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // spans several blocks, with varying term frequencies and fieldnorms.
+        for i in 0..3_000usize {
+            let mut text = String::new();
+            for _ in 0..(i * 7) % 5 {
+                text.push_str("a ");
+            }
+            for _ in 0..(i * 13) % 4 {
+                text.push_str("b ");
+            }
+            if i % 3 == 0 {
+                // excluded documents would otherwise be the best matches.
+                text = "a a a a a b b b b c".to_string();
+            }
+            for _ in 0..i % 11 {
+                text.push_str("z ");
+            }
+            index_writer.add_document(doc!(text_field => text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("a b -c")?;
+
+        let all_docs = searcher.search(query.as_ref(), &TEST_COLLECTOR_WITH_SCORE)?;
+        let mut all_scores: Vec<Score> = all_docs.scores().to_vec();
+        all_scores.sort_by(|left, right| right.partial_cmp(left).unwrap());
+
+        let top_docs = searcher.search(query.as_ref(), &TopDocs::with_limit(20))?;
+        assert_eq!(top_docs.len(), 20);
+        for ((score, doc_address), expected_score) in top_docs.iter().zip(&all_scores) {
+            assert_nearly_equals!(*score, *expected_score);
+            assert!(all_docs.docs().contains(doc_address));
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_boolean_query_many_unscored_clauses() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..2_000usize {
+            index_writer.add_document(doc!(text_field => format!("t{}", i % 100)))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = |i: usize| -> Box<dyn Query> {
+            let term = Term::from_field_text(text_field, &format!("t{}", i));
+            Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+        };
+        let should_query =
+            BooleanQuery::new((0..50).map(|i| (Occur::Should, term_query(i))).collect());
+        {
+            let weight = should_query.weight(&searcher, false)?;
+            let scorer = weight.scorer(searcher.segment_reader(0u32), 1.0)?;
+            assert!(scorer.is::<ConstScorer<BufferedUnion>>());
+        }
+        assert_eq!(should_query.count(&searcher)?, 1_000);
+
+        let query = BooleanQuery::new(
+            (0..50)
+                .map(|i| (Occur::Should, term_query(i)))
+                .chain((0..20).map(|i| (Occur::MustNot, term_query(i))))
+                .collect(),
+        );
+        assert_eq!(query.count(&searcher)?, 600);
+        let scored_docs = searcher.search(&query, &TEST_COLLECTOR_WITH_SCORE)?;
+        assert_eq!(scored_docs.docs().len(), 600);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_boolean_query_unscored_intersection_strategy() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..3_000usize {
+            let mut text = String::from("any");
+            if i % 2 == 0 {
+                text.push_str(" even");
+            }
+            if i % 3 == 0 {
+                text.push_str(" third");
+            }
+            if i == 1_500 || i == 2_000 {
+                text.push_str(" rare");
+            }
+            index_writer.add_document(doc!(text_field => text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0u32);
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+
+        // Clauses with comparable numbers of docs are intersected in a bitset.
+        let dense_query = query_parser.parse_query("+even +third")?;
+        let scorer = dense_query
+            .weight(&searcher, false)?
+            .scorer(segment_reader, 1.0)?;
+        assert!(scorer.is::<ConstScorer<BitSetDocSet>>());
+        assert_eq!(dense_query.count(&searcher)?, 500);
+        let scorer = dense_query
+            .weight(&searcher, true)?
+            .scorer(segment_reader, 1.0)?;
+        assert!(scorer.is::<Intersection<TermScorer>>());
+
+        // A rare clause drives the intersection.
+        let sparse_query = query_parser.parse_query("+even +third +rare")?;
+        let scorer = sparse_query
+            .weight(&searcher, false)?
+            .scorer(segment_reader, 1.0)?;
+        assert!(scorer.is::<Intersection<TermScorer>>());
+        assert_eq!(scorer.size_hint(), 2);
+        assert_eq!(sparse_query.count(&searcher)?, 1);
+
+        // The cost of the optional clauses adds up to the cost of the required ones.
+        let reqopt_query = query_parser.parse_query("+even third")?;
+        let scorer = reqopt_query
+            .weight(&searcher, true)?
+            .scorer(segment_reader, 1.0)?;
+        assert_eq!(scorer.size_hint(), 1_500);
+        assert_eq!(scorer.cost(), 2_500);
+        Ok(())
+    }
+
     #[test]
     pub fn test_boolean_query_with_weight() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
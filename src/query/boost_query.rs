@@ -51,6 +51,13 @@ impl Query for BoostQuery {
     fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
         self.query.query_terms(terms)
     }
+
+    fn rewrite(&self, searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        Ok(self
+            .query
+            .rewrite(searcher)?
+            .map(|query| Box::new(BoostQuery::new(query, self.boost)) as Box<dyn Query>))
+    }
 }
 
 pub(crate) struct BoostWeight {
@@ -118,6 +125,10 @@ impl<S: Scorer> DocSet for BoostScorer<S> {
         self.underlying.size_hint()
     }
 
+    fn cost(&self) -> u64 {
+        self.underlying.cost()
+    }
+
     fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
         self.underlying.count(alive_bitset)
     }
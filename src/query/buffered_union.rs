@@ -0,0 +1,166 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::query::{BitSetDocSet, VecDocSet};
+use crate::DocId;
+use common::BitSet;
+
+// Above one document every `DENSITY_RATIO` doc ids, the docs are accumulated
+// in a bitset. Below, sorting a vector of docs is cheaper than
+// allocating and scanning a bitset over the whole segment.
+const DENSITY_RATIO: u64 = 1_024;
+
+enum BufferedDocs {
+    Sparse(VecDocSet),
+    Dense(BitSetDocSet),
+}
+
+/// Union of docsets, computed eagerly when the union is built.
+///
+/// Unlike [`Union`](./struct.Union.html), which merges its docsets lazily,
+/// the `BufferedUnion` consumes all of its docsets upfront.
+/// This is much faster when the union has many short docsets
+/// (e.g. the posting lists of hundreds of terms), but it does not support scoring.
+///
+/// Depending on the estimated number of docs of the union, the docs
+/// are either accumulated in a bitset, or sorted in a vector.
+pub struct BufferedUnion {
+    docs: BufferedDocs,
+}
+
+impl BufferedUnion {
+    /// Builds the union of `docsets`, whose docs are all lower than `max_doc`.
+    pub fn build<TDocSet: DocSet>(docsets: Vec<TDocSet>, max_doc: DocId) -> BufferedUnion {
+        let num_docs_estimate: u64 = docsets
+            .iter()
+            .map(|docset| u64::from(docset.size_hint()))
+            .sum();
+        let docs = if num_docs_estimate * DENSITY_RATIO >= u64::from(max_doc) {
+            let mut bitset = BitSet::with_max_value(max_doc);
+            for mut docset in docsets {
+                let mut doc = docset.doc();
+                while doc != TERMINATED {
+                    bitset.insert(doc);
+                    doc = docset.advance();
+                }
+            }
+            BufferedDocs::Dense(BitSetDocSet::from(bitset))
+        } else {
+            let mut doc_ids: Vec<DocId> = Vec::with_capacity(num_docs_estimate as usize);
+            for mut docset in docsets {
+                let mut doc = docset.doc();
+                while doc != TERMINATED {
+                    doc_ids.push(doc);
+                    doc = docset.advance();
+                }
+            }
+            doc_ids.sort_unstable();
+            doc_ids.dedup();
+            BufferedDocs::Sparse(VecDocSet::from(doc_ids))
+        };
+        BufferedUnion { docs }
+    }
+
+    /// Returns true if the docs of the union are accumulated in a bitset.
+    pub fn is_dense(&self) -> bool {
+        matches!(self.docs, BufferedDocs::Dense(_))
+    }
+}
+
+impl DocSet for BufferedUnion {
+    fn advance(&mut self) -> DocId {
+        match &mut self.docs {
+            BufferedDocs::Sparse(docset) => docset.advance(),
+            BufferedDocs::Dense(docset) => docset.advance(),
+        }
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        match &mut self.docs {
+            BufferedDocs::Sparse(docset) => docset.seek(target),
+            BufferedDocs::Dense(docset) => docset.seek(target),
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        match &self.docs {
+            BufferedDocs::Sparse(docset) => docset.doc(),
+            BufferedDocs::Dense(docset) => docset.doc(),
+        }
+    }
+
+    fn size_hint(&self) -> u32 {
+        match &self.docs {
+            BufferedDocs::Sparse(docset) => docset.size_hint(),
+            BufferedDocs::Dense(docset) => docset.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::BufferedUnion;
+    use crate::docset::{DocSet, TERMINATED};
+    use crate::query::VecDocSet;
+    use crate::DocId;
+
+    fn test_buffered_union_aux(vals: &[Vec<DocId>], max_doc: DocId, expect_dense: bool) {
+        let docsets: Vec<VecDocSet> = vals
+            .iter()
+            .map(|doc_ids| VecDocSet::from(doc_ids.clone()))
+            .collect();
+        let mut union = BufferedUnion::build(docsets, max_doc);
+        assert_eq!(union.is_dense(), expect_dense);
+        let expected: BTreeSet<DocId> = vals.iter().flatten().cloned().collect();
+        for &doc in &expected {
+            assert_eq!(union.doc(), doc);
+            union.advance();
+        }
+        assert_eq!(union.doc(), TERMINATED);
+    }
+
+    fn short_lists(num_lists: u32, max_doc: DocId) -> Vec<Vec<DocId>> {
+        (0..num_lists)
+            .map(|i| {
+                (0..5u32)
+                    .map(|j| (i * 7_919 + j * 104_729) % max_doc)
+                    .collect::<BTreeSet<DocId>>()
+                    .into_iter()
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_buffered_union_dense() {
+        test_buffered_union_aux(&short_lists(300, 10_000), 10_000, true);
+    }
+
+    #[test]
+    fn test_buffered_union_sparse() {
+        test_buffered_union_aux(&short_lists(300, 10_000_000), 10_000_000, false);
+    }
+
+    #[test]
+    fn test_buffered_union_empty() {
+        test_buffered_union_aux(&[], 1_000, false);
+        test_buffered_union_aux(&[vec![], vec![]], 1_000, false);
+    }
+
+    #[test]
+    fn test_buffered_union_seek() {
+        for &max_doc in &[1_000u32, 100_000_000u32] {
+            let docsets = vec![
+                VecDocSet::from(vec![1, 5, 9]),
+                VecDocSet::from(vec![2, 5, 700]),
+            ];
+            let mut union = BufferedUnion::build(docsets, max_doc);
+            assert_eq!(union.doc(), 1);
+            assert_eq!(union.seek(3), 5);
+            assert_eq!(union.advance(), 9);
+            assert_eq!(union.seek(9), 9);
+            assert_eq!(union.seek(10), 700);
+            assert_eq!(union.seek(701), TERMINATED);
+        }
+    }
+}
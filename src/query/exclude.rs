@@ -78,6 +78,10 @@ where
     fn size_hint(&self) -> u32 {
         self.underlying_docset.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.underlying_docset.cost() + self.excluding_docset.cost()
+    }
 }
 
 impl<TScorer, TDocSetExclude> Scorer for Exclude<TScorer, TDocSetExclude>
@@ -42,6 +42,21 @@ impl Explanation {
         self.value
     }
 
+    /// Returns the description of the current node.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the explanations of the different parts of the current node formula.
+    pub fn details(&self) -> &[Explanation] {
+        &self.details
+    }
+
+    /// Returns the extra context attached to the current node.
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+
     /// Add some detail, explaining some part of the current node formula.
     ///
     /// Details are treated as child of the current node.
@@ -0,0 +1,276 @@
+use crate::query::explanation::does_not_match;
+use crate::query::{BitSetDocSet, ConstScorer, EmptyScorer, Explanation};
+use crate::query::{Query, Scorer, Weight};
+use crate::schema::{Facet, FieldType, IndexRecordOption};
+use crate::schema::{Field, Term};
+use crate::{DocId, DocSet, Score, Searcher, SegmentReader, TERMINATED};
+use common::BitSet;
+use std::collections::BTreeMap;
+
+/// `FacetQuery` matches the documents having a given facet,
+/// or optionally one of its descendants.
+///
+/// It is typically used to drill down into the results of a search
+/// once the user has selected a facet value returned by the
+/// [`FacetCollector`](../collector/struct.FacetCollector.html).
+///
+/// Matched documents all get a constant `Score` of one.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::FacetQuery;
+/// use tantivy::schema::{Facet, FacetOptions, Schema};
+/// use tantivy::{doc, Index};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let category = schema_builder.add_facet_field("category", FacetOptions::default());
+/// let schema = schema_builder.build();
+///
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(category => Facet::from("/electronics")))?;
+/// index_writer.add_document(doc!(category => Facet::from("/electronics/tv")))?;
+/// index_writer.add_document(doc!(category => Facet::from("/electronics/tv/oled")))?;
+/// index_writer.add_document(doc!(category => Facet::from("/electronics-refurbished")))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let electronics = FacetQuery::new(category, Facet::from("/electronics"));
+/// assert_eq!(searcher.search(&electronics, &Count)?, 3);
+/// let exactly_tv = FacetQuery::exact(category, Facet::from("/electronics/tv"));
+/// assert_eq!(searcher.search(&exactly_tv, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FacetQuery {
+    field: Field,
+    facet: Facet,
+    include_descendants: bool,
+}
+
+impl FacetQuery {
+    /// Creates a query matching the documents having the facet `facet`,
+    /// or one of its descendants.
+    ///
+    /// The root facet matches all of the documents having a facet.
+    pub fn new(field: Field, facet: Facet) -> FacetQuery {
+        FacetQuery {
+            field,
+            facet,
+            include_descendants: true,
+        }
+    }
+
+    /// Creates a query matching the documents having exactly the facet `facet`.
+    pub fn exact(field: Field, facet: Facet) -> FacetQuery {
+        FacetQuery {
+            field,
+            facet,
+            include_descendants: false,
+        }
+    }
+
+    /// The facet this query is built out of.
+    pub fn facet(&self) -> &Facet {
+        &self.facet
+    }
+
+    /// Returns true iff the query matches the descendants of the facet.
+    pub fn include_descendants(&self) -> bool {
+        self.include_descendants
+    }
+
+    fn term(&self) -> Term {
+        Term::from_facet(self.field, &self.facet)
+    }
+}
+
+impl Query for FacetQuery {
+    fn weight(
+        &self,
+        searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        if !matches!(field_entry.field_type(), FieldType::HierarchicalFacet(_)) {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied facet query on field {:?}, which is not a facet field",
+                field_entry.name()
+            )));
+        }
+        Ok(Box::new(FacetWeight {
+            term: self.term(),
+            include_descendants: self.include_descendants,
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        terms.entry(self.term()).or_insert(false);
+    }
+}
+
+/// Weight associated to the `FacetQuery`.
+///
+/// All of the ancestors of the facets of a document are indexed,
+/// so the postings of a facet contain the documents of its whole subtree.
+/// The documents having exactly the facet are those whose facet ordinals,
+/// stored in the fast field, contain the ordinal of the facet.
+struct FacetWeight {
+    term: Term,
+    include_descendants: bool,
+}
+
+impl Weight for FacetWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let field = self.term.field();
+        let inverted_index = reader.inverted_index(field)?;
+        let term_dict = inverted_index.terms();
+        let facet_ord = if let Some(facet_ord) = term_dict.term_ord(self.term.value_bytes())? {
+            facet_ord
+        } else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let term_info = term_dict.term_info_from_ord(facet_ord)?;
+        let mut postings =
+            inverted_index.read_postings_from_terminfo(&term_info, IndexRecordOption::Basic)?;
+        let mut doc_bitset = BitSet::with_max_value(reader.max_doc());
+        if self.include_descendants {
+            while postings.doc() != TERMINATED {
+                doc_bitset.insert(postings.doc());
+                postings.advance();
+            }
+        } else {
+            let facet_reader = reader.facet_reader(field)?;
+            let mut facet_ords = Vec::new();
+            while postings.doc() != TERMINATED {
+                let doc = postings.doc();
+                facet_reader.facet_ords(doc, &mut facet_ords);
+                if facet_ords.contains(&facet_ord) {
+                    doc_bitset.insert(doc);
+                }
+                postings.advance();
+            }
+        }
+        let doc_bitset = BitSetDocSet::from(doc_bitset);
+        Ok(Box::new(ConstScorer::new(doc_bitset, boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("FacetQuery", 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FacetQuery;
+    use crate::collector::{Count, DocSetCollector};
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{Facet, FacetOptions, IndexRecordOption, Schema, STRING};
+    use crate::{DocAddress, Index, Term};
+
+    fn create_index() -> crate::Result<(Index, crate::schema::Field)> {
+        let mut schema_builder = Schema::builder();
+        let facet_field = schema_builder.add_facet_field("facet", FacetOptions::default());
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        for facet in &["/a", "/a/b", "/a/b/c", "/a/c", "/ab", "/ab/c", "/b", "/b/a"] {
+            index_writer.add_document(doc!(facet_field => Facet::from(*facet)))?;
+        }
+        index_writer.add_document(doc!())?;
+        index_writer.add_document(doc!(
+            facet_field => Facet::from("/c"),
+            facet_field => Facet::from("/d/e")
+        ))?;
+        index_writer.commit()?;
+        Ok((index, facet_field))
+    }
+
+    fn matching_docs(index: &Index, query: &dyn Query) -> crate::Result<Vec<u32>> {
+        let searcher = index.reader()?.searcher();
+        let mut docs: Vec<u32> = searcher
+            .search(query, &DocSetCollector)?
+            .into_iter()
+            .map(|DocAddress { doc_id, .. }| doc_id)
+            .collect();
+        docs.sort_unstable();
+        Ok(docs)
+    }
+
+    #[test]
+    fn test_facet_query_descendants() -> crate::Result<()> {
+        let (index, facet_field) = create_index()?;
+        let facet_query = |facet: &str| FacetQuery::new(facet_field, Facet::from(facet));
+        assert_eq!(matching_docs(&index, &facet_query("/a"))?, vec![0, 1, 2, 3]);
+        assert_eq!(matching_docs(&index, &facet_query("/a/b"))?, vec![1, 2]);
+        assert_eq!(matching_docs(&index, &facet_query("/ab"))?, vec![4, 5]);
+        assert_eq!(matching_docs(&index, &facet_query("/b/a"))?, vec![7]);
+        assert_eq!(matching_docs(&index, &facet_query("/d"))?, vec![9]);
+        assert!(matching_docs(&index, &facet_query("/e"))?.is_empty());
+        assert_eq!(
+            matching_docs(&index, &FacetQuery::new(facet_field, Facet::root()))?,
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 9]
+        );
+        let searcher = index.reader()?.searcher();
+        let term_query = TermQuery::new(
+            Term::from_facet(facet_field, &Facet::from("/a/b")),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(
+            searcher.search(&facet_query("/a/b"), &Count)?,
+            searcher.search(&term_query, &Count)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_facet_query_exact() -> crate::Result<()> {
+        let (index, facet_field) = create_index()?;
+        let facet_query = |facet: &str| FacetQuery::exact(facet_field, Facet::from(facet));
+        assert_eq!(matching_docs(&index, &facet_query("/a"))?, vec![0]);
+        assert_eq!(matching_docs(&index, &facet_query("/a/b"))?, vec![1]);
+        assert!(matching_docs(&index, &facet_query("/a/b/c/d"))?.is_empty());
+        assert_eq!(matching_docs(&index, &facet_query("/c"))?, vec![9]);
+        assert!(matching_docs(&index, &facet_query("/d"))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_facet_query_as_filter() -> crate::Result<()> {
+        let (index, facet_field) = create_index()?;
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(FacetQuery::new(facet_field, Facet::from("/a"))),
+            ),
+            (
+                Occur::MustNot,
+                Box::new(FacetQuery::new(facet_field, Facet::from("/a/b"))),
+            ),
+        ]);
+        assert_eq!(matching_docs(&index, &query)?, vec![0, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_facet_query_not_a_facet_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let facet_query = FacetQuery::new(text_field, Facet::from("/a"));
+        assert!(matches!(
+            searcher.search(&facet_query, &Count),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+use crate::features::FeaturePostings;
+use crate::query::explanation::does_not_match;
+use crate::query::score_combiner::SumCombiner;
+use crate::query::{EmptyScorer, Explanation, Query, Scorer, Union, Weight};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, DocSet, Score, Searcher, SegmentReader, TantivyError, TERMINATED};
+
+/// `FeaturesQuery` matches the documents having at least one of the features of the
+/// query in a features field, and scores them with the dot product of their features
+/// and of the weights of the query.
+///
+/// This makes it possible to run learned sparse retrieval models, which expand both
+/// the documents and the queries into weighted features.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::FeaturesQuery;
+/// use tantivy::schema::{FeaturesOptions, Schema};
+/// use tantivy::{Document, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let expansion = schema_builder.add_features_field("expansion", FeaturesOptions::default());
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(50_000_000)?;
+/// let mut doc = Document::default();
+/// doc.add_features(expansion, vec![("car", 1.5), ("vehicle", 0.5)]);
+/// index_writer.add_document(doc)?;
+/// let mut doc = Document::default();
+/// doc.add_features(expansion, vec![("boat", 1.0), ("vehicle", 0.8)]);
+/// index_writer.add_document(doc)?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = FeaturesQuery::new(expansion, vec![("car".to_string(), 2.0), ("vehicle".to_string(), 1.0)]);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].0, 3.5);
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FeaturesQuery {
+    field: Field,
+    features: Vec<(String, Score)>,
+}
+
+impl FeaturesQuery {
+    /// Creates a query scoring the documents with the dot product of their features
+    /// in `field` and of `features`.
+    pub fn new(field: Field, features: Vec<(String, Score)>) -> FeaturesQuery {
+        FeaturesQuery { field, features }
+    }
+}
+
+impl Query for FeaturesQuery {
+    fn weight(
+        &self,
+        searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        if !matches!(field_entry.field_type(), FieldType::Features(_)) {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a features field.",
+                field_entry.name()
+            )));
+        }
+        Ok(Box::new(FeaturesWeight {
+            field: self.field,
+            features: self.features.clone(),
+        }))
+    }
+}
+
+struct FeaturesWeight {
+    field: Field,
+    features: Vec<(String, Score)>,
+}
+
+impl FeaturesWeight {
+    fn feature_scorers(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Vec<FeatureScorer>> {
+        let features_reader = reader.features_reader(self.field)?;
+        let mut scorers = Vec::new();
+        for (feature, query_weight) in &self.features {
+            if let Some(postings) = features_reader.postings(feature)? {
+                scorers.push(FeatureScorer::new(postings, query_weight * boost));
+            }
+        }
+        Ok(scorers)
+    }
+}
+
+impl Weight for FeaturesWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let mut scorers = self.feature_scorers(reader, boost)?;
+        match scorers.len() {
+            0 => Ok(Box::new(EmptyScorer)),
+            1 => Ok(Box::new(scorers.pop().unwrap())),
+            _ => Ok(Box::new(Union::<_, SumCombiner>::from(scorers))),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let features_reader = reader.features_reader(self.field)?;
+        let mut details = Vec::new();
+        for (feature, query_weight) in &self.features {
+            if let Some(weight) = features_reader.weight(feature, doc)? {
+                let mut detail = Explanation::new(
+                    format!("Feature {:?}, weight x query weight", feature),
+                    weight * query_weight,
+                );
+                detail.add_const("weight", weight);
+                detail.add_const("query weight", *query_weight);
+                details.push(detail);
+            }
+        }
+        if details.is_empty() {
+            return Err(does_not_match(doc));
+        }
+        let score = details.iter().map(Explanation::value).sum();
+        let mut explanation = Explanation::new("FeaturesQuery, sum of", score);
+        for detail in details {
+            explanation.add_detail(detail);
+        }
+        Ok(explanation)
+    }
+}
+
+/// Scorer over the documents having a feature, scored by the weight
+/// of the feature multiplied by the weight of the query.
+struct FeatureScorer {
+    postings: FeaturePostings,
+    cursor: usize,
+    query_weight: Score,
+}
+
+impl FeatureScorer {
+    fn new(postings: FeaturePostings, query_weight: Score) -> FeatureScorer {
+        FeatureScorer {
+            postings,
+            cursor: 0,
+            query_weight,
+        }
+    }
+}
+
+impl DocSet for FeatureScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.postings.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc() < target {
+            self.cursor = self.postings.seek(self.cursor, target);
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        if self.cursor < self.postings.len() {
+            self.postings.doc(self.cursor)
+        } else {
+            TERMINATED
+        }
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings.len() as u32
+    }
+}
+
+impl Scorer for FeatureScorer {
+    fn score(&mut self) -> Score {
+        self.postings.weight_at(self.cursor) * self.query_weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeaturesQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{FeaturesOptions, IndexRecordOption, Schema, Value, STORED, STRING};
+    use crate::{assert_nearly_equals, Document, Index, IndexSettings, IndexSortByField, Order};
+    use crate::{doc, DocAddress, Term};
+
+    fn features_doc(
+        id_field: crate::schema::Field,
+        id: &str,
+        features: crate::schema::Field,
+        vals: &[(&str, f32)],
+    ) -> Document {
+        let mut doc = doc!(id_field => id);
+        doc.add_features(features, vals.iter().cloned());
+        doc
+    }
+
+    #[test]
+    fn test_features_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING | STORED);
+        let expansion =
+            schema_builder.add_features_field("expansion", FeaturesOptions::default().set_stored());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(features_doc(
+            id,
+            "a",
+            expansion,
+            &[("car", 1.0), ("red", 2.0)],
+        ))?;
+        index_writer.add_document(features_doc(id, "b", expansion, &[("car", 3.0)]))?;
+        index_writer.add_document(features_doc(id, "c", expansion, &[("blue", 1.0)]))?;
+        index_writer.add_document(doc!(id => "d"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let features_reader = segment_reader.features_reader(expansion)?;
+        assert_eq!(features_reader.num_features(), 3);
+        assert_eq!(features_reader.weight("red", 0)?, Some(2.0));
+        assert_eq!(features_reader.weight("red", 1)?, None);
+        assert!(segment_reader.features_reader(id).is_err());
+        let stored_doc = searcher.doc(DocAddress::new(0, 0))?;
+        assert_eq!(
+            stored_doc.get_first(expansion),
+            Some(&Value::Features(vec![
+                ("car".to_string(), 1.0),
+                ("red".to_string(), 2.0)
+            ]))
+        );
+
+        let query = FeaturesQuery::new(
+            expansion,
+            vec![("car".to_string(), 1.0), ("red".to_string(), 0.5)],
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(
+            top_docs,
+            vec![(3.0, DocAddress::new(0, 1)), (2.0, DocAddress::new(0, 0))]
+        );
+        let explanation = query.explain(&searcher, DocAddress::new(0, 0))?;
+        assert_nearly_equals!(explanation.value(), 2.0);
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+
+        let unknown_query = FeaturesQuery::new(expansion, vec![("green".to_string(), 1.0)]);
+        assert_eq!(searcher.search(&unknown_query, &Count)?, 0);
+
+        // Restricting the features query with a filter.
+        let filtered_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(query.clone())),
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(id, "a"),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ]);
+        assert_eq!(searcher.search(&filtered_query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_features_merge_and_sort() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING | STORED);
+        let rank = schema_builder.add_u64_field("rank", crate::schema::FAST);
+        let expansion = schema_builder.add_features_field("expansion", FeaturesOptions::default());
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "rank".to_string(),
+                    order: Order::Desc,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        let ranked_doc = |doc_id: &str, doc_rank: u64, vals: &[(&str, f32)]| {
+            let mut doc = features_doc(id, doc_id, expansion, vals);
+            doc.add_u64(rank, doc_rank);
+            doc
+        };
+        index_writer.add_document(ranked_doc("a", 1, &[("x", 1.0)]))?;
+        index_writer.add_document(ranked_doc("b", 4, &[("x", 2.0), ("y", 1.0)]))?;
+        index_writer.commit()?;
+        index_writer.add_document(ranked_doc("c", 3, &[("y", 3.0)]))?;
+        index_writer.add_document(ranked_doc("d", 2, &[("x", 5.0)]))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(id, "d"));
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        futures::executor::block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let features_reader = searcher.segment_reader(0).features_reader(expansion)?;
+        // The documents are sorted by decreasing rank: b, c, a.
+        let x_postings = features_reader.postings("x")?.unwrap();
+        assert_eq!(
+            x_postings.iter().collect::<Vec<_>>(),
+            vec![(0, 2.0), (2, 1.0)]
+        );
+        let y_postings = features_reader.postings("y")?.unwrap();
+        assert_eq!(
+            y_postings.iter().collect::<Vec<_>>(),
+            vec![(0, 1.0), (1, 3.0)]
+        );
+
+        let query = FeaturesQuery::new(
+            expansion,
+            vec![("x".to_string(), 1.0), ("y".to_string(), 1.0)],
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (3.0, DocAddress::new(0, 0)),
+                (3.0, DocAddress::new(0, 1)),
+                (1.0, DocAddress::new(0, 2))
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_features_duplicate_feature() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let expansion = schema_builder.add_features_field("expansion", FeaturesOptions::default());
+        let schema = schema_builder.build();
+        let doc = schema.parse_document(r#"{"expansion": {"a": 1.0, "b": 0.5}}"#)?;
+        assert_eq!(
+            doc.get_first(expansion).and_then(Value::features_value),
+            Some(&[("a".to_string(), 1.0), ("b".to_string(), 0.5)][..])
+        );
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        let mut doc = Document::default();
+        doc.add_features(expansion, vec![("a", 1.0)]);
+        doc.add_features(expansion, vec![("a", 2.0)]);
+        index_writer.add_document(doc)?;
+        assert!(index_writer.commit().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+use crate::query::explanation::does_not_match;
+use crate::query::{BitSetDocSet, ConstScorer, Explanation, Query, Scorer, Weight};
+use crate::{DocId, DocSet, Opstamp, Score, Searcher, SegmentId, SegmentReader, Term, TERMINATED};
+use common::BitSet;
+use lru::LruCache;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct FilterCacheKey {
+    query_key: Arc<str>,
+    segment_id: SegmentId,
+    delete_opstamp: Option<Opstamp>,
+}
+
+/// Cache of the documents matched by filter queries, segment by segment.
+///
+/// Queries wrapped with [`FilterCache::cached`](#method.cached) are evaluated
+/// once per segment, and the set of alive documents they match is kept as a
+/// bitset. The following searches on the same segment reuse that bitset instead of
+/// evaluating the query again.
+/// Matched documents all get a constant `Score` of one, so the cache is meant for
+/// the filtering parts of a query, typically combined with a scored query in a
+/// `BooleanQuery`.
+///
+/// Queries are identified by their `Debug` representation, and their bitsets by the
+/// segment and the version of its deletes, so an entry is never used once the deletes
+/// of its segment changed.
+/// The least recently used entries are evicted once the cache holds more than
+/// `capacity` bitsets. Registering the cache with
+/// [`IndexReaderBuilder::filter_cache`](../struct.IndexReaderBuilder.html#method.filter_cache)
+/// evicts the entries of the segments that are not searchable anymore on each reload.
+///
+/// `FilterCache` is cheap to clone: its clones share the same entries.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::{BooleanQuery, FilterCache, Occur, Query, TermQuery};
+/// use tantivy::schema::{IndexRecordOption, Schema, STRING, TEXT};
+/// use tantivy::{doc, Index, Term};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let tenant = schema_builder.add_text_field("tenant", STRING);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(tenant => "acme", body => "hello world"))?;
+/// index_writer.add_document(doc!(tenant => "initech", body => "hello world"))?;
+/// index_writer.commit()?;
+///
+/// let filter_cache = FilterCache::new(1_000);
+/// let reader = index.reader_builder().filter_cache(filter_cache.clone()).try_into()?;
+/// let tenant_filter = filter_cache.cached(Box::new(TermQuery::new(
+///     Term::from_field_text(tenant, "acme"),
+///     IndexRecordOption::Basic,
+/// )));
+/// let query = BooleanQuery::new(vec![
+///     (Occur::Must, Box::new(TermQuery::new(
+///         Term::from_field_text(body, "hello"),
+///         IndexRecordOption::WithFreqs,
+///     )) as Box<dyn Query>),
+///     (Occur::Must, Box::new(tenant_filter)),
+/// ]);
+/// let searcher = reader.searcher();
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// assert_eq!(filter_cache.len(), 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone)]
+pub struct FilterCache {
+    entries: Arc<Mutex<LruCache<FilterCacheKey, Arc<BitSet>>>>,
+}
+
+impl fmt::Debug for FilterCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FilterCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl FilterCache {
+    /// Creates a filter cache holding at most `capacity` bitsets.
+    pub fn new(capacity: usize) -> FilterCache {
+        FilterCache {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Wraps a filter query so that the documents it matches are cached.
+    pub fn cached(&self, query: Box<dyn Query>) -> CachedFilterQuery {
+        let query_key: Arc<str> = Arc::from(format!("{:?}", query));
+        CachedFilterQuery {
+            query,
+            query_key,
+            filter_cache: self.clone(),
+        }
+    }
+
+    /// Returns the number of bitsets in the cache.
+    pub fn len(&self) -> usize {
+        self.lock_entries().len()
+    }
+
+    /// Returns true iff the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all of the bitsets from the cache.
+    pub fn clear(&self) {
+        self.lock_entries().clear();
+    }
+
+    /// Removes the bitsets of the segments that are not part of `segment_readers`,
+    /// or whose deletes changed.
+    pub fn retain_segments(&self, segment_readers: &[SegmentReader]) {
+        let live_segments: HashSet<(SegmentId, Option<Opstamp>)> = segment_readers
+            .iter()
+            .map(|segment_reader| (segment_reader.segment_id(), segment_reader.delete_opstamp()))
+            .collect();
+        let mut entries = self.lock_entries();
+        let stale_keys: Vec<FilterCacheKey> = entries
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| !live_segments.contains(&(key.segment_id, key.delete_opstamp)))
+            .cloned()
+            .collect();
+        for stale_key in &stale_keys {
+            entries.pop(stale_key);
+        }
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, LruCache<FilterCacheKey, Arc<BitSet>>> {
+        self.entries
+            .lock()
+            .expect("Filter cache lock poisoned. This should never happen.")
+    }
+
+    fn get(&self, key: &FilterCacheKey) -> Option<Arc<BitSet>> {
+        self.lock_entries().get(key).cloned()
+    }
+
+    fn put(&self, key: FilterCacheKey, bitset: Arc<BitSet>) {
+        self.lock_entries().put(key, bitset);
+    }
+}
+
+/// Query whose matching documents are cached in a [`FilterCache`](./struct.FilterCache.html).
+///
+/// It is built with [`FilterCache::cached`](./struct.FilterCache.html#method.cached).
+pub struct CachedFilterQuery {
+    query: Box<dyn Query>,
+    query_key: Arc<str>,
+    filter_cache: FilterCache,
+}
+
+impl Clone for CachedFilterQuery {
+    fn clone(&self) -> Self {
+        CachedFilterQuery {
+            query: self.query.box_clone(),
+            query_key: self.query_key.clone(),
+            filter_cache: self.filter_cache.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for CachedFilterQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CachedFilterQuery({:?})", self.query)
+    }
+}
+
+impl Query for CachedFilterQuery {
+    fn weight(
+        &self,
+        searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(CachedFilterWeight {
+            weight: self.query.weight(searcher, false)?,
+            query_key: self.query_key.clone(),
+            filter_cache: self.filter_cache.clone(),
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        self.query.query_terms(terms);
+    }
+}
+
+struct CachedFilterWeight {
+    weight: Box<dyn Weight>,
+    query_key: Arc<str>,
+    filter_cache: FilterCache,
+}
+
+impl CachedFilterWeight {
+    fn matching_docs(&self, reader: &SegmentReader) -> crate::Result<Arc<BitSet>> {
+        let key = FilterCacheKey {
+            query_key: self.query_key.clone(),
+            segment_id: reader.segment_id(),
+            delete_opstamp: reader.delete_opstamp(),
+        };
+        if let Some(bitset) = self.filter_cache.get(&key) {
+            return Ok(bitset);
+        }
+        let mut bitset = BitSet::with_max_value(reader.max_doc());
+        let mut scorer = self.weight.scorer(reader, 1.0)?;
+        while scorer.doc() != TERMINATED {
+            let doc = scorer.doc();
+            if !reader.is_deleted(doc) {
+                bitset.insert(doc);
+            }
+            scorer.advance();
+        }
+        let bitset = Arc::new(bitset);
+        self.filter_cache.put(key, bitset.clone());
+        Ok(bitset)
+    }
+}
+
+impl Weight for CachedFilterWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let bitset = self.matching_docs(reader)?;
+        let docset = BitSetDocSet::from(BitSet::clone(&bitset));
+        Ok(Box::new(ConstScorer::new(docset, boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        if !self.matching_docs(reader)?.contains(doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("CachedFilterQuery", 1.0))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        Ok(self.matching_docs(reader)?.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterCache;
+    use crate::collector::Count;
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STRING, TEXT};
+    use crate::{Index, Term};
+
+    #[test]
+    fn test_filter_cache() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_text_field("tenant", STRING);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let filter_cache = FilterCache::new(10);
+        let reader = index
+            .reader_builder()
+            .reload_policy(crate::ReloadPolicy::Manual)
+            .filter_cache(filter_cache.clone())
+            .try_into()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(tenant => "a", body => "x y"))?;
+        index_writer.add_document(doc!(tenant => "b", body => "x"))?;
+        index_writer.add_document(doc!(tenant => "a", body => "y"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(tenant => "a", body => "x"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+
+        let tenant_query = |tenant_value: &str| {
+            filter_cache.cached(Box::new(TermQuery::new(
+                Term::from_field_text(tenant, tenant_value),
+                IndexRecordOption::Basic,
+            )))
+        };
+        let body_query = |body_value: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(body, body_value),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let filtered_query = |tenant_value: &str, body_value: &str| {
+            BooleanQuery::new(vec![
+                (Occur::Must, body_query(body_value)),
+                (Occur::Must, Box::new(tenant_query(tenant_value))),
+            ])
+        };
+        let searcher = reader.searcher();
+        assert_eq!(searcher.search(&filtered_query("a", "x"), &Count)?, 2);
+        assert_eq!(filter_cache.len(), 2);
+        // The cached bitsets are reused.
+        assert_eq!(searcher.search(&filtered_query("a", "y"), &Count)?, 2);
+        assert_eq!(filter_cache.len(), 2);
+        assert_eq!(searcher.search(&tenant_query("a"), &Count)?, 3);
+        assert_eq!(searcher.search(&filtered_query("b", "x"), &Count)?, 1);
+        assert_eq!(filter_cache.len(), 4);
+
+        // Deletes change the version of the first segment: its bitsets are evicted
+        // on reload and recomputed, while those of the second segment are kept.
+        index_writer.delete_term(Term::from_field_text(body, "y"));
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(filter_cache.len(), 2);
+        let searcher = reader.searcher();
+        assert_eq!(searcher.search(&tenant_query("a"), &Count)?, 1);
+        assert_eq!(filter_cache.len(), 3);
+
+        filter_cache.clear();
+        assert!(filter_cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_cache_lru_eviction() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_text_field("tenant", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for tenant_value in &["a", "b", "c"] {
+            index_writer.add_document(doc!(tenant => *tenant_value))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let filter_cache = FilterCache::new(2);
+        for tenant_value in &["a", "b", "c"] {
+            let query = filter_cache.cached(Box::new(TermQuery::new(
+                Term::from_field_text(tenant, tenant_value),
+                IndexRecordOption::Basic,
+            )));
+            assert_eq!(searcher.search(&query, &Count)?, 1);
+        }
+        assert_eq!(filter_cache.len(), 2);
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
 use crate::query::{AutomatonWeight, Query, Weight};
-use crate::schema::Term;
+use crate::schema::{FieldType, Schema, Term};
 use crate::Searcher;
 use crate::TantivyError::InvalidArgument;
 use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
 use tantivy_fst::Automaton;
 
@@ -35,19 +35,84 @@ impl Automaton for DfaWrapper {
 
 /// A range of Levenshtein distances that we will build DFAs for our terms
 /// The computation is exponential, so best keep it to low single digits
-const VALID_LEVENSHTEIN_DISTANCE_RANGE: Range<u8> = 0..3;
+/// Automaton matching an exact prefix, followed by a suffix accepted by a
+/// Levenshtein automaton.
+pub(crate) struct PrefixLockedDfa {
+    exact_prefix: Vec<u8>,
+    dfa: DfaWrapper,
+}
+
+#[derive(Clone)]
+pub(crate) enum PrefixLockedState {
+    Prefix(usize),
+    Dfa(u32),
+}
+
+impl PrefixLockedDfa {
+    fn dfa_start(&self) -> PrefixLockedState {
+        PrefixLockedState::Dfa(self.dfa.start())
+    }
+}
+
+impl Automaton for PrefixLockedDfa {
+    type State = PrefixLockedState;
+
+    fn start(&self) -> Self::State {
+        if self.exact_prefix.is_empty() {
+            self.dfa_start()
+        } else {
+            PrefixLockedState::Prefix(0)
+        }
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixLockedState::Prefix(_) => false,
+            PrefixLockedState::Dfa(dfa_state) => self.dfa.is_match(dfa_state),
+        }
+    }
 
-static LEV_BUILDER: Lazy<HashMap<(u8, bool), LevenshteinAutomatonBuilder>> = Lazy::new(|| {
-    let mut lev_builder_cache = HashMap::new();
-    // TODO make population lazy on a `(distance, val)` basis
-    for distance in VALID_LEVENSHTEIN_DISTANCE_RANGE {
-        for &transposition in &[false, true] {
-            let lev_automaton_builder = LevenshteinAutomatonBuilder::new(distance, transposition);
-            lev_builder_cache.insert((distance, transposition), lev_automaton_builder);
+    fn can_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixLockedState::Prefix(_) => true,
+            PrefixLockedState::Dfa(dfa_state) => self.dfa.can_match(dfa_state),
         }
     }
-    lev_builder_cache
-});
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        match *state {
+            PrefixLockedState::Prefix(pos) if self.exact_prefix[pos] == byte => {
+                if pos + 1 == self.exact_prefix.len() {
+                    self.dfa_start()
+                } else {
+                    PrefixLockedState::Prefix(pos + 1)
+                }
+            }
+            PrefixLockedState::Prefix(_) => {
+                PrefixLockedState::Dfa(levenshtein_automata::SINK_STATE)
+            }
+            PrefixLockedState::Dfa(dfa_state) => {
+                PrefixLockedState::Dfa(self.dfa.accept(&dfa_state, byte))
+            }
+        }
+    }
+}
+
+pub(crate) const VALID_LEVENSHTEIN_DISTANCE_RANGE: Range<u8> = 0..3;
+
+pub(crate) static LEV_BUILDER: Lazy<HashMap<(u8, bool), LevenshteinAutomatonBuilder>> =
+    Lazy::new(|| {
+        let mut lev_builder_cache = HashMap::new();
+        // TODO make population lazy on a `(distance, val)` basis
+        for distance in VALID_LEVENSHTEIN_DISTANCE_RANGE {
+            for &transposition in &[false, true] {
+                let lev_automaton_builder =
+                    LevenshteinAutomatonBuilder::new(distance, transposition);
+                lev_builder_cache.insert((distance, transposition), lev_automaton_builder);
+            }
+        }
+        lev_builder_cache
+    });
 
 /// A Fuzzy Query matches all of the documents
 /// containing a specific term that is within
@@ -102,8 +167,10 @@ pub struct FuzzyTermQuery {
     distance: u8,
     /// Should a transposition cost 1 or 2?
     transposition_cost_one: bool,
-    ///
+    /// Should the term be a prefix of the matched terms, up to the distance?
     prefix: bool,
+    /// Number of leading characters that have to match exactly.
+    exact_prefix_len: usize,
 }
 
 impl FuzzyTermQuery {
@@ -114,6 +181,7 @@ impl FuzzyTermQuery {
             distance,
             transposition_cost_one,
             prefix: false,
+            exact_prefix_len: 0,
         }
     }
 
@@ -124,22 +192,69 @@ impl FuzzyTermQuery {
             distance,
             transposition_cost_one,
             prefix: true,
+            exact_prefix_len: 0,
+        }
+    }
+
+    /// Creates a new Fuzzy Query with the default fuzziness of the field of the term,
+    /// as configured with
+    /// [`TextFieldIndexing::set_fuzziness`](../schema/struct.TextFieldIndexing.html#method.set_fuzziness).
+    ///
+    /// Fields without a configured fuzziness use `FuzzinessOptions::default()`.
+    pub fn from_field_defaults(term: Term, schema: &Schema) -> FuzzyTermQuery {
+        let fuzziness = match schema.get_field_entry(term.field()).field_type() {
+            FieldType::Str(text_options) => text_options
+                .get_indexing_options()
+                .and_then(|indexing_options| indexing_options.fuzziness()),
+            _ => None,
         }
+        .unwrap_or_default();
+        FuzzyTermQuery::new(
+            term,
+            fuzziness.distance(),
+            fuzziness.transposition_cost_one(),
+        )
+        .with_exact_prefix_len(fuzziness.exact_prefix_len())
+    }
+
+    /// Requires the first `exact_prefix_len` characters of the matched terms to
+    /// be equal to the ones of the term.
+    ///
+    /// The edits are only allowed after this prefix. This shrinks the part of the
+    /// term dictionary that has to be explored, in particular for short terms.
+    pub fn with_exact_prefix_len(mut self, exact_prefix_len: usize) -> FuzzyTermQuery {
+        self.exact_prefix_len = exact_prefix_len;
+        self
+    }
+
+    /// Returns the number of leading characters that have to match exactly.
+    pub fn exact_prefix_len(&self) -> usize {
+        self.exact_prefix_len
     }
 
-    fn specialized_weight(&self) -> crate::Result<AutomatonWeight<DfaWrapper>> {
+    fn specialized_weight(&self) -> crate::Result<AutomatonWeight<PrefixLockedDfa>> {
         // LEV_BUILDER is a HashMap, whose `get` method returns an Option
         match LEV_BUILDER.get(&(self.distance, self.transposition_cost_one)) {
             // Unwrap the option and build the Ok(AutomatonWeight)
             Some(automaton_builder) => {
+                let text = self.term.text();
+                let exact_prefix_byte_len = text
+                    .char_indices()
+                    .nth(self.exact_prefix_len)
+                    .map(|(byte_offset, _)| byte_offset)
+                    .unwrap_or_else(|| text.len());
+                let (exact_prefix, suffix) = text.split_at(exact_prefix_byte_len);
                 let automaton = if self.prefix {
-                    automaton_builder.build_prefix_dfa(self.term.text())
+                    automaton_builder.build_prefix_dfa(suffix)
                 } else {
-                    automaton_builder.build_dfa(self.term.text())
+                    automaton_builder.build_dfa(suffix)
                 };
                 Ok(AutomatonWeight::new(
                     self.term.field(),
-                    DfaWrapper(automaton),
+                    PrefixLockedDfa {
+                        exact_prefix: exact_prefix.as_bytes().to_vec(),
+                        dfa: DfaWrapper(automaton),
+                    },
                 ))
             }
             None => Err(InvalidArgument(format!(
@@ -158,6 +273,10 @@ impl Query for FuzzyTermQuery {
     ) -> crate::Result<Box<dyn Weight>> {
         Ok(Box::new(self.specialized_weight()?))
     }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        terms.entry(self.term.clone()).or_insert(false);
+    }
 }
 
 #[cfg(test)]
@@ -168,6 +287,7 @@ mod test {
     use crate::collector::TopDocs;
     use crate::schema::Schema;
     use crate::schema::TEXT;
+    use crate::schema::{FuzzinessOptions, TextFieldIndexing, TextOptions};
     use crate::Index;
     use crate::Term;
 
@@ -221,6 +341,72 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    pub fn test_fuzzy_term_exact_prefix() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let country_field = schema_builder.add_text_field("country", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        for country in &["japan", "japon", "kapan", "jaoan", "éjapan"] {
+            index_writer.add_document(doc!(country_field => *country))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let count = |text: &str, exact_prefix_len: usize| {
+            let term = Term::from_field_text(country_field, text);
+            let fuzzy_query =
+                FuzzyTermQuery::new(term, 1, true).with_exact_prefix_len(exact_prefix_len);
+            searcher.search(&fuzzy_query, &Count).unwrap()
+        };
+        assert_eq!(count("japan", 0), 5);
+        assert_eq!(count("japan", 1), 3);
+        assert_eq!(count("japan", 3), 2);
+        // The prefix can be longer than the term.
+        assert_eq!(count("japan", 10), 1);
+        // The prefix is counted in characters.
+        assert_eq!(count("éjapon", 1), 1);
+        assert_eq!(count("éjapan", 2), 1);
+        let term = Term::from_field_text(country_field, "jap");
+        let fuzzy_query = FuzzyTermQuery::new_prefix(term, 1, true).with_exact_prefix_len(2);
+        assert_eq!(searcher.search(&fuzzy_query, &Count)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_fuzzy_term_from_field_defaults() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let fuzzy_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_fuzziness(FuzzinessOptions::new(2).set_exact_prefix_len(1)),
+        );
+        let country_field = schema_builder.add_text_field("country", fuzzy_options);
+        let default_field = schema_builder.add_text_field("default", TEXT);
+        let schema = schema_builder.build();
+        let fuzzy_query =
+            FuzzyTermQuery::from_field_defaults(Term::from_field_text(country_field, "a"), &schema);
+        assert_eq!(fuzzy_query.distance, 2);
+        assert!(fuzzy_query.transposition_cost_one);
+        assert_eq!(fuzzy_query.exact_prefix_len(), 1);
+        let fuzzy_query =
+            FuzzyTermQuery::from_field_defaults(Term::from_field_text(default_field, "a"), &schema);
+        assert_eq!(fuzzy_query.distance, 1);
+        assert_eq!(fuzzy_query.exact_prefix_len(), 0);
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(country_field => "japan"))?;
+        index_writer.add_document(doc!(country_field => "kapan"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fuzzy_query = FuzzyTermQuery::from_field_defaults(
+            Term::from_field_text(country_field, "jaapx"),
+            &schema,
+        );
+        assert_eq!(searcher.search(&fuzzy_query, &Count)?, 1);
+        Ok(())
+    }
+
     #[test]
     pub fn test_fuzzy_term_transposition_cost_one() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
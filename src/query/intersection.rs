@@ -1,9 +1,12 @@
 use crate::docset::{DocSet, TERMINATED};
+use crate::postings::first_common_doc;
 use crate::query::term_query::TermScorer;
+use crate::query::BitSetDocSet;
 use crate::query::EmptyScorer;
 use crate::query::Scorer;
 use crate::DocId;
 use crate::Score;
+use common::BitSet;
 
 /// Returns the intersection scorer.
 ///
@@ -20,7 +23,7 @@ pub fn intersect_scorers(mut scorers: Vec<Box<dyn Scorer>>) -> Box<dyn Scorer> {
     if scorers.len() == 1 {
         return scorers.pop().unwrap();
     }
-    scorers.sort_by_key(|scorer| scorer.size_hint());
+    scorers.sort_by_key(|scorer| (scorer.size_hint(), scorer.cost()));
     let doc = go_to_first_doc(&mut scorers[..]);
     if doc == TERMINATED {
         return Box::new(EmptyScorer);
@@ -45,6 +48,43 @@ pub fn intersect_scorers(mut scorers: Vec<Box<dyn Scorer>>) -> Box<dyn Scorer> {
     })
 }
 
+/// Computes the intersection of `docsets`, whose docs are all lower than `max_doc`,
+/// eagerly in a bitset.
+///
+/// The docs of the rarest docset are accumulated in a bitset, which is then narrowed
+/// down by going through each of the other docsets, from the rarest to the most common.
+/// Unlike `Intersection`, the docsets are iterated sequentially rather than sought.
+pub(crate) fn intersect_in_bitset<TDocSet: DocSet>(
+    mut docsets: Vec<TDocSet>,
+    max_doc: DocId,
+) -> BitSetDocSet {
+    docsets.sort_by_key(|docset| (docset.size_hint(), docset.cost()));
+    let mut bitset = BitSet::with_max_value(max_doc);
+    let mut docsets_it = docsets.into_iter();
+    if let Some(mut rarest_docset) = docsets_it.next() {
+        let mut doc = rarest_docset.doc();
+        while doc != TERMINATED {
+            bitset.insert(doc);
+            doc = rarest_docset.advance();
+        }
+    }
+    for mut docset in docsets_it {
+        if bitset.len() == 0 {
+            break;
+        }
+        let mut narrowed_bitset = BitSet::with_max_value(max_doc);
+        let mut doc = docset.doc();
+        while doc != TERMINATED {
+            if bitset.contains(doc) {
+                narrowed_bitset.insert(doc);
+            }
+            doc = docset.advance();
+        }
+        bitset = narrowed_bitset;
+    }
+    BitSetDocSet::from(bitset)
+}
+
 /// Creates a `DocSet` that iterate through the intersection of two or more `DocSet`s.
 pub struct Intersection<TDocSet: DocSet, TOtherDocSet: DocSet = Box<dyn Scorer>> {
     left: TDocSet,
@@ -67,11 +107,37 @@ fn go_to_first_doc<TDocSet: DocSet>(docsets: &mut [TDocSet]) -> DocId {
     }
 }
 
+/// Looks for the next doc of the intersection of `left` and `right` among the docs
+/// they have already decoded, and returns it, or the lowest doc after them if they
+/// have no doc in common.
+///
+/// `left` is expected to be positioned on `candidate`, and `right` on a doc
+/// lower or equal to `candidate`. If one of them has no decoded docs,
+/// `candidate` is returned as is.
+fn skip_decoded_docs<TDocSet: DocSet>(left: &TDocSet, right: &TDocSet, candidate: DocId) -> DocId {
+    let (left_docs, right_docs) = (left.buffered_docs(), right.buffered_docs());
+    let (left_last_doc, right_last_doc) = match (left_docs.last(), right_docs.last()) {
+        (Some(&left_last_doc), Some(&right_last_doc)) => (left_last_doc, right_last_doc),
+        _ => return candidate,
+    };
+    debug_assert_eq!(left_docs[0], candidate);
+    if let Some(doc) = first_common_doc(left_docs, right_docs) {
+        return doc;
+    }
+    // None of the docs up to the lowest of the last decoded docs is in the intersection.
+    candidate.max(left_last_doc.min(right_last_doc) + 1)
+}
+
 impl<TDocSet: DocSet> Intersection<TDocSet, TDocSet> {
-    pub(crate) fn new(mut docsets: Vec<TDocSet>) -> Intersection<TDocSet, TDocSet> {
+    /// Creates the intersection of the given docsets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if less than two docsets are given.
+    pub fn new(mut docsets: Vec<TDocSet>) -> Intersection<TDocSet, TDocSet> {
         let num_docsets = docsets.len();
         assert!(num_docsets >= 2);
-        docsets.sort_by_key(|docset| docset.size_hint());
+        docsets.sort_by_key(|docset| (docset.size_hint(), docset.cost()));
         go_to_first_doc(&mut docsets);
         let left = docsets.remove(0);
         let right = docsets.remove(0);
@@ -103,6 +169,7 @@ impl<TDocSet: DocSet, TOtherDocSet: DocSet> DocSet for Intersection<TDocSet, TOt
             // of the two rarest `DocSet` in the intersection.
 
             loop {
+                candidate = skip_decoded_docs(left, right, candidate);
                 let right_doc = right.seek(candidate);
                 candidate = left.seek(right_doc);
                 if candidate == right_doc {
@@ -145,6 +212,19 @@ impl<TDocSet: DocSet, TOtherDocSet: DocSet> DocSet for Intersection<TDocSet, TOt
     fn size_hint(&self) -> u32 {
         self.left.size_hint()
     }
+
+    /// The docs of the rarest docset drive the intersection: every other
+    /// docset is sought at most once per doc of the rarest docset.
+    fn cost(&self) -> u64 {
+        let left_cost = self.left.cost();
+        left_cost
+            + self.right.cost().min(left_cost)
+            + self
+                .others
+                .iter()
+                .map(|docset| docset.cost().min(left_cost))
+                .sum::<u64>()
+    }
 }
 
 impl<TScorer, TOtherScorer> Scorer for Intersection<TScorer, TOtherScorer>
@@ -161,10 +241,12 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::Intersection;
+    use super::{intersect_in_bitset, Intersection};
     use crate::docset::{DocSet, TERMINATED};
     use crate::postings::tests::test_skip_against_unoptimized;
+    use crate::postings::SegmentPostings;
     use crate::query::VecDocSet;
+    use crate::DocId;
 
     #[test]
     fn test_intersection() {
@@ -239,6 +321,39 @@ mod tests {
         );
     }
 
+    fn multiples_of(n: DocId) -> Vec<DocId> {
+        (0..3_000).filter(|doc| doc % n == 0).collect()
+    }
+
+    fn collect_docs<TDocSet: DocSet>(mut docset: TDocSet) -> Vec<DocId> {
+        let mut docs = Vec::new();
+        while docset.doc() != TERMINATED {
+            docs.push(docset.doc());
+            docset.advance();
+        }
+        docs
+    }
+
+    #[test]
+    fn test_intersection_of_decoded_blocks() {
+        let postings = |n: DocId| SegmentPostings::create_from_docs(&multiples_of(n));
+        // The decoded blocks of the postings are intersected at once.
+        let intersection = Intersection::new(vec![postings(6), postings(35)]);
+        assert_eq!(collect_docs(intersection), multiples_of(210));
+        let intersection = Intersection::new(vec![postings(2), postings(3), postings(7)]);
+        assert_eq!(collect_docs(intersection), multiples_of(42));
+        let odd_docs: Vec<DocId> = (0..3_000).filter(|doc| doc % 2 == 1).collect();
+        let intersection = Intersection::new(vec![
+            postings(2),
+            SegmentPostings::create_from_docs(&odd_docs),
+        ]);
+        assert_eq!(intersection.doc(), TERMINATED);
+        test_skip_against_unoptimized(
+            || Box::new(Intersection::new(vec![postings(4), postings(10)])),
+            vec![0, 1, 20, 21, 500, 1_999, 2_000, 2_980, 3_000],
+        );
+    }
+
     #[test]
     fn test_intersection_empty() {
         let a = VecDocSet::from(vec![1, 3]);
@@ -247,4 +362,27 @@ mod tests {
         let intersection = Intersection::new(vec![a, b, c]);
         assert_eq!(intersection.doc(), TERMINATED);
     }
+
+    #[test]
+    fn test_intersect_in_bitset() {
+        let a = VecDocSet::from(vec![1, 3, 9, 18, 40]);
+        let b = VecDocSet::from(vec![3, 4, 9, 18]);
+        let c = VecDocSet::from(vec![0, 3, 9, 40]);
+        let mut intersection = intersect_in_bitset(vec![a, b, c], 50);
+        assert_eq!(intersection.doc(), 3);
+        assert_eq!(intersection.advance(), 9);
+        assert_eq!(intersection.advance(), TERMINATED);
+        let a = VecDocSet::from(vec![1, 3]);
+        let b = VecDocSet::from(vec![2, 4]);
+        assert_eq!(intersect_in_bitset(vec![a, b], 50).doc(), TERMINATED);
+    }
+
+    #[test]
+    fn test_intersection_cost() {
+        let rare = VecDocSet::from(vec![3, 9]);
+        let common = VecDocSet::from((0..100).collect::<Vec<_>>());
+        let intersection = Intersection::new(vec![common, rare]);
+        assert_eq!(intersection.size_hint(), 2);
+        assert_eq!(intersection.cost(), 4);
+    }
 }
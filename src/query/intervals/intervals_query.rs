@@ -0,0 +1,585 @@
+use std::collections::BTreeMap;
+
+use super::intervals_source::{
+    containing_intervals, or_intervals, ordered_intervals, unordered_intervals, Interval,
+    SourceKind,
+};
+use super::IntervalsSource;
+use crate::core::searcher::Searcher;
+use crate::docset::{DocSet, TERMINATED};
+use crate::fieldnorm::FieldNormReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::query::bm25::Bm25Weight;
+use crate::query::explanation::does_not_match;
+use crate::query::{Explanation, Query, Scorer, Weight};
+use crate::schema::{IndexRecordOption, Term};
+use crate::{DocId, Score, SegmentReader};
+
+/// `IntervalsQuery` matches the documents in which an [`IntervalsSource`] has
+/// at least one interval of positions.
+///
+/// Intervals give a finer control over proximity than the slop of a phrase:
+/// terms can be required to appear in order or not, within a maximum number
+/// of gaps, and intervals can be filtered by the intervals they contain.
+/// See [`IntervalsSource`] for the available combinators.
+///
+/// The documents are scored with BM25, the term frequency being the number of
+/// intervals in the document.
+///
+/// All of the terms must belong to the same field, which must have its positions indexed.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::{IntervalsQuery, IntervalsSource};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer(50_000_000)?;
+/// index_writer.add_document(doc!(body => "the quick brown fox"))?;
+/// index_writer.add_document(doc!(body => "the fox is quick"))?;
+/// index_writer.add_document(doc!(body => "the quick and very clever brown fox"))?;
+/// index_writer.commit()?;
+///
+/// let term = |text: &str| IntervalsSource::term(Term::from_field_text(body, text));
+/// let searcher = index.reader()?.searcher();
+/// // "quick" followed by "fox", with at most two words in between.
+/// let query = IntervalsQuery::new(
+///     IntervalsSource::ordered(vec![term("quick"), term("fox")]).with_max_gaps(2),
+/// );
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// // "quick" and "fox" in any order, without "brown" in between.
+/// let query = IntervalsQuery::new(
+///     IntervalsSource::unordered(vec![term("quick"), term("fox")]).not_containing(term("brown")),
+/// );
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct IntervalsQuery {
+    source: IntervalsSource,
+}
+
+impl IntervalsQuery {
+    /// Creates a new `IntervalsQuery` matching the intervals of `source`.
+    ///
+    /// All of the terms of `source` must belong to the same field.
+    pub fn new(source: IntervalsSource) -> IntervalsQuery {
+        let mut terms = Vec::new();
+        source.terms(&mut terms);
+        let field = terms[0].field();
+        assert!(
+            terms.iter().all(|term| term.field() == field),
+            "All terms from an intervals query must belong to the same field"
+        );
+        IntervalsQuery { source }
+    }
+
+    /// The source of the intervals of the query.
+    pub fn source(&self) -> &IntervalsSource {
+        &self.source
+    }
+}
+
+impl Query for IntervalsQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> crate::Result<Box<dyn Weight>> {
+        let field = self.source.field();
+        let field_entry = searcher.schema().get_field_entry(field);
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(IndexRecordOption::has_positions)
+            .unwrap_or(false);
+        if !has_positions {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied intervals query on field {:?}, which does not have positions indexed",
+                field_entry.name()
+            )));
+        }
+        let mut terms = Vec::new();
+        self.source.terms(&mut terms);
+        let terms: Vec<Term> = terms.into_iter().cloned().collect();
+        let similarity_weight = Bm25Weight::for_terms(searcher, &terms)?;
+        Ok(Box::new(IntervalsWeight {
+            source: self.source.clone(),
+            similarity_weight,
+            scoring_enabled,
+        }))
+    }
+
+    fn query_terms(&self, term_set: &mut BTreeMap<Term, bool>) {
+        let mut terms = Vec::new();
+        self.source.terms(&mut terms);
+        for term in terms {
+            term_set.insert(term.clone(), true);
+        }
+    }
+}
+
+struct IntervalsWeight {
+    source: IntervalsSource,
+    similarity_weight: Bm25Weight,
+    scoring_enabled: bool,
+}
+
+impl IntervalsWeight {
+    fn fieldnorm_reader(&self, reader: &SegmentReader) -> crate::Result<FieldNormReader> {
+        if self.scoring_enabled {
+            reader.get_fieldnorms_reader(self.source.field())
+        } else {
+            Ok(FieldNormReader::constant(reader.max_doc(), 1))
+        }
+    }
+
+    fn intervals_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<IntervalsScorer> {
+        let root = IntervalsNode::open(&self.source, reader)?;
+        Ok(IntervalsScorer::new(
+            root,
+            self.similarity_weight.boost_by(boost),
+            self.fieldnorm_reader(reader)?,
+        ))
+    }
+}
+
+impl Weight for IntervalsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(Box::new(self.intervals_scorer(reader, boost)?))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.intervals_scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let fieldnorm_id = scorer.fieldnorm_reader.fieldnorm_id(doc);
+        let mut explanation = Explanation::new("Intervals Scorer", scorer.score());
+        explanation.add_detail(
+            self.similarity_weight
+                .explain(fieldnorm_id, scorer.num_intervals),
+        );
+        Ok(explanation)
+    }
+}
+
+/// Iterator over the documents and the intervals of an `IntervalsSource` in a segment.
+///
+/// The nodes matching the documents of all of their children (`Ordered`, `Unordered`
+/// and `Containing`) are kept on a document all of their children are on.
+enum IntervalsNode {
+    Empty,
+    Term(Box<SegmentPostings>),
+    Ordered(Vec<IntervalsNode>),
+    Unordered(Vec<IntervalsNode>),
+    Or(Vec<IntervalsNode>),
+    MaxGaps(Box<IntervalsNode>, u32),
+    Containing(Vec<IntervalsNode>),
+    NotContaining(Box<IntervalsNode>, Box<IntervalsNode>),
+}
+
+impl IntervalsNode {
+    fn open(source: &IntervalsSource, reader: &SegmentReader) -> crate::Result<IntervalsNode> {
+        let open_all = |sources: &[IntervalsSource]| -> crate::Result<Vec<IntervalsNode>> {
+            sources
+                .iter()
+                .map(|source| IntervalsNode::open(source, reader))
+                .collect()
+        };
+        let mut node = match &source.0 {
+            SourceKind::Term(term) => {
+                let inverted_index = reader.inverted_index(term.field())?;
+                let postings_opt = if reader.has_deletes() {
+                    inverted_index.read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
+                } else {
+                    inverted_index
+                        .read_postings_no_deletes(term, IndexRecordOption::WithFreqsAndPositions)?
+                };
+                postings_opt
+                    .map(|postings| IntervalsNode::Term(Box::new(postings)))
+                    .unwrap_or(IntervalsNode::Empty)
+            }
+            SourceKind::Ordered(sources) => IntervalsNode::Ordered(open_all(sources)?),
+            SourceKind::Unordered(sources) => IntervalsNode::Unordered(open_all(sources)?),
+            SourceKind::Or(sources) => IntervalsNode::Or(open_all(sources)?),
+            SourceKind::MaxGaps(source, max_gaps) => {
+                IntervalsNode::MaxGaps(Box::new(IntervalsNode::open(source, reader)?), *max_gaps)
+            }
+            SourceKind::Containing(source, other) => IntervalsNode::Containing(vec![
+                IntervalsNode::open(source, reader)?,
+                IntervalsNode::open(other, reader)?,
+            ]),
+            SourceKind::NotContaining(source, other) => IntervalsNode::NotContaining(
+                Box::new(IntervalsNode::open(source, reader)?),
+                Box::new(IntervalsNode::open(other, reader)?),
+            ),
+        };
+        if let IntervalsNode::Ordered(children)
+        | IntervalsNode::Unordered(children)
+        | IntervalsNode::Containing(children) = &mut node
+        {
+            let target = children.iter().map(IntervalsNode::doc).max().unwrap_or(0);
+            align(children, target);
+        }
+        Ok(node)
+    }
+
+    fn doc(&self) -> DocId {
+        match self {
+            IntervalsNode::Empty => TERMINATED,
+            IntervalsNode::Term(postings) => postings.doc(),
+            IntervalsNode::Ordered(children)
+            | IntervalsNode::Unordered(children)
+            | IntervalsNode::Containing(children) => children
+                .iter()
+                .map(IntervalsNode::doc)
+                .max()
+                .unwrap_or(TERMINATED),
+            IntervalsNode::Or(children) => children
+                .iter()
+                .map(IntervalsNode::doc)
+                .min()
+                .unwrap_or(TERMINATED),
+            IntervalsNode::MaxGaps(child, _) | IntervalsNode::NotContaining(child, _) => {
+                child.doc()
+            }
+        }
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        match self {
+            IntervalsNode::Empty => TERMINATED,
+            IntervalsNode::Term(postings) => postings.seek(target),
+            IntervalsNode::Ordered(children)
+            | IntervalsNode::Unordered(children)
+            | IntervalsNode::Containing(children) => align(children, target),
+            IntervalsNode::Or(children) => {
+                for child in children.iter_mut() {
+                    if child.doc() < target {
+                        child.seek(target);
+                    }
+                }
+                self.doc()
+            }
+            IntervalsNode::MaxGaps(child, _) | IntervalsNode::NotContaining(child, _) => {
+                child.seek(target)
+            }
+        }
+    }
+
+    fn advance(&mut self) -> DocId {
+        let doc = self.doc();
+        if doc == TERMINATED {
+            return TERMINATED;
+        }
+        self.seek(doc + 1)
+    }
+
+    /// Returns the intervals of the node in `doc`, sorted.
+    ///
+    /// The node is moved forward to `doc` if it is behind.
+    fn intervals(&mut self, doc: DocId) -> Vec<Interval> {
+        if self.doc() < doc {
+            self.seek(doc);
+        }
+        if self.doc() != doc {
+            return Vec::new();
+        }
+        match self {
+            IntervalsNode::Empty => Vec::new(),
+            IntervalsNode::Term(postings) => {
+                let mut positions = Vec::new();
+                postings.positions(&mut positions);
+                positions.into_iter().map(Interval::at_position).collect()
+            }
+            IntervalsNode::Ordered(children) => {
+                ordered_intervals(&children_intervals(children, doc))
+            }
+            IntervalsNode::Unordered(children) => {
+                unordered_intervals(&children_intervals(children, doc))
+            }
+            IntervalsNode::Or(children) => or_intervals(children_intervals(children, doc)),
+            IntervalsNode::MaxGaps(child, max_gaps) => {
+                let max_gaps = *max_gaps;
+                let mut intervals = child.intervals(doc);
+                intervals.retain(|interval| interval.gaps <= max_gaps);
+                intervals
+            }
+            IntervalsNode::Containing(children) => {
+                let mut children_intervals = children_intervals(children, doc);
+                let other_intervals = children_intervals.pop().unwrap_or_default();
+                let intervals = children_intervals.pop().unwrap_or_default();
+                containing_intervals(intervals, &other_intervals, false)
+            }
+            IntervalsNode::NotContaining(child, other) => {
+                containing_intervals(child.intervals(doc), &other.intervals(doc), true)
+            }
+        }
+    }
+
+    fn cost(&self) -> u64 {
+        match self {
+            IntervalsNode::Empty => 0,
+            IntervalsNode::Term(postings) => postings.cost(),
+            IntervalsNode::Ordered(children)
+            | IntervalsNode::Unordered(children)
+            | IntervalsNode::Containing(children) => {
+                children.iter().map(IntervalsNode::cost).min().unwrap_or(0)
+            }
+            IntervalsNode::Or(children) => children.iter().map(IntervalsNode::cost).sum(),
+            IntervalsNode::MaxGaps(child, _) | IntervalsNode::NotContaining(child, _) => {
+                child.cost()
+            }
+        }
+    }
+}
+
+/// Moves all of the `children` to the first document, from `target`, they are all on.
+fn align(children: &mut [IntervalsNode], mut target: DocId) -> DocId {
+    loop {
+        if target == TERMINATED {
+            return TERMINATED;
+        }
+        let mut aligned = true;
+        for child in children.iter_mut() {
+            let mut doc = child.doc();
+            if doc < target {
+                doc = child.seek(target);
+            }
+            if doc > target {
+                target = doc;
+                aligned = false;
+                break;
+            }
+        }
+        if aligned {
+            return target;
+        }
+    }
+}
+
+fn children_intervals(children: &mut [IntervalsNode], doc: DocId) -> Vec<Vec<Interval>> {
+    children
+        .iter_mut()
+        .map(|child| child.intervals(doc))
+        .collect()
+}
+
+struct IntervalsScorer {
+    root: IntervalsNode,
+    num_intervals: u32,
+    similarity_weight: Bm25Weight,
+    fieldnorm_reader: FieldNormReader,
+}
+
+impl IntervalsScorer {
+    fn new(
+        root: IntervalsNode,
+        similarity_weight: Bm25Weight,
+        fieldnorm_reader: FieldNormReader,
+    ) -> IntervalsScorer {
+        let mut scorer = IntervalsScorer {
+            root,
+            num_intervals: 0,
+            similarity_weight,
+            fieldnorm_reader,
+        };
+        let doc = scorer.root.doc();
+        scorer.find_match(doc);
+        scorer
+    }
+
+    /// Moves forward, from `doc`, to the first document with at least one interval.
+    fn find_match(&mut self, mut doc: DocId) -> DocId {
+        while doc != TERMINATED {
+            self.num_intervals = self.root.intervals(doc).len() as u32;
+            if self.num_intervals > 0 {
+                return doc;
+            }
+            doc = self.root.advance();
+        }
+        TERMINATED
+    }
+}
+
+impl DocSet for IntervalsScorer {
+    fn advance(&mut self) -> DocId {
+        let doc = self.root.advance();
+        self.find_match(doc)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let doc = self.root.seek(target);
+        self.find_match(doc)
+    }
+
+    fn doc(&self) -> DocId {
+        self.root.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.root.cost().min(u64::from(u32::MAX)) as u32
+    }
+
+    fn cost(&self) -> u64 {
+        self.root.cost()
+    }
+}
+
+impl Scorer for IntervalsScorer {
+    fn score(&mut self) -> Score {
+        let fieldnorm_id = self.fieldnorm_reader.fieldnorm_id(self.doc());
+        self.similarity_weight
+            .score(fieldnorm_id, self.num_intervals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{IntervalsQuery, IntervalsSource};
+    use crate::collector::{Count, TopDocs};
+    use crate::query::phrase_query::tests::create_index;
+    use crate::query::{PhraseQuery, Query};
+    use crate::schema::{Field, Schema, STRING};
+    use crate::{DocAddress, Index, Term};
+
+    fn term(field: Field, text: &str) -> IntervalsSource {
+        IntervalsSource::term(Term::from_field_text(field, text))
+    }
+
+    #[test]
+    fn test_intervals_query() -> crate::Result<()> {
+        let index = create_index(&["a b c d", "b a x c", "a x x x b", "c a b", "d d d"])?;
+        let text = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let matching_docs = |source: IntervalsSource| -> Vec<u32> {
+            let query = IntervalsQuery::new(source);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+            let mut docs: Vec<u32> = top_docs
+                .into_iter()
+                .map(|(_, doc_address)| doc_address.doc_id)
+                .collect();
+            docs.sort_unstable();
+            assert_eq!(searcher.search(&query, &Count).unwrap(), docs.len());
+            docs
+        };
+        let ordered = |texts: &[&str]| {
+            IntervalsSource::ordered(texts.iter().map(|&t| term(text, t)).collect())
+        };
+        let unordered = |texts: &[&str]| {
+            IntervalsSource::unordered(texts.iter().map(|&t| term(text, t)).collect())
+        };
+        assert_eq!(matching_docs(term(text, "a")), vec![0, 1, 2, 3]);
+        assert_eq!(matching_docs(ordered(&["a", "b"])), vec![0, 2, 3]);
+        assert_eq!(
+            matching_docs(ordered(&["a", "b"]).with_max_gaps(0)),
+            vec![0, 3]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "b"]).with_max_gaps(2)),
+            vec![0, 3]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "b"]).with_max_gaps(3)),
+            vec![0, 2, 3]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "c"]).with_max_gaps(1)),
+            vec![0, 1]
+        );
+        assert_eq!(
+            matching_docs(unordered(&["a", "b"]).with_max_gaps(0)),
+            vec![0, 1, 3]
+        );
+        assert_eq!(
+            matching_docs(unordered(&["c", "b", "a"]).with_max_gaps(0)),
+            vec![0, 3]
+        );
+        assert_eq!(matching_docs(ordered(&["d", "d", "d"])), vec![4]);
+        assert!(matching_docs(ordered(&["d", "d", "d", "d"])).is_empty());
+        assert!(matching_docs(ordered(&["a", "z"])).is_empty());
+        assert_eq!(
+            matching_docs(IntervalsSource::or(vec![
+                ordered(&["b", "a"]),
+                ordered(&["a", "b"]).with_max_gaps(0),
+            ])),
+            vec![0, 1, 3]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "c"]).containing(term(text, "b"))),
+            vec![0]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "c"]).not_containing(term(text, "b"))),
+            vec![1]
+        );
+        assert_eq!(
+            matching_docs(ordered(&["a", "d"]).not_containing(term(text, "z"))),
+            vec![0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_intervals_query_scores_like_phrase_query() -> crate::Result<()> {
+        let index = create_index(&["a b c", "a b a b c", "b c a", "a c b"])?;
+        let text = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let intervals_query = IntervalsQuery::new(
+            IntervalsSource::ordered(vec![term(text, "a"), term(text, "b")]).with_max_gaps(0),
+        );
+        let phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(text, "a"),
+            Term::from_field_text(text, "b"),
+        ]);
+        assert_eq!(
+            searcher.search(&intervals_query, &TopDocs::with_limit(10))?,
+            searcher.search(&phrase_query, &TopDocs::with_limit(10))?
+        );
+        assert!(intervals_query
+            .explain(&searcher, DocAddress::new(0, 1))
+            .is_ok());
+        assert!(intervals_query
+            .explain(&searcher, DocAddress::new(0, 3))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_intervals_query_with_deletes() -> crate::Result<()> {
+        let index = create_index(&["a b", "a b", "b a"])?;
+        let text = index.schema().get_field("text").unwrap();
+        let query = IntervalsQuery::new(IntervalsSource::ordered(vec![
+            term(text, "a"),
+            term(text, "b"),
+        ]));
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_text(text, "a"));
+        index_writer.add_document(doc!(text => "a x b"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.search(&query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_intervals_query_requires_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = IntervalsQuery::new(term(text, "a"));
+        assert!(matches!(
+            query.weight(&searcher, false),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+use crate::schema::{Field, Term};
+
+/// A span of positions `[start, end]` in a document, both included.
+///
+/// `gaps` is the number of positions of the span that are not covered
+/// by the intervals it was built from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) struct Interval {
+    pub start: u32,
+    pub end: u32,
+    pub gaps: u32,
+}
+
+impl Interval {
+    pub fn at_position(position: u32) -> Interval {
+        Interval {
+            start: position,
+            end: position,
+            gaps: 0,
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum SourceKind {
+    Term(Term),
+    Ordered(Vec<IntervalsSource>),
+    Unordered(Vec<IntervalsSource>),
+    Or(Vec<IntervalsSource>),
+    MaxGaps(Box<IntervalsSource>, u32),
+    Containing(Box<IntervalsSource>, Box<IntervalsSource>),
+    NotContaining(Box<IntervalsSource>, Box<IntervalsSource>),
+}
+
+/// An `IntervalsSource` describes the intervals of positions matched in a document
+/// by an [`IntervalsQuery`](./struct.IntervalsQuery.html).
+///
+/// The sources are built from terms, matching an interval at each of their positions,
+/// and combined into the minimal intervals, i.e. the intervals that do not contain
+/// any other matching interval:
+/// - `ordered` matches the intervals in which its sources appear in order, without overlapping.
+/// - `unordered` matches the intervals in which all of its sources appear, in any order.
+///   The intervals of the sources may overlap.
+/// - `or` matches the intervals of any of its sources.
+/// - `with_max_gaps` only keeps the intervals in which at most `max_gaps` positions are not
+///   covered by the intervals of the sources they are built from.
+/// - `containing` and `not_containing` keep the intervals that contain, or do not contain,
+///   an interval of another source.
+#[derive(Clone, Debug)]
+pub struct IntervalsSource(pub(crate) SourceKind);
+
+impl IntervalsSource {
+    /// Matches an interval at each position of `term`.
+    pub fn term(term: Term) -> IntervalsSource {
+        IntervalsSource(SourceKind::Term(term))
+    }
+
+    /// Matches the minimal intervals in which the intervals of `sources`
+    /// appear in order, without overlapping.
+    ///
+    /// There must be at least one source.
+    pub fn ordered(sources: Vec<IntervalsSource>) -> IntervalsSource {
+        assert!(
+            !sources.is_empty(),
+            "An ordered intervals source requires at least one source."
+        );
+        IntervalsSource(SourceKind::Ordered(sources))
+    }
+
+    /// Matches the minimal intervals containing an interval of each of `sources`,
+    /// in any order.
+    ///
+    /// There must be at least one source.
+    pub fn unordered(sources: Vec<IntervalsSource>) -> IntervalsSource {
+        assert!(
+            !sources.is_empty(),
+            "An unordered intervals source requires at least one source."
+        );
+        IntervalsSource(SourceKind::Unordered(sources))
+    }
+
+    /// Matches the intervals of any of `sources`.
+    ///
+    /// There must be at least one source.
+    pub fn or(sources: Vec<IntervalsSource>) -> IntervalsSource {
+        assert!(
+            !sources.is_empty(),
+            "A disjunction of intervals sources requires at least one source."
+        );
+        IntervalsSource(SourceKind::Or(sources))
+    }
+
+    /// Only keeps the intervals with at most `max_gaps` positions not covered
+    /// by the intervals they are built from.
+    ///
+    /// For instance, `ordered([a, b]).with_max_gaps(1)` matches `a b` and `a x b`,
+    /// but not `a x y b`.
+    pub fn with_max_gaps(self, max_gaps: u32) -> IntervalsSource {
+        IntervalsSource(SourceKind::MaxGaps(Box::new(self), max_gaps))
+    }
+
+    /// Only keeps the intervals containing an interval of `other`.
+    pub fn containing(self, other: IntervalsSource) -> IntervalsSource {
+        IntervalsSource(SourceKind::Containing(Box::new(self), Box::new(other)))
+    }
+
+    /// Only keeps the intervals not containing any interval of `other`.
+    pub fn not_containing(self, other: IntervalsSource) -> IntervalsSource {
+        IntervalsSource(SourceKind::NotContaining(Box::new(self), Box::new(other)))
+    }
+
+    /// Appends the terms of the source to `terms`.
+    pub(crate) fn terms<'a>(&'a self, terms: &mut Vec<&'a Term>) {
+        match &self.0 {
+            SourceKind::Term(term) => terms.push(term),
+            SourceKind::Ordered(sources)
+            | SourceKind::Unordered(sources)
+            | SourceKind::Or(sources) => {
+                for source in sources {
+                    source.terms(terms);
+                }
+            }
+            SourceKind::MaxGaps(source, _) => source.terms(terms),
+            SourceKind::Containing(source, other) | SourceKind::NotContaining(source, other) => {
+                source.terms(terms);
+                other.terms(terms);
+            }
+        }
+    }
+
+    /// Returns the field of the first term of the source.
+    pub(crate) fn field(&self) -> Field {
+        let mut terms = Vec::new();
+        self.terms(&mut terms);
+        terms[0].field()
+    }
+}
+
+/// Index of intervals sorted by start, returning the interval
+/// ending first among the intervals starting at or after a given position.
+struct EarliestEndIndex<'a> {
+    intervals: &'a [Interval],
+    // `earliest_end[i]` is the interval ending first among `intervals[i..]`.
+    earliest_end: Vec<usize>,
+}
+
+impl<'a> EarliestEndIndex<'a> {
+    fn new(intervals: &'a [Interval]) -> EarliestEndIndex<'a> {
+        let mut earliest_end = vec![0; intervals.len()];
+        for i in (0..intervals.len()).rev() {
+            earliest_end[i] = match earliest_end.get(i + 1) {
+                // On ties, the interval starting last is the narrowest.
+                Some(&next) if intervals[next].end <= intervals[i].end => next,
+                _ => i,
+            };
+        }
+        EarliestEndIndex {
+            intervals,
+            earliest_end,
+        }
+    }
+
+    fn earliest_end_from(&self, position: u32) -> Option<&'a Interval> {
+        let first = self
+            .intervals
+            .partition_point(|interval| interval.start < position);
+        let intervals = self.intervals;
+        self.earliest_end.get(first).map(|&i| &intervals[i])
+    }
+}
+
+/// Sorts the intervals and removes the duplicates, keeping the fewest gaps.
+fn sort_dedup(intervals: &mut Vec<Interval>) {
+    intervals.sort_unstable();
+    intervals.dedup_by_key(|interval| (interval.start, interval.end));
+}
+
+/// Keeps the intervals that do not contain any other one, sorted.
+fn minimize(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    sort_dedup(&mut intervals);
+    let mut minimal_intervals = Vec::with_capacity(intervals.len());
+    let mut min_end = u32::MAX;
+    // From the last start to the first, an interval is minimal iff it ends
+    // before all of the intervals starting after it.
+    for i in (0..intervals.len()).rev() {
+        let is_first_of_start = i == 0 || intervals[i - 1].start != intervals[i].start;
+        if is_first_of_start && intervals[i].end < min_end {
+            minimal_intervals.push(intervals[i]);
+        }
+        min_end = min_end.min(intervals[i].end);
+    }
+    minimal_intervals.reverse();
+    minimal_intervals
+}
+
+pub(crate) fn ordered_intervals(sub_intervals: &[Vec<Interval>]) -> Vec<Interval> {
+    if sub_intervals.iter().any(Vec::is_empty) {
+        return Vec::new();
+    }
+    let indexes: Vec<EarliestEndIndex> = sub_intervals[1..]
+        .iter()
+        .map(|intervals| EarliestEndIndex::new(intervals))
+        .collect();
+    let mut intervals = Vec::new();
+    'first: for first in &sub_intervals[0] {
+        let mut end = first.end;
+        let mut covered = first.width();
+        for index in &indexes {
+            match index.earliest_end_from(end + 1) {
+                Some(interval) => {
+                    end = interval.end;
+                    covered += interval.width();
+                }
+                None => continue 'first,
+            }
+        }
+        let width = end - first.start + 1;
+        intervals.push(Interval {
+            start: first.start,
+            end,
+            gaps: width - covered,
+        });
+    }
+    minimize(intervals)
+}
+
+pub(crate) fn unordered_intervals(sub_intervals: &[Vec<Interval>]) -> Vec<Interval> {
+    if sub_intervals.iter().any(Vec::is_empty) {
+        return Vec::new();
+    }
+    let indexes: Vec<EarliestEndIndex> = sub_intervals
+        .iter()
+        .map(|intervals| EarliestEndIndex::new(intervals))
+        .collect();
+    let mut starts: Vec<u32> = sub_intervals
+        .iter()
+        .flatten()
+        .map(|interval| interval.start)
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    let mut intervals = Vec::new();
+    'start: for start in starts {
+        let mut interval = Interval {
+            start: u32::MAX,
+            end: 0,
+            gaps: 0,
+        };
+        let mut covered = 0;
+        for index in &indexes {
+            match index.earliest_end_from(start) {
+                Some(sub_interval) => {
+                    interval.start = interval.start.min(sub_interval.start);
+                    interval.end = interval.end.max(sub_interval.end);
+                    covered += sub_interval.width();
+                }
+                // No interval of this source starts after `start`, nor after the next starts.
+                None => break 'start,
+            }
+        }
+        interval.gaps = interval.width().saturating_sub(covered);
+        intervals.push(interval);
+    }
+    minimize(intervals)
+}
+
+pub(crate) fn or_intervals(sub_intervals: Vec<Vec<Interval>>) -> Vec<Interval> {
+    let mut intervals: Vec<Interval> = sub_intervals.into_iter().flatten().collect();
+    sort_dedup(&mut intervals);
+    intervals
+}
+
+pub(crate) fn containing_intervals(
+    intervals: Vec<Interval>,
+    other_intervals: &[Interval],
+    negate: bool,
+) -> Vec<Interval> {
+    let index = EarliestEndIndex::new(other_intervals);
+    intervals
+        .into_iter()
+        .filter(|interval| {
+            let contains = index
+                .earliest_end_from(interval.start)
+                .map(|other_interval| other_interval.end <= interval.end)
+                .unwrap_or(false);
+            contains != negate
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        containing_intervals, minimize, or_intervals, ordered_intervals, unordered_intervals,
+        Interval,
+    };
+
+    fn positions(positions: &[u32]) -> Vec<Interval> {
+        positions
+            .iter()
+            .map(|&position| Interval::at_position(position))
+            .collect()
+    }
+
+    fn spans(intervals: &[Interval]) -> Vec<(u32, u32, u32)> {
+        intervals
+            .iter()
+            .map(|interval| (interval.start, interval.end, interval.gaps))
+            .collect()
+    }
+
+    fn interval(start: u32, end: u32) -> Interval {
+        Interval {
+            start,
+            end,
+            gaps: 0,
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        let intervals = vec![
+            interval(0, 5),
+            interval(1, 3),
+            interval(1, 4),
+            interval(2, 3),
+            interval(4, 6),
+            interval(4, 6),
+        ];
+        assert_eq!(minimize(intervals), vec![interval(2, 3), interval(4, 6)]);
+    }
+
+    #[test]
+    fn test_ordered_intervals() {
+        // a b a x b
+        let a = positions(&[0, 2]);
+        let b = positions(&[1, 4]);
+        let x = positions(&[3]);
+        assert_eq!(
+            spans(&ordered_intervals(&[a.clone(), b.clone()])),
+            vec![(0, 1, 0), (2, 4, 1)]
+        );
+        assert_eq!(
+            spans(&ordered_intervals(&[b.clone(), a.clone()])),
+            vec![(1, 2, 0)]
+        );
+        assert_eq!(
+            spans(&ordered_intervals(&[a.clone(), x, b.clone()])),
+            vec![(2, 4, 0)]
+        );
+        // the same term does not match twice at the same position.
+        assert_eq!(
+            spans(&ordered_intervals(&[a.clone(), a.clone()])),
+            vec![(0, 2, 1)]
+        );
+        assert!(ordered_intervals(&[a, Vec::new()]).is_empty());
+        assert_eq!(spans(&ordered_intervals(&[b])), vec![(1, 1, 0), (4, 4, 0)]);
+    }
+
+    #[test]
+    fn test_unordered_intervals() {
+        // a b x x a c
+        let a = positions(&[0, 4]);
+        let b = positions(&[1]);
+        let c = positions(&[5]);
+        assert_eq!(
+            spans(&unordered_intervals(&[b.clone(), a.clone()])),
+            vec![(0, 1, 0), (1, 4, 2)]
+        );
+        assert_eq!(
+            spans(&unordered_intervals(&[c.clone(), b.clone()])),
+            vec![(1, 5, 3)]
+        );
+        assert_eq!(
+            spans(&unordered_intervals(&[a.clone(), c])),
+            vec![(4, 5, 0)]
+        );
+        // the intervals may overlap.
+        assert_eq!(
+            spans(&unordered_intervals(&[a.clone(), a])),
+            vec![(0, 0, 0), (4, 4, 0)]
+        );
+        assert!(unordered_intervals(&[b, Vec::new()]).is_empty());
+    }
+
+    #[test]
+    fn test_or_intervals() {
+        let intervals = or_intervals(vec![positions(&[3, 1]), positions(&[1, 2])]);
+        assert_eq!(spans(&intervals), vec![(1, 1, 0), (2, 2, 0), (3, 3, 0)]);
+    }
+
+    #[test]
+    fn test_containing_intervals() {
+        let intervals = vec![interval(0, 2), interval(3, 6), interval(5, 9)];
+        let other_intervals = positions(&[4, 10]);
+        assert_eq!(
+            containing_intervals(intervals.clone(), &other_intervals, false),
+            vec![interval(3, 6)]
+        );
+        assert_eq!(
+            containing_intervals(intervals, &other_intervals, true),
+            vec![interval(0, 2), interval(5, 9)]
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod intervals_query;
+mod intervals_source;
+
+pub use self::intervals_query::IntervalsQuery;
+pub use self::intervals_source::IntervalsSource;
@@ -6,11 +6,16 @@ mod bitset;
 mod bm25;
 mod boolean_query;
 mod boost_query;
+mod buffered_union;
 mod empty_query;
 mod exclude;
 mod explanation;
+mod facet_query;
+mod features_query;
+mod filter_cache;
 mod fuzzy_query;
 mod intersection;
+mod intervals;
 mod more_like_this;
 mod phrase_query;
 mod query;
@@ -18,51 +23,74 @@ mod query_parser;
 mod range_query;
 mod regex_query;
 mod reqopt_scorer;
+#[cfg(feature = "roaring")]
+mod roaring_docset;
 mod scorer;
+mod scratch_arena;
+mod static_rank_query;
+mod tenant_query;
 mod term_query;
+mod term_range_query;
+mod term_set_query;
 mod union;
+mod vector_query;
 mod weight;
 
-#[cfg(test)]
+mod range_docset;
 mod vec_docset;
 
 pub(crate) mod score_combiner;
 pub(crate) use self::bm25::Bm25Weight;
 pub use self::intersection::Intersection;
+pub use self::range_docset::RangeDocSet;
 pub use self::union::Union;
-
-#[cfg(test)]
 pub use self::vec_docset::VecDocSet;
 
 pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::automaton_weight::AutomatonWeight;
 pub use self::bitset::BitSetDocSet;
-pub use self::boolean_query::BooleanQuery;
+pub use self::boolean_query::{BooleanQuery, MinimumShouldMatch, ScoreMode};
 pub use self::boost_query::BoostQuery;
+pub use self::buffered_union::BufferedUnion;
 pub use self::empty_query::{EmptyQuery, EmptyScorer, EmptyWeight};
 pub use self::exclude::Exclude;
+pub(crate) use self::explanation::does_not_match;
 pub use self::explanation::Explanation;
-#[cfg(test)]
-pub(crate) use self::fuzzy_query::DfaWrapper;
+pub use self::facet_query::FacetQuery;
+pub use self::features_query::FeaturesQuery;
+pub use self::filter_cache::{CachedFilterQuery, FilterCache};
 pub use self::fuzzy_query::FuzzyTermQuery;
+pub(crate) use self::fuzzy_query::{DfaWrapper, LEV_BUILDER, VALID_LEVENSHTEIN_DISTANCE_RANGE};
 pub use self::intersection::intersect_scorers;
+pub use self::intervals::{IntervalsQuery, IntervalsSource};
 pub use self::more_like_this::{MoreLikeThisQuery, MoreLikeThisQueryBuilder};
-pub use self::phrase_query::PhraseQuery;
+pub use self::phrase_query::{PhrasePrefixQuery, PhraseQuery, RegexPhraseQuery};
 pub use self::query::{Query, QueryClone};
 pub use self::query_parser::QueryParser;
 pub use self::query_parser::QueryParserError;
+pub use self::query_parser::TermExpander;
 pub use self::range_query::RangeQuery;
 pub use self::regex_query::RegexQuery;
 pub use self::reqopt_scorer::RequiredOptionalScorer;
+#[cfg(feature = "roaring")]
+pub use self::roaring_docset::RoaringDocSet;
 pub use self::scorer::ConstScorer;
 pub use self::scorer::Scorer;
+pub(crate) use self::static_rank_query::static_rank_field;
+pub use self::static_rank_query::{StaticRankQuery, StaticRankReader};
+pub use self::tenant_query::TenantQuery;
 pub use self::term_query::TermQuery;
+pub use self::term_range_query::TermRangeQuery;
+pub use self::term_set_query::TermSetQuery;
+pub use self::vector_query::VectorQuery;
 pub use self::weight::Weight;
+pub use tantivy_query_grammar::Error as QuerySyntaxError;
 pub use tantivy_query_grammar::Occur;
+pub use tantivy_query_grammar::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 
 #[cfg(test)]
 mod tests {
-    use crate::query::QueryParser;
+    use crate::query::{AllQuery, FuzzyTermQuery, Query, QueryParser};
     use crate::schema::{Schema, TEXT};
     use crate::Index;
     use crate::Term;
@@ -122,5 +150,24 @@ mod tests {
             let terms: Vec<(&Term, &bool)> = terms.iter().collect();
             assert_eq!(vec![(&term_a, &false), (&term_b, &false)], terms);
         }
+        {
+            // Positions are required if any of the occurrences of the term requires them.
+            for query in &["\"a b\" a", "a \"a b\"", "a^2 \"a b\"^3"] {
+                let mut terms: BTreeMap<Term, bool> = Default::default();
+                query_parser
+                    .parse_query(query)
+                    .unwrap()
+                    .query_terms(&mut terms);
+                let terms: Vec<(&Term, &bool)> = terms.iter().collect();
+                assert_eq!(vec![(&term_a, &true), (&term_b, &true)], terms);
+            }
+        }
+        {
+            let mut terms: BTreeMap<Term, bool> = Default::default();
+            FuzzyTermQuery::new(term_a.clone(), 1, true).query_terms(&mut terms);
+            AllQuery.query_terms(&mut terms);
+            let terms: Vec<(&Term, &bool)> = terms.iter().collect();
+            assert_eq!(vec![(&term_a, &false)], terms);
+        }
     }
 }
@@ -1,10 +1,14 @@
+mod phrase_prefix_query;
 mod phrase_query;
 mod phrase_scorer;
 mod phrase_weight;
+mod regex_phrase_query;
 
+pub use self::phrase_prefix_query::PhrasePrefixQuery;
 pub use self::phrase_query::PhraseQuery;
 pub use self::phrase_scorer::PhraseScorer;
 pub use self::phrase_weight::PhraseWeight;
+pub use self::regex_phrase_query::RegexPhraseQuery;
 
 #[cfg(test)]
 pub mod tests {
@@ -252,4 +256,50 @@ pub mod tests {
         assert_eq!(test_query(vec![(1, "a"), (3, "c")]), vec![0]);
         Ok(())
     }
+
+    #[test]
+    pub fn test_phrase_query_multivalued_position_gap() -> crate::Result<()> {
+        use crate::schema::{IndexRecordOption, TextFieldIndexing, TextOptions};
+        let mut schema_builder = Schema::builder();
+        let default_gap = schema_builder.add_text_field("default_gap", TEXT);
+        let text_indexing = TextFieldIndexing::default()
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+            .set_position_gap(10);
+        let large_gap = schema_builder.add_text_field(
+            "large_gap",
+            TextOptions::default().set_indexing_options(text_indexing),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(
+                default_gap => "a b",
+                default_gap => "c d",
+                large_gap => "a b",
+                large_gap => "c d",
+            ))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let num_matches = |field, texts: Vec<(usize, &str)>| -> crate::Result<usize> {
+            let terms: Vec<(usize, Term)> = texts
+                .iter()
+                .map(|(offset, text)| (*offset, Term::from_field_text(field, text)))
+                .collect();
+            let phrase_query = PhraseQuery::new_with_offset(terms);
+            Ok(searcher
+                .search(&phrase_query, &TEST_COLLECTOR_WITH_SCORE)?
+                .docs()
+                .len())
+        };
+        for &field in &[default_gap, large_gap] {
+            assert_eq!(num_matches(field, vec![(0, "a"), (1, "b")])?, 1);
+            assert_eq!(num_matches(field, vec![(0, "c"), (1, "d")])?, 1);
+            assert_eq!(num_matches(field, vec![(0, "b"), (1, "c")])?, 0);
+        }
+        assert_eq!(num_matches(default_gap, vec![(0, "b"), (2, "c")])?, 1);
+        assert_eq!(num_matches(large_gap, vec![(0, "b"), (2, "c")])?, 0);
+        assert_eq!(num_matches(large_gap, vec![(0, "b"), (11, "c")])?, 1);
+        Ok(())
+    }
 }
@@ -0,0 +1,336 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::PhraseQuery;
+use crate::core::searcher::Searcher;
+use crate::query::explanation::does_not_match;
+use crate::query::score_combiner::SumCombiner;
+use crate::query::{EmptyScorer, Explanation, Query, Scorer, TermQuery, Union, Weight};
+use crate::schema::IndexRecordOption;
+use crate::schema::{Field, Term};
+use crate::{DocId, Score, SegmentReader};
+
+/// Default maximum number of terms the prefix of a `PhrasePrefixQuery` is expanded to.
+const DEFAULT_MAX_EXPANSIONS: usize = 50;
+
+/// `PhrasePrefixQuery` matches a sequence of words, the last of which
+/// is only a prefix.
+///
+/// For instance the phrase prefix query for `"part ti"` will match
+/// the sentences
+///
+/// **Alan just got a part time job.**
+///
+/// **The part timing is tight.**
+///
+/// It is typically used for search-as-you-type, where the last word
+/// is still being typed.
+///
+/// The prefix is expanded to the terms of the index starting with it, in
+/// lexicographical order, up to a maximum number of expansions (50 by default).
+/// A document matches if it contains the phrase ending with one of the expansions.
+///
+/// Using a `PhrasePrefixQuery` with more than one term on a field requires positions
+/// to be indexed for this field.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::PhrasePrefixQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(50_000_000)?;
+/// index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+/// index_writer.add_document(doc!(title => "The Name of the Rose"))?;
+/// index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = PhrasePrefixQuery::new(vec![
+///     Term::from_field_text(title, "the"),
+///     Term::from_field_text(title, "n"),
+/// ]);
+/// assert_eq!(searcher.search(&query, &Count)?, 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PhrasePrefixQuery {
+    field: Field,
+    phrase_terms: Vec<(usize, Term)>,
+    prefix: (usize, Term),
+    max_expansions: usize,
+}
+
+impl PhrasePrefixQuery {
+    /// Creates a new `PhrasePrefixQuery` given a list of terms.
+    ///
+    /// There must be at least one term, and all terms
+    /// must belong to the same field. The last term is the prefix.
+    /// Offset for each term will be same as index in the Vector
+    pub fn new(terms: Vec<Term>) -> PhrasePrefixQuery {
+        let terms_with_offset = terms.into_iter().enumerate().collect();
+        PhrasePrefixQuery::new_with_offset(terms_with_offset)
+    }
+
+    /// Creates a new `PhrasePrefixQuery` given a list of terms and their offsets.
+    ///
+    /// The term with the largest offset is the prefix.
+    pub fn new_with_offset(mut terms: Vec<(usize, Term)>) -> PhrasePrefixQuery {
+        assert!(
+            !terms.is_empty(),
+            "A phrase prefix query is required to have at least one term."
+        );
+        terms.sort_by_key(|&(offset, _)| offset);
+        let field = terms[0].1.field();
+        assert!(
+            terms[1..].iter().all(|term| term.1.field() == field),
+            "All terms from a phrase prefix query must belong to the same field"
+        );
+        let prefix = terms.pop().unwrap();
+        PhrasePrefixQuery {
+            field,
+            phrase_terms: terms,
+            prefix,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    /// Sets the maximum number of terms the prefix is expanded to.
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> PhrasePrefixQuery {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    /// The `Field` this `PhrasePrefixQuery` is targeting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// `Term`s in the phrase preceding the prefix, without the associated offsets.
+    pub fn phrase_terms(&self) -> Vec<Term> {
+        self.phrase_terms
+            .iter()
+            .map(|(_, term)| term.clone())
+            .collect::<Vec<Term>>()
+    }
+
+    /// The prefix `Term`, ending the phrase.
+    pub fn prefix(&self) -> &Term {
+        &self.prefix.1
+    }
+
+    /// Returns the terms starting with the prefix, in all of the segments of the searcher,
+    /// limited to the `max_expansions` first ones in lexicographical order.
+    fn expand_prefix(&self, searcher: &Searcher) -> crate::Result<Vec<Term>> {
+        let prefix_bytes = self.prefix.1.value_bytes();
+        let mut expansions: BTreeSet<Vec<u8>> = BTreeSet::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(self.field)?;
+            let mut stream = inverted_index
+                .terms()
+                .range()
+                .ge(prefix_bytes)
+                .into_stream()?;
+            let mut num_segment_expansions = 0;
+            while num_segment_expansions < self.max_expansions && stream.try_advance()? {
+                let term_bytes = stream.key();
+                if !term_bytes.starts_with(prefix_bytes) {
+                    break;
+                }
+                expansions.insert(term_bytes.to_vec());
+                num_segment_expansions += 1;
+            }
+        }
+        Ok(expansions
+            .into_iter()
+            .take(self.max_expansions)
+            .map(|term_bytes| Term::from_field_bytes(self.field, &term_bytes))
+            .collect())
+    }
+}
+
+impl Query for PhrasePrefixQuery {
+    /// Create the weight associated to a query.
+    ///
+    /// See [`Weight`](./trait.Weight.html).
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        if !field_entry.is_indexed() {
+            let error_msg = format!("Field {:?} is not indexed.", field_entry.name());
+            return Err(crate::TantivyError::SchemaError(error_msg));
+        }
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(IndexRecordOption::has_positions)
+            .unwrap_or(false);
+        if !self.phrase_terms.is_empty() && !has_positions {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied phrase prefix query on field {:?}, which does not have positions indexed",
+                field_entry.name()
+            )));
+        }
+        let mut weights: Vec<Box<dyn Weight>> = Vec::new();
+        for expansion in self.expand_prefix(searcher)? {
+            if self.phrase_terms.is_empty() {
+                let term_query = TermQuery::new(expansion, IndexRecordOption::WithFreqs);
+                weights.push(Box::new(
+                    term_query.specialized_weight(searcher, scoring_enabled)?,
+                ));
+            } else {
+                let mut terms = self.phrase_terms.clone();
+                terms.push((self.prefix.0, expansion));
+                let phrase_query = PhraseQuery::new_with_offset(terms);
+                weights.push(Box::new(
+                    phrase_query.phrase_weight(searcher, scoring_enabled)?,
+                ));
+            }
+        }
+        Ok(Box::new(PhrasePrefixWeight { weights }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        for (_, term) in &self.phrase_terms {
+            terms.insert(term.clone(), true);
+        }
+    }
+}
+
+/// Weight of a `PhrasePrefixQuery`, summing the weights of the phrases
+/// ending with each of the expansions of the prefix.
+struct PhrasePrefixWeight {
+    weights: Vec<Box<dyn Weight>>,
+}
+
+impl Weight for PhrasePrefixWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let mut scorers = self
+            .weights
+            .iter()
+            .map(|weight| weight.scorer(reader, boost))
+            .collect::<crate::Result<Vec<Box<dyn Scorer>>>>()?;
+        match scorers.len() {
+            0 => Ok(Box::new(EmptyScorer)),
+            1 => Ok(scorers.pop().unwrap()),
+            _ => Ok(Box::new(Union::<_, SumCombiner>::from(scorers))),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut details = Vec::new();
+        for weight in &self.weights {
+            if let Ok(detail) = weight.explain(reader, doc) {
+                details.push(detail);
+            }
+        }
+        if details.is_empty() {
+            return Err(does_not_match(doc));
+        }
+        let score = details.iter().map(Explanation::value).sum();
+        let mut explanation = Explanation::new("PhrasePrefixQuery, sum of", score);
+        for detail in details {
+            explanation.add_detail(detail);
+        }
+        Ok(explanation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::create_index;
+    use super::PhrasePrefixQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::Query;
+    use crate::schema::{Schema, Term, STRING};
+    use crate::{DocAddress, Index};
+
+    #[test]
+    pub fn test_phrase_prefix_query() -> crate::Result<()> {
+        let index = create_index(&[
+            "big cat",
+            "big car",
+            "big dog",
+            "small cat",
+            "big big cart",
+            "a big cat and a big car",
+        ])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let matching_docs = |query: &PhrasePrefixQuery| -> Vec<u32> {
+            let top_docs = searcher.search(query, &TopDocs::with_limit(10)).unwrap();
+            let mut docs: Vec<u32> = top_docs
+                .into_iter()
+                .map(|(_, doc_address)| doc_address.doc_id)
+                .collect();
+            docs.sort_unstable();
+            docs
+        };
+        let query = PhrasePrefixQuery::new(vec![
+            Term::from_field_text(text_field, "big"),
+            Term::from_field_text(text_field, "ca"),
+        ]);
+        assert_eq!(matching_docs(&query), vec![0, 1, 4, 5]);
+        // "car" is the first expansion of "ca".
+        assert_eq!(
+            matching_docs(&query.clone().with_max_expansions(1)),
+            vec![1, 5]
+        );
+        assert_eq!(
+            matching_docs(&query.clone().with_max_expansions(0)),
+            Vec::<u32>::new()
+        );
+        let explanation = query.explain(&searcher, DocAddress::new(0, 5))?;
+        assert_eq!(explanation.details().len(), 2);
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+
+        let single_prefix_query =
+            PhrasePrefixQuery::new(vec![Term::from_field_text(text_field, "ca")]);
+        assert_eq!(matching_docs(&single_prefix_query), vec![0, 1, 3, 4, 5]);
+
+        let unknown_prefix_query = PhrasePrefixQuery::new(vec![
+            Term::from_field_text(text_field, "big"),
+            Term::from_field_text(text_field, "z"),
+        ]);
+        assert_eq!(searcher.search(&unknown_prefix_query, &Count)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_phrase_prefix_query_with_offset() -> crate::Result<()> {
+        let index = create_index(&["a b c d", "a c b e", "a b d e"])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let query = PhrasePrefixQuery::new_with_offset(vec![
+            (3, Term::from_field_text(text_field, "d")),
+            (0, Term::from_field_text(text_field, "a")),
+        ]);
+        assert_eq!(query.prefix(), &Term::from_field_text(text_field, "d"));
+        assert_eq!(searcher.search(&query, &Count)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_phrase_prefix_query_no_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let phrase_prefix_query = PhrasePrefixQuery::new(vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ]);
+        assert!(matches!(
+            phrase_prefix_query.weight(&searcher, false),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        let prefix_query = PhrasePrefixQuery::new(vec![Term::from_field_text(text_field, "a")]);
+        assert!(prefix_query.weight(&searcher, false).is_ok());
+        Ok(())
+    }
+}
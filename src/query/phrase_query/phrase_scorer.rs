@@ -2,6 +2,7 @@ use crate::docset::{DocSet, TERMINATED};
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::Postings;
 use crate::query::bm25::Bm25Weight;
+use crate::query::scratch_arena::{ScratchArena, ScratchBuffer};
 use crate::query::{Intersection, Scorer};
 use crate::{DocId, Score};
 use std::cmp::Ordering;
@@ -40,13 +41,17 @@ impl<TPostings: Postings> DocSet for PostingsWithOffset<TPostings> {
     fn size_hint(&self) -> u32 {
         self.postings.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.postings.cost()
+    }
 }
 
 pub struct PhraseScorer<TPostings: Postings> {
     intersection_docset: Intersection<PostingsWithOffset<TPostings>, PostingsWithOffset<TPostings>>,
     num_terms: usize,
-    left: Vec<u32>,
-    right: Vec<u32>,
+    left: ScratchBuffer,
+    right: ScratchBuffer,
     phrase_count: u32,
     fieldnorm_reader: FieldNormReader,
     similarity_weight: Bm25Weight,
@@ -136,6 +141,7 @@ impl<TPostings: Postings> PhraseScorer<TPostings> {
         similarity_weight: Bm25Weight,
         fieldnorm_reader: FieldNormReader,
         scoring_enabled: bool,
+        scratch_arena: &ScratchArena,
     ) -> PhraseScorer<TPostings> {
         let max_offset = term_postings
             .iter()
@@ -152,8 +158,8 @@ impl<TPostings: Postings> PhraseScorer<TPostings> {
         let mut scorer = PhraseScorer {
             intersection_docset: Intersection::new(postings_with_offsets),
             num_terms: num_docsets,
-            left: Vec::with_capacity(100),
-            right: Vec::with_capacity(100),
+            left: scratch_arena.take_buffer(),
+            right: scratch_arena.take_buffer(),
             phrase_count: 0u32,
             similarity_weight,
             fieldnorm_reader,
@@ -254,6 +260,11 @@ impl<TPostings: Postings> DocSet for PhraseScorer<TPostings> {
     fn size_hint(&self) -> u32 {
         self.intersection_docset.size_hint()
     }
+
+    /// The positions of the terms are read for each of the docs of the intersection.
+    fn cost(&self) -> u64 {
+        self.intersection_docset.cost() * self.num_terms as u64
+    }
 }
 
 impl<TPostings: Postings> Scorer for PhraseScorer<TPostings> {
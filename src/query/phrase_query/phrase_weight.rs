@@ -4,6 +4,7 @@ use crate::fieldnorm::FieldNormReader;
 use crate::postings::SegmentPostings;
 use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
+use crate::query::scratch_arena::ScratchArena;
 use crate::query::Scorer;
 use crate::query::Weight;
 use crate::query::{EmptyScorer, Explanation};
@@ -16,6 +17,7 @@ pub struct PhraseWeight {
     phrase_terms: Vec<(usize, Term)>,
     similarity_weight: Bm25Weight,
     scoring_enabled: bool,
+    scratch_arena: ScratchArena,
 }
 
 impl PhraseWeight {
@@ -29,6 +31,7 @@ impl PhraseWeight {
             phrase_terms,
             similarity_weight,
             scoring_enabled,
+            scratch_arena: ScratchArena::default(),
         }
     }
 
@@ -77,6 +80,7 @@ impl PhraseWeight {
             similarity_weight,
             fieldnorm_reader,
             self.scoring_enabled,
+            &self.scratch_arena,
         )))
     }
 }
@@ -96,7 +100,7 @@ impl Weight for PhraseWeight {
             return Err(does_not_match(doc));
         }
         let mut scorer = scorer_opt.unwrap();
-        if scorer.seek(doc) != doc {
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
             return Err(does_not_match(doc));
         }
         let fieldnorm_reader = self.fieldnorm_reader(reader)?;
@@ -137,4 +141,24 @@ mod tests {
         assert_eq!(phrase_scorer.advance(), TERMINATED);
         Ok(())
     }
+
+    #[test]
+    fn test_phrase_scorers_reuse_scratch_buffers() -> crate::Result<()> {
+        let index = create_index(&["a b c", "a c b"])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ]);
+        let phrase_weight = phrase_query.phrase_weight(&searcher, false)?;
+        let segment_reader = searcher.segment_reader(0);
+        let scorer = phrase_weight.phrase_scorer(segment_reader, 1.0)?.unwrap();
+        assert_eq!(phrase_weight.scratch_arena.num_available_buffers(), 0);
+        drop(scorer);
+        assert_eq!(phrase_weight.scratch_arena.num_available_buffers(), 2);
+        let _scorer = phrase_weight.phrase_scorer(segment_reader, 1.0)?.unwrap();
+        assert_eq!(phrase_weight.scratch_arena.num_available_buffers(), 0);
+        Ok(())
+    }
 }
@@ -0,0 +1,453 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::PhraseScorer;
+use crate::core::searcher::Searcher;
+use crate::docset::{DocSet, TERMINATED};
+use crate::error::TantivyError;
+use crate::fieldnorm::FieldNormReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::query::bm25::Bm25Weight;
+use crate::query::explanation::does_not_match;
+use crate::query::scratch_arena::ScratchArena;
+use crate::query::{EmptyScorer, Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, IndexRecordOption, Term};
+use crate::{DocId, Score, SegmentReader};
+use tantivy_fst::Regex;
+
+/// Default maximum number of terms each slot of a `RegexPhraseQuery` is expanded to.
+const DEFAULT_MAX_EXPANSIONS: usize = 50;
+
+/// Pattern of a slot matching any term.
+const ANY_TERM_PATTERN: &str = "*";
+
+/// `RegexPhraseQuery` matches a sequence of words, each of which is
+/// described by a regex rather than by a single term.
+///
+/// For instance the regex phrase query `["error", "*", "time.*"]`
+/// matches the sentences
+///
+/// **error: connection timeout**
+///
+/// **error: read timed out**
+///
+/// As a shorthand, the pattern `*` matches any term.
+///
+/// Each slot is expanded to the terms of the index matching its regex, in
+/// lexicographical order, up to a maximum number of expansions (50 by default).
+/// On large indexes, a `*` slot is therefore only expanded to a small share of the terms.
+/// A document matches if it contains, at consecutive positions, one of the
+/// expansions of each slot.
+///
+/// Using a `RegexPhraseQuery` on a field requires positions to be indexed for this field.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::RegexPhraseQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(50_000_000)?;
+/// index_writer.add_document(doc!(body => "error: read timeout"))?;
+/// index_writer.add_document(doc!(body => "error: write timeout"))?;
+/// index_writer.add_document(doc!(body => "error: timeout"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = RegexPhraseQuery::new(body, &["error", "*", "time.*"])?;
+/// assert_eq!(searcher.search(&query, &Count)?, 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct RegexPhraseQuery {
+    field: Field,
+    slots: Vec<Arc<Regex>>,
+    max_expansions: usize,
+}
+
+impl RegexPhraseQuery {
+    /// Creates a new `RegexPhraseQuery` given the patterns of its slots, in order.
+    ///
+    /// There must be at least two patterns. Returns an error if one of the
+    /// patterns is not a valid regex.
+    pub fn new(field: Field, patterns: &[&str]) -> crate::Result<RegexPhraseQuery> {
+        assert!(
+            patterns.len() > 1,
+            "A regex phrase query is required to have strictly more than one pattern."
+        );
+        let slots = patterns
+            .iter()
+            .map(|&pattern| {
+                let regex_pattern = if pattern == ANY_TERM_PATTERN {
+                    ".*"
+                } else {
+                    pattern
+                };
+                Regex::new(regex_pattern)
+                    .map(Arc::new)
+                    .map_err(|_| TantivyError::InvalidArgument(pattern.to_string()))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(RegexPhraseQuery {
+            field,
+            slots,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        })
+    }
+
+    /// Sets the maximum number of terms each slot is expanded to.
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> RegexPhraseQuery {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    /// The `Field` this `RegexPhraseQuery` is targeting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the terms matching the regex of a slot, in all of the segments of
+    /// the searcher, limited to the `max_expansions` first ones in lexicographical order.
+    fn expand_slot(&self, searcher: &Searcher, regex: &Regex) -> crate::Result<Vec<Term>> {
+        let mut expansions: BTreeSet<Vec<u8>> = BTreeSet::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(self.field)?;
+            let mut stream = inverted_index.terms().search(regex).into_stream()?;
+            let mut num_segment_expansions = 0;
+            while num_segment_expansions < self.max_expansions && stream.try_advance()? {
+                expansions.insert(stream.key().to_vec());
+                num_segment_expansions += 1;
+            }
+        }
+        Ok(expansions
+            .into_iter()
+            .take(self.max_expansions)
+            .map(|term_bytes| Term::from_field_bytes(self.field, &term_bytes))
+            .collect())
+    }
+}
+
+impl Query for RegexPhraseQuery {
+    /// Create the weight associated to a query.
+    ///
+    /// See [`Weight`](./trait.Weight.html).
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(IndexRecordOption::has_positions)
+            .unwrap_or(false);
+        if !has_positions {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied regex phrase query on field {:?}, which does not have positions indexed",
+                field_entry.name()
+            )));
+        }
+        let mut slot_terms = Vec::with_capacity(self.slots.len());
+        for regex in &self.slots {
+            slot_terms.push(self.expand_slot(searcher, regex)?);
+        }
+        if slot_terms.iter().any(Vec::is_empty) {
+            return Ok(Box::new(RegexPhraseWeight {
+                slot_terms: Vec::new(),
+                similarity_weight: None,
+                scoring_enabled,
+                scratch_arena: ScratchArena::default(),
+            }));
+        }
+        // Each slot is scored as its most frequent expansion.
+        let mut representative_terms = Vec::with_capacity(slot_terms.len());
+        for terms in &slot_terms {
+            let mut representative_term = &terms[0];
+            let mut max_doc_freq = 0;
+            for term in terms {
+                let doc_freq = searcher.doc_freq(term)?;
+                if doc_freq > max_doc_freq {
+                    max_doc_freq = doc_freq;
+                    representative_term = term;
+                }
+            }
+            representative_terms.push(representative_term.clone());
+        }
+        let similarity_weight = Bm25Weight::for_terms(searcher, &representative_terms)?;
+        Ok(Box::new(RegexPhraseWeight {
+            slot_terms,
+            similarity_weight: Some(similarity_weight),
+            scoring_enabled,
+            scratch_arena: ScratchArena::default(),
+        }))
+    }
+}
+
+/// Weight of a `RegexPhraseQuery`.
+///
+/// `slot_terms` is empty if one of the slots has no expansion.
+struct RegexPhraseWeight {
+    slot_terms: Vec<Vec<Term>>,
+    similarity_weight: Option<Bm25Weight>,
+    scoring_enabled: bool,
+    scratch_arena: ScratchArena,
+}
+
+impl RegexPhraseWeight {
+    fn fieldnorm_reader(&self, reader: &SegmentReader) -> crate::Result<FieldNormReader> {
+        let field = self.slot_terms[0][0].field();
+        if self.scoring_enabled {
+            reader.get_fieldnorms_reader(field)
+        } else {
+            Ok(FieldNormReader::constant(reader.max_doc(), 1))
+        }
+    }
+
+    fn phrase_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Option<PhraseScorer<UnionPostings>>> {
+        let similarity_weight = match &self.similarity_weight {
+            Some(similarity_weight) => similarity_weight.boost_by(boost),
+            None => return Ok(None),
+        };
+        let fieldnorm_reader = self.fieldnorm_reader(reader)?;
+        let mut slot_postings_list = Vec::with_capacity(self.slot_terms.len());
+        for (offset, terms) in self.slot_terms.iter().enumerate() {
+            let inverted_index = reader.inverted_index(terms[0].field())?;
+            let mut postings_list = Vec::new();
+            for term in terms {
+                let postings_opt = if reader.has_deletes() {
+                    inverted_index.read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
+                } else {
+                    inverted_index
+                        .read_postings_no_deletes(term, IndexRecordOption::WithFreqsAndPositions)?
+                };
+                if let Some(postings) = postings_opt {
+                    postings_list.push(postings);
+                }
+            }
+            if postings_list.is_empty() {
+                return Ok(None);
+            }
+            slot_postings_list.push((offset, UnionPostings::new(postings_list)));
+        }
+        Ok(Some(PhraseScorer::new(
+            slot_postings_list,
+            similarity_weight,
+            fieldnorm_reader,
+            self.scoring_enabled,
+            &self.scratch_arena,
+        )))
+    }
+}
+
+impl Weight for RegexPhraseWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if let Some(scorer) = self.phrase_scorer(reader, boost)? {
+            Ok(Box::new(scorer))
+        } else {
+            Ok(Box::new(EmptyScorer))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = match self.phrase_scorer(reader, 1.0)? {
+            Some(scorer) => scorer,
+            None => return Err(does_not_match(doc)),
+        };
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let fieldnorm_id = self.fieldnorm_reader(reader)?.fieldnorm_id(doc);
+        let phrase_count = scorer.phrase_count();
+        let mut explanation = Explanation::new("Regex Phrase Scorer", scorer.score());
+        if let Some(similarity_weight) = &self.similarity_weight {
+            explanation.add_detail(similarity_weight.explain(fieldnorm_id, phrase_count));
+        }
+        Ok(explanation)
+    }
+}
+
+/// Postings of the union of the expansions of a slot.
+///
+/// The positions of a document are the merged positions of all of the
+/// expansions it contains.
+struct UnionPostings {
+    postings_list: Vec<SegmentPostings>,
+    doc: DocId,
+}
+
+impl UnionPostings {
+    fn new(postings_list: Vec<SegmentPostings>) -> UnionPostings {
+        let doc = postings_list
+            .iter()
+            .map(DocSet::doc)
+            .min()
+            .unwrap_or(TERMINATED);
+        UnionPostings { postings_list, doc }
+    }
+
+    fn update_doc(&mut self) -> DocId {
+        self.doc = self
+            .postings_list
+            .iter()
+            .map(DocSet::doc)
+            .min()
+            .unwrap_or(TERMINATED);
+        self.doc
+    }
+}
+
+impl DocSet for UnionPostings {
+    fn advance(&mut self) -> DocId {
+        let doc = self.doc;
+        for postings in &mut self.postings_list {
+            if postings.doc() == doc {
+                postings.advance();
+            }
+        }
+        self.update_doc()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        for postings in &mut self.postings_list {
+            if postings.doc() < target {
+                postings.seek(target);
+            }
+        }
+        self.update_doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings_list
+            .iter()
+            .map(DocSet::size_hint)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn cost(&self) -> u64 {
+        self.postings_list.iter().map(DocSet::cost).sum()
+    }
+}
+
+impl Postings for UnionPostings {
+    fn term_freq(&self) -> u32 {
+        let doc = self.doc;
+        self.postings_list
+            .iter()
+            .filter(|postings| postings.doc() == doc)
+            .map(Postings::term_freq)
+            .sum()
+    }
+
+    fn positions_with_offset(&mut self, offset: u32, output: &mut Vec<u32>) {
+        output.clear();
+        let doc = self.doc;
+        let mut term_positions = Vec::new();
+        for postings in &mut self.postings_list {
+            if postings.doc() == doc {
+                postings.positions_with_offset(offset, &mut term_positions);
+                output.extend_from_slice(&term_positions);
+            }
+        }
+        output.sort_unstable();
+        output.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::create_index;
+    use super::RegexPhraseQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::Query;
+    use crate::schema::{Schema, STRING};
+    use crate::{DocAddress, Index, Term};
+
+    #[test]
+    pub fn test_regex_phrase_query() -> crate::Result<()> {
+        let index = create_index(&[
+            "error read timeout",
+            "error write timeout",
+            "error timeout",
+            "timeout error read",
+            "error read write timed out",
+            "warning read timeout",
+        ])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let matching_docs = |query: &RegexPhraseQuery| -> Vec<u32> {
+            let top_docs = searcher.search(query, &TopDocs::with_limit(10)).unwrap();
+            let mut docs: Vec<u32> = top_docs
+                .into_iter()
+                .map(|(_, doc_address)| doc_address.doc_id)
+                .collect();
+            docs.sort_unstable();
+            docs
+        };
+        let query = RegexPhraseQuery::new(text_field, &["error", "*", "timeout"])?;
+        assert_eq!(matching_docs(&query), vec![0, 1]);
+        let query = RegexPhraseQuery::new(text_field, &["error", "(read|write)"])?;
+        assert_eq!(matching_docs(&query), vec![0, 1, 3, 4]);
+        let query = RegexPhraseQuery::new(text_field, &["(error|warning)", "read", "time.*"])?;
+        assert_eq!(matching_docs(&query), vec![0, 5]);
+        let query = RegexPhraseQuery::new(text_field, &["*", "*", "time.*"])?;
+        assert_eq!(matching_docs(&query), vec![0, 1, 4, 5]);
+        // "read" is the first expansion of "(read|write)".
+        let query =
+            RegexPhraseQuery::new(text_field, &["error", "(read|write)"])?.with_max_expansions(1);
+        assert_eq!(matching_docs(&query), vec![0, 3, 4]);
+        let query = RegexPhraseQuery::new(text_field, &["error", "z.*"])?;
+        assert_eq!(searcher.search(&query, &Count)?, 0);
+        let query = RegexPhraseQuery::new(text_field, &["error", "*", "timeout"])?;
+        assert!(query.explain(&searcher, DocAddress::new(0, 0)).is_ok());
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_regex_phrase_query_scores_like_phrase_query() -> crate::Result<()> {
+        let index = create_index(&["a b c", "a b b c", "b c a"])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let regex_phrase_query = RegexPhraseQuery::new(text_field, &["a", "b"])?;
+        let phrase_query = crate::query::PhraseQuery::new(vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ]);
+        assert_eq!(
+            searcher.search(&regex_phrase_query, &TopDocs::with_limit(10))?,
+            searcher.search(&phrase_query, &TopDocs::with_limit(10))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_regex_phrase_query_errors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        assert!(matches!(
+            RegexPhraseQuery::new(text_field, &["a", "(b"]),
+            Err(crate::TantivyError::InvalidArgument(_))
+        ));
+        let query = RegexPhraseQuery::new(text_field, &["a", "*"])?;
+        assert!(matches!(
+            query.weight(&searcher, false),
+            Err(crate::TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
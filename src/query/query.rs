@@ -71,7 +71,24 @@ pub trait Query: QueryClone + Send + Sync + downcast_rs::Downcast + fmt::Debug {
     ///
     /// Each term is associated with a boolean indicating whether
     /// Positions are required or not.
+    ///
+    /// The terms of the subqueries of the composite queries (boolean, boost...)
+    /// are extracted recursively, which makes it possible to highlight or to
+    /// account the terms of arbitrary query trees. Fuzzy queries only report their
+    /// term, not the terms of the dictionary they match, and the queries that
+    /// do not match terms (range, regex, all...) do not report any.
     fn query_terms(&self, _term_set: &mut BTreeMap<Term, bool>) {}
+
+    /// Rewrites the query into an equivalent query that is cheaper to execute,
+    /// e.g. by flattening nested boolean queries.
+    ///
+    /// The `Searcher` calls it before creating the weight of the query.
+    /// The rewritten query has to match the same documents, with the same scores.
+    ///
+    /// Returns `None` if the query is left as is, which is the default.
+    fn rewrite(&self, _searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        Ok(None)
+    }
 }
 
 /// Implements `box_clone`.
@@ -101,6 +118,10 @@ impl Query for Box<dyn Query> {
     fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
         self.as_ref().query_terms(terms);
     }
+
+    fn rewrite(&self, searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        self.as_ref().rewrite(searcher)
+    }
 }
 
 impl QueryClone for Box<dyn Query> {
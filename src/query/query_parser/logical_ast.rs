@@ -6,7 +6,7 @@ use crate::Score;
 use std::fmt;
 use std::ops::Bound;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum LogicalLiteral {
     Term(Term),
     Phrase(Vec<(usize, Term)>),
@@ -1,5 +1,7 @@
 mod query_parser;
+mod term_expander;
 
 pub mod logical_ast;
 pub use self::query_parser::QueryParser;
 pub use self::query_parser::QueryParserError;
+pub use self::term_expander::TermExpander;
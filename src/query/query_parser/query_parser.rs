@@ -1,4 +1,5 @@
 use super::logical_ast::*;
+use super::TermExpander;
 use crate::core::Index;
 use crate::query::BooleanQuery;
 use crate::query::EmptyQuery;
@@ -14,18 +15,21 @@ use crate::schema::{FieldType, Term};
 use crate::tokenizer::TokenizerManager;
 use crate::Score;
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::num::{ParseFloatError, ParseIntError};
 use std::ops::Bound;
 use std::str::FromStr;
-use tantivy_query_grammar::{UserInputAst, UserInputBound, UserInputLeaf};
+use std::sync::Arc;
+use tantivy_query_grammar::Error as QuerySyntaxError;
+use tantivy_query_grammar::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 
 /// Possible error that may happen when parsing a query.
 #[derive(Debug, PartialEq, Eq, Error)]
 pub enum QueryParserError {
-    /// Error in the query syntax
-    #[error("Syntax Error")]
-    SyntaxError,
+    /// Error in the query syntax, locating the part of the query that could not be parsed.
+    #[error("{0}")]
+    SyntaxError(QuerySyntaxError),
     /// `FieldDoesNotExist(field_name: String)`
     /// The query references a field that is not in the schema
     #[error("Field does not exists: '{0:?}'")]
@@ -40,7 +44,7 @@ pub enum QueryParserError {
     ExpectedBase64(base64::DecodeError),
     /// The query contains a term for a `f64`-field, but the value
     /// is not a f64.
-    #[error("Invalid query: Only excluding terms given")]
+    #[error("Expected a valid float: '{0:?}'")]
     ExpectedFloat(ParseFloatError),
     /// It is forbidden queries that are only "excluding". (e.g. -title:pop)
     #[error("Invalid query: Only excluding terms given")]
@@ -144,7 +148,7 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 ///
 ///
 /// * boolean operators `AND`, `OR`. `AND` takes precedence over `OR`, so that `a AND b OR c` is interpreted
-/// as `(a AND b) OR c`.
+///   as `(a AND b) OR c`. A sub query can be negated with `NOT`, e.g. `a AND NOT b`.
 ///
 /// * In addition to the boolean operators, the `-`, `+` can help define. These operators
 ///   are sufficient to express all queries using boolean operators. For instance `x AND y OR z` can
@@ -171,6 +175,15 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 ///
 /// *  all docs query: A plain `*` will match all documents in the index.
 ///
+/// * field grouping: `title:(barack -obama)` searches all of the terms of the group
+///   that do not specify a field in `title`.
+///
+/// * escaping: special characters can be searched by escaping them with a backslash,
+///   e.g. `c\+\+` or `"say \"hi\""`.
+///
+/// A query that is not syntactically valid results in a `QueryParserError::SyntaxError`,
+/// holding the byte range of the query where the error was detected.
+///
 /// Parts of the queries can be boosted by appending `^boostfactor`.
 /// For instance, `"SRE"^2.0 OR devops^0.4` will boost documents containing `SRE` instead of
 /// devops. Negative boosts are not allowed.
@@ -178,6 +191,10 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 /// It is also possible to define a boost for a some specific field, at the query parser level.
 /// (See [`set_boost(...)`](#method.set_field_boost) ). Typically you may want to boost a title
 /// field.
+///
+/// The terms of the query can also be expanded into weighted synonyms, with a
+/// [`TermExpander`](./trait.TermExpander.html).
+/// (See [`set_term_expander(...)`](#method.set_term_expander) ).
 #[derive(Clone)]
 pub struct QueryParser {
     schema: Schema,
@@ -185,6 +202,8 @@ pub struct QueryParser {
     conjunction_by_default: bool,
     tokenizer_manager: TokenizerManager,
     boost: HashMap<Field, Score>,
+    term_expander: Option<Arc<dyn TermExpander>>,
+    max_expansions: usize,
 }
 
 fn all_negative(ast: &LogicalAst) -> bool {
@@ -213,6 +232,8 @@ impl QueryParser {
             tokenizer_manager,
             conjunction_by_default: false,
             boost: Default::default(),
+            term_expander: None,
+            max_expansions: 0,
         }
     }
 
@@ -224,6 +245,16 @@ impl QueryParser {
         QueryParser::new(index.schema(), default_fields, index.tokenizers().clone())
     }
 
+    /// Creates a `QueryParser` for an index, searching the default search fields
+    /// of its schema if no field is specifically defined in the query.
+    ///
+    /// See [`SchemaBuilder::add_default_search_field`](../schema/struct.SchemaBuilder.html#method.add_default_search_field).
+    pub fn for_index_default_fields(index: &Index) -> QueryParser {
+        let schema = index.schema();
+        let default_fields = schema.default_search_fields();
+        QueryParser::new(schema, default_fields, index.tokenizers().clone())
+    }
+
     /// Set the default way to compose queries to a conjunction.
     ///
     /// By default, the query `happy tax payer` is equivalent to the query
@@ -244,25 +275,80 @@ impl QueryParser {
         self.boost.insert(field, boost);
     }
 
+    /// Sets a `TermExpander`, rewriting each term of the parsed queries into a
+    /// disjunction of the term and of its expansions.
+    ///
+    /// The original term keeps its scoring weight, and each expansion is boosted by
+    /// its weight. Only the `max_expansions` expansions with the highest weights are kept,
+    /// so that an expander returning many alternatives does not make the query too slow.
+    ///
+    /// Terms within phrases and range bounds are not expanded.
+    pub fn set_term_expander<E: TermExpander>(&mut self, term_expander: E, max_expansions: usize) {
+        self.term_expander = Some(Arc::new(term_expander));
+        self.max_expansions = max_expansions;
+    }
+
     /// Parse a query
     ///
     /// Note that `parse_query` returns an error if the input
     /// is not a valid query.
     ///
-    /// There is currently no lenient mode for the query parser
-    /// which makes it a bad choice for a public/broad user search engine.
-    ///
-    /// Implementing a lenient mode for this query parser is tracked
-    /// in [Issue 5](https://github.com/fulmicoton/tantivy/issues/5)
+    /// For a public/broad user search engine, consider using
+    /// [`parse_query_lenient(...)`](#method.parse_query_lenient) instead.
     pub fn parse_query(&self, query: &str) -> Result<Box<dyn Query>, QueryParserError> {
         let logical_ast = self.parse_query_to_logical_ast(query)?;
         Ok(convert_to_query(logical_ast))
     }
 
+    /// Parse a query into a [`UserInputAst`](../struct.UserInputAst.html), without
+    /// resolving it against the schema.
+    ///
+    /// The AST can be inspected, rewritten (e.g. to add a filter clause), serialized,
+    /// and then turned into a query with
+    /// [`build_query_from_user_input_ast(...)`](#method.build_query_from_user_input_ast).
+    pub fn parse_query_to_user_input_ast(
+        &self,
+        query: &str,
+    ) -> Result<UserInputAst, QueryParserError> {
+        tantivy_query_grammar::parse_query(query).map_err(QueryParserError::SyntaxError)
+    }
+
+    /// Build a query from a [`UserInputAst`](../struct.UserInputAst.html),
+    /// resolving its fields and terms against the schema.
+    ///
+    /// Literals without a field are searched in the default fields, and clauses
+    /// without occur use the default occur of the query parser, as in
+    /// [`parse_query(...)`](#method.parse_query).
+    pub fn build_query_from_user_input_ast(
+        &self,
+        user_input_ast: UserInputAst,
+    ) -> Result<Box<dyn Query>, QueryParserError> {
+        let logical_ast = self.compute_logical_ast(user_input_ast)?;
+        Ok(convert_to_query(logical_ast))
+    }
+
+    /// Parse a query, ignoring the parts of the query that are not valid.
+    ///
+    /// Clauses that cannot be interpreted (e.g. `title:[a TO b c]`, a clause on a
+    /// field that does not exist, or `abc` searched in an integer field) are
+    /// dropped from the resulting query.
+    ///
+    /// If the query is not syntactically valid, it is repaired around the syntax errors:
+    /// unexpected words and dangling operators (e.g. `a AND`) are dropped, and unclosed
+    /// groups and phrases get closed. If the query still cannot be parsed, its words
+    /// are searched in the default fields.
+    ///
+    /// The errors that were ignored are returned alongside the query.
+    pub fn parse_query_lenient(&self, query: &str) -> (Box<dyn Query>, Vec<QueryParserError>) {
+        let mut errors = Vec::new();
+        let user_input_ast = parse_user_input_ast_lenient(query, &mut errors);
+        let logical_ast = self.compute_logical_ast_lenient(user_input_ast, &mut errors);
+        (convert_to_query(logical_ast), errors)
+    }
+
     /// Parse the user query into an AST.
     fn parse_query_to_logical_ast(&self, query: &str) -> Result<LogicalAst, QueryParserError> {
-        let user_input_ast =
-            tantivy_query_grammar::parse_query(query).map_err(|_| QueryParserError::SyntaxError)?;
+        let user_input_ast = self.parse_query_to_user_input_ast(query)?;
         self.compute_logical_ast(user_input_ast)
     }
 
@@ -276,16 +362,32 @@ impl QueryParser {
         &self,
         user_input_ast: UserInputAst,
     ) -> Result<LogicalAst, QueryParserError> {
-        let ast = self.compute_logical_ast_with_occur(user_input_ast)?;
+        let mut errors = Vec::new();
+        let ast = self.compute_logical_ast_lenient(user_input_ast, &mut errors);
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+        Ok(ast)
+    }
+
+    /// Computes the logical ast, dropping the parts of the query
+    /// that cannot be interpreted and pushing the associated errors to `errors`.
+    fn compute_logical_ast_lenient(
+        &self,
+        user_input_ast: UserInputAst,
+        errors: &mut Vec<QueryParserError>,
+    ) -> LogicalAst {
+        let ast = self.compute_logical_ast_with_occur(user_input_ast, errors);
         if let LogicalAst::Clause(children) = &ast {
             if children.is_empty() {
-                return Ok(ast);
+                return ast;
             }
         }
         if all_negative(&ast) {
-            return Err(QueryParserError::AllButQueryForbidden);
+            errors.push(QueryParserError::AllButQueryForbidden);
+            return LogicalAst::Clause(Vec::new());
         }
-        Ok(ast)
+        ast
     }
 
     fn compute_terms_for_string(
@@ -376,6 +478,9 @@ impl QueryParser {
                 let term = Term::from_field_bytes(field, &bytes);
                 Ok(vec![(0, term)])
             }
+            FieldType::Vector(_) | FieldType::Features(_) => Err(
+                QueryParserError::FieldNotIndexed(field_entry.name().to_string()),
+            ),
         }
     }
 
@@ -392,6 +497,46 @@ impl QueryParser {
         }
     }
 
+    /// Rewrites a term into the disjunction of the term and of its weighted expansions,
+    /// if a `TermExpander` is set.
+    fn expand_literal(&self, field: Field, literal: LogicalLiteral) -> LogicalAst {
+        let (term_expander, term) = match (&self.term_expander, &literal) {
+            (Some(term_expander), LogicalLiteral::Term(term)) => (term_expander, term),
+            _ => return LogicalAst::from(literal),
+        };
+        let mut expansions = term_expander.expand(term);
+        expansions.retain(|(_, weight)| *weight > 0.0);
+        // the sort is stable, so that expansions with the same weight keep their order.
+        expansions
+            .sort_by(|(_, left), (_, right)| right.partial_cmp(left).unwrap_or(Ordering::Equal));
+        let mut expanded_literals: Vec<LogicalLiteral> = vec![literal.clone()];
+        let mut clauses: Vec<(Occur, LogicalAst)> = Vec::new();
+        for (text, weight) in expansions {
+            if clauses.len() >= self.max_expansions {
+                break;
+            }
+            // expansions that cannot be searched in the field are ignored, rather than
+            // failing the whole query.
+            let expanded_literal = match self.compute_logical_ast_for_leaf(field, &text) {
+                Ok(Some(expanded_literal)) => expanded_literal,
+                Ok(None) | Err(_) => continue,
+            };
+            if expanded_literals.contains(&expanded_literal) {
+                continue;
+            }
+            expanded_literals.push(expanded_literal.clone());
+            clauses.push((
+                Occur::Should,
+                LogicalAst::from(expanded_literal).boost(weight),
+            ));
+        }
+        if clauses.is_empty() {
+            return LogicalAst::from(literal);
+        }
+        clauses.insert(0, (Occur::Should, LogicalAst::from(literal)));
+        LogicalAst::Clause(clauses)
+    }
+
     fn default_occur(&self) -> Occur {
         if self.conjunction_by_default {
             Occur::Must
@@ -439,23 +584,24 @@ impl QueryParser {
     fn compute_logical_ast_with_occur(
         &self,
         user_input_ast: UserInputAst,
-    ) -> Result<LogicalAst, QueryParserError> {
+        errors: &mut Vec<QueryParserError>,
+    ) -> LogicalAst {
         match user_input_ast {
             UserInputAst::Clause(sub_queries) => {
                 let default_occur = self.default_occur();
                 let mut logical_sub_queries: Vec<(Occur, LogicalAst)> = Vec::new();
                 for (occur_opt, sub_ast) in sub_queries {
-                    let sub_ast = self.compute_logical_ast_with_occur(sub_ast)?;
+                    let sub_ast = self.compute_logical_ast_with_occur(sub_ast, errors);
                     let occur = occur_opt.unwrap_or(default_occur);
                     logical_sub_queries.push((occur, sub_ast));
                 }
-                Ok(LogicalAst::Clause(logical_sub_queries))
+                LogicalAst::Clause(logical_sub_queries)
             }
             UserInputAst::Boost(ast, boost) => {
-                let ast = self.compute_logical_ast_with_occur(*ast)?;
-                Ok(ast.boost(boost as Score))
+                let ast = self.compute_logical_ast_with_occur(*ast, errors);
+                ast.boost(boost as Score)
             }
-            UserInputAst::Leaf(leaf) => self.compute_logical_ast_from_leaf(*leaf),
+            UserInputAst::Leaf(leaf) => self.compute_logical_ast_from_leaf(*leaf, errors),
         }
     }
 
@@ -466,31 +612,28 @@ impl QueryParser {
     fn compute_logical_ast_from_leaf(
         &self,
         leaf: UserInputLeaf,
-    ) -> Result<LogicalAst, QueryParserError> {
+        errors: &mut Vec<QueryParserError>,
+    ) -> LogicalAst {
         match leaf {
             UserInputLeaf::Literal(literal) => {
-                let term_phrases: Vec<(Field, String)> = match literal.field_name {
-                    Some(ref field_name) => {
-                        let field = self.resolve_field_name(field_name)?;
-                        vec![(field, literal.phrase.clone())]
-                    }
-                    None => {
-                        if self.default_fields.is_empty() {
-                            return Err(QueryParserError::NoDefaultFieldDeclared);
-                        } else {
-                            self.default_fields
-                                .iter()
-                                .map(|default_field| (*default_field, literal.phrase.clone()))
-                                .collect::<Vec<(Field, String)>>()
-                        }
+                let fields = match self.resolved_fields(&literal.field_name) {
+                    Ok(fields) => fields,
+                    Err(err) => {
+                        errors.push(err);
+                        return LogicalAst::Clause(Vec::new());
                     }
                 };
                 let mut asts: Vec<LogicalAst> = Vec::new();
-                for (field, phrase) in term_phrases {
-                    if let Some(ast) = self.compute_logical_ast_for_leaf(field, &phrase)? {
-                        // Apply some field specific boost defined at the query parser level.
-                        let boost = self.field_boost(field);
-                        asts.push(LogicalAst::Leaf(Box::new(ast)).boost(boost));
+                for &field in fields.iter() {
+                    match self.compute_logical_ast_for_leaf(field, &literal.phrase) {
+                        Ok(Some(ast)) => {
+                            let ast = self.expand_literal(field, ast);
+                            // Apply some field specific boost defined at the query parser level.
+                            let boost = self.field_boost(field);
+                            asts.push(ast.boost(boost));
+                        }
+                        Ok(None) => {}
+                        Err(err) => errors.push(err),
                     }
                 }
                 let result_ast: LogicalAst = if asts.len() == 1 {
@@ -498,31 +641,49 @@ impl QueryParser {
                 } else {
                     LogicalAst::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
                 };
-                Ok(result_ast)
+                result_ast
             }
-            UserInputLeaf::All => Ok(LogicalAst::Leaf(Box::new(LogicalLiteral::All))),
+            UserInputLeaf::All => LogicalAst::Leaf(Box::new(LogicalLiteral::All)),
             UserInputLeaf::Range {
                 field,
                 lower,
                 upper,
             } => {
-                let fields = self.resolved_fields(&field)?;
-                let mut clauses = fields
-                    .iter()
-                    .map(|&field| {
-                        let boost = self.field_boost(field);
-                        let field_entry = self.schema.get_field_entry(field);
-                        let value_type = field_entry.field_type().value_type();
-                        let logical_ast = LogicalAst::Leaf(Box::new(LogicalLiteral::Range {
-                            field,
-                            value_type,
-                            lower: self.resolve_bound(field, &lower)?,
-                            upper: self.resolve_bound(field, &upper)?,
-                        }));
-                        Ok(logical_ast.boost(boost))
-                    })
-                    .collect::<Result<Vec<_>, QueryParserError>>()?;
-                let result_ast = if clauses.len() == 1 {
+                let fields = match self.resolved_fields(&field) {
+                    Ok(fields) => fields,
+                    Err(err) => {
+                        errors.push(err);
+                        return LogicalAst::Clause(Vec::new());
+                    }
+                };
+                let mut clauses = Vec::new();
+                for &field in fields.iter() {
+                    let lower_bound = match self.resolve_bound(field, &lower) {
+                        Ok(lower_bound) => lower_bound,
+                        Err(err) => {
+                            errors.push(err);
+                            continue;
+                        }
+                    };
+                    let upper_bound = match self.resolve_bound(field, &upper) {
+                        Ok(upper_bound) => upper_bound,
+                        Err(err) => {
+                            errors.push(err);
+                            continue;
+                        }
+                    };
+                    let boost = self.field_boost(field);
+                    let field_entry = self.schema.get_field_entry(field);
+                    let value_type = field_entry.field_type().value_type();
+                    let logical_ast = LogicalAst::Leaf(Box::new(LogicalLiteral::Range {
+                        field,
+                        value_type,
+                        lower: lower_bound,
+                        upper: upper_bound,
+                    }));
+                    clauses.push(logical_ast.boost(boost));
+                }
+                if clauses.len() == 1 {
                     clauses.pop().unwrap()
                 } else {
                     LogicalAst::Clause(
@@ -531,13 +692,98 @@ impl QueryParser {
                             .map(|clause| (Occur::Should, clause))
                             .collect(),
                     )
-                };
-                Ok(result_ast)
+                }
             }
         }
     }
 }
 
+/// Maximum number of edits made to a query that is not syntactically valid
+/// by `parse_query_lenient`.
+const MAX_QUERY_REPAIRS: usize = 8;
+
+/// Parses `query`, repairing it if it is not syntactically valid.
+///
+/// Only the syntax error of the original query is pushed to `errors`.
+fn parse_user_input_ast_lenient(query: &str, errors: &mut Vec<QueryParserError>) -> UserInputAst {
+    let mut syntax_error = match tantivy_query_grammar::parse_query(query) {
+        Ok(user_input_ast) => return user_input_ast,
+        Err(syntax_error) => syntax_error,
+    };
+    errors.push(QueryParserError::SyntaxError(syntax_error.clone()));
+    let mut repaired_query = query.to_string();
+    for _ in 0..MAX_QUERY_REPAIRS {
+        repaired_query = match repair_query(&repaired_query, &syntax_error) {
+            Some(repaired_query) => repaired_query,
+            None => break,
+        };
+        match tantivy_query_grammar::parse_query(&repaired_query) {
+            Ok(user_input_ast) => return user_input_ast,
+            Err(new_syntax_error) => syntax_error = new_syntax_error,
+        }
+    }
+    plain_words_ast(query)
+}
+
+/// Edits `query` where the syntax error `syntax_error` was detected.
+///
+/// If the query ended unexpectedly, its dangling operator is dropped, or else its
+/// phrase or group is closed. Otherwise, the word starting at the error is dropped.
+///
+/// Returns `None` if there is nothing to edit.
+fn repair_query(query: &str, syntax_error: &QuerySyntaxError) -> Option<String> {
+    let start = syntax_error.span.start.min(query.len());
+    if query[start..].trim().is_empty() {
+        let trimmed_query = query.trim_end();
+        for operator in &["AND", "OR", "NOT"] {
+            if let Some(prefix) = trimmed_query.strip_suffix(operator) {
+                if prefix.is_empty() || prefix.ends_with(char::is_whitespace) {
+                    return Some(prefix.to_string());
+                }
+            }
+        }
+        if let Some(prefix) = trimmed_query.strip_suffix(&['+', '-', '^', ':'][..]) {
+            return Some(prefix.to_string());
+        }
+        if trimmed_query.matches('"').count() % 2 == 1 {
+            return Some(format!("{}\"", trimmed_query));
+        }
+        if trimmed_query.matches('(').count() > trimmed_query.matches(')').count() {
+            return Some(format!("{})", trimmed_query));
+        }
+        return None;
+    }
+    let word_len = query[start..]
+        .find(char::is_whitespace)
+        .unwrap_or(query.len() - start);
+    let end = (start + word_len).max(syntax_error.span.end);
+    let end = if end > start {
+        end
+    } else {
+        start + query[start..].chars().next()?.len_utf8()
+    };
+    Some(format!("{}{}", &query[..start], &query[end..]))
+}
+
+/// Builds a query searching the whitespace separated words of `query`
+/// in the default fields.
+fn plain_words_ast(query: &str) -> UserInputAst {
+    let sub_queries = query
+        .split_whitespace()
+        .map(|word| {
+            let literal = UserInputLiteral {
+                field_name: None,
+                phrase: word.to_string(),
+            };
+            (
+                None,
+                UserInputAst::Leaf(Box::new(UserInputLeaf::Literal(literal))),
+            )
+        })
+        .collect();
+    UserInputAst::Clause(sub_queries)
+}
+
 fn convert_literal_to_query(logical_literal: LogicalLiteral) -> Box<dyn Query> {
     match logical_literal {
         LogicalLiteral::Term(term) => Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)),
@@ -586,7 +832,7 @@ mod test {
     use super::super::logical_ast::*;
     use super::QueryParser;
     use super::QueryParserError;
-    use crate::query::Query;
+    use crate::query::{Occur, Query, UserInputAst, UserInputLeaf, UserInputLiteral};
     use crate::schema::FacetOptions;
     use crate::schema::Field;
     use crate::schema::{IndexRecordOption, TextFieldIndexing, TextOptions};
@@ -594,7 +840,7 @@ mod test {
     use crate::tokenizer::{
         LowerCaser, SimpleTokenizer, StopWordFilter, TextAnalyzer, TokenizerManager,
     };
-    use crate::Index;
+    use crate::{Index, Score};
     use matches::assert_matches;
 
     fn make_schema() -> Schema {
@@ -659,6 +905,16 @@ mod test {
         assert_eq!(query_str, expected);
     }
 
+    #[test]
+    pub fn test_parse_query_field_group() {
+        test_parse_query_to_logical_ast_helper(
+            "title:(a -b) c",
+            "((Term(field=0,bytes=[97]) -Term(field=0,bytes=[98])) \
+             (Term(field=0,bytes=[99]) Term(field=1,bytes=[99])))",
+            false,
+        );
+    }
+
     #[test]
     pub fn test_parse_query_facet() {
         let query_parser = make_query_parser();
@@ -682,6 +938,62 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_parse_query_with_term_expander() {
+        let mut query_parser = make_query_parser();
+        let expander = |term: &Term| -> Vec<(String, Score)> {
+            match term.value_bytes() {
+                b"car" => vec![
+                    ("truck".to_string(), 0.2),
+                    ("Automobile".to_string(), 0.8),
+                    ("car".to_string(), 1.0),
+                    ("auto".to_string(), 0.8),
+                    ("ignored".to_string(), 0.0),
+                    ("motor car".to_string(), 0.5),
+                    ("vehicle".to_string(), 0.5),
+                ],
+                _ => Vec::new(),
+            }
+        };
+        query_parser.set_term_expander(expander, 3);
+        // The original term keeps its weight, duplicates and non positive weights are ignored,
+        // and the expansions with the highest weights are kept.
+        let query = query_parser
+            .parse_query_to_logical_ast("title:car")
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", query),
+            "(Term(field=0,bytes=[99, 97, 114]) \
+             Term(field=0,bytes=[97, 117, 116, 111, 109, 111, 98, 105, 108, 101])^0.8 \
+             Term(field=0,bytes=[97, 117, 116, 111])^0.8 \
+             \"[(0, Term(field=0,bytes=[109, 111, 116, 111, 114])), \
+             (1, Term(field=0,bytes=[99, 97, 114]))]\"^0.5)"
+        );
+        // Terms without expansions are left untouched.
+        let query = query_parser
+            .parse_query_to_logical_ast("title:bike")
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", query),
+            "Term(field=0,bytes=[98, 105, 107, 101])"
+        );
+        // Expansions that cannot be searched in the field are ignored.
+        let query = query_parser
+            .parse_query_to_logical_ast("nottokenized:car")
+            .unwrap();
+        assert_eq!(format!("{:?}", query).matches("Term").count(), 4);
+        let query = query_parser
+            .parse_query_to_logical_ast("unsigned:3 title:\"car bike\"")
+            .unwrap();
+        assert_eq!(format!("{:?}", query).matches("Term").count(), 3);
+
+        query_parser.set_term_expander(expander, 0);
+        let query = query_parser
+            .parse_query_to_logical_ast("title:car")
+            .unwrap();
+        assert_eq!(format!("{:?}", query), "Term(field=0,bytes=[99, 97, 114])");
+    }
+
     #[test]
     pub fn test_parse_query_range_with_boost() {
         let mut query_parser = make_query_parser();
@@ -691,7 +1003,7 @@ mod test {
         let query = query_parser.parse_query("title:[A TO B]").unwrap();
         assert_eq!(
             format!("{:?}", query),
-            "Boost(query=RangeQuery { field: Field(0), value_type: Str, left_bound: Included([97]), right_bound: Included([98]) }, boost=2)"
+            "Boost(query=RangeQuery { field: Field(0), value_type: Str, left_bound: Included([97]), right_bound: Included([98]), fast_field_scan_threshold: Some(1000) }, boost=2)"
         );
     }
 
@@ -951,6 +1263,104 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_parse_query_lenient() {
+        let query_parser = make_query_parser();
+        let (query, errors) = query_parser.parse_query_lenient("title:hello boujou:abc");
+        assert_eq!(
+            format!("{:?}", query),
+            "BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[104, 101, 108, 108, 111])))] }"
+        );
+        assert_eq!(
+            errors,
+            vec![QueryParserError::FieldDoesNotExist("boujou".to_string())]
+        );
+        let (query, errors) = query_parser.parse_query_lenient("title:hello unsigned:abc");
+        assert_eq!(
+            format!("{:?}", query),
+            "BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[104, 101, 108, 108, 111])))] }"
+        );
+        assert_matches!(&errors[..], [QueryParserError::ExpectedInt(_)]);
+    }
+
+    #[test]
+    pub fn test_parse_query_lenient_default_fields() {
+        let schema = make_schema();
+        let title = schema.get_field("title").unwrap();
+        let unsigned = schema.get_field("unsigned").unwrap();
+        let query_parser =
+            QueryParser::new(schema, vec![title, unsigned], TokenizerManager::default());
+        let (query, errors) = query_parser.parse_query_lenient("3");
+        assert_eq!(
+            format!("{:?}", query),
+            "BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[51]))), (Should, TermQuery(Term(field=3,bytes=[0, 0, 0, 0, 0, 0, 0, 3])))] }"
+        );
+        assert!(errors.is_empty());
+        let (query, errors) = query_parser.parse_query_lenient("hello");
+        assert_eq!(
+            format!("{:?}", query),
+            "TermQuery(Term(field=0,bytes=[104, 101, 108, 108, 111]))"
+        );
+        assert_matches!(&errors[..], [QueryParserError::ExpectedInt(_)]);
+    }
+
+    #[test]
+    pub fn test_parse_query_lenient_syntax_error() {
+        let query_parser = make_query_parser();
+        assert_matches!(
+            query_parser.parse_query("hello (world"),
+            Err(QueryParserError::SyntaxError(_))
+        );
+        let (query, errors) = query_parser.parse_query_lenient("hello (world");
+        assert_eq!(
+            format!("{:?}", query),
+            "BooleanQuery { subqueries: [(Should, BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[104, 101, 108, 108, 111]))), (Should, TermQuery(Term(field=1,bytes=[104, 101, 108, 108, 111])))] }), (Should, BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[119, 111, 114, 108, 100]))), (Should, TermQuery(Term(field=1,bytes=[119, 111, 114, 108, 100])))] })] }"
+        );
+        assert_matches!(&errors[..], [QueryParserError::SyntaxError(err)] if err.span == (12..12));
+    }
+
+    #[test]
+    pub fn test_parse_query_lenient_repairs_query() {
+        let query_parser = make_query_parser();
+        for &(query, repaired_query) in &[
+            ("title:hello AND", "title:hello"),
+            ("title:hello OR NOT", "title:hello"),
+            ("hello +", "hello"),
+            ("hello world)", "hello world"),
+            ("hello AND AND world", "hello AND world"),
+            ("title:\"hello world", "title:\"hello world\""),
+            ("title:(hello -world", "title:(hello -world)"),
+            ("(title:(hello", "(title:(hello))"),
+        ] {
+            let (query, errors) = query_parser.parse_query_lenient(query);
+            assert_eq!(
+                format!("{:?}", query),
+                format!("{:?}", query_parser.parse_query(repaired_query).unwrap())
+            );
+            assert_matches!(&errors[..], [QueryParserError::SyntaxError(_)]);
+        }
+        let (query, errors) = query_parser.parse_query_lenient("boujou:abc OR title:hello AND");
+        assert_eq!(
+            format!("{:?}", query),
+            "BooleanQuery { subqueries: [(Should, TermQuery(Term(field=0,bytes=[104, 101, 108, 108, 111])))] }"
+        );
+        assert_matches!(
+            &errors[..],
+            [
+                QueryParserError::SyntaxError(_),
+                QueryParserError::FieldDoesNotExist(_)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_parse_query_lenient_all_negative() {
+        let query_parser = make_query_parser();
+        let (query, errors) = query_parser.parse_query_lenient("-title:hello");
+        assert_eq!(format!("{:?}", query), "EmptyQuery");
+        assert_eq!(errors, vec![QueryParserError::AllButQueryForbidden]);
+    }
+
     #[test]
     pub fn test_query_parser_field_not_indexed() {
         let query_parser = make_query_parser();
@@ -998,6 +1408,21 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_query_parser_for_index_default_fields() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_default_search_field(title);
+        let index = Index::create_in_ram(schema_builder.build());
+        let query_parser = QueryParser::for_index_default_fields(&index);
+        let query = query_parser.parse_query("happy").unwrap();
+        assert_eq!(
+            format!("{:?}", query),
+            format!("TermQuery({:?})", Term::from_field_text(title, "happy"))
+        );
+    }
+
     #[test]
     pub fn test_query_parser_expected_int() {
         let query_parser = make_query_parser();
@@ -1125,4 +1550,43 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_build_query_from_rewritten_user_input_ast() {
+        let query_parser = make_query_parser();
+        let user_input_ast = query_parser
+            .parse_query_to_user_input_ast("title:a text:b")
+            .unwrap();
+        let tenant_filter = UserInputLeaf::Literal(UserInputLiteral {
+            field_name: Some("nottokenized".to_string()),
+            phrase: "tenant1".to_string(),
+        });
+        let rewritten_ast = UserInputAst::Clause(vec![
+            (Some(Occur::Must), user_input_ast),
+            (Some(Occur::Must), tenant_filter.into()),
+        ]);
+        let json = serde_json::to_string(&rewritten_ast).unwrap();
+        let deser_ast: UserInputAst = serde_json::from_str(&json).unwrap();
+        let query = query_parser
+            .build_query_from_user_input_ast(deser_ast)
+            .unwrap();
+        let expected_query = query_parser
+            .parse_query("+(title:a text:b) +nottokenized:tenant1")
+            .unwrap();
+        assert_eq!(format!("{:?}", query), format!("{:?}", expected_query));
+    }
+
+    #[test]
+    fn test_build_query_from_user_input_ast_error() {
+        let query_parser = make_query_parser();
+        let user_input_ast: UserInputAst = UserInputLeaf::Literal(UserInputLiteral {
+            field_name: Some("doesnotexist".to_string()),
+            phrase: "a".to_string(),
+        })
+        .into();
+        assert_matches!(
+            query_parser.build_query_from_user_input_ast(user_input_ast),
+            Err(QueryParserError::FieldDoesNotExist(_))
+        );
+    }
 }
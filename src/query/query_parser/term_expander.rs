@@ -0,0 +1,30 @@
+use crate::schema::Term;
+use crate::Score;
+
+/// Expands the terms of a query into weighted alternatives, e.g. synonyms
+/// coming from a thesaurus or from an external embedding service.
+///
+/// Once registered with
+/// [`QueryParser::set_term_expander`](./struct.QueryParser.html#method.set_term_expander),
+/// every term searched by the query parser is rewritten into a disjunction of the
+/// original term and of its expansions. The original term keeps its scoring weight,
+/// and each expansion is boosted by its weight.
+///
+/// The expansions are given as text, and go through the tokenizer of the field of
+/// the term: a multi-word expansion is searched as a phrase.
+pub trait TermExpander: Send + Sync + 'static {
+    /// Returns the alternatives of `term`, with their weights.
+    ///
+    /// An expansion with a weight of `1.0` scores like the original term.
+    /// Expansions with a weight that is not strictly positive are ignored.
+    fn expand(&self, term: &Term) -> Vec<(String, Score)>;
+}
+
+impl<F> TermExpander for F
+where
+    F: Fn(&Term) -> Vec<(String, Score)> + Send + Sync + 'static,
+{
+    fn expand(&self, term: &Term) -> Vec<(String, Score)> {
+        (self)(term)
+    }
+}
@@ -0,0 +1,118 @@
+use std::ops::Range;
+
+use crate::docset::{DocSet, TERMINATED};
+use crate::DocId;
+
+/// A `DocSet` iterating through a contiguous range of `DocId`s.
+///
+/// Seeking is done in constant time.
+pub struct RangeDocSet {
+    doc: DocId,
+    end: DocId,
+}
+
+impl From<Range<DocId>> for RangeDocSet {
+    fn from(range: Range<DocId>) -> RangeDocSet {
+        let doc = if range.start < range.end {
+            range.start
+        } else {
+            TERMINATED
+        };
+        RangeDocSet {
+            doc,
+            end: range.end,
+        }
+    }
+}
+
+impl DocSet for RangeDocSet {
+    fn advance(&mut self) -> DocId {
+        self.seek(self.doc + 1)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        self.doc = if target < self.end {
+            target.max(self.doc)
+        } else {
+            TERMINATED
+        };
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        if self.doc == TERMINATED {
+            0
+        } else {
+            self.end - self.doc
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeDocSet;
+    use crate::docset::{DocSet, TERMINATED};
+    use crate::query::{BitSetDocSet, ConstScorer, Exclude, Intersection, Union, VecDocSet};
+    use crate::DocId;
+    use common::BitSet;
+
+    fn collect_docs<T: DocSet>(mut docset: T) -> Vec<DocId> {
+        let mut docs = Vec::new();
+        while docset.doc() != TERMINATED {
+            docs.push(docset.doc());
+            docset.advance();
+        }
+        docs
+    }
+
+    #[test]
+    fn test_range_docset() {
+        let mut docset = RangeDocSet::from(3..10);
+        assert_eq!(docset.size_hint(), 7);
+        assert_eq!(docset.doc(), 3);
+        assert_eq!(docset.advance(), 4);
+        assert_eq!(docset.seek(2), 4);
+        assert_eq!(docset.seek(8), 8);
+        assert_eq!(docset.size_hint(), 2);
+        assert_eq!(docset.advance(), 9);
+        assert_eq!(docset.advance(), TERMINATED);
+        assert_eq!(docset.advance(), TERMINATED);
+        assert_eq!(docset.size_hint(), 0);
+        assert_eq!(collect_docs(RangeDocSet::from(0..3)), vec![0, 1, 2]);
+        assert_eq!(RangeDocSet::from(5..5).doc(), TERMINATED);
+        assert_eq!(RangeDocSet::from(5..10).seek(10), TERMINATED);
+    }
+
+    #[test]
+    fn test_docset_adapters_compose() {
+        let mut bitset = BitSet::with_max_value(100);
+        for doc in (0..100).step_by(2) {
+            bitset.insert(doc);
+        }
+        let even_docs = || Box::new(BitSetDocSet::from(bitset.clone())) as Box<dyn DocSet>;
+        let range_docs = || Box::new(RangeDocSet::from(10..30)) as Box<dyn DocSet>;
+
+        let intersection = Intersection::new(vec![even_docs(), range_docs()]);
+        assert_eq!(
+            collect_docs(intersection),
+            vec![10, 12, 14, 16, 18, 20, 22, 24, 26, 28]
+        );
+
+        let union = Union::<_, crate::query::score_combiner::DoNothingCombiner>::from(vec![
+            ConstScorer::new(range_docs(), 1.0),
+            ConstScorer::new(Box::new(VecDocSet::from(vec![3, 40, 41])), 1.0),
+        ]);
+        let exclude = Exclude::new(union, even_docs());
+        assert_eq!(
+            collect_docs(exclude),
+            vec![3, 11, 13, 15, 17, 19, 21, 23, 25, 27, 29, 41]
+        );
+    }
+}
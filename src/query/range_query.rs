@@ -1,19 +1,28 @@
 use crate::core::Searcher;
 use crate::core::SegmentReader;
 use crate::error::TantivyError;
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader, MultiValuedFastFieldReader};
 use crate::query::explanation::does_not_match;
-use crate::query::ConstScorer;
 use crate::query::{BitSetDocSet, Explanation};
+use crate::query::{ConstScorer, EmptyScorer};
 use crate::query::{Query, Scorer, Weight};
-use crate::schema::Type;
-use crate::schema::{Field, IndexRecordOption, Term};
+use crate::schema::{Cardinality, Type};
+use crate::schema::{Field, FieldType, IndexRecordOption, Term};
 use crate::termdict::{TermDictionary, TermStreamer};
 use crate::{DocId, Score};
 use common::BitSet;
+use std::convert::TryInto;
 use std::io;
-use std::ops::{Bound, Range};
+use std::ops::{Bound, Range, RangeInclusive};
 
-fn map_bound<TFrom, TTo, Transform: Fn(&TFrom) -> TTo>(
+/// Default estimated number of terms within the range above which
+/// the fast field of the field is scanned.
+const DEFAULT_FAST_FIELD_SCAN_THRESHOLD: u64 = 1_000;
+
+/// Number of values of a single-valued fast field read at once by a scan.
+const SCAN_BLOCK_LEN: usize = 1_024;
+
+pub(crate) fn map_bound<TFrom, TTo, Transform: Fn(&TFrom) -> TTo>(
     bound: &Bound<TFrom>,
     transform: &Transform,
 ) -> Bound<TTo> {
@@ -34,6 +43,13 @@ fn map_bound<TFrom, TTo, Transform: Fn(&TFrom) -> TTo>(
 /// The current implement will iterate over the terms within the range
 /// and append all of the document cross into a `BitSet`.
 ///
+/// For the numeric fields that are both indexed and fast, iterating over the terms
+/// gets expensive when the range contains many distinct values. When the estimated
+/// number of terms within the range exceeds a threshold (see
+/// [`RangeQuery::with_fast_field_scan_threshold`](#method.with_fast_field_scan_threshold)),
+/// the values of the fast field are scanned instead, and the documents whose value
+/// falls within the range are appended into the `BitSet`.
+///
 /// # Example
 ///
 /// ```rust
@@ -71,6 +87,7 @@ pub struct RangeQuery {
     value_type: Type,
     left_bound: Bound<Vec<u8>>,
     right_bound: Bound<Vec<u8>>,
+    fast_field_scan_threshold: Option<u64>,
 }
 
 impl RangeQuery {
@@ -93,6 +110,7 @@ impl RangeQuery {
             value_type,
             left_bound: map_bound(left_bound, &verify_and_unwrap_term),
             right_bound: map_bound(right_bound, &verify_and_unwrap_term),
+            fast_field_scan_threshold: Some(DEFAULT_FAST_FIELD_SCAN_THRESHOLD),
         }
     }
 
@@ -126,6 +144,7 @@ impl RangeQuery {
             value_type: Type::I64,
             left_bound: map_bound(&left_bound, &make_term_val),
             right_bound: map_bound(&right_bound, &make_term_val),
+            fast_field_scan_threshold: Some(DEFAULT_FAST_FIELD_SCAN_THRESHOLD),
         }
     }
 
@@ -159,6 +178,7 @@ impl RangeQuery {
             value_type: Type::F64,
             left_bound: map_bound(&left_bound, &make_term_val),
             right_bound: map_bound(&right_bound, &make_term_val),
+            fast_field_scan_threshold: Some(DEFAULT_FAST_FIELD_SCAN_THRESHOLD),
         }
     }
 
@@ -180,6 +200,7 @@ impl RangeQuery {
             value_type: Type::U64,
             left_bound: map_bound(&left_bound, &make_term_val),
             right_bound: map_bound(&right_bound, &make_term_val),
+            fast_field_scan_threshold: Some(DEFAULT_FAST_FIELD_SCAN_THRESHOLD),
         }
     }
 
@@ -209,6 +230,7 @@ impl RangeQuery {
             value_type: Type::Str,
             left_bound: map_bound(&left, &make_term_val),
             right_bound: map_bound(&right, &make_term_val),
+            fast_field_scan_threshold: Some(DEFAULT_FAST_FIELD_SCAN_THRESHOLD),
         }
     }
 
@@ -224,6 +246,21 @@ impl RangeQuery {
         )
     }
 
+    /// Sets the estimated number of terms within the range above which the matching
+    /// documents are found by scanning the fast field of the field, rather than by
+    /// reading the posting lists of the terms. `None` disables the fast field scan.
+    ///
+    /// The number of terms within the range is estimated, for each segment, from its
+    /// number of terms and from the minimum and maximum values of its fast field.
+    /// The fast field scan only applies to the numeric fields that are both indexed
+    /// and fast.
+    ///
+    /// Defaults to `Some(1_000)`.
+    pub fn with_fast_field_scan_threshold(mut self, threshold: Option<u64>) -> RangeQuery {
+        self.fast_field_scan_threshold = threshold;
+        self
+    }
+
     /// Field to search over
     pub fn field(&self) -> Field {
         self.field
@@ -259,21 +296,105 @@ impl Query for RangeQuery {
             );
             return Err(TantivyError::SchemaError(err_msg));
         }
+        let fast_field_cardinality = match schema.get_field_entry(self.field).field_type() {
+            FieldType::U64(int_options)
+            | FieldType::I64(int_options)
+            | FieldType::F64(int_options)
+            | FieldType::Date(int_options) => int_options.get_fastfield_cardinality(),
+            _ => None,
+        };
         Ok(Box::new(RangeWeight {
             field: self.field,
             left_bound: self.left_bound.clone(),
             right_bound: self.right_bound.clone(),
+            fast_field_cardinality,
+            fast_field_scan_threshold: self.fast_field_scan_threshold,
         }))
     }
 }
 
+/// Estimates the number of terms within `value_range`, assuming that the
+/// `num_terms` terms are evenly spread between `min_value` and `max_value`.
+fn estimate_num_terms(
+    num_terms: u64,
+    value_range: &RangeInclusive<u64>,
+    min_value: u64,
+    max_value: u64,
+) -> u64 {
+    let start = (*value_range.start()).max(min_value);
+    let end = (*value_range.end()).min(max_value);
+    if start > end {
+        return 0;
+    }
+    let num_values_in_range = u128::from(end - start) + 1;
+    let num_values = u128::from(max_value - min_value) + 1;
+    (u128::from(num_terms) * num_values_in_range / num_values) as u64
+}
+
+fn scan_single_valued(
+    fast_field_reader: &DynamicFastFieldReader<u64>,
+    value_range: &RangeInclusive<u64>,
+    max_doc: DocId,
+) -> BitSet {
+    let mut doc_bitset = BitSet::with_max_value(max_doc);
+    let start = *value_range.start();
+    let width = *value_range.end() - start;
+    let mut vals_buffer = vec![0u64; SCAN_BLOCK_LEN];
+    for block_start in (0..max_doc).step_by(SCAN_BLOCK_LEN) {
+        let block_len = (max_doc - block_start).min(SCAN_BLOCK_LEN as u32) as usize;
+        let vals = &mut vals_buffer[..block_len];
+        fast_field_reader.get_range(u64::from(block_start), vals);
+        for (doc, &val) in (block_start..).zip(vals.iter()) {
+            // A single comparison, that the compiler can vectorize.
+            if val.wrapping_sub(start) <= width {
+                doc_bitset.insert(doc);
+            }
+        }
+    }
+    doc_bitset
+}
+
+fn scan_multi_valued(
+    fast_field_reader: &MultiValuedFastFieldReader<u64>,
+    value_range: &RangeInclusive<u64>,
+    max_doc: DocId,
+) -> BitSet {
+    let mut doc_bitset = BitSet::with_max_value(max_doc);
+    let mut vals = Vec::new();
+    for doc in 0..max_doc {
+        fast_field_reader.get_vals(doc, &mut vals);
+        if vals.iter().any(|val| value_range.contains(val)) {
+            doc_bitset.insert(doc);
+        }
+    }
+    doc_bitset
+}
+
 pub struct RangeWeight {
     field: Field,
     left_bound: Bound<Vec<u8>>,
     right_bound: Bound<Vec<u8>>,
+    fast_field_cardinality: Option<Cardinality>,
+    fast_field_scan_threshold: Option<u64>,
 }
 
 impl RangeWeight {
+    /// Creates a weight matching the documents containing a term within the
+    /// range of raw term bytes, without ever scanning the fast field.
+    pub(crate) fn for_term_range(
+        field: Field,
+        left_bound: Bound<Vec<u8>>,
+        right_bound: Bound<Vec<u8>>,
+    ) -> RangeWeight {
+        RangeWeight {
+            field,
+            left_bound,
+            right_bound,
+            fast_field_cardinality: None,
+            fast_field_scan_threshold: None,
+        }
+    }
+
     fn term_range<'a>(&self, term_dict: &'a TermDictionary) -> io::Result<TermStreamer<'a>> {
         use std::ops::Bound::*;
         let mut term_stream_builder = term_dict.range();
@@ -289,17 +410,128 @@ impl RangeWeight {
         };
         term_stream_builder.into_stream()
     }
+
+    /// Returns the range of the `u64` representation of the values within the range,
+    /// as stored in the fast field.
+    ///
+    /// Returns `None` if the range is empty.
+    fn value_range(&self) -> Option<RangeInclusive<u64>> {
+        use std::ops::Bound::*;
+        let to_u64 = |term_val: &Vec<u8>| -> Option<u64> {
+            term_val.as_slice().try_into().ok().map(u64::from_be_bytes)
+        };
+        let start = match self.left_bound {
+            Included(ref term_val) => to_u64(term_val)?,
+            Excluded(ref term_val) => to_u64(term_val)?.checked_add(1)?,
+            Unbounded => 0u64,
+        };
+        let end = match self.right_bound {
+            Included(ref term_val) => to_u64(term_val)?,
+            Excluded(ref term_val) => to_u64(term_val)?.checked_sub(1)?,
+            Unbounded => u64::MAX,
+        };
+        if start > end {
+            return None;
+        }
+        Some(start..=end)
+    }
+
+    /// Returns false if the field bounds of the segment rule the whole range out.
+    fn may_match(&self, reader: &SegmentReader) -> bool {
+        let field_bounds = if let Some(field_bounds) = reader.field_bounds(self.field) {
+            field_bounds
+        } else {
+            return true;
+        };
+        if !field_bounds.may_contain_term_within(&self.left_bound, &self.right_bound) {
+            return false;
+        }
+        // Value bounds are only recorded for numeric fields, whose terms are
+        // the `u64` representation of their values.
+        match (&field_bounds.values, self.value_range()) {
+            (Some(_), Some(value_range)) => field_bounds.may_contain_value_within(&value_range),
+            _ => true,
+        }
+    }
+
+    /// Scans the fast field for the documents within the range, if the estimated
+    /// number of terms within the range exceeds the fast field scan threshold.
+    ///
+    /// Returns `None` if the terms of the range should be read instead.
+    fn scan_fast_field(
+        &self,
+        reader: &SegmentReader,
+        term_dict: &TermDictionary,
+    ) -> crate::Result<Option<BitSet>> {
+        let (cardinality, threshold) =
+            match (self.fast_field_cardinality, self.fast_field_scan_threshold) {
+                (Some(cardinality), Some(threshold)) => (cardinality, threshold),
+                _ => return Ok(None),
+            };
+        let num_terms = term_dict.num_terms() as u64;
+        if num_terms <= threshold {
+            return Ok(None);
+        }
+        let value_range = if let Some(value_range) = self.value_range() {
+            value_range
+        } else {
+            return Ok(None);
+        };
+        let fast_fields = reader.fast_fields();
+        let doc_bitset = match cardinality {
+            Cardinality::SingleValue => {
+                let fast_field_reader = fast_fields.u64_lenient(self.field)?;
+                let estimated_num_terms = estimate_num_terms(
+                    num_terms,
+                    &value_range,
+                    fast_field_reader.min_value(),
+                    fast_field_reader.max_value(),
+                );
+                if estimated_num_terms <= threshold {
+                    return Ok(None);
+                }
+                let mut doc_bitset =
+                    scan_single_valued(&fast_field_reader, &value_range, reader.max_doc());
+                // The documents without any value hold a default value in the fast field.
+                if let Some(presence_bitset) = fast_fields.presence_bitset(self.field)? {
+                    doc_bitset.intersect_update(&presence_bitset);
+                }
+                doc_bitset
+            }
+            Cardinality::MultiValues => {
+                let fast_field_reader = fast_fields.u64s_lenient(self.field)?;
+                let estimated_num_terms = estimate_num_terms(
+                    num_terms,
+                    &value_range,
+                    fast_field_reader.min_value(),
+                    fast_field_reader.max_value(),
+                );
+                if estimated_num_terms <= threshold {
+                    return Ok(None);
+                }
+                scan_multi_valued(&fast_field_reader, &value_range, reader.max_doc())
+            }
+        };
+        Ok(Some(doc_bitset))
+    }
 }
 
 impl Weight for RangeWeight {
     fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if !self.may_match(reader) {
+            return Ok(Box::new(EmptyScorer));
+        }
         let max_doc = reader.max_doc();
-        let mut doc_bitset = BitSet::with_max_value(max_doc);
-
         let inverted_index = reader.inverted_index(self.field)?;
         let term_dict = inverted_index.terms();
+        if let Some(doc_bitset) = self.scan_fast_field(reader, term_dict)? {
+            let doc_bitset = BitSetDocSet::from(doc_bitset);
+            return Ok(Box::new(ConstScorer::new(doc_bitset, boost)));
+        }
+
+        let mut doc_bitset = BitSet::with_max_value(max_doc);
         let mut term_range = self.term_range(term_dict)?;
-        while term_range.advance() {
+        while term_range.try_advance()? {
             let term_info = term_range.value();
             let mut block_segment_postings = inverted_index
                 .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)?;
@@ -330,11 +562,16 @@ impl Weight for RangeWeight {
 #[cfg(test)]
 mod tests {
 
-    use super::RangeQuery;
-    use crate::collector::{Count, TopDocs};
-    use crate::query::QueryParser;
-    use crate::schema::{Document, Field, Schema, INDEXED, TEXT};
-    use crate::Index;
+    use super::{estimate_num_terms, RangeQuery, RangeWeight};
+    use crate::collector::{Count, DocSetCollector, TopDocs};
+    use crate::query::{Query, QueryParser, TermQuery};
+    use crate::schema::{
+        Cardinality, Document, Field, IndexRecordOption, IntOptions, Schema, FAST, INDEXED, STRING,
+        TEXT,
+    };
+    use crate::{DocAddress, Index, Term};
+    use futures::executor::block_on;
+    use std::collections::HashSet;
     use std::ops::Bound;
 
     #[test]
@@ -507,4 +744,201 @@ mod tests {
         assert_eq!(top_docs.len(), 1);
         Ok(())
     }
+
+    #[test]
+    fn test_estimate_num_terms() {
+        assert_eq!(estimate_num_terms(100, &(0..=u64::MAX), 10, 19), 100);
+        assert_eq!(estimate_num_terms(100, &(10..=14), 10, 19), 50);
+        assert_eq!(estimate_num_terms(100, &(15..=100), 10, 19), 50);
+        assert_eq!(estimate_num_terms(100, &(20..=100), 10, 19), 0);
+        assert_eq!(estimate_num_terms(100, &(0..=9), 10, 19), 0);
+        assert_eq!(estimate_num_terms(10, &(0..=u64::MAX), 0, u64::MAX), 10);
+        assert_eq!(estimate_num_terms(10, &(0..=u64::MAX / 2), 0, u64::MAX), 5);
+    }
+
+    #[test]
+    fn test_range_query_fast_field_scan() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let u64_field = schema_builder.add_u64_field("u64", INDEXED | FAST);
+        let i64_field = schema_builder.add_i64_field("i64", INDEXED | FAST);
+        let f64_field = schema_builder.add_f64_field("f64", INDEXED | FAST);
+        let multi_field = schema_builder.add_i64_field(
+            "multi",
+            IntOptions::default()
+                .set_indexed()
+                .set_fast(Cardinality::MultiValues),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0u64..2_000 {
+            let mut doc = Document::new();
+            doc.add_u64(u64_field, i * 7 % 1_500);
+            // Some of the documents do not have any value.
+            if i % 5 != 0 {
+                doc.add_i64(i64_field, i as i64 - 1_000);
+            }
+            doc.add_f64(f64_field, i as f64 / 4.0);
+            for j in 0..(i % 3) {
+                doc.add_i64(multi_field, (i * 3 + j) as i64 % 2_500 - 500);
+            }
+            index_writer.add_document(doc)?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let check_query = |query: RangeQuery| -> crate::Result<()> {
+            let scanned_query = query.clone().with_fast_field_scan_threshold(Some(0));
+            let term_query = query.clone().with_fast_field_scan_threshold(None);
+            let scanned_docs: HashSet<DocAddress> =
+                searcher.search(&scanned_query, &DocSetCollector)?;
+            let term_docs: HashSet<DocAddress> = searcher.search(&term_query, &DocSetCollector)?;
+            assert_eq!(scanned_docs, term_docs, "{:?}", query);
+
+            // Checks that the fast field is indeed scanned, unless the range is empty.
+            let weight = RangeWeight {
+                field: query.field,
+                left_bound: query.left_bound.clone(),
+                right_bound: query.right_bound.clone(),
+                fast_field_cardinality: Some(if query.field == multi_field {
+                    Cardinality::MultiValues
+                } else {
+                    Cardinality::SingleValue
+                }),
+                fast_field_scan_threshold: Some(0),
+            };
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader.inverted_index(query.field)?;
+                let scanned = weight
+                    .scan_fast_field(segment_reader, inverted_index.terms())?
+                    .is_some();
+                assert_eq!(scanned, !term_docs.is_empty(), "{:?}", query);
+            }
+            Ok(())
+        };
+        check_query(RangeQuery::new_u64(u64_field, 100..1_000))?;
+        check_query(RangeQuery::new_u64_bounds(
+            u64_field,
+            Bound::Excluded(7),
+            Bound::Included(14),
+        ))?;
+        check_query(RangeQuery::new_u64_bounds(
+            u64_field,
+            Bound::Unbounded,
+            Bound::Excluded(1),
+        ))?;
+        check_query(RangeQuery::new_u64(u64_field, 2_000..3_000))?;
+        check_query(RangeQuery::new_i64(i64_field, -500..500))?;
+        check_query(RangeQuery::new_i64_bounds(
+            i64_field,
+            Bound::Unbounded,
+            Bound::Included(-900),
+        ))?;
+        check_query(RangeQuery::new_i64_bounds(
+            i64_field,
+            Bound::Unbounded,
+            Bound::Unbounded,
+        ))?;
+        check_query(RangeQuery::new_f64(f64_field, 10.5..100.25))?;
+        check_query(RangeQuery::new_f64_bounds(
+            f64_field,
+            Bound::Excluded(-1.0),
+            Bound::Excluded(0.5),
+        ))?;
+        check_query(RangeQuery::new_i64(multi_field, -100..100))?;
+        check_query(RangeQuery::new_i64_bounds(
+            multi_field,
+            Bound::Included(1_000),
+            Bound::Unbounded,
+        ))?;
+
+        let count = |query: RangeQuery| searcher.search(&query, &Count).unwrap();
+        let all_i64 = RangeQuery::new_i64_bounds(i64_field, Bound::Unbounded, Bound::Unbounded);
+        assert_eq!(
+            count(all_i64.with_fast_field_scan_threshold(Some(0))),
+            1_600
+        );
+        let multi = RangeQuery::new_i64(multi_field, -500..2_000);
+        assert_eq!(count(multi.with_fast_field_scan_threshold(Some(0))), 1_333);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_query_segment_pruning() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let timestamp = schema_builder.add_i64_field("timestamp", INDEXED | FAST);
+        let day = schema_builder.add_text_field("day", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // One segment per day.
+        for day_ord in 0..3i64 {
+            for i in 0..10i64 {
+                index_writer.add_document(doc!(
+                    timestamp => day_ord * 100 + i - 100,
+                    day => format!("day{}", day_ord)
+                ))?;
+            }
+            index_writer.commit()?;
+        }
+        let reader = index.reader()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        let mut value_bounds: Vec<(u64, u64)> = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            let field_bounds = segment_reader.field_bounds(timestamp).unwrap();
+            let (min_value, max_value) = field_bounds.values.unwrap();
+            assert_eq!(max_value - min_value, 9);
+            let day_bounds = segment_reader.field_bounds(day).unwrap();
+            assert!(day_bounds.values.is_none());
+            let (first_day, last_day) = day_bounds.terms.clone().unwrap();
+            assert_eq!(first_day, last_day);
+            value_bounds.push((min_value, max_value));
+        }
+
+        let count = |query: &dyn Query| searcher.search(query, &Count).unwrap();
+        assert_eq!(count(&RangeQuery::new_i64(timestamp, -95..5)), 10);
+        assert_eq!(count(&RangeQuery::new_i64(timestamp, 5..95)), 5);
+        assert_eq!(count(&RangeQuery::new_i64(timestamp, 10..100)), 0);
+        let day1 = TermQuery::new(Term::from_field_text(day, "day1"), IndexRecordOption::Basic);
+        assert_eq!(count(&day1), 10);
+        // Only one of the segments may contain matches.
+        let day1_term = Term::from_field_text(day, "day1");
+        let range_query = RangeQuery::new_i64(timestamp, 0..5);
+        let range_weight = RangeWeight {
+            field: timestamp,
+            left_bound: range_query.left_bound.clone(),
+            right_bound: range_query.right_bound.clone(),
+            fast_field_cardinality: None,
+            fast_field_scan_threshold: None,
+        };
+        let mut num_term_matches = 0;
+        let mut num_range_matches = 0;
+        for segment_reader in searcher.segment_readers() {
+            let day_bounds = segment_reader.field_bounds(day).unwrap();
+            if day_bounds.may_contain_term(day1_term.value_bytes()) {
+                num_term_matches += 1;
+            }
+            if range_weight.may_match(segment_reader) {
+                num_range_matches += 1;
+            }
+        }
+        assert_eq!(num_term_matches, 1);
+        assert_eq!(num_range_matches, 1);
+        assert_eq!(count(&range_query), 5);
+
+        // The bounds are persisted in the meta.json, and recomputed on merge.
+        let segment_metas = index.searchable_segment_metas()?;
+        assert!(segment_metas
+            .iter()
+            .all(|segment_meta| segment_meta.field_bounds().contains_key("timestamp")));
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        let min_value = value_bounds.iter().map(|bounds| bounds.0).min().unwrap();
+        let max_value = value_bounds.iter().map(|bounds| bounds.1).max().unwrap();
+        let field_bounds = &segment_metas[0].field_bounds()["timestamp"];
+        assert_eq!(field_bounds.values, Some((min_value, max_value)));
+        Ok(())
+    }
 }
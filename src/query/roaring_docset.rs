@@ -0,0 +1,130 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::DocId;
+use roaring::bitmap::IntoIter;
+use roaring::RoaringBitmap;
+
+/// A `RoaringDocSet` makes it possible to iterate through a compressed
+/// `RoaringBitmap` as if it was a `DocSet`.
+///
+/// It is typically used with a bitmap materialized by
+/// [`Weight::docset_bitmap`](./trait.Weight.html#method.docset_bitmap), possibly
+/// combined with other bitmaps, and wrapped into a
+/// [`ConstScorer`](./struct.ConstScorer.html) to be searched again.
+pub struct RoaringDocSet {
+    docs: IntoIter,
+    doc: DocId,
+    len: u32,
+}
+
+impl From<RoaringBitmap> for RoaringDocSet {
+    fn from(docs: RoaringBitmap) -> RoaringDocSet {
+        let len = docs.len() as u32;
+        let mut docset = RoaringDocSet {
+            docs: docs.into_iter(),
+            doc: 0,
+            len,
+        };
+        docset.advance();
+        docset
+    }
+}
+
+impl DocSet for RoaringDocSet {
+    fn advance(&mut self) -> DocId {
+        // bitmaps built outside of tantivy may contain values above `TERMINATED`.
+        self.doc = match self.docs.next() {
+            Some(doc) if doc < TERMINATED => doc,
+            _ => TERMINATED,
+        };
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        self.docs.advance_to(target);
+        self.advance()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoaringDocSet;
+    use crate::collector::Count;
+    use crate::docset::{DocSet, TERMINATED};
+    use crate::query::{ConstScorer, Query, Scorer, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STRING};
+    use crate::{Index, Term};
+    use roaring::RoaringBitmap;
+
+    #[test]
+    fn test_roaring_docset() {
+        let bitmap: RoaringBitmap = vec![1u32, 3, 70_000, 70_001, 200_000].into_iter().collect();
+        let mut docset = RoaringDocSet::from(bitmap);
+        assert_eq!(docset.size_hint(), 5);
+        assert_eq!(docset.doc(), 1);
+        assert_eq!(docset.seek(1), 1);
+        assert_eq!(docset.advance(), 3);
+        assert_eq!(docset.seek(4), 70_000);
+        assert_eq!(docset.seek(70_001), 70_001);
+        assert_eq!(docset.advance(), 200_000);
+        assert_eq!(docset.advance(), TERMINATED);
+        assert_eq!(docset.seek(TERMINATED), TERMINATED);
+
+        let mut empty = RoaringDocSet::from(RoaringBitmap::new());
+        assert_eq!(empty.doc(), TERMINATED);
+        assert_eq!(empty.advance(), TERMINATED);
+    }
+
+    #[test]
+    fn test_docset_bitmap() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let color = schema_builder.add_text_field("color", STRING);
+        let size = schema_builder.add_text_field("size", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..10 {
+            let color_value = if i % 2 == 0 { "red" } else { "blue" };
+            let size_value = if i < 5 { "small" } else { "large" };
+            index_writer.add_document(doc!(color => color_value, size => size_value))?;
+        }
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(size, "large"));
+        index_writer.add_document(doc!(color => "red", size => "small"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = |field, text| {
+            TermQuery::new(Term::from_field_text(field, text), IndexRecordOption::Basic)
+        };
+        let red_weight = term_query(color, "red").weight(&searcher, false)?;
+        let small_weight = term_query(size, "small").weight(&searcher, false)?;
+
+        let mut num_docs = 0;
+        for segment_reader in searcher.segment_readers() {
+            // The deleted documents are not part of the bitmaps.
+            let red_bitmap = red_weight.docset_bitmap(segment_reader)?;
+            let small_bitmap = small_weight.docset_bitmap(segment_reader)?;
+            assert_eq!(red_bitmap.len(), red_weight.count(segment_reader)? as u64);
+            let red_and_small = &red_bitmap & &small_bitmap;
+            let mut scorer = ConstScorer::new(RoaringDocSet::from(red_and_small), 1.0);
+            while scorer.doc() != TERMINATED {
+                assert_eq!(scorer.score(), 1.0);
+                num_docs += 1;
+                scorer.advance();
+            }
+        }
+        // docs 0, 2, 4 of the first segment, and the document of the second segment.
+        assert_eq!(num_docs, 4);
+        assert_eq!(searcher.search(&term_query(color, "red"), &Count)?, 4);
+        Ok(())
+    }
+}
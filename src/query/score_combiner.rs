@@ -78,3 +78,31 @@ impl ScoreCombiner for SumWithCoordsCombiner {
         self.score
     }
 }
+
+/// Keeps the best score of different scorers.
+#[derive(Clone, Copy)]
+pub struct MaxCombiner {
+    score: Score,
+}
+
+impl Default for MaxCombiner {
+    fn default() -> MaxCombiner {
+        MaxCombiner {
+            score: Score::NEG_INFINITY,
+        }
+    }
+}
+
+impl ScoreCombiner for MaxCombiner {
+    fn update<TScorer: Scorer>(&mut self, scorer: &mut TScorer) {
+        self.score = self.score.max(scorer.score());
+    }
+
+    fn clear(&mut self) {
+        self.score = Score::NEG_INFINITY;
+    }
+
+    fn score(&self) -> Score {
+        self.score
+    }
+}
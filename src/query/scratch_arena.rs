@@ -0,0 +1,113 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of buffers kept by a `ScratchArena`, so that a query searching
+/// many segments in parallel does not retain an unbounded amount of memory.
+const MAX_NUM_BUFFERS: usize = 64;
+
+/// Scratch buffers shared by the scorers of a query.
+///
+/// The weight of a query is created once per query, and builds the scorers of
+/// all of the segments. The scorers take their temporary buffers from the arena of
+/// their weight, and give them back when they are dropped: the scorer of the next
+/// segment then reuses them, already grown to their working size, instead of
+/// allocating its own.
+///
+/// Cloning an arena is cheap, and the clones share the same buffers.
+#[derive(Clone, Default)]
+pub(crate) struct ScratchArena {
+    buffers: Arc<Mutex<Vec<Vec<u32>>>>,
+}
+
+impl ScratchArena {
+    /// Takes an empty buffer from the arena, or allocates one if none is available.
+    pub fn take_buffer(&self) -> ScratchBuffer {
+        let buffer = self
+            .buffers
+            .lock()
+            .expect("Scratch arena lock poisoned. This should never happen.")
+            .pop()
+            .unwrap_or_default();
+        ScratchBuffer {
+            buffer,
+            arena: self.clone(),
+        }
+    }
+
+    fn give_back(&self, mut buffer: Vec<u32>) {
+        buffer.clear();
+        let mut buffers = self
+            .buffers
+            .lock()
+            .expect("Scratch arena lock poisoned. This should never happen.");
+        if buffers.len() < MAX_NUM_BUFFERS {
+            buffers.push(buffer);
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn num_available_buffers(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+}
+
+/// A buffer taken from a `ScratchArena`, given back to the arena when dropped.
+pub(crate) struct ScratchBuffer {
+    buffer: Vec<u32>,
+    arena: ScratchArena,
+}
+
+impl Deref for ScratchBuffer {
+    type Target = Vec<u32>;
+
+    fn deref(&self) -> &Vec<u32> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for ScratchBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u32> {
+        &mut self.buffer
+    }
+}
+
+impl Drop for ScratchBuffer {
+    fn drop(&mut self) {
+        self.arena.give_back(mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScratchArena, MAX_NUM_BUFFERS};
+
+    #[test]
+    fn test_scratch_arena_reuses_buffers() {
+        let arena = ScratchArena::default();
+        let mut buffer = arena.take_buffer();
+        buffer.extend(0..1_000);
+        let buffer_ptr = buffer.as_ptr();
+        drop(buffer);
+        assert_eq!(arena.num_available_buffers(), 1);
+
+        // The buffer is given back empty, but keeps its capacity.
+        let buffer = arena.clone().take_buffer();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 1_000);
+        assert_eq!(buffer.as_ptr(), buffer_ptr);
+        assert_eq!(arena.num_available_buffers(), 0);
+        let other_buffer = arena.take_buffer();
+        assert_eq!(other_buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn test_scratch_arena_max_num_buffers() {
+        let arena = ScratchArena::default();
+        let buffers: Vec<_> = (0..MAX_NUM_BUFFERS + 10)
+            .map(|_| arena.take_buffer())
+            .collect();
+        drop(buffers);
+        assert_eq!(arena.num_available_buffers(), MAX_NUM_BUFFERS);
+    }
+}
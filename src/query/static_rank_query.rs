@@ -0,0 +1,488 @@
+use crate::fastfield::{DynamicFastFieldReader, FastFieldReader};
+use crate::query::explanation::does_not_match;
+use crate::query::{Explanation, Query, Scorer, Weight};
+use crate::schema::{Cardinality, Field, FieldType, Schema};
+use crate::{DocId, DocSet, Score, Searcher, SegmentReader, TantivyError, Term};
+use std::collections::BTreeMap;
+use std::fmt;
+
+fn u64_to_score(val: u64) -> Score {
+    val as Score
+}
+
+fn i64_to_score(val: u64) -> Score {
+    common::u64_to_i64(val) as Score
+}
+
+fn f64_to_score(val: u64) -> Score {
+    common::u64_to_f64(val) as Score
+}
+
+/// Returns the function converting the `u64` representation of the values of
+/// the static rank field `field` into scores, or an error if `field` is not a
+/// single-valued `u64`, `i64` or `f64` fast field.
+fn static_rank_to_score(schema: &Schema, field: Field) -> crate::Result<fn(u64) -> Score> {
+    let field_entry = schema.get_field_entry(field);
+    let (int_options, to_score): (_, fn(u64) -> Score) = match field_entry.field_type() {
+        FieldType::U64(int_options) => (int_options, u64_to_score),
+        FieldType::I64(int_options) => (int_options, i64_to_score),
+        FieldType::F64(int_options) => (int_options, f64_to_score),
+        _ => {
+            return Err(TantivyError::SchemaError(format!(
+                "The static rank field {:?} is not a u64, i64 or f64 field.",
+                field_entry.name()
+            )));
+        }
+    };
+    if int_options.get_fastfield_cardinality() != Some(Cardinality::SingleValue) {
+        return Err(TantivyError::SchemaError(format!(
+            "The static rank field {:?} is not a single-valued fast field.",
+            field_entry.name()
+        )));
+    }
+    Ok(to_score)
+}
+
+/// Returns the static rank field named `field_name`, after checking that it is a
+/// single-valued numeric fast field.
+pub(crate) fn static_rank_field(schema: &Schema, field_name: &str) -> crate::Result<Field> {
+    let field = schema.get_field(field_name).ok_or_else(|| {
+        TantivyError::InvalidArgument(format!("static rank field not found: {:?}", field_name))
+    })?;
+    static_rank_to_score(schema, field)?;
+    Ok(field)
+}
+
+/// The weighted static ranks of the documents of a segment.
+///
+/// See [`StaticRankQuery`].
+#[derive(Clone)]
+pub struct StaticRankReader {
+    static_ranks: DynamicFastFieldReader<u64>,
+    to_score: fn(u64) -> Score,
+    weight: Score,
+    max_score: Score,
+    // True if the postings of the segment record the maximum static rank of their blocks.
+    has_block_max_static_ranks: bool,
+}
+
+impl StaticRankReader {
+    fn open(reader: &SegmentReader, field: Field, weight: Score) -> crate::Result<Self> {
+        let to_score = static_rank_to_score(reader.schema(), field)?;
+        let static_ranks = reader.fast_fields().u64_lenient(field)?;
+        let max_score = weight * to_score(static_ranks.max_value());
+        Ok(StaticRankReader {
+            static_ranks,
+            to_score,
+            weight,
+            max_score,
+            has_block_max_static_ranks: reader.static_rank_field() == Some(field),
+        })
+    }
+
+    /// Returns the weighted static rank of `doc`.
+    pub fn score(&self, doc: DocId) -> Score {
+        self.weight * (self.to_score)(self.static_ranks.get(doc))
+    }
+
+    /// Returns the maximum weighted static rank of the documents of the segment.
+    pub fn max_score(&self) -> Score {
+        self.max_score
+    }
+
+    /// Returns the maximum weighted static rank of the documents of a block of
+    /// postings, given the maximum static rank recorded for the block.
+    pub(crate) fn block_max_score(&self, block_max_static_rank: Option<u64>) -> Score {
+        match block_max_static_rank {
+            Some(static_rank) if self.has_block_max_static_ranks => {
+                self.weight * (self.to_score)(static_rank)
+            }
+            _ => self.max_score,
+        }
+    }
+}
+
+/// `StaticRankQuery` blends a query-independent static rank, like a pagerank,
+/// in the scores of a query.
+///
+/// The document set matched by the `StaticRankQuery` is strictly the same as the
+/// underlying query. The score of each document is the score of the underlying
+/// query, plus `weight` times the value of the document in the static rank field,
+/// a single-valued `u64`, `i64` or `f64` fast field.
+///
+/// If the static rank field is the `static_rank_field` of the
+/// [`IndexSettings`](crate::IndexSettings), the postings record the maximum static
+/// rank of the documents of each of their blocks. When collecting the top documents
+/// of a term query, or of a union of term queries, this maximum is then folded in the
+/// block max scores, so that the blocks that cannot reach the top documents are skipped
+/// rather than fully scored.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{QueryParser, StaticRankQuery};
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, DocAddress, Index, IndexSettings};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let pagerank = schema_builder.add_f64_field("pagerank", FAST);
+/// let index = Index::builder()
+///     .schema(schema_builder.build())
+///     .settings(IndexSettings {
+///         static_rank_field: Some("pagerank".to_string()),
+///         ..Default::default()
+///     })
+///     .create_in_ram()?;
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(title => "The Name of the Rose", pagerank => 0.1))?;
+/// index_writer.add_document(doc!(title => "The Rose", pagerank => 2.0))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("name rose")?;
+/// let query = StaticRankQuery::new(query, pagerank, 1.0);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+/// # Ok(())
+/// # }
+/// ```
+pub struct StaticRankQuery {
+    query: Box<dyn Query>,
+    field: Field,
+    weight: Score,
+}
+
+impl StaticRankQuery {
+    /// Creates a query adding `weight` times the static rank read from `field`
+    /// to the scores of `query`.
+    ///
+    /// `weight` must be non-negative.
+    pub fn new(query: Box<dyn Query>, field: Field, weight: Score) -> StaticRankQuery {
+        StaticRankQuery {
+            query,
+            field,
+            weight,
+        }
+    }
+}
+
+impl Clone for StaticRankQuery {
+    fn clone(&self) -> Self {
+        StaticRankQuery {
+            query: self.query.box_clone(),
+            field: self.field,
+            weight: self.weight,
+        }
+    }
+}
+
+impl fmt::Debug for StaticRankQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "StaticRank(query={:?}, field={:?}, weight={})",
+            self.query, self.field, self.weight
+        )
+    }
+}
+
+impl Query for StaticRankQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> crate::Result<Box<dyn Weight>> {
+        let weight = self.query.weight(searcher, scoring_enabled)?;
+        if !scoring_enabled {
+            return Ok(weight);
+        }
+        if self.weight.is_nan() || self.weight < 0.0 {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The weight of the static rank must be non-negative, got {}.",
+                self.weight
+            )));
+        }
+        static_rank_to_score(searcher.schema(), self.field)?;
+        Ok(Box::new(StaticRankWeight {
+            weight,
+            field: self.field,
+            static_rank_weight: self.weight,
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        self.query.query_terms(terms)
+    }
+
+    fn rewrite(&self, searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        Ok(self.query.rewrite(searcher)?.map(|query| {
+            Box::new(StaticRankQuery::new(query, self.field, self.weight)) as Box<dyn Query>
+        }))
+    }
+}
+
+struct StaticRankWeight {
+    weight: Box<dyn Weight>,
+    field: Field,
+    static_rank_weight: Score,
+}
+
+impl Weight for StaticRankWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let static_ranks =
+            StaticRankReader::open(reader, self.field, self.static_rank_weight * boost)?;
+        let scorer = self.weight.scorer(reader, boost)?;
+        Ok(Box::new(StaticRankScorer::new(scorer, static_ranks)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let static_ranks = StaticRankReader::open(reader, self.field, self.static_rank_weight)?;
+        let mut explanation = Explanation::new("Score + static rank x weight", scorer.score());
+        explanation.add_detail(self.weight.explain(reader, doc)?);
+        explanation.add_const("static rank x weight", static_ranks.score(doc));
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        self.weight.count(reader)
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        let static_ranks = StaticRankReader::open(reader, self.field, self.static_rank_weight)?;
+        self.weight
+            .for_each_pruning_with_static_ranks(threshold, reader, &static_ranks, callback)
+    }
+}
+
+/// Scorer adding the weighted static rank of the documents to the scores of
+/// an underlying scorer.
+pub(crate) struct StaticRankScorer<S: Scorer> {
+    underlying: S,
+    static_ranks: StaticRankReader,
+}
+
+impl<S: Scorer> StaticRankScorer<S> {
+    pub fn new(underlying: S, static_ranks: StaticRankReader) -> StaticRankScorer<S> {
+        StaticRankScorer {
+            underlying,
+            static_ranks,
+        }
+    }
+}
+
+impl<S: Scorer> DocSet for StaticRankScorer<S> {
+    fn advance(&mut self) -> DocId {
+        self.underlying.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl<S: Scorer> Scorer for StaticRankScorer<S> {
+    fn score(&mut self) -> Score {
+        self.underlying.score() + self.static_ranks.score(self.doc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StaticRankQuery;
+    use crate::assert_nearly_equals;
+    use crate::collector::TopDocs;
+    use crate::fastfield::FastFieldReader;
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{Field, IndexRecordOption, Schema, FAST, INDEXED, STRING, TEXT};
+    use crate::{DocAddress, DocSet, Index, IndexSettings, Score, TantivyError, Term, TERMINATED};
+    use futures::executor::block_on;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn create_index(static_rank_field: Option<&str>) -> crate::Result<(Index, Field, Field)> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let rank = schema_builder.add_f64_field("rank", FAST);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                static_rank_field: static_rank_field.map(ToString::to_string),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut rng = StdRng::from_seed([3u8; 32]);
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..2_000 {
+            let words: Vec<&str> = ["a", "b", "c"]
+                .iter()
+                .copied()
+                .filter(|_| rng.gen_bool(0.5))
+                .collect();
+            let doc_rank: f64 = if rng.gen_bool(0.01) {
+                rng.gen_range(0.0..100.0)
+            } else {
+                rng.gen_range(0.0..1.0)
+            };
+            index_writer.add_document(doc!(text => words.join(" "), rank => doc_rank))?;
+            if i == 999 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        Ok((index, text, rank))
+    }
+
+    fn term_query(text: Field, word: &str) -> Box<dyn Query> {
+        Box::new(TermQuery::new(
+            Term::from_field_text(text, word),
+            IndexRecordOption::WithFreqs,
+        ))
+    }
+
+    // Top docs computed by scoring all of the matching documents.
+    fn exhaustive_top_docs(
+        index: &Index,
+        query: &dyn Query,
+        limit: usize,
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let searcher = index.reader()?.searcher();
+        let weight = query.weight(&searcher, true)?;
+        let mut top_docs = Vec::new();
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let mut scorer = weight.scorer(segment_reader, 1.0)?;
+            while scorer.doc() != TERMINATED {
+                top_docs.push((
+                    scorer.score(),
+                    DocAddress::new(segment_ord as u32, scorer.doc()),
+                ));
+                scorer.advance();
+            }
+        }
+        top_docs.sort_by(|left, right| right.0.partial_cmp(&left.0).unwrap());
+        top_docs.truncate(limit);
+        Ok(top_docs)
+    }
+
+    fn check_top_docs(index: &Index, text: Field, rank: Field) -> crate::Result<()> {
+        let searcher = index.reader()?.searcher();
+        let queries: Vec<Box<dyn Query>> = vec![
+            term_query(text, "a"),
+            Box::new(BooleanQuery::new(vec![
+                (Occur::Should, term_query(text, "a")),
+                (Occur::Should, term_query(text, "b")),
+            ])),
+            Box::new(BooleanQuery::new(vec![
+                (Occur::Should, term_query(text, "a")),
+                (Occur::Should, term_query(text, "b")),
+                (Occur::MustNot, term_query(text, "c")),
+            ])),
+            Box::new(BooleanQuery::new(vec![
+                (Occur::Must, term_query(text, "a")),
+                (Occur::Should, term_query(text, "b")),
+            ])),
+        ];
+        for query in queries {
+            let query = StaticRankQuery::new(query, rank, 0.5);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            let expected_top_docs = exhaustive_top_docs(index, &query, 10)?;
+            assert_eq!(top_docs.len(), expected_top_docs.len());
+            for ((score, _), (expected_score, _)) in top_docs.iter().zip(expected_top_docs.iter()) {
+                assert_nearly_equals!(*score, *expected_score);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_static_rank_query_top_docs() -> crate::Result<()> {
+        let (index, text, rank) = create_index(Some("rank"))?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        for segment_reader in searcher.segment_readers() {
+            assert_eq!(segment_reader.static_rank_field(), Some(rank));
+        }
+        check_top_docs(&index, text, rank)?;
+
+        let segment_ids = index.searchable_segment_ids()?;
+        let mut index_writer = index.writer_for_tests()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(index.searchable_segment_metas()?.len(), 1);
+        check_top_docs(&index, text, rank)
+    }
+
+    #[test]
+    fn test_static_rank_query_without_block_max_static_ranks() -> crate::Result<()> {
+        let (index, text, rank) = create_index(None)?;
+        let searcher = index.reader()?.searcher();
+        for segment_reader in searcher.segment_readers() {
+            assert_eq!(segment_reader.static_rank_field(), None);
+        }
+        check_top_docs(&index, text, rank)
+    }
+
+    #[test]
+    fn test_block_max_static_rank() -> crate::Result<()> {
+        let (index, text, rank) = create_index(Some("rank"))?;
+        let searcher = index.reader()?.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let term_weight = term_query.specialized_weight(&searcher, true)?;
+        for segment_reader in searcher.segment_readers() {
+            let static_ranks = segment_reader.fast_fields().f64(rank)?;
+            let mut term_scorer = term_weight.specialized_scorer(segment_reader, 1.0)?;
+            while term_scorer.doc() != TERMINATED {
+                let last_doc_in_block = term_scorer.last_doc_in_block();
+                let block_max_static_rank = term_scorer.block_max_static_rank();
+                let mut max_static_rank = 0.0f64;
+                while term_scorer.doc() <= last_doc_in_block {
+                    max_static_rank = max_static_rank.max(static_ranks.get(term_scorer.doc()));
+                    if term_scorer.advance() == TERMINATED {
+                        break;
+                    }
+                }
+                if last_doc_in_block != TERMINATED {
+                    let block_max_static_rank = block_max_static_rank.map(common::u64_to_f64);
+                    assert_eq!(block_max_static_rank, Some(max_static_rank));
+                } else {
+                    assert_eq!(block_max_static_rank, None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_static_rank_query_invalid_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let indexed = schema_builder.add_u64_field("indexed", INDEXED);
+        let string = schema_builder.add_text_field("string", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        for field in vec![indexed, string] {
+            let query = StaticRankQuery::new(term_query(text, "a"), field, 1.0);
+            assert!(matches!(
+                query.weight(&searcher, true),
+                Err(TantivyError::SchemaError(_))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use crate::query::explanation::does_not_match;
+use crate::query::{EmptyScorer, Explanation, Query, Scorer, Weight};
+use crate::{DocId, DocSet, Score, Searcher, SegmentReader, TantivyError, Term, TERMINATED};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// `TenantQuery` restricts a query to the documents of a single tenant, in an index
+/// with a tenant field (see `IndexSettings::tenant_field`).
+///
+/// The documents of each segment are grouped by tenant: the segments without any
+/// document of the tenant are skipped without evaluating the query, and on the other
+/// segments the underlying query is only evaluated over the doc id range of the tenant.
+///
+/// The scores are the scores of the underlying query.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::{QueryParser, TenantQuery};
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, Index, IndexSettings};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let tenant = schema_builder.add_u64_field("tenant", FAST);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let settings = IndexSettings {
+///     tenant_field: Some("tenant".to_string()),
+///     ..Default::default()
+/// };
+/// let index = Index::builder()
+///     .schema(schema_builder.build())
+///     .settings(settings)
+///     .create_in_ram()?;
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(tenant => 2u64, body => "invoice paid"))?;
+/// index_writer.add_document(doc!(tenant => 1u64, body => "invoice sent"))?;
+/// index_writer.add_document(doc!(tenant => 2u64, body => "invoice sent"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![body]).parse_query("invoice")?;
+/// assert_eq!(searcher.search(&TenantQuery::new(2, query.box_clone()), &Count)?, 2);
+/// assert_eq!(searcher.search(&TenantQuery::new(3, query), &Count)?, 0);
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct TenantQuery {
+    tenant: u64,
+    query: Box<dyn Query>,
+}
+
+impl TenantQuery {
+    /// Creates a query matching the documents of `tenant` matched by `query`.
+    pub fn new(tenant: u64, query: Box<dyn Query>) -> TenantQuery {
+        TenantQuery { tenant, query }
+    }
+
+    /// Returns the tenant the query is restricted to.
+    pub fn tenant(&self) -> u64 {
+        self.tenant
+    }
+}
+
+impl Clone for TenantQuery {
+    fn clone(&self) -> Self {
+        TenantQuery {
+            tenant: self.tenant,
+            query: self.query.box_clone(),
+        }
+    }
+}
+
+impl fmt::Debug for TenantQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tenant(tenant={}, query={:?})", self.tenant, self.query)
+    }
+}
+
+impl Query for TenantQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> crate::Result<Box<dyn Weight>> {
+        if searcher.index().settings().tenant_field.is_none() {
+            return Err(TantivyError::InvalidArgument(
+                "The index does not have a tenant field.".to_string(),
+            ));
+        }
+        Ok(Box::new(TenantWeight {
+            tenant: self.tenant,
+            weight: self.query.weight(searcher, scoring_enabled)?,
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        self.query.query_terms(terms)
+    }
+
+    fn rewrite(&self, searcher: &Searcher) -> crate::Result<Option<Box<dyn Query>>> {
+        Ok(self
+            .query
+            .rewrite(searcher)?
+            .map(|query| Box::new(TenantQuery::new(self.tenant, query)) as Box<dyn Query>))
+    }
+}
+
+struct TenantWeight {
+    tenant: u64,
+    weight: Box<dyn Weight>,
+}
+
+impl Weight for TenantWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let doc_range = reader.tenant_doc_range(self.tenant)?;
+        if doc_range.start == doc_range.end {
+            return Ok(Box::new(EmptyScorer));
+        }
+        let scorer = self.weight.scorer(reader, boost)?;
+        Ok(Box::new(DocRangeScorer::new(
+            scorer,
+            doc_range.start,
+            doc_range.end,
+        )))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        if !reader.tenant_doc_range(self.tenant)?.contains(&doc) {
+            return Err(does_not_match(doc));
+        }
+        self.weight.explain(reader, doc)
+    }
+}
+
+/// Restricts a scorer to the doc ids within `[start, end)`.
+struct DocRangeScorer {
+    underlying: Box<dyn Scorer>,
+    end: DocId,
+    doc: DocId,
+}
+
+impl DocRangeScorer {
+    fn new(mut underlying: Box<dyn Scorer>, start: DocId, end: DocId) -> DocRangeScorer {
+        let doc = if underlying.doc() < start {
+            underlying.seek(start)
+        } else {
+            underlying.doc()
+        };
+        DocRangeScorer {
+            underlying,
+            end,
+            doc: if doc < end { doc } else { TERMINATED },
+        }
+    }
+
+    fn bound(&mut self, doc: DocId) -> DocId {
+        self.doc = if doc < self.end { doc } else { TERMINATED };
+        self.doc
+    }
+}
+
+impl DocSet for DocRangeScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        let doc = self.underlying.advance();
+        self.bound(doc)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc == TERMINATED || target >= self.end {
+            self.doc = TERMINATED;
+            return TERMINATED;
+        }
+        let doc = self.underlying.seek(target);
+        self.bound(doc)
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl Scorer for DocRangeScorer {
+    fn score(&mut self) -> Score {
+        self.underlying.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TenantQuery;
+    use crate::collector::Count;
+    use crate::query::{AllQuery, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, STRING};
+    use crate::{Index, IndexSettings, IndexSortByField, Order, TantivyError, Term};
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_tenant_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_u64_field("tenant", FAST);
+        let color = schema_builder.add_text_field("color", STRING);
+        let settings = IndexSettings {
+            tenant_field: Some("tenant".to_string()),
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..30u64 {
+            let color_value = if i % 2 == 0 { "red" } else { "blue" };
+            index_writer.add_document(doc!(tenant => i % 3, color => color_value))?;
+        }
+        index_writer.commit()?;
+        index_writer.add_document(doc!(tenant => 7u64, color => "red"))?;
+        index_writer.add_document(doc!(tenant => u64::MAX, color => "red"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // The documents are grouped by tenant within the segments.
+        let segment_ord = searcher
+            .segment_readers()
+            .iter()
+            .position(|segment_reader| segment_reader.max_doc() == 30)
+            .unwrap() as u32;
+        let segment_reader = searcher.segment_reader(segment_ord);
+        let ranges: Vec<_> = (0..4)
+            .map(|tenant| segment_reader.tenant_doc_range(tenant))
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(ranges, vec![0..10, 10..20, 20..30, 0..0]);
+        assert_eq!(
+            searcher
+                .segment_reader(1 - segment_ord)
+                .tenant_doc_range(u64::MAX)?,
+            1..2
+        );
+
+        let red_query = TermQuery::new(
+            Term::from_field_text(color, "red"),
+            IndexRecordOption::WithFreqs,
+        );
+        let count = |tenant: u64, query: &dyn Query| -> crate::Result<usize> {
+            searcher.search(&TenantQuery::new(tenant, query.box_clone()), &Count)
+        };
+        assert_eq!(count(0, &red_query)?, 5);
+        assert_eq!(count(1, &red_query)?, 5);
+        assert_eq!(count(7, &red_query)?, 1);
+        assert_eq!(count(u64::MAX, &red_query)?, 1);
+        assert_eq!(count(8, &red_query)?, 0);
+        assert_eq!(count(2, &AllQuery)?, 10);
+        // Explanations are given for the documents of the tenant only.
+        let query = TenantQuery::new(1, Box::new(red_query));
+        assert!(query
+            .explain(&searcher, crate::DocAddress::new(segment_ord, 11))
+            .is_ok());
+        assert!(query
+            .explain(&searcher, crate::DocAddress::new(segment_ord, 0))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tenant_query_merge() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_u64_field("tenant", FAST);
+        let settings = IndexSettings {
+            tenant_field: Some("tenant".to_string()),
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for &tenant_value in &[3u64, 1, 2, 1] {
+            index_writer.add_document(doc!(tenant => tenant_value))?;
+            index_writer.add_document(doc!(tenant => tenant_value))?;
+            index_writer.commit()?;
+        }
+        let segment_ids = index.searchable_segment_ids()?;
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let reader = index.reader()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.tenant_doc_range(1)?, 0..4);
+        assert_eq!(segment_reader.tenant_doc_range(2)?, 4..6);
+        assert_eq!(segment_reader.tenant_doc_range(3)?, 6..8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tenant_field_validation() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("tenant", FAST);
+        schema_builder.add_text_field("name", STRING);
+        let schema = schema_builder.build();
+        let create = |settings: IndexSettings| {
+            Index::builder()
+                .schema(schema.clone())
+                .settings(settings)
+                .create_in_ram()
+        };
+        assert!(matches!(
+            create(IndexSettings {
+                tenant_field: Some("name".to_string()),
+                ..Default::default()
+            }),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert!(matches!(
+            create(IndexSettings {
+                tenant_field: Some("missing".to_string()),
+                ..Default::default()
+            }),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            create(IndexSettings {
+                tenant_field: Some("tenant".to_string()),
+                sort_by_field: Some(IndexSortByField {
+                    field: "tenant".to_string(),
+                    order: Order::Desc,
+                }),
+                ..Default::default()
+            }),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+
+        // A `TenantQuery` requires a tenant field.
+        let index = create(IndexSettings::default()).unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        assert!(matches!(
+            searcher.search(&TenantQuery::new(1, Box::new(AllQuery)), &Count),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+    }
+}
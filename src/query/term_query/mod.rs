@@ -43,6 +43,79 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_term_query_positions_not_indexed() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(text_field => "a"))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqsAndPositions,
+        );
+        assert!(matches!(
+            term_query.weight(&searcher, true),
+            Err(crate::TantivyError::SchemaError(msg))
+            if msg == "Requested positions for a term query on field \"text\", which does not \
+            have positions indexed"
+        ));
+        // Positions are not read when scoring is disabled.
+        assert!(term_query.weight(&searcher, false).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_term_query_index_record_option() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let string_field = schema_builder.add_text_field("string", STRING);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(text_field => "a a", string_field => "a"))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let weight_record_option = |field, requested_option, scoring_enabled| {
+            let term_query = TermQuery::new(Term::from_field_text(field, "a"), requested_option);
+            assert_eq!(term_query.index_record_option(), requested_option);
+            let term_weight = term_query
+                .specialized_weight(&searcher, scoring_enabled)
+                .unwrap();
+            let term_scorer = term_weight
+                .specialized_scorer(searcher.segment_reader(0), 1.0)
+                .unwrap();
+            assert_eq!(term_scorer.doc(), 0);
+            (term_weight.index_record_option(), term_scorer.term_freq())
+        };
+        assert_eq!(
+            weight_record_option(text_field, IndexRecordOption::WithFreqs, true),
+            (IndexRecordOption::WithFreqs, 2)
+        );
+        assert_eq!(
+            weight_record_option(text_field, IndexRecordOption::WithFreqsAndPositions, true),
+            (IndexRecordOption::WithFreqsAndPositions, 2)
+        );
+        // Filters only read the doc ids.
+        assert_eq!(
+            weight_record_option(text_field, IndexRecordOption::WithFreqsAndPositions, false),
+            (IndexRecordOption::Basic, 1)
+        );
+        // The requested option is clamped to what was indexed.
+        assert_eq!(
+            weight_record_option(string_field, IndexRecordOption::WithFreqs, true),
+            (IndexRecordOption::Basic, 1)
+        );
+        Ok(())
+    }
+
     #[test]
     pub fn test_term_query_multiple_of_block_len() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
@@ -197,6 +270,20 @@ mod tests {
         {
             let explanation = term_query.explain(&searcher, DocAddress::new(0u32, 1u32))?;
             assert_nearly_equals!(explanation.value(), 0.6931472);
+            let descriptions: Vec<&str> = explanation
+                .details()
+                .iter()
+                .map(|detail| detail.description())
+                .collect();
+            assert_eq!(
+                descriptions,
+                &[
+                    "(K1+1)",
+                    "idf, computed as log(1 + (N - n + 0.5) / (n + 0.5))",
+                    "freq / (freq + k1 * (1 - b + b * dl / avgdl))"
+                ]
+            );
+            assert_eq!(explanation.details()[1].details().len(), 2);
         }
         {
             let explanation_err = term_query.explain(&searcher, DocAddress::new(0u32, 0u32));
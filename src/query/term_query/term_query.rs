@@ -82,6 +82,14 @@ impl TermQuery {
         &self.term
     }
 
+    /// Returns the detail of the postings requested by the query.
+    ///
+    /// The scorers only read as much as the field was indexed with, and only
+    /// the doc ids if scoring is disabled.
+    pub fn index_record_option(&self) -> IndexRecordOption {
+        self.index_record_option
+    }
+
     /// Returns a weight object.
     ///
     /// While `.weight(...)` returns a boxed trait object,
@@ -97,13 +105,27 @@ impl TermQuery {
             let error_msg = format!("Field {:?} is not indexed.", field_entry.name());
             return Err(crate::TantivyError::SchemaError(error_msg));
         }
+        let indexed_record_option = field_entry
+            .field_type()
+            .get_index_record_option()
+            .unwrap_or(IndexRecordOption::Basic);
+        if scoring_enabled
+            && self.index_record_option.has_positions()
+            && !indexed_record_option.has_positions()
+        {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Requested positions for a term query on field {:?}, which does not have \
+                 positions indexed",
+                field_entry.name()
+            )));
+        }
         let bm25_weight = if scoring_enabled {
             Bm25Weight::for_terms(searcher, &[self.term.clone()])?
         } else {
             Bm25Weight::new(Explanation::new("<no score>".to_string(), 1.0f32), 1.0f32)
         };
         let index_record_option = if scoring_enabled {
-            self.index_record_option
+            self.index_record_option.downgrade(indexed_record_option)
         } else {
             IndexRecordOption::Basic
         };
@@ -123,6 +145,6 @@ impl Query for TermQuery {
         ))
     }
     fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
-        terms.insert(self.term.clone(), false);
+        terms.entry(self.term.clone()).or_insert(false);
     }
 }
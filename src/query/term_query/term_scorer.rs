@@ -98,6 +98,14 @@ impl TermScorer {
     pub fn last_doc_in_block(&self) -> DocId {
         self.postings.block_cursor.skip_reader.last_doc_in_block()
     }
+
+    /// Returns the maximum static rank of the documents of the current block,
+    /// if the segment records it.
+    ///
+    /// See [`StaticRankQuery`](crate::query::StaticRankQuery).
+    pub fn block_max_static_rank(&self) -> Option<u64> {
+        self.postings.block_cursor.block_max_static_rank()
+    }
 }
 
 impl DocSet for TermScorer {
@@ -109,6 +117,10 @@ impl DocSet for TermScorer {
         self.postings.seek(target)
     }
 
+    fn buffered_docs(&self) -> &[DocId] {
+        self.postings.buffered_docs()
+    }
+
     fn doc(&self) -> DocId {
         self.postings.doc()
     }
@@ -7,7 +7,7 @@ use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
 use crate::query::weight::for_each_scorer;
 use crate::query::Weight;
-use crate::query::{Explanation, Scorer};
+use crate::query::{Explanation, Scorer, StaticRankReader};
 use crate::schema::IndexRecordOption;
 use crate::Term;
 use crate::{DocId, Score};
@@ -40,7 +40,9 @@ impl Weight for TermWeight {
     }
 
     fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
-        if let Some(alive_bitset) = reader.alive_bitset() {
+        if !self.may_match(reader) {
+            Ok(0)
+        } else if let Some(alive_bitset) = reader.alive_bitset() {
             Ok(self.scorer(reader, 1.0)?.count(alive_bitset))
         } else {
             let field = self.term.field();
@@ -79,7 +81,24 @@ impl Weight for TermWeight {
         callback: &mut dyn FnMut(DocId, Score) -> Score,
     ) -> crate::Result<()> {
         let scorer = self.specialized_scorer(reader, 1.0)?;
-        crate::query::boolean_query::block_wand_single_scorer(scorer, threshold, callback);
+        crate::query::boolean_query::block_wand_single_scorer(scorer, threshold, None, callback);
+        Ok(())
+    }
+
+    fn for_each_pruning_with_static_ranks(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        static_ranks: &StaticRankReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        let scorer = self.specialized_scorer(reader, 1.0)?;
+        crate::query::boolean_query::block_wand_single_scorer(
+            scorer,
+            threshold,
+            Some(static_ranks),
+            callback,
+        );
         Ok(())
     }
 }
@@ -99,12 +118,36 @@ impl TermWeight {
         }
     }
 
+    /// Returns the detail of the postings read by the scorers of this weight.
+    ///
+    /// It is the option requested by the query, clamped to the option the field
+    /// was indexed with, or `IndexRecordOption::Basic` if scoring is disabled.
+    pub fn index_record_option(&self) -> IndexRecordOption {
+        self.index_record_option
+    }
+
+    /// Returns false if the field bounds of the segment rule the term out.
+    fn may_match(&self, reader: &SegmentReader) -> bool {
+        reader
+            .field_bounds(self.term.field())
+            .map(|field_bounds| field_bounds.may_contain_term(self.term.value_bytes()))
+            .unwrap_or(true)
+    }
+
     pub(crate) fn specialized_scorer(
         &self,
         reader: &SegmentReader,
         boost: Score,
     ) -> crate::Result<TermScorer> {
         let field = self.term.field();
+        let similarity_weight = self.similarity_weight.boost_by(boost);
+        if !self.may_match(reader) {
+            return Ok(TermScorer::new(
+                SegmentPostings::empty(),
+                FieldNormReader::constant(reader.max_doc(), 1),
+                similarity_weight,
+            ));
+        }
         let inverted_index = reader.inverted_index(field)?;
         let fieldnorm_reader_opt = if self.scoring_enabled {
             reader.fieldnorms_readers().get_field(field)?
@@ -113,7 +156,6 @@ impl TermWeight {
         };
         let fieldnorm_reader =
             fieldnorm_reader_opt.unwrap_or_else(|| FieldNormReader::constant(reader.max_doc(), 1));
-        let similarity_weight = self.similarity_weight.boost_by(boost);
         let postings_opt: Option<SegmentPostings> =
             inverted_index.read_postings(&self.term, self.index_record_option)?;
         if let Some(segment_postings) = postings_opt {
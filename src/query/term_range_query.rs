@@ -0,0 +1,296 @@
+use crate::core::Searcher;
+use crate::query::range_query::{map_bound, RangeWeight};
+use crate::query::{Query, Weight};
+use crate::schema::{Field, Term};
+use crate::termdict::{TermDictionary, TermStreamer};
+use std::io;
+use std::ops::{Bound, Range};
+
+/// Returns the smallest key greater than all of the keys starting with `prefix`.
+fn prefix_upper_bound(prefix: &[u8]) -> Bound<Vec<u8>> {
+    let mut upper = prefix.to_vec();
+    while let Some(last_byte) = upper.pop() {
+        if last_byte != u8::MAX {
+            upper.push(last_byte + 1);
+            return Bound::Excluded(upper);
+        }
+    }
+    Bound::Unbounded
+}
+
+/// `TermRangeQuery` matches all documents that have at least one term within a
+/// lexicographic range of raw term bytes.
+///
+/// Unlike [`RangeQuery`](./struct.RangeQuery.html), the bounds are not interpreted
+/// according to the type of the field: they are compared to the bytes of the terms
+/// as stored in the term dictionary. This makes it possible to search for ranges of
+/// keyword ids, or for all of the terms starting with a given prefix. Numeric values
+/// are encoded in a way that preserves their order, so the bounds can also be built
+/// from numeric terms, e.g. with `Term::from_field_u64`.
+///
+/// Matched documents all get a constant `Score` of one.
+///
+/// The terms of the range within a segment can also be streamed, in increasing or
+/// decreasing order, with [`TermRangeQuery::term_stream`](#method.term_stream).
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::TermRangeQuery;
+/// use tantivy::schema::{Schema, STRING};
+/// use tantivy::{doc, Index};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let id_field = schema_builder.add_text_field("id", STRING);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for id in 0..100 {
+///     index_writer.add_document(doc!(id_field => format!("order-{:03}", id)))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = TermRangeQuery::new_str(id_field, "order-010".."order-020");
+/// assert_eq!(searcher.search(&query, &Count)?, 10);
+/// let query = TermRangeQuery::prefix(id_field, b"order-09");
+/// assert_eq!(searcher.search(&query, &Count)?, 10);
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct TermRangeQuery {
+    field: Field,
+    lower_bound: Bound<Vec<u8>>,
+    upper_bound: Bound<Vec<u8>>,
+    reversed: bool,
+}
+
+impl TermRangeQuery {
+    /// Creates a new `TermRangeQuery` from bounds on the raw bytes of the terms.
+    pub fn new(
+        field: Field,
+        lower_bound: Bound<Vec<u8>>,
+        upper_bound: Bound<Vec<u8>>,
+    ) -> TermRangeQuery {
+        TermRangeQuery {
+            field,
+            lower_bound,
+            upper_bound,
+            reversed: false,
+        }
+    }
+
+    /// Creates a new `TermRangeQuery` from bounded start and end terms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the terms does not belong to `field`.
+    pub fn new_term_bounds(
+        field: Field,
+        lower_bound: &Bound<Term>,
+        upper_bound: &Bound<Term>,
+    ) -> TermRangeQuery {
+        let verify_and_unwrap_term = |term: &Term| {
+            assert_eq!(field, term.field());
+            term.value_bytes().to_owned()
+        };
+        TermRangeQuery::new(
+            field,
+            map_bound(lower_bound, &verify_and_unwrap_term),
+            map_bound(upper_bound, &verify_and_unwrap_term),
+        )
+    }
+
+    /// Creates a new `TermRangeQuery` over the terms of a text field
+    /// within `range`.
+    pub fn new_str(field: Field, range: Range<&str>) -> TermRangeQuery {
+        TermRangeQuery::new(
+            field,
+            Bound::Included(range.start.as_bytes().to_vec()),
+            Bound::Excluded(range.end.as_bytes().to_vec()),
+        )
+    }
+
+    /// Creates a new `TermRangeQuery` over the terms starting with `prefix`.
+    pub fn prefix(field: Field, prefix: &[u8]) -> TermRangeQuery {
+        TermRangeQuery::new(
+            field,
+            Bound::Included(prefix.to_vec()),
+            prefix_upper_bound(prefix),
+        )
+    }
+
+    /// Sets whether [`TermRangeQuery::term_stream`](#method.term_stream) streams
+    /// the terms in decreasing order.
+    ///
+    /// The documents matched by the query do not depend on this order.
+    ///
+    /// Defaults to `false`.
+    pub fn with_reversed(mut self, reversed: bool) -> TermRangeQuery {
+        self.reversed = reversed;
+        self
+    }
+
+    /// Field to search over
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Lower bound of range
+    pub fn lower_bound(&self) -> Bound<Term> {
+        map_bound(&self.lower_bound, &|bytes| {
+            Term::from_field_bytes(self.field, bytes)
+        })
+    }
+
+    /// Upper bound of range
+    pub fn upper_bound(&self) -> Bound<Term> {
+        map_bound(&self.upper_bound, &|bytes| {
+            Term::from_field_bytes(self.field, bytes)
+        })
+    }
+
+    /// Returns whether the terms are streamed in decreasing order.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Streams the terms of `term_dict` within the range, in decreasing order
+    /// if the query is reversed.
+    ///
+    /// `term_dict` is expected to be the term dictionary of the field of the query
+    /// within a segment.
+    pub fn term_stream<'a>(&self, term_dict: &'a TermDictionary) -> io::Result<TermStreamer<'a>> {
+        let mut term_stream_builder = term_dict.range();
+        term_stream_builder = match self.lower_bound {
+            Bound::Included(ref term_val) => term_stream_builder.ge(term_val),
+            Bound::Excluded(ref term_val) => term_stream_builder.gt(term_val),
+            Bound::Unbounded => term_stream_builder,
+        };
+        term_stream_builder = match self.upper_bound {
+            Bound::Included(ref term_val) => term_stream_builder.le(term_val),
+            Bound::Excluded(ref term_val) => term_stream_builder.lt(term_val),
+            Bound::Unbounded => term_stream_builder,
+        };
+        if self.reversed {
+            term_stream_builder = term_stream_builder.backward();
+        }
+        term_stream_builder.into_stream()
+    }
+}
+
+impl Query for TermRangeQuery {
+    fn weight(
+        &self,
+        _searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(RangeWeight::for_term_range(
+            self.field,
+            self.lower_bound.clone(),
+            self.upper_bound.clone(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prefix_upper_bound, TermRangeQuery};
+    use crate::collector::Count;
+    use crate::schema::{Schema, Term, INDEXED, STRING};
+    use crate::{Index, Searcher};
+    use std::ops::Bound;
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound(b"abc"), Bound::Excluded(b"abd".to_vec()));
+        assert_eq!(
+            prefix_upper_bound(&[1u8, 255u8, 255u8]),
+            Bound::Excluded(vec![2u8])
+        );
+        assert_eq!(prefix_upper_bound(&[255u8, 255u8]), Bound::Unbounded);
+        assert_eq!(prefix_upper_bound(b""), Bound::Unbounded);
+    }
+
+    #[test]
+    fn test_term_range_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let num_field = schema_builder.add_u64_field("num", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for id in 0..100u64 {
+            index_writer.add_document(doc!(
+                id_field => format!("id-{:03}", id),
+                num_field => id * 1_000,
+            ))?;
+        }
+        index_writer.add_document(doc!(id_field => "other"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let count = |query: &TermRangeQuery| searcher.search(query, &Count).unwrap();
+
+        assert_eq!(
+            count(&TermRangeQuery::new_str(id_field, "id-010".."id-020")),
+            10
+        );
+        assert_eq!(
+            count(&TermRangeQuery::new(
+                id_field,
+                Bound::Excluded(b"id-090".to_vec()),
+                Bound::Unbounded
+            )),
+            10
+        );
+        assert_eq!(count(&TermRangeQuery::prefix(id_field, b"id-05")), 10);
+        assert_eq!(count(&TermRangeQuery::prefix(id_field, b"id-")), 100);
+        assert_eq!(count(&TermRangeQuery::prefix(id_field, b"")), 101);
+        assert_eq!(count(&TermRangeQuery::prefix(id_field, b"missing")), 0);
+
+        // The bounds can be built from numeric terms.
+        let query = TermRangeQuery::new_term_bounds(
+            num_field,
+            &Bound::Included(Term::from_field_u64(num_field, 2_000)),
+            &Bound::Included(Term::from_field_u64(num_field, 10_000)),
+        );
+        assert_eq!(count(&query), 9);
+        Ok(())
+    }
+
+    fn streamed_terms(searcher: &Searcher, query: &TermRangeQuery) -> crate::Result<Vec<String>> {
+        let inverted_index = searcher.segment_reader(0).inverted_index(query.field())?;
+        let mut term_stream = query.term_stream(inverted_index.terms())?;
+        let mut terms = Vec::new();
+        while term_stream.try_advance()? {
+            terms.push(String::from_utf8(term_stream.key().to_vec()).unwrap());
+        }
+        Ok(terms)
+    }
+
+    #[test]
+    fn test_term_range_query_term_stream() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for id in &["a", "b", "c", "d", "e"] {
+            index_writer.add_document(doc!(id_field => *id))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = TermRangeQuery::new(
+            id_field,
+            Bound::Excluded(b"a".to_vec()),
+            Bound::Included(b"d".to_vec()),
+        );
+        assert!(!query.is_reversed());
+        assert_eq!(streamed_terms(&searcher, &query)?, vec!["b", "c", "d"]);
+        let query = query.with_reversed(true);
+        assert_eq!(streamed_terms(&searcher, &query)?, vec!["d", "c", "b"]);
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::core::Searcher;
+use crate::core::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::ConstScorer;
+use crate::query::{BitSetDocSet, Explanation};
+use crate::query::{Query, Scorer, Weight};
+use crate::schema::{Field, IndexRecordOption, Term};
+use crate::{DocId, Score};
+use common::BitSet;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `TermSetQuery` matches all documents containing at least one of a set of terms.
+///
+/// Matched documents all get a constant `Score` of one.
+///
+/// It is a cheaper alternative to a boolean union of `TermQuery`s when the set of
+/// terms is large, e.g. to filter the documents on a list of ids: the documents of
+/// all of the terms are appended into a `BitSet`, without scoring them.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::TermSetQuery;
+/// use tantivy::schema::{Schema, STRING};
+/// use tantivy::{doc, Index, Term};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let id_field = schema_builder.add_text_field("id", STRING);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for id in &["a", "b", "c", "d"] {
+///     index_writer.add_document(doc!(id_field => *id))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = TermSetQuery::new(vec![
+///     Term::from_field_text(id_field, "a"),
+///     Term::from_field_text(id_field, "c"),
+///     Term::from_field_text(id_field, "z"),
+/// ]);
+/// assert_eq!(searcher.search(&query, &Count)?, 2);
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct TermSetQuery {
+    terms_per_field: BTreeMap<Field, BTreeSet<Term>>,
+}
+
+impl TermSetQuery {
+    /// Creates a new `TermSetQuery` matching the documents containing any of `terms`.
+    ///
+    /// The terms may belong to different fields.
+    pub fn new<T: IntoIterator<Item = Term>>(terms: T) -> TermSetQuery {
+        let mut terms_per_field: BTreeMap<Field, BTreeSet<Term>> = BTreeMap::new();
+        for term in terms {
+            terms_per_field
+                .entry(term.field())
+                .or_default()
+                .insert(term);
+        }
+        TermSetQuery { terms_per_field }
+    }
+}
+
+impl Query for TermSetQuery {
+    fn weight(
+        &self,
+        _searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(TermSetWeight {
+            terms_per_field: self.terms_per_field.clone(),
+        }))
+    }
+
+    fn query_terms(&self, terms: &mut BTreeMap<Term, bool>) {
+        for term in self.terms_per_field.values().flatten() {
+            terms.entry(term.clone()).or_insert(false);
+        }
+    }
+}
+
+struct TermSetWeight {
+    terms_per_field: BTreeMap<Field, BTreeSet<Term>>,
+}
+
+impl Weight for TermSetWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let mut doc_bitset = BitSet::with_max_value(reader.max_doc());
+        for (&field, terms) in &self.terms_per_field {
+            let inverted_index = reader.inverted_index(field)?;
+            for term in terms {
+                let term_info = if let Some(term_info) = inverted_index.get_term_info(term)? {
+                    term_info
+                } else {
+                    continue;
+                };
+                let mut block_segment_postings = inverted_index
+                    .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)?;
+                loop {
+                    let docs = block_segment_postings.docs();
+                    if docs.is_empty() {
+                        break;
+                    }
+                    for &doc in docs {
+                        doc_bitset.insert(doc);
+                    }
+                    block_segment_postings.advance();
+                }
+            }
+        }
+        let doc_bitset = BitSetDocSet::from(doc_bitset);
+        Ok(Box::new(ConstScorer::new(doc_bitset, boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("TermSetQuery", 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermSetQuery;
+    use crate::collector::{Count, DocSetCollector};
+    use crate::query::Query;
+    use crate::schema::{Schema, INDEXED, STRING};
+    use crate::{DocAddress, Index, Term};
+    use std::collections::{BTreeMap, HashSet};
+
+    #[test]
+    fn test_term_set_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING);
+        let num = schema_builder.add_u64_field("num", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(id => format!("id{}", i), num => i % 3))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let id_query = TermSetQuery::new(vec![
+            Term::from_field_text(id, "id1"),
+            Term::from_field_text(id, "id8"),
+            Term::from_field_text(id, "id1"),
+            Term::from_field_text(id, "missing"),
+        ]);
+        let docs = searcher.search(&id_query, &DocSetCollector)?;
+        let expected_docs: HashSet<DocAddress> = vec![DocAddress::new(0, 1), DocAddress::new(0, 8)]
+            .into_iter()
+            .collect();
+        assert_eq!(docs, expected_docs);
+
+        // Documents matching several terms of the set are counted once.
+        let query = TermSetQuery::new(vec![
+            Term::from_field_u64(num, 0),
+            Term::from_field_text(id, "id0"),
+            Term::from_field_text(id, "id1"),
+        ]);
+        assert_eq!(searcher.search(&query, &Count)?, 5);
+        assert_eq!(searcher.search(&TermSetQuery::new(vec![]), &Count)?, 0);
+
+        let mut query_terms = BTreeMap::new();
+        query.query_terms(&mut query_terms);
+        assert_eq!(query_terms.len(), 3);
+        Ok(())
+    }
+}
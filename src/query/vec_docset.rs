@@ -1,14 +1,10 @@
-#![allow(dead_code)]
-
 use crate::docset::{DocSet, TERMINATED};
 use crate::DocId;
 use common::HasLen;
 
-/// Simulate a `Postings` objects from a `VecPostings`.
-/// `VecPostings` only exist for testing purposes.
+/// A `DocSet` iterating through a sorted vector of `DocId`s.
 ///
-/// Term frequencies always return 1.
-/// No positions are returned.
+/// The doc ids are expected to be strictly increasing.
 pub struct VecDocSet {
     doc_ids: Vec<DocId>,
     cursor: usize,
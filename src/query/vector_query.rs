@@ -0,0 +1,398 @@
+use crate::query::explanation::does_not_match;
+use crate::query::{Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, DocSet, Score, Searcher, SegmentReader, TantivyError, TERMINATED};
+use common::BitSet;
+use std::fmt;
+
+/// Default number of lists of the IVF index visited by a `VectorQuery`.
+const DEFAULT_NUM_PROBES: usize = 8;
+
+/// `VectorQuery` matches the `top_k` documents whose vectors are the closest
+/// to a query vector, scored by the similarity of their vectors.
+///
+/// The search is approximate: only the `num_probes` lists of the IVF index of each segment
+/// whose centroids are the closest to the query vector are visited. Increasing
+/// `num_probes` improves the recall, at the cost of visiting more vectors.
+///
+/// The `top_k` closest documents are searched in each segment, so the query can match up to
+/// `top_k` documents per segment: collect it with a `TopDocs` collector of limit `top_k`
+/// to get the `top_k` closest documents of the index.
+///
+/// An optional filter query restricts the search to the documents it matches.
+/// The filter is applied while the vectors are visited, so that the query still
+/// returns `top_k` documents if enough of them match the filter.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::VectorQuery;
+/// use tantivy::schema::{Schema, Value, VectorOptions};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(50_000_000)?;
+/// index_writer.add_document(doc!(embedding => Value::Vector(vec![1.0f32, 0.0])))?;
+/// index_writer.add_document(doc!(embedding => Value::Vector(vec![0.0f32, 1.0])))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = VectorQuery::new(embedding, vec![0.9, 0.1], 1);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs.len(), 1);
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct VectorQuery {
+    field: Field,
+    vector: Vec<f32>,
+    top_k: usize,
+    num_probes: usize,
+    filter: Option<Box<dyn Query>>,
+}
+
+impl VectorQuery {
+    /// Creates a query matching the `top_k` documents whose vectors
+    /// in `field` are the closest to `vector`.
+    pub fn new(field: Field, vector: Vec<f32>, top_k: usize) -> VectorQuery {
+        VectorQuery {
+            field,
+            vector,
+            top_k,
+            num_probes: DEFAULT_NUM_PROBES,
+            filter: None,
+        }
+    }
+
+    /// Sets the number of lists of the IVF index visited in each segment.
+    pub fn with_num_probes(mut self, num_probes: usize) -> VectorQuery {
+        self.num_probes = num_probes;
+        self
+    }
+
+    /// Restricts the search to the documents matching `filter`.
+    pub fn with_filter(mut self, filter: Box<dyn Query>) -> VectorQuery {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl Clone for VectorQuery {
+    fn clone(&self) -> Self {
+        VectorQuery {
+            field: self.field,
+            vector: self.vector.clone(),
+            top_k: self.top_k,
+            num_probes: self.num_probes,
+            filter: self.filter.as_ref().map(|filter| filter.box_clone()),
+        }
+    }
+}
+
+impl fmt::Debug for VectorQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Vector(field={:?}, top_k={}, num_probes={}, filter={:?})",
+            self.field, self.top_k, self.num_probes, self.filter
+        )
+    }
+}
+
+impl Query for VectorQuery {
+    fn weight(
+        &self,
+        searcher: &Searcher,
+        _scoring_enabled: bool,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        let dimension = match field_entry.field_type() {
+            FieldType::Vector(vector_options) => vector_options.dimension(),
+            _ => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} is not a vector field.",
+                    field_entry.name()
+                )));
+            }
+        };
+        if self.vector.len() != dimension {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Expected a query vector of dimension {} for the field {:?}, got {}",
+                dimension,
+                field_entry.name(),
+                self.vector.len()
+            )));
+        }
+        let filter_weight = self
+            .filter
+            .as_ref()
+            .map(|filter| filter.weight(searcher, false))
+            .transpose()?;
+        Ok(Box::new(VectorWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            top_k: self.top_k,
+            num_probes: self.num_probes,
+            filter_weight,
+        }))
+    }
+}
+
+struct VectorWeight {
+    field: Field,
+    vector: Vec<f32>,
+    top_k: usize,
+    num_probes: usize,
+    filter_weight: Option<Box<dyn Weight>>,
+}
+
+impl VectorWeight {
+    /// Returns the alive documents matching the filter, or `None` if there is no filter.
+    fn filter_docs(&self, reader: &SegmentReader) -> crate::Result<Option<BitSet>> {
+        let filter_weight = if let Some(filter_weight) = self.filter_weight.as_ref() {
+            filter_weight
+        } else {
+            return Ok(None);
+        };
+        let mut bitset = BitSet::with_max_value(reader.max_doc());
+        let mut scorer = filter_weight.scorer(reader, 1.0)?;
+        while scorer.doc() != TERMINATED {
+            let doc = scorer.doc();
+            if !reader.is_deleted(doc) {
+                bitset.insert(doc);
+            }
+            scorer.advance();
+        }
+        Ok(Some(bitset))
+    }
+
+    fn vector_scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<VectorScorer> {
+        let vector_reader = reader.vector_reader(self.field)?;
+        let filter_docs = self.filter_docs(reader)?;
+        let mut hits = vector_reader.search(&self.vector, self.top_k, self.num_probes, &|doc| {
+            if let Some(filter_docs) = filter_docs.as_ref() {
+                filter_docs.contains(doc)
+            } else {
+                !reader.is_deleted(doc)
+            }
+        });
+        hits.sort_unstable_by_key(|(doc, _)| *doc);
+        Ok(VectorScorer {
+            hits,
+            cursor: 0,
+            boost,
+        })
+    }
+}
+
+impl Weight for VectorWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(Box::new(self.vector_scorer(reader, boost)?))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.vector_scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("VectorQuery similarity", scorer.score()))
+    }
+}
+
+/// Scorer over the `(doc, similarity)` of the nearest neighbors found in a segment.
+struct VectorScorer {
+    hits: Vec<(DocId, Score)>,
+    cursor: usize,
+    boost: Score,
+}
+
+impl DocSet for VectorScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.hits.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.hits
+            .get(self.cursor)
+            .map(|(doc, _)| *doc)
+            .unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.hits.len() as u32
+    }
+}
+
+impl Scorer for VectorScorer {
+    fn score(&mut self) -> Score {
+        self.hits[self.cursor].1 * self.boost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VectorQuery;
+    use crate::collector::TopDocs;
+    use crate::query::{Query, TermQuery};
+    use crate::schema::{
+        IndexRecordOption, Schema, Value, VectorOptions, VectorSimilarity, STORED, STRING,
+    };
+    use crate::{doc, DocAddress, Index, IndexSettings, IndexSortByField, Order, Term};
+
+    #[test]
+    fn test_vector_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let category = schema_builder.add_text_field("category", STRING);
+        let embedding = schema_builder.add_vector_field(
+            "embedding",
+            VectorOptions::new(2)
+                .set_similarity(VectorSimilarity::Euclidean)
+                .set_stored(),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..1_000u32 {
+            let category_val = if i % 2 == 0 { "even" } else { "odd" };
+            index_writer.add_document(doc!(
+                category => category_val,
+                embedding => Value::Vector(vec![(i % 40) as f32, (i / 40) as f32]),
+            ))?;
+        }
+        index_writer.add_document(doc!(category => "even"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        let vector_reader = segment_reader.vector_reader(embedding)?;
+        assert_eq!(vector_reader.num_vectors(), 1_000);
+        assert_eq!(vector_reader.vector(41), Some(vec![1.0, 1.0]));
+        assert_eq!(vector_reader.vector(1_000), None);
+
+        let query = VectorQuery::new(embedding, vec![10.1, 10.2], 3).with_num_probes(100);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(3))?;
+        let docs: Vec<u32> = top_docs.iter().map(|(_, doc)| doc.doc_id).collect();
+        assert_eq!(docs, vec![410, 450, 411]);
+        let stored_doc = searcher.doc(DocAddress::new(0, 410))?;
+        assert_eq!(
+            stored_doc.get_first(embedding),
+            Some(&Value::Vector(vec![10.0, 10.0]))
+        );
+
+        let odd_query = TermQuery::new(
+            Term::from_field_text(category, "odd"),
+            IndexRecordOption::Basic,
+        );
+        let filtered_query = query.with_filter(Box::new(odd_query));
+        let top_docs = searcher.search(&filtered_query, &TopDocs::with_limit(3))?;
+        let docs: Vec<u32> = top_docs.iter().map(|(_, doc)| doc.doc_id).collect();
+        assert_eq!(docs, vec![411, 409, 451]);
+        let explanation = filtered_query
+            .weight(&searcher, true)?
+            .explain(segment_reader, 411)?;
+        assert_eq!(explanation.value(), top_docs[0].0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_query_after_merge() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING | STORED);
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(3));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer
+            .add_document(doc!(id => "a", embedding => Value::Vector(vec![1.0f32, 0.0, 0.0])))?;
+        index_writer.add_document(doc!(id => "b"))?;
+        index_writer.commit()?;
+        index_writer
+            .add_document(doc!(id => "c", embedding => Value::Vector(vec![0.0f32, 1.0, 0.0])))?;
+        index_writer
+            .add_document(doc!(id => "d", embedding => Value::Vector(vec![0.5f32, 0.5, 0.0])))?;
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(id, "d"));
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        futures::executor::block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        let vector_reader = segment_reader.vector_reader(embedding)?;
+        assert_eq!(vector_reader.num_vectors(), 2);
+        let stored_id = |doc_address: DocAddress| -> crate::Result<String> {
+            let doc = searcher.doc(doc_address)?;
+            Ok(doc.get_first(id).and_then(Value::text).unwrap().to_string())
+        };
+        for doc in segment_reader.doc_ids_alive() {
+            let expected_vector = match stored_id(DocAddress::new(0, doc))?.as_str() {
+                "a" => Some(vec![1.0, 0.0, 0.0]),
+                "c" => Some(vec![0.0, 1.0, 0.0]),
+                _ => None,
+            };
+            assert_eq!(vector_reader.vector(doc), expected_vector);
+        }
+
+        let query = VectorQuery::new(embedding, vec![0.0, 2.0, 0.1], 10);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let ids: Vec<String> = top_docs
+            .iter()
+            .map(|(_, doc_address)| stored_id(*doc_address))
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(ids, vec!["c".to_string(), "a".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_sorted_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let rank = schema_builder.add_u64_field("rank", crate::schema::FAST);
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(1));
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "rank".to_string(),
+                    order: Order::Desc,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(rank => 1u64, embedding => Value::Vector(vec![1.0f32])))?;
+        index_writer.add_document(doc!(rank => 2u64))?;
+        index_writer.add_document(doc!(rank => 3u64, embedding => Value::Vector(vec![3.0f32])))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let vector_reader = searcher.segment_reader(0).vector_reader(embedding)?;
+        assert_eq!(vector_reader.vector(0), Some(vec![3.0]));
+        assert_eq!(vector_reader.vector(1), None);
+        assert_eq!(vector_reader.vector(2), Some(vec![1.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_dimension_mismatch() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = VectorQuery::new(embedding, vec![1.0], 1);
+        assert!(matches!(
+            searcher.search(&query, &TopDocs::with_limit(1)),
+            Err(crate::TantivyError::InvalidArgument(_))
+        ));
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(embedding => Value::Vector(vec![1.0f32, 2.0, 3.0])))?;
+        assert!(index_writer.commit().is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 use super::Scorer;
 use crate::core::SegmentReader;
-use crate::query::Explanation;
+use crate::query::static_rank_query::StaticRankScorer;
+use crate::query::{Explanation, StaticRankReader};
 use crate::{DocId, Score, TERMINATED};
+#[cfg(feature = "roaring")]
+use roaring::RoaringBitmap;
 
 /// Iterates through all of the document matched by the DocSet
 /// `DocSet` and push the scored documents to the collector.
@@ -66,6 +69,33 @@ pub trait Weight: Send + Sync + 'static {
         }
     }
 
+    /// Returns the alive documents matched within the given `SegmentReader`,
+    /// as a compressed bitmap.
+    ///
+    /// The bitmaps of different queries on the same segment can be combined with
+    /// set operations, and iterated again as a `DocSet` with a
+    /// [`RoaringDocSet`](./struct.RoaringDocSet.html), e.g. to cache filters.
+    ///
+    /// Requires the `roaring` feature.
+    #[cfg(feature = "roaring")]
+    fn docset_bitmap(&self, reader: &SegmentReader) -> crate::Result<RoaringBitmap> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        let alive_bitset_opt = reader.alive_bitset();
+        let mut bitmap = RoaringBitmap::new();
+        let mut doc = scorer.doc();
+        while doc != TERMINATED {
+            let is_alive = match alive_bitset_opt {
+                Some(alive_bitset) => alive_bitset.is_alive(doc),
+                None => true,
+            };
+            if is_alive {
+                bitmap.push(doc);
+            }
+            doc = scorer.advance();
+        }
+        Ok(bitmap)
+    }
+
     /// Iterates through all of the document matched by the DocSet
     /// `DocSet` and push the scored documents to the collector.
     fn for_each(
@@ -98,4 +128,21 @@ pub trait Weight: Send + Sync + 'static {
         for_each_pruning_scorer(scorer.as_mut(), threshold, callback);
         Ok(())
     }
+
+    /// Same as `for_each_pruning`, except that the weighted static ranks
+    /// of `static_ranks` are added to the scores.
+    ///
+    /// See [`StaticRankQuery`](./struct.StaticRankQuery.html).
+    fn for_each_pruning_with_static_ranks(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        static_ranks: &StaticRankReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        let scorer = self.scorer(reader, 1.0)?;
+        let mut scorer = StaticRankScorer::new(scorer, static_ranks.clone());
+        for_each_pruning_scorer(&mut scorer, threshold, callback);
+        Ok(())
+    }
 }
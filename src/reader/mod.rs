@@ -1,15 +1,26 @@
+mod pinned_searcher;
 mod pool;
+mod warming;
 
+use self::pinned_searcher::InnerPinnedSearcher;
+pub use self::pinned_searcher::PinnedSearcher;
 pub use self::pool::LeasedItem;
 use self::pool::Pool;
-use crate::core::Segment;
+pub use self::warming::Warmer;
+use crate::core::{check_segment, GlobalOrdinalsCache, Segment, SkipReason};
 use crate::directory::WatchHandle;
 use crate::directory::META_LOCK;
-use crate::directory::{Directory, WatchCallback};
+use crate::directory::{Directory, ManagedDirectory, WatchCallback};
+use crate::error::{DataCorruption, TantivyError};
+use crate::query::FilterCache;
 use crate::Index;
+use crate::Opstamp;
 use crate::Searcher;
+use crate::SegmentId;
 use crate::SegmentReader;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
 use std::{convert::TryInto, io};
 
 /// Defines when a new version of the index should be reloaded.
@@ -40,10 +51,22 @@ pub enum ReloadPolicy {
 /// - `reload_policy` (by default `ReloadPolicy::OnCommit`):
 ///
 ///   See [`ReloadPolicy`](./enum.ReloadPolicy.html) for more details.
+/// - `filter_cache` (by default, none):
+///
+///   See [`FilterCache`](../query/struct.FilterCache.html) for more details.
+/// - `warmers` (by default, none):
+///
+///   See [`Warmer`](./trait.Warmer.html) for more details.
+/// - `quarantine_corrupted_segments` (by default, `false`):
+///
+///   Opens the index in a degraded mode, skipping the corrupted segments.
 #[derive(Clone)]
 pub struct IndexReaderBuilder {
     num_searchers: usize,
     reload_policy: ReloadPolicy,
+    filter_cache: Option<FilterCache>,
+    warmers: Vec<Arc<dyn Warmer>>,
+    quarantine_corrupted_segments: bool,
     index: Index,
 }
 
@@ -52,6 +75,9 @@ impl IndexReaderBuilder {
         IndexReaderBuilder {
             num_searchers: num_cpus::get(),
             reload_policy: ReloadPolicy::OnCommit,
+            filter_cache: None,
+            warmers: Vec::new(),
+            quarantine_corrupted_segments: false,
             index,
         }
     }
@@ -66,6 +92,11 @@ impl IndexReaderBuilder {
             index: self.index,
             num_searchers: self.num_searchers,
             searcher_pool: Pool::new(),
+            generation: Mutex::new(Generation::default()),
+            pinned_searchers: Mutex::new(HashMap::new()),
+            filter_cache: self.filter_cache,
+            warmers: self.warmers,
+            quarantine_corrupted_segments: self.quarantine_corrupted_segments,
         };
         inner_reader.reload()?;
         let inner_reader_arc = Arc::new(inner_reader);
@@ -111,6 +142,42 @@ impl IndexReaderBuilder {
         self.num_searchers = num_searchers;
         self
     }
+
+    /// Registers a filter cache to the reader.
+    ///
+    /// On each reload, the reader evicts the cached bitsets of the segments
+    /// that are not searchable anymore, or whose deletes changed.
+    pub fn filter_cache(mut self, filter_cache: FilterCache) -> IndexReaderBuilder {
+        self.filter_cache = Some(filter_cache);
+        self
+    }
+
+    /// Registers a warmer, called on each new generation of searchers
+    /// before it is exposed by the reader, including the first one.
+    ///
+    /// See [`Warmer`](./trait.Warmer.html) for more details.
+    pub fn warmer<W: Warmer + 'static>(mut self, warmer: W) -> IndexReaderBuilder {
+        self.warmers.push(Arc::new(warmer));
+        self
+    }
+
+    /// Opens the index in a degraded mode, in which the corrupted segments are
+    /// quarantined instead of failing the reload.
+    ///
+    /// Before a segment is opened, the checksums of all of its files are verified,
+    /// which requires reading them entirely. The segments with a corrupted file, or
+    /// failing to open with a `DataCorruption` error, are left out of the searchers:
+    /// the queries then run on the other segments, and miss the documents of the
+    /// quarantined segments.
+    ///
+    /// See [`IndexReader::quarantined_segments`](./struct.IndexReader.html#method.quarantined_segments).
+    pub fn quarantine_corrupted_segments(
+        mut self,
+        quarantine_corrupted_segments: bool,
+    ) -> IndexReaderBuilder {
+        self.quarantine_corrupted_segments = quarantine_corrupted_segments;
+        self
+    }
 }
 
 impl TryInto<IndexReader> for IndexReaderBuilder {
@@ -121,25 +188,142 @@ impl TryInto<IndexReader> for IndexReaderBuilder {
     }
 }
 
+// Last published generation of the reader.
+#[derive(Default)]
+struct Generation {
+    // Opstamp of the commit of the generation.
+    opstamp: Opstamp,
+    // Payload of the commit of the generation.
+    payload: Option<String>,
+    // Segment readers of the generation.
+    //
+    // They are reused on reload when the segment and its deletes did not change,
+    // so that a commit only opens the segments it actually touched.
+    // If only the deletes of a segment changed, the new reader shares everything
+    // but its alive bitset with the previous one.
+    segment_readers: Vec<SegmentReader>,
+    // Files of the commit of the generation.
+    files: HashSet<PathBuf>,
+    // Segments of the commit left out of the generation because they are corrupted.
+    quarantined_segments: HashMap<SegmentId, DataCorruption>,
+}
+
 struct InnerIndexReader {
     num_searchers: usize,
     searcher_pool: Pool<Searcher>,
+    generation: Mutex<Generation>,
+    // Pinned searchers that are still alive, by opstamp.
+    pinned_searchers: Mutex<HashMap<Opstamp, Weak<InnerPinnedSearcher>>>,
+    filter_cache: Option<FilterCache>,
+    warmers: Vec<Arc<dyn Warmer>>,
+    quarantine_corrupted_segments: bool,
     index: Index,
 }
 
+/// Verifies the checksums of the files of the segment, and opens it.
+///
+/// Returns the corruption of the segment, if it is corrupted.
+fn open_segment_checked(
+    segment: &Segment,
+    directory: &ManagedDirectory,
+) -> crate::Result<Result<SegmentReader, DataCorruption>> {
+    if let Some(data_corruption) = check_segment(directory, segment.meta())?
+        .as_ref()
+        .and_then(SkipReason::data_corruption)
+    {
+        return Ok(Err(data_corruption));
+    }
+    match SegmentReader::open(segment) {
+        Ok(segment_reader) => Ok(Ok(segment_reader)),
+        Err(TantivyError::DataCorruption(data_corruption)) => Ok(Err(data_corruption)),
+        Err(err) => Err(err),
+    }
+}
+
 impl InnerIndexReader {
     fn reload(&self) -> crate::Result<()> {
-        let segment_readers: Vec<SegmentReader> = {
-            let _meta_lock = self.index.directory().acquire_lock(&META_LOCK)?;
-            let searchable_segments = self.searchable_segments()?;
-            searchable_segments
+        let mut generation_guard = self
+            .generation
+            .lock()
+            .expect("Generation lock poisoned. This should never happen.");
+        let (index_meta, segment_readers, quarantined_segments) = {
+            let _meta_lock = self
+                .index
+                .directory()
+                .acquire_lock(&META_LOCK)
+                .map_err(|err| {
+                    TantivyError::LockFailure(
+                        err,
+                        Some(
+                            "Failed to acquire the meta lock while reloading the searchers."
+                                .to_string(),
+                        ),
+                    )
+                })?;
+            let index_meta = self.index.searchable_metas()?;
+            let previous_segment_readers: HashMap<SegmentId, &SegmentReader> = generation_guard
+                .segment_readers
                 .iter()
-                .map(SegmentReader::open)
-                .collect::<crate::Result<_>>()?
+                .map(|segment_reader| (segment_reader.segment_id(), segment_reader))
+                .collect();
+            let mut segment_readers: Vec<SegmentReader> =
+                Vec::with_capacity(index_meta.segments.len());
+            let mut quarantined_segments = HashMap::new();
+            for segment_meta in &index_meta.segments {
+                let segment: Segment = self.index.segment(segment_meta.clone());
+                let segment_reader = match previous_segment_readers.get(&segment.id()) {
+                    Some(segment_reader)
+                        if segment_reader.delete_opstamp() == segment.meta().delete_opstamp() =>
+                    {
+                        (*segment_reader).clone()
+                    }
+                    Some(segment_reader) => segment_reader.with_deletes_of(&segment)?,
+                    None if self.quarantine_corrupted_segments => {
+                        let checked_segment_reader =
+                            match generation_guard.quarantined_segments.get(&segment.id()) {
+                                Some(data_corruption) => Err(data_corruption.clone()),
+                                None => open_segment_checked(&segment, self.index.directory())?,
+                            };
+                        match checked_segment_reader {
+                            Ok(segment_reader) => segment_reader,
+                            Err(data_corruption) => {
+                                warn!(
+                                    "Quarantining the corrupted segment {}: {:?}",
+                                    segment.id().short_uuid_string(),
+                                    data_corruption
+                                );
+                                quarantined_segments.insert(segment.id(), data_corruption);
+                                continue;
+                            }
+                        }
+                    }
+                    None => SegmentReader::open(&segment)?,
+                };
+                segment_readers.push(segment_reader);
+            }
+            (index_meta, segment_readers, quarantined_segments)
         };
         let schema = self.index.schema();
+        let global_ordinals_cache = GlobalOrdinalsCache::default();
+        if !self.warmers.is_empty() {
+            let searcher =
+                Searcher::new(schema.clone(), self.index.clone(), segment_readers.clone())?
+                    .with_global_ordinals_cache(global_ordinals_cache.clone());
+            for warmer in &self.warmers {
+                warmer.warm(&searcher)?;
+            }
+        }
+        generation_guard.opstamp = index_meta.opstamp;
+        generation_guard.payload = index_meta.payload.clone();
+        generation_guard.files = index_meta.files();
+        generation_guard.segment_readers = segment_readers.clone();
+        generation_guard.quarantined_segments = quarantined_segments;
+        if let Some(filter_cache) = self.filter_cache.as_ref() {
+            filter_cache.retain_segments(&segment_readers);
+        }
         let searchers: Vec<Searcher> = std::iter::repeat_with(|| {
             Searcher::new(schema.clone(), self.index.clone(), segment_readers.clone())
+                .map(|searcher| searcher.with_global_ordinals_cache(global_ordinals_cache.clone()))
         })
         .take(self.num_searchers)
         .collect::<io::Result<_>>()?;
@@ -147,14 +331,67 @@ impl InnerIndexReader {
         Ok(())
     }
 
-    /// Returns the list of segments that are searchable
-    fn searchable_segments(&self) -> crate::Result<Vec<Segment>> {
-        self.index.searchable_segments()
+    fn pin_searcher(&self) -> crate::Result<PinnedSearcher> {
+        let generation_guard = self
+            .generation
+            .lock()
+            .expect("Generation lock poisoned. This should never happen.");
+        let mut pinned_searchers_guard = self
+            .pinned_searchers
+            .lock()
+            .expect("Pinned searchers lock poisoned. This should never happen.");
+        pinned_searchers_guard.retain(|_, pinned_searcher| pinned_searcher.strong_count() > 0);
+        if let Some(inner) = pinned_searchers_guard
+            .get(&generation_guard.opstamp)
+            .and_then(Weak::upgrade)
+        {
+            return Ok(PinnedSearcher { inner });
+        }
+        let file_pin = self
+            .index
+            .directory()
+            .pin_files(generation_guard.files.iter().cloned().collect());
+        let searcher = Searcher::new(
+            self.index.schema(),
+            self.index.clone(),
+            generation_guard.segment_readers.clone(),
+        )?;
+        let pinned_searcher = PinnedSearcher::new(searcher, generation_guard.opstamp, file_pin);
+        pinned_searchers_guard.insert(
+            generation_guard.opstamp,
+            Arc::downgrade(&pinned_searcher.inner),
+        );
+        Ok(pinned_searcher)
+    }
+
+    fn pinned_searcher(&self, opstamp: Opstamp) -> Option<PinnedSearcher> {
+        let pinned_searchers_guard = self
+            .pinned_searchers
+            .lock()
+            .expect("Pinned searchers lock poisoned. This should never happen.");
+        let inner = pinned_searchers_guard.get(&opstamp)?.upgrade()?;
+        Some(PinnedSearcher { inner })
     }
 
     fn searcher(&self) -> LeasedItem<Searcher> {
         self.searcher_pool.acquire()
     }
+
+    fn commit_payload(&self) -> Option<String> {
+        self.generation
+            .lock()
+            .expect("Generation lock poisoned. This should never happen.")
+            .payload
+            .clone()
+    }
+
+    fn quarantined_segments(&self) -> HashMap<SegmentId, DataCorruption> {
+        self.generation
+            .lock()
+            .expect("Generation lock poisoned. This should never happen.")
+            .quarantined_segments
+            .clone()
+    }
 }
 
 /// `IndexReader` is your entry point to read and search the index.
@@ -202,4 +439,139 @@ impl IndexReader {
     pub fn searcher(&self) -> LeasedItem<Searcher> {
         self.inner.searcher()
     }
+
+    /// Returns a searcher pinned to the last loaded commit.
+    ///
+    /// The searcher keeps on searching this commit after the reader is reloaded,
+    /// which makes it possible to paginate consistently across requests.
+    /// Calling this method again before the commit changes returns the same
+    /// searcher, as long as it is alive.
+    ///
+    /// See [`PinnedSearcher`](./struct.PinnedSearcher.html) for more details.
+    pub fn pin_searcher(&self) -> crate::Result<PinnedSearcher> {
+        self.inner.pin_searcher()
+    }
+
+    /// Returns the searcher pinned to the commit of the given opstamp,
+    /// if one is still alive.
+    ///
+    /// See [`pin_searcher`](#method.pin_searcher).
+    pub fn pinned_searcher(&self, opstamp: Opstamp) -> Option<PinnedSearcher> {
+        self.inner.pinned_searcher(opstamp)
+    }
+
+    /// Returns the payload of the last loaded commit, if any.
+    ///
+    /// See [`IndexWriter::commit_with_payload`](./struct.IndexWriter.html#method.commit_with_payload).
+    /// A soft commit keeps the payload of the last durable commit.
+    pub fn commit_payload(&self) -> Option<String> {
+        self.inner.commit_payload()
+    }
+
+    /// Returns the segments of the last loaded commit that were left out of the
+    /// searchers because they are corrupted, with the description of their corruption.
+    ///
+    /// Segments are only quarantined if the reader was built with
+    /// [`quarantine_corrupted_segments`](./struct.IndexReaderBuilder.html#method.quarantine_corrupted_segments).
+    pub fn quarantined_segments(&self) -> HashMap<SegmentId, DataCorruption> {
+        self.inner.quarantined_segments()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::Count;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, STORED, STRING};
+    use crate::{Directory, Index, ReloadPolicy, SegmentComponent};
+
+    #[test]
+    fn test_commit_payload() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        assert_eq!(reader.commit_payload(), None);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a"))?;
+        let opstamp = index_writer.commit_with_payload("kafka_offset=12345")?;
+        assert_eq!(reader.commit_payload(), None);
+        reader.reload()?;
+        assert_eq!(
+            reader.commit_payload().as_deref(),
+            Some("kafka_offset=12345")
+        );
+        let index_meta = Index::open(index.directory().clone())?.load_metas()?;
+        assert_eq!(index_meta.opstamp, opstamp);
+        assert_eq!(index_meta.payload.as_deref(), Some("kafka_offset=12345"));
+
+        // A soft commit keeps the payload of the last durable commit.
+        index_writer.add_document(doc!(text => "b"))?;
+        index_writer.soft_commit()?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().num_docs(), 2);
+        assert_eq!(
+            reader.commit_payload().as_deref(),
+            Some("kafka_offset=12345")
+        );
+
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(reader.commit_payload(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quarantine_corrupted_segments() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.add_document(doc!(text => "b"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "c"))?;
+        index_writer.commit()?;
+
+        let corrupted_segment_meta = index
+            .searchable_segment_metas()?
+            .into_iter()
+            .find(|segment_meta| segment_meta.max_doc() == 2)
+            .unwrap();
+        let store_path = corrupted_segment_meta.relative_path(SegmentComponent::Store);
+        let mut store_bytes = index.directory().atomic_read(&store_path)?;
+        store_bytes[0] ^= 0xff;
+        index.directory().atomic_write(&store_path, &store_bytes)?;
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .quarantine_corrupted_segments(true)
+            .try_into()?;
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 1);
+        let quarantined_segments = reader.quarantined_segments();
+        assert_eq!(quarantined_segments.len(), 1);
+        let data_corruption = &quarantined_segments[&corrupted_segment_meta.id()];
+        assert_eq!(data_corruption.filepath(), Some(store_path.as_path()));
+
+        // The segment remains quarantined across reloads.
+        index_writer.add_document(doc!(text => "d"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 2);
+        assert_eq!(reader.quarantined_segments().len(), 1);
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        assert!(reader.quarantined_segments().is_empty());
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 4);
+        Ok(())
+    }
 }
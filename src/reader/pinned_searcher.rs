@@ -0,0 +1,140 @@
+use crate::directory::FilePin;
+use crate::{Opstamp, Searcher};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+pub(crate) struct InnerPinnedSearcher {
+    searcher: Searcher,
+    opstamp: Opstamp,
+    _file_pin: FilePin,
+}
+
+/// `Searcher` pinned to a commit of the index.
+///
+/// A `PinnedSearcher` is obtained with
+/// [`IndexReader::pin_searcher`](./struct.IndexReader.html#method.pin_searcher).
+/// Unlike the searchers lent by the `IndexReader`, it is not replaced on reload: it keeps
+/// on searching the commit it was pinned to, whatever the commits and merges that happened
+/// since then. The files of this commit are protected from garbage collection until all of
+/// the clones of the `PinnedSearcher` are dropped.
+///
+/// This makes it possible to paginate consistently through the results of a query
+/// across requests: the opstamp of the commit can be sent along with the page, and the
+/// searcher can be retrieved with
+/// [`IndexReader::pinned_searcher`](./struct.IndexReader.html#method.pinned_searcher)
+/// to serve the next page.
+///
+/// `PinnedSearcher` is cheap to clone, and dereferences to a `Searcher`.
+#[derive(Clone)]
+pub struct PinnedSearcher {
+    pub(crate) inner: Arc<InnerPinnedSearcher>,
+}
+
+impl PinnedSearcher {
+    pub(crate) fn new(searcher: Searcher, opstamp: Opstamp, file_pin: FilePin) -> PinnedSearcher {
+        PinnedSearcher {
+            inner: Arc::new(InnerPinnedSearcher {
+                searcher,
+                opstamp,
+                _file_pin: file_pin,
+            }),
+        }
+    }
+
+    /// Returns the opstamp of the commit the searcher is pinned to.
+    pub fn opstamp(&self) -> Opstamp {
+        self.inner.opstamp
+    }
+}
+
+impl Deref for PinnedSearcher {
+    type Target = Searcher;
+
+    fn deref(&self) -> &Searcher {
+        &self.inner.searcher
+    }
+}
+
+impl fmt::Debug for PinnedSearcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PinnedSearcher(opstamp={}, {:?})",
+            self.opstamp(),
+            self.inner.searcher
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::{Count, TopDocs};
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, FAST, INDEXED};
+    use crate::{Directory, Index, ReloadPolicy};
+    use futures::executor::block_on;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_pinned_searcher() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_u64_field("num", INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(num => i))?;
+        }
+        index_writer.commit()?;
+        reader.reload()?;
+
+        let pinned_searcher = reader.pin_searcher()?;
+        let opstamp = pinned_searcher.opstamp();
+        assert_eq!(reader.pin_searcher()?.opstamp(), opstamp);
+        let first_page =
+            pinned_searcher.search(&AllQuery, &TopDocs::with_limit(5).order_by_u64_field(num))?;
+        drop(pinned_searcher);
+        assert!(reader.pinned_searcher(opstamp).is_none());
+        let pinned_searcher = reader.pin_searcher()?;
+
+        // New commits and merges do not affect the pinned searcher.
+        for i in 10..20u64 {
+            index_writer.add_document(doc!(num => i))?;
+        }
+        index_writer.commit()?;
+        block_on(index_writer.merge(&index.searchable_segment_ids()?))?;
+        block_on(index_writer.garbage_collect_files())?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 20);
+
+        let pinned_searcher_again = reader
+            .pinned_searcher(opstamp)
+            .expect("The searcher is still pinned");
+        assert_eq!(pinned_searcher_again.search(&AllQuery, &Count)?, 10);
+        let second_page = pinned_searcher_again.search(
+            &AllQuery,
+            &TopDocs::with_limit(5).and_offset(5).order_by_u64_field(num),
+        )?;
+        assert_eq!(
+            first_page
+                .iter()
+                .chain(second_page.iter())
+                .map(|(value, _)| *value)
+                .collect::<Vec<u64>>(),
+            (0..10u64).rev().collect::<Vec<u64>>()
+        );
+        for segment_reader in pinned_searcher.segment_readers() {
+            let segment_id = segment_reader.segment_id();
+            assert!(!index.searchable_segment_ids()?.contains(&segment_id));
+            // The files of the merged segments are not garbage collected.
+            let postings_path = PathBuf::from(format!("{}.idx", segment_id.uuid_string()));
+            assert!(index.directory().exists(&postings_path)?);
+        }
+        assert_ne!(reader.pin_searcher()?.opstamp(), opstamp);
+        Ok(())
+    }
+}
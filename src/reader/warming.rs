@@ -0,0 +1,93 @@
+use crate::Searcher;
+
+/// A `Warmer` prepares each new generation of searchers before the
+/// [`IndexReader`](./struct.IndexReader.html) exposes it.
+///
+/// Warmers are registered with
+/// [`IndexReaderBuilder::warmer`](./struct.IndexReaderBuilder.html#method.warmer).
+/// On each reload, they are called in order of registration with a searcher of the
+/// new generation, before it replaces the previous generation. They can for instance
+/// load the fast fields or the term dictionaries needed by the queries, or fill a cache.
+/// Since the searchers of a generation share their segment readers, whatever is
+/// loaded by a warmer benefits to all of them.
+///
+/// If a warmer fails, the reload fails and the previous generation remains in use.
+///
+/// Closures taking a `&Searcher` are warmers.
+pub trait Warmer: Send + Sync {
+    /// Warms up the searcher of a new generation.
+    fn warm(&self, searcher: &Searcher) -> crate::Result<()>;
+}
+
+impl<F> Warmer for F
+where
+    F: Fn(&Searcher) -> crate::Result<()> + Send + Sync,
+{
+    fn warm(&self, searcher: &Searcher) -> crate::Result<()> {
+        self(searcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::Count;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, FAST};
+    use crate::{Index, ReloadPolicy, Searcher, TantivyError};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_warmers() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let warmed_num_docs = Arc::new(Mutex::new(Vec::new()));
+        let warmed_num_docs_clone = warmed_num_docs.clone();
+        let fail = Arc::new(AtomicBool::new(false));
+        let fail_clone = fail.clone();
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .warmer(move |searcher: &Searcher| {
+                for segment_reader in searcher.segment_readers() {
+                    segment_reader.fast_fields().u64(num)?;
+                }
+                warmed_num_docs_clone
+                    .lock()
+                    .unwrap()
+                    .push(searcher.num_docs());
+                Ok(())
+            })
+            .warmer(move |_: &Searcher| {
+                if fail_clone.load(Ordering::SeqCst) {
+                    Err(TantivyError::InvalidArgument("warming failed".to_string()))
+                } else {
+                    Ok(())
+                }
+            })
+            .try_into()?;
+        assert_eq!(*warmed_num_docs.lock().unwrap(), vec![0]);
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(num => 1u64))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(*warmed_num_docs.lock().unwrap(), vec![0, 1]);
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 1);
+
+        // The new generation is not exposed if a warmer fails.
+        index_writer.add_document(doc!(num => 2u64))?;
+        index_writer.commit()?;
+        fail.store(true, Ordering::SeqCst);
+        assert!(reader.reload().is_err());
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 1);
+        assert_eq!(reader.pin_searcher()?.search(&AllQuery, &Count)?, 1);
+
+        fail.store(false, Ordering::SeqCst);
+        reader.reload()?;
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 2);
+        assert_eq!(*warmed_num_docs.lock().unwrap(), vec![0, 1, 2, 2]);
+        Ok(())
+    }
+}
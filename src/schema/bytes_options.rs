@@ -10,6 +10,12 @@ pub struct BytesOptions {
     fieldnorms: bool,
     fast: bool,
     stored: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    stored_raw: bool,
+}
+
+fn is_false(val: &bool) -> bool {
+    !*val
 }
 
 /// For backward compability we add an intermediary to interpret the
@@ -24,6 +30,8 @@ struct BytesOptionsDeser {
     fieldnorms: Option<bool>,
     fast: bool,
     stored: bool,
+    #[serde(default)]
+    stored_raw: bool,
 }
 
 impl From<BytesOptionsDeser> for BytesOptions {
@@ -33,6 +41,7 @@ impl From<BytesOptionsDeser> for BytesOptions {
             fieldnorms: deser.fieldnorms.unwrap_or(deser.indexed),
             fast: deser.fast,
             stored: deser.stored,
+            stored_raw: deser.stored_raw,
         }
     }
 }
@@ -58,6 +67,12 @@ impl BytesOptions {
         self.stored
     }
 
+    /// Returns true iff the value is stored outside of the compressed blocks
+    /// of the doc store.
+    pub fn is_stored_raw(&self) -> bool {
+        self.stored_raw
+    }
+
     /// Set the field as indexed.
     ///
     /// Setting an integer as indexed will generate
@@ -95,6 +110,17 @@ impl BytesOptions {
         self.stored = true;
         self
     }
+
+    /// Set the field as stored, without compressing its values.
+    ///
+    /// The values of the field are written in an uncompressed region of the
+    /// doc store rather than in its compressed blocks. This avoids compressing
+    /// twice values that are already compressed, e.g. images or compressed blobs.
+    pub fn set_stored_raw(mut self) -> BytesOptions {
+        self.stored = true;
+        self.stored_raw = true;
+        self
+    }
 }
 
 impl<T: Into<BytesOptions>> BitOr<T> for BytesOptions {
@@ -106,6 +132,7 @@ impl<T: Into<BytesOptions>> BitOr<T> for BytesOptions {
             indexed: self.indexed | other.indexed,
             fieldnorms: self.fieldnorms | other.fieldnorms,
             stored: self.stored | other.stored,
+            stored_raw: self.stored_raw | other.stored_raw,
             fast: self.fast | other.fast,
         }
     }
@@ -123,6 +150,7 @@ impl From<FastFlag> for BytesOptions {
             indexed: false,
             fieldnorms: false,
             stored: false,
+            stored_raw: false,
             fast: true,
         }
     }
@@ -134,6 +162,7 @@ impl From<StoredFlag> for BytesOptions {
             indexed: false,
             fieldnorms: false,
             stored: true,
+            stored_raw: false,
             fast: false,
         }
     }
@@ -145,6 +174,7 @@ impl From<IndexedFlag> for BytesOptions {
             indexed: true,
             fieldnorms: true,
             stored: false,
+            stored_raw: false,
             fast: false,
         }
     }
@@ -208,6 +238,22 @@ mod tests {
         assert!(BytesOptions::default().set_fieldnorms().fieldnorms());
     }
 
+    #[test]
+    fn test_bytes_options_stored_raw() {
+        let bytes_options = BytesOptions::default().set_stored_raw();
+        assert!(bytes_options.is_stored());
+        assert!(bytes_options.is_stored_raw());
+        assert!(!BytesOptions::default().set_stored().is_stored_raw());
+        assert!((bytes_options.clone() | STORED).is_stored_raw());
+        let json = serde_json::to_string(&bytes_options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"indexed":false,"fieldnorms":false,"fast":false,"stored":true,"stored_raw":true}"#
+        );
+        let deser_bytes_options: BytesOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser_bytes_options, bytes_options);
+    }
+
     #[test]
     fn test_bytes_options_deser_if_fieldnorm_missing_indexed_true() {
         let json = r#"{
@@ -222,7 +268,8 @@ mod tests {
                 indexed: true,
                 fieldnorms: true,
                 fast: false,
-                stored: false
+                stored: false,
+                stored_raw: false,
             }
         );
     }
@@ -241,7 +288,8 @@ mod tests {
                 indexed: false,
                 fieldnorms: false,
                 fast: false,
-                stored: false
+                stored: false,
+                stored_raw: false,
             }
         );
     }
@@ -261,7 +309,8 @@ mod tests {
                 indexed: true,
                 fieldnorms: false,
                 fast: false,
-                stored: false
+                stored: false,
+                stored_raw: false,
             }
         );
     }
@@ -282,7 +331,8 @@ mod tests {
                 indexed: false,
                 fieldnorms: true,
                 fast: false,
-                stored: false
+                stored: false,
+                stored_raw: false,
             }
         );
     }
@@ -0,0 +1,121 @@
+use crate::DateTime;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Format in which the values of a date field are given in a JSON document.
+///
+/// See [`IntOptions::set_date_formats`](./struct.IntOptions.html#method.set_date_formats).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateTimeFormat {
+    /// RFC 3339 string, e.g. `"2021-11-05T12:30:00+01:00"`.
+    Rfc3339,
+    /// Number of seconds since the unix epoch, e.g. `1636111800`.
+    UnixSeconds,
+    /// Number of milliseconds since the unix epoch, e.g. `1636111800000`.
+    UnixMillis,
+    /// Custom format, following the `strftime` syntax of `chrono`, e.g. `"%Y-%m-%d %H:%M"`.
+    ///
+    /// If the format has no timezone, the date is assumed to be in UTC.
+    /// If it has no time either, the date is set to midnight.
+    Strftime(String),
+}
+
+impl DateTimeFormat {
+    /// Returns true iff the format expects numbers.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DateTimeFormat::UnixSeconds | DateTimeFormat::UnixMillis
+        )
+    }
+
+    /// Parses a date given as a string.
+    ///
+    /// Numeric formats only accept the string representation of an integer.
+    pub fn parse_str(&self, text: &str) -> Option<DateTime> {
+        match self {
+            DateTimeFormat::Rfc3339 => chrono::DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|date_time| date_time.with_timezone(&Utc)),
+            DateTimeFormat::UnixSeconds | DateTimeFormat::UnixMillis => {
+                self.parse_timestamp(text.trim().parse().ok()?)
+            }
+            DateTimeFormat::Strftime(format) => parse_strftime(text, format),
+        }
+    }
+
+    /// Parses a date given as a timestamp.
+    ///
+    /// Returns `None` if the format is not numeric, or if the timestamp is out of range.
+    pub fn parse_timestamp(&self, timestamp: i64) -> Option<DateTime> {
+        match self {
+            DateTimeFormat::UnixSeconds => Utc.timestamp_opt(timestamp, 0).single(),
+            DateTimeFormat::UnixMillis => Utc.timestamp_millis_opt(timestamp).single(),
+            DateTimeFormat::Rfc3339 | DateTimeFormat::Strftime(_) => None,
+        }
+    }
+}
+
+fn parse_strftime(text: &str, format: &str) -> Option<DateTime> {
+    if let Ok(date_time) = chrono::DateTime::parse_from_str(text, format) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    if let Ok(naive_date_time) = NaiveDateTime::parse_from_str(text, format) {
+        return Some(Utc.from_utc_datetime(&naive_date_time));
+    }
+    let naive_date = NaiveDate::parse_from_str(text, format).ok()?;
+    Some(Utc.from_utc_datetime(&naive_date.and_hms_opt(0, 0, 0)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DateTimeFormat;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_date_time_format_parse() {
+        let expected = Utc.with_ymd_and_hms(2021, 11, 5, 11, 30, 0).single();
+        assert_eq!(
+            DateTimeFormat::Rfc3339.parse_str("2021-11-05T12:30:00+01:00"),
+            expected
+        );
+        assert_eq!(DateTimeFormat::Rfc3339.parse_str("1636111800"), None);
+        assert_eq!(
+            DateTimeFormat::UnixSeconds.parse_str("1636111800"),
+            expected
+        );
+        assert_eq!(
+            DateTimeFormat::UnixSeconds.parse_timestamp(1_636_111_800),
+            expected
+        );
+        assert_eq!(
+            DateTimeFormat::UnixMillis.parse_timestamp(1_636_111_800_000),
+            expected
+        );
+        assert_eq!(DateTimeFormat::Rfc3339.parse_timestamp(1_636_111_800), None);
+        let strftime = DateTimeFormat::Strftime("%d/%m/%Y %H:%M".to_string());
+        assert_eq!(strftime.parse_str("05/11/2021 11:30"), expected);
+        assert_eq!(strftime.parse_str("2021-11-05"), None);
+        let strftime_tz = DateTimeFormat::Strftime("%d/%m/%Y %H:%M %z".to_string());
+        assert_eq!(strftime_tz.parse_str("05/11/2021 12:30 +0100"), expected);
+        let strftime_date = DateTimeFormat::Strftime("%Y-%m-%d".to_string());
+        assert_eq!(
+            strftime_date.parse_str("2021-11-05"),
+            Utc.with_ymd_and_hms(2021, 11, 5, 0, 0, 0).single()
+        );
+    }
+
+    #[test]
+    fn test_date_time_format_serialization() {
+        let formats = vec![
+            DateTimeFormat::Rfc3339,
+            DateTimeFormat::UnixMillis,
+            DateTimeFormat::Strftime("%Y-%m-%d".to_string()),
+        ];
+        let json = serde_json::to_string(&formats).unwrap();
+        assert_eq!(json, r#"["rfc3339","unix_millis",{"strftime":"%Y-%m-%d"}]"#);
+        let deser_formats: Vec<DateTimeFormat> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser_formats, formats);
+    }
+}
@@ -113,6 +113,24 @@ impl Document {
         self.add(FieldValue::new(field, Value::Bytes(value.into())))
     }
 
+    /// Add a vector field
+    pub fn add_vector<T: Into<Vec<f32>>>(&mut self, field: Field, value: T) {
+        self.add(FieldValue::new(field, Value::Vector(value.into())))
+    }
+
+    /// Add the weights of the features of a features field
+    pub fn add_features<S: ToString>(
+        &mut self,
+        field: Field,
+        features: impl IntoIterator<Item = (S, f32)>,
+    ) {
+        let features = features
+            .into_iter()
+            .map(|(feature, weight)| (feature.to_string(), weight))
+            .collect();
+        self.add(FieldValue::new(field, Value::Features(features)))
+    }
+
     /// Add a field value
     pub fn add(&mut self, field_value: FieldValue) {
         self.field_values.push(field_value);
@@ -189,6 +207,26 @@ impl Document {
     }
 }
 
+impl Document {
+    /// Deserializes a document, only keeping the values of the given `fields`.
+    ///
+    /// The values of the other fields are skipped over without being deserialized.
+    pub(crate) fn deserialize_fields(reader: &mut &[u8], fields: &[Field]) -> io::Result<Document> {
+        let num_field_values = VInt::deserialize(reader)?.val() as usize;
+        let mut field_values = Vec::new();
+        for _ in 0..num_field_values {
+            let field = Field::deserialize(reader)?;
+            if fields.contains(&field) {
+                let value = Value::deserialize(reader)?;
+                field_values.push(FieldValue::new(field, value));
+            } else {
+                super::value::skip_value(reader)?;
+            }
+        }
+        Ok(Document::from(field_values))
+    }
+}
+
 impl BinarySerializable for Document {
     fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         let field_values = self.field_values();
@@ -237,6 +275,7 @@ mod tests {
                 position: 0,
                 text: String::from("A"),
                 position_length: 1,
+                payload: Vec::new(),
             }],
         };
 
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Define how a features field should be handled by tantivy.
+///
+/// A features field holds, for each document, a sparse vector of named features
+/// and their weights (e.g. the term expansions of a learned sparse retrieval model).
+/// The features are not indexed in the inverted index: for each feature, the documents
+/// having it are written along with their weight to the features component of the segment,
+/// and searched with a `FeaturesQuery`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeaturesOptions {
+    stored: bool,
+}
+
+impl FeaturesOptions {
+    /// Returns true iff the features are also stored in the doc store.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Set the field as stored.
+    pub fn set_stored(mut self) -> FeaturesOptions {
+        self.stored = true;
+        self
+    }
+}
@@ -1,9 +1,12 @@
 use crate::schema::FacetOptions;
+use crate::schema::FeaturesOptions;
 use crate::schema::TextOptions;
+use crate::schema::VectorOptions;
 use crate::schema::{is_valid_field_name, IntOptions};
 
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::FieldType;
+use crate::schema::IndexRecordOption;
 use serde::{Deserialize, Serialize};
 
 /// A `FieldEntry` represents a field and its configuration.
@@ -13,11 +16,38 @@ use serde::{Deserialize, Serialize};
 /// - a field name
 /// - a field type, itself wrapping up options describing
 /// how the field should be indexed.
+/// - the aliases of the field, alternative names resolving to the same field.
+/// - whether the field is one of the default search fields of the schema.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FieldEntry {
     name: String,
     #[serde(flatten)]
     field_type: FieldType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    default_search: bool,
+}
+
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
+/// What a field supports, as configured in the schema.
+///
+/// See [`FieldEntry::capabilities`](./struct.FieldEntry.html#method.capabilities).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FieldCapabilities {
+    /// The field is indexed, and therefore searchable.
+    pub is_indexed: bool,
+    /// The term frequencies of the field are indexed.
+    pub has_freqs: bool,
+    /// The positions of the field are indexed, as required by phrase queries.
+    pub has_positions: bool,
+    /// The values of the field can be read from a fast field reader.
+    pub is_fast: bool,
+    /// The values of the field are stored in the doc store.
+    pub is_stored: bool,
 }
 
 impl FieldEntry {
@@ -27,6 +57,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type,
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -37,6 +69,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::Str(text_options),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -47,6 +81,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::U64(field_type),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -57,6 +93,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::I64(field_type),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -67,6 +105,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::F64(field_type),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -77,6 +117,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::Date(field_type),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -86,6 +128,8 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::HierarchicalFacet(field_type),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -94,6 +138,30 @@ impl FieldEntry {
         FieldEntry {
             name: field_name,
             field_type: FieldType::Bytes(bytes_type),
+            aliases: Vec::new(),
+            default_search: false,
+        }
+    }
+
+    /// Creates a field entry for a vector field
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        assert!(is_valid_field_name(&field_name));
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Vector(vector_options),
+            aliases: Vec::new(),
+            default_search: false,
+        }
+    }
+
+    /// Creates a field entry for a features field
+    pub fn new_features(field_name: String, features_options: FeaturesOptions) -> FieldEntry {
+        assert!(is_valid_field_name(&field_name));
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Features(features_options),
+            aliases: Vec::new(),
+            default_search: false,
         }
     }
 
@@ -107,6 +175,26 @@ impl FieldEntry {
         &self.field_type
     }
 
+    /// Returns the aliases of the field.
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub(crate) fn add_alias(&mut self, alias: String) {
+        self.aliases.push(alias);
+    }
+
+    /// Returns true iff the field is one of the default search fields of the schema.
+    ///
+    /// See [`SchemaBuilder::add_default_search_field`](./struct.SchemaBuilder.html#method.add_default_search_field).
+    pub fn is_default_search(&self) -> bool {
+        self.default_search
+    }
+
+    pub(crate) fn set_default_search(&mut self) {
+        self.default_search = true;
+    }
+
     /// Returns true iff the field is indexed.
     ///
     /// An indexed field is searchable.
@@ -140,6 +228,41 @@ impl FieldEntry {
             FieldType::Str(ref options) => options.is_stored(),
             FieldType::HierarchicalFacet(ref options) => options.is_stored(),
             FieldType::Bytes(ref options) => options.is_stored(),
+            FieldType::Vector(ref options) => options.is_stored(),
+            FieldType::Features(ref options) => options.is_stored(),
+        }
+    }
+
+    /// Returns what the field supports.
+    ///
+    /// Unlike `is_fast`, the `is_fast` capability also covers the fast fields that are not
+    /// integers, i.e. bytes and facet fields.
+    pub fn capabilities(&self) -> FieldCapabilities {
+        let record_option_opt = self.field_type.get_index_record_option();
+        let is_fast = match self.field_type {
+            FieldType::Bytes(ref options) => options.is_fast(),
+            FieldType::HierarchicalFacet(_) => true,
+            _ => self.is_fast(),
+        };
+        FieldCapabilities {
+            is_indexed: self.is_indexed(),
+            has_freqs: record_option_opt
+                .map(IndexRecordOption::has_freq)
+                .unwrap_or(false),
+            has_positions: record_option_opt
+                .map(IndexRecordOption::has_positions)
+                .unwrap_or(false),
+            is_fast,
+            is_stored: self.is_stored(),
+        }
+    }
+
+    /// Returns true iff the field is stored outside of the compressed
+    /// blocks of the doc store.
+    pub fn is_stored_raw(&self) -> bool {
+        match self.field_type {
+            FieldType::Bytes(ref options) => options.is_stored_raw(),
+            _ => false,
         }
     }
 }
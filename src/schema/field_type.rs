@@ -1,14 +1,16 @@
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::facet_options::FacetOptions;
+use crate::schema::DateTimeFormat;
 use crate::schema::Facet;
 use crate::schema::IndexRecordOption;
 use crate::schema::TextFieldIndexing;
 use crate::schema::Value;
-use crate::schema::{IntOptions, TextOptions};
+use crate::schema::{FeaturesOptions, IntOptions, TextOptions, VectorOptions};
 use crate::tokenizer::PreTokenizedString;
-use chrono::{FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::value::Number;
 use serde_json::Value as JsonValue;
+use std::convert::TryFrom;
 
 /// Possible error that may occur while parsing a field value
 /// At this point the JSON is known to be valid.
@@ -25,6 +27,74 @@ pub enum ValueParsingError {
     InvalidBase64(String),
 }
 
+/// Formats accepted for the values of a date field, if none are set in its options.
+const DEFAULT_DATE_FORMATS: [DateTimeFormat; 2] =
+    [DateTimeFormat::Rfc3339, DateTimeFormat::UnixSeconds];
+
+fn date_formats(options: &IntOptions) -> &[DateTimeFormat] {
+    if options.date_formats().is_empty() {
+        &DEFAULT_DATE_FORMATS[..]
+    } else {
+        options.date_formats()
+    }
+}
+
+fn date_from_str(options: &IntOptions, text: &str) -> Result<Value, ValueParsingError> {
+    let formats = date_formats(options);
+    formats
+        .iter()
+        .filter(|format| options.is_coerce() || !format.is_numeric())
+        .find_map(|format| format.parse_str(text))
+        .map(Value::Date)
+        .ok_or_else(|| {
+            ValueParsingError::TypeError(format!(
+                "Failed to parse date from JSON. Expected one of the formats {:?}, got {}.",
+                formats, text
+            ))
+        })
+}
+
+fn date_from_number(options: &IntOptions, number: &Number) -> Result<Value, ValueParsingError> {
+    let formats = date_formats(options);
+    if !formats.iter().any(DateTimeFormat::is_numeric) {
+        return Err(ValueParsingError::TypeError(format!(
+            "Expected a date string in one of the formats {:?}, got {}.",
+            formats, number
+        )));
+    }
+    let timestamp = int_from_number(options, number).ok_or_else(|| {
+        ValueParsingError::OverflowError(format!("Expected an i64 timestamp, got {}", number))
+    })?;
+    formats
+        .iter()
+        .find_map(|format| format.parse_timestamp(timestamp))
+        .map(Value::Date)
+        .ok_or_else(|| {
+            ValueParsingError::OverflowError(format!("Timestamp {} is out of range.", number))
+        })
+}
+
+/// Converts the number to an integer, accepting floats without a fractional part
+/// if the parsing is permissive.
+fn int_from_number<T: TryFrom<i64> + TryFrom<u64>>(
+    options: &IntOptions,
+    number: &Number,
+) -> Option<T> {
+    if let Some(val) = number.as_u64() {
+        return T::try_from(val).ok();
+    }
+    if let Some(val) = number.as_i64() {
+        return T::try_from(val).ok();
+    }
+    let val = number.as_f64()?;
+    // `i64::MAX as f64` rounds up to 2^63, hence the strict comparison.
+    if options.is_coerce() && val.fract() == 0.0 && val >= i64::MIN as f64 && val < i64::MAX as f64
+    {
+        return T::try_from(val as i64).ok();
+    }
+    None
+}
+
 /// Type of the value that a field can take.
 ///
 /// Contrary to FieldType, this does
@@ -45,6 +115,10 @@ pub enum Type {
     HierarchicalFacet,
     /// `Vec<u8>`
     Bytes,
+    /// `Vec<f32>`
+    Vector,
+    /// `Vec<(String, f32)>`
+    Features,
 }
 
 /// A `FieldType` describes the type (text, u64) of a field as well as
@@ -68,6 +142,10 @@ pub enum FieldType {
     HierarchicalFacet(FacetOptions),
     /// Bytes (one per document)
     Bytes(BytesOptions),
+    /// Fixed-dimension `f32` vectors (one per document)
+    Vector(VectorOptions),
+    /// Sparse vectors of named features and their weights
+    Features(FeaturesOptions),
 }
 
 impl FieldType {
@@ -81,6 +159,8 @@ impl FieldType {
             FieldType::Date(_) => Type::Date,
             FieldType::HierarchicalFacet(_) => Type::HierarchicalFacet,
             FieldType::Bytes(_) => Type::Bytes,
+            FieldType::Vector(_) => Type::Vector,
+            FieldType::Features(_) => Type::Features,
         }
     }
 
@@ -94,6 +174,7 @@ impl FieldType {
             FieldType::Date(ref date_options) => date_options.is_indexed(),
             FieldType::HierarchicalFacet(ref _facet_options) => true,
             FieldType::Bytes(ref bytes_options) => bytes_options.is_indexed(),
+            FieldType::Vector(_) | FieldType::Features(_) => false,
         }
     }
 
@@ -110,6 +191,18 @@ impl FieldType {
             | FieldType::Date(ref int_options) => int_options.fieldnorms(),
             FieldType::HierarchicalFacet(_) => false,
             FieldType::Bytes(ref bytes_options) => bytes_options.fieldnorms(),
+            FieldType::Vector(_) | FieldType::Features(_) => false,
+        }
+    }
+
+    /// returns true iff the field records the payloads of its positions.
+    pub fn has_payloads(&self) -> bool {
+        match *self {
+            FieldType::Str(ref text_options) => text_options
+                .get_indexing_options()
+                .map(TextFieldIndexing::payloads)
+                .unwrap_or(false),
+            _ => false,
         }
     }
 
@@ -140,6 +233,7 @@ impl FieldType {
                     None
                 }
             }
+            FieldType::Vector(_) | FieldType::Features(_) => None,
         }
     }
 
@@ -148,23 +242,30 @@ impl FieldType {
     /// Tantivy will not try to cast values.
     /// For instance, If the json value is the integer `3` and the
     /// target field is a `Str`, this method will return an Error.
+    ///
+    /// Numeric fields can be made more permissive with `IntOptions::set_coerce`,
+    /// and the formats accepted for dates are set with `IntOptions::set_date_formats`.
     pub fn value_from_json(&self, json: &JsonValue) -> Result<Value, ValueParsingError> {
         match *json {
             JsonValue::String(ref field_text) => match *self {
-                FieldType::Date(_) => {
-                    let dt_with_fixed_tz: chrono::DateTime<FixedOffset> =
-                        chrono::DateTime::parse_from_rfc3339(field_text).map_err(|err|
-                            ValueParsingError::TypeError(format!(
-                                "Failed to parse date from JSON. Expected rfc3339 format, got {}. {:?}",
-                                field_text, err
-                            ))
-                        )?;
-                    Ok(Value::Date(dt_with_fixed_tz.with_timezone(&Utc)))
-                }
+                FieldType::Date(ref options) => date_from_str(options, field_text),
                 FieldType::Str(_) => Ok(Value::Str(field_text.clone())),
-                FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) => Err(
-                    ValueParsingError::TypeError(format!("Expected an integer, got {:?}", json)),
-                ),
+                FieldType::U64(ref options)
+                | FieldType::I64(ref options)
+                | FieldType::F64(ref options) => {
+                    let number_opt = if options.is_coerce() {
+                        field_text.trim().parse::<Number>().ok()
+                    } else {
+                        None
+                    };
+                    match number_opt {
+                        Some(number) => self.value_from_json(&JsonValue::Number(number)),
+                        None => Err(ValueParsingError::TypeError(format!(
+                            "Expected an integer, got {:?}",
+                            json
+                        ))),
+                    }
+                }
                 FieldType::HierarchicalFacet(_) => Ok(Value::Facet(Facet::from(field_text))),
                 FieldType::Bytes(_) => base64::decode(field_text).map(Value::Bytes).map_err(|_| {
                     ValueParsingError::InvalidBase64(format!(
@@ -172,18 +273,27 @@ impl FieldType {
                         field_text
                     ))
                 }),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError(format!(
+                    "Expected an array of numbers, got {:?}",
+                    json
+                ))),
+                FieldType::Features(_) => Err(ValueParsingError::TypeError(format!(
+                    "Expected an object of feature weights, got {:?}",
+                    json
+                ))),
             },
             JsonValue::Number(ref field_val_num) => match *self {
-                FieldType::I64(_) | FieldType::Date(_) => {
-                    if let Some(field_val_i64) = field_val_num.as_i64() {
+                FieldType::Date(ref options) => date_from_number(options, field_val_num),
+                FieldType::I64(ref options) => {
+                    if let Some(field_val_i64) = int_from_number(options, field_val_num) {
                         Ok(Value::I64(field_val_i64))
                     } else {
                         let msg = format!("Expected an i64 int, got {:?}", json);
                         Err(ValueParsingError::OverflowError(msg))
                     }
                 }
-                FieldType::U64(_) => {
-                    if let Some(field_val_u64) = field_val_num.as_u64() {
+                FieldType::U64(ref options) => {
+                    if let Some(field_val_u64) = int_from_number(options, field_val_num) {
                         Ok(Value::U64(field_val_u64))
                     } else {
                         let msg = format!("Expected a u64 int, got {:?}", json);
@@ -202,8 +312,26 @@ impl FieldType {
                     let msg = format!("Expected a string, got {:?}", json);
                     Err(ValueParsingError::TypeError(msg))
                 }
+                FieldType::Vector(_) => {
+                    let msg = format!("Expected an array of numbers, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
+                FieldType::Features(_) => {
+                    let msg = format!("Expected an object of feature weights, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
             },
-            JsonValue::Object(_) => match *self {
+            JsonValue::Array(ref json_items) if matches!(*self, FieldType::Vector(_)) => {
+                let vector: Option<Vec<f32>> = json_items
+                    .iter()
+                    .map(|json_item| json_item.as_f64().map(|val| val as f32))
+                    .collect();
+                vector.map(Value::Vector).ok_or_else(|| {
+                    let msg = format!("Expected an array of numbers, got {:?}", json);
+                    ValueParsingError::TypeError(msg)
+                })
+            }
+            JsonValue::Object(ref json_object) => match *self {
                 FieldType::Str(_) => {
                     if let Ok(tok_str_val) =
                         serde_json::from_value::<PreTokenizedString>(json.clone())
@@ -217,6 +345,20 @@ impl FieldType {
                         Err(ValueParsingError::TypeError(msg))
                     }
                 }
+                FieldType::Features(_) => {
+                    let features: Option<Vec<(String, f32)>> = json_object
+                        .iter()
+                        .map(|(feature, weight)| {
+                            weight
+                                .as_f64()
+                                .map(|weight| (feature.clone(), weight as f32))
+                        })
+                        .collect();
+                    features.map(Value::Features).ok_or_else(|| {
+                        let msg = format!("Expected an object of feature weights, got {:?}", json);
+                        ValueParsingError::TypeError(msg)
+                    })
+                }
                 _ => {
                     let msg = format!(
                         "Json value not supported error {:?}. Expected {:?}",
@@ -242,10 +384,10 @@ mod tests {
     use crate::schema::field_type::ValueParsingError;
     use crate::schema::TextOptions;
     use crate::schema::Value;
-    use crate::schema::{Schema, INDEXED};
+    use crate::schema::{DateTimeFormat, IntOptions, Schema, INDEXED};
     use crate::tokenizer::{PreTokenizedString, Token};
     use crate::{DateTime, Document};
-    use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 
     #[test]
     fn test_deserialize_json_date() {
@@ -258,6 +400,126 @@ mod tests {
         assert_eq!(format!("{:?}", date), "Date(2019-10-12T05:20:50.520Z)");
     }
 
+    #[test]
+    fn test_deserialize_json_date_formats() {
+        let expected = Value::Date(Utc.with_ymd_and_hms(2021, 11, 5, 11, 30, 0).unwrap());
+        let default_date = FieldType::Date(IntOptions::default());
+        assert_eq!(
+            default_date.value_from_json(&json!("2021-11-05T12:30:00+01:00")),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            default_date.value_from_json(&json!(1_636_111_800)),
+            Ok(expected.clone())
+        );
+        assert!(matches!(
+            default_date.value_from_json(&json!("1636111800")),
+            Err(ValueParsingError::TypeError(_))
+        ));
+
+        let custom_date = FieldType::Date(IntOptions::default().set_date_formats(vec![
+            DateTimeFormat::Strftime("%d/%m/%Y %H:%M".to_string()),
+            DateTimeFormat::UnixMillis,
+        ]));
+        assert_eq!(
+            custom_date.value_from_json(&json!("05/11/2021 11:30")),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            custom_date.value_from_json(&json!(1_636_111_800_000i64)),
+            Ok(expected.clone())
+        );
+        assert!(custom_date
+            .value_from_json(&json!("2021-11-05T12:30:00+01:00"))
+            .is_err());
+        assert!(custom_date
+            .value_from_json(&json!("1636111800000"))
+            .is_err());
+
+        let coerce_date = FieldType::Date(
+            IntOptions::default()
+                .set_date_formats(vec![DateTimeFormat::UnixMillis])
+                .set_coerce(),
+        );
+        assert_eq!(
+            coerce_date.value_from_json(&json!("1636111800000")),
+            Ok(expected)
+        );
+
+        let string_only_date =
+            FieldType::Date(IntOptions::default().set_date_formats(vec![DateTimeFormat::Rfc3339]));
+        assert!(matches!(
+            string_only_date.value_from_json(&json!(1_636_111_800)),
+            Err(ValueParsingError::TypeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_coerce_numbers_from_json() {
+        let strict_u64 = FieldType::U64(IntOptions::default());
+        assert!(strict_u64.value_from_json(&json!("42")).is_err());
+        assert!(strict_u64.value_from_json(&json!(42.0)).is_err());
+
+        let coerce_u64 = FieldType::U64(IntOptions::default().set_coerce());
+        assert_eq!(coerce_u64.value_from_json(&json!(42)), Ok(Value::U64(42)));
+        assert_eq!(
+            coerce_u64.value_from_json(&json!(" 42 ")),
+            Ok(Value::U64(42))
+        );
+        assert_eq!(coerce_u64.value_from_json(&json!(42.0)), Ok(Value::U64(42)));
+        assert_eq!(
+            coerce_u64.value_from_json(&json!("42.0")),
+            Ok(Value::U64(42))
+        );
+        assert!(coerce_u64.value_from_json(&json!(42.5)).is_err());
+        assert!(coerce_u64.value_from_json(&json!(-1)).is_err());
+        assert!(matches!(
+            coerce_u64.value_from_json(&json!("forty-two")),
+            Err(ValueParsingError::TypeError(_))
+        ));
+
+        let coerce_i64 = FieldType::I64(IntOptions::default().set_coerce());
+        assert_eq!(
+            coerce_i64.value_from_json(&json!("-42")),
+            Ok(Value::I64(-42))
+        );
+        assert_eq!(
+            coerce_i64.value_from_json(&json!(-42.0)),
+            Ok(Value::I64(-42))
+        );
+        assert!(coerce_i64.value_from_json(&json!(1e20)).is_err());
+
+        let coerce_f64 = FieldType::F64(IntOptions::default().set_coerce());
+        assert_eq!(
+            coerce_f64.value_from_json(&json!("4.5")),
+            Ok(Value::F64(4.5))
+        );
+        assert_eq!(coerce_f64.value_from_json(&json!(4)), Ok(Value::F64(4.0)));
+    }
+
+    #[test]
+    fn test_parse_document_with_date_formats() {
+        let mut schema_builder = Schema::builder();
+        let date_options = IntOptions::default()
+            .set_indexed()
+            .set_date_formats(vec![DateTimeFormat::Strftime("%Y-%m-%d".to_string())]);
+        let date_field = schema_builder.add_date_field("date", date_options);
+        let count_field = schema_builder.add_u64_field("count", IntOptions::default().set_coerce());
+        let schema = schema_builder.build();
+        let schema_json = serde_json::to_string(&schema).unwrap();
+        let schema: Schema = serde_json::from_str(&schema_json).unwrap();
+        let doc = schema
+            .parse_document(r#"{"date": "2021-11-05", "count": "12"}"#)
+            .unwrap();
+        assert_eq!(
+            doc.get_first(date_field),
+            Some(&Value::Date(
+                Utc.with_ymd_and_hms(2021, 11, 5, 0, 0, 0).unwrap()
+            ))
+        );
+        assert_eq!(doc.get_first(count_field), Some(&Value::U64(12)));
+    }
+
     #[test]
     fn test_serialize_json_date() {
         let mut doc = Document::new();
@@ -330,6 +592,7 @@ mod tests {
                     position: 0,
                     text: String::from("The"),
                     position_length: 1,
+                    payload: Vec::new(),
                 },
                 Token {
                     offset_from: 4,
@@ -337,6 +600,7 @@ mod tests {
                     position: 1,
                     text: String::from("Old"),
                     position_length: 1,
+                    payload: Vec::new(),
                 },
                 Token {
                     offset_from: 8,
@@ -344,6 +608,7 @@ mod tests {
                     position: 2,
                     text: String::from("Man"),
                     position_length: 1,
+                    payload: Vec::new(),
                 },
             ],
         });
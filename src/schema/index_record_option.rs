@@ -48,4 +48,12 @@ impl IndexRecordOption {
             IndexRecordOption::WithFreqsAndPositions => true,
         }
     }
+
+    /// Downgrades to the next level if provided `IndexRecordOption` is unavailable.
+    ///
+    /// This is typically used to clamp the option requested at read time
+    /// to the option the field was actually indexed with.
+    pub fn downgrade(self, other: IndexRecordOption) -> IndexRecordOption {
+        self.min(other)
+    }
 }
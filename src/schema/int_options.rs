@@ -1,4 +1,5 @@
 use crate::schema::flags::{FastFlag, IndexedFlag, SchemaFlagList, StoredFlag};
+use crate::schema::DateTimeFormat;
 use serde::{Deserialize, Serialize};
 use std::ops::BitOr;
 
@@ -24,6 +25,14 @@ pub struct IntOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     fast: Option<Cardinality>,
     stored: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    date_formats: Vec<DateTimeFormat>,
+    #[serde(skip_serializing_if = "is_false")]
+    coerce: bool,
+}
+
+fn is_false(val: &bool) -> bool {
+    !*val
 }
 
 /// For backward compability we add an intermediary to interpret the
@@ -39,6 +48,10 @@ struct IntOptionsDeser {
     #[serde(default)]
     fast: Option<Cardinality>,
     stored: bool,
+    #[serde(default)]
+    date_formats: Vec<DateTimeFormat>,
+    #[serde(default)]
+    coerce: bool,
 }
 
 impl From<IntOptionsDeser> for IntOptions {
@@ -48,6 +61,8 @@ impl From<IntOptionsDeser> for IntOptions {
             fieldnorms: deser.fieldnorms.unwrap_or(deser.indexed),
             fast: deser.fast,
             stored: deser.stored,
+            date_formats: deser.date_formats,
+            coerce: deser.coerce,
         }
     }
 }
@@ -120,6 +135,39 @@ impl IntOptions {
     pub fn get_fastfield_cardinality(&self) -> Option<Cardinality> {
         self.fast
     }
+
+    /// Sets the formats accepted for the values of a date field in JSON documents.
+    ///
+    /// Strings are parsed with the non-numeric formats, numbers with the numeric ones,
+    /// trying the formats in the given order.
+    ///
+    /// By default, dates are expected as RFC 3339 strings, or as timestamps in seconds.
+    /// This option has no effect on the other types of fields.
+    pub fn set_date_formats(mut self, date_formats: Vec<DateTimeFormat>) -> IntOptions {
+        self.date_formats = date_formats;
+        self
+    }
+
+    /// Returns the formats accepted for the values of a date field in JSON documents.
+    ///
+    /// An empty list means the default formats.
+    pub fn date_formats(&self) -> &[DateTimeFormat] {
+        &self.date_formats
+    }
+
+    /// Makes the parsing of the values of the field in JSON documents permissive.
+    ///
+    /// Numbers can then be given as strings (e.g. `"42"`), integers as floats without
+    /// a fractional part (e.g. `42.0`), and dates in a numeric format as strings.
+    pub fn set_coerce(mut self) -> IntOptions {
+        self.coerce = true;
+        self
+    }
+
+    /// Returns true iff the parsing of the values of the field in JSON documents is permissive.
+    pub fn is_coerce(&self) -> bool {
+        self.coerce
+    }
 }
 
 impl From<()> for IntOptions {
@@ -135,6 +183,7 @@ impl From<FastFlag> for IntOptions {
             fieldnorms: false,
             stored: false,
             fast: Some(Cardinality::SingleValue),
+            ..IntOptions::default()
         }
     }
 }
@@ -146,6 +195,7 @@ impl From<StoredFlag> for IntOptions {
             fieldnorms: false,
             stored: true,
             fast: None,
+            ..IntOptions::default()
         }
     }
 }
@@ -157,6 +207,7 @@ impl From<IndexedFlag> for IntOptions {
             fieldnorms: true,
             stored: false,
             fast: None,
+            ..IntOptions::default()
         }
     }
 }
@@ -171,6 +222,12 @@ impl<T: Into<IntOptions>> BitOr<T> for IntOptions {
             fieldnorms: self.fieldnorms | other.fieldnorms,
             stored: self.stored | other.stored,
             fast: self.fast.or(other.fast),
+            date_formats: if self.date_formats.is_empty() {
+                other.date_formats
+            } else {
+                self.date_formats
+            },
+            coerce: self.coerce | other.coerce,
         }
     }
 }
@@ -203,7 +260,8 @@ mod tests {
                 indexed: true,
                 fieldnorms: true,
                 fast: None,
-                stored: false
+                stored: false,
+                ..IntOptions::default()
             }
         );
     }
@@ -221,7 +279,8 @@ mod tests {
                 indexed: false,
                 fieldnorms: false,
                 fast: None,
-                stored: false
+                stored: false,
+                ..IntOptions::default()
             }
         );
     }
@@ -240,7 +299,8 @@ mod tests {
                 indexed: true,
                 fieldnorms: false,
                 fast: None,
-                stored: false
+                stored: false,
+                ..IntOptions::default()
             }
         );
     }
@@ -260,8 +320,24 @@ mod tests {
                 indexed: false,
                 fieldnorms: true,
                 fast: None,
-                stored: false
+                stored: false,
+                ..IntOptions::default()
             }
         );
     }
+
+    #[test]
+    fn test_int_options_date_formats_and_coerce_serialization() {
+        let int_options = IntOptions::default()
+            .set_indexed()
+            .set_date_formats(vec![DateTimeFormat::UnixMillis])
+            .set_coerce();
+        let json = serde_json::to_string(&int_options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"indexed":true,"fieldnorms":false,"stored":false,"date_formats":["unix_millis"],"coerce":true}"#
+        );
+        let deser_int_options: IntOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser_int_options, int_options);
+    }
 }
@@ -113,12 +113,15 @@ mod field_type;
 mod field_value;
 
 mod bytes_options;
+mod date_time_format;
+mod features_options;
 mod field;
 mod index_record_option;
 mod int_options;
 mod named_field_document;
 mod text_options;
 mod value;
+mod vector_options;
 
 mod flags;
 
@@ -136,20 +139,25 @@ pub use self::document::Document;
 pub use self::field::Field;
 pub use self::term::Term;
 
-pub use self::field_entry::FieldEntry;
+pub use self::field_entry::{FieldCapabilities, FieldEntry};
 pub use self::field_type::{FieldType, Type};
 pub use self::field_value::FieldValue;
 
 pub use self::index_record_option::IndexRecordOption;
+pub use self::text_options::FuzzinessOptions;
 pub use self::text_options::TextFieldIndexing;
 pub use self::text_options::TextOptions;
+pub(crate) use self::text_options::DEFAULT_POSITION_GAP;
 pub use self::text_options::STRING;
 pub use self::text_options::TEXT;
 
 pub use self::bytes_options::BytesOptions;
+pub use self::date_time_format::DateTimeFormat;
+pub use self::features_options::FeaturesOptions;
 pub use self::flags::{FAST, INDEXED, STORED};
 pub use self::int_options::Cardinality;
 pub use self::int_options::IntOptions;
+pub use self::vector_options::{VectorOptions, VectorSimilarity};
 
 /// Validator for a potential `field_name`.
 /// Returns true if the name can be use for a field name.
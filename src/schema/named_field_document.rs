@@ -8,5 +8,52 @@ use std::collections::BTreeMap;
 /// A `NamedFieldDocument` is a simple representation of a document
 /// as a `BTreeMap<String, Vec<Value>>`.
 ///
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct NamedFieldDocument(pub BTreeMap<String, Vec<Value>>);
+
+impl NamedFieldDocument {
+    /// Returns all of the `Value`s associated the given field name
+    pub fn get_all(&self, field_name: &str) -> impl Iterator<Item = &Value> {
+        self.0
+            .get(field_name)
+            .map(|values| values.as_slice())
+            .unwrap_or(&[])
+            .iter()
+    }
+
+    /// Returns the first `Value` associated the given field name
+    pub fn get_first(&self, field_name: &str) -> Option<&Value> {
+        self.get_all(field_name).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::{Document, Schema, STORED, TEXT};
+
+    #[test]
+    fn test_named_field_document_accessors() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let count = schema_builder.add_u64_field("count", STORED);
+        let schema = schema_builder.build();
+        let mut doc = Document::default();
+        doc.add_text(title, "hello");
+        doc.add_text(title, "happy");
+        doc.add_u64(count, 3);
+        let named_doc = schema.to_named_doc(&doc);
+        let titles: Vec<&str> = named_doc
+            .get_all("title")
+            .flat_map(|value| value.text())
+            .collect();
+        assert_eq!(titles, &["hello", "happy"]);
+        assert_eq!(
+            named_doc
+                .get_first("count")
+                .and_then(|value| value.u64_value()),
+            Some(3)
+        );
+        assert!(named_doc.get_first("missing").is_none());
+        assert_eq!(named_doc.get_all("missing").count(), 0);
+    }
+}
@@ -174,15 +174,88 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a vector field to the schema.
+    ///
+    /// Each document can have one vector of `field_options.dimension()` values
+    /// for this field, searchable with a
+    /// [`VectorQuery`](../query/struct.VectorQuery.html).
+    pub fn add_vector_field(&mut self, field_name: &str, field_options: VectorOptions) -> Field {
+        let field_entry = FieldEntry::new_vector(field_name.to_string(), field_options);
+        self.add_field(field_entry)
+    }
+
+    /// Adds a features field to the schema.
+    ///
+    /// Each document can have a sparse vector of named features and their
+    /// weights for this field, searchable with a
+    /// [`FeaturesQuery`](../query/struct.FeaturesQuery.html).
+    pub fn add_features_field(
+        &mut self,
+        field_name: &str,
+        field_options: FeaturesOptions,
+    ) -> Field {
+        let field_entry = FieldEntry::new_features(field_name.to_string(), field_options);
+        self.add_field(field_entry)
+    }
+
     /// Adds a field entry to the schema in build.
     pub fn add_field(&mut self, field_entry: FieldEntry) -> Field {
         let field = Field::from_field_id(self.fields.len() as u32);
         let field_name = field_entry.name().to_string();
+        for alias in field_entry.aliases() {
+            self.fields_map.insert(alias.clone(), field);
+        }
         self.fields.push(field_entry);
         self.fields_map.insert(field_name, field);
         field
     }
 
+    /// Adds an alias to a field: `Schema::get_field`, and therefore the
+    /// query parser and the parsing of JSON documents, resolve `alias` to `field`.
+    ///
+    /// Aliases make it possible to rename a field without reindexing: the new
+    /// name is added as an alias of the field, and the schema is updated with
+    /// [`Index::open_with_schema`](../struct.Index.html#method.open_with_schema).
+    /// The documents are still converted to JSON with the name of the field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alias` is not a valid field name, or is already the name
+    /// or the alias of a field.
+    pub fn add_field_alias(&mut self, alias: &str, field: Field) {
+        assert!(
+            is_valid_field_name(alias),
+            "{:?} is not a valid field name.",
+            alias
+        );
+        assert!(
+            !self.fields_map.contains_key(alias),
+            "{:?} is already the name or the alias of a field.",
+            alias
+        );
+        self.fields[field.field_id() as usize].add_alias(alias.to_string());
+        self.fields_map.insert(alias.to_string(), field);
+    }
+
+    /// Adds `field` to the default search fields of the schema, i.e. the fields
+    /// searched by the query parser when a query does not specify a field
+    /// (see [`QueryParser::for_index_default_fields`](../query/struct.QueryParser.html#method.for_index_default_fields)).
+    ///
+    /// The default search fields are persisted with the schema.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `field` is not indexed.
+    pub fn add_default_search_field(&mut self, field: Field) {
+        let field_entry = &mut self.fields[field.field_id() as usize];
+        assert!(
+            field_entry.is_indexed(),
+            "The default search field {:?} is not indexed.",
+            field_entry.name()
+        );
+        field_entry.set_default_search();
+    }
+
     /// Finalize the creation of a `Schema`
     /// This will consume your `SchemaBuilder`
     pub fn build(self) -> Schema {
@@ -254,16 +327,61 @@ impl Schema {
             .map(|(field_id, field_entry)| (Field::from_field_id(field_id as u32), field_entry))
     }
 
+    /// Returns the default search fields of the schema, in the order of the schema.
+    pub fn default_search_fields(&self) -> Vec<Field> {
+        self.fields()
+            .filter(|(_, field_entry)| field_entry.is_default_search())
+            .map(|(field, _)| field)
+            .collect()
+    }
+
+    /// Returns what `field` supports, e.g. to check that its positions are
+    /// indexed before running a phrase query.
+    pub fn field_capabilities(&self, field: Field) -> FieldCapabilities {
+        self.get_field_entry(field).capabilities()
+    }
+
     /// Creates a new builder.
     pub fn builder() -> SchemaBuilder {
         SchemaBuilder::default()
     }
 
-    /// Returns the field option associated with a given name.
+    /// Returns the field associated with a given name or alias.
     pub fn get_field(&self, field_name: &str) -> Option<Field> {
         self.0.fields_map.get(field_name).cloned()
     }
 
+    /// Checks that this schema can replace `previous_schema` on an existing index.
+    ///
+    /// The fields of `previous_schema` must be kept unchanged and in the same order:
+    /// the only modifications allowed are the addition of new fields at the end,
+    /// the addition or removal of field aliases, and changes of the default search fields.
+    pub fn check_extends(&self, previous_schema: &Schema) -> crate::Result<()> {
+        if self.num_fields() < previous_schema.num_fields() {
+            return Err(crate::TantivyError::SchemaError(format!(
+                "The schema has {} fields, but the index schema has {} fields. Fields cannot \
+                 be removed.",
+                self.num_fields(),
+                previous_schema.num_fields()
+            )));
+        }
+        for (field_entry, previous_field_entry) in
+            self.0.fields.iter().zip(&previous_schema.0.fields)
+        {
+            if field_entry.name() != previous_field_entry.name()
+                || field_entry.field_type() != previous_field_entry.field_type()
+            {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "Field {:?} is incompatible with the field {:?} of the index schema. \
+                     Existing fields cannot be modified.",
+                    field_entry.name(),
+                    previous_field_entry.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Create a named document off the doc.
     pub fn convert_named_doc(
         &self,
@@ -296,7 +414,7 @@ impl Schema {
         NamedFieldDocument(field_map)
     }
 
-    /// Encode the schema in JSON.
+    /// Encode the document in JSON, using the field names of the schema.
     ///
     /// Encoding a document cannot fail.
     pub fn to_json(&self, doc: &Document) -> String {
@@ -307,10 +425,9 @@ impl Schema {
     pub fn parse_document(&self, doc_json: &str) -> Result<Document, DocParsingError> {
         let json_obj: JsonObject<String, JsonValue> =
             serde_json::from_str(doc_json).map_err(|_| {
-                let doc_json_sample: String = if doc_json.len() < 20 {
-                    String::from(doc_json)
-                } else {
-                    format!("{:?}...", &doc_json[0..20])
+                let doc_json_sample: String = match doc_json.char_indices().nth(20) {
+                    Some((sample_len, _)) => format!("{:?}...", &doc_json[..sample_len]),
+                    None => String::from(doc_json),
                 };
                 DocParsingError::NotJson(doc_json_sample)
             })?;
@@ -321,7 +438,11 @@ impl Schema {
                 let field_entry = self.get_field_entry(field);
                 let field_type = field_entry.field_type();
                 match *json_value {
-                    JsonValue::Array(ref json_items) => {
+                    // The values of a vector are given as an array of numbers.
+                    JsonValue::Array(ref json_items)
+                        if !(matches!(field_type, FieldType::Vector(_))
+                            && json_items.iter().all(JsonValue::is_number)) =>
+                    {
                         for json_item in json_items {
                             let value = field_type
                                 .value_from_json(json_item)
@@ -748,6 +869,194 @@ mod tests {
             );
             assert_matches!(json_err, Err(NotJson(_)));
         }
+        {
+            let json_err = schema.parse_document(r#"{"title": "ééééééééééééééééééééé"#);
+            assert_eq!(
+                json_err,
+                Err(NotJson(r#""{\"title\": \"ééééééééé"..."#.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_parse_document_vector() {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder.add_vector_field(
+            "embedding",
+            VectorOptions::new(3)
+                .set_similarity(VectorSimilarity::DotProduct)
+                .set_stored(),
+        );
+        let schema = schema_builder.build();
+        let doc = schema
+            .parse_document(r#"{"embedding": [1.0, -2, 0.5]}"#)
+            .unwrap();
+        assert_eq!(
+            doc.get_first(embedding).unwrap().vector_value(),
+            Some(&[1.0f32, -2.0, 0.5][..])
+        );
+        assert_eq!(schema.to_json(&doc), r#"{"embedding":[[1.0,-2.0,0.5]]}"#);
+        assert_matches!(
+            schema.parse_document(r#"{"embedding": "abc"}"#),
+            Err(DocParsingError::ValueError(
+                _,
+                ValueParsingError::TypeError(_)
+            ))
+        );
+        let schema_json = serde_json::to_string(&schema).unwrap();
+        assert!(schema_json.contains(r#""similarity":"dot_product""#));
+        let schema_from_json: Schema = serde_json::from_str(&schema_json).unwrap();
+        assert_eq!(schema_from_json, schema);
+    }
+
+    #[test]
+    pub fn test_schema_check_extends() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_u64_field("count", FAST);
+        let schema = schema_builder.build();
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_u64_field("count", FAST);
+        schema_builder.add_text_field("body", TEXT);
+        let extended_schema = schema_builder.build();
+        assert!(schema.check_extends(&schema).is_ok());
+        assert!(extended_schema.check_extends(&schema).is_ok());
+        assert!(schema.check_extends(&extended_schema).is_err());
+
+        // changing the type of an existing field.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_i64_field("count", FAST);
+        schema_builder.add_text_field("body", TEXT);
+        assert!(schema_builder.build().check_extends(&schema).is_err());
+
+        // inserting a field before the existing ones.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_u64_field("count", FAST);
+        assert!(schema_builder.build().check_extends(&schema).is_err());
+    }
+
+    #[test]
+    pub fn test_field_alias() {
+        let mut schema_builder = Schema::builder();
+        let content = schema_builder.add_text_field("content", TEXT | STORED);
+        let count = schema_builder.add_u64_field("count", FAST);
+        schema_builder.add_field_alias("body", content);
+        schema_builder.add_field_alias("text", content);
+        let schema = schema_builder.build();
+        assert_eq!(schema.get_field("content"), Some(content));
+        assert_eq!(schema.get_field("body"), Some(content));
+        assert_eq!(schema.get_field("text"), Some(content));
+        assert_eq!(schema.get_field_name(content), "content");
+        assert_eq!(schema.get_field_entry(content).aliases(), &["body", "text"]);
+        assert!(schema.get_field_entry(count).aliases().is_empty());
+
+        let doc = schema
+            .parse_document(r#"{"body": "hello", "content": "happy tax payer"}"#)
+            .unwrap();
+        assert_eq!(doc.get_all(content).count(), 2);
+        assert_eq!(
+            schema.to_json(&doc),
+            r#"{"content":["hello","happy tax payer"]}"#
+        );
+
+        // the aliases are kept on serialization.
+        let schema_json = serde_json::to_string(&schema).unwrap();
+        assert!(schema_json.contains(r#""aliases":["body","text"]"#));
+        assert_eq!(schema_json.matches("aliases").count(), 1);
+        let deserialized_schema: Schema = serde_json::from_str(&schema_json).unwrap();
+        assert_eq!(deserialized_schema, schema);
+        assert_eq!(deserialized_schema.get_field("body"), Some(content));
+
+        // aliases can be added to the fields of an existing schema.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("content", TEXT | STORED);
+        schema_builder.add_u64_field("count", FAST);
+        let schema_without_aliases = schema_builder.build();
+        assert!(schema.check_extends(&schema_without_aliases).is_ok());
+        assert!(schema_without_aliases.check_extends(&schema).is_ok());
+        assert_ne!(schema_without_aliases, schema);
+    }
+
+    #[test]
+    #[should_panic(expected = "is already the name or the alias of a field")]
+    pub fn test_field_alias_conflict() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_field_alias("body", title);
+    }
+
+    #[test]
+    pub fn test_default_search_fields() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("id", STRING);
+        let body = schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_default_search_field(body);
+        schema_builder.add_default_search_field(title);
+        let schema = schema_builder.build();
+        assert_eq!(schema.default_search_fields(), vec![title, body]);
+        assert!(schema.get_field_entry(title).is_default_search());
+
+        // the default search fields are kept on serialization.
+        let schema_json = serde_json::to_string(&schema).unwrap();
+        assert_eq!(schema_json.matches(r#""default_search":true"#).count(), 2);
+        let deserialized_schema: Schema = serde_json::from_str(&schema_json).unwrap();
+        assert_eq!(
+            deserialized_schema.default_search_fields(),
+            vec![title, body]
+        );
+        assert!(deserialized_schema.check_extends(&schema).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "The default search field \"stored\" is not indexed.")]
+    pub fn test_default_search_field_not_indexed() {
+        let mut schema_builder = Schema::builder();
+        let stored = schema_builder.add_text_field("stored", STORED);
+        schema_builder.add_default_search_field(stored);
+    }
+
+    #[test]
+    pub fn test_field_capabilities() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let id = schema_builder.add_text_field("id", STRING);
+        let count = schema_builder.add_u64_field("count", FAST);
+        let facet = schema_builder.add_facet_field("facet", FacetOptions::default());
+        let bytes = schema_builder.add_bytes_field("bytes", BytesOptions::default().set_fast());
+        let schema = schema_builder.build();
+        assert_eq!(
+            schema.field_capabilities(title),
+            FieldCapabilities {
+                is_indexed: true,
+                has_freqs: true,
+                has_positions: true,
+                is_fast: false,
+                is_stored: true,
+            }
+        );
+        assert_eq!(
+            schema.field_capabilities(id),
+            FieldCapabilities {
+                is_indexed: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            schema.field_capabilities(count),
+            FieldCapabilities {
+                is_fast: true,
+                ..Default::default()
+            }
+        );
+        assert!(schema.field_capabilities(facet).is_fast);
+        assert!(schema.field_capabilities(bytes).is_fast);
     }
 
     #[test]
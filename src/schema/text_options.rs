@@ -42,11 +42,98 @@ impl TextOptions {
 /// - the amount of information that should be stored about the presence of a term in a document.
 /// Essentially, should we store the term frequency and/or the positions (See [`IndexRecordOption`](./enum.IndexRecordOption.html)).
 /// - the name of the `Tokenizer` that should be used to process the field.
+/// - whether the payloads attached to the tokens should be recorded.
+/// - the position gap inserted between the values of a multi-valued field.
+/// - the maximum term frequency recorded for a term in a document.
+/// - the default fuzziness of the fuzzy queries on the field.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct TextFieldIndexing {
     record: IndexRecordOption,
     fieldnorms: bool,
     tokenizer: Cow<'static, str>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    payloads: bool,
+    #[serde(
+        default = "default_position_gap",
+        skip_serializing_if = "is_default_position_gap"
+    )]
+    position_gap: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_term_freq: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fuzziness: Option<FuzzinessOptions>,
+}
+
+/// Default fuzziness of the fuzzy queries on a text field.
+///
+/// See [`TextFieldIndexing::set_fuzziness`](./struct.TextFieldIndexing.html#method.set_fuzziness)
+/// and [`FuzzyTermQuery::from_field_defaults`](../query/struct.FuzzyTermQuery.html#method.from_field_defaults).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct FuzzinessOptions {
+    distance: u8,
+    transposition_cost_one: bool,
+    #[serde(default)]
+    exact_prefix_len: usize,
+}
+
+impl FuzzinessOptions {
+    /// Creates fuzziness options allowing `distance` edits, transpositions
+    /// costing one edit, and no exact prefix.
+    pub fn new(distance: u8) -> FuzzinessOptions {
+        FuzzinessOptions {
+            distance,
+            transposition_cost_one: true,
+            exact_prefix_len: 0,
+        }
+    }
+
+    /// Sets whether a transposition costs one edit, rather than two.
+    pub fn set_transposition_cost_one(mut self, transposition_cost_one: bool) -> FuzzinessOptions {
+        self.transposition_cost_one = transposition_cost_one;
+        self
+    }
+
+    /// Sets the number of leading characters of the terms that have to match exactly.
+    pub fn set_exact_prefix_len(mut self, exact_prefix_len: usize) -> FuzzinessOptions {
+        self.exact_prefix_len = exact_prefix_len;
+        self
+    }
+
+    /// Returns the maximum Levenshtein distance.
+    pub fn distance(&self) -> u8 {
+        self.distance
+    }
+
+    /// Returns true iff a transposition costs one edit.
+    pub fn transposition_cost_one(&self) -> bool {
+        self.transposition_cost_one
+    }
+
+    /// Returns the number of leading characters that have to match exactly.
+    pub fn exact_prefix_len(&self) -> usize {
+        self.exact_prefix_len
+    }
+}
+
+impl Default for FuzzinessOptions {
+    fn default() -> FuzzinessOptions {
+        FuzzinessOptions::new(1)
+    }
+}
+
+/// Default number of positions left empty between two values of a text field.
+pub(crate) const DEFAULT_POSITION_GAP: u32 = 1;
+
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
+fn default_position_gap() -> u32 {
+    DEFAULT_POSITION_GAP
+}
+
+fn is_default_position_gap(position_gap: &u32) -> bool {
+    *position_gap == DEFAULT_POSITION_GAP
 }
 
 impl Default for TextFieldIndexing {
@@ -55,6 +142,10 @@ impl Default for TextFieldIndexing {
             tokenizer: Cow::Borrowed("default"),
             record: IndexRecordOption::Basic,
             fieldnorms: true,
+            payloads: false,
+            position_gap: DEFAULT_POSITION_GAP,
+            max_term_freq: None,
+            fuzziness: None,
         }
     }
 }
@@ -96,6 +187,79 @@ impl TextFieldIndexing {
     pub fn index_option(&self) -> IndexRecordOption {
         self.record
     }
+
+    /// Sets whether the payloads of the tokens (see [`Token::payload`](../tokenizer/struct.Token.html#structfield.payload))
+    /// should be recorded, one per position.
+    ///
+    /// Payloads are only recorded if the field is indexed with
+    /// `IndexRecordOption::WithFreqsAndPositions`.
+    /// They can then be read from the
+    /// [`SegmentPostings`](../postings/struct.SegmentPostings.html#method.payloads).
+    pub fn set_payloads(mut self, payloads: bool) -> TextFieldIndexing {
+        self.payloads = payloads;
+        self
+    }
+
+    /// Returns true iff the payloads of the tokens are recorded.
+    pub fn payloads(&self) -> bool {
+        self.payloads && self.record.has_positions()
+    }
+
+    /// Sets the number of positions left empty between the values
+    /// of a document for this field (by default `1`).
+    ///
+    /// When a document has several values for the field, their tokens are indexed one
+    /// after the other. The gap prevents a `PhraseQuery` from matching across two values.
+    /// A `PhraseQuery` with a slop lower than the gap cannot match across two values.
+    pub fn set_position_gap(mut self, position_gap: u32) -> TextFieldIndexing {
+        self.position_gap = position_gap;
+        self
+    }
+
+    /// Returns the number of positions left empty between the values of a document.
+    pub fn position_gap(&self) -> u32 {
+        self.position_gap
+    }
+
+    /// Sets the maximum number of occurrences of a term recorded for a document.
+    ///
+    /// The occurrences beyond the first `max_term_freq` ones are not recorded:
+    /// they do not contribute to the term frequency, and their positions are dropped.
+    /// They are still counted in the fieldnorm.
+    ///
+    /// This bounds the size of the postings, and the score, of documents
+    /// repeating a term many times.
+    ///
+    /// # Panics
+    /// If `max_term_freq` is 0.
+    pub fn set_max_term_freq(mut self, max_term_freq: u32) -> TextFieldIndexing {
+        assert!(
+            max_term_freq > 0,
+            "The maximum term frequency must be positive."
+        );
+        self.max_term_freq = Some(max_term_freq);
+        self
+    }
+
+    /// Returns the maximum number of occurrences of a term recorded for a document,
+    /// if any.
+    pub fn max_term_freq(&self) -> Option<u32> {
+        self.max_term_freq
+    }
+
+    /// Sets the default fuzziness of the fuzzy queries on the field.
+    ///
+    /// Requiring an exact prefix shrinks the part of the term dictionary
+    /// the fuzzy queries have to explore.
+    pub fn set_fuzziness(mut self, fuzziness: FuzzinessOptions) -> TextFieldIndexing {
+        self.fuzziness = Some(fuzziness);
+        self
+    }
+
+    /// Returns the default fuzziness of the fuzzy queries on the field, if any.
+    pub fn fuzziness(&self) -> Option<FuzzinessOptions> {
+        self.fuzziness
+    }
 }
 
 /// The field will be untokenized and indexed.
@@ -104,6 +268,10 @@ pub const STRING: TextOptions = TextOptions {
         tokenizer: Cow::Borrowed("raw"),
         fieldnorms: true,
         record: IndexRecordOption::Basic,
+        payloads: false,
+        position_gap: DEFAULT_POSITION_GAP,
+        max_term_freq: None,
+        fuzziness: None,
     }),
     stored: false,
 };
@@ -114,6 +282,10 @@ pub const TEXT: TextOptions = TextOptions {
         tokenizer: Cow::Borrowed("default"),
         fieldnorms: true,
         record: IndexRecordOption::WithFreqsAndPositions,
+        payloads: false,
+        position_gap: DEFAULT_POSITION_GAP,
+        max_term_freq: None,
+        fuzziness: None,
     }),
     stored: false,
 };
@@ -175,6 +347,69 @@ mod tests {
                 if text_options.get_indexing_options().unwrap().tokenizer() == "default"));
     }
 
+    #[test]
+    fn test_text_field_indexing_payloads() {
+        let indexing = TextFieldIndexing::default()
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+            .set_payloads(true);
+        assert!(indexing.payloads());
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""payloads":true"#));
+        let deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser, indexing);
+        // Payloads require positions.
+        assert!(!indexing
+            .set_index_option(IndexRecordOption::WithFreqs)
+            .payloads());
+        // Payloads are not serialized when disabled.
+        let json = serde_json::to_string(&TextFieldIndexing::default()).unwrap();
+        assert!(!json.contains("payloads"));
+    }
+
+    #[test]
+    fn test_text_field_indexing_position_gap() {
+        assert_eq!(TextFieldIndexing::default().position_gap(), 1);
+        let indexing = TextFieldIndexing::default().set_position_gap(100);
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""position_gap":100"#));
+        let deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser.position_gap(), 100);
+        // The default gap is not serialized.
+        let json = serde_json::to_string(&TextFieldIndexing::default()).unwrap();
+        assert!(!json.contains("position_gap"));
+        let deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser.position_gap(), 1);
+    }
+
+    #[test]
+    fn test_text_field_indexing_max_term_freq() {
+        assert_eq!(TextFieldIndexing::default().max_term_freq(), None);
+        let indexing = TextFieldIndexing::default().set_max_term_freq(3);
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""max_term_freq":3"#));
+        let deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser.max_term_freq(), Some(3));
+        let json = serde_json::to_string(&TextFieldIndexing::default()).unwrap();
+        assert!(!json.contains("max_term_freq"));
+    }
+
+    #[test]
+    fn test_text_field_indexing_fuzziness() {
+        assert_eq!(TextFieldIndexing::default().fuzziness(), None);
+        let fuzziness = FuzzinessOptions::new(2)
+            .set_transposition_cost_one(false)
+            .set_exact_prefix_len(3);
+        let indexing = TextFieldIndexing::default().set_fuzziness(fuzziness);
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(
+            r#""fuzziness":{"distance":2,"transposition_cost_one":false,"exact_prefix_len":3}"#
+        ));
+        let deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deser.fuzziness(), Some(fuzziness));
+        let json = serde_json::to_string(&TextFieldIndexing::default()).unwrap();
+        assert!(!json.contains("fuzziness"));
+    }
+
     #[test]
     fn test_cmp_index_record_option() {
         assert!(IndexRecordOption::WithFreqsAndPositions > IndexRecordOption::WithFreqs);
@@ -25,6 +25,10 @@ pub enum Value {
     Facet(Facet),
     /// Arbitrarily sized byte array
     Bytes(Vec<u8>),
+    /// Vector of `f32`
+    Vector(Vec<f32>),
+    /// Named features and their weights
+    Features(Vec<(String, f32)>),
 }
 
 impl Eq for Value {}
@@ -43,6 +47,8 @@ impl Ord for Value {
             (Value::Date(l), Value::Date(r)) => l.cmp(r),
             (Value::Facet(l), Value::Facet(r)) => l.cmp(r),
             (Value::Bytes(l), Value::Bytes(r)) => l.cmp(r),
+            (Value::Vector(l), Value::Vector(r)) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
+            (Value::Features(l), Value::Features(r)) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
             (Value::F64(l), Value::F64(r)) => {
                 match (l.is_nan(), r.is_nan()) {
                     (false, false) => l.partial_cmp(r).unwrap(), // only fail on NaN
@@ -65,6 +71,10 @@ impl Ord for Value {
             (_, Value::Date(_)) => Ordering::Greater,
             (Value::Facet(_), _) => Ordering::Less,
             (_, Value::Facet(_)) => Ordering::Greater,
+            (Value::Bytes(_), _) => Ordering::Less,
+            (_, Value::Bytes(_)) => Ordering::Greater,
+            (Value::Vector(_), _) => Ordering::Less,
+            (_, Value::Vector(_)) => Ordering::Greater,
         }
     }
 }
@@ -83,6 +93,12 @@ impl Serialize for Value {
             Value::Date(ref date) => serializer.serialize_str(&date.to_rfc3339()),
             Value::Facet(ref facet) => facet.serialize(serializer),
             Value::Bytes(ref bytes) => serializer.serialize_bytes(bytes),
+            Value::Vector(ref vector) => vector.serialize(serializer),
+            Value::Features(ref features) => serializer.collect_map(
+                features
+                    .iter()
+                    .map(|(feature, weight)| (feature.as_str(), weight)),
+            ),
         }
     }
 }
@@ -210,6 +226,28 @@ impl Value {
             None
         }
     }
+
+    /// Returns the features and their weights, provided the value is of the `Features` type.
+    ///
+    /// Returns None if the value is not of type `Features`.
+    pub fn features_value(&self) -> Option<&[(String, f32)]> {
+        if let Value::Features(features) = self {
+            Some(features)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the vector value, provided the value is of the `Vector` type.
+    ///
+    /// Returns None if the value is not of type `Vector`.
+    pub fn vector_value(&self) -> Option<&[f32]> {
+        if let Value::Vector(vector) = self {
+            Some(vector)
+        } else {
+            None
+        }
+    }
 }
 
 impl From<String> for Value {
@@ -272,12 +310,14 @@ impl From<PreTokenizedString> for Value {
     }
 }
 
+pub(crate) use self::binary_serialize::skip_value;
+
 mod binary_serialize {
     use super::Value;
     use crate::schema::Facet;
     use crate::tokenizer::PreTokenizedString;
     use chrono::{TimeZone, Utc};
-    use common::{f64_to_u64, u64_to_f64, BinarySerializable};
+    use common::{f64_to_u64, u64_to_f64, BinarySerializable, VInt};
     use std::io::{self, Read, Write};
 
     const TEXT_CODE: u8 = 0;
@@ -288,11 +328,56 @@ mod binary_serialize {
     const DATE_CODE: u8 = 5;
     const F64_CODE: u8 = 6;
     const EXT_CODE: u8 = 7;
+    const VECTOR_CODE: u8 = 8;
+    const FEATURES_CODE: u8 = 9;
 
     // extended types
 
     const TOK_STR_CODE: u8 = 0;
 
+    /// Advances `reader` past a serialized `Value`, without deserializing it.
+    pub(crate) fn skip_value(reader: &mut &[u8]) -> io::Result<()> {
+        let type_code = u8::deserialize(reader)?;
+        let num_bytes = match type_code {
+            U64_CODE | I64_CODE | F64_CODE | DATE_CODE => 8,
+            TEXT_CODE | HIERARCHICAL_FACET_CODE | BYTES_CODE => {
+                VInt::deserialize(reader)?.val() as usize
+            }
+            VECTOR_CODE => VInt::deserialize(reader)?.val() as usize * 4,
+            FEATURES_CODE => {
+                let num_features = VInt::deserialize(reader)?.val();
+                for _ in 0..num_features {
+                    let feature_len = VInt::deserialize(reader)?.val() as usize;
+                    skip_bytes(reader, feature_len + 4)?;
+                }
+                0
+            }
+            EXT_CODE => {
+                // All of the extended types are serialized as strings.
+                u8::deserialize(reader)?;
+                VInt::deserialize(reader)?.val() as usize
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("No field type is associated with code {:?}", type_code),
+                ))
+            }
+        };
+        skip_bytes(reader, num_bytes)
+    }
+
+    fn skip_bytes(reader: &mut &[u8], num_bytes: usize) -> io::Result<()> {
+        if reader.len() < num_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Truncated field value.",
+            ));
+        }
+        *reader = &reader[num_bytes..];
+        Ok(())
+    }
+
     impl BinarySerializable for Value {
         fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
             match *self {
@@ -336,6 +421,23 @@ mod binary_serialize {
                     BYTES_CODE.serialize(writer)?;
                     bytes.serialize(writer)
                 }
+                Value::Vector(ref vector) => {
+                    VECTOR_CODE.serialize(writer)?;
+                    VInt(vector.len() as u64).serialize(writer)?;
+                    for val in vector {
+                        val.serialize(writer)?;
+                    }
+                    Ok(())
+                }
+                Value::Features(ref features) => {
+                    FEATURES_CODE.serialize(writer)?;
+                    VInt(features.len() as u64).serialize(writer)?;
+                    for (feature, weight) in features {
+                        feature.serialize(writer)?;
+                        weight.serialize(writer)?;
+                    }
+                    Ok(())
+                }
             }
         }
         fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
@@ -363,6 +465,20 @@ mod binary_serialize {
                 }
                 HIERARCHICAL_FACET_CODE => Ok(Value::Facet(Facet::deserialize(reader)?)),
                 BYTES_CODE => Ok(Value::Bytes(Vec::<u8>::deserialize(reader)?)),
+                VECTOR_CODE => {
+                    let len = VInt::deserialize(reader)?.val() as usize;
+                    let vector = (0..len)
+                        .map(|_| f32::deserialize(reader))
+                        .collect::<io::Result<Vec<f32>>>()?;
+                    Ok(Value::Vector(vector))
+                }
+                FEATURES_CODE => {
+                    let num_features = VInt::deserialize(reader)?.val() as usize;
+                    let features = (0..num_features)
+                        .map(|_| Ok((String::deserialize(reader)?, f32::deserialize(reader)?)))
+                        .collect::<io::Result<Vec<(String, f32)>>>()?;
+                    Ok(Value::Features(features))
+                }
                 EXT_CODE => {
                     let ext_type_code = u8::deserialize(reader)?;
                     match ext_type_code {
@@ -400,6 +516,7 @@ mod binary_serialize {
 mod tests {
     use super::Value;
     use crate::DateTime;
+    use common::BinarySerializable;
     use std::str::FromStr;
 
     #[test]
@@ -408,4 +525,20 @@ mod tests {
         let serialized_value_json = serde_json::to_string_pretty(&value).unwrap();
         assert_eq!(serialized_value_json, r#""1996-12-20T00:39:57+00:00""#);
     }
+
+    #[test]
+    fn test_serialize_features() {
+        let value = Value::Features(vec![("a".to_string(), 0.5), ("bc".to_string(), 2.0)]);
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"a":0.5,"bc":2.0}"#
+        );
+        let mut buffer = Vec::new();
+        value.serialize(&mut buffer).unwrap();
+        buffer.push(42u8);
+        assert_eq!(Value::deserialize(&mut &buffer[..]).unwrap(), value);
+        let mut reader = &buffer[..];
+        super::binary_serialize::skip_value(&mut reader).unwrap();
+        assert_eq!(reader, &[42u8]);
+    }
 }
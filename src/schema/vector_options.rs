@@ -0,0 +1,126 @@
+use crate::Score;
+use serde::{Deserialize, Serialize};
+
+/// Similarity function used to compare the vectors of a vector field.
+///
+/// All of the similarities are "higher is closer".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorSimilarity {
+    /// Cosine of the angle between the two vectors.
+    #[default]
+    Cosine,
+    /// Dot product of the two vectors. Equivalent to the cosine
+    /// similarity for normalized vectors, and cheaper to compute.
+    DotProduct,
+    /// `1 / (1 + d²)`, where `d` is the euclidean distance between the two vectors.
+    Euclidean,
+}
+
+impl VectorSimilarity {
+    /// Computes the similarity of two vectors of the same dimension.
+    pub fn score(self, left: &[f32], right: &[f32]) -> Score {
+        debug_assert_eq!(left.len(), right.len());
+        match self {
+            VectorSimilarity::DotProduct => dot(left, right),
+            VectorSimilarity::Cosine => {
+                let norms = (dot(left, left) * dot(right, right)).sqrt();
+                if norms == 0.0 {
+                    0.0
+                } else {
+                    dot(left, right) / norms
+                }
+            }
+            VectorSimilarity::Euclidean => {
+                let squared_dist: f32 = left
+                    .iter()
+                    .zip(right)
+                    .map(|(left_val, right_val)| (left_val - right_val) * (left_val - right_val))
+                    .sum();
+                1.0 / (1.0 + squared_dist)
+            }
+        }
+    }
+}
+
+fn dot(left: &[f32], right: &[f32]) -> f32 {
+    left.iter()
+        .zip(right)
+        .map(|(left_val, right_val)| left_val * right_val)
+        .sum()
+}
+
+/// Define how a vector field should be handled by tantivy.
+///
+/// Each document has at most one vector, of exactly `dimension` values.
+/// The vectors are not indexed in the inverted index: they are written to the
+/// vector component of the segment, along with an approximate nearest neighbor
+/// index, and searched with a `VectorQuery`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dimension: usize,
+    #[serde(default)]
+    similarity: VectorSimilarity,
+    stored: bool,
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field of the given dimension,
+    /// compared with the cosine similarity.
+    pub fn new(dimension: usize) -> VectorOptions {
+        VectorOptions {
+            dimension,
+            similarity: VectorSimilarity::default(),
+            stored: false,
+        }
+    }
+
+    /// Returns the number of values of the vectors.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the similarity used to compare the vectors.
+    pub fn similarity(&self) -> VectorSimilarity {
+        self.similarity
+    }
+
+    /// Returns true iff the vectors are also stored in the doc store.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Sets the similarity used to compare the vectors.
+    pub fn set_similarity(mut self, similarity: VectorSimilarity) -> VectorOptions {
+        self.similarity = similarity;
+        self
+    }
+
+    /// Set the field as stored.
+    ///
+    /// The vectors can be read from the `VectorReader` of the segments in any case,
+    /// so this is only useful to retrieve them with the rest of the document.
+    pub fn set_stored(mut self) -> VectorOptions {
+        self.stored = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VectorSimilarity;
+    use crate::assert_nearly_equals;
+
+    #[test]
+    fn test_vector_similarity() {
+        let left = [1.0, 0.0];
+        let right = [2.0, 2.0];
+        assert_nearly_equals!(VectorSimilarity::DotProduct.score(&left, &right), 2.0);
+        assert_nearly_equals!(
+            VectorSimilarity::Cosine.score(&left, &right),
+            std::f32::consts::FRAC_1_SQRT_2
+        );
+        assert_nearly_equals!(VectorSimilarity::Euclidean.score(&left, &right), 1.0 / 6.0);
+        assert_nearly_equals!(VectorSimilarity::Cosine.score(&left, &[0.0, 0.0]), 0.0);
+    }
+}
@@ -47,6 +47,30 @@ impl FragmentCandidate {
             self.highlighted.push(token.offset_from..token.offset_to);
         }
     }
+
+    /// Shifts the offsets of the fragment by `offset` bytes.
+    fn shift(mut self, offset: usize) -> FragmentCandidate {
+        self.start_offset += offset;
+        self.stop_offset += offset;
+        for highlighted in &mut self.highlighted {
+            *highlighted = highlighted.start + offset..highlighted.end + offset;
+        }
+        self
+    }
+
+    /// Creates the snippet of the fragment of `text`.
+    fn to_snippet(&self, text: &str) -> Snippet {
+        let fragment_text = &text[self.start_offset..self.stop_offset];
+        let highlighted = self
+            .highlighted
+            .iter()
+            .map(|item| item.start - self.start_offset..item.end - self.start_offset)
+            .collect();
+        Snippet {
+            fragments: fragment_text.to_string(),
+            highlighted,
+        }
+    }
 }
 
 /// `Snippet`
@@ -144,6 +168,39 @@ fn search_fragments<'a>(
     fragments
 }
 
+/// Returns the byte ranges of the sentences of `text`.
+///
+/// A sentence ends after a `.`, `!` or `?` followed by a whitespace, or at an empty line.
+/// The whitespaces between sentences are not part of them.
+fn sentence_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut sentence_start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if c.is_whitespace() && sentence_start.is_none() {
+            continue;
+        }
+        let start = *sentence_start.get_or_insert(offset);
+        let next_is_whitespace = chars
+            .peek()
+            .map(|&(_, next)| next.is_whitespace())
+            .unwrap_or(false);
+        let next_is_newline = chars.peek().map(|&(_, next)| next == '\n').unwrap_or(false);
+        if c == '\n' && next_is_newline {
+            sentences.push(start..offset);
+            sentence_start = None;
+        } else if matches!(c, '.' | '!' | '?') && next_is_whitespace {
+            sentences.push(start..offset + c.len_utf8());
+            sentence_start = None;
+        }
+    }
+    if let Some(start) = sentence_start {
+        sentences.push(start..text.len());
+    }
+    sentences.retain(|sentence| !text[sentence.clone()].trim().is_empty());
+    sentences
+}
+
 /// Returns a Snippet
 ///
 /// Takes a vector of `FragmentCandidate`s and the text.
@@ -161,16 +218,7 @@ fn select_best_fragment_combination(fragments: &[FragmentCandidate], text: &str)
         }
     });
     if let Some(fragment) = best_fragment_opt {
-        let fragment_text = &text[fragment.start_offset..fragment.stop_offset];
-        let highlighted = fragment
-            .highlighted
-            .iter()
-            .map(|item| item.start - fragment.start_offset..item.end - fragment.start_offset)
-            .collect();
-        Snippet {
-            fragments: fragment_text.to_string(),
-            highlighted,
-        }
+        fragment.to_snippet(text)
     } else {
         // when there no fragments to chose from,
         // for now create a empty snippet
@@ -181,6 +229,96 @@ fn select_best_fragment_combination(fragments: &[FragmentCandidate], text: &str)
     }
 }
 
+/// Where the fragments of a text can be cut.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FragmentBoundary {
+    /// The fragments are cut between two tokens.
+    Word,
+    /// The fragments are made of a single sentence, and only the sentences longer
+    /// than the maximum number of chars of a fragment are cut between two tokens.
+    Sentence,
+}
+
+/// Configures how the texts are cut into fragments, and how many of them are kept.
+///
+/// See [`MultiFieldSnippetGenerator`](./struct.MultiFieldSnippetGenerator.html).
+#[derive(Clone, Debug)]
+pub struct FragmenterConfig {
+    max_num_chars: usize,
+    num_fragments: usize,
+    boundary: FragmentBoundary,
+}
+
+impl Default for FragmenterConfig {
+    fn default() -> Self {
+        FragmenterConfig {
+            max_num_chars: DEFAULT_MAX_NUM_CHARS,
+            num_fragments: 1,
+            boundary: FragmentBoundary::Word,
+        }
+    }
+}
+
+impl FragmenterConfig {
+    /// Sets the maximum number of chars of a fragment.
+    pub fn with_max_num_chars(mut self, max_num_chars: usize) -> Self {
+        self.max_num_chars = max_num_chars;
+        self
+    }
+
+    /// Sets the maximum number of fragments kept per document.
+    pub fn with_num_fragments(mut self, num_fragments: usize) -> Self {
+        self.num_fragments = num_fragments;
+        self
+    }
+
+    /// Sets where the fragments can be cut.
+    pub fn with_boundary(mut self, boundary: FragmentBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Returns the maximum number of chars of a fragment.
+    pub fn max_num_chars(&self) -> usize {
+        self.max_num_chars
+    }
+
+    /// Returns the maximum number of fragments kept per document.
+    pub fn num_fragments(&self) -> usize {
+        self.num_fragments
+    }
+
+    /// Returns where the fragments can be cut.
+    pub fn boundary(&self) -> FragmentBoundary {
+        self.boundary
+    }
+}
+
+/// A highlighted fragment of one of the fields of a document.
+#[derive(Debug)]
+pub struct HighlightedFragment {
+    field: Field,
+    score: Score,
+    snippet: Snippet,
+}
+
+impl HighlightedFragment {
+    /// Returns the field the fragment was extracted from.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the score of the fragment, the sum of the scores of its highlighted terms.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    /// Returns the fragment and its highlighted parts.
+    pub fn snippet(&self) -> &Snippet {
+        &self.snippet
+    }
+}
+
 /// `SnippetGenerator`
 ///
 /// # Example
@@ -287,11 +425,149 @@ impl SnippetGenerator {
             search_fragments(&self.tokenizer, text, &self.terms_text, self.max_num_chars);
         select_best_fragment_combination(&fragment_candidates[..], text)
     }
+
+    /// Returns the fragments of `text` containing at least one of the terms,
+    /// cut as defined by `config`.
+    fn fragment_candidates(&self, text: &str, config: &FragmenterConfig) -> Vec<FragmentCandidate> {
+        match config.boundary {
+            FragmentBoundary::Word => search_fragments(
+                &self.tokenizer,
+                text,
+                &self.terms_text,
+                config.max_num_chars,
+            ),
+            FragmentBoundary::Sentence => sentence_ranges(text)
+                .into_iter()
+                .flat_map(|sentence| {
+                    search_fragments(
+                        &self.tokenizer,
+                        &text[sentence.clone()],
+                        &self.terms_text,
+                        config.max_num_chars,
+                    )
+                    .into_iter()
+                    .map(move |fragment| fragment.shift(sentence.start))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `MultiFieldSnippetGenerator` highlights the terms of a query in several fields,
+/// and returns the best fragments of a document across all of these fields.
+///
+/// The fragments are scored as with the `SnippetGenerator`, so that the fragments
+/// of the different fields compete with each other. How the texts are cut into
+/// fragments, and the number of fragments returned, is set by a
+/// [`FragmenterConfig`](./struct.FragmenterConfig.html).
+///
+/// ```rust
+/// # use tantivy::query::QueryParser;
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, Index};
+/// use tantivy::{FragmentBoundary, FragmenterConfig, MultiFieldSnippetGenerator};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// # let mut schema_builder = Schema::builder();
+/// # let title = schema_builder.add_text_field("title", TEXT);
+/// # let body = schema_builder.add_text_field("body", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// # let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// let doc = doc!(
+///     title => "The Old Man and the Sea",
+///     body => "He was an old man who fished alone. He had gone eighty-four days \
+///              now without taking a fish. The sea was calm."
+/// );
+/// # index_writer.add_document(doc.clone())?;
+/// # index_writer.commit()?;
+/// # let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title, body]).parse_query("sea fish")?;
+/// let mut snippet_generator =
+///     MultiFieldSnippetGenerator::create(&searcher, &*query, &[title, body])?;
+/// snippet_generator.set_fragmenter_config(
+///     FragmenterConfig::default()
+///         .with_num_fragments(2)
+///         .with_boundary(FragmentBoundary::Sentence),
+/// );
+/// let fragments = snippet_generator.fragments_from_doc(&doc);
+/// assert_eq!(fragments.len(), 2);
+/// assert_eq!(fragments[0].field(), title);
+/// assert_eq!(fragments[0].snippet().to_html(), "The Old Man and the <b>Sea</b>");
+/// assert_eq!(fragments[1].field(), body);
+/// assert_eq!(
+///     fragments[1].snippet().to_html(),
+///     "He had gone eighty-four days now without taking a <b>fish</b>"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiFieldSnippetGenerator {
+    snippet_generators: Vec<SnippetGenerator>,
+    fragmenter_config: FragmenterConfig,
+}
+
+impl MultiFieldSnippetGenerator {
+    /// Creates a new snippet generator for `fields`.
+    pub fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        fields: &[Field],
+    ) -> crate::Result<MultiFieldSnippetGenerator> {
+        let snippet_generators = fields
+            .iter()
+            .map(|&field| SnippetGenerator::create(searcher, query, field))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(MultiFieldSnippetGenerator {
+            snippet_generators,
+            fragmenter_config: FragmenterConfig::default(),
+        })
+    }
+
+    /// Sets how the texts are cut into fragments, and how many fragments are returned.
+    pub fn set_fragmenter_config(&mut self, fragmenter_config: FragmenterConfig) {
+        self.fragmenter_config = fragmenter_config;
+    }
+
+    /// Returns the best fragments of `doc` across all of the fields, by decreasing score.
+    ///
+    /// The fragments with the same score are ordered by field, as given to `create`,
+    /// and then by position in the text of the field.
+    pub fn fragments_from_doc(&self, doc: &Document) -> Vec<HighlightedFragment> {
+        let mut fragments = Vec::new();
+        for snippet_generator in &self.snippet_generators {
+            let text: String = doc
+                .get_all(snippet_generator.field)
+                .flat_map(Value::text)
+                .collect::<Vec<&str>>()
+                .join(" ");
+            let fragment_candidates =
+                snippet_generator.fragment_candidates(&text, &self.fragmenter_config);
+            fragments.extend(fragment_candidates.iter().map(|fragment_candidate| {
+                HighlightedFragment {
+                    field: snippet_generator.field,
+                    score: fragment_candidate.score,
+                    snippet: fragment_candidate.to_snippet(&text),
+                }
+            }));
+        }
+        // The sort is stable, and keeps the fragments of equal scores in field and text order.
+        fragments.sort_by(|left, right| {
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+        });
+        fragments.truncate(self.fragmenter_config.num_fragments);
+        fragments
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{search_fragments, select_best_fragment_combination};
+    use super::{
+        search_fragments, select_best_fragment_combination, sentence_ranges, FragmentBoundary,
+        FragmenterConfig, MultiFieldSnippetGenerator,
+    };
     use crate::query::QueryParser;
     use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, TEXT};
     use crate::tokenizer::SimpleTokenizer;
@@ -551,4 +827,87 @@ Survey in 2016, 2017, and 2018."#;
         }
         Ok(())
     }
+
+    #[test]
+    fn test_sentence_ranges() {
+        let text = "  First sentence. Second one!Not split?\nThird\nline\n\n  Last...  ";
+        let sentences: Vec<&str> = sentence_ranges(text)
+            .into_iter()
+            .map(|sentence| &text[sentence])
+            .collect();
+        assert_eq!(
+            sentences,
+            vec![
+                "First sentence.",
+                "Second one!Not split?",
+                "Third\nline",
+                "Last..."
+            ]
+        );
+        assert!(sentence_ranges("").is_empty());
+        assert!(sentence_ranges(" \n ").is_empty());
+    }
+
+    #[test]
+    fn test_multi_field_snippet_generator() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let doc = doc!(
+            title => "Rust",
+            body => TEST_TEXT,
+            body => "Rust has a borrow checker."
+        );
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc.clone())?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title, body]);
+        let query = query_parser.parse_query("rust language")?;
+        let mut snippet_generator =
+            MultiFieldSnippetGenerator::create(&searcher, &*query, &[title, body])?;
+        {
+            let fragments = snippet_generator.fragments_from_doc(&doc);
+            assert_eq!(fragments.len(), 1);
+            assert_eq!(fragments[0].field(), body);
+            assert_eq!(fragments[0].snippet().to_html(), "<b>Rust</b> is a systems programming <b>language</b> sponsored by\nMozilla which describes it as a &quot;safe, concurrent, practical <b>language</b>&quot;, supporting functional and");
+        }
+        {
+            snippet_generator.set_fragmenter_config(
+                FragmenterConfig::default()
+                    .with_num_fragments(10)
+                    .with_boundary(FragmentBoundary::Sentence),
+            );
+            let fragments = snippet_generator.fragments_from_doc(&doc);
+            let scores: Vec<_> = fragments.iter().map(|fragment| fragment.score()).collect();
+            let mut sorted_scores = scores.clone();
+            sorted_scores.sort_by(|left, right| right.partial_cmp(left).unwrap());
+            assert_eq!(scores, sorted_scores);
+            for fragment in &fragments {
+                let text = fragment.snippet().fragments();
+                assert!(!text.starts_with(char::is_whitespace));
+                assert!(!text.contains(". "));
+            }
+            assert!(fragments.iter().any(|fragment| fragment.field() == title
+                && fragment.snippet().to_html() == "<b>Rust</b>"));
+            assert!(fragments.iter().any(|fragment| fragment.field() == body
+                && fragment.snippet().to_html() == "<b>Rust</b> has a borrow checker"));
+        }
+        {
+            snippet_generator.set_fragmenter_config(
+                FragmenterConfig::default()
+                    .with_num_fragments(2)
+                    .with_max_num_chars(20),
+            );
+            let fragments = snippet_generator.fragments_from_doc(&doc);
+            assert_eq!(fragments.len(), 2);
+            assert!(fragments
+                .iter()
+                .all(|fragment| fragment.snippet().fragments().len() <= 20));
+        }
+        Ok(())
+    }
 }
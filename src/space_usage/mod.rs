@@ -71,6 +71,8 @@ pub struct SegmentSpaceUsage {
     positions: PerFieldSpaceUsage,
     fast_fields: PerFieldSpaceUsage,
     fieldnorms: PerFieldSpaceUsage,
+    vectors: PerFieldSpaceUsage,
+    features: PerFieldSpaceUsage,
 
     store: StoreSpaceUsage,
 
@@ -88,6 +90,8 @@ impl SegmentSpaceUsage {
         positions: PerFieldSpaceUsage,
         fast_fields: PerFieldSpaceUsage,
         fieldnorms: PerFieldSpaceUsage,
+        vectors: PerFieldSpaceUsage,
+        features: PerFieldSpaceUsage,
         store: StoreSpaceUsage,
         deletes: ByteCount,
     ) -> SegmentSpaceUsage {
@@ -96,6 +100,8 @@ impl SegmentSpaceUsage {
             + positions.total()
             + fast_fields.total()
             + fieldnorms.total()
+            + vectors.total()
+            + features.total()
             + store.total()
             + deletes;
         SegmentSpaceUsage {
@@ -105,6 +111,8 @@ impl SegmentSpaceUsage {
             positions,
             fast_fields,
             fieldnorms,
+            vectors,
+            features,
             store,
             deletes,
             total,
@@ -127,6 +135,8 @@ impl SegmentSpaceUsage {
             SegmentComponent::Store => ComponentSpaceUsage::Store(self.store().clone()),
             SegmentComponent::TempStore => ComponentSpaceUsage::Store(self.store().clone()),
             Delete => Basic(self.deletes()),
+            Vectors => PerField(self.vectors().clone()),
+            Features => PerField(self.features().clone()),
         }
     }
 
@@ -160,6 +170,16 @@ impl SegmentSpaceUsage {
         &self.fieldnorms
     }
 
+    /// Space usage for vectors
+    pub fn vectors(&self) -> &PerFieldSpaceUsage {
+        &self.vectors
+    }
+
+    /// Space usage for features
+    pub fn features(&self) -> &PerFieldSpaceUsage {
+        &self.features
+    }
+
     /// Space usage for stored documents
     pub fn store(&self) -> &StoreSpaceUsage {
         &self.store
@@ -170,6 +190,24 @@ impl SegmentSpaceUsage {
         self.deletes
     }
 
+    /// Space usage of a given field, summed over all of the per-field components
+    /// (term dictionary, postings, positions, fast fields and field norms).
+    ///
+    /// The doc store is not broken down by field, and is not included.
+    pub fn field_total(&self, field: Field) -> ByteCount {
+        [
+            &self.termdict,
+            &self.postings,
+            &self.positions,
+            &self.fast_fields,
+            &self.fieldnorms,
+        ]
+        .iter()
+        .flat_map(|per_field_space_usage| per_field_space_usage.field_usage(field))
+        .map(FieldUsage::total)
+        .sum()
+    }
+
     /// Total space usage in bytes for this segment.
     pub fn total(&self) -> ByteCount {
         self.total
@@ -229,6 +267,12 @@ impl PerFieldSpaceUsage {
         self.fields.iter()
     }
 
+    /// Space usage of the given field, or `None` if the field
+    /// does not appear in the represented file.
+    pub fn field_usage(&self, field: Field) -> Option<&FieldUsage> {
+        self.fields.get(&field)
+    }
+
     /// Bytes used by the represented file
     pub fn total(&self) -> ByteCount {
         self.total
@@ -398,6 +442,35 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_field_total() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let num = schema_builder.add_u64_field("num", FAST);
+        let unused = schema_builder.add_u64_field("unused", STORED);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(title => "hello happy tax payer", num => 3u64))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let searcher_space_usage = searcher.space_usage()?;
+        let segment = &searcher_space_usage.segments()[0];
+        assert_eq!(
+            segment.field_total(title),
+            segment.termdict().total()
+                + segment.postings().total()
+                + segment.positions().total()
+                + segment.fieldnorms().field_usage(title).unwrap().total()
+        );
+        assert_eq!(segment.field_total(num), segment.fast_fields().total());
+        assert!(segment.termdict().field_usage(num).is_none());
+        assert_eq!(segment.field_total(unused), 0);
+        Ok(())
+    }
+
     #[test]
     fn test_store() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
@@ -0,0 +1,138 @@
+use std::convert::TryInto;
+use std::io;
+use std::mem;
+
+use zstd::bulk::{Compressor, Decompressor};
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Maximum size of an uncompressed block.
+///
+/// The size header of a block is checked against it before allocating
+/// the decompression buffer, so that a corrupted header cannot trigger
+/// a huge allocation.
+const MAX_BLOCK_NUM_BYTES: usize = 1 << 28;
+
+/// Trains a dictionary of at most `max_size` bytes on the given samples.
+pub fn train_dictionary(samples: &[&[u8]], max_size: usize) -> io::Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+}
+
+/// zstd compression context, reused for all of the blocks of a doc store.
+pub struct ZstdCompressor {
+    compressor: Compressor<'static>,
+}
+
+impl ZstdCompressor {
+    /// Creates a compression context, loaded with `dictionary` if any.
+    pub fn new(dictionary: Option<&[u8]>) -> io::Result<ZstdCompressor> {
+        let compressor = match dictionary {
+            Some(dictionary) => Compressor::with_dictionary(COMPRESSION_LEVEL, dictionary)?,
+            None => Compressor::new(COMPRESSION_LEVEL)?,
+        };
+        Ok(ZstdCompressor { compressor })
+    }
+
+    #[inline]
+    pub fn compress(&mut self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()> {
+        if uncompressed.len() > MAX_BLOCK_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "doc store block too large",
+            ));
+        }
+        let num_bytes = uncompressed.len() as u32;
+        let header_len = mem::size_of::<u32>();
+        compressed.clear();
+        compressed.resize(
+            header_len + zstd::zstd_safe::compress_bound(uncompressed.len()),
+            0,
+        );
+        compressed[..header_len].copy_from_slice(&num_bytes.to_le_bytes());
+        let bytes_written = self
+            .compressor
+            .compress_to_buffer(uncompressed, &mut compressed[header_len..])?;
+        compressed.truncate(header_len + bytes_written);
+        Ok(())
+    }
+}
+
+/// zstd decompression context, reused for all of the blocks of a doc store.
+pub struct ZstdDecompressor {
+    decompressor: Decompressor<'static>,
+}
+
+impl ZstdDecompressor {
+    /// Creates a decompression context, loaded with `dictionary` if any.
+    pub fn new(dictionary: Option<&[u8]>) -> io::Result<ZstdDecompressor> {
+        let decompressor = match dictionary {
+            Some(dictionary) => Decompressor::with_dictionary(dictionary)?,
+            None => Decompressor::new()?,
+        };
+        Ok(ZstdDecompressor { decompressor })
+    }
+
+    #[inline]
+    pub fn decompress(&mut self, compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()> {
+        let header_len = mem::size_of::<u32>();
+        let uncompressed_size_bytes: &[u8; 4] = compressed
+            .get(..header_len)
+            .ok_or(io::ErrorKind::InvalidData)?
+            .try_into()
+            .unwrap();
+        let uncompressed_size = u32::from_le_bytes(*uncompressed_size_bytes) as usize;
+        if uncompressed_size > MAX_BLOCK_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "doc store block size exceeds the maximum block size, data corruption",
+            ));
+        }
+        decompressed.clear();
+        decompressed.resize(uncompressed_size, 0);
+        let bytes_written = self
+            .decompressor
+            .decompress_to_buffer(&compressed[header_len..], &mut decompressed[..])?;
+        if bytes_written != uncompressed_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "doc store block not completely decompressed, data corruption".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ZstdCompressor, ZstdDecompressor, MAX_BLOCK_NUM_BYTES};
+    use std::io;
+
+    #[test]
+    fn test_zstd_contexts_are_reused() -> io::Result<()> {
+        let mut compressor = ZstdCompressor::new(None)?;
+        let mut decompressor = ZstdDecompressor::new(None)?;
+        let mut compressed = Vec::new();
+        let mut decompressed = Vec::new();
+        for i in 0..10u8 {
+            let block = vec![i; 1_000 * usize::from(i)];
+            compressor.compress(&block, &mut compressed)?;
+            decompressor.decompress(&compressed, &mut decompressed)?;
+            assert_eq!(decompressed, block);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_rejects_corrupted_size_header() -> io::Result<()> {
+        let mut compressed = Vec::new();
+        ZstdCompressor::new(None)?.compress(b"hello", &mut compressed)?;
+        compressed[..4].copy_from_slice(&(MAX_BLOCK_NUM_BYTES as u32 + 1).to_le_bytes());
+        let mut decompressed = Vec::new();
+        let err = ZstdDecompressor::new(None)?
+            .decompress(&compressed, &mut decompressed)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(decompressed.capacity() < MAX_BLOCK_NUM_BYTES);
+        Ok(())
+    }
+}
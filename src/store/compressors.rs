@@ -1,6 +1,25 @@
+use super::dictionary::StoreDictionary;
 use serde::{Deserialize, Serialize};
 use std::io;
 
+/// Compression context of a doc store writer, reused for all of its blocks.
+///
+/// Only the zstd compressor keeps a context between blocks.
+#[derive(Default)]
+pub(crate) struct CompressionContext {
+    #[cfg(feature = "zstd-compression")]
+    zstd: Option<super::compression_zstd_block::ZstdCompressor>,
+}
+
+/// Decompression context of a doc store reader, reused for all of its blocks.
+///
+/// Only the zstd compressor keeps a context between blocks.
+#[derive(Default)]
+pub(crate) struct DecompressionContext {
+    #[cfg(feature = "zstd-compression")]
+    zstd: Option<super::compression_zstd_block::ZstdDecompressor>,
+}
+
 pub trait StoreCompressor {
     fn compress(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()>;
     fn decompress(&self, compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()>;
@@ -25,6 +44,12 @@ pub enum Compressor {
     #[serde(rename = "snappy")]
     /// Use the snap compressor
     Snappy,
+    #[serde(rename = "zstd")]
+    /// Use the zstd compressor
+    ///
+    /// The blocks can be compressed with a dictionary trained on the documents
+    /// of the segment, see `IndexSettings::docstore_dictionary_size`.
+    Zstd,
 }
 
 impl Default for Compressor {
@@ -35,6 +60,8 @@ impl Default for Compressor {
             Compressor::Brotli
         } else if cfg!(feature = "snappy-compression") {
             Compressor::Snappy
+        } else if cfg!(feature = "zstd-compression") {
+            Compressor::Zstd
         } else {
             Compressor::None
         }
@@ -48,6 +75,7 @@ impl Compressor {
             1 => Compressor::Lz4,
             2 => Compressor::Brotli,
             3 => Compressor::Snappy,
+            4 => Compressor::Zstd,
             _ => panic!("unknown compressor id {:?}", id),
         }
     }
@@ -57,10 +85,22 @@ impl Compressor {
             Self::Lz4 => 1,
             Self::Brotli => 2,
             Self::Snappy => 3,
+            Self::Zstd => 4,
         }
     }
+    /// Compresses a block of the doc store.
+    ///
+    /// `dictionary` and `context` are only used by the zstd compressor.
+    /// The context is created with the dictionary on the first call: the same
+    /// dictionary has to be passed for all of the blocks of a doc store.
     #[inline]
-    pub(crate) fn compress(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()> {
+    pub(crate) fn compress(
+        &self,
+        uncompressed: &[u8],
+        compressed: &mut Vec<u8>,
+        dictionary: Option<&StoreDictionary>,
+        context: &mut CompressionContext,
+    ) -> io::Result<()> {
         match self {
             Self::None => {
                 compressed.clear();
@@ -97,14 +137,41 @@ impl Compressor {
                     panic!("snappy-compression feature flag not activated");
                 }
             }
+            Self::Zstd => {
+                #[cfg(feature = "zstd-compression")]
+                {
+                    let compressor =
+                        match &mut context.zstd {
+                            Some(compressor) => compressor,
+                            None => context.zstd.insert(
+                                super::compression_zstd_block::ZstdCompressor::new(
+                                    dictionary.map(StoreDictionary::as_bytes),
+                                )?,
+                            ),
+                        };
+                    compressor.compress(uncompressed, compressed)
+                }
+                #[cfg(not(feature = "zstd-compression"))]
+                {
+                    let _ = (dictionary, context);
+                    panic!("zstd-compression feature flag not activated");
+                }
+            }
         }
     }
 
+    /// Decompresses a block of the doc store.
+    ///
+    /// `dictionary` and `context` are only used by the zstd compressor.
+    /// The context is created with the dictionary on the first call: the same
+    /// dictionary has to be passed for all of the blocks of a doc store.
     #[inline]
     pub(crate) fn decompress(
         &self,
         compressed: &[u8],
         decompressed: &mut Vec<u8>,
+        dictionary: Option<&StoreDictionary>,
+        context: &mut DecompressionContext,
     ) -> io::Result<()> {
         match self {
             Self::None => {
@@ -142,6 +209,25 @@ impl Compressor {
                     panic!("snappy-compression feature flag not activated");
                 }
             }
+            Self::Zstd => {
+                #[cfg(feature = "zstd-compression")]
+                {
+                    let decompressor = match &mut context.zstd {
+                        Some(decompressor) => decompressor,
+                        None => context.zstd.insert(
+                            super::compression_zstd_block::ZstdDecompressor::new(
+                                dictionary.map(StoreDictionary::as_bytes),
+                            )?,
+                        ),
+                    };
+                    decompressor.decompress(compressed, decompressed)
+                }
+                #[cfg(not(feature = "zstd-compression"))]
+                {
+                    let _ = (dictionary, context);
+                    panic!("zstd-compression feature flag not activated");
+                }
+            }
         }
     }
 }
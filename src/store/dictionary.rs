@@ -0,0 +1,83 @@
+use crate::directory::OwnedBytes;
+use std::io;
+
+/// Ratio between the size of the sample a dictionary is trained on and its size.
+///
+/// zstd recommends training on about a hundred times the size of the dictionary.
+const SAMPLE_SIZE_RATIO: usize = 100;
+
+/// Maximum number of documents in the sample a dictionary is trained on.
+const MAX_NUM_SAMPLES: usize = 10_000;
+
+/// Minimum number of documents required to train a dictionary.
+/// Below it, the documents are compressed without dictionary.
+const MIN_NUM_SAMPLES: usize = 16;
+
+/// Compression dictionary of a doc store, trained on a sample of its documents.
+///
+/// The dictionary is persisted in the doc store, and shared by all of its blocks.
+#[derive(Clone)]
+pub(crate) struct StoreDictionary {
+    data: OwnedBytes,
+}
+
+impl StoreDictionary {
+    /// Opens a dictionary serialized with `as_bytes`.
+    pub fn open(data: OwnedBytes) -> StoreDictionary {
+        StoreDictionary { data }
+    }
+
+    /// Trains a dictionary of at most `dictionary_size` bytes on an evenly
+    /// spread sample of the `num_docs` documents of `docs`.
+    ///
+    /// Returns `None` if there are not enough documents to train a dictionary.
+    pub fn train<TDocs>(
+        docs: TDocs,
+        num_docs: usize,
+        dictionary_size: usize,
+    ) -> crate::Result<Option<StoreDictionary>>
+    where
+        TDocs: Iterator<Item = crate::Result<OwnedBytes>>,
+    {
+        let max_sample_num_bytes = dictionary_size * SAMPLE_SIZE_RATIO;
+        let step = (num_docs / MAX_NUM_SAMPLES).max(1);
+        let mut samples = Vec::new();
+        let mut sample_num_bytes = 0;
+        for doc_bytes_res in docs.step_by(step) {
+            let doc_bytes = doc_bytes_res?;
+            sample_num_bytes += doc_bytes.len();
+            samples.push(doc_bytes);
+            if sample_num_bytes >= max_sample_num_bytes {
+                break;
+            }
+        }
+        if samples.len() < MIN_NUM_SAMPLES {
+            return Ok(None);
+        }
+        let samples: Vec<&[u8]> = samples.iter().map(OwnedBytes::as_slice).collect();
+        match train_dictionary(&samples, dictionary_size) {
+            Ok(dictionary) => Ok(Some(StoreDictionary::open(OwnedBytes::new(dictionary)))),
+            Err(err) => {
+                // Training fails on samples that are too small or too uniform.
+                // The documents are then compressed without dictionary.
+                info!("Failed to train the doc store dictionary: {:?}", err);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the serialized dictionary.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+}
+
+#[cfg(feature = "zstd-compression")]
+fn train_dictionary(samples: &[&[u8]], dictionary_size: usize) -> io::Result<Vec<u8>> {
+    super::compression_zstd_block::train_dictionary(samples, dictionary_size)
+}
+
+#[cfg(not(feature = "zstd-compression"))]
+fn train_dictionary(_samples: &[&[u8]], _dictionary_size: usize) -> io::Result<Vec<u8>> {
+    panic!("zstd-compression feature flag not activated");
+}
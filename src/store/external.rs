@@ -0,0 +1,29 @@
+use std::io;
+
+/// A key-value store holding the stored documents outside of the index,
+/// e.g. in RocksDB or in an object storage.
+///
+/// With an external doc store, the doc store of a segment only holds, for each
+/// document, the key of its serialized stored fields in the external store. This
+/// keeps the index small when the documents have very large bodies, while
+/// `Searcher::doc()` keeps working as usual.
+///
+/// The keys are 20 bytes long, and are never reused: the first 16 bytes identify the
+/// `StoreWriter` that put the document, and the last 4 bytes are its doc id in that writer.
+/// When segments are merged, the keys of their documents are copied over as long as the
+/// merged segment is written to the same external store, so the documents are not put again.
+///
+/// Tantivy never removes any entry of the external store. The entries of the deleted
+/// documents, or of the segments that never got committed, are left to be garbage
+/// collected by the owner of the external store.
+///
+/// The external store is plugged in with an
+/// [`ExternalStoreCodec`](../struct.ExternalStoreCodec.html).
+pub trait ExternalDocStore: Send + Sync + 'static {
+    /// Stores the serialized stored fields of a document under `key`.
+    fn put(&self, key: &[u8], doc_bytes: &[u8]) -> io::Result<()>;
+
+    /// Returns the serialized stored fields of the document stored under `key`,
+    /// or `None` if there are none.
+    fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>>;
+}
@@ -6,28 +6,42 @@ use std::io;
 pub struct DocStoreFooter {
     pub offset: u64,
     pub compressor: Compressor,
+    /// Length of the compression dictionary, stored right before the offset index.
+    /// 0 if the blocks are compressed without dictionary.
+    pub dictionary_len: u32,
+    /// Length of the offsets of the raw stored values, stored right after the dictionary.
+    /// 0 if no field is stored raw.
+    pub raw_offsets_len: u64,
 }
 
 /// Serialises the footer to a byte-array
 /// - offset : 8 bytes
 ///-  compressor id: 1 byte
-/// - reserved for future use: 15 bytes
+/// - dictionary length: 4 bytes
+/// - raw offsets length: 8 bytes
+/// - reserved for future use: 3 bytes
 impl BinarySerializable for DocStoreFooter {
     fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
         BinarySerializable::serialize(&self.offset, writer)?;
         BinarySerializable::serialize(&self.compressor.get_id(), writer)?;
-        writer.write_all(&[0; 15])?;
+        BinarySerializable::serialize(&self.dictionary_len, writer)?;
+        BinarySerializable::serialize(&self.raw_offsets_len, writer)?;
+        writer.write_all(&[0; 3])?;
         Ok(())
     }
 
     fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
         let offset = u64::deserialize(reader)?;
         let compressor_id = u8::deserialize(reader)?;
-        let mut skip_buf = [0; 15];
+        let dictionary_len = u32::deserialize(reader)?;
+        let raw_offsets_len = u64::deserialize(reader)?;
+        let mut skip_buf = [0; 3];
         reader.read_exact(&mut skip_buf)?;
         Ok(DocStoreFooter {
             offset,
             compressor: Compressor::from_id(compressor_id),
+            dictionary_len,
+            raw_offsets_len,
         })
     }
 }
@@ -37,8 +51,18 @@ impl FixedSize for DocStoreFooter {
 }
 
 impl DocStoreFooter {
-    pub fn new(offset: u64, compressor: Compressor) -> Self {
-        DocStoreFooter { offset, compressor }
+    pub fn new(
+        offset: u64,
+        compressor: Compressor,
+        dictionary_len: u32,
+        raw_offsets_len: u64,
+    ) -> Self {
+        DocStoreFooter {
+            offset,
+            compressor,
+            dictionary_len,
+            raw_offsets_len,
+        }
     }
 
     pub fn extract_footer(file: FileSlice) -> io::Result<(DocStoreFooter, FileSlice)> {
@@ -62,5 +86,5 @@ impl DocStoreFooter {
 fn doc_store_footer_test() {
     // This test is just to safe guard changes on the footer.
     // When the doc store footer is updated, make sure to update also the serialize/deserialize methods
-    assert_eq!(core::mem::size_of::<DocStoreFooter>(), 16);
+    assert_eq!(core::mem::size_of::<DocStoreFooter>(), 24);
 }
@@ -5,17 +5,29 @@ A field needs to be marked as stored in the schema in
 order to be handled in the `Store`.
 
 Internally, documents (or rather their stored fields) are serialized to a buffer.
-When the buffer exceeds 16K, the buffer is compressed using `brotli`, `LZ4` or `snappy`
-and the resulting block is written to disk.
+When the buffer exceeds 16K, the buffer is compressed using `brotli`, `LZ4`, `snappy`
+or `zstd` and the resulting block is written to disk.
+
+With `zstd`, the blocks can be compressed with a dictionary trained on a sample of
+the documents of the segment (see `IndexSettings::docstore_dictionary_size`).
+This significantly improves the compression of many small, similar documents.
+The dictionary is persisted in the doc store.
+
+The values of the bytes fields set as `stored_raw` (see `BytesOptions::set_stored_raw`),
+e.g. already compressed blobs, are not compressed a second time. They are written
+uncompressed in front of the compressed data of their block, and the store keeps their
+offsets so that they can be read directly.
 
 One can then request for a specific `DocId`.
 A skip list helps navigating to the right block,
 decompresses it entirely and returns the document within it.
 
-If the last document requested was in the same block,
-the reader is smart enough to avoid decompressing
-the block a second time, but their is no real
-*uncompressed block* cache.
+Decompressed blocks are kept in a small LRU cache, shared by all
+of the `StoreReader`s of a given `SegmentReader`, so that fetching
+several documents from the same block only decompresses it once.
+
+The stored fields can also live in an external key-value store (see `ExternalDocStore`),
+in which case the doc store only holds the key of each document.
 
 A typical use case for the store is, once
 the search result page has been computed, returning
@@ -34,12 +46,17 @@ and should rely on either
 !*/
 
 mod compressors;
+mod dictionary;
+mod external;
 mod footer;
 mod index;
 mod reader;
 mod writer;
 pub use self::compressors::Compressor;
-pub use self::reader::StoreReader;
+pub(crate) use self::dictionary::StoreDictionary;
+pub use self::external::ExternalDocStore;
+pub(crate) use self::reader::DEFAULT_CACHE_NUM_BLOCKS;
+pub use self::reader::{CacheStats, StoreReader};
 pub use self::writer::StoreWriter;
 
 #[cfg(feature = "lz4-compression")]
@@ -51,6 +68,9 @@ mod compression_brotli;
 #[cfg(feature = "snappy-compression")]
 mod compression_snap;
 
+#[cfg(feature = "zstd-compression")]
+mod compression_zstd_block;
+
 #[cfg(test)]
 pub mod tests {
 
@@ -64,7 +84,7 @@ pub mod tests {
         directory::{Directory, RamDirectory, WritePtr},
         Term,
     };
-    use crate::{schema::Schema, Index};
+    use crate::{schema::Schema, Index, IndexSettings};
     use std::path::Path;
 
     const LOREM: &str = "Doc Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed \
@@ -80,6 +100,7 @@ pub mod tests {
         writer: WritePtr,
         num_docs: usize,
         compressor: Compressor,
+        blocksize: usize,
     ) -> Schema {
         let mut schema_builder = Schema::builder();
         let field_body = schema_builder.add_text_field("body", TextOptions::default().set_stored());
@@ -87,7 +108,7 @@ pub mod tests {
             schema_builder.add_text_field("title", TextOptions::default().set_stored());
         let schema = schema_builder.build();
         {
-            let mut store_writer = StoreWriter::new(writer, compressor);
+            let mut store_writer = StoreWriter::new(writer, compressor, blocksize);
             for i in 0..num_docs {
                 let mut fields: Vec<FieldValue> = Vec::new();
                 {
@@ -109,6 +130,7 @@ pub mod tests {
     }
 
     const NUM_DOCS: usize = 1_000;
+    pub const BLOCK_SIZE: usize = 16_384;
     #[test]
     fn test_doc_store_iter_with_delete_bug_1077() -> crate::Result<()> {
         // this will cover deletion of the first element in a checkpoint
@@ -119,7 +141,7 @@ pub mod tests {
         let path = Path::new("store");
         let directory = RamDirectory::create();
         let store_wrt = directory.open_write(path)?;
-        let schema = write_lorem_ipsum_store(store_wrt, NUM_DOCS, Compressor::Lz4);
+        let schema = write_lorem_ipsum_store(store_wrt, NUM_DOCS, Compressor::Lz4, BLOCK_SIZE);
         let field_title = schema.get_field("title").unwrap();
         let store_file = directory.open_read(path)?;
         let store = StoreReader::open(store_file)?;
@@ -155,11 +177,164 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_store_cache_num_blocks_setting() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", STORED);
+        let schema = schema_builder.build();
+        let cache_stats_after_reads = |docstore_cache_num_blocks: Option<usize>| {
+            let settings = IndexSettings {
+                docstore_blocksize: 64,
+                docstore_cache_num_blocks,
+                ..Default::default()
+            };
+            let index = Index::builder()
+                .schema(schema.clone())
+                .settings(settings)
+                .create_in_ram()?;
+            let mut index_writer = index.writer_for_tests()?;
+            for i in 0..100u64 {
+                index_writer.add_document(doc!(id_field => i))?;
+            }
+            index_writer.commit()?;
+            let searcher = index.reader()?.searcher();
+            let store_reader = searcher.segment_reader(0).get_store_reader()?;
+            // Reads the first block, the last one, and the first one again.
+            for &doc in &[0, 99, 0] {
+                assert_eq!(
+                    store_reader
+                        .get(doc)?
+                        .get_first(id_field)
+                        .unwrap()
+                        .u64_value(),
+                    Some(u64::from(doc))
+                );
+            }
+            crate::Result::Ok(store_reader.cache_stats())
+        };
+        let cache_stats = |num_entries, capacity, cache_hits, cache_misses| CacheStats {
+            num_entries,
+            capacity,
+            cache_hits,
+            cache_misses,
+        };
+        assert_eq!(cache_stats_after_reads(None)?, cache_stats(2, 100, 1, 2));
+        assert_eq!(cache_stats_after_reads(Some(1))?, cache_stats(1, 1, 0, 3));
+        assert_eq!(cache_stats_after_reads(Some(0))?, cache_stats(0, 0, 0, 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_impossible_doc_length_is_data_corruption() -> crate::Result<()> {
+        let path = Path::new("store");
+        let directory = RamDirectory::create();
+        let store_wrt = directory.open_write(path)?;
+        write_lorem_ipsum_store(store_wrt, 10, Compressor::None, BLOCK_SIZE);
+        let mut store_bytes = directory.atomic_read(path)?;
+        // The first document of the block now claims to be longer than the block.
+        store_bytes[1] = 0xff;
+        let corrupted_path = Path::new("corrupted_store");
+        directory.atomic_write(corrupted_path, &store_bytes)?;
+        let store = StoreReader::open(directory.open_read(corrupted_path)?)?
+            .with_filepath(corrupted_path.to_path_buf());
+        match store.get(0) {
+            Err(crate::TantivyError::DataCorruption(data_corruption)) => {
+                assert_eq!(data_corruption.filepath(), Some(corrupted_path));
+                assert_eq!(data_corruption.offset(), Some(0));
+            }
+            _ => panic!("Expected a data corruption error"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_get_fields() -> crate::Result<()> {
+        use crate::schema::{Facet, FacetOptions, Field};
+        use crate::tokenizer::{PreTokenizedString, Token};
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let body = schema_builder.add_text_field("body", STORED);
+        let count = schema_builder.add_u64_field("count", STORED);
+        let delta = schema_builder.add_i64_field("delta", STORED);
+        let score = schema_builder.add_f64_field("score", STORED);
+        let date = schema_builder.add_date_field("date", STORED);
+        let facet = schema_builder.add_facet_field("facet", FacetOptions::default().set_stored());
+        let bytes = schema_builder.add_bytes_field("bytes", STORED);
+        let pretok = schema_builder.add_text_field("pretok", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let mut doc = Document::default();
+        doc.add_text(body, LOREM);
+        doc.add_u64(count, 3);
+        doc.add_text(title, "hello");
+        doc.add_i64(delta, -7);
+        doc.add_f64(score, 1.5);
+        doc.add_date(
+            date,
+            &"2021-08-24T12:00:00+00:00"
+                .parse::<crate::DateTime>()
+                .unwrap(),
+        );
+        doc.add_facet(facet, Facet::from("/a/b"));
+        doc.add_bytes(bytes, vec![1u8, 2, 3]);
+        doc.add_pre_tokenized_text(
+            pretok,
+            &PreTokenizedString {
+                text: "pre tokenized".to_string(),
+                tokens: vec![Token {
+                    offset_from: 0,
+                    offset_to: 3,
+                    position: 0,
+                    text: "pre".to_string(),
+                    position_length: 1,
+                    payload: Vec::new(),
+                }],
+            },
+        );
+        doc.add_text(title, "happy");
+        index_writer.add_document(doc)?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let store_reader = searcher.segment_reader(0).get_store_reader()?;
+        let full_doc = store_reader.get(0)?;
+        let all_fields = [title, body, count, delta, score, date, facet, bytes, pretok];
+        for &field in &all_fields {
+            let partial_doc = store_reader.get_fields(0, &[field])?;
+            let expected: Vec<&FieldValue> = full_doc
+                .field_values()
+                .iter()
+                .filter(|field_value| field_value.field() == field)
+                .collect();
+            let partial_field_values: Vec<&FieldValue> =
+                partial_doc.field_values().iter().collect();
+            assert_eq!(partial_field_values, expected);
+        }
+        let partial_doc = store_reader.get_fields(0, &[title, count])?;
+        let titles: Vec<&str> = partial_doc
+            .get_all(title)
+            .flat_map(|value| value.text())
+            .collect();
+        assert_eq!(titles, &["hello", "happy"]);
+        assert_eq!(
+            partial_doc
+                .get_first(count)
+                .and_then(|value| value.u64_value()),
+            Some(3)
+        );
+        assert_eq!(partial_doc.field_values().len(), 3);
+        assert!(store_reader.get_fields(0, &[])?.is_empty());
+        assert_eq!(store_reader.get_fields(0, &all_fields)?, full_doc);
+        assert!(store_reader
+            .get_fields(0, &[Field::from_field_id(100)])?
+            .is_empty());
+        Ok(())
+    }
+
     fn test_store(compressor: Compressor) -> crate::Result<()> {
         let path = Path::new("store");
         let directory = RamDirectory::create();
         let store_wrt = directory.open_write(path)?;
-        let schema = write_lorem_ipsum_store(store_wrt, NUM_DOCS, compressor);
+        let schema = write_lorem_ipsum_store(store_wrt, NUM_DOCS, compressor, BLOCK_SIZE);
         let field_title = schema.get_field("title").unwrap();
         let store_file = directory.open_read(path)?;
         let store = StoreReader::open(store_file)?;
@@ -202,6 +377,120 @@ pub mod tests {
     fn test_store_brotli() -> crate::Result<()> {
         test_store(Compressor::Brotli)
     }
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_store_zstd() -> crate::Result<()> {
+        test_store(Compressor::Zstd)
+    }
+
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_store_zstd_dictionary() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", STORED);
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let schema = schema_builder.build();
+        let build_index = |docstore_dictionary_size: Option<usize>| -> crate::Result<Index> {
+            // dictionaries shine on small blocks.
+            let settings = IndexSettings {
+                docstore_compression: Compressor::Zstd,
+                docstore_blocksize: 512,
+                docstore_dictionary_size,
+                ..Default::default()
+            };
+            let index = Index::builder()
+                .schema(schema.clone())
+                .settings(settings)
+                .create_in_ram()?;
+            let mut index_writer = index.writer_for_tests()?;
+            for i in 0..1_000u64 {
+                let text = format!(
+                    "level=info service=search-api-{} request handled status={} duration_ms={}",
+                    i % 7,
+                    200 + (i % 3),
+                    i * 13 % 997
+                );
+                index_writer.add_document(doc!(id_field => i, text_field => text))?;
+                if i == 499 {
+                    index_writer.commit()?;
+                }
+            }
+            index_writer.commit()?;
+            Ok(index)
+        };
+        let store_num_bytes = |index: &Index| -> crate::Result<usize> {
+            let searcher = index.reader()?.searcher();
+            Ok(searcher
+                .segment_readers()
+                .iter()
+                .map(|reader| reader.get_store_reader().unwrap().space_usage().total())
+                .sum())
+        };
+        let check_docs = |index: &Index, has_dictionary: bool| -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            let mut ids = Vec::new();
+            for segment_reader in searcher.segment_readers() {
+                let store_reader = segment_reader.get_store_reader()?;
+                assert_eq!(store_reader.has_dictionary(), has_dictionary);
+                for doc in store_reader.iter(segment_reader.alive_bitset()) {
+                    let doc = doc?;
+                    let id = doc.get_first(id_field).unwrap().u64_value().unwrap();
+                    let text = doc.get_first(text_field).unwrap().text().unwrap();
+                    assert!(text.contains(&format!("duration_ms={}", id * 13 % 997)));
+                    ids.push(id);
+                }
+            }
+            ids.sort_unstable();
+            assert_eq!(ids, (0..1_000u64).collect::<Vec<_>>());
+            Ok(())
+        };
+
+        let index_without_dictionary = build_index(None)?;
+        check_docs(&index_without_dictionary, false)?;
+        let index = build_index(Some(4_096))?;
+        check_docs(&index, true)?;
+        assert!(store_num_bytes(&index)? < store_num_bytes(&index_without_dictionary)?);
+
+        // a new dictionary is trained on the documents of the merged segments.
+        let segment_ids = index.searchable_segment_ids()?;
+        assert_eq!(segment_ids.len(), 2);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.delete_term(Term::from_field_u64(id_field, 0));
+        block_on(index_writer.merge(&segment_ids))?;
+        index_writer.wait_merging_threads()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        check_docs(&index, true)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_store_zstd_dictionary_few_docs() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let settings = IndexSettings {
+            docstore_compression: Compressor::Zstd,
+            docstore_dictionary_size: Some(4_096),
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "hello"))?;
+        index_writer.commit()?;
+        // not enough documents to train a dictionary.
+        let searcher = index.reader()?.searcher();
+        let store_reader = searcher.segment_reader(0).get_store_reader()?;
+        assert!(!store_reader.has_dictionary());
+        assert_eq!(
+            store_reader.get(0)?.get_first(text_field).unwrap().text(),
+            Some("hello")
+        );
+        Ok(())
+    }
 
     #[test]
     fn test_store_with_delete() -> crate::Result<()> {
@@ -341,12 +630,192 @@ pub mod tests {
         assert_eq!(store.block_checkpoints().count(), 1);
         Ok(())
     }
+
+    #[test]
+    fn test_merge_stacks_doc_store_blocks() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text_field", TEXT | STORED);
+        let schema = schema_builder.build();
+        let index = Index::builder().schema(schema).create_in_ram()?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            // put enough data create enough blocks in the doc store to be considered for stacking
+            for segment in &["first", "second"] {
+                for i in 0..200 {
+                    let text = format!("{} {} {}", segment, i, LOREM);
+                    index_writer.add_document(doc!(text_field=> text))?;
+                }
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        let mut segment_ids = Vec::new();
+        let mut expected_block_data = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            segment_ids.push(segment_reader.segment_id());
+            let store = segment_reader.get_store_reader()?;
+            assert!(store.block_checkpoints().count() >= 6);
+            expected_block_data.extend_from_slice(store.block_data()?.as_slice());
+        }
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            block_on(index_writer.merge(&segment_ids))?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        let store = reader.get_store_reader()?;
+        // Without deletes, the blocks are copied verbatim.
+        assert_eq!(store.block_data()?.as_slice(), &expected_block_data[..]);
+        assert_eq!(store.iter_raw(None).count(), 400);
+
+        // With deletes, the documents are recompressed.
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.delete_term(Term::from_field_text(text_field, "first"));
+            index_writer.commit()?;
+            let segment_ids = index.searchable_segment_ids()?;
+            block_on(index_writer.merge(&segment_ids))?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 200);
+        let reader = searcher.segment_reader(0);
+        let store = reader.get_store_reader()?;
+        assert!(store.block_data()?.len() < expected_block_data.len());
+        assert_eq!(store.iter_raw(None).count(), 200);
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_raw_fields() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text_field", TEXT | STORED);
+        let blob_field = schema_builder
+            .add_bytes_field("blob", schema::BytesOptions::default().set_stored_raw());
+        let schema = schema_builder.build();
+        let index = Index::builder().schema(schema).create_in_ram()?;
+        let blob = |segment: &str, i: usize| format!("blob {} {}", segment, i).into_bytes();
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            for segment in &["first", "second"] {
+                for i in 0..200 {
+                    let text = format!("{} {} {}", segment, i, LOREM);
+                    index_writer
+                        .add_document(doc!(text_field => text, blob_field => blob(segment, i)))?;
+                }
+                index_writer.add_document(doc!(text_field => *segment))?;
+                index_writer.commit()?;
+            }
+        }
+        let check_store = |store: &StoreReader, segments: &[&str]| -> crate::Result<()> {
+            let block_data = store.block_data()?;
+            let mut doc_id = 0;
+            for segment in segments {
+                for i in 0..200 {
+                    let doc = store.get(doc_id)?;
+                    let text = format!("{} {} {}", segment, i, LOREM);
+                    assert_eq!(doc.get_first(text_field).unwrap().text(), Some(&text[..]));
+                    let expected_blob = blob(segment, i);
+                    assert_eq!(
+                        doc.get_first(blob_field).unwrap().bytes_value(),
+                        Some(&expected_blob[..])
+                    );
+                    let raw_values = store.get_raw_values(doc_id)?;
+                    assert_eq!(raw_values.len(), 1);
+                    assert_eq!(
+                        raw_values.get_first(blob_field).unwrap().bytes_value(),
+                        Some(&expected_blob[..])
+                    );
+                    // The raw values are written uncompressed.
+                    assert!(block_data
+                        .as_slice()
+                        .windows(expected_blob.len())
+                        .any(|window| window == &expected_blob[..]));
+                    doc_id += 1;
+                }
+                let doc = store.get(doc_id)?;
+                assert!(doc.get_first(blob_field).is_none());
+                assert!(store.get_raw_values(doc_id)?.is_empty());
+                doc_id += 1;
+            }
+            assert_eq!(store.iter(None).count(), doc_id as usize);
+            Ok(())
+        };
+        let searcher = index.reader()?.searcher();
+        let mut segment_ids = Vec::new();
+        let mut segments = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            segment_ids.push(segment_reader.segment_id());
+            let store = segment_reader.get_store_reader()?;
+            assert!(store.block_checkpoints().count() >= 6);
+            let first_doc = store.get(0)?;
+            let first_text = first_doc.get_first(text_field).unwrap().text().unwrap();
+            let segment = if first_text.starts_with("first") {
+                "first"
+            } else {
+                "second"
+            };
+            check_store(&store, &[segment])?;
+            segments.push(segment);
+        }
+        // Without deletes, the stores are stacked.
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            block_on(index_writer.merge(&segment_ids))?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        check_store(&searcher.segment_reader(0).get_store_reader()?, &segments)?;
+        // With deletes, the documents are stored one by one.
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.delete_term(Term::from_field_text(text_field, "first"));
+            index_writer.commit()?;
+            let segment_ids = index.searchable_segment_ids()?;
+            block_on(index_writer.merge(&segment_ids))?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 201);
+        check_store(&searcher.segment_reader(0).get_store_reader()?, &["second"])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_blocksize_setting() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text_field", TEXT | STORED);
+        let schema = schema_builder.build();
+        let settings = IndexSettings {
+            docstore_blocksize: 100,
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema)
+            .settings(settings)
+            .create_in_ram()?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            for _ in 0..10 {
+                index_writer.add_document(doc!(text_field=> LOREM))?;
+            }
+            index_writer.commit()?;
+        }
+        assert_eq!(index.settings().docstore_blocksize, 100);
+        let searcher = index.reader()?.searcher();
+        let store = searcher.segment_reader(0).get_store_reader()?;
+        assert_eq!(store.block_checkpoints().count(), 10);
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "unstable"))]
 mod bench {
 
-    use super::tests::write_lorem_ipsum_store;
+    use super::tests::{write_lorem_ipsum_store, BLOCK_SIZE};
     use crate::directory::Directory;
     use crate::directory::RamDirectory;
     use crate::store::Compressor;
@@ -364,6 +833,7 @@ mod bench {
                 directory.open_write(path).unwrap(),
                 1_000,
                 Compressor::default(),
+                BLOCK_SIZE,
             );
             directory.delete(path).unwrap();
         });
@@ -377,6 +847,7 @@ mod bench {
             directory.open_write(path).unwrap(),
             1_000,
             Compressor::default(),
+            BLOCK_SIZE,
         );
         let store_file = directory.open_read(path).unwrap();
         let store = StoreReader::open(store_file).unwrap();
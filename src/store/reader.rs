@@ -1,7 +1,8 @@
-use super::Compressor;
+use super::compressors::DecompressionContext;
 use super::{footer::DocStoreFooter, index::SkipIndex};
+use super::{Compressor, ExternalDocStore, StoreDictionary};
 use crate::directory::{FileSlice, OwnedBytes};
-use crate::schema::Document;
+use crate::schema::{Document, Field};
 use crate::space_usage::StoreSpaceUsage;
 use crate::store::index::Checkpoint;
 use crate::DocId;
@@ -9,24 +10,57 @@ use crate::{error::DataCorruption, fastfield::AliveBitSet};
 use common::{BinarySerializable, HasLen, VInt};
 use lru::LruCache;
 use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-const LRU_CACHE_CAPACITY: usize = 100;
+/// Number of decompressed blocks kept in the cache, unless configured otherwise
+/// with `IndexSettings::docstore_cache_num_blocks`.
+pub(crate) const DEFAULT_CACHE_NUM_BLOCKS: usize = 100;
 
 type Block = OwnedBytes;
 
 type BlockCache = Arc<Mutex<LruCache<usize, Block>>>;
 
+/// Number of bytes of the start and end offsets of the raw values of a document.
+const RAW_OFFSETS_NUM_BYTES: usize = 16;
+
+/// Maximum number of bytes of the length of the raw values of a block.
+const RAW_BLOCK_HEADER_MAX_NUM_BYTES: usize = 10;
+
+/// Statistics of the cache of decompressed blocks of a [`StoreReader`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Number of blocks in the cache.
+    pub num_entries: usize,
+    /// Maximum number of blocks in the cache.
+    pub capacity: usize,
+    /// Number of blocks read from the cache.
+    pub cache_hits: usize,
+    /// Number of blocks read and decompressed because they were not in the cache.
+    pub cache_misses: usize,
+}
+
 /// Reads document off tantivy's [`Store`](./index.html)
+///
+/// Cloning a `StoreReader` is cheap, and the clones share the same
+/// cache of decompressed blocks, and the same decompression context.
+#[derive(Clone)]
 pub struct StoreReader {
     compressor: Compressor,
+    dictionary: Option<StoreDictionary>,
+    decompression_context: Arc<Mutex<DecompressionContext>>,
+    raw_offsets: Option<OwnedBytes>,
     data: FileSlice,
+    offset_index_file: FileSlice,
     cache: BlockCache,
     cache_hits: Arc<AtomicUsize>,
     cache_misses: Arc<AtomicUsize>,
     skip_index: Arc<SkipIndex>,
     space_usage: StoreSpaceUsage,
+    filepath: Option<PathBuf>,
+    external_store: Option<Arc<dyn ExternalDocStore>>,
 }
 
 impl StoreReader {
@@ -38,17 +72,114 @@ impl StoreReader {
         let index_data = offset_index_file.read_bytes()?;
         let space_usage = StoreSpaceUsage::new(data_file.len(), offset_index_file.len());
         let skip_index = SkipIndex::open(index_data);
+        let (data_file, raw_offsets_file) =
+            data_file.split_from_end(footer.raw_offsets_len as usize);
+        let (data_file, dictionary_file) = data_file.split_from_end(footer.dictionary_len as usize);
+        let dictionary = if dictionary_file.is_empty() {
+            None
+        } else {
+            Some(StoreDictionary::open(dictionary_file.read_bytes()?))
+        };
+        let raw_offsets = if raw_offsets_file.is_empty() {
+            None
+        } else {
+            Some(raw_offsets_file.read_bytes()?)
+        };
         Ok(StoreReader {
             compressor: footer.compressor,
+            dictionary,
+            decompression_context: Default::default(),
+            raw_offsets,
             data: data_file,
-            cache: Arc::new(Mutex::new(LruCache::new(LRU_CACHE_CAPACITY))),
+            offset_index_file,
+            cache: Arc::new(Mutex::new(LruCache::new(DEFAULT_CACHE_NUM_BLOCKS))),
             cache_hits: Default::default(),
             cache_misses: Default::default(),
             skip_index: Arc::new(skip_index),
             space_usage,
+            filepath: None,
+            external_store: None,
         })
     }
 
+    /// Sets the number of decompressed blocks kept in the cache.
+    ///
+    /// The cache is replaced by an empty one, so this should be called right after opening.
+    pub(crate) fn with_cache_num_blocks(mut self, cache_num_blocks: usize) -> StoreReader {
+        self.cache = Arc::new(Mutex::new(LruCache::new(cache_num_blocks)));
+        self
+    }
+
+    /// Returns the statistics of the cache of decompressed blocks,
+    /// shared by all of the clones of the `StoreReader`.
+    pub fn cache_stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats {
+            num_entries: cache.len(),
+            capacity: cache.cap(),
+            cache_hits: self.cache_hits.load(Ordering::SeqCst),
+            cache_misses: self.cache_misses.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Sets the path of the store file, reported in the `DataCorruption` errors.
+    pub(crate) fn with_filepath(mut self, filepath: PathBuf) -> StoreReader {
+        self.filepath = Some(filepath);
+        self
+    }
+
+    /// Reads the stored fields of the documents from `external_store`, the doc store
+    /// only holding their keys. See [`ExternalDocStore`](./trait.ExternalDocStore.html).
+    pub fn with_external_store(mut self, external_store: Arc<dyn ExternalDocStore>) -> StoreReader {
+        self.external_store = Some(external_store);
+        self
+    }
+
+    /// Returns the external store holding the stored fields of the documents, if any.
+    pub(crate) fn external_store(&self) -> Option<&Arc<dyn ExternalDocStore>> {
+        self.external_store.as_ref()
+    }
+
+    /// Returns the serialized stored fields of a document, given its entry in the
+    /// doc store: the entry itself, or its key in the external store.
+    pub(crate) fn resolve_entry(
+        &self,
+        doc_id: DocId,
+        entry: OwnedBytes,
+    ) -> crate::Result<OwnedBytes> {
+        let external_store = match self.external_store.as_ref() {
+            Some(external_store) => external_store,
+            None => return Ok(entry),
+        };
+        let doc_bytes = external_store.get(entry.as_slice())?.ok_or_else(|| {
+            let comment = format!(
+                "Doc #{} is missing from the external doc store (key {:?})",
+                doc_id,
+                entry.as_slice()
+            );
+            match self.filepath.as_ref() {
+                Some(filepath) => {
+                    DataCorruption::comment_only(comment).with_filepath(filepath.clone())
+                }
+                None => DataCorruption::comment_only(comment),
+            }
+        })?;
+        Ok(OwnedBytes::new(doc_bytes))
+    }
+
+    fn data_corruption(&self, offset: usize, comment: String) -> DataCorruption {
+        let data_corruption = DataCorruption::comment_only(comment).with_offset(offset as u64);
+        match self.filepath.as_ref() {
+            Some(filepath) => data_corruption.with_filepath(filepath.clone()),
+            None => data_corruption,
+        }
+    }
+
+    /// Returns the data of the doc store index.
+    pub(crate) fn offset_index_file(&self) -> &FileSlice {
+        &self.offset_index_file
+    }
+
     pub(crate) fn block_checkpoints(&self) -> impl Iterator<Item = Checkpoint> + '_ {
         self.skip_index.checkpoints()
     }
@@ -57,6 +188,59 @@ impl StoreReader {
         self.compressor
     }
 
+    /// Returns true iff the blocks are compressed with a dictionary.
+    pub(crate) fn has_dictionary(&self) -> bool {
+        self.dictionary.is_some()
+    }
+
+    /// Returns the number of documents in the store, including the deleted ones.
+    pub(crate) fn max_doc(&self) -> DocId {
+        self.block_checkpoints()
+            .last()
+            .map(|checkpoint| checkpoint.doc_range.end)
+            .unwrap_or(0)
+    }
+
+    /// Returns the byte range of the raw values of the document, if it has any.
+    pub(crate) fn raw_range(&self, doc_id: DocId) -> io::Result<Option<Range<usize>>> {
+        let raw_offsets = match self.raw_offsets.as_ref() {
+            Some(raw_offsets) => raw_offsets,
+            None => return Ok(None),
+        };
+        let start = doc_id as usize * RAW_OFFSETS_NUM_BYTES;
+        let mut raw_offsets_bytes = raw_offsets
+            .as_slice()
+            .get(start..start + RAW_OFFSETS_NUM_BYTES)
+            .unwrap_or(&[]);
+        if raw_offsets_bytes.is_empty() {
+            return Ok(None);
+        }
+        let start_offset = u64::deserialize(&mut raw_offsets_bytes)? as usize;
+        let end_offset = u64::deserialize(&mut raw_offsets_bytes)? as usize;
+        if start_offset == end_offset {
+            return Ok(None);
+        }
+        Ok(Some(start_offset..end_offset))
+    }
+
+    /// Appends the raw values of the document to its serialized values.
+    fn with_raw_values(&self, doc_id: DocId, doc_bytes: OwnedBytes) -> io::Result<OwnedBytes> {
+        let raw_range = match self.raw_range(doc_id)? {
+            Some(raw_range) => raw_range,
+            None => return Ok(doc_bytes),
+        };
+        let raw_bytes = self.data.slice(raw_range).read_bytes()?;
+        let mut doc_cursor = doc_bytes.as_slice();
+        let num_field_values = VInt::deserialize(&mut doc_cursor)?.val();
+        let mut raw_cursor = raw_bytes.as_slice();
+        let num_raw_field_values = VInt::deserialize(&mut raw_cursor)?.val();
+        let mut merged_doc_bytes = Vec::with_capacity(doc_bytes.len() + raw_bytes.len());
+        VInt(num_field_values + num_raw_field_values).serialize(&mut merged_doc_bytes)?;
+        merged_doc_bytes.extend_from_slice(doc_cursor);
+        merged_doc_bytes.extend_from_slice(raw_cursor);
+        Ok(OwnedBytes::new(merged_doc_bytes))
+    }
+
     fn block_checkpoint(&self, doc_id: DocId) -> Option<Checkpoint> {
         self.skip_index.seek(doc_id)
     }
@@ -65,8 +249,24 @@ impl StoreReader {
         self.data.read_bytes()
     }
 
+    /// Returns true iff some fields of the store are stored raw.
+    pub(crate) fn has_raw_values(&self) -> bool {
+        self.raw_offsets.is_some()
+    }
+
     fn compressed_block(&self, checkpoint: &Checkpoint) -> io::Result<OwnedBytes> {
-        self.data.slice(checkpoint.byte_range.clone()).read_bytes()
+        let mut byte_range = checkpoint.byte_range.clone();
+        if self.has_raw_values() {
+            // The raw values of the documents of the block, prefixed by their
+            // length, come before its compressed data.
+            let header_end =
+                (byte_range.start + RAW_BLOCK_HEADER_MAX_NUM_BYTES).min(byte_range.end);
+            let header = self.data.slice(byte_range.start..header_end).read_bytes()?;
+            let mut header_cursor = header.as_slice();
+            let raw_block_len = VInt::deserialize(&mut header_cursor)?.val() as usize;
+            byte_range.start += header.len() - header_cursor.len() + raw_block_len;
+        }
+        self.data.slice(byte_range).read_bytes()
     }
 
     fn read_block(&self, checkpoint: &Checkpoint) -> io::Result<Block> {
@@ -79,8 +279,20 @@ impl StoreReader {
 
         let compressed_block = self.compressed_block(checkpoint)?;
         let mut decompressed_block = vec![];
-        self.compressor
-            .decompress(compressed_block.as_slice(), &mut decompressed_block)?;
+        let mut decompress = |context: &mut DecompressionContext| {
+            self.compressor.decompress(
+                compressed_block.as_slice(),
+                &mut decompressed_block,
+                self.dictionary.as_ref(),
+                context,
+            )
+        };
+        // When the context is in use by another thread, decompressing with
+        // a fresh context is cheaper than waiting for it.
+        match self.decompression_context.try_lock() {
+            Ok(mut context) => decompress(&mut context),
+            Err(_) => decompress(&mut DecompressionContext::default()),
+        }?;
 
         let block = OwnedBytes::new(decompressed_block);
         self.cache
@@ -105,30 +317,99 @@ impl StoreReader {
         Ok(Document::deserialize(&mut doc_bytes)?)
     }
 
+    /// Reads a given document, only keeping the values of the given `fields`.
+    ///
+    /// The values of the other fields are skipped over without being
+    /// deserialized, which is cheaper than `.get(doc)` when only a few
+    /// small fields (e.g. a title or an id) are needed.
+    pub fn get_fields(&self, doc_id: DocId, fields: &[Field]) -> crate::Result<Document> {
+        let doc_bytes = self.get_document_bytes(doc_id)?;
+        Ok(Document::deserialize_fields(
+            &mut doc_bytes.as_slice(),
+            fields,
+        )?)
+    }
+
+    /// Reads the values of the fields stored raw of a given document.
+    ///
+    /// These values are read directly at their offsets, without decompressing
+    /// any block.
+    pub fn get_raw_values(&self, doc_id: DocId) -> crate::Result<Document> {
+        match self.raw_range(doc_id)? {
+            Some(raw_range) => {
+                let mut raw_bytes = self.data.slice(raw_range).read_bytes()?;
+                Ok(Document::deserialize(&mut raw_bytes)?)
+            }
+            None => Ok(Document::new()),
+        }
+    }
+
     /// Reads raw bytes of a given document. Returns `RawDocument`, which contains the block of a document and its start and end
     /// position within the block.
     ///
+    /// The values of the fields stored raw come after the other values of the document.
+    ///
     /// Calling `.get(doc)` is relatively costly as it requires
     /// decompressing a compressed block. The store utilizes a LRU cache,
     /// so accessing docs from the same compressed block should be faster.
     /// For that reason a store reader should be kept and reused.
     ///
     pub fn get_document_bytes(&self, doc_id: DocId) -> crate::Result<OwnedBytes> {
+        let entry = self.get_entry(doc_id)?;
+        self.resolve_entry(doc_id, entry)
+    }
+
+    /// Reads the entry of a given document in the doc store.
+    fn get_entry(&self, doc_id: DocId) -> crate::Result<OwnedBytes> {
         let checkpoint = self.block_checkpoint(doc_id).ok_or_else(|| {
             crate::TantivyError::InvalidArgument(format!("Failed to lookup Doc #{}.", doc_id))
         })?;
+        let block_offset = checkpoint.byte_range.start;
+        if checkpoint.byte_range.end > self.data.len() {
+            return Err(self
+                .data_corruption(
+                    block_offset,
+                    format!(
+                        "The block of doc #{} ends at {}, after the end of the doc store",
+                        doc_id, checkpoint.byte_range.end
+                    ),
+                )
+                .into());
+        }
         let block = self.read_block(&checkpoint)?;
         let mut cursor = &block[..];
         let cursor_len_before = cursor.len();
-        for _ in checkpoint.doc_range.start..doc_id {
-            let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
+        for doc in checkpoint.doc_range.start..doc_id {
+            let doc_length = self.read_doc_length(&mut cursor, doc, block_offset)?;
             cursor = &cursor[doc_length..];
         }
-
-        let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
+        let doc_length = self.read_doc_length(&mut cursor, doc_id, block_offset)?;
         let start_pos = cursor_len_before - cursor.len();
-        let end_pos = cursor_len_before - cursor.len() + doc_length;
-        Ok(block.slice(start_pos..end_pos))
+        let end_pos = start_pos + doc_length;
+        Ok(self.with_raw_values(doc_id, block.slice(start_pos..end_pos))?)
+    }
+
+    /// Reads the length of the serialized values of `doc`, which must fit in the rest of its block.
+    fn read_doc_length(
+        &self,
+        cursor: &mut &[u8],
+        doc: DocId,
+        block_offset: usize,
+    ) -> crate::Result<usize> {
+        let doc_length = VInt::deserialize(cursor)?.val() as usize;
+        if doc_length > cursor.len() {
+            return Err(self
+                .data_corruption(
+                    block_offset,
+                    format!(
+                        "The length of doc #{} exceeds its block by {} bytes",
+                        doc,
+                        doc_length - cursor.len()
+                    ),
+                )
+                .into());
+        }
+        Ok(doc_length)
     }
 
     /// Iterator over all Documents in their order as they are stored in the doc store.
@@ -151,11 +432,20 @@ impl StoreReader {
         &'b self,
         alive_bitset: Option<&'a AliveBitSet>,
     ) -> impl Iterator<Item = crate::Result<OwnedBytes>> + 'b {
-        let last_doc_id = self
-            .block_checkpoints()
-            .last()
-            .map(|checkpoint| checkpoint.doc_range.end)
-            .unwrap_or(0);
+        self.iter_entries(alive_bitset).map(move |entry_res| {
+            let (doc_id, entry) = entry_res?;
+            self.resolve_entry(doc_id, entry)
+        })
+    }
+
+    /// Iterator over the entries of the documents in the doc store, with their doc ids.
+    ///
+    /// With an external store, the entries are the keys of the documents in the external store.
+    pub(crate) fn iter_entries<'a: 'b, 'b>(
+        &'b self,
+        alive_bitset: Option<&'a AliveBitSet>,
+    ) -> impl Iterator<Item = crate::Result<(DocId, OwnedBytes)>> + 'b {
+        let last_doc_id = self.max_doc();
         let mut checkpoint_block_iter = self.block_checkpoints();
         let mut curr_checkpoint = checkpoint_block_iter.next();
         let mut curr_block = curr_checkpoint
@@ -181,7 +471,7 @@ impl StoreReader {
 
                 let alive = alive_bitset.map_or(true, |bitset| bitset.is_alive(doc_id));
                 if alive {
-                    let ret = Some((curr_block.clone(), num_skipped, reset_block_pos));
+                    let ret = Some((doc_id, curr_block.clone(), num_skipped, reset_block_pos));
                     // the map block will move over the num_skipped, so we reset to 0
                     num_skipped = 0;
                     reset_block_pos = false;
@@ -192,7 +482,7 @@ impl StoreReader {
                     None
                 }
             })
-            .map(move |(block, num_skipped, reset_block_pos)| {
+            .map(move |(doc_id, block, num_skipped, reset_block_pos)| {
                 let block = block
                     .ok_or_else(|| {
                         DataCorruption::comment_only(
@@ -225,7 +515,7 @@ impl StoreReader {
                 let end_pos = block_start_pos + doc_length;
                 let doc_bytes = block.slice(block_start_pos..end_pos);
                 block_start_pos = end_pos;
-                Ok(doc_bytes)
+                Ok((doc_id, self.with_raw_values(doc_id, doc_bytes)?))
             })
     }
 
@@ -240,7 +530,8 @@ mod tests {
     use super::*;
     use crate::schema::Document;
     use crate::schema::Field;
-    use crate::{directory::RamDirectory, store::tests::write_lorem_ipsum_store, Directory};
+    use crate::store::tests::{write_lorem_ipsum_store, BLOCK_SIZE};
+    use crate::{directory::RamDirectory, Directory};
     use std::path::Path;
 
     fn get_text_field<'a>(doc: &'a Document, field: &'a Field) -> Option<&'a str> {
@@ -252,7 +543,7 @@ mod tests {
         let directory = RamDirectory::create();
         let path = Path::new("store");
         let writer = directory.open_write(path)?;
-        let schema = write_lorem_ipsum_store(writer, 500, Compressor::default());
+        let schema = write_lorem_ipsum_store(writer, 500, Compressor::default(), BLOCK_SIZE);
         let title = schema.get_field("title").unwrap();
         let store_file = directory.open_read(path)?;
         let store = StoreReader::open(store_file)?;
@@ -312,4 +603,26 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_store_lru_cache_shared_across_clones() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let path = Path::new("store");
+        let writer = directory.open_write(path)?;
+        let schema = write_lorem_ipsum_store(writer, 500, Compressor::default(), BLOCK_SIZE);
+        let title = schema.get_field("title").unwrap();
+        let store_file = directory.open_read(path)?;
+        let store = StoreReader::open(store_file)?;
+        let store_clone = store.clone();
+
+        let doc = store.get(0)?;
+        assert_eq!(get_text_field(&doc, &title), Some("Doc 0"));
+        let doc = store_clone.get(1)?;
+        assert_eq!(get_text_field(&doc, &title), Some("Doc 1"));
+
+        assert_eq!(store.cache.lock().unwrap().len(), 1);
+        assert_eq!(store.cache_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(store.cache_misses.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
 }
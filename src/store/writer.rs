@@ -1,16 +1,19 @@
+use super::compressors::{CompressionContext, Compressor};
+use super::footer::DocStoreFooter;
 use super::index::SkipIndexBuilder;
-use super::StoreReader;
-use super::{compressors::Compressor, footer::DocStoreFooter};
+use super::{ExternalDocStore, StoreDictionary, StoreReader};
 use crate::directory::TerminatingWrite;
 use crate::directory::WritePtr;
-use crate::schema::Document;
+use crate::schema::{Document, Field, FieldValue};
 use crate::store::index::Checkpoint;
 use crate::DocId;
 use common::CountingWriter;
 use common::{BinarySerializable, VInt};
 use std::io::{self, Write};
-
-const BLOCK_SIZE: usize = 16_384;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use uuid::Uuid;
 
 /// Write tantivy's [`Store`](./index.html)
 ///
@@ -20,40 +23,132 @@ const BLOCK_SIZE: usize = 16_384;
 ///
 /// The skip list index on the other hand, is built in memory.
 ///
+/// The values of the fields stored raw are written uncompressed, in front of
+/// the compressed data of their block, and their offsets are kept in memory
+/// until the store is closed.
+///
+/// With an [`ExternalDocStore`](./trait.ExternalDocStore.html), the documents are put in
+/// the external store, and only their keys are written in the blocks.
 pub struct StoreWriter {
     compressor: Compressor,
+    dictionary: Option<StoreDictionary>,
+    compression_context: CompressionContext,
+    raw_fields: Vec<Field>,
+    raw_offsets: Vec<Range<usize>>,
+    current_raw_block: Vec<u8>,
+    block_size: usize,
     doc: DocId,
     first_doc_in_block: DocId,
     offset_index_writer: SkipIndexBuilder,
     writer: CountingWriter<WritePtr>,
     intermediary_buffer: Vec<u8>,
     current_block: Vec<u8>,
+    external_store: Option<(Arc<dyn ExternalDocStore>, Uuid)>,
 }
 
 impl StoreWriter {
     /// Create a store writer.
     ///
     /// The store writer will writes blocks on disc as
-    /// document are added.
-    pub fn new(writer: WritePtr, compressor: Compressor) -> StoreWriter {
+    /// document are added. A block is compressed and written
+    /// as soon as its size exceeds `block_size` bytes.
+    pub fn new(writer: WritePtr, compressor: Compressor, block_size: usize) -> StoreWriter {
         StoreWriter {
             compressor,
+            dictionary: None,
+            compression_context: CompressionContext::default(),
+            raw_fields: Vec::new(),
+            raw_offsets: Vec::new(),
+            current_raw_block: Vec::new(),
+            block_size,
             doc: 0,
             first_doc_in_block: 0,
             offset_index_writer: SkipIndexBuilder::new(),
             writer: CountingWriter::wrap(writer),
             intermediary_buffer: Vec::new(),
             current_block: Vec::new(),
+            external_store: None,
         }
     }
 
+    /// Puts the stored fields of the documents in `external_store`, and only
+    /// writes their keys in the doc store.
+    ///
+    /// The fields stored raw and the compression dictionary are ignored, as
+    /// they only apply to the stored fields.
+    pub fn with_external_store(mut self, external_store: Arc<dyn ExternalDocStore>) -> StoreWriter {
+        self.external_store = Some((external_store, Uuid::new_v4()));
+        self
+    }
+
+    /// Returns true iff the entries of the documents of `store_reader` can be copied
+    /// as they are, i.e. both of them hold the stored fields themselves, or the keys
+    /// of the stored fields in the same external store.
+    pub(crate) fn shares_entries_with(&self, store_reader: &StoreReader) -> bool {
+        match (self.external_store.as_ref(), store_reader.external_store()) {
+            (None, None) => true,
+            (Some((external_store, _)), Some(reader_external_store)) => {
+                Arc::as_ptr(external_store) as *const u8
+                    == Arc::as_ptr(reader_external_store) as *const u8
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true iff the keys of the documents of `store_reader` in the external
+    /// store can be copied over, instead of putting the documents again.
+    pub(crate) fn copies_entries_of(&self, store_reader: &StoreReader) -> bool {
+        self.external_store.is_some() && self.shares_entries_with(store_reader)
+    }
+
     pub(crate) fn compressor(&self) -> Compressor {
         self.compressor
     }
 
+    /// Sets the dictionary used to compress the blocks.
+    ///
+    /// # Panics
+    /// If documents were already written.
+    pub(crate) fn set_dictionary(&mut self, dictionary: StoreDictionary) {
+        assert_eq!(
+            self.doc, 0,
+            "The dictionary must be set before writing documents."
+        );
+        if self.external_store.is_none() {
+            self.dictionary = Some(dictionary);
+        }
+    }
+
+    /// Returns true iff the blocks are compressed with a dictionary.
+    pub(crate) fn has_dictionary(&self) -> bool {
+        self.dictionary.is_some()
+    }
+
+    /// Sets the fields whose values are stored uncompressed.
+    ///
+    /// # Panics
+    /// If documents were already written.
+    pub(crate) fn set_raw_fields(&mut self, raw_fields: Vec<Field>) {
+        assert_eq!(
+            self.doc, 0,
+            "The raw fields must be set before writing documents."
+        );
+        if self.external_store.is_none() {
+            self.raw_fields = raw_fields;
+        }
+    }
+
+    /// Returns true iff some fields are stored uncompressed.
+    pub(crate) fn has_raw_fields(&self) -> bool {
+        !self.raw_fields.is_empty()
+    }
+
     /// The memory used (inclusive childs)
     pub fn mem_usage(&self) -> usize {
-        self.intermediary_buffer.capacity() + self.current_block.capacity()
+        self.intermediary_buffer.capacity()
+            + self.current_block.capacity()
+            + self.current_raw_block.capacity()
+            + self.raw_offsets.capacity() * mem::size_of::<Range<usize>>()
     }
 
     /// Store bytes of a serialized document.
@@ -62,11 +157,26 @@ impl StoreWriter {
     /// of documents.
     ///
     pub fn store_bytes(&mut self, serialized_document: &[u8]) -> io::Result<()> {
-        let doc_num_bytes = serialized_document.len();
-        VInt(doc_num_bytes as u64).serialize(&mut self.current_block)?;
-        self.current_block.write_all(serialized_document)?;
+        if let Some((external_store, writer_id)) = self.external_store.as_ref() {
+            let key = external_key(writer_id, self.doc);
+            external_store.put(&key, serialized_document)?;
+            return self.store_entry(&key);
+        }
+        if !self.raw_fields.is_empty() {
+            // The raw values have to be split from the other ones.
+            let document = Document::deserialize(&mut &serialized_document[..])?;
+            return self.store(&document);
+        }
+        self.store_entry(serialized_document)
+    }
+
+    /// Writes the entry of the next document in the doc store: its serialized
+    /// stored fields, or their key in the external store.
+    pub(crate) fn store_entry(&mut self, entry: &[u8]) -> io::Result<()> {
+        VInt(entry.len() as u64).serialize(&mut self.current_block)?;
+        self.current_block.write_all(entry)?;
         self.doc += 1;
-        if self.current_block.len() > BLOCK_SIZE {
+        if self.current_block.len() > self.block_size {
             self.write_and_compress_block()?;
         }
         Ok(())
@@ -79,7 +189,17 @@ impl StoreWriter {
     ///
     pub fn store(&mut self, stored_document: &Document) -> io::Result<()> {
         self.intermediary_buffer.clear();
-        stored_document.serialize(&mut self.intermediary_buffer)?;
+        if let Some((external_store, writer_id)) = self.external_store.as_ref() {
+            stored_document.serialize(&mut self.intermediary_buffer)?;
+            let key = external_key(writer_id, self.doc);
+            external_store.put(&key, &self.intermediary_buffer)?;
+            return self.store_entry(&key);
+        }
+        if self.raw_fields.is_empty() {
+            stored_document.serialize(&mut self.intermediary_buffer)?;
+        } else {
+            self.write_raw_values(stored_document)?;
+        }
         // calling store bytes would be preferable for code reuse, but then we can't use
         // intermediary_buffer due to the borrow checker
         // a new buffer costs ~1% indexing performance
@@ -88,17 +208,39 @@ impl StoreWriter {
         self.current_block
             .write_all(&self.intermediary_buffer[..])?;
         self.doc += 1;
-        if self.current_block.len() > BLOCK_SIZE {
+        if self.current_block.len() + self.current_raw_block.len() > self.block_size {
             self.write_and_compress_block()?;
         }
         Ok(())
     }
 
+    /// Serializes the raw values of the document to the current raw block, and
+    /// its other values to the intermediary buffer.
+    fn write_raw_values(&mut self, stored_document: &Document) -> io::Result<()> {
+        let (raw_field_values, field_values): (Vec<&FieldValue>, Vec<&FieldValue>) =
+            stored_document
+                .field_values()
+                .iter()
+                .partition(|field_value| self.raw_fields.contains(&field_value.field()));
+        serialize_field_values(&field_values, &mut self.intermediary_buffer)?;
+        // The offsets are relative to the raw block until it is written.
+        let start_offset = self.current_raw_block.len();
+        if !raw_field_values.is_empty() {
+            serialize_field_values(&raw_field_values, &mut self.current_raw_block)?;
+        }
+        let end_offset = self.current_raw_block.len();
+        self.raw_offsets.push(start_offset..end_offset);
+        Ok(())
+    }
+
     /// Stacks a store reader on top of the documents written so far.
     /// This method is an optimization compared to iterating over the documents
     /// in the store and adding them one by one, as the store's data will
     /// not be decompressed and then recompressed.
+    ///
+    /// Stores compressed with a dictionary cannot be stacked.
     pub fn stack(&mut self, store_reader: &StoreReader) -> io::Result<()> {
+        assert!(!self.has_dictionary() && !store_reader.has_dictionary());
         if !self.current_block.is_empty() {
             self.write_and_compress_block()?;
         }
@@ -119,6 +261,15 @@ impl StoreWriter {
             checkpoint.byte_range.end += start_shift;
             self.register_checkpoint(checkpoint);
         }
+        if !self.raw_fields.is_empty() {
+            for doc_id in 0..store_reader.max_doc() {
+                let raw_range = store_reader
+                    .raw_range(doc_id)?
+                    .map(|raw_range| raw_range.start + start_shift..raw_range.end + start_shift)
+                    .unwrap_or(0..0);
+                self.raw_offsets.push(raw_range);
+            }
+        }
         Ok(())
     }
 
@@ -131,9 +282,25 @@ impl StoreWriter {
     fn write_and_compress_block(&mut self) -> io::Result<()> {
         assert!(self.doc > 0);
         self.intermediary_buffer.clear();
-        self.compressor
-            .compress(&self.current_block[..], &mut self.intermediary_buffer)?;
+        self.compressor.compress(
+            &self.current_block[..],
+            &mut self.intermediary_buffer,
+            self.dictionary.as_ref(),
+            &mut self.compression_context,
+        )?;
         let start_offset = self.writer.written_bytes() as usize;
+        if !self.raw_fields.is_empty() {
+            VInt(self.current_raw_block.len() as u64).serialize(&mut self.writer)?;
+            let raw_block_offset = self.writer.written_bytes() as usize;
+            self.writer.write_all(&self.current_raw_block)?;
+            for raw_range in &mut self.raw_offsets[self.first_doc_in_block as usize..] {
+                if raw_range.start != raw_range.end {
+                    raw_range.start += raw_block_offset;
+                    raw_range.end += raw_block_offset;
+                }
+            }
+            self.current_raw_block.clear();
+        }
         self.writer.write_all(&self.intermediary_buffer)?;
         let end_offset = self.writer.written_bytes() as usize;
         let end_doc = self.doc;
@@ -148,15 +315,52 @@ impl StoreWriter {
     /// Finalized the store writer.
     ///
     /// Compress the last unfinished block if any,
-    /// and serializes the skip list index on disc.
+    /// and serializes the dictionary, the offsets of the raw values and
+    /// the skip list index on disc.
     pub fn close(mut self) -> io::Result<()> {
         if !self.current_block.is_empty() {
             self.write_and_compress_block()?;
         }
+        let dictionary_len = if let Some(dictionary) = self.dictionary.as_ref() {
+            self.writer.write_all(dictionary.as_bytes())?;
+            dictionary.as_bytes().len() as u32
+        } else {
+            0
+        };
+        let raw_offsets_start = self.writer.written_bytes();
+        for raw_range in &self.raw_offsets {
+            (raw_range.start as u64).serialize(&mut self.writer)?;
+            (raw_range.end as u64).serialize(&mut self.writer)?;
+        }
+        let raw_offsets_len = self.writer.written_bytes() - raw_offsets_start;
         let header_offset: u64 = self.writer.written_bytes() as u64;
-        let footer = DocStoreFooter::new(header_offset, self.compressor);
+        let footer = DocStoreFooter::new(
+            header_offset,
+            self.compressor,
+            dictionary_len,
+            raw_offsets_len,
+        );
         self.offset_index_writer.write(&mut self.writer)?;
         footer.serialize(&mut self.writer)?;
         self.writer.terminate()
     }
 }
+
+/// Returns the key of the document `doc` of the store writer `writer_id` in the external store.
+fn external_key(writer_id: &Uuid, doc: DocId) -> [u8; 20] {
+    let mut key = [0u8; 20];
+    key[..16].copy_from_slice(writer_id.as_bytes());
+    key[16..].copy_from_slice(&doc.to_be_bytes());
+    key
+}
+
+fn serialize_field_values<W: Write>(
+    field_values: &[&FieldValue],
+    writer: &mut W,
+) -> io::Result<()> {
+    VInt(field_values.len() as u64).serialize(writer)?;
+    for field_value in field_values {
+        field_value.serialize(writer)?;
+    }
+    Ok(())
+}
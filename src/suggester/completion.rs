@@ -0,0 +1,166 @@
+use super::{collect_suggestions, levenshtein_automaton_builder, SpellingDictionary, Suggestion};
+use crate::query::DfaWrapper;
+use crate::schema::Field;
+use crate::Searcher;
+
+/// `CompletionSuggester` completes a prefix (typeahead) with the terms of a field.
+///
+/// Completions are served directly from the term dictionary of the field,
+/// using the document frequency of the terms as their weight.
+/// The most frequent terms starting with the prefix come first.
+/// For a large index, a [`SpellingDictionary`](./struct.SpellingDictionary.html)
+/// refreshed on each reload can serve them instead, see `set_dictionary`.
+///
+/// Fuzzy completion can be enabled with `set_max_distance`:
+/// terms starting with a prefix within the given Levenshtein distance of the
+/// original prefix are then completed as well, after the exact completions.
+///
+/// ```rust
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, CompletionSuggester, Index};
+/// # fn main() -> tantivy::Result<()> {
+/// # let mut schema_builder = Schema::builder();
+/// # let title = schema_builder.add_text_field("title", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// # let mut index_writer = index.writer(3_000_000)?;
+/// # index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+/// # index_writer.add_document(doc!(title => "Dear diary"))?;
+/// # index_writer.commit()?;
+/// # let searcher = index.reader()?.searcher();
+/// let completion_suggester = CompletionSuggester::new(title);
+/// let completions = completion_suggester.complete(&searcher, "d", 2)?;
+/// assert_eq!(completions[0].text(), "diary");
+/// assert_eq!(completions[1].text(), "dear");
+/// # Ok(())
+/// # }
+/// ```
+pub struct CompletionSuggester {
+    field: Field,
+    max_distance: u8,
+    dictionary: Option<SpellingDictionary>,
+}
+
+impl CompletionSuggester {
+    /// Creates a new completion suggester for the given field.
+    pub fn new(field: Field) -> CompletionSuggester {
+        CompletionSuggester {
+            field,
+            max_distance: 0,
+            dictionary: None,
+        }
+    }
+
+    /// Sets the maximum Levenshtein distance between the prefix and the
+    /// beginning of the completed terms.
+    ///
+    /// Defaults to 0, i.e. only terms starting with the exact prefix are completed.
+    pub fn set_max_distance(&mut self, max_distance: u8) {
+        self.max_distance = max_distance;
+    }
+
+    /// Searches the completions in `dictionary`, instead of the term dictionaries
+    /// of the segments of the searcher.
+    ///
+    /// The document frequencies of the terms are summed once, when the dictionary
+    /// is refreshed, and the searcher given to `complete` is then ignored.
+    pub fn set_dictionary(&mut self, dictionary: SpellingDictionary) {
+        self.dictionary = Some(dictionary);
+    }
+
+    /// Returns at most `limit` completions of `prefix`, best completion first.
+    pub fn complete(
+        &self,
+        searcher: &Searcher,
+        prefix: &str,
+        limit: usize,
+    ) -> crate::Result<Vec<Suggestion>> {
+        let automaton_builder = levenshtein_automaton_builder(self.max_distance)?;
+        let automaton = DfaWrapper(automaton_builder.build_prefix_dfa(prefix));
+        if let Some(dictionary) = self.dictionary.as_ref() {
+            return Ok(dictionary.suggestions(&automaton, limit));
+        }
+        collect_suggestions(searcher, self.field, &automaton, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompletionSuggester;
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, SpellingDictionary};
+
+    #[test]
+    fn test_complete() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "tantivy is a full text search engine"))?;
+        index_writer.add_document(doc!(title => "a text about tantrums"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "tantivy, tantivy!"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let mut completion_suggester = CompletionSuggester::new(title);
+        let completions = |completion_suggester: &CompletionSuggester,
+                           prefix: &str,
+                           limit: usize|
+         -> crate::Result<Vec<(String, u8, u64)>> {
+            Ok(completion_suggester
+                .complete(&searcher, prefix, limit)?
+                .into_iter()
+                .map(|suggestion| {
+                    (
+                        suggestion.text().to_string(),
+                        suggestion.distance(),
+                        suggestion.doc_freq(),
+                    )
+                })
+                .collect())
+        };
+        assert_eq!(
+            completions(&completion_suggester, "tan", 10)?,
+            vec![
+                ("tantivy".to_string(), 0, 2),
+                ("tantrums".to_string(), 0, 1)
+            ]
+        );
+        assert_eq!(
+            completions(&completion_suggester, "tan", 1)?,
+            vec![("tantivy".to_string(), 0, 2)]
+        );
+        assert_eq!(
+            completions(&completion_suggester, "tex", 10)?,
+            vec![("text".to_string(), 0, 2)]
+        );
+        assert!(completions(&completion_suggester, "zz", 10)?.is_empty());
+        assert!(completions(&completion_suggester, "tna", 10)?.is_empty());
+        completion_suggester.set_max_distance(1);
+        assert_eq!(
+            completions(&completion_suggester, "tna", 10)?,
+            vec![
+                ("tantivy".to_string(), 1, 2),
+                ("tantrums".to_string(), 1, 1)
+            ]
+        );
+
+        // The completions served by a dictionary are the same.
+        let dictionary = SpellingDictionary::new(vec![title]);
+        dictionary.refresh(&searcher)?;
+        completion_suggester.set_dictionary(dictionary);
+        assert_eq!(
+            completions(&completion_suggester, "tna", 1)?,
+            vec![("tantivy".to_string(), 1, 2)]
+        );
+        assert_eq!(
+            completions(&completion_suggester, "t", 3)?,
+            vec![
+                ("tantivy".to_string(), 0, 2),
+                ("text".to_string(), 0, 2),
+                ("tantrums".to_string(), 0, 1)
+            ]
+        );
+        assert!(completions(&completion_suggester, "t", 0)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use super::{Suggestion, TopSuggestions};
+use crate::query::DfaWrapper;
+use crate::reader::Warmer;
+use crate::schema::Field;
+use crate::Searcher;
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, RwLock};
+use tantivy_fst::{IntoStreamer, Map, Streamer};
+
+/// A compact spelling dictionary, holding the terms of one or more fields with
+/// their document frequencies in a single FST.
+///
+/// A [`SpellingSuggester`](./struct.SpellingSuggester.html) or a
+/// [`CompletionSuggester`](./struct.CompletionSuggester.html) using a dictionary (see
+/// their `set_dictionary` method) searches its candidates in the dictionary, instead
+/// of the term dictionaries of all of the segments of the searcher.
+/// Rare terms, which are often misspellings themselves, can be pruned from the dictionary
+/// with `with_min_doc_freq`.
+///
+/// The dictionary is empty until it is refreshed. Registering it as a
+/// [`Warmer`](./trait.Warmer.html) of the `IndexReader` refreshes it on each reload,
+/// e.g. after each commit. `SpellingDictionary` is cheap to clone, and its clones
+/// share the same terms.
+///
+/// ```rust
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, Index, SpellingDictionary, SpellingSuggester};
+/// # fn main() -> tantivy::Result<()> {
+/// # let mut schema_builder = Schema::builder();
+/// # let title = schema_builder.add_text_field("title", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// let dictionary = SpellingDictionary::new(vec![title]);
+/// let reader = index.reader_builder().warmer(dictionary.clone()).try_into()?;
+/// let mut index_writer = index.writer(3_000_000)?;
+/// index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+/// index_writer.commit()?;
+/// reader.reload()?;
+///
+/// let searcher = reader.searcher();
+/// let mut suggester = SpellingSuggester::create(&searcher, title)?;
+/// suggester.set_dictionary(dictionary);
+/// assert_eq!(suggester.suggest_phrase(&searcher, "Yuong Girl")?, "young girl");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SpellingDictionary {
+    fields: Vec<Field>,
+    min_doc_freq: u64,
+    terms: Arc<RwLock<Arc<Map<Vec<u8>>>>>,
+}
+
+impl SpellingDictionary {
+    /// Creates an empty dictionary of the terms of `fields`.
+    pub fn new(fields: Vec<Field>) -> SpellingDictionary {
+        let empty_terms = Map::from_iter(Vec::<(Vec<u8>, u64)>::new())
+            .expect("Building an empty FST should never fail");
+        SpellingDictionary {
+            fields,
+            min_doc_freq: 1,
+            terms: Arc::new(RwLock::new(Arc::new(empty_terms))),
+        }
+    }
+
+    /// Only keeps the terms contained in at least `min_doc_freq` documents.
+    ///
+    /// Defaults to 1, i.e. all of the terms are kept.
+    pub fn with_min_doc_freq(mut self, min_doc_freq: u64) -> SpellingDictionary {
+        self.min_doc_freq = min_doc_freq;
+        self
+    }
+
+    /// Rebuilds the dictionary from the terms of the segments of `searcher`.
+    ///
+    /// The document frequency of a term is summed over the fields and the segments,
+    /// and includes the deleted documents that are not merged away yet.
+    pub fn refresh(&self, searcher: &Searcher) -> crate::Result<()> {
+        let mut doc_freqs: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        for segment_reader in searcher.segment_readers() {
+            for &field in &self.fields {
+                let inverted_index = segment_reader.inverted_index(field)?;
+                let mut term_stream = inverted_index.terms().stream()?;
+                while term_stream.try_advance()? {
+                    *doc_freqs.entry(term_stream.key().to_vec()).or_insert(0) +=
+                        u64::from(term_stream.value().doc_freq);
+                }
+            }
+        }
+        let min_doc_freq = self.min_doc_freq;
+        let terms = Map::from_iter(
+            doc_freqs
+                .into_iter()
+                .filter(|&(_, doc_freq)| doc_freq >= min_doc_freq),
+        )
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        *self
+            .terms
+            .write()
+            .expect("Spelling dictionary lock poisoned. This should never happen.") =
+            Arc::new(terms);
+        Ok(())
+    }
+
+    fn terms(&self) -> Arc<Map<Vec<u8>>> {
+        self.terms
+            .read()
+            .expect("Spelling dictionary lock poisoned. This should never happen.")
+            .clone()
+    }
+
+    /// Returns the number of terms of the dictionary.
+    pub fn num_terms(&self) -> usize {
+        self.terms().len()
+    }
+
+    /// Returns the number of documents containing `term`, or 0 if the term
+    /// is not in the dictionary.
+    pub fn doc_freq(&self, term: &str) -> u64 {
+        self.terms().get(term).unwrap_or(0)
+    }
+
+    /// Returns the best `max_num_suggestions` terms accepted by the `automaton`.
+    pub(crate) fn suggestions(
+        &self,
+        automaton: &DfaWrapper,
+        max_num_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        let terms = self.terms();
+        let mut top_suggestions = TopSuggestions::with_limit(max_num_suggestions);
+        let mut stream = terms.search(automaton).into_stream();
+        while let Some((term_bytes, doc_freq)) = stream.next() {
+            top_suggestions.push(term_bytes, doc_freq, automaton);
+        }
+        top_suggestions.into_sorted_vec()
+    }
+}
+
+impl Warmer for SpellingDictionary {
+    fn warm(&self, searcher: &Searcher) -> crate::Result<()> {
+        self.refresh(searcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpellingDictionary;
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, SpellingSuggester, Term};
+
+    #[test]
+    fn test_spelling_dictionary() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let dictionary = SpellingDictionary::new(vec![title, body]).with_min_doc_freq(2);
+        let reader = index
+            .reader_builder()
+            .warmer(dictionary.clone())
+            .try_into()?;
+        assert_eq!(dictionary.num_terms(), 0);
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "The Diary of Muadib", body => "diary"))?;
+        index_writer.add_document(doc!(title => "A Dairy Cow"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "Dear diary", body => "a diaty"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+
+        // The document frequencies are summed over the fields and the segments,
+        // and the rare terms are pruned.
+        assert_eq!(dictionary.doc_freq("diary"), 3);
+        assert_eq!(dictionary.doc_freq("a"), 2);
+        assert_eq!(dictionary.doc_freq("dairy"), 0);
+        assert_eq!(dictionary.doc_freq("diaty"), 0);
+        assert_eq!(dictionary.num_terms(), 2);
+
+        let mut suggester = SpellingSuggester::create(&searcher, title)?;
+        suggester.set_dictionary(dictionary.clone());
+        let suggestions = suggester.suggest_term(&searcher, "diaty")?;
+        let suggestions: Vec<(&str, u8, u64)> = suggestions
+            .iter()
+            .map(|suggestion| {
+                (
+                    suggestion.text(),
+                    suggestion.distance(),
+                    suggestion.doc_freq(),
+                )
+            })
+            .collect();
+        assert_eq!(suggestions, &[("diary", 1, 3)]);
+
+        // The dictionary is refreshed on reload, and seen by the suggester.
+        index_writer.delete_term(Term::from_field_text(title, "cow"));
+        index_writer.add_document(doc!(title => "dairy farm"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(dictionary.doc_freq("dairy"), 2);
+        let suggestions = suggester.suggest_term(&reader.searcher(), "dairy")?;
+        assert_eq!(suggestions[0].text(), "dairy");
+        assert_eq!(suggestions[0].distance(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+mod completion;
+mod dictionary;
+mod spelling;
+
+pub use self::completion::CompletionSuggester;
+pub use self::dictionary::SpellingDictionary;
+pub use self::spelling::SpellingSuggester;
+
+use crate::query::{DfaWrapper, LEV_BUILDER, VALID_LEVENSHTEIN_DISTANCE_RANGE};
+use crate::schema::Field;
+use crate::termdict::TermMerger;
+use crate::Searcher;
+use crate::TantivyError::InvalidArgument;
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io;
+use std::str;
+
+/// A suggestion returned by the `SpellingSuggester` or the `CompletionSuggester`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suggestion {
+    text: String,
+    distance: u8,
+    doc_freq: u64,
+}
+
+impl Suggestion {
+    /// The text of the suggested term.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Levenshtein distance between the suggested term and the original term,
+    /// or the original prefix for completions.
+    pub fn distance(&self) -> u8 {
+        self.distance
+    }
+
+    /// Number of documents containing the suggested term.
+    pub fn doc_freq(&self) -> u64 {
+        self.doc_freq
+    }
+}
+
+fn levenshtein_automaton_builder(
+    max_distance: u8,
+) -> crate::Result<&'static LevenshteinAutomatonBuilder> {
+    LEV_BUILDER.get(&(max_distance, true)).ok_or_else(|| {
+        InvalidArgument(format!(
+            "Levenshtein distance of {} is not allowed. Choose a value in the {:?} range",
+            max_distance, VALID_LEVENSHTEIN_DISTANCE_RANGE
+        ))
+    })
+}
+
+/// Returns the terms of `field` accepted by the `automaton`, ranked by
+/// distance and then by decreasing document frequency.
+///
+/// The term streams of the segments are merged, so that only the best
+/// `max_num_suggestions` terms are kept in memory.
+fn collect_suggestions(
+    searcher: &Searcher,
+    field: Field,
+    automaton: &DfaWrapper,
+    max_num_suggestions: usize,
+) -> crate::Result<Vec<Suggestion>> {
+    let inverted_indexes = searcher
+        .segment_readers()
+        .iter()
+        .map(|segment_reader| segment_reader.inverted_index(field))
+        .collect::<crate::Result<Vec<_>>>()?;
+    let term_streams = inverted_indexes
+        .iter()
+        .map(|inverted_index| inverted_index.terms().search(automaton).into_stream())
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut term_merger = TermMerger::new(term_streams);
+    let mut top_suggestions = TopSuggestions::with_limit(max_num_suggestions);
+    while term_merger.advance()? {
+        let doc_freq: u64 = term_merger
+            .current_segment_ords_and_term_infos()
+            .map(|(_, term_info)| u64::from(term_info.doc_freq))
+            .sum();
+        top_suggestions.push(term_merger.key(), doc_freq, automaton);
+    }
+    Ok(top_suggestions.into_sorted_vec())
+}
+
+/// A suggestion ordered by rank: the best suggestion is the smallest.
+struct RankedSuggestion(Suggestion);
+
+impl RankedSuggestion {
+    fn rank(&self) -> (u8, Reverse<u64>, &[u8]) {
+        (
+            self.0.distance,
+            Reverse(self.0.doc_freq),
+            self.0.text.as_bytes(),
+        )
+    }
+}
+
+impl PartialEq for RankedSuggestion {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl Eq for RankedSuggestion {}
+
+impl PartialOrd for RankedSuggestion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedSuggestion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+/// Keeps the best suggestions among the terms accepted by an automaton,
+/// ranked by distance and then by decreasing document frequency.
+struct TopSuggestions {
+    limit: usize,
+    // The worst of the suggestions kept is at the top of the heap.
+    heap: BinaryHeap<RankedSuggestion>,
+}
+
+impl TopSuggestions {
+    fn with_limit(limit: usize) -> TopSuggestions {
+        TopSuggestions {
+            limit,
+            heap: BinaryHeap::with_capacity(limit),
+        }
+    }
+
+    fn push(&mut self, term_bytes: &[u8], doc_freq: u64, automaton: &DfaWrapper) {
+        if self.limit == 0 {
+            return;
+        }
+        let distance = match automaton.0.eval(term_bytes) {
+            Distance::Exact(distance) => distance,
+            Distance::AtLeast(_) => return,
+        };
+        if self.heap.len() == self.limit {
+            let worst = self.heap.peek().expect("The heap cannot be empty");
+            if (distance, Reverse(doc_freq), term_bytes) >= worst.rank() {
+                return;
+            }
+        }
+        let text = match str::from_utf8(term_bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => return,
+        };
+        if self.heap.len() == self.limit {
+            self.heap.pop();
+        }
+        self.heap.push(RankedSuggestion(Suggestion {
+            text,
+            distance,
+            doc_freq,
+        }));
+    }
+
+    /// Returns the suggestions kept, best suggestion first.
+    fn into_sorted_vec(self) -> Vec<Suggestion> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ranked_suggestion| ranked_suggestion.0)
+            .collect()
+    }
+}
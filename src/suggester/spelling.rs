@@ -0,0 +1,192 @@
+use super::SpellingDictionary;
+use super::{collect_suggestions, levenshtein_automaton_builder, Suggestion};
+use crate::query::DfaWrapper;
+use crate::schema::Field;
+use crate::tokenizer::TextAnalyzer;
+use crate::Searcher;
+
+const DEFAULT_MAX_DISTANCE: u8 = 2;
+const DEFAULT_MAX_NUM_SUGGESTIONS: usize = 5;
+
+/// `SpellingSuggester` proposes spelling corrections ("did you mean...")
+/// using the terms of a field's term dictionary.
+///
+/// Candidates are the terms within a given Levenshtein distance of the
+/// original term (a transposition costs 1). They are ranked by distance,
+/// and then by decreasing document frequency.
+///
+/// By default, the candidates are searched in the term dictionaries of all of the
+/// segments. A [`SpellingDictionary`](./struct.SpellingDictionary.html) can be
+/// set instead with `set_dictionary`.
+///
+/// ```rust
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, Index, SpellingSuggester};
+/// # fn main() -> tantivy::Result<()> {
+/// # let mut schema_builder = Schema::builder();
+/// # let title = schema_builder.add_text_field("title", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// # let mut index_writer = index.writer(3_000_000)?;
+/// # index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+/// # index_writer.commit()?;
+/// # let searcher = index.reader()?.searcher();
+/// let suggester = SpellingSuggester::create(&searcher, title)?;
+/// let suggestions = suggester.suggest_term(&searcher, "dairy")?;
+/// assert_eq!(suggestions[0].text(), "diary");
+/// assert_eq!(suggester.suggest_phrase(&searcher, "Yuong Girl")?, "young girl");
+/// # Ok(())
+/// # }
+/// ```
+pub struct SpellingSuggester {
+    field: Field,
+    tokenizer: TextAnalyzer,
+    max_distance: u8,
+    max_num_suggestions: usize,
+    dictionary: Option<SpellingDictionary>,
+}
+
+impl SpellingSuggester {
+    /// Creates a new spelling suggester for the given field.
+    pub fn create(searcher: &Searcher, field: Field) -> crate::Result<SpellingSuggester> {
+        let tokenizer = searcher.index().tokenizer_for_field(field)?;
+        Ok(SpellingSuggester {
+            field,
+            tokenizer,
+            max_distance: DEFAULT_MAX_DISTANCE,
+            max_num_suggestions: DEFAULT_MAX_NUM_SUGGESTIONS,
+            dictionary: None,
+        })
+    }
+
+    /// Sets the maximum Levenshtein distance of the suggestions.
+    ///
+    /// Defaults to 2.
+    pub fn set_max_distance(&mut self, max_distance: u8) {
+        self.max_distance = max_distance;
+    }
+
+    /// Sets the maximum number of suggestions returned for a term.
+    ///
+    /// Defaults to 5.
+    pub fn set_max_num_suggestions(&mut self, max_num_suggestions: usize) {
+        self.max_num_suggestions = max_num_suggestions;
+    }
+
+    /// Searches the suggestions in `dictionary`, instead of the term dictionaries
+    /// of the segments of the searcher.
+    ///
+    /// The searcher given to `suggest_term` and `suggest_phrase` is then ignored.
+    pub fn set_dictionary(&mut self, dictionary: SpellingDictionary) {
+        self.dictionary = Some(dictionary);
+    }
+
+    /// Returns the suggestions for a single term, best suggestion first.
+    ///
+    /// `text` is expected to be a term as emitted by the field's tokenizer.
+    /// If the term is in the dictionary, it is part of the suggestions,
+    /// with a distance of 0.
+    pub fn suggest_term(&self, searcher: &Searcher, text: &str) -> crate::Result<Vec<Suggestion>> {
+        let automaton_builder = levenshtein_automaton_builder(self.max_distance)?;
+        let automaton = DfaWrapper(automaton_builder.build_dfa(text));
+        if let Some(dictionary) = self.dictionary.as_ref() {
+            return Ok(dictionary.suggestions(&automaton, self.max_num_suggestions));
+        }
+        collect_suggestions(searcher, self.field, &automaton, self.max_num_suggestions)
+    }
+
+    /// Returns the suggested correction for a whole phrase.
+    ///
+    /// The phrase is tokenized with the field's tokenizer, and each token is
+    /// replaced by its best suggestion. Tokens without any suggestion are kept
+    /// as they are.
+    pub fn suggest_phrase(&self, searcher: &Searcher, text: &str) -> crate::Result<String> {
+        let mut words: Vec<String> = Vec::new();
+        let mut token_stream = self.tokenizer.token_stream(text);
+        while token_stream.advance() {
+            let token_text = token_stream.token().text.clone();
+            let word = self
+                .suggest_term(searcher, &token_text)?
+                .into_iter()
+                .next()
+                .map(|suggestion| suggestion.text)
+                .unwrap_or(token_text);
+            words.push(word);
+        }
+        Ok(words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpellingSuggester;
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, TantivyError};
+
+    fn create_index() -> crate::Result<(Index, crate::schema::Field)> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+        index_writer.add_document(doc!(title => "A Dairy Cow"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+        index_writer.add_document(doc!(title => "Dear diary"))?;
+        index_writer.commit()?;
+        Ok((index, title))
+    }
+
+    #[test]
+    fn test_suggest_term() -> crate::Result<()> {
+        let (index, title) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let suggester = SpellingSuggester::create(&searcher, title)?;
+        let suggestions = suggester.suggest_term(&searcher, "diaty")?;
+        let suggestions: Vec<(&str, u8, u64)> = suggestions
+            .iter()
+            .map(|suggestion| {
+                (
+                    suggestion.text(),
+                    suggestion.distance(),
+                    suggestion.doc_freq(),
+                )
+            })
+            .collect();
+        assert_eq!(suggestions, &[("diary", 1, 3), ("dairy", 2, 1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_suggest_term_options() -> crate::Result<()> {
+        let (index, title) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let mut suggester = SpellingSuggester::create(&searcher, title)?;
+        suggester.set_max_num_suggestions(1);
+        let suggestions = suggester.suggest_term(&searcher, "dairy")?;
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].text(), "dairy");
+        assert_eq!(suggestions[0].distance(), 0);
+        suggester.set_max_distance(0);
+        assert!(suggester.suggest_term(&searcher, "diaty")?.is_empty());
+        suggester.set_max_distance(10);
+        assert!(matches!(
+            suggester.suggest_term(&searcher, "diaty"),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_suggest_phrase() -> crate::Result<()> {
+        let (index, title) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let suggester = SpellingSuggester::create(&searcher, title)?;
+        assert_eq!(
+            suggester.suggest_phrase(&searcher, "Yuong grl, diaty")?,
+            "young girl diary"
+        );
+        assert_eq!(suggester.suggest_phrase(&searcher, "")?, "");
+        Ok(())
+    }
+}
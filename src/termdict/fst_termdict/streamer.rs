@@ -78,8 +78,8 @@ pub struct TermStreamer<'a, A = AlwaysMatch>
 where
     A: Automaton,
 {
-    pub(crate) fst_map: &'a TermDictionary,
-    pub(crate) stream: Stream<'a, A>,
+    fst_map: &'a TermDictionary,
+    stream: Stream<'a, A>,
     term_ord: TermOrdinal,
     current_key: Vec<u8>,
     current_value: TermInfo,
@@ -138,14 +138,4 @@ where
     pub fn value(&self) -> &TermInfo {
         &self.current_value
     }
-
-    /// Return the next `(key, value)` pair.
-    #[cfg_attr(feature = "cargo-clippy", allow(clippy::should_implement_trait))]
-    pub fn next(&mut self) -> Option<(&[u8], &TermInfo)> {
-        if self.advance() {
-            Some((self.key(), self.value()))
-        } else {
-            None
-        }
-    }
 }
@@ -15,6 +15,7 @@ struct TermInfoBlockMeta {
     offset: u64,
     ref_term_info: TermInfo,
     doc_freq_nbits: u8,
+    total_term_freq_nbits: u8,
     postings_offset_nbits: u8,
     positions_offset_nbits: u8,
 }
@@ -25,6 +26,7 @@ impl BinarySerializable for TermInfoBlockMeta {
         self.ref_term_info.serialize(write)?;
         write.write_all(&[
             self.doc_freq_nbits,
+            self.total_term_freq_nbits,
             self.postings_offset_nbits,
             self.positions_offset_nbits,
         ])?;
@@ -34,26 +36,30 @@ impl BinarySerializable for TermInfoBlockMeta {
     fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
         let offset = u64::deserialize(reader)?;
         let ref_term_info = TermInfo::deserialize(reader)?;
-        let mut buffer = [0u8; 3];
+        let mut buffer = [0u8; 4];
         reader.read_exact(&mut buffer)?;
         Ok(TermInfoBlockMeta {
             offset,
             ref_term_info,
             doc_freq_nbits: buffer[0],
-            postings_offset_nbits: buffer[1],
-            positions_offset_nbits: buffer[2],
+            total_term_freq_nbits: buffer[1],
+            postings_offset_nbits: buffer[2],
+            positions_offset_nbits: buffer[3],
         })
     }
 }
 
 impl FixedSize for TermInfoBlockMeta {
-    const SIZE_IN_BYTES: usize =
-        u64::SIZE_IN_BYTES + TermInfo::SIZE_IN_BYTES + 3 * u8::SIZE_IN_BYTES;
+    // The 4 bytes hold the number of bits of each of the bitpacked values.
+    const SIZE_IN_BYTES: usize = u64::SIZE_IN_BYTES + TermInfo::SIZE_IN_BYTES + 4;
 }
 
 impl TermInfoBlockMeta {
     fn num_bits(&self) -> u8 {
-        self.doc_freq_nbits + self.postings_offset_nbits + self.positions_offset_nbits
+        self.doc_freq_nbits
+            + self.total_term_freq_nbits
+            + self.postings_offset_nbits
+            + self.positions_offset_nbits
     }
 
     // Here inner_offset is the offset within the block, WITHOUT the first term_info.
@@ -71,6 +77,7 @@ impl TermInfoBlockMeta {
         let positions_end_addr = positions_start_addr + num_bits as usize;
 
         let doc_freq_addr = positions_start_addr + self.positions_offset_nbits as usize;
+        let total_term_freq_addr = doc_freq_addr + self.doc_freq_nbits as usize;
 
         let postings_start_offset = self.ref_term_info.postings_range.start
             + extract_bits(data, posting_start_addr, self.postings_offset_nbits) as usize;
@@ -83,9 +90,11 @@ impl TermInfoBlockMeta {
             + extract_bits(data, positions_end_addr, self.positions_offset_nbits) as usize;
 
         let doc_freq = extract_bits(data, doc_freq_addr, self.doc_freq_nbits) as u32;
+        let total_term_freq = extract_bits(data, total_term_freq_addr, self.total_term_freq_nbits);
 
         TermInfo {
             doc_freq,
+            total_term_freq,
             postings_range: postings_start_offset..postings_end_offset,
             positions_range: positions_start_offset..positions_end_offset,
         }
@@ -184,6 +193,11 @@ fn bitpack_serialize<W: Write>(
         term_info_block_meta.doc_freq_nbits,
         write,
     )?;
+    bit_packer.write(
+        term_info.total_term_freq,
+        term_info_block_meta.total_term_freq_nbits,
+        write,
+    )?;
     Ok(())
 }
 
@@ -216,12 +230,15 @@ impl TermInfoStoreWriter {
         }
 
         let mut max_doc_freq: u32 = 0u32;
+        let mut max_total_term_freq: u64 = 0u64;
 
         for term_info in &self.term_infos[1..] {
             max_doc_freq = cmp::max(max_doc_freq, term_info.doc_freq);
+            max_total_term_freq = cmp::max(max_total_term_freq, term_info.total_term_freq);
         }
 
         let max_doc_freq_nbits: u8 = compute_num_bits(u64::from(max_doc_freq));
+        let max_total_term_freq_nbits: u8 = compute_num_bits(max_total_term_freq);
         let max_postings_offset_nbits = compute_num_bits(postings_end_offset as u64);
         let max_positions_offset_nbits = compute_num_bits(positions_end_offset as u64);
 
@@ -229,6 +246,7 @@ impl TermInfoStoreWriter {
             offset: self.buffer_term_infos.len() as u64,
             ref_term_info,
             doc_freq_nbits: max_doc_freq_nbits,
+            total_term_freq_nbits: max_total_term_freq_nbits,
             postings_offset_nbits: max_postings_offset_nbits,
             positions_offset_nbits: max_positions_offset_nbits,
         };
@@ -325,10 +343,12 @@ mod tests {
             offset: 2009u64,
             ref_term_info: TermInfo {
                 doc_freq: 512,
+                total_term_freq: 2_048,
                 postings_range: 51..57,
                 positions_range: 110..134,
             },
             doc_freq_nbits: 10,
+            total_term_freq_nbits: 12,
             postings_offset_nbits: 5,
             positions_offset_nbits: 8,
         };
@@ -347,6 +367,7 @@ mod tests {
         for i in 0usize..1000usize {
             let term_info = TermInfo {
                 doc_freq: i as u32,
+                total_term_freq: (i * i) as u64,
                 postings_range: offset(i)..offset(i + 1),
                 positions_range: offset(i) * 3..offset(i + 1) * 3,
             };
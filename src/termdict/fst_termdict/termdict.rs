@@ -1,5 +1,5 @@
 use super::term_info_store::{TermInfoStore, TermInfoStoreWriter};
-use super::{TermStreamer, TermStreamerBuilder};
+use super::TermStreamerBuilder;
 use crate::directory::{FileSlice, OwnedBytes};
 use crate::error::DataCorruption;
 use crate::postings::TermInfo;
@@ -190,13 +190,13 @@ impl TermDictionary {
         TermStreamerBuilder::new(self, self.fst_index.range())
     }
 
-    /// A stream of all the sorted terms. [See also `.stream_field()`](#method.stream_field)
-    pub fn stream(&self) -> io::Result<TermStreamer<'_>> {
-        self.range().into_stream()
-    }
-
     /// Returns a search builder, to stream all of the terms
     /// within the Automaton
+    ///
+    /// The automaton is run over the bytes of the terms, and the branches of the
+    /// dictionary that cannot lead to a match (see `Automaton::can_match`) are skipped.
+    /// The resulting stream can be further restricted to a range of terms,
+    /// and yields the `TermInfo` of each matching term.
     pub fn search<'a, A: Automaton + 'a>(&'a self, automaton: A) -> TermStreamerBuilder<'a, A> {
         let stream_builder = self.fst_index.search(automaton);
         TermStreamerBuilder::<A>::new(self, stream_builder)
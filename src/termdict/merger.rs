@@ -1,11 +1,36 @@
 use crate::postings::TermInfo;
-use crate::termdict::TermDictionary;
 use crate::termdict::TermOrdinal;
 use crate::termdict::TermStreamer;
-use tantivy_fst::map::OpBuilder;
-use tantivy_fst::map::Union;
-use tantivy_fst::raw::IndexedValue;
-use tantivy_fst::Streamer;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io;
+use tantivy_fst::automaton::AlwaysMatch;
+use tantivy_fst::Automaton;
+
+struct HeapItem<'a, A: Automaton> {
+    streamer: TermStreamer<'a, A>,
+    segment_ord: usize,
+}
+
+impl<'a, A: Automaton> PartialEq for HeapItem<'a, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.segment_ord == other.segment_ord
+    }
+}
+
+impl<'a, A: Automaton> Eq for HeapItem<'a, A> {}
+
+impl<'a, A: Automaton> PartialOrd for HeapItem<'a, A> {
+    fn partial_cmp(&self, other: &HeapItem<'a, A>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, A: Automaton> Ord for HeapItem<'a, A> {
+    fn cmp(&self, other: &HeapItem<'a, A>) -> Ordering {
+        (&other.streamer.key(), &other.segment_ord).cmp(&(&self.streamer.key(), &self.segment_ord))
+    }
+}
 
 /// Given a list of sorted term streams,
 /// returns an iterator over sorted unique terms.
@@ -14,52 +39,68 @@ use tantivy_fst::Streamer;
 /// - the term
 /// - a slice with the ordinal of the segments containing
 /// the term.
-pub struct TermMerger<'a> {
-    dictionaries: Vec<&'a TermDictionary>,
-    union: Union<'a>,
-    current_key: Vec<u8>,
-    current_segment_and_term_ordinals: Vec<IndexedValue>,
+///
+/// The streams may come from term dictionaries of different types,
+/// and may be restricted to the terms accepted by an automaton.
+pub struct TermMerger<'a, A: Automaton = AlwaysMatch> {
+    heap: BinaryHeap<HeapItem<'a, A>>,
+    current_streamers: Vec<HeapItem<'a, A>>,
 }
 
-impl<'a> TermMerger<'a> {
+impl<'a, A: Automaton> TermMerger<'a, A> {
     /// Stream of merged term dictionary
     ///
-    pub fn new(streams: Vec<TermStreamer<'a>>) -> TermMerger<'a> {
-        let mut op_builder = OpBuilder::new();
-        let mut dictionaries = vec![];
-        for streamer in streams {
-            op_builder.push(streamer.stream);
-            dictionaries.push(streamer.fst_map);
-        }
+    pub fn new(streams: Vec<TermStreamer<'a, A>>) -> TermMerger<'a, A> {
         TermMerger {
-            dictionaries,
-            union: op_builder.union(),
-            current_key: vec![],
-            current_segment_and_term_ordinals: vec![],
+            heap: BinaryHeap::new(),
+            current_streamers: streams
+                .into_iter()
+                .enumerate()
+                .map(|(segment_ord, streamer)| HeapItem {
+                    streamer,
+                    segment_ord,
+                })
+                .collect(),
         }
     }
 
+    /// Iterator over the (segment ordinal, term ordinal) pairs of the current term,
+    /// sorted by segment ordinal.
+    ///
+    /// Term ordinals are local to the term dictionary of each segment.
+    ///
+    /// This method may be called
+    /// iff advance() has been called before
+    /// and "true" was returned.
     pub fn matching_segments<'b: 'a>(&'b self) -> impl 'b + Iterator<Item = (usize, TermOrdinal)> {
-        self.current_segment_and_term_ordinals
+        self.current_streamers
             .iter()
-            .map(|iv| (iv.index, iv.value))
+            .map(|heap_item| (heap_item.segment_ord, heap_item.streamer.term_ord()))
     }
 
     /// Advance the term iterator to the next term.
     /// Returns true if there is indeed another term
     /// False if there is none.
-    pub fn advance(&mut self) -> bool {
-        if let Some((k, values)) = self.union.next() {
-            self.current_key.clear();
-            self.current_key.extend_from_slice(k);
-            self.current_segment_and_term_ordinals.clear();
-            self.current_segment_and_term_ordinals
-                .extend_from_slice(values);
-            self.current_segment_and_term_ordinals
-                .sort_by_key(|iv| iv.index);
-            true
+    ///
+    /// Returns an error if a block of one of the term dictionaries is corrupted.
+    pub fn advance(&mut self) -> io::Result<bool> {
+        for mut heap_item in self.current_streamers.drain(..) {
+            if heap_item.streamer.try_advance()? {
+                self.heap.push(heap_item);
+            }
+        }
+        if let Some(head) = self.heap.pop() {
+            self.current_streamers.push(head);
+            while let Some(next_streamer) = self.heap.peek() {
+                if self.current_streamers[0].streamer.key() != next_streamer.streamer.key() {
+                    break;
+                }
+                let next_heap_it = self.heap.pop().unwrap();
+                self.current_streamers.push(next_heap_it);
+            }
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
@@ -69,7 +110,7 @@ impl<'a> TermMerger<'a> {
     /// iff advance() has been called before
     /// and "true" was returned.
     pub fn key(&self) -> &[u8] {
-        &self.current_key
+        self.current_streamers[0].streamer.key()
     }
 
     /// Iterator over (segment ordinal, TermInfo) pairs iterator sorted by the ordinal.
@@ -80,14 +121,9 @@ impl<'a> TermMerger<'a> {
     pub fn current_segment_ords_and_term_infos<'b: 'a>(
         &'b self,
     ) -> impl 'b + Iterator<Item = (usize, TermInfo)> {
-        self.current_segment_and_term_ordinals
+        self.current_streamers
             .iter()
-            .map(move |iv| {
-                (
-                    iv.index,
-                    self.dictionaries[iv.index].term_info_from_ord(iv.value),
-                )
-            })
+            .map(|heap_item| (heap_item.segment_ord, heap_item.streamer.value().clone()))
     }
 }
 
@@ -105,6 +141,7 @@ mod bench {
         let offset = |term_ord: u64| (term_ord * 100 + term_ord * term_ord) as usize;
         TermInfo {
             doc_freq: term_ord as u32,
+            total_term_freq: term_ord * 3,
             postings_range: offset(term_ord)..offset(term_ord + 1),
             positions_range: offset(term_ord)..offset(term_ord + 1),
         }
@@ -143,7 +180,7 @@ mod bench {
             let stream2 = dict2.stream()?;
             let mut merger = TermMerger::new(vec![stream1, stream2]);
             let mut count = 0;
-            while merger.advance() {
+            while merger.advance()? {
                 count += 1;
             }
             Ok(count)
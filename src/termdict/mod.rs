@@ -18,39 +18,33 @@ and then treated as a `u64`.
 as `u64`.
 
 A second datastructure makes it possible to access a [`TermInfo`](../postings/struct.TermInfo.html).
+
+Alternatively, the terms can be stored in an SSTable-style dictionary (see
+[`TermDictionaryType::SSTable`](./enum.TermDictionaryType.html)): sorted blocks of
+prefix-compressed terms, along with an index of the last term of each block. It is
+cheaper to build, and its streams are decoded sequentially. The segments written with
+the `sstable` codec use it.
+
+The terms matching an [`Automaton`](./trait.Automaton.html) can be streamed with
+[`TermDictionary::search`](./struct.TermDictionary.html#method.search). This is the building
+block of the fuzzy and regex queries, and of any user-defined term expansion.
 */
 
-use tantivy_fst::automaton::AlwaysMatch;
+pub use tantivy_fst::automaton::AlwaysMatch;
+pub use tantivy_fst::Automaton;
 
 mod fst_termdict;
-use fst_termdict as termdict;
-
 mod merger;
+mod sstable_termdict;
+mod term_dictionary;
 
 #[cfg(test)]
 mod tests;
 
+pub use self::merger::TermMerger;
+pub use self::term_dictionary::{
+    TermDictionary, TermDictionaryBuilder, TermDictionaryType, TermStreamer, TermStreamerBuilder,
+};
+
 /// Position of the term in the sorted list of terms.
 pub type TermOrdinal = u64;
-
-/// The term dictionary contains all of the terms in
-/// `tantivy index` in a sorted manner.
-pub type TermDictionary = self::termdict::TermDictionary;
-
-/// Builder for the new term dictionary.
-///
-/// Inserting must be done in the order of the `keys`.
-pub type TermDictionaryBuilder<W> = self::termdict::TermDictionaryBuilder<W>;
-
-/// Given a list of sorted term streams,
-/// returns an iterator over sorted unique terms.
-///
-/// The item yield is actually a pair with
-/// - the term
-/// - a slice with the ordinal of the segments containing
-/// the terms.
-pub type TermMerger<'a> = self::merger::TermMerger<'a>;
-
-/// `TermStreamer` acts as a cursor over a range of terms of a segment.
-/// Terms are guaranteed to be sorted.
-pub type TermStreamer<'a, A = AlwaysMatch> = self::termdict::TermStreamer<'a, A>;
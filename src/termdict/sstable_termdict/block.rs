@@ -0,0 +1,190 @@
+use crate::postings::TermInfo;
+use common::{BinarySerializable, VInt};
+use std::io;
+use std::ops::Range;
+
+/// Number of terms of a block. The last block of a dictionary may have fewer terms.
+pub const BLOCK_NUM_TERMS: usize = 64;
+
+fn zigzag_encode(val: i64) -> u64 {
+    ((val << 1) ^ (val >> 63)) as u64
+}
+
+fn zigzag_decode(val: u64) -> i64 {
+    ((val >> 1) as i64) ^ -((val & 1) as i64)
+}
+
+fn common_prefix_len(left: &[u8], right: &[u8]) -> usize {
+    left.iter()
+        .zip(right.iter())
+        .take_while(|(left_byte, right_byte)| left_byte == right_byte)
+        .count()
+}
+
+fn read_range(data: &mut &[u8], prev_end: usize) -> io::Result<Range<usize>> {
+    let start_delta = zigzag_decode(VInt::deserialize(data)?.val());
+    let start = (prev_end as i64 + start_delta) as usize;
+    let len = VInt::deserialize(data)?.val() as usize;
+    Ok(start..start + len)
+}
+
+/// Serializes the terms of a block.
+///
+/// Each term is encoded as the length of the prefix it shares with the previous
+/// term of the block, followed by the rest of its bytes. The postings and positions
+/// ranges are encoded as their offset to the end of the ranges of the previous term,
+/// which is `0` for contiguous postings, and as their length.
+#[derive(Default)]
+pub struct BlockWriter {
+    buffer: Vec<u8>,
+    num_terms: usize,
+    last_key: Vec<u8>,
+    postings_end: usize,
+    positions_end: usize,
+}
+
+impl BlockWriter {
+    pub fn num_terms(&self) -> usize {
+        self.num_terms
+    }
+
+    pub fn last_key(&self) -> &[u8] {
+        &self.last_key
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn insert_key(&mut self, key: &[u8]) {
+        let prefix_len = if self.num_terms == 0 {
+            0
+        } else {
+            common_prefix_len(&self.last_key, key)
+        };
+        VInt(prefix_len as u64).serialize_into_vec(&mut self.buffer);
+        VInt((key.len() - prefix_len) as u64).serialize_into_vec(&mut self.buffer);
+        self.buffer.extend_from_slice(&key[prefix_len..]);
+        self.last_key.truncate(prefix_len);
+        self.last_key.extend_from_slice(&key[prefix_len..]);
+    }
+
+    pub fn insert_value(&mut self, term_info: &TermInfo) {
+        VInt(u64::from(term_info.doc_freq)).serialize_into_vec(&mut self.buffer);
+        VInt(term_info.total_term_freq).serialize_into_vec(&mut self.buffer);
+        for (range, prev_end) in [
+            (&term_info.postings_range, &mut self.postings_end),
+            (&term_info.positions_range, &mut self.positions_end),
+        ] {
+            let start_delta = range.start as i64 - *prev_end as i64;
+            VInt(zigzag_encode(start_delta)).serialize_into_vec(&mut self.buffer);
+            VInt(range.len() as u64).serialize_into_vec(&mut self.buffer);
+            *prev_end = range.end;
+        }
+        self.num_terms += 1;
+    }
+
+    /// Clears the block, keeping its last key.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.num_terms = 0;
+        self.postings_end = 0;
+        self.positions_end = 0;
+    }
+}
+
+/// Cursor over the terms of a block.
+pub struct BlockReader<'a> {
+    data: &'a [u8],
+    key: Vec<u8>,
+    term_info: TermInfo,
+}
+
+impl<'a> BlockReader<'a> {
+    pub fn new(data: &'a [u8]) -> BlockReader<'a> {
+        BlockReader {
+            data,
+            key: Vec::new(),
+            term_info: TermInfo::default(),
+        }
+    }
+
+    /// Decodes the next term of the block. Returns false if the block is exhausted.
+    pub fn advance(&mut self) -> io::Result<bool> {
+        if self.data.is_empty() {
+            return Ok(false);
+        }
+        let prefix_len = VInt::deserialize(&mut self.data)?.val() as usize;
+        let suffix_len = VInt::deserialize(&mut self.data)?.val() as usize;
+        if prefix_len > self.key.len() || suffix_len > self.data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The term dictionary block is corrupted.",
+            ));
+        }
+        self.key.truncate(prefix_len);
+        self.key.extend_from_slice(&self.data[..suffix_len]);
+        self.data = &self.data[suffix_len..];
+        let doc_freq = VInt::deserialize(&mut self.data)?.val() as u32;
+        let total_term_freq = VInt::deserialize(&mut self.data)?.val();
+        let postings_range = read_range(&mut self.data, self.term_info.postings_range.end)?;
+        let positions_range = read_range(&mut self.data, self.term_info.positions_range.end)?;
+        self.term_info = TermInfo {
+            doc_freq,
+            total_term_freq,
+            postings_range,
+            positions_range,
+        };
+        Ok(true)
+    }
+
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    pub fn term_info(&self) -> &TermInfo {
+        &self.term_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{zigzag_decode, zigzag_encode, BlockReader, BlockWriter};
+    use crate::postings::TermInfo;
+
+    #[test]
+    fn test_zigzag() {
+        for &val in &[0i64, 1, -1, 2, -2, 1_000, -1_000, i64::MAX, i64::MIN] {
+            assert_eq!(zigzag_decode(zigzag_encode(val)), val);
+        }
+        assert_eq!(zigzag_encode(0), 0);
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+    }
+
+    #[test]
+    fn test_block_prefix_compression() -> std::io::Result<()> {
+        let terms = ["abc", "abcd", "abd", "b", "bcd"];
+        let term_info = |ord: usize| TermInfo {
+            doc_freq: ord as u32 + 1,
+            total_term_freq: ord as u64 * 3,
+            postings_range: ord * 10..ord * 10 + 10,
+            positions_range: 100 - ord * 20..100 - ord * 20 + 5,
+        };
+        let mut block_writer = BlockWriter::default();
+        for (ord, term) in terms.iter().enumerate() {
+            block_writer.insert_key(term.as_bytes());
+            block_writer.insert_value(&term_info(ord));
+        }
+        assert_eq!(block_writer.num_terms(), terms.len());
+        assert_eq!(block_writer.last_key(), b"bcd");
+        let mut block_reader = BlockReader::new(block_writer.bytes());
+        for (ord, term) in terms.iter().enumerate() {
+            assert!(block_reader.advance()?);
+            assert_eq!(block_reader.key(), term.as_bytes());
+            assert_eq!(block_reader.term_info(), &term_info(ord));
+        }
+        assert!(!block_reader.advance()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+/*!
+SSTable-style term dictionary.
+
+The terms are stored sorted, in blocks of a fixed number of terms. Within a block,
+each term is encoded as the length of the prefix it shares with the previous term,
+followed by the rest of its bytes, and by its `TermInfo`.
+
+The last term of each block is recorded in a block index, itself prefix-compressed,
+which is loaded in memory when the dictionary is opened. A term is looked up by
+binary searching the block index, and then by scanning its block.
+
+Compared to the `fst` based dictionary, the dictionary is cheaper to build, and
+streams are decoded sequentially, block by block.
+*/
+mod block;
+mod streamer;
+mod termdict;
+
+pub use self::streamer::{TermStreamer, TermStreamerBuilder};
+pub use self::termdict::{TermDictionary, TermDictionaryBuilder};
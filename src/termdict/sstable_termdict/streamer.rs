@@ -0,0 +1,300 @@
+use std::io;
+use std::ops::{Bound, Range};
+
+use super::block::BLOCK_NUM_TERMS;
+use super::TermDictionary;
+use crate::postings::TermInfo;
+use crate::termdict::TermOrdinal;
+use tantivy_fst::automaton::AlwaysMatch;
+use tantivy_fst::Automaton;
+
+/// `TermStreamerBuilder` is a helper object used to define
+/// a range of terms that should be streamed.
+pub struct TermStreamerBuilder<'a, A = AlwaysMatch>
+where
+    A: Automaton,
+{
+    term_dict: &'a TermDictionary,
+    automaton: A,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    backward: bool,
+}
+
+impl<'a, A> TermStreamerBuilder<'a, A>
+where
+    A: Automaton,
+{
+    pub(crate) fn new(term_dict: &'a TermDictionary, automaton: A) -> Self {
+        TermStreamerBuilder {
+            term_dict,
+            automaton,
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            backward: false,
+        }
+    }
+
+    /// Limit the range to terms greater or equal to the bound
+    pub fn ge<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.lower = Bound::Included(bound.as_ref().to_vec());
+        self
+    }
+
+    /// Limit the range to terms strictly greater than the bound
+    pub fn gt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.lower = Bound::Excluded(bound.as_ref().to_vec());
+        self
+    }
+
+    /// Limit the range to terms lesser or equal to the bound
+    pub fn le<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.upper = Bound::Included(bound.as_ref().to_vec());
+        self
+    }
+
+    /// Limit the range to terms lesser or equal to the bound
+    pub fn lt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.upper = Bound::Excluded(bound.as_ref().to_vec());
+        self
+    }
+
+    /// Iterate over the range backwards.
+    pub fn backward(mut self) -> Self {
+        self.backward = true;
+        self
+    }
+
+    /// Creates the stream corresponding to the range
+    /// of terms defined using the `TermStreamerBuilder`.
+    pub fn into_stream(self) -> io::Result<TermStreamer<'a, A>> {
+        let num_blocks = self.term_dict.num_blocks();
+        let next_block_ord = if self.backward {
+            let block_ord = match &self.upper {
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    self.term_dict.block_ord_for_key(key)
+                }
+                Bound::Unbounded => num_blocks,
+            };
+            block_ord.min(num_blocks.saturating_sub(1))
+        } else {
+            match &self.lower {
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    self.term_dict.block_ord_for_key(key)
+                }
+                Bound::Unbounded => 0,
+            }
+        };
+        let start_state = self.automaton.start();
+        Ok(TermStreamer {
+            term_dict: self.term_dict,
+            automaton: self.automaton,
+            lower: self.lower,
+            upper: self.upper,
+            backward: self.backward,
+            next_block_ord: Some(next_block_ord),
+            block_keys: Vec::new(),
+            block_terms: Vec::new(),
+            block_pos: 0,
+            block_first_term_ord: 0,
+            automaton_states: vec![start_state],
+            automaton_key: Vec::new(),
+            term_ord: 0u64,
+            current_key: Vec::with_capacity(100),
+            current_value: TermInfo::default(),
+        })
+    }
+}
+
+fn is_above_upper(key: &[u8], upper: &Bound<Vec<u8>>) -> bool {
+    match upper {
+        Bound::Included(upper) => key > upper.as_slice(),
+        Bound::Excluded(upper) => key >= upper.as_slice(),
+        Bound::Unbounded => false,
+    }
+}
+
+fn is_below_lower(key: &[u8], lower: &Bound<Vec<u8>>) -> bool {
+    match lower {
+        Bound::Included(lower) => key < lower.as_slice(),
+        Bound::Excluded(lower) => key <= lower.as_slice(),
+        Bound::Unbounded => false,
+    }
+}
+
+/// Runs the automaton over `key`.
+///
+/// `states` holds the states of the automaton after each byte of
+/// `automaton_key`, the key previously run, so that only the bytes
+/// following their common prefix need to be fed to the automaton.
+fn automaton_matches<A: Automaton>(
+    automaton: &A,
+    states: &mut Vec<A::State>,
+    automaton_key: &mut Vec<u8>,
+    key: &[u8],
+) -> bool {
+    let prefix_len = automaton_key
+        .iter()
+        .zip(key.iter())
+        .take_while(|(left, right)| left == right)
+        .count()
+        .min(states.len() - 1);
+    states.truncate(prefix_len + 1);
+    automaton_key.clear();
+    automaton_key.extend_from_slice(key);
+    for &byte in &key[prefix_len..] {
+        let state = states.last().expect("The start state is never removed");
+        if automaton.will_always_match(state) {
+            return true;
+        }
+        if !automaton.can_match(state) {
+            return false;
+        }
+        let next_state = automaton.accept(state, byte);
+        states.push(next_state);
+    }
+    automaton.is_match(states.last().expect("The start state is never removed"))
+}
+
+/// `TermStreamer` acts as a cursor over a range of terms of a segment.
+/// Terms are guaranteed to be sorted.
+///
+/// The terms are decoded one block at a time.
+pub struct TermStreamer<'a, A = AlwaysMatch>
+where
+    A: Automaton,
+{
+    term_dict: &'a TermDictionary,
+    automaton: A,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    backward: bool,
+    next_block_ord: Option<usize>,
+    block_keys: Vec<u8>,
+    block_terms: Vec<(Range<usize>, TermInfo)>,
+    block_pos: usize,
+    block_first_term_ord: TermOrdinal,
+    automaton_states: Vec<A::State>,
+    automaton_key: Vec<u8>,
+    term_ord: TermOrdinal,
+    current_key: Vec<u8>,
+    current_value: TermInfo,
+}
+
+impl<'a, A> TermStreamer<'a, A>
+where
+    A: Automaton,
+{
+    /// Decodes the next block to visit. Returns false if there is none.
+    fn load_next_block(&mut self) -> io::Result<bool> {
+        let block_ord = match self.next_block_ord {
+            Some(block_ord) if block_ord < self.term_dict.num_blocks() => block_ord,
+            _ => return Ok(false),
+        };
+        self.block_keys.clear();
+        self.block_terms.clear();
+        let mut block_reader = self.term_dict.block_reader(block_ord);
+        while block_reader.advance()? {
+            let key_start = self.block_keys.len();
+            self.block_keys.extend_from_slice(block_reader.key());
+            self.block_terms.push((
+                key_start..self.block_keys.len(),
+                block_reader.term_info().clone(),
+            ));
+        }
+        self.block_pos = 0;
+        self.block_first_term_ord = (block_ord * BLOCK_NUM_TERMS) as TermOrdinal;
+        self.next_block_ord = if self.backward {
+            block_ord.checked_sub(1)
+        } else {
+            Some(block_ord + 1)
+        };
+        Ok(true)
+    }
+
+    /// Advance position the stream on the next item.
+    /// Before the first call to `.advance()`, the stream
+    /// is an unitialized state.
+    ///
+    /// Returns an error if a block of the term dictionary is corrupted.
+    pub fn advance(&mut self) -> io::Result<bool> {
+        loop {
+            if self.block_pos == self.block_terms.len() {
+                if !self.load_next_block()? {
+                    return Ok(false);
+                }
+                continue;
+            }
+            let term_idx = if self.backward {
+                self.block_terms.len() - 1 - self.block_pos
+            } else {
+                self.block_pos
+            };
+            self.block_pos += 1;
+            let (key_range, term_info) = &self.block_terms[term_idx];
+            let key = &self.block_keys[key_range.clone()];
+            let (is_before_range, is_after_range) = if self.backward {
+                (
+                    is_above_upper(key, &self.upper),
+                    is_below_lower(key, &self.lower),
+                )
+            } else {
+                (
+                    is_below_lower(key, &self.lower),
+                    is_above_upper(key, &self.upper),
+                )
+            };
+            if is_after_range {
+                self.next_block_ord = None;
+                self.block_terms.clear();
+                self.block_pos = 0;
+                return Ok(false);
+            }
+            if is_before_range
+                || !automaton_matches(
+                    &self.automaton,
+                    &mut self.automaton_states,
+                    &mut self.automaton_key,
+                    key,
+                )
+            {
+                continue;
+            }
+            self.current_key.clear();
+            self.current_key.extend_from_slice(key);
+            self.current_value = term_info.clone();
+            self.term_ord = self.block_first_term_ord + term_idx as TermOrdinal;
+            return Ok(true);
+        }
+    }
+
+    /// Returns the `TermOrdinal` of the given term.
+    ///
+    /// May panic if the called as `.advance()` as never
+    /// been called before.
+    pub fn term_ord(&self) -> TermOrdinal {
+        self.term_ord
+    }
+
+    /// Accesses the current key.
+    ///
+    /// `.key()` should return the key that was returned
+    /// by the `.next()` method.
+    ///
+    /// If the end of the stream as been reached, and `.next()`
+    /// has been called and returned `None`, `.key()` remains
+    /// the value of the last key encountered.
+    ///
+    /// Before any call to `.next()`, `.key()` returns an empty array.
+    pub fn key(&self) -> &[u8] {
+        &self.current_key
+    }
+
+    /// Accesses the current value.
+    ///
+    /// Calling `.value()` after the end of the stream will return the
+    /// last `.value()` encountered.
+    pub fn value(&self) -> &TermInfo {
+        &self.current_value
+    }
+}
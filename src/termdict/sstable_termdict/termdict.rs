@@ -0,0 +1,301 @@
+use super::block::{BlockReader, BlockWriter, BLOCK_NUM_TERMS};
+use super::TermStreamerBuilder;
+use crate::directory::{FileSlice, OwnedBytes};
+use crate::error::DataCorruption;
+use crate::postings::TermInfo;
+use crate::termdict::TermOrdinal;
+use common::{BinarySerializable, CountingWriter, HasLen, VInt};
+use std::io::{self, Write};
+use std::ops::Range;
+use tantivy_fst::Automaton;
+
+/// Builder for the new term dictionary.
+///
+/// Inserting must be done in the order of the `keys`.
+pub struct TermDictionaryBuilder<W: Write> {
+    write: CountingWriter<W>,
+    block_writer: BlockWriter,
+    index: Vec<u8>,
+    previous_block_last_key: Vec<u8>,
+    num_terms: u64,
+}
+
+impl<W: Write> TermDictionaryBuilder<W> {
+    /// Creates a new `TermDictionaryBuilder`
+    pub fn create(w: W) -> io::Result<Self> {
+        Ok(TermDictionaryBuilder {
+            write: CountingWriter::wrap(w),
+            block_writer: BlockWriter::default(),
+            index: Vec::new(),
+            previous_block_last_key: Vec::new(),
+            num_terms: 0,
+        })
+    }
+
+    /// Inserts a `(key, value)` pair in the term dictionary.
+    ///
+    /// *Keys have to be inserted in order.*
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key_ref: K, value: &TermInfo) -> io::Result<()> {
+        self.insert_key(key_ref.as_ref())?;
+        self.insert_value(value)?;
+        Ok(())
+    }
+
+    /// # Warning
+    ///
+    /// Calls to `insert_key` and `insert_value` must be alternated.
+    pub(crate) fn insert_key(&mut self, key: &[u8]) -> io::Result<()> {
+        if self.block_writer.num_terms() == BLOCK_NUM_TERMS {
+            self.flush_block()?;
+        }
+        self.block_writer.insert_key(key);
+        self.num_terms += 1;
+        Ok(())
+    }
+
+    /// # Warning
+    ///
+    /// See `.insert_key(...)`.
+    pub(crate) fn insert_value(&mut self, term_info: &TermInfo) -> io::Result<()> {
+        self.block_writer.insert_value(term_info);
+        Ok(())
+    }
+
+    /// Writes the current block, and adds its last key and its length to the block index.
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.block_writer.num_terms() == 0 {
+            return Ok(());
+        }
+        self.write.write_all(self.block_writer.bytes())?;
+        let last_key = self.block_writer.last_key();
+        let prefix_len = last_key
+            .iter()
+            .zip(self.previous_block_last_key.iter())
+            .take_while(|(left, right)| left == right)
+            .count();
+        VInt(self.block_writer.bytes().len() as u64).serialize_into_vec(&mut self.index);
+        VInt(prefix_len as u64).serialize_into_vec(&mut self.index);
+        VInt((last_key.len() - prefix_len) as u64).serialize_into_vec(&mut self.index);
+        self.index.extend_from_slice(&last_key[prefix_len..]);
+        self.previous_block_last_key.clear();
+        self.previous_block_last_key.extend_from_slice(last_key);
+        self.block_writer.clear();
+        Ok(())
+    }
+
+    /// Finalize writing the builder, and returns the underlying
+    /// `Write` object.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_block()?;
+        self.write.write_all(&self.index)?;
+        self.num_terms.serialize(&mut self.write)?;
+        (self.index.len() as u64).serialize(&mut self.write)?;
+        Ok(self.write.finish())
+    }
+}
+
+/// Last key and byte range of a block of terms.
+struct BlockMeta {
+    last_key: Vec<u8>,
+    byte_range: Range<usize>,
+}
+
+fn read_block_index(mut index_bytes: &[u8]) -> io::Result<Vec<BlockMeta>> {
+    let mut block_metas: Vec<BlockMeta> = Vec::new();
+    let mut block_start = 0;
+    while !index_bytes.is_empty() {
+        let block_len = VInt::deserialize(&mut index_bytes)?.val() as usize;
+        let prefix_len = VInt::deserialize(&mut index_bytes)?.val() as usize;
+        let suffix_len = VInt::deserialize(&mut index_bytes)?.val() as usize;
+        let previous_last_key: &[u8] = block_metas
+            .last()
+            .map(|block_meta| &block_meta.last_key[..])
+            .unwrap_or(&[]);
+        if prefix_len > previous_last_key.len() || suffix_len > index_bytes.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The term dictionary block index is corrupted.",
+            ));
+        }
+        let mut last_key = previous_last_key[..prefix_len].to_vec();
+        last_key.extend_from_slice(&index_bytes[..suffix_len]);
+        index_bytes = &index_bytes[suffix_len..];
+        block_metas.push(BlockMeta {
+            last_key,
+            byte_range: block_start..block_start + block_len,
+        });
+        block_start += block_len;
+    }
+    Ok(block_metas)
+}
+
+/// The term dictionary contains all of the terms in
+/// `tantivy index` in a sorted manner.
+///
+/// The terms are stored in blocks of `64` terms, each term sharing its prefix
+/// with the previous term of its block. An index of the last term of each block
+/// is kept in memory to find the block of a term.
+pub struct TermDictionary {
+    data: OwnedBytes,
+    block_metas: Vec<BlockMeta>,
+    num_terms: u64,
+}
+
+impl TermDictionary {
+    /// Opens a `TermDictionary`.
+    pub fn open(file: FileSlice) -> crate::Result<Self> {
+        let (main_slice, footer_slice) = file.split_from_end(16);
+        let mut footer_bytes = footer_slice.read_bytes()?;
+        let num_terms = u64::deserialize(&mut footer_bytes)?;
+        let index_len = u64::deserialize(&mut footer_bytes)? as usize;
+        if index_len > main_slice.len() {
+            return Err(DataCorruption::comment_only(
+                "The term dictionary block index is larger than the term dictionary.",
+            )
+            .into());
+        }
+        let (data_slice, index_slice) = main_slice.split_from_end(index_len);
+        let block_metas = read_block_index(index_slice.read_bytes()?.as_slice())?;
+        let data = data_slice.read_bytes()?;
+        let data_len = block_metas
+            .last()
+            .map(|block_meta| block_meta.byte_range.end)
+            .unwrap_or(0);
+        let expected_num_blocks = (num_terms as usize).div_ceil(BLOCK_NUM_TERMS);
+        if data_len != data.len() || expected_num_blocks != block_metas.len() {
+            return Err(DataCorruption::comment_only(
+                "The term dictionary block index does not match its blocks.",
+            )
+            .into());
+        }
+        Ok(TermDictionary {
+            data,
+            block_metas,
+            num_terms,
+        })
+    }
+
+    /// Returns the number of terms in the dictionary.
+    /// Term ordinals range from 0 to `num_terms() - 1`.
+    pub fn num_terms(&self) -> usize {
+        self.num_terms as usize
+    }
+
+    pub(crate) fn num_blocks(&self) -> usize {
+        self.block_metas.len()
+    }
+
+    pub(crate) fn block_reader(&self, block_ord: usize) -> BlockReader<'_> {
+        let byte_range = self.block_metas[block_ord].byte_range.clone();
+        BlockReader::new(&self.data.as_slice()[byte_range])
+    }
+
+    /// Returns the ordinal of the first block that may contain `key`,
+    /// i.e. whose last key is greater or equal to `key`.
+    ///
+    /// Returns `num_blocks()` if `key` is greater than all of the terms.
+    pub(crate) fn block_ord_for_key(&self, key: &[u8]) -> usize {
+        self.block_metas
+            .partition_point(|block_meta| block_meta.last_key.as_slice() < key)
+    }
+
+    /// Returns the ordinal associated to a given term.
+    pub fn term_ord<K: AsRef<[u8]>>(&self, key: K) -> io::Result<Option<TermOrdinal>> {
+        Ok(self
+            .lookup(key.as_ref())?
+            .map(|(term_ord, _term_info)| term_ord))
+    }
+
+    fn lookup(&self, key: &[u8]) -> io::Result<Option<(TermOrdinal, TermInfo)>> {
+        let block_ord = self.block_ord_for_key(key);
+        if block_ord == self.num_blocks() {
+            return Ok(None);
+        }
+        let mut block_reader = self.block_reader(block_ord);
+        let mut term_ord = (block_ord * BLOCK_NUM_TERMS) as TermOrdinal;
+        while block_reader.advance()? {
+            match block_reader.key().cmp(key) {
+                std::cmp::Ordering::Less => {}
+                std::cmp::Ordering::Equal => {
+                    return Ok(Some((term_ord, block_reader.term_info().clone())));
+                }
+                std::cmp::Ordering::Greater => {
+                    return Ok(None);
+                }
+            }
+            term_ord += 1;
+        }
+        Ok(None)
+    }
+
+    /// Positions a block reader on the term of ordinal `term_ord`.
+    fn block_reader_at_ord(&self, term_ord: TermOrdinal) -> io::Result<Option<BlockReader<'_>>> {
+        if term_ord >= self.num_terms {
+            return Ok(None);
+        }
+        let block_ord = term_ord as usize / BLOCK_NUM_TERMS;
+        let mut block_reader = self.block_reader(block_ord);
+        for _ in 0..=(term_ord as usize % BLOCK_NUM_TERMS) {
+            if !block_reader.advance()? {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "The term dictionary block has less terms than expected.",
+                ));
+            }
+        }
+        Ok(Some(block_reader))
+    }
+
+    /// Returns the term associated to a given term ordinal.
+    ///
+    /// Term ordinals are defined as the position of the term in
+    /// the sorted list of terms.
+    ///
+    /// Returns true iff the term has been found.
+    ///
+    /// Regardless of whether the term is found or not,
+    /// the buffer may be modified.
+    pub fn ord_to_term(&self, term_ord: TermOrdinal, bytes: &mut Vec<u8>) -> io::Result<bool> {
+        bytes.clear();
+        if let Some(block_reader) = self.block_reader_at_ord(term_ord)? {
+            bytes.extend_from_slice(block_reader.key());
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns the `TermInfo` associated to a given term ordinal.
+    ///
+    /// Returns an error if the block of the term is corrupted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the term ordinal is out of bounds.
+    pub fn term_info_from_ord(&self, term_ord: TermOrdinal) -> io::Result<TermInfo> {
+        Ok(self
+            .block_reader_at_ord(term_ord)?
+            .expect("The term ordinal is out of bounds.")
+            .term_info()
+            .clone())
+    }
+
+    /// Lookups the value corresponding to the key.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> io::Result<Option<TermInfo>> {
+        Ok(self
+            .lookup(key.as_ref())?
+            .map(|(_term_ord, term_info)| term_info))
+    }
+
+    /// Returns a range builder, to stream all of the terms
+    /// within an interval.
+    pub fn range(&self) -> TermStreamerBuilder<'_> {
+        TermStreamerBuilder::new(self, tantivy_fst::automaton::AlwaysMatch)
+    }
+
+    /// Returns a search builder, to stream all of the terms
+    /// within the Automaton
+    pub fn search<'a, A: Automaton + 'a>(&'a self, automaton: A) -> TermStreamerBuilder<'a, A> {
+        TermStreamerBuilder::new(self, automaton)
+    }
+}
@@ -0,0 +1,379 @@
+use super::{fst_termdict, sstable_termdict, TermOrdinal};
+use crate::directory::FileSlice;
+use crate::postings::TermInfo;
+use std::io::{self, Write};
+use tantivy_fst::automaton::AlwaysMatch;
+use tantivy_fst::Automaton;
+
+/// Format of a term dictionary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TermDictionaryType {
+    /// Terms are stored in an `fst`, associating them to their ordinal.
+    #[default]
+    Fst,
+    /// Terms are stored in prefix-compressed blocks, with an index of
+    /// the last term of each block.
+    ///
+    /// It is cheaper to build than the `Fst` dictionary, and its streams
+    /// are decoded sequentially.
+    SSTable,
+}
+
+enum InnerTermDictionaryBuilder<W: Write> {
+    Fst(fst_termdict::TermDictionaryBuilder<W>),
+    SSTable(sstable_termdict::TermDictionaryBuilder<W>),
+}
+
+/// Builder for the new term dictionary.
+///
+/// Inserting must be done in the order of the `keys`.
+pub struct TermDictionaryBuilder<W: Write>(InnerTermDictionaryBuilder<W>);
+
+impl<W> TermDictionaryBuilder<W>
+where
+    W: Write,
+{
+    /// Creates a new `TermDictionaryBuilder`, writing an `Fst` term dictionary.
+    pub fn create(w: W) -> io::Result<Self> {
+        TermDictionaryBuilder::create_with_type(w, TermDictionaryType::Fst)
+    }
+
+    /// Creates a new `TermDictionaryBuilder`, writing a term dictionary of the given type.
+    pub fn create_with_type(w: W, term_dictionary_type: TermDictionaryType) -> io::Result<Self> {
+        let inner = match term_dictionary_type {
+            TermDictionaryType::Fst => {
+                InnerTermDictionaryBuilder::Fst(fst_termdict::TermDictionaryBuilder::create(w)?)
+            }
+            TermDictionaryType::SSTable => InnerTermDictionaryBuilder::SSTable(
+                sstable_termdict::TermDictionaryBuilder::create(w)?,
+            ),
+        };
+        Ok(TermDictionaryBuilder(inner))
+    }
+
+    /// Inserts a `(key, value)` pair in the term dictionary.
+    ///
+    /// *Keys have to be inserted in order.*
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key_ref: K, value: &TermInfo) -> io::Result<()> {
+        match &mut self.0 {
+            InnerTermDictionaryBuilder::Fst(builder) => builder.insert(key_ref, value),
+            InnerTermDictionaryBuilder::SSTable(builder) => builder.insert(key_ref, value),
+        }
+    }
+
+    /// # Warning
+    /// Horribly dangerous internal API
+    ///
+    /// If used, it must be used by systematically alternating calls
+    /// to insert_key and insert_value.
+    ///
+    /// Prefer using `.insert(key, value)`
+    pub(crate) fn insert_key(&mut self, key: &[u8]) -> io::Result<()> {
+        match &mut self.0 {
+            InnerTermDictionaryBuilder::Fst(builder) => builder.insert_key(key),
+            InnerTermDictionaryBuilder::SSTable(builder) => builder.insert_key(key),
+        }
+    }
+
+    /// # Warning
+    ///
+    /// Horribly dangerous internal API. See `.insert_key(...)`.
+    pub(crate) fn insert_value(&mut self, term_info: &TermInfo) -> io::Result<()> {
+        match &mut self.0 {
+            InnerTermDictionaryBuilder::Fst(builder) => builder.insert_value(term_info),
+            InnerTermDictionaryBuilder::SSTable(builder) => builder.insert_value(term_info),
+        }
+    }
+
+    /// Finalize writing the builder, and returns the underlying
+    /// `Write` object.
+    pub fn finish(self) -> io::Result<W> {
+        match self.0 {
+            InnerTermDictionaryBuilder::Fst(builder) => builder.finish(),
+            InnerTermDictionaryBuilder::SSTable(builder) => builder.finish(),
+        }
+    }
+}
+
+enum InnerTermDictionary {
+    Fst(fst_termdict::TermDictionary),
+    SSTable(sstable_termdict::TermDictionary),
+}
+
+/// The term dictionary contains all of the terms in
+/// `tantivy index` in a sorted manner.
+///
+/// Depending on its [`TermDictionaryType`](./enum.TermDictionaryType.html),
+/// the terms are stored in an `fst` or in prefix-compressed blocks.
+pub struct TermDictionary(InnerTermDictionary);
+
+impl TermDictionary {
+    /// Opens an `Fst` `TermDictionary`.
+    pub fn open(file: FileSlice) -> crate::Result<Self> {
+        TermDictionary::open_with_type(file, TermDictionaryType::Fst)
+    }
+
+    /// Opens a `TermDictionary` of the given type.
+    pub fn open_with_type(
+        file: FileSlice,
+        term_dictionary_type: TermDictionaryType,
+    ) -> crate::Result<Self> {
+        let inner = match term_dictionary_type {
+            TermDictionaryType::Fst => {
+                InnerTermDictionary::Fst(fst_termdict::TermDictionary::open(file)?)
+            }
+            TermDictionaryType::SSTable => {
+                InnerTermDictionary::SSTable(sstable_termdict::TermDictionary::open(file)?)
+            }
+        };
+        Ok(TermDictionary(inner))
+    }
+
+    /// Creates an empty term dictionary which contains no terms.
+    pub fn empty() -> Self {
+        TermDictionary(InnerTermDictionary::Fst(
+            fst_termdict::TermDictionary::empty(),
+        ))
+    }
+
+    /// Returns the type of the term dictionary.
+    pub fn term_dictionary_type(&self) -> TermDictionaryType {
+        match &self.0 {
+            InnerTermDictionary::Fst(_) => TermDictionaryType::Fst,
+            InnerTermDictionary::SSTable(_) => TermDictionaryType::SSTable,
+        }
+    }
+
+    /// Returns the number of terms in the dictionary.
+    /// Term ordinals range from 0 to `num_terms() - 1`.
+    pub fn num_terms(&self) -> usize {
+        match &self.0 {
+            InnerTermDictionary::Fst(termdict) => termdict.num_terms(),
+            InnerTermDictionary::SSTable(termdict) => termdict.num_terms(),
+        }
+    }
+
+    /// Returns the ordinal associated to a given term.
+    pub fn term_ord<K: AsRef<[u8]>>(&self, key: K) -> io::Result<Option<TermOrdinal>> {
+        match &self.0 {
+            InnerTermDictionary::Fst(termdict) => termdict.term_ord(key),
+            InnerTermDictionary::SSTable(termdict) => termdict.term_ord(key),
+        }
+    }
+
+    /// Returns the term associated to a given term ordinal.
+    ///
+    /// Term ordinals are defined as the position of the term in
+    /// the sorted list of terms.
+    ///
+    /// Returns true iff the term has been found.
+    ///
+    /// Regardless of whether the term is found or not,
+    /// the buffer may be modified.
+    pub fn ord_to_term(&self, ord: TermOrdinal, bytes: &mut Vec<u8>) -> io::Result<bool> {
+        match &self.0 {
+            InnerTermDictionary::Fst(termdict) => termdict.ord_to_term(ord, bytes),
+            InnerTermDictionary::SSTable(termdict) => termdict.ord_to_term(ord, bytes),
+        }
+    }
+
+    /// Returns the `TermInfo` associated to a given term ordinal.
+    ///
+    /// Returns an error if the block of the term in an SSTable dictionary is corrupted.
+    pub fn term_info_from_ord(&self, term_ord: TermOrdinal) -> io::Result<TermInfo> {
+        match &self.0 {
+            InnerTermDictionary::Fst(termdict) => Ok(termdict.term_info_from_ord(term_ord)),
+            InnerTermDictionary::SSTable(termdict) => termdict.term_info_from_ord(term_ord),
+        }
+    }
+
+    /// Lookups the value corresponding to the key.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> io::Result<Option<TermInfo>> {
+        match &self.0 {
+            InnerTermDictionary::Fst(termdict) => termdict.get(key),
+            InnerTermDictionary::SSTable(termdict) => termdict.get(key),
+        }
+    }
+
+    /// Returns a range builder, to stream all of the terms
+    /// within an interval.
+    pub fn range(&self) -> TermStreamerBuilder<'_> {
+        TermStreamerBuilder(match &self.0 {
+            InnerTermDictionary::Fst(termdict) => InnerTermStreamerBuilder::Fst(termdict.range()),
+            InnerTermDictionary::SSTable(termdict) => {
+                InnerTermStreamerBuilder::SSTable(termdict.range())
+            }
+        })
+    }
+
+    /// A stream of all the sorted terms. [See also `.stream_field()`](#method.stream_field)
+    pub fn stream(&self) -> io::Result<TermStreamer<'_>> {
+        self.range().into_stream()
+    }
+
+    /// Returns a search builder, to stream all of the terms
+    /// within the Automaton
+    ///
+    /// The automaton is run over the bytes of the terms, and the branches of the
+    /// dictionary that cannot lead to a match (see `Automaton::can_match`) are skipped.
+    /// The resulting stream can be further restricted to a range of terms,
+    /// and yields the `TermInfo` of each matching term.
+    pub fn search<'a, A: Automaton + 'a>(&'a self, automaton: A) -> TermStreamerBuilder<'a, A> {
+        TermStreamerBuilder(match &self.0 {
+            InnerTermDictionary::Fst(termdict) => {
+                InnerTermStreamerBuilder::Fst(termdict.search(automaton))
+            }
+            InnerTermDictionary::SSTable(termdict) => {
+                InnerTermStreamerBuilder::SSTable(termdict.search(automaton))
+            }
+        })
+    }
+}
+
+enum InnerTermStreamerBuilder<'a, A: Automaton> {
+    Fst(fst_termdict::TermStreamerBuilder<'a, A>),
+    SSTable(sstable_termdict::TermStreamerBuilder<'a, A>),
+}
+
+/// `TermStreamerBuilder` is a helper object used to define
+/// a range of terms that should be streamed.
+pub struct TermStreamerBuilder<'a, A = AlwaysMatch>(InnerTermStreamerBuilder<'a, A>)
+where
+    A: Automaton;
+
+macro_rules! map_stream_builder {
+    ($stream_builder:expr, $method:ident $(, $arg:expr)*) => {
+        TermStreamerBuilder(match $stream_builder.0 {
+            InnerTermStreamerBuilder::Fst(stream_builder) => {
+                InnerTermStreamerBuilder::Fst(stream_builder.$method($($arg),*))
+            }
+            InnerTermStreamerBuilder::SSTable(stream_builder) => {
+                InnerTermStreamerBuilder::SSTable(stream_builder.$method($($arg),*))
+            }
+        })
+    };
+}
+
+impl<'a, A> TermStreamerBuilder<'a, A>
+where
+    A: Automaton,
+{
+    /// Limit the range to terms greater or equal to the bound
+    pub fn ge<T: AsRef<[u8]>>(self, bound: T) -> Self {
+        map_stream_builder!(self, ge, bound)
+    }
+
+    /// Limit the range to terms strictly greater than the bound
+    pub fn gt<T: AsRef<[u8]>>(self, bound: T) -> Self {
+        map_stream_builder!(self, gt, bound)
+    }
+
+    /// Limit the range to terms lesser or equal to the bound
+    pub fn le<T: AsRef<[u8]>>(self, bound: T) -> Self {
+        map_stream_builder!(self, le, bound)
+    }
+
+    /// Limit the range to terms lesser or equal to the bound
+    pub fn lt<T: AsRef<[u8]>>(self, bound: T) -> Self {
+        map_stream_builder!(self, lt, bound)
+    }
+
+    /// Iterate over the range backwards.
+    pub fn backward(self) -> Self {
+        map_stream_builder!(self, backward)
+    }
+
+    /// Creates the stream corresponding to the range
+    /// of terms defined using the `TermStreamerBuilder`.
+    pub fn into_stream(self) -> io::Result<TermStreamer<'a, A>> {
+        let inner = match self.0 {
+            InnerTermStreamerBuilder::Fst(stream_builder) => {
+                InnerTermStreamer::Fst(stream_builder.into_stream()?)
+            }
+            InnerTermStreamerBuilder::SSTable(stream_builder) => {
+                InnerTermStreamer::SSTable(stream_builder.into_stream()?)
+            }
+        };
+        Ok(TermStreamer(inner))
+    }
+}
+
+enum InnerTermStreamer<'a, A: Automaton> {
+    Fst(fst_termdict::TermStreamer<'a, A>),
+    SSTable(sstable_termdict::TermStreamer<'a, A>),
+}
+
+/// `TermStreamer` acts as a cursor over a range of terms of a segment.
+/// Terms are guaranteed to be sorted.
+pub struct TermStreamer<'a, A = AlwaysMatch>(InnerTermStreamer<'a, A>)
+where
+    A: Automaton;
+
+impl<'a, A> TermStreamer<'a, A>
+where
+    A: Automaton,
+{
+    /// Advance position the stream on the next item.
+    /// Before the first call to `.advance()`, the stream
+    /// is an unitialized state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a block of an SSTable dictionary is corrupted.
+    /// See [`try_advance`](#method.try_advance).
+    pub fn advance(&mut self) -> bool {
+        self.try_advance()
+            .expect("The term dictionary block is corrupted.")
+    }
+
+    /// Same as [`advance`](#method.advance), but returns an error instead of panicking
+    /// if a block of an SSTable dictionary is corrupted.
+    pub fn try_advance(&mut self) -> io::Result<bool> {
+        match &mut self.0 {
+            InnerTermStreamer::Fst(streamer) => Ok(streamer.advance()),
+            InnerTermStreamer::SSTable(streamer) => streamer.advance(),
+        }
+    }
+
+    /// Returns the `TermOrdinal` of the given term.
+    ///
+    /// May panic if the called as `.advance()` as never
+    /// been called before.
+    pub fn term_ord(&self) -> TermOrdinal {
+        match &self.0 {
+            InnerTermStreamer::Fst(streamer) => streamer.term_ord(),
+            InnerTermStreamer::SSTable(streamer) => streamer.term_ord(),
+        }
+    }
+
+    /// Accesses the current key.
+    ///
+    /// `.key()` should return the key that was returned
+    /// by the `.next()` method.
+    ///
+    /// If the end of the stream as been reached, and `.next()`
+    /// has been called and returned `None`, `.key()` remains
+    /// the value of the last key encountered.
+    ///
+    /// Before any call to `.next()`, `.key()` returns an empty array.
+    pub fn key(&self) -> &[u8] {
+        match &self.0 {
+            InnerTermStreamer::Fst(streamer) => streamer.key(),
+            InnerTermStreamer::SSTable(streamer) => streamer.key(),
+        }
+    }
+
+    /// Accesses the current value.
+    ///
+    /// Calling `.value()` after the end of the stream will return the
+    /// last `.value()` encountered.
+    ///
+    /// Calling `.value()` before the first call to `.advance()` returns
+    /// `TermInfo::default()`.
+    pub fn value(&self) -> &TermInfo {
+        match &self.0 {
+            InnerTermStreamer::Fst(streamer) => streamer.value(),
+            InnerTermStreamer::SSTable(streamer) => streamer.value(),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{TermDictionary, TermDictionaryBuilder, TermStreamer};
+use super::{TermDictionary, TermDictionaryBuilder, TermDictionaryType, TermMerger, TermStreamer};
 
 use crate::directory::{Directory, FileSlice, RamDirectory, TerminatingWrite};
 use crate::postings::TermInfo;
@@ -12,6 +12,7 @@ fn make_term_info(term_ord: u64) -> TermInfo {
     let offset = |term_ord: u64| (term_ord * 100 + term_ord * term_ord) as usize;
     TermInfo {
         doc_freq: term_ord as u32,
+        total_term_freq: term_ord * 3,
         postings_range: offset(term_ord)..offset(term_ord + 1),
         positions_range: offset(term_ord) * 2..offset(term_ord + 1) * 2,
     }
@@ -20,7 +21,7 @@ fn make_term_info(term_ord: u64) -> TermInfo {
 #[test]
 fn test_empty_term_dictionary() {
     let empty = TermDictionary::empty();
-    assert!(empty.stream().unwrap().next().is_none());
+    assert!(!empty.stream().unwrap().advance());
 }
 
 #[test]
@@ -72,20 +73,12 @@ fn test_term_dictionary_simple() -> crate::Result<()> {
     assert_eq!(term_dict.get("abcd")?.unwrap().doc_freq, 346u32);
     let mut stream = term_dict.stream()?;
     {
-        {
-            let (k, v) = stream.next().unwrap();
-            assert_eq!(k, "abc".as_bytes());
-            assert_eq!(v.doc_freq, 34u32);
-        }
+        assert!(stream.advance());
         assert_eq!(stream.key(), "abc".as_bytes());
         assert_eq!(stream.value().doc_freq, 34u32);
     }
     {
-        {
-            let (k, v) = stream.next().unwrap();
-            assert_eq!(k, "abcd".as_bytes());
-            assert_eq!(v.doc_freq, 346u32);
-        }
+        assert!(stream.advance());
         assert_eq!(stream.key(), "abcd".as_bytes());
         assert_eq!(stream.value().doc_freq, 346u32);
     }
@@ -112,10 +105,10 @@ fn test_term_dictionary_stream() -> crate::Result<()> {
     {
         let mut streamer = term_dictionary.stream()?;
         let mut i = 0;
-        while let Some((streamer_k, streamer_v)) = streamer.next() {
+        while streamer.advance() {
             let &(ref key, ref v) = &ids[i];
-            assert_eq!(streamer_k, key.as_bytes());
-            assert_eq!(streamer_v, &make_term_info(*v as u64));
+            assert_eq!(streamer.key(), key.as_bytes());
+            assert_eq!(streamer.value(), &make_term_info(*v as u64));
             i += 1;
         }
     }
@@ -180,11 +173,11 @@ fn test_stream_range() -> crate::Result<()> {
                 .ge(target_key.as_bytes())
                 .into_stream()?;
             for j in 0..3 {
-                let (streamer_k, streamer_v) = streamer.next().unwrap();
+                assert!(streamer.advance());
                 let &(ref key, ref v) = &ids[i + j];
-                assert_eq!(str::from_utf8(streamer_k).unwrap(), key);
-                assert_eq!(streamer_v.doc_freq, *v);
-                assert_eq!(streamer_v, &make_term_info(*v as u64));
+                assert_eq!(str::from_utf8(streamer.key()).unwrap(), key);
+                assert_eq!(streamer.value().doc_freq, *v);
+                assert_eq!(streamer.value(), &make_term_info(*v as u64));
             }
         }
     }
@@ -197,10 +190,10 @@ fn test_stream_range() -> crate::Result<()> {
                 .gt(target_key.as_bytes())
                 .into_stream()?;
             for j in 0..3 {
-                let (streamer_k, streamer_v) = streamer.next().unwrap();
+                assert!(streamer.advance());
                 let &(ref key, ref v) = &ids[i + j + 1];
-                assert_eq!(streamer_k, key.as_bytes());
-                assert_eq!(streamer_v.doc_freq, *v);
+                assert_eq!(streamer.key(), key.as_bytes());
+                assert_eq!(streamer.value().doc_freq, *v);
             }
         }
     }
@@ -216,9 +209,9 @@ fn test_stream_range() -> crate::Result<()> {
                     .lt(last_key.as_bytes())
                     .into_stream()?;
                 for _ in 0..j {
-                    assert!(streamer.next().is_some());
+                    assert!(streamer.advance());
                 }
-                assert!(streamer.next().is_none());
+                assert!(!streamer.advance());
             }
         }
     }
@@ -266,8 +259,8 @@ fn test_stream_range_boundaries_forward() -> crate::Result<()> {
     let term_dictionary = stream_range_test_dict()?;
     let value_list = |mut streamer: TermStreamer<'_>| {
         let mut res: Vec<u32> = vec![];
-        while let Some((_, v)) = streamer.next() {
-            res.push(v.doc_freq);
+        while streamer.advance() {
+            res.push(streamer.value().doc_freq);
         }
         res
     };
@@ -308,8 +301,8 @@ fn test_stream_range_boundaries_backward() -> crate::Result<()> {
     let term_dictionary = stream_range_test_dict()?;
     let value_list_backward = |mut streamer: TermStreamer<'_>| {
         let mut res: Vec<u32> = vec![];
-        while let Some((_, v)) = streamer.next() {
-            res.push(v.doc_freq);
+        while streamer.advance() {
+            res.push(streamer.value().doc_freq);
         }
         res.reverse();
         res
@@ -428,3 +421,309 @@ fn test_automaton_search() -> crate::Result<()> {
     assert!(!range.advance());
     Ok(())
 }
+
+/// Matches the terms of a given length, whatever their bytes.
+struct LengthAutomaton(usize);
+
+impl super::Automaton for LengthAutomaton {
+    type State = Option<usize>;
+
+    fn start(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn is_match(&self, state: &Option<usize>) -> bool {
+        *state == Some(self.0)
+    }
+
+    fn can_match(&self, state: &Option<usize>) -> bool {
+        state.is_some()
+    }
+
+    fn accept(&self, state: &Option<usize>, _byte: u8) -> Option<usize> {
+        state.filter(|&len| len < self.0).map(|len| len + 1)
+    }
+}
+
+#[test]
+fn test_automaton_search_user_defined_automaton() -> crate::Result<()> {
+    let term_dict = build_term_dictionary(&[
+        ("a", 0),
+        ("abc", 1),
+        ("abcd", 2),
+        ("bcd", 3),
+        ("cde", 4),
+        ("de", 5),
+    ])?;
+    let mut stream = term_dict.search(LengthAutomaton(3)).into_stream()?;
+    let mut matches = Vec::new();
+    while stream.advance() {
+        let term = str::from_utf8(stream.key()).unwrap().to_string();
+        matches.push((term, stream.value().clone()));
+    }
+    assert_eq!(
+        matches,
+        vec![
+            ("abc".to_string(), make_term_info(1)),
+            ("bcd".to_string(), make_term_info(3)),
+            ("cde".to_string(), make_term_info(4)),
+        ]
+    );
+    let mut stream = term_dict
+        .search(LengthAutomaton(3))
+        .gt("abc")
+        .lt("cde")
+        .into_stream()?;
+    assert!(stream.advance());
+    assert_eq!(stream.key(), b"bcd");
+    assert_eq!(stream.term_ord(), 3);
+    assert!(!stream.advance());
+    Ok(())
+}
+
+fn build_term_dictionary(terms: &[(&str, u64)]) -> crate::Result<TermDictionary> {
+    let mut term_dictionary_builder = TermDictionaryBuilder::create(Vec::new())?;
+    for &(term, term_ord) in terms {
+        term_dictionary_builder.insert(term.as_bytes(), &make_term_info(term_ord))?;
+    }
+    let buffer = term_dictionary_builder.finish()?;
+    TermDictionary::open(FileSlice::from(buffer))
+}
+
+#[test]
+fn test_term_merger() -> crate::Result<()> {
+    let dict0 = build_term_dictionary(&[("apple", 1), ("banana", 2), ("cherry", 3)])?;
+    let dict1 = build_term_dictionary(&[("banana", 4)])?;
+    let dict2 = build_term_dictionary(&[("apple", 5), ("date", 6)])?;
+    let mut term_merger = TermMerger::new(vec![dict0.stream()?, dict1.stream()?, dict2.stream()?]);
+    let mut merged_terms: Vec<(String, Vec<(usize, u32)>)> = Vec::new();
+    while term_merger.advance()? {
+        let key = str::from_utf8(term_merger.key()).unwrap().to_string();
+        let segment_ords_and_doc_freqs = term_merger
+            .current_segment_ords_and_term_infos()
+            .map(|(segment_ord, term_info)| (segment_ord, term_info.doc_freq))
+            .collect();
+        merged_terms.push((key, segment_ords_and_doc_freqs));
+    }
+    assert_eq!(
+        merged_terms,
+        vec![
+            ("apple".to_string(), vec![(0, 1), (2, 5)]),
+            ("banana".to_string(), vec![(0, 2), (1, 4)]),
+            ("cherry".to_string(), vec![(0, 3)]),
+            ("date".to_string(), vec![(2, 6)]),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_term_merger_matching_segments_and_ranges() -> crate::Result<()> {
+    let dict0 = build_term_dictionary(&[("a", 10), ("b", 11), ("c", 12), ("d", 13)])?;
+    let dict1 = build_term_dictionary(&[("b", 20), ("d", 21), ("e", 22)])?;
+    let mut term_merger = TermMerger::new(vec![
+        dict0.range().ge("b").lt("d").into_stream()?,
+        dict1.range().gt("b").into_stream()?,
+    ]);
+    let mut merged_terms: Vec<(String, Vec<(usize, u64)>)> = Vec::new();
+    while term_merger.advance()? {
+        let key = str::from_utf8(term_merger.key()).unwrap().to_string();
+        merged_terms.push((key, term_merger.matching_segments().collect()));
+    }
+    // Term ordinals are local to each dictionary.
+    assert_eq!(
+        merged_terms,
+        vec![
+            ("b".to_string(), vec![(0, 1)]),
+            ("c".to_string(), vec![(0, 2)]),
+            ("d".to_string(), vec![(1, 1)]),
+            ("e".to_string(), vec![(1, 2)]),
+        ]
+    );
+    assert!(!term_merger.advance()?);
+    Ok(())
+}
+
+fn build_term_dictionary_with_type(
+    terms: &[String],
+    term_dictionary_type: TermDictionaryType,
+) -> crate::Result<TermDictionary> {
+    let mut term_dictionary_builder =
+        TermDictionaryBuilder::create_with_type(Vec::new(), term_dictionary_type)?;
+    for (term_ord, term) in terms.iter().enumerate() {
+        term_dictionary_builder.insert(term.as_bytes(), &make_term_info(term_ord as u64))?;
+    }
+    let buffer = term_dictionary_builder.finish()?;
+    TermDictionary::open_with_type(FileSlice::from(buffer), term_dictionary_type)
+}
+
+fn stream_terms<A: super::Automaton>(
+    mut stream: TermStreamer<'_, A>,
+) -> Vec<(Vec<u8>, u64, TermInfo)> {
+    let mut terms = Vec::new();
+    while stream.advance() {
+        terms.push((
+            stream.key().to_vec(),
+            stream.term_ord(),
+            stream.value().clone(),
+        ));
+    }
+    terms
+}
+
+#[test]
+fn test_sstable_term_dictionary() -> crate::Result<()> {
+    let terms: Vec<String> = (0..1_000).map(|i| format!("doc{:0>6}", i * 3)).collect();
+    let term_dict = build_term_dictionary_with_type(&terms, TermDictionaryType::SSTable)?;
+    assert_eq!(
+        term_dict.term_dictionary_type(),
+        TermDictionaryType::SSTable
+    );
+    assert_eq!(term_dict.num_terms(), terms.len());
+    let mut bytes = Vec::new();
+    for (term_ord, term) in terms.iter().enumerate() {
+        let term_ord = term_ord as u64;
+        assert_eq!(term_dict.term_ord(term)?, Some(term_ord));
+        assert_eq!(term_dict.get(term)?, Some(make_term_info(term_ord)));
+        assert_eq!(
+            term_dict.term_info_from_ord(term_ord)?,
+            make_term_info(term_ord)
+        );
+        assert!(term_dict.ord_to_term(term_ord, &mut bytes)?);
+        assert_eq!(bytes, term.as_bytes());
+    }
+    assert_eq!(term_dict.term_ord("doc000001")?, None);
+    assert_eq!(term_dict.get("")?, None);
+    assert_eq!(term_dict.get("zzz")?, None);
+    assert!(!term_dict.ord_to_term(terms.len() as u64, &mut bytes)?);
+    let streamed_terms: Vec<Vec<u8>> = stream_terms(term_dict.stream()?)
+        .into_iter()
+        .map(|(term, _, _)| term)
+        .collect();
+    let expected_terms: Vec<Vec<u8>> = terms.iter().map(|term| term.as_bytes().to_vec()).collect();
+    assert_eq!(streamed_terms, expected_terms);
+
+    let empty = build_term_dictionary_with_type(&[], TermDictionaryType::SSTable)?;
+    assert_eq!(empty.num_terms(), 0);
+    assert_eq!(empty.get("a")?, None);
+    assert!(!empty.stream()?.advance());
+    assert!(!empty.range().backward().into_stream()?.advance());
+    Ok(())
+}
+
+#[test]
+fn test_sstable_term_dictionary_corrupted_block() -> crate::Result<()> {
+    let terms: Vec<String> = (0..10).map(|i| format!("doc{}", i)).collect();
+    let mut term_dictionary_builder =
+        TermDictionaryBuilder::create_with_type(Vec::new(), TermDictionaryType::SSTable)?;
+    for (term_ord, term) in terms.iter().enumerate() {
+        term_dictionary_builder.insert(term.as_bytes(), &make_term_info(term_ord as u64))?;
+    }
+    let mut buffer = term_dictionary_builder.finish()?;
+    // The first block starts the file.
+    for byte in &mut buffer[..16] {
+        *byte = 255u8;
+    }
+    let term_dict =
+        TermDictionary::open_with_type(FileSlice::from(buffer), TermDictionaryType::SSTable)?;
+    assert!(term_dict.term_info_from_ord(0).is_err());
+    let mut stream = term_dict.stream()?;
+    assert!(stream.try_advance().is_err());
+    // Merging the segment reports the error instead of panicking.
+    let mut term_merger = TermMerger::new(vec![term_dict.stream()?]);
+    assert!(term_merger.advance().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_sstable_term_dictionary_streams_match_fst() -> crate::Result<()> {
+    use crate::query::DfaWrapper;
+    use levenshtein_automata::LevenshteinAutomatonBuilder;
+
+    let terms: Vec<String> = (0..500)
+        .map(|i| format!("{}", i * 7))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let fst_dict = build_term_dictionary_with_type(&terms, TermDictionaryType::Fst)?;
+    let sstable_dict = build_term_dictionary_with_type(&terms, TermDictionaryType::SSTable)?;
+    let bounds: [&[u8]; 6] = [b"", b"1", b"1400", b"2", b"35", b"9999"];
+    for &backward in &[false, true] {
+        for lower in bounds.iter() {
+            for upper in bounds.iter() {
+                let streams = |term_dict: &TermDictionary| -> crate::Result<_> {
+                    let mut range = term_dict.range().gt(lower).le(upper);
+                    let mut range_inclusive = term_dict.range().ge(lower).lt(upper);
+                    if backward {
+                        range = range.backward();
+                        range_inclusive = range_inclusive.backward();
+                    }
+                    Ok((
+                        stream_terms(range.into_stream()?),
+                        stream_terms(range_inclusive.into_stream()?),
+                    ))
+                };
+                assert_eq!(streams(&sstable_dict)?, streams(&fst_dict)?);
+            }
+        }
+        let full_stream = |term_dict: &TermDictionary| -> crate::Result<_> {
+            let range = if backward {
+                term_dict.range().backward()
+            } else {
+                term_dict.range()
+            };
+            Ok(stream_terms(range.into_stream()?))
+        };
+        assert_eq!(full_stream(&sstable_dict)?, full_stream(&fst_dict)?);
+    }
+    let lev_automaton_builder = LevenshteinAutomatonBuilder::new(1, true);
+    let search = |term_dict: &TermDictionary| -> crate::Result<_> {
+        Ok((
+            stream_terms(
+                term_dict
+                    .search(DfaWrapper(lev_automaton_builder.build_dfa("1400")))
+                    .into_stream()?,
+            ),
+            stream_terms(term_dict.search(LengthAutomaton(3)).gt("2").into_stream()?),
+        ))
+    };
+    let (sstable_fuzzy_matches, sstable_length_matches) = search(&sstable_dict)?;
+    assert!(!sstable_fuzzy_matches.is_empty());
+    assert!(!sstable_length_matches.is_empty());
+    assert_eq!(
+        (sstable_fuzzy_matches, sstable_length_matches),
+        search(&fst_dict)?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_term_merger_mixed_dictionary_types() -> crate::Result<()> {
+    let to_terms =
+        |terms: &[&str]| -> Vec<String> { terms.iter().map(|term| term.to_string()).collect() };
+    let dict0 = build_term_dictionary_with_type(
+        &to_terms(&["apple", "banana", "cherry"]),
+        TermDictionaryType::SSTable,
+    )?;
+    let dict1 = build_term_dictionary_with_type(&to_terms(&["banana"]), TermDictionaryType::Fst)?;
+    let dict2 = build_term_dictionary_with_type(
+        &to_terms(&["apple", "date"]),
+        TermDictionaryType::SSTable,
+    )?;
+    let mut term_merger = TermMerger::new(vec![dict0.stream()?, dict1.stream()?, dict2.stream()?]);
+    let mut merged_terms: Vec<(String, Vec<(usize, u64)>)> = Vec::new();
+    while term_merger.advance()? {
+        let key = str::from_utf8(term_merger.key()).unwrap().to_string();
+        merged_terms.push((key, term_merger.matching_segments().collect()));
+    }
+    assert_eq!(
+        merged_terms,
+        vec![
+            ("apple".to_string(), vec![(0, 0), (2, 0)]),
+            ("banana".to_string(), vec![(0, 1), (1, 0)]),
+            ("cherry".to_string(), vec![(0, 2)]),
+            ("date".to_string(), vec![(2, 1)]),
+        ]
+    );
+    Ok(())
+}
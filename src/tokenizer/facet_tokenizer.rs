@@ -38,6 +38,7 @@ impl Tokenizer for FacetTokenizer {
 
 impl<'a> TokenStream for FacetTokenStream<'a> {
     fn advance(&mut self) -> bool {
+        self.token.payload.clear();
         match self.state {
             State::RootFacetNotEmitted => {
                 self.state = if self.text.is_empty() {
@@ -0,0 +1,215 @@
+use crate::schema::Field;
+use crate::tokenizer::{TextAnalyzer, TokenizerManager};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Detects the language of a text.
+///
+/// See [`LanguageRouter`](./struct.LanguageRouter.html).
+pub trait LanguageDetector: Send + Sync + 'static {
+    /// Returns the code identifying the language of `text` (e.g. `"en"`),
+    /// or `None` if the language could not be detected.
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+impl<F> LanguageDetector for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    fn detect(&self, text: &str) -> Option<String> {
+        self(text)
+    }
+}
+
+/// A `LanguageRouter` analyzes the text of a field with the analyzer of
+/// the language detected for each document.
+///
+/// The router is registered in the `TokenizerManager` under a name, like a tokenizer,
+/// and is used for the text fields whose indexing options name it as their tokenizer.
+/// When a document is indexed, the language of the text of such a field is detected,
+/// and the text is analyzed with the analyzer configured for that language, or with
+/// the default analyzer if the language is unknown or has no analyzer.
+///
+/// Looking up the router name in the `TokenizerManager` returns the default analyzer,
+/// which is therefore the analyzer used on queries, as they carry no detected language.
+///
+/// The detected language can be recorded in a bytes or text field of the documents,
+/// e.g. a bytes fast field, to filter or group the documents by language.
+///
+/// ```rust
+/// use tantivy::schema::{Schema, TextFieldIndexing, TextOptions, FAST};
+/// use tantivy::tokenizer::LanguageRouter;
+/// use tantivy::Index;
+///
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field(
+///     "body",
+///     TextOptions::default()
+///         .set_indexing_options(TextFieldIndexing::default().set_tokenizer("multilingual")),
+/// );
+/// let language = schema_builder.add_bytes_field("language", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let detect_language = |text: &str| {
+///     let is_french = text.contains(" le ") || text.contains(" la ");
+///     Some(if is_french { "fr" } else { "en" }.to_string())
+/// };
+/// index.tokenizers().register_language_router(
+///     "multilingual",
+///     LanguageRouter::new(detect_language, "default")
+///         .with_analyzer("en", "en_stem")
+///         .with_language_field(language),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct LanguageRouter {
+    detector: Arc<dyn LanguageDetector>,
+    default_tokenizer: String,
+    tokenizers: HashMap<String, String>,
+    language_field: Option<Field>,
+}
+
+impl LanguageRouter {
+    /// Creates a router detecting the languages with `detector`, and
+    /// analyzing the texts with the tokenizer named `default_tokenizer`
+    /// unless an analyzer is configured for their language.
+    pub fn new<D: LanguageDetector>(detector: D, default_tokenizer: &str) -> LanguageRouter {
+        LanguageRouter {
+            detector: Arc::new(detector),
+            default_tokenizer: default_tokenizer.to_string(),
+            tokenizers: HashMap::new(),
+            language_field: None,
+        }
+    }
+
+    /// Analyzes the texts detected as `language` with the tokenizer named `tokenizer`.
+    pub fn with_analyzer(mut self, language: &str, tokenizer: &str) -> LanguageRouter {
+        self.tokenizers
+            .insert(language.to_string(), tokenizer.to_string());
+        self
+    }
+
+    /// Records the detected language in `language_field`, which must be
+    /// a bytes or text field.
+    pub fn with_language_field(mut self, language_field: Field) -> LanguageRouter {
+        self.language_field = Some(language_field);
+        self
+    }
+
+    /// Returns the name of the tokenizer used when no analyzer
+    /// is configured for the detected language.
+    pub fn default_tokenizer(&self) -> &str {
+        &self.default_tokenizer
+    }
+
+    /// Returns the field recording the detected language, if any.
+    pub fn language_field(&self) -> Option<Field> {
+        self.language_field
+    }
+
+    /// Returns the name of the tokenizer configured for `language`, if any.
+    pub fn tokenizer_for_language(&self, language: &str) -> Option<&str> {
+        self.tokenizers.get(language).map(String::as_str)
+    }
+
+    /// Detects the language of `text`.
+    pub fn detect(&self, text: &str) -> Option<String> {
+        self.detector.detect(text)
+    }
+
+    /// Resolves the analyzers of the router in `tokenizer_manager`.
+    ///
+    /// The languages whose tokenizer is not registered fall back to the default analyzer.
+    pub(crate) fn resolve(&self, tokenizer_manager: &TokenizerManager) -> ResolvedLanguageRouter {
+        let analyzers = self
+            .tokenizers
+            .iter()
+            .filter_map(|(language, tokenizer_name)| {
+                let analyzer = tokenizer_manager.get(tokenizer_name)?;
+                Some((language.clone(), analyzer))
+            })
+            .collect();
+        ResolvedLanguageRouter {
+            router: self.clone(),
+            analyzers,
+        }
+    }
+}
+
+/// A `LanguageRouter` together with the analyzers of its languages.
+pub(crate) struct ResolvedLanguageRouter {
+    router: LanguageRouter,
+    analyzers: HashMap<String, TextAnalyzer>,
+}
+
+impl ResolvedLanguageRouter {
+    pub fn router(&self) -> &LanguageRouter {
+        &self.router
+    }
+
+    /// Returns the analyzer configured for `language`, if any.
+    pub fn analyzer(&self, language: &str) -> Option<&TextAnalyzer> {
+        self.analyzers.get(language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LanguageRouter;
+    use crate::collector::Count;
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST};
+    use crate::{doc, Index, Term};
+
+    fn detect_language(text: &str) -> Option<String> {
+        if text.starts_with("le ") {
+            Some("fr".to_string())
+        } else if text.starts_with("the ") {
+            Some("en".to_string())
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn test_language_router() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field(
+            "body",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("multilingual")),
+        );
+        let language = schema_builder.add_bytes_field("language", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register_language_router(
+            "multilingual",
+            LanguageRouter::new(detect_language, "default")
+                .with_analyzer("en", "en_stem")
+                .with_analyzer("de", "unregistered")
+                .with_language_field(language),
+        );
+        assert!(index.tokenizers().get("multilingual").is_some());
+
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(body => "the dogs are running"))?;
+        index_writer.add_document(doc!(body => "le chat running"))?;
+        index_writer.add_document(doc!(body => "dogs running"))?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |text: &str| {
+            let query = TermQuery::new(Term::from_field_text(body, text), IndexRecordOption::Basic);
+            searcher.search(&query, &Count)
+        };
+        // Only the English document is stemmed.
+        assert_eq!(count("run")?, 1);
+        assert_eq!(count("running")?, 2);
+        assert_eq!(count("dog")?, 1);
+
+        let segment_reader = searcher.segment_reader(0);
+        let languages = segment_reader.fast_fields().bytes(language)?;
+        assert_eq!(languages.get_bytes(0), b"en");
+        assert_eq!(languages.get_bytes(1), b"fr");
+        assert_eq!(languages.get_bytes(2), b"");
+        Ok(())
+    }
+}
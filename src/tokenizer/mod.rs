@@ -52,6 +52,14 @@
 //! remove their inflection. This tokenizer is slower than the default one,
 //! but is recommended to improve recall.
 //!
+//! ## `unicode_word`
+//!
+//! Like `default`, but chops your text on the word boundaries defined by the Unicode
+//! Text Segmentation specification (UAX #29) with the
+//! [`UnicodeWordTokenizer`](./struct.UnicodeWordTokenizer.html). Apostrophes and
+//! decimal separators are kept within words and numbers, and the Han ideographs
+//! are split into single characters.
+//!
 //!
 //! # Custom tokenizers
 //!
@@ -120,6 +128,7 @@
 mod alphanum_only;
 mod ascii_folding_filter;
 mod facet_tokenizer;
+mod language_router;
 mod lower_caser;
 mod ngram_tokenizer;
 mod raw_tokenizer;
@@ -131,11 +140,14 @@ mod token_stream_chain;
 mod tokenized_string;
 mod tokenizer;
 mod tokenizer_manager;
+mod unicode_word_tokenizer;
 mod whitespace_tokenizer;
 
 pub use self::alphanum_only::AlphaNumOnlyFilter;
 pub use self::ascii_folding_filter::AsciiFoldingFilter;
 pub use self::facet_tokenizer::FacetTokenizer;
+pub(crate) use self::language_router::ResolvedLanguageRouter;
+pub use self::language_router::{LanguageDetector, LanguageRouter};
 pub use self::lower_caser::LowerCaser;
 pub use self::ngram_tokenizer::NgramTokenizer;
 pub use self::raw_tokenizer::RawTokenizer;
@@ -152,6 +164,7 @@ pub use self::tokenizer::{
 };
 
 pub use self::tokenizer_manager::TokenizerManager;
+pub use self::unicode_word_tokenizer::UnicodeWordTokenizer;
 
 /// Maximum authorized len (in bytes) for a token.
 ///
@@ -154,7 +154,7 @@ impl<'a> TokenStream for NgramTokenStream<'a> {
             self.token.position = 0;
             self.token.offset_from = offset_from;
             self.token.offset_to = offset_to;
-            self.token.text.clear();
+            self.token.reset();
             self.token.text.push_str(&self.text[offset_from..offset_to]);
             true
         } else {
@@ -1,11 +1,13 @@
 use crate::tokenizer::{BoxTokenStream, Token, TokenStream};
 use std::ops::DerefMut;
 
-const POSITION_GAP: usize = 2;
+// Number of positions left empty between two token streams by default.
+const DEFAULT_POSITION_GAP: usize = 1;
 
 pub(crate) struct TokenStreamChain<'a> {
     offsets: Vec<usize>,
     token_streams: Vec<BoxTokenStream<'a>>,
+    position_gap: usize,
     position_shift: usize,
     stream_idx: usize,
     token: Token,
@@ -20,10 +22,17 @@ impl<'a> TokenStreamChain<'a> {
             offsets,
             stream_idx: 0,
             token_streams,
+            position_gap: DEFAULT_POSITION_GAP,
             position_shift: 0,
             token: Token::default(),
         }
     }
+
+    /// Sets the number of positions left empty between two token streams.
+    pub fn with_position_gap(mut self, position_gap: usize) -> TokenStreamChain<'a> {
+        self.position_gap = position_gap;
+        self
+    }
 }
 
 impl<'a> TokenStream for TokenStreamChain<'a> {
@@ -38,10 +47,12 @@ impl<'a> TokenStream for TokenStreamChain<'a> {
                 self.token.position = token.position + self.position_shift;
                 self.token.text.clear();
                 self.token.text.push_str(token.text.as_str());
+                self.token.payload.clear();
+                self.token.payload.extend_from_slice(&token.payload);
                 return true;
             } else {
                 self.stream_idx += 1;
-                self.position_shift = self.token.position.wrapping_add(POSITION_GAP);
+                self.position_shift = self.token.position.wrapping_add(1 + self.position_gap);
             }
         }
         false
@@ -68,7 +79,7 @@ impl<'a> TokenStream for TokenStreamChain<'a> {
 mod tests {
     use super::super::{SimpleTokenizer, TokenStream, Tokenizer};
     use super::TokenStreamChain;
-    use super::POSITION_GAP;
+    use super::DEFAULT_POSITION_GAP;
 
     #[test]
     fn test_chain_first_emits_no_tokens() {
@@ -82,14 +93,30 @@ mod tests {
         assert_eq!(token_chain.token().text, "hello");
         assert_eq!(token_chain.token().offset_from, 0);
         assert_eq!(token_chain.token().offset_to, 5);
-        assert_eq!(token_chain.token().position, POSITION_GAP - 1);
+        assert_eq!(token_chain.token().position, DEFAULT_POSITION_GAP);
 
         assert!(token_chain.advance());
         assert_eq!(token_chain.token().text, "world");
         assert_eq!(token_chain.token().offset_from, 6);
         assert_eq!(token_chain.token().offset_to, 11);
-        assert_eq!(token_chain.token().position, POSITION_GAP);
+        assert_eq!(token_chain.token().position, DEFAULT_POSITION_GAP + 1);
 
         assert!(!token_chain.advance());
     }
+
+    #[test]
+    fn test_chain_position_gap() {
+        let token_streams = vec![
+            SimpleTokenizer.token_stream("hello world"),
+            SimpleTokenizer.token_stream("happy"),
+            SimpleTokenizer.token_stream("tax payer"),
+        ];
+        let mut token_chain =
+            TokenStreamChain::new(vec![0, 11, 16], token_streams).with_position_gap(100);
+        let mut positions = Vec::new();
+        while token_chain.advance() {
+            positions.push(token_chain.token().position);
+        }
+        assert_eq!(positions, vec![0, 1, 102, 203, 204]);
+    }
 }
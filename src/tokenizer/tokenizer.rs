@@ -21,6 +21,28 @@ pub struct Token {
     pub text: String,
     /// Is the length expressed in term of number of original tokens.
     pub position_length: usize,
+    /// Optional payload attached to this occurrence of the token.
+    ///
+    /// It is only recorded if the field is configured to record payloads
+    /// (see `TextFieldIndexing::set_payloads`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub payload: Vec<u8>,
+}
+
+impl Token {
+    /// Returns the slice of `text` the token was generated from.
+    ///
+    /// `text` has to be the text that was analyzed.
+    pub fn original_text<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.offset_from..self.offset_to]
+    }
+
+    /// Clears the text and the payload of the token, so that the token
+    /// can be reused for the next token of a stream.
+    pub fn reset(&mut self) {
+        self.text.clear();
+        self.payload.clear();
+    }
 }
 
 impl Default for Token {
@@ -31,6 +53,7 @@ impl Default for Token {
             position: usize::max_value(),
             text: String::with_capacity(200),
             position_length: 1,
+            payload: Vec::new(),
         }
     }
 }
@@ -85,7 +108,7 @@ impl TextAnalyzer {
     /// Tokenize an array`&str`
     ///
     /// The resulting `BoxTokenStream` is equivalent to what would be obtained if the &str were
-    /// one concatenated `&str`, with an artificial position gap of `2` between the different fields
+    /// one concatenated `&str`, with an artificial position gap of `1` between the different fields
     /// to prevent accidental `PhraseQuery` to match accross two terms.
     pub fn token_stream_texts<'a>(&self, texts: &'a [&'a str]) -> BoxTokenStream<'a> {
         assert!(!texts.is_empty());
@@ -115,6 +138,62 @@ impl TextAnalyzer {
         }
         token_stream
     }
+
+    /// Analyzes a `str` and returns all of its tokens.
+    ///
+    /// This is the same analysis as the one done when indexing the text,
+    /// which makes it possible to re-analyze stored text, e.g. to highlight it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::tokenizer::*;
+    ///
+    /// let analyzer = TextAnalyzer::from(SimpleTokenizer).filter(LowerCaser);
+    /// let text = "Hello, happy tax payer";
+    /// let tokens = analyzer.analyze(text);
+    /// assert_eq!(tokens.len(), 4);
+    /// assert_eq!(tokens[1].text, "happy");
+    /// assert_eq!(tokens[1].position, 1);
+    /// assert_eq!(tokens[0].original_text(text), "Hello");
+    /// ```
+    pub fn analyze(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        self.token_stream(text)
+            .process(&mut |token| tokens.push(token.clone()));
+        tokens
+    }
+
+    /// Analyzes the values of a multivalued text field and returns all of their tokens,
+    /// along with the ordinal of the value they belong to.
+    ///
+    /// Positions are the ones of the index, with `position_gap` empty positions between
+    /// two values, while the offsets of each token are relative to its own value.
+    pub fn analyze_texts(&self, texts: &[&str], position_gap: usize) -> Vec<(usize, Token)> {
+        let mut offsets = Vec::with_capacity(texts.len());
+        let mut total_offset = 0;
+        for text in texts {
+            offsets.push(total_offset);
+            total_offset += text.len();
+        }
+        let token_streams = texts.iter().map(|text| self.token_stream(text)).collect();
+        let mut token_stream =
+            TokenStreamChain::new(offsets.clone(), token_streams).with_position_gap(position_gap);
+        let mut tokens = Vec::new();
+        token_stream.process(&mut |token| {
+            // Empty values share their offset with the next value, which is
+            // the one the token comes from.
+            let text_ord = offsets
+                .iter()
+                .rposition(|&offset| offset <= token.offset_from)
+                .unwrap_or(0);
+            let mut token = token.clone();
+            token.offset_from -= offsets[text_ord];
+            token.offset_to -= offsets[text_ord];
+            tokens.push((text_ord, token));
+        });
+        tokens
+    }
 }
 
 impl Clone for TextAnalyzer {
@@ -153,6 +232,20 @@ impl<T: Tokenizer + Clone> TokenizerClone for T {
     }
 }
 
+impl Clone for Box<dyn Tokenizer> {
+    fn clone(&self) -> Self {
+        (**self).box_clone()
+    }
+}
+
+/// Boxed tokenizers are tokenizers themselves, so that tokenizers only known
+/// at runtime can be registered in the `TokenizerManager`.
+impl Tokenizer for Box<dyn Tokenizer> {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        (**self).token_stream(text)
+    }
+}
+
 impl<'a> TokenStream for Box<dyn TokenStream + 'a> {
     fn advance(&mut self) -> bool {
         let token_stream: &mut dyn TokenStream = self.borrow_mut();
@@ -312,7 +405,63 @@ impl<T: TokenFilter + Clone> TokenFilterClone for T {
 
 #[cfg(test)]
 mod test {
-    use super::Token;
+    use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+    use crate::tokenizer::{SimpleTokenizer, TextAnalyzer};
+
+    /// Attaches the length of the token as a payload, to the tokens
+    /// starting with an `a` only.
+    #[derive(Clone)]
+    struct PayloadFilter;
+
+    struct PayloadFilterStream<'a> {
+        tail: BoxTokenStream<'a>,
+    }
+
+    impl TokenFilter for PayloadFilter {
+        fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+            BoxTokenStream::from(PayloadFilterStream { tail: token_stream })
+        }
+    }
+
+    impl<'a> TokenStream for PayloadFilterStream<'a> {
+        fn advance(&mut self) -> bool {
+            if !self.tail.advance() {
+                return false;
+            }
+            let token = self.tail.token_mut();
+            if token.text.starts_with('a') {
+                token.payload = vec![token.text.len() as u8];
+            }
+            true
+        }
+
+        fn token(&self) -> &Token {
+            self.tail.token()
+        }
+
+        fn token_mut(&mut self) -> &mut Token {
+            self.tail.token_mut()
+        }
+    }
+
+    #[test]
+    fn test_payload_set_on_some_tokens() {
+        let analyzer = TextAnalyzer::from(SimpleTokenizer).filter(PayloadFilter);
+        let mut token_stream = analyzer.token_stream("abc b ab c");
+        let mut payloads: Vec<(String, Vec<u8>)> = Vec::new();
+        token_stream.process(&mut |token: &Token| {
+            payloads.push((token.text.clone(), token.payload.clone()));
+        });
+        assert_eq!(
+            payloads,
+            vec![
+                ("abc".to_string(), vec![3]),
+                ("b".to_string(), vec![]),
+                ("ab".to_string(), vec![2]),
+                ("c".to_string(), vec![]),
+            ]
+        );
+    }
 
     #[test]
     fn clone() {
@@ -322,6 +471,7 @@ mod test {
             offset_to: 3,
             text: "abc".to_string(),
             position_length: 1,
+            payload: Vec::new(),
         };
         let t2 = t1.clone();
 
@@ -330,4 +480,34 @@ mod test {
         assert_eq!(t1.offset_to, t2.offset_to);
         assert_eq!(t1.text, t2.text);
     }
+
+    #[test]
+    fn test_analyze_texts() {
+        use crate::tokenizer::{LowerCaser, SimpleTokenizer, TextAnalyzer};
+        let analyzer = TextAnalyzer::from(SimpleTokenizer).filter(LowerCaser);
+        let texts = ["Hello World", "", "Happy tax payer"];
+        let tokens = analyzer.analyze_texts(&texts, 10);
+        let summary: Vec<(usize, &str, usize, &str)> = tokens
+            .iter()
+            .map(|(text_ord, token)| {
+                (
+                    *text_ord,
+                    token.text.as_str(),
+                    token.position,
+                    token.original_text(texts[*text_ord]),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, "hello", 0, "Hello"),
+                (0, "world", 1, "World"),
+                (2, "happy", 12, "Happy"),
+                (2, "tax", 13, "tax"),
+                (2, "payer", 14, "payer"),
+            ]
+        );
+        assert_eq!(analyzer.analyze("").len(), 0);
+    }
 }
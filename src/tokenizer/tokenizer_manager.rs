@@ -1,10 +1,12 @@
 use crate::tokenizer::stemmer::Language;
 use crate::tokenizer::tokenizer::TextAnalyzer;
+use crate::tokenizer::LanguageRouter;
 use crate::tokenizer::LowerCaser;
 use crate::tokenizer::RawTokenizer;
 use crate::tokenizer::RemoveLongFilter;
 use crate::tokenizer::SimpleTokenizer;
 use crate::tokenizer::Stemmer;
+use crate::tokenizer::UnicodeWordTokenizer;
 use crate::tokenizer::WhitespaceTokenizer;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -21,9 +23,13 @@ use std::sync::{Arc, RwLock};
 ///  * `en_stem` : Like `default`, but also applies stemming on the
 ///  resulting tokens. Stemming can improve the recall of your
 ///  search engine.
+///  * `unicode_word` : Like `default`, but chops the text on Unicode word boundaries.
+///
+/// It also stores the `LanguageRouter`s, which are named like tokenizers.
 #[derive(Clone)]
 pub struct TokenizerManager {
     tokenizers: Arc<RwLock<HashMap<String, TextAnalyzer>>>,
+    language_routers: Arc<RwLock<HashMap<String, LanguageRouter>>>,
 }
 
 impl TokenizerManager {
@@ -40,11 +46,45 @@ impl TokenizerManager {
     }
 
     /// Accessing a tokenizer given its name.
+    ///
+    /// For the name of a `LanguageRouter`, the default analyzer of the router is returned.
     pub fn get(&self, tokenizer_name: &str) -> Option<TextAnalyzer> {
-        self.tokenizers
+        let tokenizer_opt = self
+            .tokenizers
             .read()
             .expect("Acquiring the lock should never fail")
             .get(tokenizer_name)
+            .cloned();
+        tokenizer_opt.or_else(|| {
+            let language_router = self.language_router(tokenizer_name)?;
+            self.get(language_router.default_tokenizer())
+        })
+    }
+
+    /// Returns true if a tokenizer or a language router is registered under `tokenizer_name`.
+    pub fn contains(&self, tokenizer_name: &str) -> bool {
+        self.tokenizers
+            .read()
+            .expect("Acquiring the lock should never fail")
+            .contains_key(tokenizer_name)
+            || self.language_router(tokenizer_name).is_some()
+    }
+
+    /// Registers a language router under a given name, which can then
+    /// be used as the tokenizer of text fields.
+    pub fn register_language_router(&self, name: &str, language_router: LanguageRouter) {
+        self.language_routers
+            .write()
+            .expect("Acquiring the lock should never fail")
+            .insert(name.to_string(), language_router);
+    }
+
+    /// Accessing a language router given its name.
+    pub fn language_router(&self, name: &str) -> Option<LanguageRouter> {
+        self.language_routers
+            .read()
+            .expect("Acquiring the lock should never fail")
+            .get(name)
             .cloned()
     }
 }
@@ -54,10 +94,12 @@ impl Default for TokenizerManager {
     /// the default pre-configured tokenizers of `tantivy`.
     /// - simple
     /// - en_stem
+    /// - unicode_word
     /// - ja
     fn default() -> TokenizerManager {
         let manager = TokenizerManager {
             tokenizers: Arc::new(RwLock::new(HashMap::new())),
+            language_routers: Arc::new(RwLock::new(HashMap::new())),
         };
         manager.register("raw", RawTokenizer);
         manager.register(
@@ -73,6 +115,12 @@ impl Default for TokenizerManager {
                 .filter(LowerCaser)
                 .filter(Stemmer::new(Language::English)),
         );
+        manager.register(
+            "unicode_word",
+            TextAnalyzer::from(UnicodeWordTokenizer)
+                .filter(RemoveLongFilter::limit(40))
+                .filter(LowerCaser),
+        );
         manager.register("whitespace", WhitespaceTokenizer);
         manager
     }
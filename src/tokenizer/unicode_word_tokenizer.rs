@@ -0,0 +1,128 @@
+use super::BoxTokenStream;
+use super::{Token, TokenStream, Tokenizer};
+use unicode_segmentation::{UWordBoundIndices, UnicodeSegmentation};
+
+/// Tokenize the text into words, following the word boundaries of the
+/// [Unicode Text Segmentation (UAX #29)](https://unicode.org/reports/tr29/#Word_Boundaries)
+/// specification.
+///
+/// Unlike the `SimpleTokenizer`, it keeps the apostrophes within words (`can't`, `l'avion`),
+/// the decimal separators within numbers (`3.14`, `1,000`), the underscores within
+/// identifiers, and the combining marks with the letter they apply to. Hyphens are word
+/// boundaries. The runs of Katakana are kept as a single token, while each Han ideograph
+/// and Hiragana character is a token of its own. The scripts written without spaces
+/// (e.g. Thai) require a dictionary to be segmented, and are split into characters.
+///
+/// Only the segments containing a letter, a digit or an ideograph are emitted:
+/// punctuation and whitespaces are dropped.
+///
+/// The word boundaries are found by the `unicode-segmentation` crate.
+#[derive(Clone)]
+pub struct UnicodeWordTokenizer;
+
+pub struct UnicodeWordTokenStream<'a> {
+    word_bounds: UWordBoundIndices<'a>,
+    token: Token,
+}
+
+impl Tokenizer for UnicodeWordTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(UnicodeWordTokenStream {
+            word_bounds: text.split_word_bound_indices(),
+            token: Token::default(),
+        })
+    }
+}
+
+impl<'a> TokenStream for UnicodeWordTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        self.token.reset();
+        for (offset_from, segment) in &mut self.word_bounds {
+            // Ideographs are alphabetic, so that they are emitted too.
+            if segment.chars().any(char::is_alphanumeric) {
+                self.token.offset_from = offset_from;
+                self.token.offset_to = offset_from + segment.len();
+                self.token.position = self.token.position.wrapping_add(1);
+                self.token.text.push_str(segment);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{TextAnalyzer, Token, UnicodeWordTokenizer};
+
+    #[test]
+    fn test_unicode_word_tokenizer() {
+        let tokens = token_stream_helper("Hello, happy tax-payer!");
+        assert_eq!(tokens.len(), 4);
+        assert_token(&tokens[0], 0, "Hello", 0, 5);
+        assert_token(&tokens[1], 1, "happy", 7, 12);
+        assert_token(&tokens[2], 2, "tax", 13, 16);
+        assert_token(&tokens[3], 3, "payer", 17, 22);
+    }
+
+    #[test]
+    fn test_unicode_word_tokenizer_word_boundaries() {
+        let texts = |text: &str| -> Vec<String> {
+            token_stream_helper(text)
+                .into_iter()
+                .map(|token| token.text)
+                .collect()
+        };
+        assert_eq!(
+            texts("can't won\u{2019}t l'avion 'quoted'"),
+            &["can't", "won\u{2019}t", "l'avion", "quoted"]
+        );
+        assert_eq!(
+            texts("e.g. 3.14 1,000,000 v2.0 $42"),
+            &["e.g", "3.14", "1,000,000", "v2.0", "42"]
+        );
+        assert_eq!(texts("snake_case __init__ _"), &["snake_case", "__init__"]);
+        // The combining marks are kept within the words.
+        assert_eq!(texts("nai\u{0308}ve café"), &["nai\u{0308}ve", "café"]);
+        assert_eq!(texts("Привет, мир"), &["Привет", "мир"]);
+        assert_eq!(texts("ภาษา"), &["ภ", "า", "ษ", "า"]);
+        // The format characters are ignored (WB4), and the Hebrew acronyms are kept (WB7b).
+        assert_eq!(texts("hy\u{00AD}phen"), &["hy\u{00AD}phen"]);
+        assert_eq!(texts("צה\"ל"), &["צה\"ל"]);
+        assert_eq!(
+            texts("東京都のカタカナ"),
+            &["東", "京", "都", "の", "カタカナ"]
+        );
+        assert!(texts(" -- ... ").is_empty());
+        assert!(texts("").is_empty());
+    }
+
+    #[test]
+    fn test_unicode_word_tokenizer_offsets() {
+        let tokens = token_stream_helper("«Él» 東京");
+        assert_eq!(tokens.len(), 3);
+        assert_token(&tokens[0], 0, "Él", 2, 5);
+        assert_token(&tokens[1], 1, "東", 8, 11);
+        assert_token(&tokens[2], 2, "京", 11, 14);
+    }
+
+    fn token_stream_helper(text: &str) -> Vec<Token> {
+        let a = TextAnalyzer::from(UnicodeWordTokenizer);
+        let mut token_stream = a.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+}
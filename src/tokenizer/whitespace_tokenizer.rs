@@ -35,7 +35,7 @@ impl<'a> WhitespaceTokenStream<'a> {
 
 impl<'a> TokenStream for WhitespaceTokenStream<'a> {
     fn advance(&mut self) -> bool {
-        self.token.text.clear();
+        self.token.reset();
         self.token.position = self.token.position.wrapping_add(1);
         while let Some((offset_from, c)) = self.chars.next() {
             if !c.is_ascii_whitespace() {
@@ -0,0 +1,125 @@
+use crate::schema::VectorSimilarity;
+
+/// Number of iterations of the k-means clustering.
+const NUM_ITERATIONS: usize = 8;
+/// Maximum number of vectors used to train the centroids.
+const MAX_TRAINING_VECTORS: usize = 32_768;
+/// Segments with less vectors than this are searched exhaustively.
+const MIN_VECTORS_FOR_CLUSTERING: usize = 256;
+/// Upper bound of the number of lists.
+const MAX_NUM_LISTS: usize = 4_096;
+
+/// An inverted file (IVF) index: the vectors are clustered around
+/// `num_lists` centroids, and each list holds the ordinals of the
+/// vectors closest to its centroid.
+pub(crate) struct IvfIndex {
+    pub centroids: Vec<f32>,
+    pub lists: Vec<Vec<u32>>,
+}
+
+/// Returns the number of lists of the IVF index of `num_vectors` vectors.
+fn num_lists(num_vectors: usize) -> usize {
+    if num_vectors < MIN_VECTORS_FOR_CLUSTERING {
+        1
+    } else {
+        ((num_vectors as f64).sqrt() as usize).min(MAX_NUM_LISTS)
+    }
+}
+
+/// Returns the ordinal of the centroid closest to `vector`.
+pub(crate) fn closest_centroid(
+    similarity: VectorSimilarity,
+    centroids: &[f32],
+    vector: &[f32],
+) -> usize {
+    let mut best = (0, f32::NEG_INFINITY);
+    for (centroid_ord, centroid) in centroids.chunks_exact(vector.len()).enumerate() {
+        let score = similarity.score(centroid, vector);
+        if score > best.1 {
+            best = (centroid_ord, score);
+        }
+    }
+    best.0
+}
+
+impl IvfIndex {
+    /// Builds the IVF index of `vectors`, the concatenation of vectors
+    /// of `dimension` values, with a k-means clustering.
+    ///
+    /// The clustering is deterministic: the same vectors always yield the same index.
+    pub fn build(similarity: VectorSimilarity, dimension: usize, vectors: &[f32]) -> IvfIndex {
+        let num_vectors = vectors.len() / dimension.max(1);
+        let num_lists = num_lists(num_vectors);
+        if num_lists <= 1 || dimension == 0 {
+            return IvfIndex {
+                centroids: vec![0.0; dimension],
+                lists: vec![(0..num_vectors as u32).collect()],
+            };
+        }
+        let training_step = (num_vectors / MAX_TRAINING_VECTORS).max(1);
+        let training_vectors: Vec<&[f32]> = vectors
+            .chunks_exact(dimension)
+            .step_by(training_step)
+            .collect();
+        // The centroids are initialized with vectors spread over the training set.
+        let init_step = training_vectors.len() / num_lists;
+        let mut centroids: Vec<f32> = (0..num_lists)
+            .flat_map(|list_ord| training_vectors[list_ord * init_step].iter().cloned())
+            .collect();
+        let mut sums = vec![0f32; num_lists * dimension];
+        let mut counts = vec![0u32; num_lists];
+        for _ in 0..NUM_ITERATIONS {
+            sums.iter_mut().for_each(|sum| *sum = 0.0);
+            counts.iter_mut().for_each(|count| *count = 0);
+            for vector in &training_vectors {
+                let list_ord = closest_centroid(similarity, &centroids, vector);
+                counts[list_ord] += 1;
+                let sum = &mut sums[list_ord * dimension..(list_ord + 1) * dimension];
+                for (sum_val, val) in sum.iter_mut().zip(vector.iter()) {
+                    *sum_val += val;
+                }
+            }
+            for (list_ord, &count) in counts.iter().enumerate() {
+                // Empty clusters keep their previous centroid.
+                if count == 0 {
+                    continue;
+                }
+                let range = list_ord * dimension..(list_ord + 1) * dimension;
+                for (centroid_val, sum_val) in centroids[range.clone()].iter_mut().zip(&sums[range])
+                {
+                    *centroid_val = sum_val / count as f32;
+                }
+            }
+        }
+        let mut lists = vec![Vec::new(); num_lists];
+        for (vector_ord, vector) in vectors.chunks_exact(dimension).enumerate() {
+            lists[closest_centroid(similarity, &centroids, vector)].push(vector_ord as u32);
+        }
+        IvfIndex { centroids, lists }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IvfIndex;
+    use crate::schema::VectorSimilarity;
+
+    #[test]
+    fn test_ivf_index_lists_every_vector_once() {
+        let vectors: Vec<f32> = (0..1_000)
+            .flat_map(|i| vec![(i % 10) as f32, (i / 10) as f32])
+            .collect();
+        let ivf_index = IvfIndex::build(VectorSimilarity::Euclidean, 2, &vectors);
+        assert_eq!(ivf_index.lists.len(), 31);
+        assert_eq!(ivf_index.centroids.len(), 31 * 2);
+        let mut ordinals: Vec<u32> = ivf_index.lists.iter().flatten().cloned().collect();
+        ordinals.sort_unstable();
+        assert_eq!(ordinals, (0..1_000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_ivf_index_small() {
+        let ivf_index = IvfIndex::build(VectorSimilarity::Cosine, 3, &[1.0, 0.0, 0.0]);
+        assert_eq!(ivf_index.lists, vec![vec![0]]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Vector fields, and approximate nearest neighbor search.
+//!
+//! Each document can have one dense vector per vector field. The vectors of a
+//! segment are written to the `.vec` component, along with an inverted file (IVF)
+//! index: the vectors are clustered with k-means, and a search only visits
+//! the clusters whose centroids are the closest to the query vector.
+//!
+//! The vectors are searched with a [`VectorQuery`](crate::query::VectorQuery).
+
+mod ivf;
+mod reader;
+mod serializer;
+mod writer;
+
+pub use self::reader::{VectorReader, VectorReaders};
+pub use self::serializer::VectorsSerializer;
+pub use self::writer::VectorsWriter;
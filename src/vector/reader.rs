@@ -0,0 +1,244 @@
+use crate::directory::{CompositeFile, FileSlice, OwnedBytes};
+use crate::schema::{Field, VectorSimilarity};
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::{DocId, Score};
+use std::convert::TryInto;
+use std::io;
+use std::sync::Arc;
+
+/// Data of the vector fields of a segment.
+#[derive(Clone)]
+pub struct VectorReaders {
+    data: Arc<CompositeFile>,
+}
+
+impl VectorReaders {
+    /// Creates a vector readers from a file.
+    pub fn open(file: FileSlice) -> crate::Result<VectorReaders> {
+        let data = CompositeFile::open(&file)?;
+        Ok(VectorReaders {
+            data: Arc::new(data),
+        })
+    }
+
+    /// Returns the vector readers of a segment without any vector.
+    pub fn empty() -> VectorReaders {
+        VectorReaders {
+            data: Arc::new(CompositeFile::empty()),
+        }
+    }
+
+    /// Returns the `VectorReader` of `field`, or `None` if the segment
+    /// does not have any data for this field.
+    pub fn get_field(
+        &self,
+        field: Field,
+        similarity: VectorSimilarity,
+    ) -> crate::Result<Option<VectorReader>> {
+        if let Some(file) = self.data.open_read(field) {
+            Ok(Some(VectorReader::open(file, similarity)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Return a break down of the space usage per field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.data.space_usage()
+    }
+}
+
+fn read_u32(data: &[u8], idx: usize) -> u32 {
+    u32::from_le_bytes(data[idx * 4..idx * 4 + 4].try_into().unwrap())
+}
+
+fn read_f32s(data: &[u8], output: &mut Vec<f32>) {
+    output.clear();
+    output.extend(
+        data.chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())),
+    );
+}
+
+/// Reads the vectors of a vector field of a segment, and searches
+/// the nearest neighbors of a vector with their IVF index.
+#[derive(Clone)]
+pub struct VectorReader {
+    similarity: VectorSimilarity,
+    dimension: usize,
+    num_vectors: usize,
+    num_lists: usize,
+    doc_ids: OwnedBytes,
+    vectors: OwnedBytes,
+    centroids: OwnedBytes,
+    list_offsets: OwnedBytes,
+    list_vector_ords: OwnedBytes,
+}
+
+impl VectorReader {
+    /// Opens the vectors of a field.
+    pub fn open(file: FileSlice, similarity: VectorSimilarity) -> io::Result<VectorReader> {
+        let data = file.read_bytes()?;
+        let truncated = || io::Error::new(io::ErrorKind::InvalidData, "Truncated vector data.");
+        if data.len() < 8 {
+            return Err(truncated());
+        }
+        let dimension = read_u32(data.as_slice(), 0) as usize;
+        let num_vectors = read_u32(data.as_slice(), 1) as usize;
+        let (_, data) = data.split(8);
+        if data.len() < num_vectors * 4 * (1 + dimension) + 4 {
+            return Err(truncated());
+        }
+        let (doc_ids, data) = data.split(num_vectors * 4);
+        let (vectors, data) = data.split(num_vectors * dimension * 4);
+        let num_lists = read_u32(data.as_slice(), 0) as usize;
+        let (_, data) = data.split(4);
+        if data.len() != (num_lists * (1 + dimension) + 1 + num_vectors) * 4 {
+            return Err(truncated());
+        }
+        let (centroids, data) = data.split(num_lists * dimension * 4);
+        let (list_offsets, list_vector_ords) = data.split((num_lists + 1) * 4);
+        Ok(VectorReader {
+            similarity,
+            dimension,
+            num_vectors,
+            num_lists,
+            doc_ids,
+            vectors,
+            centroids,
+            list_offsets,
+            list_vector_ords,
+        })
+    }
+
+    /// Returns the reader of a field without any vector.
+    pub fn empty(dimension: usize, similarity: VectorSimilarity) -> VectorReader {
+        VectorReader {
+            similarity,
+            dimension,
+            num_vectors: 0,
+            num_lists: 0,
+            doc_ids: OwnedBytes::empty(),
+            vectors: OwnedBytes::empty(),
+            centroids: OwnedBytes::empty(),
+            list_offsets: OwnedBytes::empty(),
+            list_vector_ords: OwnedBytes::empty(),
+        }
+    }
+
+    /// Returns the dimension of the vectors.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the number of documents of the segment that have a vector.
+    pub fn num_vectors(&self) -> usize {
+        self.num_vectors
+    }
+
+    /// Returns the number of lists of the IVF index of the vectors.
+    pub fn num_lists(&self) -> usize {
+        self.num_lists
+    }
+
+    fn doc_id(&self, vector_ord: usize) -> DocId {
+        read_u32(self.doc_ids.as_slice(), vector_ord)
+    }
+
+    fn read_vector(&self, vector_ord: usize, output: &mut Vec<f32>) {
+        let start = vector_ord * self.dimension * 4;
+        read_f32s(
+            &self.vectors.as_slice()[start..start + self.dimension * 4],
+            output,
+        );
+    }
+
+    /// Returns the vector of the document `doc`, or `None` if it does not have any.
+    pub fn vector(&self, doc: DocId) -> Option<Vec<f32>> {
+        let vector_ord = self.vector_ord(doc)?;
+        let mut vector = Vec::with_capacity(self.dimension);
+        self.read_vector(vector_ord, &mut vector);
+        Some(vector)
+    }
+
+    fn vector_ord(&self, doc: DocId) -> Option<usize> {
+        let (mut start, mut end) = (0, self.num_vectors);
+        while start < end {
+            let mid = (start + end) / 2;
+            match self.doc_id(mid).cmp(&doc) {
+                std::cmp::Ordering::Less => start = mid + 1,
+                std::cmp::Ordering::Greater => end = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    /// Returns the similarity between `query` and the vector of `doc`,
+    /// or `None` if the document does not have any vector.
+    pub fn score(&self, doc: DocId, query: &[f32]) -> Option<Score> {
+        let vector = self.vector(doc)?;
+        Some(self.similarity.score(query, &vector))
+    }
+
+    /// Searches the `top_k` vectors closest to `query` among the documents
+    /// accepted by `filter`, returning their `(doc, score)` sorted by decreasing score.
+    ///
+    /// Only the vectors of the `num_probes` lists whose centroids are the
+    /// closest to `query` are visited: the search is approximate, and its
+    /// recall increases with `num_probes`.
+    pub fn search(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        num_probes: usize,
+        filter: &dyn Fn(DocId) -> bool,
+    ) -> Vec<(DocId, Score)> {
+        assert_eq!(query.len(), self.dimension);
+        if top_k == 0 || self.num_vectors == 0 {
+            return Vec::new();
+        }
+        let mut centroids = Vec::with_capacity(self.num_lists * self.dimension);
+        read_f32s(self.centroids.as_slice(), &mut centroids);
+        let mut probed_lists: Vec<(Score, usize)> = centroids
+            .chunks_exact(self.dimension.max(1))
+            .map(|centroid| self.similarity.score(query, centroid))
+            .zip(0..self.num_lists)
+            .collect();
+        if probed_lists.len() > num_probes.max(1) {
+            probed_lists.select_nth_unstable_by(num_probes.max(1) - 1, |left, right| {
+                right
+                    .0
+                    .partial_cmp(&left.0)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            probed_lists.truncate(num_probes.max(1));
+        }
+        let mut vector = Vec::with_capacity(self.dimension);
+        let mut hits: Vec<(DocId, Score)> = Vec::new();
+        let list_offsets = self.list_offsets.as_slice();
+        let list_vector_ords = self.list_vector_ords.as_slice();
+        for (_, list_ord) in probed_lists {
+            let start = read_u32(list_offsets, list_ord) as usize;
+            let end = read_u32(list_offsets, list_ord + 1) as usize;
+            for idx in start..end {
+                let vector_ord = read_u32(list_vector_ords, idx) as usize;
+                let doc = self.doc_id(vector_ord);
+                if !filter(doc) {
+                    continue;
+                }
+                self.read_vector(vector_ord, &mut vector);
+                hits.push((doc, self.similarity.score(query, &vector)));
+            }
+        }
+        hits.sort_unstable_by(|left, right| {
+            right
+                .1
+                .partial_cmp(&left.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(left.0.cmp(&right.0))
+        });
+        hits.truncate(top_k);
+        hits
+    }
+}
@@ -0,0 +1,78 @@
+use super::ivf::IvfIndex;
+use crate::directory::CompositeWrite;
+use crate::directory::WritePtr;
+use crate::schema::{Field, VectorSimilarity};
+use crate::DocId;
+use common::BinarySerializable;
+use std::io;
+use std::io::Write;
+
+/// The vectors serializer is in charge of the serialization
+/// of the vectors of all of the vector fields, and of building
+/// their approximate nearest neighbor index.
+///
+/// The data of a field is laid out as follows, all integers and
+/// floats being little endian:
+/// - the dimension and the number of vectors, as `u32`,
+/// - the sorted doc ids of the documents having a vector,
+/// - the vectors, in the order of the doc ids,
+/// - the number of lists of the IVF index, and their centroids,
+/// - the offsets (`num_lists + 1` of them) of the lists in the
+///   vector ordinals that follow.
+pub struct VectorsSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl VectorsSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<VectorsSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(VectorsSerializer { composite_write })
+    }
+
+    /// Serializes the vectors of a field.
+    ///
+    /// `vectors` is the concatenation of the vectors of `doc_ids`, in the same order.
+    /// `doc_ids` must be sorted.
+    pub fn serialize_field(
+        &mut self,
+        field: Field,
+        similarity: VectorSimilarity,
+        dimension: usize,
+        doc_ids: &[DocId],
+        vectors: &[f32],
+    ) -> io::Result<()> {
+        debug_assert_eq!(doc_ids.len() * dimension, vectors.len());
+        let ivf_index = IvfIndex::build(similarity, dimension, vectors);
+        let write = self.composite_write.for_field(field);
+        (dimension as u32).serialize(write)?;
+        (doc_ids.len() as u32).serialize(write)?;
+        for doc_id in doc_ids {
+            doc_id.serialize(write)?;
+        }
+        for val in vectors {
+            val.serialize(write)?;
+        }
+        (ivf_index.lists.len() as u32).serialize(write)?;
+        for val in &ivf_index.centroids {
+            val.serialize(write)?;
+        }
+        let mut offset = 0u32;
+        offset.serialize(write)?;
+        for list in &ivf_index.lists {
+            offset += list.len() as u32;
+            offset.serialize(write)?;
+        }
+        for vector_ord in ivf_index.lists.iter().flatten() {
+            vector_ord.serialize(write)?;
+        }
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use super::VectorsSerializer;
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::{Document, Field, FieldType, Schema, VectorSimilarity};
+use crate::DocId;
+use std::io;
+
+struct FieldVectorsWriter {
+    field: Field,
+    dimension: usize,
+    similarity: VectorSimilarity,
+    doc_ids: Vec<DocId>,
+    vectors: Vec<f32>,
+}
+
+/// The `VectorsWriter` is in charge of accumulating the vectors
+/// of the vector fields of the documents of a segment being written.
+pub struct VectorsWriter {
+    field_writers: Vec<FieldVectorsWriter>,
+}
+
+impl VectorsWriter {
+    /// Initialize with state for tracking the vectors of the vector fields of `schema`.
+    pub fn for_schema(schema: &Schema) -> VectorsWriter {
+        let field_writers = schema
+            .fields()
+            .filter_map(|(field, field_entry)| match field_entry.field_type() {
+                FieldType::Vector(options) => Some(FieldVectorsWriter {
+                    field,
+                    dimension: options.dimension(),
+                    similarity: options.similarity(),
+                    doc_ids: Vec::new(),
+                    vectors: Vec::new(),
+                }),
+                _ => None,
+            })
+            .collect();
+        VectorsWriter { field_writers }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.field_writers
+            .iter()
+            .map(|field_writer| {
+                field_writer.doc_ids.capacity() * std::mem::size_of::<DocId>()
+                    + field_writer.vectors.capacity() * std::mem::size_of::<f32>()
+            })
+            .sum()
+    }
+
+    /// Records the vectors of a document.
+    ///
+    /// Returns a `SchemaError` if a vector does not have the dimension of its field,
+    /// or if the document has more than one vector for the same field.
+    pub fn add_document(&mut self, doc_id: DocId, doc: &Document) -> crate::Result<()> {
+        // The document is checked entirely before any of its vectors is recorded.
+        let mut doc_vectors: Vec<(usize, &[f32])> = Vec::new();
+        for (field_ord, field_writer) in self.field_writers.iter().enumerate() {
+            let mut vectors = doc
+                .get_all(field_writer.field)
+                .filter_map(|value| value.vector_value());
+            let vector = if let Some(vector) = vectors.next() {
+                vector
+            } else {
+                continue;
+            };
+            if vectors.next().is_some() {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "A document can have at most one vector for the field {:?}",
+                    field_writer.field
+                )));
+            }
+            if vector.len() != field_writer.dimension {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "Expected a vector of dimension {} for the field {:?}, got {}",
+                    field_writer.dimension,
+                    field_writer.field,
+                    vector.len()
+                )));
+            }
+            doc_vectors.push((field_ord, vector));
+        }
+        for (field_ord, vector) in doc_vectors {
+            let field_writer = &mut self.field_writers[field_ord];
+            field_writer.doc_ids.push(doc_id);
+            field_writer.vectors.extend_from_slice(vector);
+        }
+        Ok(())
+    }
+
+    /// Serialize the vectors of all of the vector fields, remapping
+    /// the doc ids with `doc_id_map` if the segment is sorted.
+    pub fn serialize(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> io::Result<()> {
+        for field_writer in &self.field_writers {
+            let dimension = field_writer.dimension;
+            if let Some(doc_id_map) = doc_id_map {
+                let mut remapped: Vec<(DocId, usize)> = field_writer
+                    .doc_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(vector_ord, &doc_id)| (doc_id_map.get_new_doc_id(doc_id), vector_ord))
+                    .collect();
+                remapped.sort_unstable();
+                let doc_ids: Vec<DocId> = remapped.iter().map(|(doc_id, _)| *doc_id).collect();
+                let vectors: Vec<f32> = remapped
+                    .iter()
+                    .flat_map(|&(_, vector_ord)| {
+                        field_writer.vectors[vector_ord * dimension..(vector_ord + 1) * dimension]
+                            .iter()
+                            .cloned()
+                    })
+                    .collect();
+                vectors_serializer.serialize_field(
+                    field_writer.field,
+                    field_writer.similarity,
+                    dimension,
+                    &doc_ids,
+                    &vectors,
+                )?;
+            } else {
+                vectors_serializer.serialize_field(
+                    field_writer.field,
+                    field_writer.similarity,
+                    dimension,
+                    &field_writer.doc_ids,
+                    &field_writer.vectors,
+                )?;
+            }
+        }
+        vectors_serializer.close()
+    }
+}